@@ -0,0 +1,625 @@
+// Archivo generado con `cargo run -- --emit-types`. No editar a mano.
+//
+// Campos de actualización (Update*) con tipo `T | null | undefined`:
+//   - ausente (undefined): se conserva el valor actual
+//   - null: se borra el valor
+//   - T: se reemplaza el valor
+
+export type UserRole = "Veterinarian" | "Assistant" | "Admin";
+
+export type AnimalSpecies = "Dog" | "Cat" | "Bird" | "Reptile" | "Rodent" | "Rabbit" | "Other" | string;
+
+export type AnimalGender = "Male" | "Female" | "Unknown";
+
+export type AppointmentStatus = "Scheduled" | "Completed" | "Canceled" | "NoShow";
+
+export type BookingSource = "Staff" | "Portal" | "Api" | "Import" | "WalkIn";
+
+export type ProcedureType = "Vaccine" | "Surgery" | "Deworming" | "Test" | "Grooming" | "Other";
+
+export type TaskStatus = "Pending" | "Done" | "Skipped";
+
+export type CustomFieldEntity = "Patient" | "Client";
+
+export type CustomFieldType = "Text" | "Number" | "Date" | "Bool";
+
+export type ReferralDirection = "Received" | "Sent";
+
+export type NotificationChannel = "Email";
+
+export type NotificationOutcome = "Pending" | "Sent" | "Failed";
+
+export type Page<T> = { items: Array<T>, total: number, limit: number, offset: number, };
+
+export type NewUser = { email: string, password: string, name: string, role: UserRole, license_number?: string | null, };
+
+export type UpdateUser = { email?: string | null, password?: string | null, name?: string | null, role?: UserRole | null, license_number?: string | null, is_active?: boolean | null, };
+
+export type UserResponse = { id: number, email: string, name: string, role: UserRole, license_number: string | null, is_active: boolean, created_at: string, last_login_at: string | null, 
+/**
+ * Citas programadas a futuro; solo con `include_workload=true`
+ */
+upcoming_appointments?: number, 
+/**
+ * Citas de la semana local en curso (lunes a domingo), sin canceladas;
+ * solo con `include_workload=true`
+ */
+appointments_this_week?: number, };
+
+export type UserFilter = { email?: string, role?: UserRole, roles?: string, q?: string, license_number?: string, is_active?: boolean, created_after?: string, created_before?: string, last_login_before?: string, include_workload?: boolean, limit?: number, offset?: number, };
+
+export type LoginUser = { email: string, password: string, };
+
+export type AccessToken = { token: string, token_type: string, issued_at: string, expires_at: string, expires_in: number, };
+
+export type LoginResponse = { refresh_token: string, user: UserResponse, token: string, token_type: string, issued_at: string, expires_at: string, expires_in: number, };
+
+export type RefreshRequest = { refresh_token: string, };
+
+export type RefreshResponse = { refresh_token: string, token: string, token_type: string, issued_at: string, expires_at: string, expires_in: number, };
+
+export type PasswordResetRequest = { email: string, };
+
+export type PasswordResetConfirm = { token: string, new_password: string, };
+
+export type ChangePassword = { current_password: string, new_password: string, };
+
+export type ImpersonationResponse = { token: string, expires_at: string, impersonator_id: number, user: UserResponse, };
+
+export type NewApiKey = { name: string, role: UserRole, scopes: Array<string>, read_only: boolean, expires_at?: string | null, };
+
+export type UpdateApiKey = { name?: string | null, scopes?: Array<string> | null, read_only?: boolean | null, 
+/**
+ * Omitir para conservar el valor, `null` para quitar la expiración
+ */
+expires_at?: string | null | undefined, };
+
+export type ApiKeyResponse = { id: number, name: string, prefix: string, role: UserRole, scopes: Array<string>, read_only: boolean, expires_at: string | null, last_used_at: string | null, revoked_at: string | null, created_by: number, created_at: string, };
+
+export type CreatedApiKeyResponse = { key: string, id: number, name: string, prefix: string, role: UserRole, scopes: Array<string>, read_only: boolean, expires_at: string | null, last_used_at: string | null, revoked_at: string | null, created_by: number, created_at: string, };
+
+export type ApiKeyFilter = { include_revoked?: boolean, limit?: number, offset?: number, };
+
+export type NewClient = { name: string, email?: string | null, phone: string, address?: string | null, notes?: string | null, assigned_to?: number | null, marketing_opt_out: boolean, custom_fields: Record<string, string | number | boolean | null>, household_id?: number | null, };
+
+export type UpdateClient = { name?: string | null, email?: string | null, phone?: string | null, address?: string | null, notes?: string | null, 
+/**
+ * Omitir para conservar el valor, `null` para desasignar
+ */
+assigned_to?: number | null | undefined, marketing_opt_out?: boolean | null, 
+/**
+ * Si se envía, reemplaza todos los campos personalizados
+ */
+custom_fields?: Record<string, string | number | boolean | null>, 
+/**
+ * Omitir para conservar el valor, `null` para salir del hogar
+ */
+household_id?: number | null | undefined, };
+
+export type ClientResponse = { id: number, name: string, email: string | null, phone: string, assigned_to: number | null, marketing_opt_out: boolean, primary_contact: ClientContactResponse | null, custom_fields: Record<string, string | number | boolean>, household_id: number | null, household_members: Array<HouseholdMember>, };
+
+export type ClientFilter = { name?: string, phone?: string, assigned_to?: number, limit?: number, offset?: number, };
+
+export type StatementFormat = "json" | "pdf";
+
+export type StatementQuery = { start_date: string, end_date: string, format: StatementFormat, };
+
+export type StatementEntryKind = "appointment" | "procedure";
+
+export type StatementEntry = { date: string, kind: StatementEntryKind, patient_id: number, patient_name: string, description: string, price: string | null, };
+
+export type StatementPetSubtotal = { patient_id: number, patient_name: string, subtotal: string, unpriced_procedures: number, };
+
+export type ClientStatement = { client_id: number, client_name: string, start_date: string, end_date: string, entries: Array<StatementEntry>, pets: Array<StatementPetSubtotal>, total: string, unpriced_procedures: number, };
+
+export type NewClientContact = { name: string, relationship?: string | null, phone?: string | null, email?: string | null, is_primary: boolean, };
+
+export type UpdateClientContact = { name?: string | null, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+relationship?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+phone?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+email?: string | null | undefined, is_primary?: boolean | null, };
+
+export type ClientContactResponse = { id: number, client_id: number, name: string, relationship: string | null, phone: string | null, email: string | null, is_primary: boolean, created_at: string, };
+
+export type NewHousehold = { name?: string | null, client_ids: Array<number>, };
+
+export type HouseholdMember = { id: number, name: string, email: string | null, };
+
+export type HouseholdPatient = { id: number, name: string, species: AnimalSpecies, client_id: number, };
+
+export type HouseholdResponse = { id: number, name: string | null, members: Array<HouseholdMember>, patients: Array<HouseholdPatient>, };
+
+export type NewCustomFieldDefinition = { entity_type: CustomFieldEntity, key: string, label: string, value_type: CustomFieldType, required: boolean, };
+
+export type UpdateCustomFieldDefinition = { label?: string | null, required?: boolean | null, };
+
+export type CustomFieldDefinitionResponse = { id: number, entity_type: CustomFieldEntity, key: string, label: string, value_type: CustomFieldType, required: boolean, created_at: string, };
+
+export type CustomFieldDefinitionFilter = { entity_type?: CustomFieldEntity, };
+
+export type NewBreed = { species: AnimalSpecies, name: string, };
+
+export type BreedResponse = { id: number, species: AnimalSpecies, name: string, };
+
+export type BreedUpsertResponse = { created: boolean, id: number, species: AnimalSpecies, name: string, };
+
+export type PaginationParams = { limit?: number, offset?: number, };
+
+export type NewPatient = { name: string, species: AnimalSpecies, breed_id?: number | null, birth_date?: string | null, gender?: AnimalGender | null, weight_kg?: number | null, client_id: number, photo_url?: string | null, custom_fields: Record<string, string | number | boolean | null>, insurance_provider?: string | null, insurance_policy_number?: string | null, insurance_expires_on?: string | null, };
+
+export type UpdatePatient = { name?: string | null, species?: AnimalSpecies | null, 
+/**
+ * Omitir para conservar el valor, `null` para quitar raza
+ */
+breed_id?: number | null | undefined, birth_date?: string | null, gender?: AnimalGender | null, weight_kg?: number | null, client_id?: number | null, photo_url?: string | null, 
+/**
+ * Fecha de fallecimiento. Omitir para conservar el valor, `null` para borrar
+ */
+deceased_on?: string | null | undefined, 
+/**
+ * Si se envía, reemplaza todos los campos personalizados
+ */
+custom_fields?: Record<string, string | number | boolean | null>, 
+/**
+ * Aseguradora. Omitir para conservar el valor, `null` para quitar el seguro
+ */
+insurance_provider?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+insurance_policy_number?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+insurance_expires_on?: string | null | undefined, };
+
+export type PatientResponse = { id: number, name: string, species: AnimalSpecies, breed: string | null, breed_id: number | null, birth_date: string | null, gender: AnimalGender | null, weight_kg: number | null, client_id: number, photo_url: string | null, deceased_on: string | null, custom_fields: Record<string, string | number | boolean>, insurance_provider: string | null, insurance_policy_number: string | null, insurance_expires_on: string | null, };
+
+export type PatientFilter = { name?: string, species?: AnimalSpecies, breed_id?: number, client_id?: number, gender?: AnimalGender, insured?: boolean, limit?: number, offset?: number, };
+
+export type PatientQuery = { include?: string, include_limit?: number, };
+
+export type PatientDetailResponse = { appointments?: Array<AppointmentResponse>, procedures?: Array<PatientProcedureResponse>, records?: Array<MedicalRecordResponse>, id: number, name: string, species: AnimalSpecies, breed: string | null, breed_id: number | null, birth_date: string | null, gender: AnimalGender | null, weight_kg: number | null, client_id: number, photo_url: string | null, deceased_on: string | null, custom_fields: Record<string, string | number | boolean>, insurance_provider: string | null, insurance_policy_number: string | null, insurance_expires_on: string | null, };
+
+export type PatientHistoryResponse = { patient_id: number, medical_records: Array<MedicalRecordResponse>, procedures: Array<PatientProcedureResponse>, hospitalizations: Array<HospitalizationResponse>, referrals: Array<ReferralResponse>, };
+
+export type CreatePatientQuery = { force: boolean, override_warnings: boolean, };
+
+export type DuplicatePatientCandidate = { id: number, name: string, species: AnimalSpecies, similarity: number, };
+
+export type DuplicatePatientPair = { client_id: number, client_name: string | null, patient_id: number, patient_name: string, duplicate_id: number, duplicate_name: string, similarity: number, };
+
+export type DuplicatePatientFilter = { limit?: number, offset?: number, };
+
+export type BirthdayQuery = { month?: number, upcoming_days?: number, };
+
+export type PatientBirthday = { patient_id: number, patient_name: string, species: AnimalSpecies, birth_date: string, anniversary: string, turning_age: number, client_id: number, owner_name: string, owner_phone: string, owner_email: string | null, marketing_opt_out: boolean, };
+
+export type InsuranceClaimQuery = { medical_record_id: number, };
+
+export type ClaimProcedure = { id: number, name: string, date: string, price: number | null, };
+
+export type InsuranceClaim = { patient_id: number, patient_name: string, species: AnimalSpecies, owner_name: string | null, insurance_provider: string, insurance_policy_number: string | null, insurance_expires_on: string | null, medical_record_id: number, folio: number | null, visit_date: string, veterinarian_name: string | null, diagnosis: string, treatment: string | null, procedures: Array<ClaimProcedure>, total: number, };
+
+export type BulkSetBreed = { patient_ids: Array<number>, breed_id: number, };
+
+export type BulkSetBreedResponse = { breed_id: number, count: number, patient_ids: Array<number>, };
+
+export type NewAppointment = { patient_id?: number | null, client_id?: number | null, veterinarian_id: number, start_time: string, end_time: string, reason: string, is_emergency: boolean, walk_in: boolean, procedure_id?: number | null, internal_notes?: string | null, };
+
+export type UpdateAppointment = { 
+/**
+ * Omitir para conservar el valor, `null` para desasociar
+ */
+patient_id?: number | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para desasociar
+ */
+client_id?: number | null | undefined, veterinarian_id?: number | null, start_time?: string | null, end_time?: string | null, status?: AppointmentStatus | null, reason?: string | null, is_emergency?: boolean | null, 
+/**
+ * Omitir para conservar el valor, `null` para quitar el procedimiento
+ */
+procedure_id?: number | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+internal_notes?: string | null | undefined, 
+/**
+ * Solo junto con `status: "Canceled"`
+ */
+cancellation_reason?: string | null, };
+
+export type UpdateAppointmentQuery = { override_warnings: boolean, allow_past_edit: boolean, };
+
+export type UpdateAppointmentStatus = { status: AppointmentStatus, note?: string | null, };
+
+export type AppointmentResponse = { id: number, patient_id: number | null, patient_name: string | null, client_id: number | null, client_name: string | null, veterinarian_id: number, veterinarian_name: string, start_time: string, end_time: string, start_time_local: string, end_time_local: string, timezone: string, status: AppointmentStatus, reason: string, is_emergency: boolean, procedure_id: number | null, procedure_name: string | null, room_id: number | null, room_name: string | null, booking_source: BookingSource, internal_notes: string | null, created_at: string, updated_at: string, canceled_at: string | null, cancellation_reason: string | null, duration_minutes: number, latest_notes: Array<AppointmentNoteResponse>, };
+
+export type AppointmentPublicResponse = { id: number, patient_name: string | null, veterinarian_name: string, start_time: string, end_time: string, status: AppointmentStatus, reason: string, };
+
+export type AppointmentFilter = { patient_id?: number, client_id?: number, veterinarian_id?: number, status?: AppointmentStatus, start_date?: string, end_date?: string, reason_contains?: string, booking_source?: BookingSource, created_after?: string, created_before?: string, at?: string, active_now?: boolean, include_counts?: boolean, envelope?: boolean, include_archived?: boolean, limit?: number, offset?: number, };
+
+export type AppointmentStatusCounts = { scheduled: number, completed: number, canceled: number, no_show: number, };
+
+export type AppointmentListResponse = { status_counts?: AppointmentStatusCounts, items: Array<AppointmentResponse>, total: number, limit: number, offset: number, };
+
+export type CalendarQuery = { start: string, days?: number, veterinarian_ids?: string, };
+
+export type CalendarAppointment = { id: number, start: string, end: string, start_local: string, end_local: string, status: AppointmentStatus, patient_name: string | null, is_emergency: boolean, };
+
+export type CalendarVeterinarian = { veterinarian_id: number, veterinarian_name: string, appointments: Array<CalendarAppointment>, };
+
+export type CalendarDay = { date: string, timezone: string, veterinarians: Array<CalendarVeterinarian>, };
+
+export type CalendarRangeQuery = { from: string, to: string, veterinarian_id?: number, };
+
+export type CalendarEntry = { id: number, start_time: string, end_time: string, status: AppointmentStatus, patient_name: string | null, reason: string, };
+
+export type TimeSlot = { start_time: string, end_time: string, };
+
+export type AvailabilityQuery = { date: string, slot_minutes?: number, duration?: number, };
+
+export type VeterinarianAvailability = { veterinarian_id: number, date: string, slot_minutes: number, slots: Array<TimeSlot>, };
+
+export type NextAvailableQuery = { veterinarian_id?: number, duration_minutes?: number, after?: string, procedure_id?: number, limit?: number, };
+
+export type SuggestedSlot = { veterinarian_id: number, veterinarian_name: string, start_time: string, end_time: string, };
+
+export type AppointmentConflictDetails = { conflicting: TimeSlot, next_available: TimeSlot | null, };
+
+export type ReassignAppointments = { from_veterinarian_id: number, to_veterinarian_id: number, start_date: string, end_date: string, };
+
+export type SkippedReassignment = { appointment_id: number, reason: string, };
+
+export type ReassignmentResponse = { moved_ids: Array<number>, skipped: Array<SkippedReassignment>, };
+
+export type NewAppointmentNote = { content: string, };
+
+export type AppointmentNoteResponse = { id: number, appointment_id: number, author_id: number, author_name: string, content: string, created_at: string, };
+
+export type AppointmentNoteFilter = { limit?: number, offset?: number, };
+
+export type AppointmentEventFilter = { veterinarian_id?: number, };
+
+export type AppointmentStreamEvent = { type: "AppointmentCreated" | "AppointmentUpdated" | "AppointmentRescheduled" | "AppointmentCanceled", appointment_id: number, veterinarian_id: number, status: AppointmentStatus, start_time: string, };
+
+export type LobbyEntry = { owner_first_name: string, pet_initial: string, veterinarian_name: string, status: string, expected_time: string, };
+
+export type LobbyFeed = { date: string, generated_at: string, entries: Array<LobbyEntry>, };
+
+export type ClinicProfile = { name: string | null, address: string | null, phone: string | null, timezone: string, date: string, opening_time: string | null, closing_time: string | null, open_now: boolean, available_veterinarians: number, generated_at: string, };
+
+export type NewIntake = { client: NewClient, patient: Omit<NewPatient, "client_id">, appointment: Omit<NewAppointment, "patient_id" | "client_id">, };
+
+export type IntakeResponse = { client: ClientResponse, patient: PatientResponse, appointment: AppointmentResponse, };
+
+export type NewMedicalRecord = { patient_id: number, veterinarian_id?: number | null, template_id?: number | null, diagnosis?: string | null, treatment?: string | null, notes?: string | null, weight_at_visit?: number | null, temperature_c?: number | null, heart_rate_bpm?: number | null, respiratory_rate_rpm?: number | null, };
+
+export type UpdateMedicalRecord = { patient_id?: number | null, veterinarian_id?: number | null, diagnosis?: string | null, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+treatment?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+notes?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+weight_at_visit?: number | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+temperature_c?: number | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+heart_rate_bpm?: number | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+respiratory_rate_rpm?: number | null | undefined, };
+
+export type MedicalRecordResponse = { id: number, patient_id: number, veterinarian_id: number, veterinarian_name: string, date: string, diagnosis: string, treatment: string | null, notes: string | null, weight_at_visit: number | null, temperature_c: VitalReading | null, heart_rate_bpm: VitalReading | null, respiratory_rate_rpm: VitalReading | null, folio: number | null, };
+
+export type MedicalRecordFilter = { patient_id?: number, veterinarian_id?: number, start_date?: string, end_date?: string, diagnosis_contains?: string, limit?: number, offset?: number, };
+
+export type VitalSign = "temperature_c" | "heart_rate_bpm" | "respiratory_rate_rpm";
+
+export type VitalFlag = "low" | "normal" | "high";
+
+export type VitalReading = { value: number, flag?: VitalFlag, };
+
+export type VitalRange = { species: AnimalSpecies, sign: VitalSign, min: number, max: number, };
+
+export type NewRecordTemplate = { name: string, diagnosis?: string | null, treatment?: string | null, notes?: string | null, is_shared: boolean, };
+
+export type UpdateRecordTemplate = { name?: string | null, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+diagnosis?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+treatment?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+notes?: string | null | undefined, is_shared?: boolean | null, };
+
+export type RecordTemplateResponse = { id: number, name: string, diagnosis: string | null, treatment: string | null, notes: string | null, owner_id: number | null, owner_name: string | null, is_shared: boolean, created_at: string, };
+
+export type RecordTemplateFilter = { owner_id?: number, is_shared?: boolean, q?: string, limit?: number, offset?: number, };
+
+export type NewProcedure = { name: string, procedure_type: ProcedureType, description?: string | null, duration_minutes?: number | null, species: Array<AnimalSpecies>, price?: number | null, required_room_type?: string | null, };
+
+export type UpdateProcedure = { name?: string | null, procedure_type?: ProcedureType | null, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+description?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+duration_minutes?: number | null | undefined, species?: Array<AnimalSpecies> | null, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+price?: number | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+required_room_type?: string | null | undefined, };
+
+export type ProcedureResponse = { id: number, name: string, procedure_type: ProcedureType, description: string | null, duration_minutes: number | null, duration_formatted: string | null, duration_iso8601: string | null, species: Array<AnimalSpecies>, price: number | null, required_room_type: string | null, };
+
+export type ProcedureFilter = { name_contains?: string, procedure_type?: ProcedureType, min_duration?: number, max_duration?: number, limit?: number, offset?: number, };
+
+export type CatalogProcedure = { name: string, procedure_type: ProcedureType, description?: string | null, duration_minutes?: number | null, species: Array<AnimalSpecies>, price?: number | null, };
+
+export type ProcedureCatalog = { version: number, exported_at: string, procedures: Array<CatalogProcedure>, };
+
+export type MergeStrategy = "skip_existing" | "update_existing";
+
+export type ProcedureImportQuery = { strategy?: MergeStrategy, };
+
+export type ImportAction = "created" | "updated" | "skipped";
+
+export type ProcedureImportItem = { name: string, action: ImportAction, procedure_id: number, };
+
+export type ProcedureImportResponse = { created: number, updated: number, skipped: number, items: Array<ProcedureImportItem>, };
+
+export type NewPatientProcedure = { patient_id: number, procedure_id: number, veterinarian_id?: number | null, performed_by?: number | null, date: string, next_due_date?: string | null, notes?: string | null, batch_number?: string | null, manufacturer?: string | null, };
+
+export type UpdatePatientProcedure = { patient_id?: number | null, procedure_id?: number | null, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+veterinarian_id?: number | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+performed_by?: number | null | undefined, date?: string | null, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+next_due_date?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+notes?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+batch_number?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+manufacturer?: string | null | undefined, };
+
+export type PatientProcedureResponse = { id: number, patient_id: number, patient_name: string, procedure_id: number, procedure_name: string, veterinarian_id: number | null, veterinarian_name: string | null, performed_by: number | null, performed_by_name: string | null, date: string, next_due_date: string | null, notes: string | null, batch_number: string | null, manufacturer: string | null, };
+
+export type PatientProcedureWithOwner = { client_id: number | null, client_name: string | null, client_phone: string | null, client_email: string | null, id: number, patient_id: number, patient_name: string, procedure_id: number, procedure_name: string, veterinarian_id: number | null, veterinarian_name: string | null, performed_by: number | null, performed_by_name: string | null, date: string, next_due_date: string | null, notes: string | null, batch_number: string | null, manufacturer: string | null, };
+
+export type PatientProcedureFilter = { patient_id?: number, procedure_id?: number, veterinarian_id?: number, start_date?: string, end_date?: string, batch_number?: string, limit?: number, offset?: number, };
+
+export type CreatePatientProcedureQuery = { allow_duplicate: boolean, override_warnings: boolean, consent_waived: boolean, };
+
+export type BulkReschedule = { procedure_id: number, administered_from: string, administered_to: string, interval_days?: number | null, next_due_date?: string | null, };
+
+export type BulkRescheduleQuery = { dry_run: boolean, };
+
+export type BulkRescheduleResponse = { dry_run: boolean, count: number, patient_ids: Array<number>, };
+
+export type NewDigitalConsent = { client_name: string, };
+
+export type ConsentDocumentQuery = { client_name?: string, };
+
+export type ConsentResponse = { id: number, patient_procedure_id: number, kind: string, client_name: string | null, accepted_at: string, ip_address: string | null, content_type: string | null, document_size: number | null, recorded_by: number | null, };
+
+export type NewRoom = { name: string, room_type: string, };
+
+export type UpdateRoom = { name?: string | null, room_type?: string | null, is_active?: boolean | null, };
+
+export type RoomResponse = { id: number, name: string, room_type: string, is_active: boolean, };
+
+export type RoomFilter = { room_type?: string, is_active?: boolean, };
+
+export type ClinicHoursDay = { weekday: number, opens_at: string, closes_at: string, };
+
+export type UpdateClinicHours = { days: Array<ClinicHoursDay>, };
+
+export type ClinicHoursResponse = { timezone: string, days: Array<ClinicHoursDay>, };
+
+export type NewReferral = { direction: ReferralDirection, internal_user_id?: number | null, external_clinic?: string | null, reason: string, referral_date?: string | null, medical_record_id?: number | null, };
+
+export type ReferralFilter = { direction?: ReferralDirection, from_date?: string, to_date?: string, limit?: number, offset?: number, };
+
+export type ReferralResponse = { id: number, patient_id: number, direction: ReferralDirection, internal_user_id: number | null, internal_user_name: string | null, external_clinic: string | null, reason: string, referral_date: string, medical_record_id: number | null, created_at: string, };
+
+export type NewHospitalization = { veterinarian_id?: number | null, admitted_at?: string | null, expected_discharge_at?: string | null, kennel?: string | null, reason: string, };
+
+export type UpdateHospitalization = { 
+/**
+ * Omitir para conservar el valor, `null` para quitar el veterinario
+ */
+veterinarian_id?: number | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+expected_discharge_at?: string | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+kennel?: string | null | undefined, reason?: string | null, };
+
+export type DischargeHospitalization = { discharged_at?: string | null, summary?: string | null, };
+
+export type HospitalizationResponse = { id: number, patient_id: number, patient_name: string, veterinarian_id: number | null, veterinarian_name: string | null, admitted_at: string, expected_discharge_at: string | null, discharged_at: string | null, kennel: string | null, reason: string, discharge_summary: string | null, };
+
+export type ActiveHospitalization = { id: number, patient_id: number, patient_name: string, species: AnimalSpecies, client_id: number | null, client_name: string | null, client_phone: string | null, veterinarian_id: number | null, veterinarian_name: string | null, kennel: string | null, admitted_at: string, expected_discharge_at: string | null, reason: string, };
+
+export type NewTreatmentTask = { hospitalization_id: number, description: string, scheduled_time: string, assigned_role?: UserRole | null, repeat_every_hours?: number | null, occurrences?: number | null, };
+
+export type CompleteTreatmentTask = { skipped: boolean, notes?: string | null, };
+
+export type DueTasksQuery = { window_minutes?: number, role?: UserRole, };
+
+export type TreatmentTaskFilter = { hospitalization_id?: number, status?: TaskStatus, limit?: number, offset?: number, };
+
+export type TreatmentTaskResponse = { id: number, hospitalization_id: number, patient_id: number, patient_name: string, kennel: string | null, description: string, scheduled_time: string, assigned_role: UserRole | null, status: TaskStatus, done_by: number | null, done_by_name: string | null, done_at: string | null, notes: string | null, };
+
+export type SpeciesResponse = { species: AnimalSpecies, code: string, name: string, is_built_in: boolean, created_at: string, };
+
+export type NewSpecies = { code: string, name: string, };
+
+export type UpdateSpecies = { name: string, };
+
+export type ProtocolItem = { procedure_id: number, offset_days: number, };
+
+export type NewSpeciesProtocol = { name: string, species: AnimalSpecies, min_age_days?: number | null, max_age_days?: number | null, items: Array<ProtocolItem>, };
+
+export type UpdateSpeciesProtocol = { name?: string | null, species?: AnimalSpecies | null, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+min_age_days?: number | null | undefined, 
+/**
+ * Omitir para conservar el valor, `null` para borrar
+ */
+max_age_days?: number | null | undefined, items?: Array<ProtocolItem> | null, };
+
+export type ProtocolItemResponse = { procedure_id: number, procedure_name: string, offset_days: number, };
+
+export type SpeciesProtocolResponse = { id: number, name: string, species: AnimalSpecies, min_age_days: number | null, max_age_days: number | null, items: Array<ProtocolItemResponse>, };
+
+export type SpeciesProtocolFilter = { species?: AnimalSpecies, limit?: number, offset?: number, };
+
+export type SkippedProtocolItem = { procedure_id: number, procedure_name: string, date: string, reason: string, };
+
+export type ProtocolApplicationResponse = { created: Array<PatientProcedureResponse>, skipped: Array<SkippedProtocolItem>, };
+
+export type SurveyInfo = { veterinarian_first_name: string, appointment_date: string, };
+
+export type SurveyAnswer = { rating: number, comment?: string | null, };
+
+export type SyncQuery = { since: string, entities?: string, limit?: number, };
+
+export type SyncChanges<T> = { updated: Array<T>, deleted_ids: Array<number>, has_more: boolean, next_since: string | null, };
+
+export type SyncResponse = { server_time: string, clients: SyncChanges<ClientResponse> | null, patients: SyncChanges<PatientResponse> | null, appointments: SyncChanges<AppointmentResponse> | null, };
+
+export type JobState = "Running" | "Completed" | "Failed";
+
+export type JobStatus = { id: number, kind: string, state: JobState, processed: number, total: number, message: string | null, started_at: string, finished_at: string | null, };
+
+export type DemoDatasetRequest = { clients?: number | null, confirm: boolean, };
+
+export type DailyDigestRequest = { date?: string | null, };
+
+export type IntegrityQuery = { checks?: string, };
+
+export type IntegrityCheckResult = { check: string, description: string, count: number, sample_ids: Array<number>, };
+
+export type IntegrityReport = { generated_at: string, checks: Array<IntegrityCheckResult>, };
+
+export type RetentionQuery = { dry_run: boolean, };
+
+export type RetentionPolicyReport = { policy: string, description: string, retention_days: number | null, cutoff: string | null, eligible: number, };
+
+export type RetentionReport = { generated_at: string, archive_dir: string | null, policies: Array<RetentionPolicyReport>, };
+
+export type AppointmentArchiveQuery = { dry_run: boolean, };
+
+export type AppointmentArchiveReport = { generated_at: string, archive_days: number | null, cutoff: string | null, eligible: number, archived: number, };
+
+export type LimitUsage = { limit: string, current: number, max: number | null, };
+
+export type UsageReport = { generated_at: string, limits: Array<LimitUsage>, };
+
+export type LimitExceededResponse = { code: string, message: string, limit: string, current: number, max: number | null, };
+
+export type UpdateMaintenance = { read_only: boolean, };
+
+export type MaintenanceStatus = { read_only: boolean, };
+
+export type DeletionEffect = "block" | "cascade" | "set_null" | "keep";
+
+export type DependencyCount = { table: string, column: string, effect: DeletionEffect, condition?: string, count: number, };
+
+export type DeletePreview = { resource: string, id: number, can_delete: boolean, dependencies: Array<DependencyCount>, };
+
+export type MissingBreedBySpecies = { species: AnimalSpecies, patients: number, missing: number, percentage: number, };
+
+export type UnassignedNameCluster = { name: string, species: AnimalSpecies, count: number, };
+
+export type UnusedBreed = { id: number, species: AnimalSpecies, name: string, };
+
+export type BreedQualityReport = { generated_at: string, missing_by_species: Array<MissingBreedBySpecies>, unassigned_name_clusters: Array<UnassignedNameCluster>, unused_breeds: Array<UnusedBreed>, };
+
+export type NotificationLogFilter = { client_id?: number, channel?: NotificationChannel, outcome?: NotificationOutcome, start_date?: string, end_date?: string, limit?: number, offset?: number, };
+
+export type NotificationLogEntry = { id: number, channel: NotificationChannel, target: string, template: string, subject: string, client_id: number | null, appointment_id: number | null, outcome: NotificationOutcome, provider_message_id: string | null, error: string | null, retry_of: number | null, attempted_at: string, completed_at: string | null, };
+
+export type StatisticsQuery = { user_id?: number, start_date?: string, end_date?: string, type_?: string, by?: ProcedureActor, species?: AnimalSpecies, };
+
+export type StatisticType = "appointments" | "users" | "procedures" | "patients" | "referrals" | "satisfaction";
+
+export type ProcedureActor = "performer" | "veterinarian";
+
+export type ProcedureDetailQuery = { procedure_type: ProcedureType, start_date?: string, end_date?: string, species?: AnimalSpecies, user_id?: number, by?: ProcedureActor, limit?: number, offset?: number, };
+
+export type StatisticsResponse = { appointments_by_month: Array<AppointmentsByMonth> | null, appointments_by_source: Array<AppointmentsBySource> | null, user_counts: UserCounts | null, procedures_by_type: Array<ProceduresByType> | null, patients_by_species: Array<PatientsBySpecies> | null, veterinarian_stats: VeterinarianStats | null, referrals_by_month: Array<ReferralsByMonth> | null, satisfaction: Array<SatisfactionByVeterinarian> | null, };
+
+export type AppointmentsByMonth = { month: string, count: number, };
+
+export type AppointmentsBySource = { source: string, count: number, };
+
+export type UserCounts = { total_users: number, veterinarians: number, assistants: number, admins: number, };
+
+export type ProceduresByType = { procedure_type: string, count: number, };
+
+export type PatientsBySpecies = { species: string, count: number, };
+
+export type VeterinarianStats = { appointments_by_status: Array<AppointmentsByStatus>, procedures_performed: Array<ProceduresByType>, medical_records_created: number, patients_attended: Array<PatientsBySpecies>, average_duration_minutes: number | null, no_show_rate: number | null, survey_responses: number, average_rating: number | null, };
+
+export type AppointmentsByStatus = { status: string, count: number, };
+
+export type ReferralsByMonth = { month: string, received: number, sent: number, };
+
+export type SatisfactionByVeterinarian = { veterinarian_id: number, veterinarian_name: string, responses: number, average_rating: number | null, by_month: Array<SatisfactionByMonth>, };
+
+export type SatisfactionByMonth = { month: string, responses: number, average_rating: number | null, };
+
+export type VeterinarianReportQuery = { month?: string, };
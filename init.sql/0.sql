@@ -1,20 +1,27 @@
+-- Extensiones
+CREATE EXTENSION IF NOT EXISTS pg_trgm; -- Similitud de nombres (pacientes duplicados)
+
 -- Tipos enumerados para estandarizar opciones
 CREATE TYPE user_role AS ENUM ('veterinarian', 'assistant', 'admin');
 
-CREATE TYPE animal_species AS ENUM (
-    'dog',
-    'cat',
-    'bird',
-    'reptile',
-    'rodent',
-    'rabbit',
-    'other'
-);
-
 CREATE TYPE animal_gender AS ENUM ('male', 'female', 'unknown');
 
 CREATE TYPE appointment_status AS ENUM ('scheduled', 'completed', 'canceled', 'no_show');
 
+CREATE TYPE task_status AS ENUM ('pending', 'done', 'skipped');
+
+CREATE TYPE custom_field_entity AS ENUM ('patient', 'client');
+
+CREATE TYPE custom_field_type AS ENUM ('text', 'number', 'date', 'bool');
+
+CREATE TYPE referral_direction AS ENUM ('received', 'sent');
+
+CREATE TYPE booking_source AS ENUM ('staff', 'portal', 'api', 'import', 'walk_in');
+
+CREATE TYPE notification_channel AS ENUM ('email');
+
+CREATE TYPE notification_outcome AS ENUM ('pending', 'sent', 'failed');
+
 CREATE TYPE procedure_type AS ENUM (
     'vaccine',
     'surgery',
@@ -34,20 +41,47 @@ CREATE TABLE users (
     license_number VARCHAR(50),
     is_active BOOLEAN NOT NULL DEFAULT TRUE,
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
-    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    last_login_at TIMESTAMPTZ -- NULL si nunca ha iniciado sesión
+);
+
+-- Hogares: clientes de una misma familia, que pueden compartir email
+CREATE TABLE households (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100), -- Ej: "Familia Pérez"
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
 -- Dueños de Mascotas
 CREATE TABLE clients (
     id SERIAL PRIMARY KEY,
     name VARCHAR(100) NOT NULL,
-    email VARCHAR(255) UNIQUE,
+    email VARCHAR(255), -- Único salvo entre integrantes del mismo hogar (validado en la API)
+    household_id INTEGER REFERENCES households(id) ON DELETE SET NULL,
     phone VARCHAR(20) NOT NULL,
     address TEXT,
     notes TEXT,
-    assigned_to INTEGER REFERENCES users(id) ON DELETE SET NULL
+    assigned_to INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    marketing_opt_out BOOLEAN NOT NULL DEFAULT FALSE, -- No desea mensajes promocionales
+    custom_fields JSONB NOT NULL DEFAULT '{}', -- Validado contra custom_field_definitions
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CLOCK_TIMESTAMP() -- Cursor de /sync (trg_touch_updated_at)
 );
 
+-- Personas de contacto adicionales del cliente (familia, cuidadores)
+CREATE TABLE client_contacts (
+    id SERIAL PRIMARY KEY,
+    client_id INTEGER NOT NULL REFERENCES clients(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    relationship VARCHAR(50),
+    phone VARCHAR(20),
+    email VARCHAR(255),
+    is_primary BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Un solo contacto principal por cliente
+CREATE UNIQUE INDEX idx_client_contacts_primary ON client_contacts(client_id) WHERE is_primary;
+
 -- Función de validación CORREGIDA
 CREATE OR REPLACE FUNCTION validate_assigned_role()
 RETURNS TRIGGER AS $$
@@ -69,23 +103,55 @@ BEFORE INSERT OR UPDATE ON clients
 FOR EACH ROW EXECUTE FUNCTION validate_assigned_role();
 
 -- Animales
+-- Catálogo de especies; las integradas (is_built_in) tienen variante en el
+-- código y no se eliminan. Se referencia por `code`.
+CREATE TABLE species (
+    code VARCHAR(30) PRIMARY KEY CHECK (code ~ '^[a-z][a-z0-9_]{1,29}$'),
+    name VARCHAR(50) NOT NULL UNIQUE, -- Nombre para mostrar
+    is_built_in BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO species (code, name, is_built_in) VALUES
+    ('dog', 'Perro', TRUE),
+    ('cat', 'Gato', TRUE),
+    ('bird', 'Ave', TRUE),
+    ('reptile', 'Reptil', TRUE),
+    ('rodent', 'Roedor', TRUE),
+    ('rabbit', 'Conejo', TRUE),
+    ('other', 'Otra', TRUE);
+
 CREATE TABLE breeds (
     id SERIAL PRIMARY KEY,
-    species animal_species NOT NULL,
+    species VARCHAR(30) NOT NULL REFERENCES species (code),
     name VARCHAR(50) NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), -- Last-Modified del catálogo
     UNIQUE (species, name)
 );
 
+-- Sin duplicados que solo difieran en mayúsculas; árbitro de PUT /breeds/upsert
+CREATE UNIQUE INDEX idx_breed_species_name_lower ON breeds (species, LOWER(name));
+
 CREATE TABLE patients (
     id SERIAL PRIMARY KEY,
     name VARCHAR(100) NOT NULL,
-    species animal_species NOT NULL,
+    species VARCHAR(30) NOT NULL REFERENCES species (code),
     breed INTEGER REFERENCES breeds(id) ON DELETE SET NULL,
     birth_date DATE,
     gender animal_gender,
     weight_kg DECIMAL(5, 2),
     client_id INTEGER REFERENCES clients(id) ON DELETE CASCADE,
-    photo_url TEXT
+    photo_url TEXT,
+    deceased_on DATE, -- NULL mientras el paciente esté vivo
+    custom_fields JSONB NOT NULL DEFAULT '{}', -- Validado contra custom_field_definitions
+    insurance_provider VARCHAR(100), -- Aseguradora; NULL = sin seguro
+    insurance_policy_number VARCHAR(50),
+    insurance_expires_on DATE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CLOCK_TIMESTAMP(), -- Cursor de /sync (trg_touch_updated_at)
+    CONSTRAINT chk_patient_insurance CHECK (
+        insurance_provider IS NOT NULL
+        OR (insurance_policy_number IS NULL AND insurance_expires_on IS NULL)
+    )
 );
 
 -- Función de validación para especie-raza
@@ -117,16 +183,63 @@ CREATE TABLE medical_records (
     diagnosis TEXT NOT NULL,
     treatment TEXT,
     notes TEXT,
-    weight_at_visit DECIMAL(5, 2)
+    weight_at_visit DECIMAL(5, 2),
+    temperature_c DECIMAL(3, 1), -- Signos vitales, opcionales
+    heart_rate_bpm INTEGER,
+    respiratory_rate_rpm INTEGER,
+    folio BIGINT UNIQUE -- Consecutivo sin huecos asignado al crear (document_sequences)
+);
+
+-- Consecutivos legales por tipo de documento. Se incrementan con
+-- SELECT ... FOR UPDATE dentro de la transacción que crea el documento, así
+-- que una transacción revertida no deja huecos.
+CREATE TABLE document_sequences (
+    document_type VARCHAR(50) PRIMARY KEY,
+    last_value BIGINT NOT NULL DEFAULT 0
+);
+
+INSERT INTO document_sequences (document_type) VALUES ('medical_record');
+
+-- Plantillas de diagnóstico y tratamiento para registros médicos
+CREATE TABLE record_templates (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    diagnosis TEXT,
+    treatment TEXT,
+    notes TEXT,
+    owner_id INTEGER REFERENCES users (id) ON DELETE SET NULL, -- Quien la creó
+    is_shared BOOLEAN NOT NULL DEFAULT FALSE, -- Visible para todos los usuarios
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
 );
 
+CREATE INDEX idx_record_templates_owner ON record_templates (owner_id);
+
 -- Vacunas/Procedimientos
 CREATE TABLE procedures (
     id SERIAL PRIMARY KEY,
     name VARCHAR(100) NOT NULL,
     type procedure_type NOT NULL, -- Enum
     description TEXT,
-    duration_minutes INTEGER
+    duration_minutes INTEGER,
+    species VARCHAR(30)[] NOT NULL DEFAULT '{}', -- Códigos de especies habituales (vacío = todas)
+    price NUMERIC(10, 2) CHECK (price >= 0),
+    required_room_type VARCHAR(50), -- Tipo de sala que ocupa (ej: 'surgery'); NULL = ninguna
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW() -- Last-Modified del catálogo
+);
+
+-- Salas y equipos que se asignan a las citas según su procedimiento
+CREATE TABLE rooms (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL UNIQUE,
+    room_type VARCHAR(50) NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+-- Última eliminación en cada catálogo, para que Last-Modified también cambie
+-- al borrar filas
+CREATE TABLE catalog_deletions (
+    catalog VARCHAR(20) PRIMARY KEY, -- 'breeds' o 'procedures'
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
 -- Tabla de relación Paciente-Procedimiento
@@ -134,16 +247,107 @@ CREATE TABLE patient_procedures (
     id SERIAL PRIMARY KEY,
     patient_id INTEGER REFERENCES patients (id) ON DELETE CASCADE,
     procedure_id INTEGER REFERENCES procedures (id),
-    veterinarian_id INTEGER REFERENCES users (id),
+    veterinarian_id INTEGER REFERENCES users (id), -- Veterinario responsable
+    performed_by INTEGER REFERENCES users (id), -- Quien lo realizó (ej: asistente, groomer)
     date DATE NOT NULL,
     next_due_date DATE,
     notes TEXT,
+    batch_number VARCHAR(50), -- Lote aplicado (ej: vacunas), para rastrear retiros
+    manufacturer VARCHAR(100),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), -- Plazo para adjuntar el consentimiento
     CONSTRAINT chk_next_date CHECK (
         next_due_date IS NULL
         OR next_due_date >= date
     )
 );
 
+-- Búsqueda por lote en un retiro del fabricante
+CREATE INDEX idx_patient_procedure_batch ON patient_procedures (LOWER(batch_number))
+WHERE batch_number IS NOT NULL;
+
+-- Consentimiento firmado de un procedimiento (ej: cirugías): una aceptación
+-- digital escrita (nombre, fecha e IP) o el documento firmado escaneado
+CREATE TABLE procedure_consents (
+    id SERIAL PRIMARY KEY,
+    patient_procedure_id INTEGER NOT NULL UNIQUE REFERENCES patient_procedures (id) ON DELETE CASCADE,
+    client_name VARCHAR(100), -- Nombre escrito por quien acepta
+    accepted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ip_address VARCHAR(45), -- Desde donde se aceptó o se subió el documento
+    content_type VARCHAR(50), -- application/pdf, image/png o image/jpeg
+    document BYTEA,
+    recorded_by INTEGER REFERENCES users (id),
+    CONSTRAINT chk_consent_document CHECK ((content_type IS NULL) = (document IS NULL)),
+    CONSTRAINT chk_consent_evidence CHECK (client_name IS NOT NULL OR document IS NOT NULL)
+);
+
+-- Protocolos de procedimientos por especie (ej: esquema de vacunación de cachorros)
+CREATE TABLE species_protocols (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    species VARCHAR(30) NOT NULL REFERENCES species (code),
+    min_age_days INTEGER, -- Edad mínima del paciente (opcional)
+    max_age_days INTEGER, -- Edad máxima del paciente (opcional)
+    CONSTRAINT chk_protocol_age_range CHECK (
+        min_age_days IS NULL
+        OR max_age_days IS NULL
+        OR min_age_days <= max_age_days
+    )
+);
+
+CREATE TABLE species_protocol_items (
+    id SERIAL PRIMARY KEY,
+    protocol_id INTEGER NOT NULL REFERENCES species_protocols (id) ON DELETE CASCADE,
+    procedure_id INTEGER NOT NULL REFERENCES procedures (id) ON DELETE CASCADE,
+    offset_days INTEGER NOT NULL CHECK (offset_days >= 0) -- Días desde el nacimiento (o la aplicación)
+);
+
+-- Referencias de pacientes entre veterinarios y clínicas externas
+CREATE TABLE referrals (
+    id SERIAL PRIMARY KEY,
+    patient_id INTEGER NOT NULL REFERENCES patients(id) ON DELETE CASCADE,
+    direction referral_direction NOT NULL,
+    internal_user_id INTEGER REFERENCES users(id) ON DELETE SET NULL, -- Contraparte interna
+    external_clinic VARCHAR(150), -- Contraparte externa (texto libre)
+    reason TEXT NOT NULL,
+    referral_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    medical_record_id INTEGER REFERENCES medical_records(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Hospitalizaciones (pacientes internados)
+CREATE TABLE hospitalizations (
+    id SERIAL PRIMARY KEY,
+    patient_id INTEGER NOT NULL REFERENCES patients (id) ON DELETE CASCADE,
+    veterinarian_id INTEGER REFERENCES users (id) ON DELETE SET NULL, -- Veterinario a cargo
+    admitted_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    expected_discharge_at TIMESTAMPTZ,
+    discharged_at TIMESTAMPTZ, -- NULL mientras sigue internado
+    kennel VARCHAR(50), -- Jaula o cubículo
+    reason TEXT NOT NULL,
+    discharge_summary TEXT,
+    CONSTRAINT chk_expected_discharge CHECK (
+        expected_discharge_at IS NULL
+        OR expected_discharge_at > admitted_at
+    ),
+    CONSTRAINT chk_discharge_after_admission CHECK (
+        discharged_at IS NULL
+        OR discharged_at >= admitted_at
+    )
+);
+
+-- Tareas de tratamiento durante la hospitalización (medicación, curaciones)
+CREATE TABLE treatment_tasks (
+    id SERIAL PRIMARY KEY,
+    hospitalization_id INTEGER NOT NULL REFERENCES hospitalizations (id) ON DELETE CASCADE,
+    description TEXT NOT NULL,
+    scheduled_time TIMESTAMPTZ NOT NULL,
+    assigned_role user_role, -- Rol que debe realizarla (NULL = cualquiera)
+    status task_status NOT NULL DEFAULT 'pending',
+    done_by INTEGER REFERENCES users (id) ON DELETE SET NULL,
+    done_at TIMESTAMPTZ,
+    notes TEXT
+);
+
 -- Primero creamos la tabla sin el CHECK que contiene subconsulta
 CREATE TABLE appointments (
     id SERIAL PRIMARY KEY,
@@ -154,10 +358,41 @@ CREATE TABLE appointments (
     end_time TIMESTAMPTZ,
     status appointment_status DEFAULT 'scheduled',
     reason TEXT NOT NULL,
+    is_emergency BOOLEAN NOT NULL DEFAULT FALSE,
+    procedure_id INTEGER REFERENCES procedures(id) ON DELETE SET NULL, -- Procedimiento planeado
+    room_id INTEGER REFERENCES rooms(id) ON DELETE SET NULL, -- Asignada según el procedimiento
+    booking_source booking_source NOT NULL DEFAULT 'staff', -- Deducido de las credenciales
+    internal_notes VARCHAR(2000), -- Solo personal de la clínica; nunca se muestra al dueño
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CLOCK_TIMESTAMP(), -- Cursor de /sync (trg_touch_updated_at)
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), -- Cuándo se agendó
+    canceled_at TIMESTAMPTZ, -- Cuándo pasó a canceled
+    cancellation_reason VARCHAR(500),
     -- Fechas coherentes
     CONSTRAINT chk_valid_times CHECK (start_time < end_time)
 );
 
+-- Notas de seguimiento de la cita (ej: "el dueño llegará tarde"); no se editan
+CREATE TABLE appointment_notes (
+    id SERIAL PRIMARY KEY,
+    appointment_id INTEGER NOT NULL REFERENCES appointments (id) ON DELETE CASCADE,
+    author_id INTEGER NOT NULL REFERENCES users (id),
+    content VARCHAR(1000) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_appointment_notes_appointment ON appointment_notes (appointment_id, created_at DESC);
+
+-- Encuesta de satisfacción enviada al completar una cita; se responde una sola vez
+CREATE TABLE satisfaction_surveys (
+    id SERIAL PRIMARY KEY,
+    appointment_id INTEGER NOT NULL UNIQUE REFERENCES appointments (id) ON DELETE CASCADE,
+    rating SMALLINT CHECK (rating BETWEEN 1 AND 5), -- NULL hasta que se responde
+    comment VARCHAR(1000),
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    answered_at TIMESTAMPTZ,
+    CONSTRAINT chk_survey_answer CHECK ((rating IS NULL) = (answered_at IS NULL))
+);
+
 -- Función de validación para el rol de veterinario
 CREATE OR REPLACE FUNCTION validate_veterinarian_role()
 RETURNS TRIGGER AS $$
@@ -178,17 +413,217 @@ CREATE TRIGGER trg_validate_veterinarian_role
 BEFORE INSERT OR UPDATE ON appointments
 FOR EACH ROW EXECUTE FUNCTION validate_veterinarian_role();
 
+-- Llaves de API para cuentas de servicio (scripts, integraciones)
+CREATE TABLE api_keys (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    prefix VARCHAR(16) UNIQUE NOT NULL, -- Parte pública de la llave, identifica la fila
+    key_hash VARCHAR(255) NOT NULL,
+    role user_role NOT NULL,
+    scopes TEXT[] NOT NULL DEFAULT '{}', -- Prefijos de recursos permitidos (vacío = todos)
+    read_only BOOLEAN NOT NULL DEFAULT FALSE,
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    created_by INTEGER NOT NULL REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+-- Resumen diario de agenda enviado a cada veterinario (evita duplicados al reintentar)
+CREATE TABLE digest_deliveries (
+    id SERIAL PRIMARY KEY,
+    veterinarian_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    digest_date DATE NOT NULL, -- Día de la agenda enviada
+    appointments INTEGER NOT NULL,
+    attempted_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    sent_at TIMESTAMPTZ, -- NULL si el último intento falló
+    error TEXT,
+    UNIQUE (veterinarian_id, digest_date)
+);
+
+-- Bitácora de notificaciones enviadas (encuestas, resúmenes) para soporte
+CREATE TABLE notification_log (
+    id SERIAL PRIMARY KEY,
+    channel notification_channel NOT NULL,
+    target VARCHAR(255) NOT NULL, -- Destinatario (correo)
+    template VARCHAR(50) NOT NULL, -- satisfaction_survey, daily_digest, password_reset
+    subject VARCHAR(255) NOT NULL,
+    body TEXT, -- Se conserva para reenviar el mismo mensaje; NULL si llevaba un secreto
+    client_id INTEGER REFERENCES clients (id) ON DELETE SET NULL,
+    appointment_id INTEGER REFERENCES appointments (id) ON DELETE SET NULL,
+    outcome notification_outcome NOT NULL DEFAULT 'pending',
+    provider_message_id VARCHAR(255), -- Message-ID del correo
+    error TEXT,
+    retry_of INTEGER REFERENCES notification_log (id) ON DELETE SET NULL, -- Envío original que se reintenta
+    attempted_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_notification_log_attempted ON notification_log (attempted_at);
+
+CREATE INDEX idx_notification_log_client ON notification_log (client_id, attempted_at);
+
+-- Un envío original admite un solo reintento en curso o exitoso
+CREATE UNIQUE INDEX idx_notification_log_retry ON notification_log (retry_of)
+WHERE outcome <> 'failed';
+
+-- Campos personalizados definidos por la clínica para pacientes y clientes
+CREATE TABLE custom_field_definitions (
+    id SERIAL PRIMARY KEY,
+    entity_type custom_field_entity NOT NULL,
+    key VARCHAR(50) NOT NULL,
+    label VARCHAR(100) NOT NULL,
+    value_type custom_field_type NOT NULL,
+    required BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT chk_custom_field_key CHECK (key ~ '^[a-z][a-z0-9_]*$'),
+    CONSTRAINT uq_custom_field_key UNIQUE (entity_type, key)
+);
+
 -- Indices
 -- Para búsquedas frecuentes
 CREATE INDEX idx_patient_client ON patients (client_id);
 
+CREATE INDEX idx_clients_email ON clients (email);
+
+CREATE INDEX idx_clients_household ON clients (household_id);
+
 CREATE INDEX idx_medical_patient ON medical_records (patient_id);
 
 CREATE INDEX idx_appointment_vet ON appointments (veterinarian_id);
 
 CREATE INDEX idx_appointment_status ON appointments (status);
 
+-- Rangos de fechas y `at` del listado de citas
+CREATE INDEX idx_appointment_start ON appointments (start_time);
+
+-- `created_after` / `created_before` del listado de citas
+CREATE INDEX idx_appointment_created ON appointments (created_at);
+
+CREATE INDEX idx_protocol_items_protocol ON species_protocol_items (protocol_id);
+
+CREATE INDEX idx_treatment_tasks_pending ON treatment_tasks (scheduled_time)
+WHERE
+    status = 'pending';
+
+CREATE INDEX idx_referrals_patient ON referrals (patient_id, referral_date);
+
+-- Un paciente solo puede tener una hospitalización activa
+CREATE UNIQUE INDEX idx_hospitalization_active ON hospitalizations (patient_id)
+WHERE
+    discharged_at IS NULL;
+
 -- Para campos únicos adicionales
 CREATE UNIQUE INDEX idx_client_phone ON clients (phone)
 WHERE
     phone IS NOT NULL;
+
+-- Sincronización diferencial (/sync): updated_at se actualiza en cada UPDATE y
+-- los borrados quedan como lápidas. Se usa CLOCK_TIMESTAMP() y no NOW() para
+-- que las filas de una misma transacción no compartan la marca de tiempo.
+CREATE OR REPLACE FUNCTION touch_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at := CLOCK_TIMESTAMP();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TABLE sync_tombstones (
+    id SERIAL PRIMARY KEY,
+    entity VARCHAR(30) NOT NULL, -- clients, patients o appointments
+    entity_id INTEGER NOT NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT CLOCK_TIMESTAMP()
+);
+
+CREATE INDEX idx_sync_tombstones_entity ON sync_tombstones (entity, deleted_at);
+
+CREATE OR REPLACE FUNCTION record_tombstone()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO sync_tombstones (entity, entity_id) VALUES (TG_TABLE_NAME, OLD.id);
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_touch_updated_at BEFORE UPDATE ON clients
+FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+CREATE TRIGGER trg_touch_updated_at BEFORE UPDATE ON patients
+FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+CREATE TRIGGER trg_touch_updated_at BEFORE UPDATE ON appointments
+FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+CREATE TRIGGER trg_record_tombstone AFTER DELETE ON clients
+FOR EACH ROW EXECUTE FUNCTION record_tombstone();
+CREATE TRIGGER trg_record_tombstone AFTER DELETE ON patients
+FOR EACH ROW EXECUTE FUNCTION record_tombstone();
+CREATE TRIGGER trg_record_tombstone AFTER DELETE ON appointments
+FOR EACH ROW EXECUTE FUNCTION record_tombstone();
+
+CREATE INDEX idx_clients_updated_at ON clients (updated_at);
+CREATE INDEX idx_patients_updated_at ON patients (updated_at);
+CREATE INDEX idx_appointments_updated_at ON appointments (updated_at);
+
+-- Tokens de renovación del login. Solo se guarda el hash; se eliminan al
+-- canjearlos (se emite otro) o al cerrar sesión
+CREATE TABLE refresh_tokens (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    prefix VARCHAR(16) UNIQUE NOT NULL, -- Parte pública del token, identifica la fila
+    token_hash VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_refresh_tokens_user ON refresh_tokens (user_id);
+
+-- Enlaces para restablecer la contraseña. Solo se guarda el hash; sirven una
+-- sola vez (`used_at`) y hasta `expires_at`
+CREATE TABLE password_reset_tokens (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    prefix VARCHAR(16) UNIQUE NOT NULL,
+    token_hash VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_password_reset_tokens_user ON password_reset_tokens (user_id);
+
+-- Citas canceladas o no_show antiguas que el archivado (archive.rs) saca de
+-- appointments. Misma forma y mismas columnas en el mismo orden, para moverlas
+-- con INSERT ... SELECT; sin triggers, así que conservan su updated_at.
+CREATE TABLE appointments_archive (
+    LIKE appointments INCLUDING DEFAULTS INCLUDING CONSTRAINTS INCLUDING INDEXES,
+    FOREIGN KEY (patient_id) REFERENCES patients (id) ON DELETE SET NULL,
+    FOREIGN KEY (client_id) REFERENCES clients (id) ON DELETE SET NULL,
+    FOREIGN KEY (veterinarian_id) REFERENCES users (id),
+    FOREIGN KEY (procedure_id) REFERENCES procedures (id) ON DELETE SET NULL,
+    FOREIGN KEY (room_id) REFERENCES rooms (id) ON DELETE SET NULL
+);
+
+-- Horario de atención por día de la semana (ISO: 1 = lunes ... 7 = domingo),
+-- en hora local de CLINIC_TIMEZONE. Un día sin fila es un día cerrado.
+CREATE TABLE clinic_hours (
+    weekday SMALLINT PRIMARY KEY CHECK (weekday BETWEEN 1 AND 7),
+    opens_at TIME NOT NULL,
+    closes_at TIME NOT NULL,
+    CHECK (closes_at > opens_at)
+);
+
+INSERT INTO clinic_hours (weekday, opens_at, closes_at) VALUES
+    (1, '08:00', '20:00'),
+    (2, '08:00', '20:00'),
+    (3, '08:00', '20:00'),
+    (4, '08:00', '20:00'),
+    (5, '08:00', '20:00'),
+    (6, '08:00', '20:00');
+
+-- Versión del esquema; el servidor la verifica al arrancar (db::SCHEMA_VERSION)
+CREATE TABLE schema_version (
+    version INTEGER NOT NULL,
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO schema_version (version) VALUES (4);
@@ -350,3 +350,13 @@ VALUES
         'scheduled',
         'Behavioral consult'
     );
+
+-- Folios de los registros médicos de ejemplo
+UPDATE medical_records m
+SET folio = numbered.folio
+FROM (SELECT id, ROW_NUMBER() OVER (ORDER BY id) AS folio FROM medical_records) numbered
+WHERE m.id = numbered.id;
+
+UPDATE document_sequences
+SET last_value = (SELECT COALESCE(MAX(folio), 0) FROM medical_records)
+WHERE document_type = 'medical_record';
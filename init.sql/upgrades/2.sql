@@ -0,0 +1,44 @@
+-- Actualiza una base de datos de la versión 1 a la 2: el enum animal_species
+-- se reemplaza por el catálogo `species`, que se administra desde la API.
+-- Los valores existentes se conservan como códigos ('dog', 'cat', ...).
+-- Ejecutar una sola vez, con la API detenida.
+BEGIN;
+
+CREATE TABLE species (
+    code VARCHAR(30) PRIMARY KEY CHECK (code ~ '^[a-z][a-z0-9_]{1,29}$'),
+    name VARCHAR(50) NOT NULL UNIQUE, -- Nombre para mostrar
+    is_built_in BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO species (code, name, is_built_in) VALUES
+    ('dog', 'Perro', TRUE),
+    ('cat', 'Gato', TRUE),
+    ('bird', 'Ave', TRUE),
+    ('reptile', 'Reptil', TRUE),
+    ('rodent', 'Roedor', TRUE),
+    ('rabbit', 'Conejo', TRUE),
+    ('other', 'Otra', TRUE);
+
+ALTER TABLE breeds
+    ALTER COLUMN species TYPE VARCHAR(30) USING species::TEXT,
+    ADD FOREIGN KEY (species) REFERENCES species (code);
+
+ALTER TABLE patients
+    ALTER COLUMN species TYPE VARCHAR(30) USING species::TEXT,
+    ADD FOREIGN KEY (species) REFERENCES species (code);
+
+ALTER TABLE species_protocols
+    ALTER COLUMN species TYPE VARCHAR(30) USING species::TEXT,
+    ADD FOREIGN KEY (species) REFERENCES species (code);
+
+ALTER TABLE procedures
+    ALTER COLUMN species DROP DEFAULT,
+    ALTER COLUMN species TYPE VARCHAR(30)[] USING species::TEXT[],
+    ALTER COLUMN species SET DEFAULT '{}';
+
+DROP TYPE animal_species;
+
+INSERT INTO schema_version (version) VALUES (2);
+
+COMMIT;
@@ -0,0 +1,38 @@
+-- Actualiza una base de datos de la versión 2 a la 3: agrega la bitácora de
+-- notificaciones `notification_log`.
+-- Ejecutar una sola vez, con la API detenida.
+BEGIN;
+
+CREATE TYPE notification_channel AS ENUM ('email');
+
+CREATE TYPE notification_outcome AS ENUM ('pending', 'sent', 'failed');
+
+-- Bitácora de notificaciones enviadas (encuestas, resúmenes) para soporte
+CREATE TABLE notification_log (
+    id SERIAL PRIMARY KEY,
+    channel notification_channel NOT NULL,
+    target VARCHAR(255) NOT NULL, -- Destinatario (correo)
+    template VARCHAR(50) NOT NULL, -- satisfaction_survey, daily_digest, password_reset
+    subject VARCHAR(255) NOT NULL,
+    body TEXT, -- Se conserva para reenviar el mismo mensaje; NULL si llevaba un secreto
+    client_id INTEGER REFERENCES clients (id) ON DELETE SET NULL,
+    appointment_id INTEGER REFERENCES appointments (id) ON DELETE SET NULL,
+    outcome notification_outcome NOT NULL DEFAULT 'pending',
+    provider_message_id VARCHAR(255), -- Message-ID del correo
+    error TEXT,
+    retry_of INTEGER REFERENCES notification_log (id) ON DELETE SET NULL, -- Envío original que se reintenta
+    attempted_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_notification_log_attempted ON notification_log (attempted_at);
+
+CREATE INDEX idx_notification_log_client ON notification_log (client_id, attempted_at);
+
+-- Un envío original admite un solo reintento en curso o exitoso
+CREATE UNIQUE INDEX idx_notification_log_retry ON notification_log (retry_of)
+WHERE outcome <> 'failed';
+
+INSERT INTO schema_version (version) VALUES (3);
+
+COMMIT;
@@ -0,0 +1,256 @@
+-- Actualiza una base de datos de la versión 3 a la 4: agrega lo que 0.sql
+-- incorporó sin script propio (hogares y contactos de clientes, salas,
+-- plantillas y folios de registros médicos, consentimientos, encuestas,
+-- sincronización diferencial, tokens de sesión y de restablecimiento, archivo
+-- de citas, horario de atención, origen de las citas, seguros, etc.).
+-- Cada sentencia se omite si el objeto ya existe, así que también puede
+-- aplicarse a una base creada con un 0.sql intermedio de la versión 3.
+-- Los registros médicos existentes quedan sin folio.
+-- Ejecutar una sola vez, con la API detenida.
+BEGIN;
+
+DO $$
+BEGIN
+    CREATE TYPE booking_source AS ENUM ('staff', 'portal', 'api', 'import', 'walk_in');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
+
+ALTER TYPE booking_source ADD VALUE IF NOT EXISTS 'walk_in';
+
+-- Usuarios
+ALTER TABLE users ADD COLUMN IF NOT EXISTS last_login_at TIMESTAMPTZ;
+
+-- Hogares: clientes de una misma familia, que pueden compartir email
+CREATE TABLE IF NOT EXISTS households (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- El email ya no es único: lo comparten los integrantes de un hogar
+ALTER TABLE clients DROP CONSTRAINT IF EXISTS clients_email_key;
+
+ALTER TABLE clients
+    ADD COLUMN IF NOT EXISTS household_id INTEGER REFERENCES households(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS marketing_opt_out BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT CLOCK_TIMESTAMP();
+
+CREATE TABLE IF NOT EXISTS client_contacts (
+    id SERIAL PRIMARY KEY,
+    client_id INTEGER NOT NULL REFERENCES clients(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    relationship VARCHAR(50),
+    phone VARCHAR(20),
+    email VARCHAR(255),
+    is_primary BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_client_contacts_primary ON client_contacts(client_id) WHERE is_primary;
+
+-- Razas: sin duplicados que solo difieran en mayúsculas. Falla si ya los hay;
+-- en ese caso hay que unificarlos antes de reintentar.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_breed_species_name_lower ON breeds (species, LOWER(name));
+
+-- Pacientes
+ALTER TABLE patients
+    ADD COLUMN IF NOT EXISTS deceased_on DATE,
+    ADD COLUMN IF NOT EXISTS insurance_provider VARCHAR(100),
+    ADD COLUMN IF NOT EXISTS insurance_policy_number VARCHAR(50),
+    ADD COLUMN IF NOT EXISTS insurance_expires_on DATE,
+    ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT CLOCK_TIMESTAMP();
+
+DO $$
+BEGIN
+    ALTER TABLE patients ADD CONSTRAINT chk_patient_insurance CHECK (
+        insurance_provider IS NOT NULL
+        OR (insurance_policy_number IS NULL AND insurance_expires_on IS NULL)
+    );
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
+
+-- Registros médicos: signos vitales y folio
+ALTER TABLE medical_records
+    ADD COLUMN IF NOT EXISTS temperature_c DECIMAL(3, 1),
+    ADD COLUMN IF NOT EXISTS heart_rate_bpm INTEGER,
+    ADD COLUMN IF NOT EXISTS respiratory_rate_rpm INTEGER,
+    ADD COLUMN IF NOT EXISTS folio BIGINT UNIQUE;
+
+CREATE TABLE IF NOT EXISTS document_sequences (
+    document_type VARCHAR(50) PRIMARY KEY,
+    last_value BIGINT NOT NULL DEFAULT 0
+);
+
+INSERT INTO document_sequences (document_type) VALUES ('medical_record')
+ON CONFLICT DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS record_templates (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    diagnosis TEXT,
+    treatment TEXT,
+    notes TEXT,
+    owner_id INTEGER REFERENCES users (id) ON DELETE SET NULL,
+    is_shared BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+CREATE INDEX IF NOT EXISTS idx_record_templates_owner ON record_templates (owner_id);
+
+-- Procedimientos y salas
+ALTER TABLE procedures ADD COLUMN IF NOT EXISTS required_room_type VARCHAR(50);
+
+CREATE TABLE IF NOT EXISTS rooms (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL UNIQUE,
+    room_type VARCHAR(50) NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+ALTER TABLE patient_procedures
+    ADD COLUMN IF NOT EXISTS batch_number VARCHAR(50),
+    ADD COLUMN IF NOT EXISTS manufacturer VARCHAR(100),
+    ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+CREATE INDEX IF NOT EXISTS idx_patient_procedure_batch ON patient_procedures (LOWER(batch_number))
+WHERE batch_number IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS procedure_consents (
+    id SERIAL PRIMARY KEY,
+    patient_procedure_id INTEGER NOT NULL UNIQUE REFERENCES patient_procedures (id) ON DELETE CASCADE,
+    client_name VARCHAR(100),
+    accepted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ip_address VARCHAR(45),
+    content_type VARCHAR(50),
+    document BYTEA,
+    recorded_by INTEGER REFERENCES users (id),
+    CONSTRAINT chk_consent_document CHECK ((content_type IS NULL) = (document IS NULL)),
+    CONSTRAINT chk_consent_evidence CHECK (client_name IS NOT NULL OR document IS NOT NULL)
+);
+
+-- Citas. Las columnas se agregan en el mismo orden que en 0.sql, porque
+-- appointments_archive se crea a partir de esta tabla
+ALTER TABLE appointments
+    ADD COLUMN IF NOT EXISTS procedure_id INTEGER REFERENCES procedures(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS room_id INTEGER REFERENCES rooms(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS booking_source booking_source NOT NULL DEFAULT 'staff',
+    ADD COLUMN IF NOT EXISTS internal_notes VARCHAR(2000),
+    ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT CLOCK_TIMESTAMP(),
+    ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ADD COLUMN IF NOT EXISTS canceled_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS cancellation_reason VARCHAR(500);
+
+CREATE TABLE IF NOT EXISTS satisfaction_surveys (
+    id SERIAL PRIMARY KEY,
+    appointment_id INTEGER NOT NULL UNIQUE REFERENCES appointments (id) ON DELETE CASCADE,
+    rating SMALLINT CHECK (rating BETWEEN 1 AND 5),
+    comment VARCHAR(1000),
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    answered_at TIMESTAMPTZ,
+    CONSTRAINT chk_survey_answer CHECK ((rating IS NULL) = (answered_at IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_clients_email ON clients (email);
+
+CREATE INDEX IF NOT EXISTS idx_clients_household ON clients (household_id);
+
+CREATE INDEX IF NOT EXISTS idx_appointment_start ON appointments (start_time);
+
+CREATE INDEX IF NOT EXISTS idx_appointment_created ON appointments (created_at);
+
+-- Sincronización diferencial (/sync)
+CREATE OR REPLACE FUNCTION touch_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at := CLOCK_TIMESTAMP();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TABLE IF NOT EXISTS sync_tombstones (
+    id SERIAL PRIMARY KEY,
+    entity VARCHAR(30) NOT NULL,
+    entity_id INTEGER NOT NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT CLOCK_TIMESTAMP()
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_tombstones_entity ON sync_tombstones (entity, deleted_at);
+
+CREATE OR REPLACE FUNCTION record_tombstone()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO sync_tombstones (entity, entity_id) VALUES (TG_TABLE_NAME, OLD.id);
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER trg_touch_updated_at BEFORE UPDATE ON clients
+FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+CREATE OR REPLACE TRIGGER trg_touch_updated_at BEFORE UPDATE ON patients
+FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+CREATE OR REPLACE TRIGGER trg_touch_updated_at BEFORE UPDATE ON appointments
+FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+CREATE OR REPLACE TRIGGER trg_record_tombstone AFTER DELETE ON clients
+FOR EACH ROW EXECUTE FUNCTION record_tombstone();
+CREATE OR REPLACE TRIGGER trg_record_tombstone AFTER DELETE ON patients
+FOR EACH ROW EXECUTE FUNCTION record_tombstone();
+CREATE OR REPLACE TRIGGER trg_record_tombstone AFTER DELETE ON appointments
+FOR EACH ROW EXECUTE FUNCTION record_tombstone();
+
+CREATE INDEX IF NOT EXISTS idx_clients_updated_at ON clients (updated_at);
+CREATE INDEX IF NOT EXISTS idx_patients_updated_at ON patients (updated_at);
+CREATE INDEX IF NOT EXISTS idx_appointments_updated_at ON appointments (updated_at);
+
+-- Sesiones y restablecimiento de contraseña
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    prefix VARCHAR(16) UNIQUE NOT NULL,
+    token_hash VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_user ON refresh_tokens (user_id);
+
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    prefix VARCHAR(16) UNIQUE NOT NULL,
+    token_hash VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_password_reset_tokens_user ON password_reset_tokens (user_id);
+
+-- Archivo de citas (archive.rs); va después de completar las columnas de
+-- appointments para heredar la misma forma
+CREATE TABLE IF NOT EXISTS appointments_archive (
+    LIKE appointments INCLUDING DEFAULTS INCLUDING CONSTRAINTS INCLUDING INDEXES,
+    FOREIGN KEY (patient_id) REFERENCES patients (id) ON DELETE SET NULL,
+    FOREIGN KEY (client_id) REFERENCES clients (id) ON DELETE SET NULL,
+    FOREIGN KEY (veterinarian_id) REFERENCES users (id),
+    FOREIGN KEY (procedure_id) REFERENCES procedures (id) ON DELETE SET NULL,
+    FOREIGN KEY (room_id) REFERENCES rooms (id) ON DELETE SET NULL
+);
+
+-- Horario de atención; solo se siembra si la tabla es nueva
+CREATE TABLE IF NOT EXISTS clinic_hours (
+    weekday SMALLINT PRIMARY KEY CHECK (weekday BETWEEN 1 AND 7),
+    opens_at TIME NOT NULL,
+    closes_at TIME NOT NULL,
+    CHECK (closes_at > opens_at)
+);
+
+INSERT INTO clinic_hours (weekday, opens_at, closes_at)
+SELECT d, '08:00', '20:00' FROM generate_series(1, 6) AS d
+WHERE NOT EXISTS (SELECT 1 FROM clinic_hours);
+
+INSERT INTO schema_version (version) VALUES (4);
+
+COMMIT;
@@ -0,0 +1,178 @@
+//! Archivado periódico de citas antiguas que ya no se atenderán.
+//!
+//! Las citas canceladas o `no_show` cuyo inicio es anterior al corte se mueven
+//! de `appointments` a `appointments_archive`, que tiene la misma forma. Siguen
+//! disponibles en `GET /appointments/{id}` y con `include_archived=true`.
+
+use crate::clinic;
+use crate::errors::ApiError;
+use crate::jobs::JobRegistry;
+use crate::models::admin::AppointmentArchiveReport;
+use crate::models::job::JobStatus;
+use chrono::{DateTime, NaiveTime, Utc};
+use sqlx::PgPool;
+use std::env;
+
+/// Tipo de trabajo registrado en `/jobs`
+pub const JOB_KIND: &str = "appointment_archive";
+
+/// Citas movidas por transacción, para no retener bloqueos por mucho tiempo
+const BATCH_SIZE: i64 = 500;
+
+/// Antigüedad en días a partir de la cual se archiva (`APPOINTMENT_ARCHIVE_DAYS`);
+/// `None` si el archivado está desactivado
+pub fn archive_days() -> Option<i64> {
+    env::var("APPOINTMENT_ARCHIVE_DAYS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|days: &i64| *days > 0)
+}
+
+fn cutoff(now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    archive_days().map(|days| now - chrono::Duration::days(days))
+}
+
+/// Hora local de ejecución (`APPOINTMENT_ARCHIVE_TIME`, default: 03:30; `off` la desactiva)
+fn scheduled_time() -> Option<NaiveTime> {
+    match env::var("APPOINTMENT_ARCHIVE_TIME") {
+        Ok(value) if value.eq_ignore_ascii_case("off") => None,
+        Ok(value) => NaiveTime::parse_from_str(&value, "%H:%M").ok(),
+        Err(_) => NaiveTime::from_hms_opt(3, 30, 0),
+    }
+}
+
+/// Programa el archivado diario
+pub fn spawn_scheduler(pool: PgPool, jobs: actix_web::web::Data<JobRegistry>) {
+    let Some(run_at) = scheduled_time() else {
+        tracing::info!("Archivado de citas desactivado");
+        return;
+    };
+
+    actix_web::rt::spawn(async move {
+        loop {
+            let next = clinic::next_occurrence(run_at);
+            tracing::info!("Próximo archivado de citas: {}", next);
+            let wait = (next - Utc::now()).to_std().unwrap_or_default();
+            actix_web::rt::time::sleep(wait).await;
+
+            if archive_days().is_none() {
+                continue;
+            }
+            if let Err(e) = start(&pool, &jobs).await {
+                tracing::error!("No se pudo iniciar el archivado de citas: {:?}", e);
+            }
+        }
+    });
+}
+
+/// Cuenta las citas que se archivarían en este momento
+pub async fn preview(pool: &PgPool) -> Result<AppointmentArchiveReport, ApiError> {
+    let now = Utc::now();
+    let cutoff = cutoff(now);
+    let eligible = match cutoff {
+        Some(cutoff) => sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM appointments a
+            WHERE a.status IN ('canceled', 'no_show')
+              AND a.start_time < $1
+              AND NOT EXISTS (SELECT 1 FROM appointment_notes n WHERE n.appointment_id = a.id)
+            "#,
+            cutoff
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| ApiError::internal("Error al calcular el archivado de citas", e))?,
+        None => 0,
+    };
+    let archived = sqlx::query_scalar!(r#"SELECT COUNT(*) as "count!" FROM appointments_archive"#)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(AppointmentArchiveReport {
+        generated_at: now,
+        archive_days: archive_days(),
+        cutoff,
+        eligible,
+        archived,
+    })
+}
+
+/// Registra el trabajo y lo ejecuta en segundo plano
+pub async fn start(
+    pool: &PgPool,
+    jobs: &actix_web::web::Data<JobRegistry>,
+) -> Result<JobStatus, ApiError> {
+    let report = preview(pool).await?;
+
+    let job = jobs.start(JOB_KIND, report.eligible);
+    tracing::info!("Archivando {} citas (trabajo {})", report.eligible, job.id);
+
+    let pool = pool.clone();
+    let registry = jobs.clone();
+    let job_id = job.id;
+    actix_web::rt::spawn(async move {
+        let Some(cutoff) = report.cutoff else {
+            registry.finish(job_id, Ok("Archivado desactivado".into()));
+            return;
+        };
+
+        let result = archive(&pool, cutoff, |moved| registry.progress(job_id, moved))
+            .await
+            .map(|moved| format!("Citas archivadas: {moved}"))
+            .map_err(|e| {
+                tracing::error!("Archivado de citas: {}", e);
+                format!("Falló el archivado de citas: {e}")
+            });
+        registry.finish(job_id, result);
+    });
+
+    Ok(job)
+}
+
+/// Mueve por lotes las citas elegibles y devuelve cuántas se movieron.
+///
+/// Cada lote se borra de `appointments` y se inserta en el archivo en la misma
+/// sentencia, así que una cita nunca queda en ambas tablas ni en ninguna. Las
+/// citas con notas se dejan en su lugar: las notas dependen de la cita.
+async fn archive(
+    pool: &PgPool,
+    cutoff: DateTime<Utc>,
+    on_progress: impl Fn(i64),
+) -> Result<i64, sqlx::Error> {
+    let mut moved = 0;
+    loop {
+        let batch = sqlx::query!(
+            r#"
+            WITH batch AS (
+                SELECT a.id FROM appointments a
+                WHERE a.status IN ('canceled', 'no_show')
+                  AND a.start_time < $1
+                  AND NOT EXISTS (SELECT 1 FROM appointment_notes n WHERE n.appointment_id = a.id)
+                ORDER BY a.id
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            ), moved AS (
+                DELETE FROM appointments a
+                USING batch
+                WHERE a.id = batch.id
+                RETURNING a.*
+            )
+            INSERT INTO appointments_archive
+            SELECT * FROM moved
+            "#,
+            cutoff,
+            BATCH_SIZE
+        )
+        .execute(pool)
+        .await?
+        .rows_affected() as i64;
+
+        moved += batch;
+        on_progress(moved);
+
+        if batch < BATCH_SIZE {
+            return Ok(moved);
+        }
+    }
+}
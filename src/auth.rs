@@ -1,18 +1,91 @@
 use crate::errors::ApiError;
 use crate::models::enums::UserRole;
+use actix_web::{FromRequest, HttpMessage, HttpRequest, dev::Payload};
 use argon2::{
     Argon2,
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
+    password_hash::{
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+        rand_core::{OsRng, RngCore},
+    },
 };
+use futures::future::{Ready, ready};
 use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use serde::{Deserialize, Serialize};
 use std::env;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Tolerancia a diferencias de reloj al validar `exp` e `iat` de un JWT
+const JWT_LEEWAY_SECONDS: u64 = 60;
+
+/// Prefijo que identifica las llaves de cuentas de servicio frente a un JWT
+pub const API_KEY_PREFIX: &str = "mck_";
+
+/// Vigencia de los tokens de suplantación
+pub const IMPERSONATION_MINUTES: i64 = 15;
+
+/// Vigencia del token de acceso del login (`ACCESS_TOKEN_MINUTES`, default: 15)
+pub fn access_token_minutes() -> i64 {
+    env::var("ACCESS_TOKEN_MINUTES")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|minutes: &i64| *minutes > 0)
+        .unwrap_or(15)
+}
+
+/// Vigencia del token de renovación (`REFRESH_TOKEN_DAYS`, default: 30)
+pub fn refresh_token_days() -> i64 {
+    env::var("REFRESH_TOKEN_DAYS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|days: &i64| *days > 0)
+        .unwrap_or(30)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: i32, // user id
     pub role: UserRole,
+    pub iat: usize, // issued-at timestamp
     pub exp: usize, // expiry timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_id: Option<i32>, // Presente solo en claims sintéticos de llaves de API
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<i32>, // Administrador que actúa como `sub` (soporte)
+}
+
+impl Claims {
+    pub fn issued_at(&self) -> chrono::DateTime<chrono::Utc> {
+        timestamp(self.iat)
+    }
+
+    pub fn expires_at(&self) -> chrono::DateTime<chrono::Utc> {
+        timestamp(self.exp)
+    }
+
+    /// Verifica que el rol del token esté entre los permitidos
+    pub fn require_role(&self, roles: &[UserRole]) -> Result<(), ApiError> {
+        if roles.contains(&self.role) {
+            Ok(())
+        } else {
+            Err(ApiError::Forbidden(
+                "No tienes permisos para realizar esta acción".into(),
+            ))
+        }
+    }
+}
+
+/// Extrae los claims que el middleware de autenticación dejó en la petición
+impl FromRequest for Claims {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<Claims>()
+                .cloned()
+                .ok_or_else(|| ApiError::Unauthorized("Se requiere un token de usuario".into())),
+        )
+    }
 }
 
 pub fn hash_password(password: &str) -> Result<String, ApiError> {
@@ -20,49 +93,209 @@ pub fn hash_password(password: &str) -> Result<String, ApiError> {
     let argon2 = Argon2::default();
     let password_hash = argon2
         .hash_password(password.as_bytes(), &salt)
-        .map_err(|e| ApiError::InternalServerError(e.to_string()))?
+        .map_err(|e| ApiError::internal("Error al procesar la contraseña", e))?
         .to_string();
     Ok(password_hash)
 }
 
 pub fn verify_password(password: &str, hash: &str) -> Result<bool, ApiError> {
-    let parsed_hash =
-        PasswordHash::new(hash).map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+    let parsed_hash = PasswordHash::new(hash)
+        .map_err(|e| ApiError::internal("Error al verificar la contraseña", e))?;
     Ok(Argon2::default()
         .verify_password(password.as_bytes(), &parsed_hash)
         .is_ok())
 }
 
-pub fn create_jwt(user_id: i32, role: &UserRole) -> Result<String, ApiError> {
-    let secret = env::var("JWT_SECRET")
-        .map_err(|_| ApiError::InternalServerError("JWT_SECRET no declarado".into()))?;
-    let expiration = chrono::Utc::now()
-        .checked_add_signed(chrono::Duration::days(30))
-        .expect("valid timestamp")
-        .timestamp();
+/// Genera una llave de API aleatoria con la forma `mck_<prefijo>_<secreto>`.
+///
+/// Devuelve `(prefijo, llave_completa)`; solo el prefijo se guarda en claro.
+pub fn generate_api_key() -> (String, String) {
+    let prefix = random_hex(4);
+    let secret = random_hex(32);
+    let key = format!("{API_KEY_PREFIX}{prefix}_{secret}");
+    (prefix, key)
+}
+
+/// Obtiene el prefijo público de una llave de API, si tiene el formato esperado
+pub fn api_key_prefix(key: &str) -> Option<&str> {
+    key.strip_prefix(API_KEY_PREFIX)?
+        .split_once('_')
+        .map(|(prefix, _)| prefix)
+}
 
-    let claims = Claims {
-        sub: user_id,
-        role: role.clone(),
-        exp: expiration as usize,
-    };
+/// Vigencia del enlace para restablecer la contraseña
+/// (`PASSWORD_RESET_MINUTES`, default: 60)
+pub fn password_reset_minutes() -> i64 {
+    env::var("PASSWORD_RESET_MINUTES")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|minutes: &i64| *minutes > 0)
+        .unwrap_or(60)
+}
 
-    encode(
+/// Genera un token aleatorio de usuario (renovación de sesión o
+/// restablecimiento de contraseña) con la forma `<prefijo>_<secreto>`.
+///
+/// Devuelve `(prefijo, token)`; como en las llaves de API, solo el prefijo se
+/// guarda en claro.
+pub fn generate_user_token() -> (String, String) {
+    let prefix = random_hex(8);
+    let secret = random_hex(32);
+    let token = format!("{prefix}_{secret}");
+    (prefix, token)
+}
+
+/// Obtiene el prefijo de un token de usuario, si tiene el formato esperado
+pub fn user_token_prefix(token: &str) -> Option<&str> {
+    token
+        .split_once('_')
+        .map(|(prefix, _)| prefix)
+        .filter(|prefix| !prefix.is_empty())
+}
+
+fn random_hex(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buffer);
+    buffer.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn timestamp(seconds: usize) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::from_timestamp(seconds as i64, 0)
+        .unwrap_or(chrono::DateTime::<chrono::Utc>::MAX_UTC)
+}
+
+/// JWT firmado junto con los claims que lleva, para informar al cliente las
+/// fechas exactas del token
+#[derive(Debug)]
+pub struct IssuedToken {
+    pub token: String,
+    pub claims: Claims,
+}
+
+/// Emite el token de acceso de un usuario; vence a los `access_token_minutes()`
+pub fn create_jwt(user_id: i32, role: &UserRole) -> Result<IssuedToken, ApiError> {
+    sign(
+        Claims {
+            sub: user_id,
+            role: role.clone(),
+            iat: 0,
+            exp: 0,
+            api_key_id: None,
+            impersonator: None,
+        },
+        chrono::Duration::minutes(access_token_minutes()),
+    )
+}
+
+/// Emite un token de corta duración para que un administrador vea la API como
+/// otro usuario
+pub fn create_impersonation_jwt(
+    user_id: i32,
+    role: &UserRole,
+    impersonator: i32,
+) -> Result<IssuedToken, ApiError> {
+    sign(
+        Claims {
+            sub: user_id,
+            role: role.clone(),
+            iat: 0,
+            exp: 0,
+            api_key_id: None,
+            impersonator: Some(impersonator),
+        },
+        chrono::Duration::minutes(IMPERSONATION_MINUTES),
+    )
+}
+
+/// Secreto de firma de los tokens (`JWT_SECRET`). Las pruebas usan uno fijo si
+/// no está declarado.
+pub fn jwt_secret() -> Result<String, ApiError> {
+    env::var("JWT_SECRET")
+        .or_else(|e| {
+            if cfg!(test) {
+                Ok("secreto-de-pruebas".into())
+            } else {
+                Err(e)
+            }
+        })
+        .map_err(|_| ApiError::InternalServerError("JWT_SECRET no declarado".into()))
+}
+
+/// Firma los claims con `iat` ahora y `exp` tras la vigencia indicada
+fn sign(mut claims: Claims, lifetime: chrono::Duration) -> Result<IssuedToken, ApiError> {
+    let secret = jwt_secret()?;
+
+    let now = chrono::Utc::now();
+    claims.iat = now.timestamp() as usize;
+    claims.exp = (now + lifetime).timestamp() as usize;
+
+    let token = encode(
         &Header::default(),
         &claims,
         &EncodingKey::from_secret(secret.as_bytes()),
     )
-    .map_err(|e| ApiError::InternalServerError(e.to_string()))
+    .map_err(|e| ApiError::internal("Error al firmar el token", e))?;
+    Ok(IssuedToken { token, claims })
 }
 
 pub fn decode_jwt(token: &str) -> Result<Claims, ApiError> {
-    let secret = env::var("JWT_SECRET")
-        .map_err(|_| ApiError::InternalServerError("JWT_SECRET no declarado".into()))?;
-    decode::<Claims>(
+    let secret = jwt_secret()?;
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.leeway = JWT_LEEWAY_SECONDS;
+    let claims = decode::<Claims>(
         token,
         &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::new(Algorithm::HS256),
+        &validation,
     )
     .map(|data| data.claims)
-    .map_err(|e| ApiError::Unauthorized(e.to_string()))
+    .map_err(|e| ApiError::Unauthorized(e.to_string()))?;
+
+    // Un token emitido "en el futuro" solo se explica por un reloj desfasado
+    // más allá de la tolerancia o por uno fabricado
+    let now = chrono::Utc::now().timestamp() as u64;
+    if claims.iat as u64 > now + JWT_LEEWAY_SECONDS || claims.iat > claims.exp {
+        return Err(ApiError::Unauthorized(
+            "Fecha de emisión del token inválida".into(),
+        ));
+    }
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(role: UserRole) -> Claims {
+        Claims {
+            sub: 7,
+            role,
+            iat: 0,
+            exp: usize::MAX,
+            api_key_id: None,
+            impersonator: None,
+        }
+    }
+
+    #[test]
+    fn assistant_is_rejected_from_user_management() {
+        let result = claims(UserRole::Assistant).require_role(&[UserRole::Admin]);
+        assert!(matches!(result, Err(ApiError::Forbidden(_))));
+    }
+
+    #[test]
+    fn admin_passes_user_management() {
+        assert!(
+            claims(UserRole::Admin)
+                .require_role(&[UserRole::Admin])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn medical_records_require_a_veterinarian() {
+        let roles = [UserRole::Veterinarian];
+        assert!(claims(UserRole::Veterinarian).require_role(&roles).is_ok());
+        assert!(claims(UserRole::Admin).require_role(&roles).is_err());
+        assert!(claims(UserRole::Assistant).require_role(&roles).is_err());
+    }
 }
@@ -0,0 +1,189 @@
+use crate::clinic;
+use crate::errors::ApiError;
+use crate::models::enums::AnimalSpecies;
+use crate::models::patient::{ClaimProcedure, InsuranceClaim};
+use crate::pdf::{PdfWriter, Style};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+
+/// Una reclamación cabe de sobra en unas cuantas páginas
+const CLAIM_MAX_PAGES: usize = 20;
+
+/// Reúne la póliza del paciente, la consulta reclamada y los procedimientos
+/// aplicados ese mismo día (fecha local) con su precio de catálogo.
+///
+/// 404 si el paciente no existe o el registro médico no es suyo; 422 si el
+/// paciente no tiene seguro o la póliza ya estaba vencida el día de la consulta.
+pub async fn build(
+    pool: &PgPool,
+    patient_id: i32,
+    medical_record_id: i32,
+) -> Result<InsuranceClaim, ApiError> {
+    let patient = sqlx::query!(
+        r#"
+        SELECT
+            p.name,
+            p.species as "species!: AnimalSpecies",
+            p.insurance_provider,
+            p.insurance_policy_number,
+            p.insurance_expires_on,
+            c.name as "owner_name?"
+        FROM patients p
+        LEFT JOIN clients c ON c.id = p.client_id
+        WHERE p.id = $1
+        "#,
+        patient_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::NotFound("El paciente no existe".into()))?;
+
+    let insurance_provider = patient
+        .insurance_provider
+        .ok_or(ApiError::UnprocessableEntity(
+            "El paciente no tiene seguro registrado".into(),
+        ))?;
+
+    let record = sqlx::query!(
+        r#"
+        SELECT
+            mr.date as "date!: DateTime<Utc>",
+            mr.folio,
+            mr.diagnosis,
+            mr.treatment,
+            u.name as "veterinarian_name?"
+        FROM medical_records mr
+        LEFT JOIN users u ON u.id = mr.veterinarian_id
+        WHERE mr.id = $1 AND mr.patient_id = $2
+        "#,
+        medical_record_id,
+        patient_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::NotFound("El registro médico no existe".into()))?;
+
+    let visit_date = clinic::local(record.date).date_naive();
+    if let Some(expires_on) = patient.insurance_expires_on.filter(|on| *on < visit_date) {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "La póliza venció el {} y la consulta fue el {}",
+            expires_on, visit_date
+        )));
+    }
+
+    let procedures = sqlx::query_as!(
+        ClaimProcedure,
+        r#"
+        SELECT pp.id, pr.name, pp.date, pr.price::FLOAT8 as price
+        FROM patient_procedures pp
+        JOIN procedures pr ON pr.id = pp.procedure_id
+        WHERE pp.patient_id = $1 AND pp.date = $2
+        ORDER BY pp.id
+        "#,
+        patient_id,
+        visit_date
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(InsuranceClaim {
+        patient_id,
+        patient_name: patient.name,
+        species: patient.species,
+        owner_name: patient.owner_name,
+        insurance_provider,
+        insurance_policy_number: patient.insurance_policy_number,
+        insurance_expires_on: patient.insurance_expires_on,
+        medical_record_id,
+        folio: record.folio,
+        visit_date,
+        veterinarian_name: record.veterinarian_name,
+        diagnosis: record.diagnosis,
+        treatment: record.treatment,
+        total: procedures
+            .iter()
+            .filter_map(|procedure| procedure.price)
+            .fold(0.0, |total, price| total + price),
+        procedures,
+    })
+}
+
+fn date(date: NaiveDate) -> String {
+    date.format("%d/%m/%Y").to_string()
+}
+
+/// La misma reclamación como PDF para adjuntarla al trámite
+pub fn render_pdf(claim: &InsuranceClaim) -> Vec<u8> {
+    let mut pdf = PdfWriter::new(
+        format!(
+            "Reclamación de seguro - {} - consulta del {}",
+            claim.patient_name,
+            date(claim.visit_date)
+        ),
+        CLAIM_MAX_PAGES,
+    );
+
+    pdf.text(Style::Title, "Reclamación de seguro de mascota");
+    pdf.gap(6.0);
+    pdf.text(Style::Heading, "Póliza");
+    pdf.text(
+        Style::Body,
+        &format!("Aseguradora: {}", claim.insurance_provider),
+    );
+    pdf.text(
+        Style::Body,
+        &format!(
+            "Número de póliza: {}    Vigencia: {}",
+            claim.insurance_policy_number.as_deref().unwrap_or("-"),
+            claim.insurance_expires_on.map_or("-".into(), date)
+        ),
+    );
+    pdf.text(
+        Style::Body,
+        &format!(
+            "Asegurado: {} ({:?})    Titular: {}",
+            claim.patient_name,
+            claim.species,
+            claim.owner_name.as_deref().unwrap_or("-")
+        ),
+    );
+
+    pdf.gap(10.0);
+    pdf.text(Style::Heading, "Consulta");
+    pdf.text(
+        Style::Body,
+        &format!(
+            "Fecha: {}    Folio: {}    Veterinario: {}",
+            date(claim.visit_date),
+            claim.folio.map_or("-".into(), |folio| folio.to_string()),
+            claim.veterinarian_name.as_deref().unwrap_or("-")
+        ),
+    );
+    pdf.text(Style::Body, &format!("Diagnóstico: {}", claim.diagnosis));
+    if let Some(treatment) = &claim.treatment {
+        pdf.text(Style::Body, &format!("Tratamiento: {treatment}"));
+    }
+
+    pdf.gap(10.0);
+    pdf.text(Style::Heading, "Procedimientos");
+    if claim.procedures.is_empty() {
+        pdf.text(Style::Body, "Sin procedimientos registrados ese día");
+    }
+    for procedure in &claim.procedures {
+        pdf.text(
+            Style::Body,
+            &format!(
+                "{}  {}  {}",
+                date(procedure.date),
+                procedure.name,
+                procedure
+                    .price
+                    .map_or("Sin precio".into(), |price| format!("${price:.2}"))
+            ),
+        );
+    }
+    pdf.gap(6.0);
+    pdf.text(Style::Heading, &format!("Total: ${:.2}", claim.total));
+
+    pdf.finish()
+}
@@ -0,0 +1,277 @@
+use crate::errors::ApiError;
+use crate::models::lobby::ClinicProfile;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Zona horaria de la clínica (`CLINIC_TIMEZONE`, default: America/Mexico_City)
+pub fn timezone() -> Tz {
+    env::var("CLINIC_TIMEZONE")
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(chrono_tz::America::Mexico_City)
+}
+
+/// Nombre IANA de la zona horaria de la clínica (ej: `America/Mexico_City`)
+pub fn timezone_name() -> &'static str {
+    timezone().name()
+}
+
+/// Instante en hora local de la clínica. Las respuestas, los PDF y los correos
+/// convierten con esta función para mostrar la misma hora.
+pub fn local(at: DateTime<Utc>) -> DateTime<Tz> {
+    at.with_timezone(&timezone())
+}
+
+/// Instante en hora local de la clínica en ISO 8601 con su desplazamiento
+/// (ej: `2024-06-03T09:00:00-06:00`)
+pub fn local_iso(at: DateTime<Utc>) -> String {
+    local_iso_in(&timezone(), at)
+}
+
+fn local_iso_in(tz: &Tz, at: DateTime<Utc>) -> String {
+    at.with_timezone(tz)
+        .format("%Y-%m-%dT%H:%M:%S%:z")
+        .to_string()
+}
+
+/// Instante, en UTC, de una hora local. Si la hora se salta al adelantar el
+/// reloj (cambio de horario), se toma la misma hora ya adelantada; si se
+/// repite al atrasarlo, la primera de las dos.
+fn from_local(tz: &Tz, naive: NaiveDateTime) -> DateTime<Utc> {
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(naive + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .unwrap_or_else(|| tz.from_utc_datetime(&naive))
+        .with_timezone(&Utc)
+}
+
+/// Inicio y fin (exclusivo) de un día local de la clínica, en UTC
+pub fn day_bounds(date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    day_bounds_in(&timezone(), date)
+}
+
+fn day_bounds_in(tz: &Tz, date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let local_midnight = |day: NaiveDate| from_local(tz, day.and_time(NaiveTime::MIN));
+    (
+        local_midnight(date),
+        local_midnight(date + chrono::Days::new(1)),
+    )
+}
+
+/// Inicio y fin (exclusivo) de la semana local, de lunes a domingo, que contiene `date`
+pub fn week_bounds(date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let monday = date - chrono::Days::new(date.weekday().num_days_from_monday().into());
+    (
+        day_bounds(monday).0,
+        day_bounds(monday + chrono::Days::new(7)).0,
+    )
+}
+
+/// Fecha local actual de la clínica
+pub fn today() -> NaiveDate {
+    Utc::now().with_timezone(&timezone()).date_naive()
+}
+
+/// Próximo instante, en UTC, en que el reloj local de la clínica marca `time`
+pub fn next_occurrence(time: NaiveTime) -> DateTime<Utc> {
+    let tz = timezone();
+    let now = Utc::now();
+    let mut next = today().and_time(time);
+    if tz
+        .from_local_datetime(&next)
+        .earliest()
+        .is_none_or(|local| local <= now)
+    {
+        next += chrono::Duration::days(1);
+    }
+    tz.from_local_datetime(&next)
+        .earliest()
+        .map_or(now + chrono::Duration::days(1), |local| {
+            local.with_timezone(&Utc)
+        })
+}
+
+/// Dato de contacto de la clínica (`CLINIC_NAME`, `CLINIC_ADDRESS`,
+/// `CLINIC_PHONE`); `None` si no está configurado
+pub fn contact(name: &str) -> Option<String> {
+    env::var(name)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Nombre en plural del día de la semana, para los mensajes ("los domingos")
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "lunes",
+        Weekday::Tue => "martes",
+        Weekday::Wed => "miércoles",
+        Weekday::Thu => "jueves",
+        Weekday::Fri => "viernes",
+        Weekday::Sat => "sábados",
+        Weekday::Sun => "domingos",
+    }
+}
+
+/// Instante, en UTC, en que el reloj local de la clínica marca `time` el día `date`
+pub fn local_instant(date: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+    from_local(&timezone(), date.and_time(time))
+}
+
+/// Horario de atención (apertura, cierre) de un día local según la tabla
+/// `clinic_hours`, o `None` si la clínica no abre ese día
+pub async fn hours_on(
+    pool: &PgPool,
+    date: NaiveDate,
+) -> Result<Option<(NaiveTime, NaiveTime)>, ApiError> {
+    let weekday = i16::try_from(date.weekday().number_from_monday()).unwrap_or_default();
+    let hours = sqlx::query!(
+        "SELECT opens_at, closes_at FROM clinic_hours WHERE weekday = $1",
+        weekday
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(hours.map(|hours| (hours.opens_at, hours.closes_at)))
+}
+
+/// Si el rango cae completo dentro del horario del día en que empieza
+pub fn within_hours(
+    hours: Option<(NaiveTime, NaiveTime)>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> bool {
+    let tz = timezone();
+    let (local_start, local_end) = (start.with_timezone(&tz), end.with_timezone(&tz));
+    hours.is_some_and(|(opening, closing)| {
+        local_start.date_naive() == local_end.date_naive()
+            && local_start.time() >= opening
+            && local_end.time() <= closing
+    })
+}
+
+/// Verifica que el rango caiga completo dentro del horario de atención del
+/// día en que empieza: una cita que cruza el cierre o cae en un día cerrado
+/// responde 400. Con `exempt` (urgencias y visitas sin cita ya ocurridas) no
+/// se rechaza; se devuelve la advertencia para que el usuario la acepte.
+pub async fn check_opening_hours(
+    pool: &PgPool,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    exempt: bool,
+) -> Result<Option<String>, ApiError> {
+    let date = start.with_timezone(&timezone()).date_naive();
+    let hours = hours_on(pool, date).await?;
+    if within_hours(hours, start, end) {
+        return Ok(None);
+    }
+
+    let message = match hours {
+        Some((opening, closing)) => format!(
+            "La cita está fuera del horario de atención de los {} ({} a {})",
+            weekday_name(date.weekday()),
+            opening.format("%H:%M"),
+            closing.format("%H:%M")
+        ),
+        None => format!("La clínica no abre los {}", weekday_name(date.weekday())),
+    };
+    if exempt {
+        Ok(Some(message))
+    } else {
+        Err(ApiError::ValidationError(message))
+    }
+}
+
+/// Último perfil público calculado (`GET /public/clinic-profile`). La ruta no
+/// requiere credenciales, así que las visitas al sitio web no deben llegar a
+/// la base de datos una por una; se comparte como `web::Data<ClinicProfileCache>`.
+#[derive(Debug, Default)]
+pub struct ClinicProfileCache(Mutex<Option<(Instant, ClinicProfile)>>);
+
+impl ClinicProfileCache {
+    /// El perfil guardado si tiene menos de `max_age`
+    pub fn get(&self, max_age: Duration) -> Option<ClinicProfile> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .filter(|(at, _)| at.elapsed() < max_age)
+            .map(|(_, profile)| profile.clone())
+    }
+
+    pub fn set(&self, profile: ClinicProfile) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), profile));
+    }
+
+    /// Descarta el perfil guardado, p. ej. al cambiar el horario de atención
+    pub fn clear(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::America::{Mexico_City, New_York};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn utc(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn local_iso_carries_the_offset_of_each_side_of_the_transition() {
+        // Nueva York adelanta el reloj el 10 de marzo de 2024 a las 02:00
+        assert_eq!(
+            local_iso_in(&New_York, utc("2024-03-10T06:59:00Z")),
+            "2024-03-10T01:59:00-05:00"
+        );
+        assert_eq!(
+            local_iso_in(&New_York, utc("2024-03-10T07:00:00Z")),
+            "2024-03-10T03:00:00-04:00"
+        );
+        // Ciudad de México dejó de cambiar de horario en 2022
+        assert_eq!(
+            local_iso_in(&Mexico_City, utc("2021-04-04T09:00:00Z")),
+            "2021-04-04T04:00:00-05:00"
+        );
+        assert_eq!(
+            local_iso_in(&Mexico_City, utc("2024-04-07T09:00:00Z")),
+            "2024-04-07T03:00:00-06:00"
+        );
+    }
+
+    #[test]
+    fn transition_days_are_shorter_or_longer() {
+        let (start, end) = day_bounds_in(&New_York, date(2024, 3, 10));
+        assert_eq!(start, utc("2024-03-10T05:00:00Z"));
+        assert_eq!((end - start).num_hours(), 23);
+
+        let (start, end) = day_bounds_in(&New_York, date(2024, 11, 3));
+        assert_eq!(start, utc("2024-11-03T04:00:00Z"));
+        assert_eq!((end - start).num_hours(), 25);
+    }
+
+    #[test]
+    fn skipped_local_time_moves_forward() {
+        let naive = date(2024, 3, 10).and_hms_opt(2, 30, 0).unwrap();
+        assert_eq!(from_local(&New_York, naive), utc("2024-03-10T07:30:00Z"));
+    }
+
+    #[test]
+    fn repeated_local_time_takes_the_first() {
+        let naive = date(2024, 11, 3).and_hms_opt(1, 30, 0).unwrap();
+        assert_eq!(from_local(&New_York, naive), utc("2024-11-03T05:30:00Z"));
+    }
+}
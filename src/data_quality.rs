@@ -0,0 +1,73 @@
+use crate::errors::ApiError;
+use crate::models::admin::{
+    BreedQualityReport, MissingBreedBySpecies, UnassignedNameCluster, UnusedBreed,
+};
+use crate::models::enums::AnimalSpecies;
+use sqlx::PgPool;
+
+/// Nombres repetidos sin raza que se incluyen en el reporte
+const NAME_CLUSTERS: i64 = 20;
+
+/// Reporte de pacientes sin raza y razas sin pacientes
+pub async fn breeds(pool: &PgPool) -> Result<BreedQualityReport, ApiError> {
+    let missing_by_species = sqlx::query!(
+        r#"
+        SELECT
+            species as "species!: AnimalSpecies",
+            COUNT(*) as "patients!",
+            COUNT(*) FILTER (WHERE breed IS NULL) as "missing!"
+        FROM patients
+        GROUP BY species
+        ORDER BY species
+        "#
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| MissingBreedBySpecies {
+        species: row.species,
+        patients: row.patients,
+        missing: row.missing,
+        percentage: (row.missing as f64 / row.patients as f64 * 10000.0).round() / 100.0,
+    })
+    .collect();
+
+    // Solo nombres que se repiten: uno aislado no revela ningún patrón
+    let unassigned_name_clusters = sqlx::query_as!(
+        UnassignedNameCluster,
+        r#"
+        SELECT
+            LOWER(TRIM(name)) as "name!",
+            species as "species!: AnimalSpecies",
+            COUNT(*) as "count!"
+        FROM patients
+        WHERE breed IS NULL
+        GROUP BY LOWER(TRIM(name)), species
+        HAVING COUNT(*) > 1
+        ORDER BY COUNT(*) DESC, LOWER(TRIM(name)), species
+        LIMIT $1
+        "#,
+        NAME_CLUSTERS
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let unused_breeds = sqlx::query_as!(
+        UnusedBreed,
+        r#"
+        SELECT b.id, b.species as "species!: AnimalSpecies", b.name
+        FROM breeds b
+        WHERE NOT EXISTS (SELECT 1 FROM patients p WHERE p.breed = b.id)
+        ORDER BY b.species, b.name
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(BreedQualityReport {
+        generated_at: chrono::Utc::now(),
+        missing_by_species,
+        unassigned_name_clusters,
+        unused_breeds,
+    })
+}
@@ -1,6 +1,27 @@
 use sqlx::postgres::PgPoolOptions;
 use sqlx::{Pool, Postgres};
 
+/// Versión del esquema que espera este binario (tabla `schema_version`)
+pub const SCHEMA_VERSION: i32 = 4;
+
+/// Tipos enum que deben existir en la base de datos
+const REQUIRED_ENUMS: &[&str] = &[
+    "user_role",
+    "animal_gender",
+    "appointment_status",
+    "procedure_type",
+    "task_status",
+    "custom_field_entity",
+    "custom_field_type",
+    "referral_direction",
+    "booking_source",
+    "notification_channel",
+    "notification_outcome",
+];
+
+/// Extensiones de Postgres requeridas
+const REQUIRED_EXTENSIONS: &[&str] = &["pg_trgm"];
+
 pub async fn connect_to_db() -> Result<Pool<Postgres>, sqlx::Error> {
     let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     PgPoolOptions::new()
@@ -10,3 +31,56 @@ pub async fn connect_to_db() -> Result<Pool<Postgres>, sqlx::Error> {
         .connect(&database_url)
         .await
 }
+
+/// `SKIP_SCHEMA_CHECK` omite la verificación del esquema al arrancar
+pub fn schema_check_skipped() -> bool {
+    std::env::var("SKIP_SCHEMA_CHECK")
+        .is_ok_and(|value| !matches!(value.trim(), "" | "0" | "false"))
+}
+
+/// Verifica que existan los enums, las extensiones y la versión del esquema.
+///
+/// Devuelve la lista de problemas encontrados (vacía si todo está en orden).
+pub async fn check_schema(pool: &Pool<Postgres>) -> Result<Vec<String>, sqlx::Error> {
+    let mut problems = Vec::new();
+
+    let enums: Vec<String> =
+        sqlx::query_scalar("SELECT typname::TEXT FROM pg_type WHERE typtype = 'e'")
+            .fetch_all(pool)
+            .await?;
+    for name in REQUIRED_ENUMS {
+        if !enums.iter().any(|existing| existing == name) {
+            problems.push(format!("Falta el tipo enum `{name}`"));
+        }
+    }
+
+    let extensions: Vec<String> = sqlx::query_scalar("SELECT extname::TEXT FROM pg_extension")
+        .fetch_all(pool)
+        .await?;
+    for name in REQUIRED_EXTENSIONS {
+        if !extensions.iter().any(|existing| existing == name) {
+            problems.push(format!("Falta la extensión `{name}`"));
+        }
+    }
+
+    let has_version_table: bool =
+        sqlx::query_scalar("SELECT to_regclass('schema_version') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+    if has_version_table {
+        let version: Option<i32> = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+            .fetch_one(pool)
+            .await?;
+        match version {
+            Some(SCHEMA_VERSION) => {}
+            Some(version) => problems.push(format!(
+                "La versión del esquema es {version}, se esperaba {SCHEMA_VERSION}"
+            )),
+            None => problems.push("La tabla `schema_version` está vacía".into()),
+        }
+    } else {
+        problems.push("Falta la tabla `schema_version`".into());
+    }
+
+    Ok(problems)
+}
@@ -0,0 +1,102 @@
+use crate::auth::Claims;
+use crate::models::enums::UserRole;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{AUTHORIZATION, HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use tracing::{Instrument, Level};
+use tracing_subscriber::filter::DynFilterFn;
+use tracing_subscriber::prelude::*;
+
+/// Cabecera con la que un administrador pide la traza de una petición
+const DEBUG_TRACE_HEADER: &str = "x-debug-trace";
+
+/// Cabecera de respuesta con el id para buscar la traza en los logs
+const DEBUG_TRACE_ID_HEADER: &str = "x-debug-trace-id";
+
+/// Nombre del span que eleva el nivel de los logs de su petición
+const DEBUG_TRACE_SPAN: &str = "debug_trace";
+
+/// Inicializa los logs: nivel info para todo, salvo dentro de una petición
+/// con traza de depuración, donde también se emiten los eventos debug y trace
+/// (entre ellos cada consulta de sqlx con su duración y filas).
+pub fn init_tracing() {
+    let filter = DynFilterFn::new(|metadata, cx| {
+        *metadata.level() <= Level::INFO
+            || cx
+                .lookup_current()
+                .is_some_and(|span| span.scope().any(|span| span.name() == DEBUG_TRACE_SPAN))
+    });
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(filter))
+        .init();
+}
+
+/// Token que habilita la traza (`DEBUG_TRACE_TOKEN`); sin variable no hay traza
+fn trace_token() -> Option<String> {
+    std::env::var("DEBUG_TRACE_TOKEN")
+        .ok()
+        .filter(|token| !token.trim().is_empty())
+}
+
+/// Solo administradores o la `API_KEY` del sistema pueden pedir la traza
+fn is_admin(req: &ServiceRequest) -> bool {
+    match req.extensions().get::<Claims>() {
+        Some(claims) => claims.role == UserRole::Admin,
+        // Sin claims puede ser la API_KEY o una ruta pública sin credenciales
+        None => req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .zip(std::env::var("API_KEY").ok())
+            .is_some_and(|(token, api_key)| token == api_key),
+    }
+}
+
+/// Middleware de la traza de depuración por petición.
+///
+/// Con `X-Debug-Trace: <DEBUG_TRACE_TOKEN>` y credenciales de administrador,
+/// la petición corre dentro de un span que eleva sus logs a nivel debug y la
+/// respuesta lleva `X-Debug-Trace-Id` para buscarlos. Un token inválido o
+/// ausente no cambia nada, y el cuerpo de la respuesta nunca se altera. Debe
+/// ir dentro del de autenticación para conocer los `Claims`.
+pub async fn debug_trace(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let requested = req
+        .headers()
+        .get(DEBUG_TRACE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim);
+    let enabled = match (requested, trace_token()) {
+        (Some(requested), Some(token)) => requested == token && is_admin(&req),
+        _ => false,
+    };
+    if !enabled {
+        return next.call(req).await;
+    }
+
+    let mut bytes = [0u8; 6];
+    OsRng.fill_bytes(&mut bytes);
+    let trace_id: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+
+    let span = tracing::info_span!(
+        DEBUG_TRACE_SPAN,
+        request_id = %trace_id,
+        method = %req.method(),
+        path = %req.path()
+    );
+    span.in_scope(|| tracing::info!("Traza de depuración activada"));
+
+    let mut response = next.call(req).instrument(span).await?;
+    if let Ok(value) = HeaderValue::from_str(&trace_id) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(DEBUG_TRACE_ID_HEADER), value);
+    }
+    Ok(response)
+}
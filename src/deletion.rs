@@ -0,0 +1,277 @@
+use crate::errors::ApiError;
+use crate::models::deletion::{DeletePreview, DeletionEffect, DependencyCount};
+use sqlx::PgPool;
+
+/// Tabla y columna que referencian a un recurso
+struct Dependency {
+    table: &'static str,
+    column: &'static str,
+    effect: DeletionEffect,
+    condition: Option<&'static str>, // Filtro SQL adicional sobre `table`
+    blocked: &'static str,           // Motivo del 409 si `effect` es `Block`
+}
+
+const fn dependency(
+    table: &'static str,
+    column: &'static str,
+    effect: DeletionEffect,
+) -> Dependency {
+    Dependency {
+        table,
+        column,
+        effect,
+        condition: None,
+        blocked: "",
+    }
+}
+
+const fn blocking(table: &'static str, column: &'static str, blocked: &'static str) -> Dependency {
+    Dependency {
+        table,
+        column,
+        effect: DeletionEffect::Block,
+        condition: None,
+        blocked,
+    }
+}
+
+/// Como `blocking`, pero solo bloquean las filas que cumplen `condition`
+const fn blocking_where(
+    table: &'static str,
+    column: &'static str,
+    condition: &'static str,
+    blocked: &'static str,
+) -> Dependency {
+    Dependency {
+        table,
+        column,
+        effect: DeletionEffect::Block,
+        condition: Some(condition),
+        blocked,
+    }
+}
+
+/// Recursos con vista previa de eliminación.
+///
+/// El mapa de dependencias de cada uno alimenta tanto `delete-preview` como
+/// la verificación del `DELETE`, para que nunca difieran.
+#[derive(Debug, Clone, Copy)]
+pub enum Resource {
+    Client,
+    Patient,
+    User,
+    Breed,
+    Procedure,
+}
+
+impl Resource {
+    /// Tabla del recurso; también es su nombre en las respuestas
+    pub fn table(self) -> &'static str {
+        match self {
+            Resource::Client => "clients",
+            Resource::Patient => "patients",
+            Resource::User => "users",
+            Resource::Breed => "breeds",
+            Resource::Procedure => "procedures",
+        }
+    }
+
+    fn not_found(self) -> &'static str {
+        match self {
+            Resource::Client => "Cliente no encontrado",
+            Resource::Patient => "El paciente no existe",
+            Resource::User => "Usuario no encontrado",
+            Resource::Breed => "La raza no existe",
+            Resource::Procedure => "El procedimiento no existe",
+        }
+    }
+
+    fn dependencies(self) -> &'static [Dependency] {
+        match self {
+            Resource::Client => CLIENT_DEPENDENCIES,
+            Resource::Patient => PATIENT_DEPENDENCIES,
+            Resource::User => USER_DEPENDENCIES,
+            Resource::Breed => BREED_DEPENDENCIES,
+            Resource::Procedure => PROCEDURE_DEPENDENCIES,
+        }
+    }
+}
+
+const CLIENT_DEPENDENCIES: &[Dependency] = &[
+    blocking(
+        "patients",
+        "client_id",
+        "No se puede eliminar, el cliente tiene mascotas registradas",
+    ),
+    dependency("client_contacts", "client_id", DeletionEffect::Cascade),
+    dependency("appointments", "client_id", DeletionEffect::SetNull),
+    dependency("appointments_archive", "client_id", DeletionEffect::SetNull),
+];
+
+/// Un folio emitido no puede desaparecer de la secuencia: los registros médicos
+/// con folio impiden eliminar al paciente
+const PATIENT_DEPENDENCIES: &[Dependency] = &[
+    blocking_where(
+        "medical_records",
+        "patient_id",
+        "folio IS NOT NULL",
+        "No se puede eliminar, el paciente tiene registros médicos con folio",
+    ),
+    dependency("medical_records", "patient_id", DeletionEffect::Cascade),
+    dependency("patient_procedures", "patient_id", DeletionEffect::Cascade),
+    dependency("referrals", "patient_id", DeletionEffect::Cascade),
+    dependency("hospitalizations", "patient_id", DeletionEffect::Cascade),
+    dependency("appointments", "patient_id", DeletionEffect::SetNull),
+    dependency(
+        "appointments_archive",
+        "patient_id",
+        DeletionEffect::SetNull,
+    ),
+];
+
+/// El DELETE de usuarios solo los desactiva; todo se conserva
+const USER_DEPENDENCIES: &[Dependency] = &[
+    dependency("appointments", "veterinarian_id", DeletionEffect::Keep),
+    dependency(
+        "appointments_archive",
+        "veterinarian_id",
+        DeletionEffect::Keep,
+    ),
+    dependency("medical_records", "veterinarian_id", DeletionEffect::Keep),
+    dependency(
+        "patient_procedures",
+        "veterinarian_id",
+        DeletionEffect::Keep,
+    ),
+    dependency("patient_procedures", "performed_by", DeletionEffect::Keep),
+    dependency("hospitalizations", "veterinarian_id", DeletionEffect::Keep),
+    dependency("clients", "assigned_to", DeletionEffect::Keep),
+    dependency("referrals", "internal_user_id", DeletionEffect::Keep),
+    dependency("record_templates", "owner_id", DeletionEffect::Keep),
+    dependency("appointment_notes", "author_id", DeletionEffect::Keep),
+    dependency("treatment_tasks", "done_by", DeletionEffect::Keep),
+    dependency("api_keys", "created_by", DeletionEffect::Keep),
+];
+
+const BREED_DEPENDENCIES: &[Dependency] = &[blocking(
+    "patients",
+    "breed",
+    "No se puede eliminar, la raza tiene mascotas registradas",
+)];
+
+const PROCEDURE_DEPENDENCIES: &[Dependency] = &[
+    blocking(
+        "patient_procedures",
+        "procedure_id",
+        "No se puede eliminar, el procedimiento tiene registros en pacientes",
+    ),
+    dependency(
+        "species_protocol_items",
+        "procedure_id",
+        DeletionEffect::Cascade,
+    ),
+    dependency("appointments", "procedure_id", DeletionEffect::SetNull),
+    dependency(
+        "appointments_archive",
+        "procedure_id",
+        DeletionEffect::SetNull,
+    ),
+];
+
+/// Cuenta las filas de cada dependencia; 404 si el recurso no existe
+pub async fn preview(
+    pool: &PgPool,
+    resource: Resource,
+    id: i32,
+) -> Result<DeletePreview, ApiError> {
+    let exists: bool = sqlx::query_scalar(&format!(
+        "SELECT EXISTS (SELECT 1 FROM {} WHERE id = $1)",
+        resource.table()
+    ))
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+    if !exists {
+        return Err(ApiError::NotFound(resource.not_found().into()));
+    }
+
+    let mut dependencies = Vec::new();
+    for dependency in resource.dependencies() {
+        let count: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM {} WHERE {} = $1 AND ({})",
+            dependency.table,
+            dependency.column,
+            dependency.condition.unwrap_or("TRUE")
+        ))
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+
+        dependencies.push(DependencyCount {
+            table: dependency.table.into(),
+            column: dependency.column.into(),
+            effect: dependency.effect,
+            condition: dependency.condition.map(Into::into),
+            count,
+        });
+    }
+
+    Ok(DeletePreview {
+        resource: resource.table().into(),
+        id,
+        can_delete: !dependencies
+            .iter()
+            .any(|dependency| dependency.effect == DeletionEffect::Block && dependency.count > 0),
+        dependencies,
+    })
+}
+
+/// Verificación previa al DELETE: 404 si no existe y 409 con la vista previa
+/// en `details` si alguna dependencia lo impide
+pub async fn ensure_deletable(pool: &PgPool, resource: Resource, id: i32) -> Result<(), ApiError> {
+    let preview = preview(pool, resource, id).await?;
+    if preview.can_delete {
+        return Ok(());
+    }
+
+    let message = resource
+        .dependencies()
+        .iter()
+        .zip(&preview.dependencies)
+        .find(|(dependency, count)| dependency.effect == DeletionEffect::Block && count.count > 0)
+        .map_or("No se puede eliminar", |(dependency, _)| dependency.blocked);
+    Err(ApiError::DetailedConflict {
+        message: message.into(),
+        details: serde_json::to_value(&preview)
+            .map_err(|e| ApiError::internal("Error al generar la vista previa", e))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrations = false, fixtures(path = "../init.sql", scripts("0", "1")))]
+    async fn records_with_folio_block_patient_deletion(pool: PgPool) {
+        let patient_id = sqlx::query_scalar!(
+            "SELECT patient_id as \"patient_id!\" FROM medical_records WHERE folio IS NOT NULL LIMIT 1"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let result = ensure_deletable(&pool, Resource::Patient, patient_id).await;
+        assert!(matches!(result, Err(ApiError::DetailedConflict { .. })));
+
+        // Sin folios, los registros se eliminan en cascada con el paciente
+        sqlx::query!(
+            "UPDATE medical_records SET folio = NULL WHERE patient_id = $1",
+            patient_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let preview = preview(&pool, Resource::Patient, patient_id).await.unwrap();
+        assert!(preview.can_delete);
+    }
+}
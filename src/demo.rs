@@ -0,0 +1,288 @@
+use crate::errors::ApiError;
+use crate::jobs::JobRegistry;
+use crate::models::appointment::{NewAppointment, validate_appointment_times};
+use crate::models::client::NewClient;
+use crate::models::enums::{AnimalGender, AnimalSpecies, AppointmentStatus};
+use crate::models::patient::NewPatient;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use bigdecimal::{BigDecimal, FromPrimitive};
+use chrono::{Duration, NaiveTime, Utc};
+use sqlx::PgPool;
+use validator::Validate;
+
+/// Prefijo que marca los nombres de clientes generados para demostraciones
+pub const DEMO_MARKER: &str = "[DEMO]";
+
+/// Dominio de correo reservado para los clientes de demostración
+pub const DEMO_EMAIL_DOMAIN: &str = "demo.micita.local";
+
+const FIRST_NAMES: &[&str] = &[
+    "Ana", "Luis", "María", "Carlos", "Sofía", "Jorge", "Lucía", "Miguel", "Valeria", "Diego",
+    "Fernanda", "Andrés", "Camila", "Ricardo", "Paola", "Héctor",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "García",
+    "Hernández",
+    "López",
+    "Martínez",
+    "González",
+    "Pérez",
+    "Rodríguez",
+    "Sánchez",
+    "Ramírez",
+    "Torres",
+    "Flores",
+    "Rivera",
+    "Gómez",
+    "Díaz",
+];
+
+const PET_NAMES: &[&str] = &[
+    "Max", "Luna", "Rocky", "Nala", "Toby", "Kira", "Simba", "Lola", "Coco", "Milo", "Canela",
+    "Bruno", "Pelusa", "Chispa", "Manchas", "Oreo",
+];
+
+const REASONS: &[&str] = &[
+    "Consulta general",
+    "Vacunación anual",
+    "Revisión de control",
+    "Desparasitación",
+    "Limpieza dental",
+    "Revisión de piel y pelaje",
+];
+
+const SPECIES: &[AnimalSpecies] = &[
+    AnimalSpecies::Dog,
+    AnimalSpecies::Dog,
+    AnimalSpecies::Cat,
+    AnimalSpecies::Cat,
+    AnimalSpecies::Bird,
+    AnimalSpecies::Rabbit,
+    AnimalSpecies::Rodent,
+];
+
+/// Genera `clients` clientes sintéticos con sus mascotas y citas del último año.
+///
+/// Cada cliente se inserta en su propia transacción y todos los datos pasan por
+/// las validaciones de los modelos antes de llegar a la base de datos. El
+/// progreso se reporta en el registro de trabajos.
+pub async fn generate_dataset(
+    pool: PgPool,
+    jobs: &JobRegistry,
+    job_id: i32,
+    clients: i64,
+    veterinarians: Vec<i32>,
+) {
+    let result = generate_clients(&pool, jobs, job_id, clients, &veterinarians).await;
+
+    match result {
+        Ok(summary) => {
+            tracing::info!("Datos de demostración generados: {}", summary);
+            jobs.finish(job_id, Ok(summary));
+        }
+        Err(e) => {
+            tracing::error!("Error al generar datos de demostración: {:?}", e);
+            jobs.finish(
+                job_id,
+                Err("Error al generar los datos de demostración".into()),
+            );
+        }
+    }
+}
+
+async fn generate_clients(
+    pool: &PgPool,
+    jobs: &JobRegistry,
+    job_id: i32,
+    clients: i64,
+    veterinarians: &[i32],
+) -> Result<String, ApiError> {
+    let breeds = sqlx::query!(r#"SELECT id, species as "species!: AnimalSpecies" FROM breeds"#)
+        .fetch_all(pool)
+        .await?;
+
+    let batch = Utc::now().timestamp();
+    let (mut created_clients, mut created_patients, mut created_appointments, mut skipped) =
+        (0, 0, 0, 0);
+
+    for index in 0..clients {
+        let new_client = NewClient {
+            name: format!("{DEMO_MARKER} {} {}", pick(FIRST_NAMES), pick(LAST_NAMES)),
+            email: Some(format!("demo.{batch}.{index}@{DEMO_EMAIL_DOMAIN}")),
+            phone: format!("555{:07}", random_below(10_000_000)),
+            address: None,
+            notes: Some("Registro de demostración generado automáticamente".into()),
+            assigned_to: None,
+            marketing_opt_out: false,
+            custom_fields: Default::default(),
+            household_id: None,
+        };
+        new_client.validate()?;
+
+        let mut tx = pool.begin().await?;
+
+        // Un teléfono repetido no detiene el trabajo: se omite el cliente
+        let client_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO clients (name, email, phone, notes)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT DO NOTHING
+            RETURNING id
+            "#,
+            new_client.name,
+            new_client.email,
+            new_client.phone,
+            new_client.notes
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(client_id) = client_id else {
+            skipped += 1;
+            jobs.progress(job_id, index + 1);
+            continue;
+        };
+        created_clients += 1;
+
+        for _ in 0..1 + random_below(3) {
+            let species = SPECIES[random_below(SPECIES.len() as u32) as usize].clone();
+            let species_breeds: Vec<i32> = breeds
+                .iter()
+                .filter(|breed| breed.species == species)
+                .map(|breed| breed.id)
+                .collect();
+
+            let new_patient = NewPatient {
+                name: pick(PET_NAMES).to_string(),
+                breed_id: (!species_breeds.is_empty())
+                    .then(|| species_breeds[random_below(species_breeds.len() as u32) as usize]),
+                birth_date: Some(
+                    Utc::now().date_naive() - Duration::days(120 + random_below(4500) as i64),
+                ),
+                gender: Some(if random_below(2) == 0 {
+                    AnimalGender::Male
+                } else {
+                    AnimalGender::Female
+                }),
+                weight_kg: Some(demo_weight(&species)),
+                species,
+                client_id,
+                photo_url: None,
+                custom_fields: Default::default(),
+                insurance_provider: None,
+                insurance_policy_number: None,
+                insurance_expires_on: None,
+            };
+            new_patient.validate()?;
+
+            let patient_id = sqlx::query_scalar!(
+                r#"
+                INSERT INTO patients (name, species, breed, birth_date, gender, weight_kg, client_id)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                RETURNING id
+                "#,
+                new_patient.name,
+                new_patient.species as AnimalSpecies,
+                new_patient.breed_id,
+                new_patient.birth_date,
+                new_patient.gender as Option<AnimalGender>,
+                new_patient.weight_kg.and_then(BigDecimal::from_f64),
+                new_patient.client_id
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            created_patients += 1;
+
+            for _ in 0..1 + random_below(4) {
+                // Citas pasadas entre 9:00 y 16:30 (UTC-6): se omite la validación de
+                // fecha futura, no la de duración
+                let day = Utc::now().date_naive() - Duration::days(1 + random_below(364) as i64);
+                let time = NaiveTime::from_hms_opt(15 + random_below(8), 30 * random_below(2), 0)
+                    .expect("hora válida");
+                let start_time = day.and_time(time).and_utc();
+
+                let appointment = NewAppointment {
+                    patient_id: Some(patient_id),
+                    client_id: Some(client_id),
+                    veterinarian_id: veterinarians
+                        [random_below(veterinarians.len() as u32) as usize],
+                    start_time,
+                    end_time: start_time + Duration::minutes(30),
+                    reason: format!("{DEMO_MARKER} {}", pick(REASONS)),
+                    is_emergency: false,
+                    procedure_id: None,
+                    internal_notes: None,
+                    walk_in: false,
+                };
+                validate_appointment_times(&appointment)
+                    .map_err(|e| ApiError::ValidationError(e.to_string()))?;
+
+                let status = match random_below(100) {
+                    0..70 => AppointmentStatus::Completed,
+                    70..85 => AppointmentStatus::Canceled,
+                    _ => AppointmentStatus::NoShow,
+                };
+
+                sqlx::query!(
+                    r#"
+                    INSERT INTO appointments (
+                        patient_id,
+                        client_id,
+                        veterinarian_id,
+                        start_time,
+                        end_time,
+                        status,
+                        reason,
+                        booking_source,
+                        created_at,
+                        canceled_at
+                    )
+                    VALUES (
+                        $1, $2, $3, $4, $5, $6, $7, 'import',
+                        $4::timestamptz - INTERVAL '7 days',
+                        CASE WHEN $6::appointment_status = 'canceled' THEN $4::timestamptz - INTERVAL '1 day' END
+                    )
+                    "#,
+                    appointment.patient_id,
+                    appointment.client_id,
+                    appointment.veterinarian_id,
+                    appointment.start_time,
+                    appointment.end_time,
+                    status as AppointmentStatus,
+                    appointment.reason
+                )
+                .execute(&mut *tx)
+                .await?;
+                created_appointments += 1;
+            }
+        }
+
+        tx.commit().await?;
+        jobs.progress(job_id, index + 1);
+    }
+
+    Ok(format!(
+        "Generados {created_clients} clientes, {created_patients} pacientes y \
+         {created_appointments} citas de demostración ({skipped} clientes omitidos por conflicto)"
+    ))
+}
+
+/// Peso plausible según la especie, con dos decimales
+fn demo_weight(species: &AnimalSpecies) -> f64 {
+    let (min, max) = match species {
+        AnimalSpecies::Dog => (300, 4000),
+        AnimalSpecies::Cat => (200, 700),
+        AnimalSpecies::Rabbit => (100, 500),
+        _ => (10, 300),
+    };
+    (min + random_below(max - min)) as f64 / 100.0
+}
+
+fn pick(items: &[&'static str]) -> &'static str {
+    items[random_below(items.len() as u32) as usize]
+}
+
+fn random_below(max: u32) -> u32 {
+    OsRng.next_u32() % max
+}
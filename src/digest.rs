@@ -0,0 +1,202 @@
+use crate::clinic;
+use crate::errors::ApiError;
+use crate::jobs::JobRegistry;
+use crate::models::appointment::{AgendaEntry, veterinarian_agenda};
+use crate::models::job::JobStatus;
+use crate::notifications;
+use chrono::{NaiveDate, NaiveTime, Utc};
+use sqlx::PgPool;
+use std::env;
+
+/// Tipo de trabajo registrado en `/jobs`
+pub const JOB_KIND: &str = "daily_digest";
+
+/// Plantilla registrada en la bitácora de notificaciones
+const TEMPLATE: &str = "daily_digest";
+
+/// Hora local de envío (`DAILY_DIGEST_TIME`, default: 18:00; `off` lo desactiva)
+fn scheduled_time() -> Option<NaiveTime> {
+    match env::var("DAILY_DIGEST_TIME") {
+        Ok(value) if value.eq_ignore_ascii_case("off") => None,
+        Ok(value) => NaiveTime::parse_from_str(&value, "%H:%M").ok(),
+        Err(_) => NaiveTime::from_hms_opt(18, 0, 0),
+    }
+}
+
+/// Programa el envío diario de la agenda del día siguiente
+pub fn spawn_scheduler(pool: PgPool, jobs: actix_web::web::Data<JobRegistry>) {
+    let Some(send_at) = scheduled_time() else {
+        tracing::info!("Resumen diario desactivado");
+        return;
+    };
+
+    actix_web::rt::spawn(async move {
+        loop {
+            let next = clinic::next_occurrence(send_at);
+            tracing::info!("Próximo resumen diario: {}", next);
+            let wait = (next - Utc::now()).to_std().unwrap_or_default();
+            actix_web::rt::time::sleep(wait).await;
+
+            let job = start(&pool, &jobs, clinic::today() + chrono::Days::new(1)).await;
+            if let Err(e) = job {
+                tracing::error!("No se pudo iniciar el resumen diario: {:?}", e);
+            }
+        }
+    });
+}
+
+/// Registra el trabajo y lo ejecuta en segundo plano
+pub async fn start(
+    pool: &PgPool,
+    jobs: &actix_web::web::Data<JobRegistry>,
+    date: NaiveDate,
+) -> Result<JobStatus, ApiError> {
+    let veterinarians = sqlx::query!(
+        r#"
+        SELECT id, name, email
+        FROM users
+        WHERE role = 'veterinarian' AND is_active = TRUE
+        ORDER BY id
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let job = jobs.start(JOB_KIND, veterinarians.len() as i64);
+    tracing::info!("Enviando resumen del {} (trabajo {})", date, job.id);
+
+    let pool = pool.clone();
+    let registry = jobs.clone();
+    let job_id = job.id;
+    actix_web::rt::spawn(async move {
+        let (mut sent, mut empty, mut already_sent, mut failed) = (0, 0, 0, 0);
+
+        for (index, vet) in veterinarians.into_iter().enumerate() {
+            match send_to_veterinarian(&pool, vet.id, &vet.name, &vet.email, date).await {
+                Ok(Outcome::Sent) => sent += 1,
+                Ok(Outcome::Empty) => empty += 1,
+                Ok(Outcome::AlreadySent) => already_sent += 1,
+                Err(e) => {
+                    tracing::error!("Resumen para el veterinario {}: {}", vet.id, e);
+                    failed += 1;
+                }
+            }
+            registry.progress(job_id, index as i64 + 1);
+        }
+
+        let summary = format!(
+            "Resumen del {date}: {sent} enviados, {empty} sin citas, {already_sent} ya enviados, {failed} fallidos"
+        );
+        registry.finish(
+            job_id,
+            if failed == 0 {
+                Ok(summary)
+            } else {
+                Err(summary)
+            },
+        );
+    });
+
+    Ok(job)
+}
+
+enum Outcome {
+    Sent,
+    Empty,
+    AlreadySent,
+}
+
+/// Envía la agenda a un veterinario y registra el resultado
+async fn send_to_veterinarian(
+    pool: &PgPool,
+    veterinarian_id: i32,
+    name: &str,
+    email: &str,
+    date: NaiveDate,
+) -> Result<Outcome, String> {
+    let agenda = veterinarian_agenda(pool, veterinarian_id, date)
+        .await
+        .map_err(|e| e.to_string())?;
+    if agenda.is_empty() {
+        return Ok(Outcome::Empty);
+    }
+
+    // Reserva el envío; si ya se envió para esta fecha no se repite
+    let claimed = sqlx::query_scalar!(
+        r#"
+        INSERT INTO digest_deliveries (veterinarian_id, digest_date, appointments)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (veterinarian_id, digest_date) DO UPDATE
+        SET attempted_at = NOW(), appointments = EXCLUDED.appointments, error = NULL
+        WHERE digest_deliveries.sent_at IS NULL
+        RETURNING id
+        "#,
+        veterinarian_id,
+        date,
+        agenda.len() as i32
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let Some(delivery_id) = claimed else {
+        return Ok(Outcome::AlreadySent);
+    };
+
+    let subject = format!("Tu agenda del {}", date.format("%d/%m/%Y"));
+    let result = notifications::deliver(
+        pool,
+        notifications::Email {
+            template: TEMPLATE,
+            to: email,
+            subject: &subject,
+            body: render(name, date, &agenda),
+            client_id: None,
+            appointment_id: None,
+            secret: false,
+        },
+    )
+    .await;
+
+    sqlx::query!(
+        r#"
+        UPDATE digest_deliveries
+        SET
+            sent_at = CASE WHEN $1::TEXT IS NULL THEN NOW() END,
+            error = $1
+        WHERE id = $2
+        "#,
+        result.as_ref().err(),
+        delivery_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    result.map(|_| Outcome::Sent)
+}
+
+/// Texto del correo con las citas en hora local
+fn render(name: &str, date: NaiveDate, agenda: &[AgendaEntry]) -> String {
+    let mut body = format!(
+        "Hola {name},\n\nEstas son tus citas del {}:\n\n",
+        date.format("%d/%m/%Y")
+    );
+    for entry in agenda {
+        body.push_str(&format!(
+            "- {} a {}  {} ({}) — {}{}\n",
+            clinic::local(entry.start_time).format("%H:%M"),
+            clinic::local(entry.end_time).format("%H:%M"),
+            entry.patient_name.as_deref().unwrap_or("Sin paciente"),
+            entry.client_name.as_deref().unwrap_or("Sin cliente"),
+            entry.reason,
+            entry
+                .client_phone
+                .as_deref()
+                .map(|phone| format!(" · Tel. {phone}"))
+                .unwrap_or_default()
+        ));
+    }
+    body.push_str(&format!("\nTotal: {} citas.\n", agenda.len()));
+    body
+}
@@ -0,0 +1,293 @@
+use crate::clinic;
+use crate::errors::ApiError;
+use crate::models::enums::{AnimalGender, AnimalSpecies};
+use crate::pdf::{PdfWriter, Style};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::env;
+
+/// Registros médicos leídos por consulta al armar el expediente
+const RECORDS_CHUNK: i64 = 100;
+
+/// Páginas máximas del expediente (`DOSSIER_MAX_PAGES`, default: 200)
+fn max_pages() -> usize {
+    env::var("DOSSIER_MAX_PAGES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|pages| *pages > 0)
+        .unwrap_or(200)
+}
+
+fn local(date: DateTime<Utc>) -> String {
+    clinic::local(date).format("%d/%m/%Y %H:%M").to_string()
+}
+
+/// Expediente clínico completo del paciente en PDF: datos del paciente y del
+/// dueño, cartilla de vacunación, registros médicos en orden cronológico,
+/// procedimientos y hospitalizaciones. 404 si el paciente no existe.
+pub async fn render(pool: &PgPool, patient_id: i32) -> Result<Vec<u8>, ApiError> {
+    let patient = sqlx::query!(
+        r#"
+        SELECT
+            p.name,
+            p.species as "species!: AnimalSpecies",
+            b.name as "breed?",
+            p.birth_date,
+            p.gender as "gender: AnimalGender",
+            p.weight_kg,
+            p.deceased_on,
+            c.name as "owner_name?",
+            c.phone as "owner_phone?",
+            c.email as "owner_email?",
+            c.address as "owner_address?"
+        FROM patients p
+        LEFT JOIN breeds b ON b.id = p.breed
+        LEFT JOIN clients c ON c.id = p.client_id
+        WHERE p.id = $1
+        "#,
+        patient_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::NotFound("El paciente no existe".into()))?;
+
+    let mut pdf = PdfWriter::new(
+        format!(
+            "Expediente de {} - generado el {}",
+            patient.name,
+            local(Utc::now())
+        ),
+        max_pages(),
+    );
+
+    // Encabezado: paciente y dueño
+    pdf.text(
+        Style::Title,
+        &format!("Expediente clínico: {}", patient.name),
+    );
+    pdf.gap(6.0);
+    pdf.text(Style::Heading, "Paciente");
+    pdf.text(
+        Style::Body,
+        &format!(
+            "Especie: {:?}    Raza: {}",
+            patient.species,
+            patient.breed.as_deref().unwrap_or("Sin raza")
+        ),
+    );
+    pdf.text(
+        Style::Body,
+        &format!(
+            "Nacimiento: {}    Sexo: {}    Peso: {}",
+            patient
+                .birth_date
+                .map_or("-".into(), |date| date.format("%d/%m/%Y").to_string()),
+            patient
+                .gender
+                .map_or("-".into(), |gender| format!("{gender:?}")),
+            patient
+                .weight_kg
+                .map_or("-".into(), |weight| format!("{weight} kg"))
+        ),
+    );
+    if let Some(deceased_on) = patient.deceased_on {
+        pdf.text(
+            Style::Body,
+            &format!("Fallecido el {}", deceased_on.format("%d/%m/%Y")),
+        );
+    }
+    pdf.gap(6.0);
+    pdf.text(Style::Heading, "Dueño");
+    match patient.owner_name {
+        Some(owner_name) => {
+            pdf.text(Style::Body, &owner_name);
+            pdf.text(
+                Style::Body,
+                &format!(
+                    "Teléfono: {}    Correo: {}",
+                    patient.owner_phone.as_deref().unwrap_or("-"),
+                    patient.owner_email.as_deref().unwrap_or("-")
+                ),
+            );
+            if let Some(address) = patient.owner_address {
+                pdf.text(Style::Body, &format!("Domicilio: {address}"));
+            }
+        }
+        None => pdf.text(Style::Body, "Sin dueño registrado"),
+    }
+
+    // Cartilla de vacunación y demás procedimientos
+    let procedures = sqlx::query!(
+        r#"
+        SELECT
+            pr.name,
+            pr.type::TEXT = 'vaccine' as "is_vaccine!",
+            pp.date,
+            pp.next_due_date,
+            v.name as "veterinarian_name?",
+            pp.notes
+        FROM patient_procedures pp
+        JOIN procedures pr ON pr.id = pp.procedure_id
+        LEFT JOIN users v ON v.id = pp.veterinarian_id
+        WHERE pp.patient_id = $1
+        ORDER BY pp.date, pp.id
+        "#,
+        patient_id
+    )
+    .fetch_all(pool)
+    .await?;
+    let (vaccines, others): (Vec<_>, Vec<_>) = procedures
+        .into_iter()
+        .partition(|procedure| procedure.is_vaccine);
+
+    pdf.gap(10.0);
+    pdf.text(Style::Heading, "Cartilla de vacunación");
+    if vaccines.is_empty() {
+        pdf.text(Style::Body, "Sin vacunas registradas");
+    }
+    for vaccine in &vaccines {
+        pdf.text(
+            Style::Body,
+            &format!(
+                "{}  {}  Próxima dosis: {}",
+                vaccine.date.format("%d/%m/%Y"),
+                vaccine.name,
+                vaccine
+                    .next_due_date
+                    .map_or("-".into(), |date| date.format("%d/%m/%Y").to_string())
+            ),
+        );
+    }
+
+    // Registros médicos, por bloques para no cargar historiales largos completos
+    pdf.gap(10.0);
+    pdf.text(Style::Heading, "Historial médico");
+    let mut offset = 0;
+    loop {
+        let records = sqlx::query!(
+            r#"
+            SELECT
+                mr.date as "date!: DateTime<Utc>",
+                mr.folio,
+                u.name as "veterinarian_name?",
+                mr.diagnosis,
+                mr.treatment,
+                mr.notes,
+                mr.weight_at_visit
+            FROM medical_records mr
+            LEFT JOIN users u ON u.id = mr.veterinarian_id
+            WHERE mr.patient_id = $1
+            ORDER BY mr.date, mr.id
+            LIMIT $2 OFFSET $3
+            "#,
+            patient_id,
+            RECORDS_CHUNK,
+            offset
+        )
+        .fetch_all(pool)
+        .await?;
+        if offset == 0 && records.is_empty() {
+            pdf.text(Style::Body, "Sin registros médicos");
+        }
+
+        for record in &records {
+            pdf.gap(4.0);
+            pdf.text(
+                Style::Heading,
+                &format!(
+                    "{}{} - {}",
+                    local(record.date),
+                    record
+                        .folio
+                        .map_or(String::new(), |folio| format!(" (folio {folio})")),
+                    record
+                        .veterinarian_name
+                        .as_deref()
+                        .unwrap_or("Veterinario desconocido")
+                ),
+            );
+            pdf.text(Style::Body, &format!("Diagnóstico: {}", record.diagnosis));
+            if let Some(treatment) = &record.treatment {
+                pdf.text(
+                    Style::Body,
+                    &format!("Tratamiento y prescripción: {treatment}"),
+                );
+            }
+            if let Some(weight) = &record.weight_at_visit {
+                pdf.text(Style::Body, &format!("Peso en consulta: {weight} kg"));
+            }
+            if let Some(notes) = &record.notes {
+                pdf.text(Style::Body, &format!("Notas: {notes}"));
+            }
+        }
+
+        if (records.len() as i64) < RECORDS_CHUNK || pdf.truncated() {
+            break;
+        }
+        offset += RECORDS_CHUNK;
+    }
+
+    pdf.gap(10.0);
+    pdf.text(Style::Heading, "Procedimientos");
+    if others.is_empty() {
+        pdf.text(Style::Body, "Sin procedimientos registrados");
+    }
+    for procedure in &others {
+        pdf.text(
+            Style::Body,
+            &format!(
+                "{}  {}  ({})",
+                procedure.date.format("%d/%m/%Y"),
+                procedure.name,
+                procedure
+                    .veterinarian_name
+                    .as_deref()
+                    .unwrap_or("Veterinario desconocido")
+            ),
+        );
+        if let Some(notes) = &procedure.notes {
+            pdf.text(Style::Body, &format!("    {notes}"));
+        }
+    }
+
+    let hospitalizations = sqlx::query!(
+        r#"
+        SELECT admitted_at, discharged_at, kennel, reason, discharge_summary
+        FROM hospitalizations
+        WHERE patient_id = $1
+        ORDER BY admitted_at
+        "#,
+        patient_id
+    )
+    .fetch_all(pool)
+    .await?;
+    if !hospitalizations.is_empty() {
+        pdf.gap(10.0);
+        pdf.text(Style::Heading, "Hospitalizaciones");
+        for stay in hospitalizations {
+            pdf.text(
+                Style::Body,
+                &format!(
+                    "{} a {}{}: {}",
+                    local(stay.admitted_at),
+                    stay.discharged_at.map_or("la fecha".into(), local),
+                    stay.kennel
+                        .map_or(String::new(), |kennel| format!(" (jaula {kennel})")),
+                    stay.reason
+                ),
+            );
+            if let Some(summary) = stay.discharge_summary {
+                pdf.text(Style::Body, &format!("    Alta: {summary}"));
+            }
+        }
+    }
+
+    if pdf.truncated() {
+        tracing::warn!(
+            "Expediente del paciente {} truncado a {} páginas",
+            patient_id,
+            max_pages()
+        );
+    }
+    Ok(pdf.finish())
+}
@@ -0,0 +1,193 @@
+use crate::errors::ApiError;
+use crate::rate_limit::principal;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::http::header::CONTENT_LENGTH;
+use actix_web::middleware::Next;
+use actix_web::{Error, ResponseError, web};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Cuánto tiempo después de terminar se sigue rechazando la misma petición
+const DUPLICATE_WINDOW: Duration = Duration::from_secs(3);
+
+/// Cuerpos más grandes (ej: el PDF de un consentimiento) no se comparan
+const MAX_GUARDED_BODY_BYTES: u64 = 64 * 1024;
+
+/// El login no crea nada y reintentarlo debe funcionar siempre
+const EXEMPT_PATHS: &[&str] = &["/api/users/login"];
+
+#[derive(Debug, Clone, Copy)]
+enum Entry {
+    InFlight,
+    Completed(Instant),
+}
+
+/// Protección contra el doble envío de un mismo `POST` (ej: doble clic en
+/// "Agendar"), compartida como `web::Data<DuplicateGuard>`.
+///
+/// Se activa con `DUPLICATE_REQUEST_GUARD`. Solo recuerda un hash de cliente,
+/// ruta y cuerpo, nunca la respuesta, y vive en memoria del proceso.
+#[derive(Debug, Default)]
+pub struct DuplicateGuard {
+    enabled: bool,
+    entries: Mutex<HashMap<u64, Entry>>,
+}
+
+impl DuplicateGuard {
+    pub fn from_env() -> Self {
+        let enabled = std::env::var("DUPLICATE_REQUEST_GUARD")
+            .is_ok_and(|value| matches!(value.trim(), "1" | "true"));
+        Self {
+            enabled,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Registra la petición como en curso; `false` si una idéntica sigue en
+    /// curso o terminó hace menos de `DUPLICATE_WINDOW`
+    fn begin(&self, key: u64) -> bool {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, entry| match entry {
+            Entry::InFlight => true,
+            Entry::Completed(at) => now.duration_since(*at) < DUPLICATE_WINDOW,
+        });
+        if entries.contains_key(&key) {
+            return false;
+        }
+        entries.insert(key, Entry::InFlight);
+        true
+    }
+
+    /// Cierra la petición: si tuvo éxito se recuerda durante la ventana, y si
+    /// falló se olvida para que el reintento pase
+    fn finish(&self, key: u64, succeeded: bool) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if succeeded {
+            entries.insert(key, Entry::Completed(Instant::now()));
+        } else {
+            entries.remove(&key);
+        }
+    }
+}
+
+/// Libera la entrada aunque la petición se abandone a medias (ej: el cliente
+/// cerró la conexión), para que no quede en curso para siempre
+struct InFlight<'a> {
+    guard: &'a DuplicateGuard,
+    key: u64,
+    succeeded: bool,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.guard.finish(self.key, self.succeeded);
+    }
+}
+
+fn request_key(req: &ServiceRequest, body: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    principal(req).hash(&mut hasher);
+    req.path().hash(&mut hasher);
+    req.query_string().hash(&mut hasher);
+    body.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Middleware que rechaza con 409 un `POST` idéntico a otro en curso o recién
+/// terminado. Debe ir dentro del de autenticación para conocer los `Claims`.
+pub async fn reject_duplicates(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let guard = req
+        .app_data::<web::Data<DuplicateGuard>>()
+        .filter(|guard| guard.enabled)
+        .cloned();
+    let body_len = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    let applies = *req.method() == Method::POST
+        && !EXEMPT_PATHS.contains(&req.path())
+        && body_len.is_some_and(|len| len <= MAX_GUARDED_BODY_BYTES);
+
+    let Some(guard) = guard.filter(|_| applies) else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+
+    // Se lee el cuerpo para el hash y se devuelve intacto al handler
+    let body = req.extract::<web::Bytes>().await?;
+    let key = request_key(&req, &body);
+    req.set_payload(Payload::from(body));
+
+    if !guard.begin(key) {
+        tracing::info!(
+            "Petición duplicada rechazada de {}: {} {}",
+            principal(&req),
+            req.method(),
+            req.path()
+        );
+        let response = ApiError::DuplicateRequest(
+            "Una petición idéntica se está procesando o acaba de procesarse".into(),
+        )
+        .error_response();
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    let mut in_flight = InFlight {
+        guard: &guard,
+        key,
+        succeeded: false,
+    };
+    let response = next.call(req).await?;
+    in_flight.succeeded = response.status().is_success();
+    Ok(response.map_into_left_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{App, HttpResponse, test};
+
+    #[actix_web::test]
+    async fn concurrent_identical_posts_run_once() {
+        let guard = web::Data::new(DuplicateGuard {
+            enabled: true,
+            entries: Mutex::default(),
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(guard)
+                .wrap(actix_web::middleware::from_fn(reject_duplicates))
+                .route(
+                    "/api/appointments",
+                    web::post().to(|| async {
+                        // Lo bastante lento para que ambas peticiones se traslapen
+                        actix_web::rt::time::sleep(Duration::from_millis(100)).await;
+                        HttpResponse::Created().finish()
+                    }),
+                ),
+        )
+        .await;
+
+        let post = || {
+            let request = test::TestRequest::post()
+                .uri("/api/appointments")
+                .set_json(serde_json::json!({ "patient_id": 1, "reason": "Vacuna" }))
+                .to_request();
+            test::call_service(&app, request)
+        };
+        let (first, second) = futures::join!(post(), post());
+
+        let mut statuses = [first.status().as_u16(), second.status().as_u16()];
+        statuses.sort_unstable();
+        assert_eq!(statuses, [201, 409]);
+    }
+}
@@ -1,4 +1,7 @@
+use crate::models::admin::{LimitExceededResponse, LimitUsage};
+use actix_web::error::QueryPayloadError;
 use actix_web::{HttpResponse, ResponseError};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -7,12 +10,33 @@ pub enum ApiError {
     NotFound(String),
     #[error("Conflict")]
     Conflict(String),
+    #[error("Duplicate request")]
+    DuplicateRequest(String),
+    #[error("Conflict")]
+    DetailedConflict {
+        message: String,
+        details: serde_json::Value,
+    },
     #[error("Unauthorized")]
     Unauthorized(String),
+    #[error("Forbidden")]
+    Forbidden(String),
     #[error("Internal server error")]
     InternalServerError(String),
     #[error("Validation error")]
     ValidationError(String),
+    #[error("Unprocessable entity")]
+    UnprocessableEntity(String),
+    #[error("Limit exceeded")]
+    LimitExceeded(LimitUsage),
+    #[error("Too many requests")]
+    TooManyRequests(String),
+    #[error("Gone")]
+    Gone(String),
+    #[error("Maintenance")]
+    Maintenance(String),
+    #[error("Service unavailable")]
+    ServiceUnavailable(String),
 }
 
 impl ResponseError for ApiError {
@@ -20,26 +44,137 @@ impl ResponseError for ApiError {
         match self {
             ApiError::NotFound(message) => HttpResponse::NotFound().json(message),
             ApiError::Conflict(message) => HttpResponse::Conflict().json(message),
+            ApiError::DuplicateRequest(message) => HttpResponse::Conflict()
+                .json(serde_json::json!({ "code": "duplicate_request", "message": message })),
+            ApiError::DetailedConflict { message, details } => HttpResponse::Conflict()
+                .json(serde_json::json!({ "message": message, "details": details })),
             ApiError::Unauthorized(message) => HttpResponse::Unauthorized().json(message),
+            ApiError::Forbidden(message) => HttpResponse::Forbidden().json(message),
             ApiError::InternalServerError(message) => {
                 HttpResponse::InternalServerError().json(message)
             }
             ApiError::ValidationError(message) => HttpResponse::BadRequest().json(message),
+            ApiError::UnprocessableEntity(message) => {
+                HttpResponse::UnprocessableEntity().json(message)
+            }
+            ApiError::LimitExceeded(usage) => {
+                HttpResponse::PaymentRequired().json(LimitExceededResponse::from_usage(usage))
+            }
+            ApiError::TooManyRequests(message) => HttpResponse::TooManyRequests().json(message),
+            ApiError::Gone(message) => HttpResponse::Gone().json(message),
+            ApiError::Maintenance(message) => HttpResponse::ServiceUnavailable()
+                .json(serde_json::json!({ "code": "maintenance", "message": message })),
+            ApiError::ServiceUnavailable(message) => {
+                HttpResponse::ServiceUnavailable().json(message)
+            }
         }
     }
 }
 
+impl ApiError {
+    /// Error 500 que no expone el detalle al cliente.
+    ///
+    /// El error completo se registra en el log con un id de referencia, y la
+    /// respuesta lleva `message` seguido de ese id para poder encontrarlo.
+    pub fn internal(message: &str, error: impl std::fmt::Display) -> Self {
+        let mut bytes = [0u8; 6];
+        OsRng.fill_bytes(&mut bytes);
+        let reference: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+
+        tracing::error!(error_ref = %reference, "{}: {}", message, error);
+        ApiError::InternalServerError(format!("{message} (referencia: {reference})"))
+    }
+}
+
 impl From<sqlx::Error> for ApiError {
     fn from(error: sqlx::Error) -> Self {
         match error {
             sqlx::Error::RowNotFound => ApiError::NotFound("Resource not found".into()),
-            _ => ApiError::InternalServerError(error.to_string()),
+            _ => ApiError::internal("Error interno del servidor", error),
         }
     }
 }
 
+/// Un filtro inválido en el query string (ej: `?status=sheduled`) responde 400
+/// con el detalle, nunca la lista sin filtrar
+impl From<QueryPayloadError> for ApiError {
+    fn from(error: QueryPayloadError) -> Self {
+        let detail = match &error {
+            QueryPayloadError::Deserialize(e) => e.to_string(),
+            e => e.to_string(),
+        };
+        ApiError::ValidationError(format!("Parámetro de consulta inválido: {detail}"))
+    }
+}
+
+/// Configuración de `web::Query` que responde los filtros inválidos con el
+/// 400 de `ApiError`
+pub fn query_config() -> actix_web::web::QueryConfig {
+    actix_web::web::QueryConfig::default().error_handler(|err, _| ApiError::from(err).into())
+}
+
 impl From<validator::ValidationErrors> for ApiError {
     fn from(error: validator::ValidationErrors) -> Self {
         ApiError::ValidationError(error.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventBus;
+    use crate::models::enums::UserRole;
+    use crate::testing::{as_user, claims};
+    use actix_web::{App, test, web};
+    use sqlx::PgPool;
+
+    fn assert_no_database_details(body: &str) {
+        assert!(body.contains("referencia: "), "{body}");
+        for detail in [
+            "relation",
+            "constraint",
+            "appointment_notes",
+            "users_email_key",
+        ] {
+            assert!(!body.contains(detail), "{body}");
+        }
+    }
+
+    #[sqlx::test(migrations = false, fixtures(path = "../init.sql", scripts("0", "1")))]
+    async fn database_errors_keep_details_out_of_the_response(pool: PgPool) {
+        let duplicate = sqlx::query!(
+            "INSERT INTO users (email, password_hash, name, role)
+             SELECT email, password_hash, name, role FROM users WHERE id = 1"
+        )
+        .execute(&pool)
+        .await
+        .unwrap_err();
+        let response = ApiError::from(duplicate).error_response();
+        assert_eq!(response.status(), 500);
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert_no_database_details(std::str::from_utf8(&body).unwrap());
+
+        // A través de un handler: la consulta del listado de citas falla
+        sqlx::query("DROP TABLE appointment_notes")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(EventBus::default()))
+                .configure(crate::routes::config),
+        )
+        .await;
+        let request = test::TestRequest::get()
+            .uri("/api/appointments")
+            .to_request();
+        let response =
+            test::call_service(&app, as_user(request, claims(3, UserRole::Assistant))).await;
+        assert_eq!(response.status(), 500);
+        let body = test::read_body(response).await;
+        assert_no_database_details(std::str::from_utf8(&body).unwrap());
+    }
+}
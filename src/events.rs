@@ -0,0 +1,160 @@
+use crate::models::appointment::Appointment;
+use crate::models::enums::AppointmentStatus;
+use crate::models::event::AppointmentStreamEvent;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::broadcast;
+
+/// Capacidad del canal; un suscriptor que se atrasa más pierde los eventos
+/// más antiguos.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Eventos de dominio que publican los handlers para que otros módulos
+/// (notificaciones, webhooks) reaccionen sin acoplarse a ellos.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+#[allow(clippy::enum_variant_names)] // Los nombres son el `type` publicado
+pub enum DomainEvent {
+    AppointmentNoteAdded {
+        appointment_id: i32,
+        note_id: i32,
+        author_id: i32,
+    },
+    AppointmentCompleted {
+        appointment_id: i32,
+    },
+    AppointmentReassigned {
+        appointment_id: i32,
+        from_veterinarian_id: i32,
+        to_veterinarian_id: i32,
+    },
+    AppointmentCreated(AppointmentSnapshot),
+    AppointmentUpdated(AppointmentSnapshot),
+    /// Cambió el horario o el veterinario
+    AppointmentRescheduled(AppointmentSnapshot),
+    AppointmentCanceled(AppointmentSnapshot),
+    PatientCreated {
+        patient_id: i32,
+    },
+    MedicalRecordCreated {
+        record_id: i32,
+        patient_id: i32,
+    },
+    /// Inicio de sesión rechazado (correo desconocido o contraseña incorrecta)
+    LoginFailed,
+}
+
+impl DomainEvent {
+    /// Versión reducida para los clientes del flujo de citas; `None` para los
+    /// eventos que no describen un alta, cambio, reprogramación o cancelación
+    pub fn appointment_stream_event(&self) -> Option<AppointmentStreamEvent> {
+        let (event_type, snapshot) = match self {
+            DomainEvent::AppointmentCreated(snapshot) => ("AppointmentCreated", snapshot),
+            DomainEvent::AppointmentUpdated(snapshot) => ("AppointmentUpdated", snapshot),
+            DomainEvent::AppointmentRescheduled(snapshot) => ("AppointmentRescheduled", snapshot),
+            DomainEvent::AppointmentCanceled(snapshot) => ("AppointmentCanceled", snapshot),
+            _ => return None,
+        };
+        Some(AppointmentStreamEvent {
+            event_type,
+            appointment_id: snapshot.appointment_id,
+            veterinarian_id: snapshot.veterinarian_id,
+            status: snapshot.status.clone(),
+            start_time: snapshot.start_time,
+        })
+    }
+}
+
+/// Datos mínimos de la cita que viajan con sus eventos
+#[derive(Debug, Clone, Serialize)]
+pub struct AppointmentSnapshot {
+    pub appointment_id: i32,
+    pub veterinarian_id: i32,
+    pub status: AppointmentStatus,
+    pub start_time: DateTime<Utc>,
+}
+
+impl From<&Appointment> for AppointmentSnapshot {
+    fn from(appointment: &Appointment) -> Self {
+        Self {
+            appointment_id: appointment.id,
+            veterinarian_id: appointment.veterinarian_id,
+            status: appointment.status.clone(),
+            start_time: appointment.start_time,
+        }
+    }
+}
+
+/// Bus en memoria de eventos de dominio.
+///
+/// Se comparte como `web::Data<EventBus>`; los eventos no se persisten.
+#[derive(Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<DomainEvent>,
+    streams: Arc<AtomicUsize>, // Suscripciones abiertas por clientes HTTP (SSE)
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            streams: Arc::default(),
+        }
+    }
+}
+
+impl EventBus {
+    /// Publica un evento; si no hay suscriptores se descarta
+    pub fn publish(&self, event: DomainEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DomainEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Suscripción para un cliente HTTP, o `None` si ya hay `max` abiertas.
+    /// El cupo se libera al soltar la suscripción (ej: el cliente se desconecta).
+    pub fn subscribe_stream(&self, max: usize) -> Option<StreamSubscription> {
+        self.streams
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |open| {
+                (open < max).then_some(open + 1)
+            })
+            .ok()?;
+        Some(StreamSubscription {
+            receiver: self.sender.subscribe(),
+            streams: Arc::clone(&self.streams),
+        })
+    }
+}
+
+/// Suscripción de un cliente HTTP que ocupa un cupo mientras existe
+pub struct StreamSubscription {
+    pub receiver: broadcast::Receiver<DomainEvent>,
+    streams: Arc<AtomicUsize>,
+}
+
+impl Drop for StreamSubscription {
+    fn drop(&mut self) {
+        self.streams.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Registra en el log cada evento publicado
+pub fn spawn_logger(events: &EventBus) {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => tracing::info!("Evento de dominio: {:?}", event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Log de eventos atrasado, {} eventos omitidos", skipped)
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
@@ -0,0 +1,383 @@
+use crate::auth::Claims;
+use crate::errors::ApiError;
+use crate::models::appointment::Appointment;
+use crate::models::enums::{
+    AnimalGender, AnimalSpecies, AppointmentStatus, BookingSource, UserRole,
+};
+use crate::models::patient::{Patient, PatientRaw};
+use actix_web::http::Method;
+use actix_web::http::header::ACCEPT_LANGUAGE;
+use actix_web::{FromRequest, HttpMessage, HttpRequest, dev::Payload, web};
+use bigdecimal::BigDecimal;
+use futures::future::{LocalBoxFuture, Ready, ready};
+use sqlx::PgPool;
+use std::fmt;
+use std::ops::Deref;
+
+/// ID de recurso tomado del segmento `{id}` de la ruta.
+///
+/// Rechaza con 404 los IDs no positivos o fuera del rango de `i32` antes de
+/// consultar la base de datos.
+#[derive(Debug, Clone, Copy)]
+pub struct PathId(pub i32);
+
+impl PathId {
+    /// Lee un ID de otro segmento de la ruta (ej: `{protocol_id}`) con las mismas reglas
+    pub fn from_segment(req: &HttpRequest, name: &str) -> Result<Self, ApiError> {
+        req.match_info()
+            .get(name)
+            .and_then(|raw| raw.parse::<i32>().ok())
+            .filter(|id| *id > 0)
+            .map(PathId)
+            .ok_or_else(|| {
+                tracing::warn!("ID de ruta inválido: {}", req.path());
+                ApiError::NotFound("Recurso no encontrado".into())
+            })
+    }
+}
+
+impl FromRequest for PathId {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Self::from_segment(req, "id"))
+    }
+}
+
+impl Deref for PathId {
+    type Target = i32;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Display for PathId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Idioma de los textos generados por la API (ej: duraciones legibles).
+///
+/// Se toma de `Accept-Language`: el primer idioma soportado en orden de
+/// preferencia (`q`). Sin cabecera o sin idiomas soportados, español.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    Es,
+    En,
+}
+
+impl Locale {
+    pub fn from_accept_language(header: &str) -> Self {
+        let mut preferences: Vec<(&str, f32)> = header
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';').map(str::trim);
+                let tag = parts.next().filter(|tag| !tag.is_empty())?;
+                let quality = parts
+                    .find_map(|param| param.strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.parse().ok())?;
+                Some((tag, quality))
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect();
+        // Orden estable: a igual `q` se respeta el orden de la cabecera
+        preferences.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        preferences
+            .into_iter()
+            .find_map(|(tag, _)| {
+                let language = tag.split('-').next().unwrap_or(tag);
+                match language.to_ascii_lowercase().as_str() {
+                    "es" => Some(Locale::Es),
+                    "en" => Some(Locale::En),
+                    _ => None,
+                }
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl FromRequest for Locale {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let locale = req
+            .headers()
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map_or(Locale::default(), Locale::from_accept_language);
+        ready(Ok(locale))
+    }
+}
+
+/// Acceso que pide la petición sobre una entidad, según el método HTTP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Delete,
+}
+
+impl Access {
+    fn of(req: &HttpRequest) -> Self {
+        match *req.method() {
+            Method::GET | Method::HEAD => Access::Read,
+            Method::DELETE => Access::Delete,
+            _ => Access::Write,
+        }
+    }
+}
+
+/// Entidad que se carga por el `{id}` de la ruta con [`Loaded`], junto con la
+/// política de quién puede leerla, modificarla o eliminarla
+pub trait Loadable: Sized + 'static {
+    /// Mensaje del 404 cuando no existe
+    const NOT_FOUND: &'static str;
+
+    async fn load(pool: &PgPool, id: i32) -> Result<Option<Self>, sqlx::Error>;
+
+    /// Responde 403 si el usuario del token no tiene el acceso pedido. Sin
+    /// claims (API_KEY del sistema) no hay usuario: un acceso que exige un rol
+    /// responde 401.
+    fn authorize(&self, claims: Option<&Claims>, access: Access) -> Result<(), ApiError>;
+}
+
+/// Entidad del `{id}` de la ruta ya cargada y autorizada: responde 404 si no
+/// existe y 403 si el usuario no tiene acceso, antes de llegar al handler.
+#[derive(Debug)]
+pub struct Loaded<T>(pub T);
+
+pub type LoadedPatient = Loaded<Patient>;
+pub type LoadedAppointment = Loaded<Appointment>;
+
+impl<T> Loaded<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Loaded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Loadable> FromRequest for Loaded<T> {
+    type Error = ApiError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            let id = PathId::from_segment(&req, "id")?;
+            let pool = req
+                .app_data::<web::Data<PgPool>>()
+                .ok_or_else(|| ApiError::InternalServerError("Pool no configurado".into()))?;
+            let entity = T::load(pool.get_ref(), *id)
+                .await
+                .map_err(|e| ApiError::internal("Error al acceder a la base de datos", e))?
+                .ok_or_else(|| {
+                    tracing::warn!("ID {} inexistente: {}", id, req.path());
+                    ApiError::NotFound(T::NOT_FOUND.into())
+                })?;
+            let claims = req.extensions().get::<Claims>().cloned();
+            entity.authorize(claims.as_ref(), Access::of(&req))?;
+            Ok(Loaded(entity))
+        })
+    }
+}
+
+/// Usuario que exige una política; sin claims responde 401, igual que el
+/// extractor de [`Claims`]
+fn require_user(claims: Option<&Claims>) -> Result<&Claims, ApiError> {
+    claims.ok_or_else(|| ApiError::Unauthorized("Se requiere un token de usuario".into()))
+}
+
+/// Cualquier rol consulta y edita pacientes; solo administradores y
+/// veterinarios los eliminan, porque se lleva su historia clínica
+impl Loadable for Patient {
+    const NOT_FOUND: &'static str = "El paciente no existe";
+
+    async fn load(pool: &PgPool, id: i32) -> Result<Option<Self>, sqlx::Error> {
+        let patient = sqlx::query_as!(
+            PatientRaw,
+            r#"
+            SELECT
+                id,
+                name,
+                species as "species!: AnimalSpecies",
+                breed as "breed_id!: Option<i32>",
+                birth_date,
+                gender as "gender!: Option<AnimalGender>",
+                weight_kg as "weight_kg!: BigDecimal",
+                client_id as "client_id!: i32",
+                photo_url,
+                deceased_on,
+                custom_fields,
+                insurance_provider,
+                insurance_policy_number,
+                insurance_expires_on
+            FROM patients
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(patient.map(Patient::from))
+    }
+
+    fn authorize(&self, claims: Option<&Claims>, access: Access) -> Result<(), ApiError> {
+        match access {
+            Access::Read | Access::Write => Ok(()),
+            Access::Delete => {
+                require_user(claims)?.require_role(&[UserRole::Admin, UserRole::Veterinarian])
+            }
+        }
+    }
+}
+
+/// Cualquiera consulta las citas; modificarlas o eliminarlas requiere un
+/// usuario. Recepción (asistentes) administra la agenda completa y un
+/// veterinario solo modifica o elimina las suyas
+impl Loadable for Appointment {
+    const NOT_FOUND: &'static str = "La cita no existe";
+
+    async fn load(pool: &PgPool, id: i32) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            Appointment,
+            r#"
+            SELECT
+                id,
+                patient_id,
+                client_id,
+                veterinarian_id,
+                start_time as "start_time!: chrono::DateTime<chrono::Utc>",
+                end_time as "end_time!: chrono::DateTime<chrono::Utc>",
+                status as "status!: AppointmentStatus",
+                reason,
+                is_emergency,
+                procedure_id,
+                room_id,
+                booking_source as "booking_source!: BookingSource",
+                internal_notes,
+                created_at as "created_at!: chrono::DateTime<chrono::Utc>",
+                updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+                canceled_at,
+                cancellation_reason
+            FROM appointments
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    fn authorize(&self, claims: Option<&Claims>, access: Access) -> Result<(), ApiError> {
+        if access == Access::Read {
+            return Ok(());
+        }
+        let claims = require_user(claims)?;
+        if claims.role == UserRole::Veterinarian && self.veterinarian_id != claims.sub {
+            return Err(ApiError::Forbidden(
+                "Solo puedes modificar tus propias citas".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn claims(sub: i32, role: UserRole) -> Claims {
+        Claims {
+            sub,
+            role,
+            iat: 0,
+            exp: usize::MAX,
+            api_key_id: None,
+            impersonator: None,
+        }
+    }
+
+    fn appointment(veterinarian_id: i32) -> Appointment {
+        let now = Utc::now();
+        Appointment {
+            id: 1,
+            patient_id: Some(1),
+            client_id: Some(1),
+            veterinarian_id,
+            start_time: now,
+            end_time: now,
+            status: AppointmentStatus::Scheduled,
+            reason: "Consulta".into(),
+            is_emergency: false,
+            procedure_id: None,
+            room_id: None,
+            booking_source: BookingSource::Staff,
+            internal_notes: None,
+            created_at: now,
+            updated_at: now,
+            canceled_at: None,
+            cancellation_reason: None,
+        }
+    }
+
+    #[test]
+    fn system_api_key_cannot_modify_appointments() {
+        let appointment = appointment(3);
+        assert!(appointment.authorize(None, Access::Read).is_ok());
+        for access in [Access::Write, Access::Delete] {
+            assert!(matches!(
+                appointment.authorize(None, access),
+                Err(ApiError::Unauthorized(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn veterinarian_only_modifies_own_appointments() {
+        let appointment = appointment(3);
+        let owner = claims(3, UserRole::Veterinarian);
+        let other = claims(4, UserRole::Veterinarian);
+        assert!(appointment.authorize(Some(&owner), Access::Write).is_ok());
+        assert!(matches!(
+            appointment.authorize(Some(&other), Access::Delete),
+            Err(ApiError::Forbidden(_))
+        ));
+        assert!(appointment.authorize(Some(&other), Access::Read).is_ok());
+    }
+
+    #[test]
+    fn assistant_manages_the_whole_schedule() {
+        let assistant = claims(9, UserRole::Assistant);
+        assert!(
+            appointment(3)
+                .authorize(Some(&assistant), Access::Delete)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn locale_follows_accept_language_preferences() {
+        assert_eq!(Locale::from_accept_language("en-US,en;q=0.9"), Locale::En);
+        assert_eq!(
+            Locale::from_accept_language("fr, en;q=0.5, es;q=0.8"),
+            Locale::Es
+        );
+        assert_eq!(Locale::from_accept_language("es;q=0, en"), Locale::En);
+        assert_eq!(Locale::from_accept_language("fr-FR"), Locale::Es);
+        assert_eq!(Locale::from_accept_language(""), Locale::Es);
+    }
+}
@@ -0,0 +1,388 @@
+use crate::archive;
+use crate::auth::Claims;
+use crate::clinic;
+use crate::data_quality;
+use crate::demo;
+use crate::digest;
+use crate::errors::ApiError;
+use crate::extractors::PathId;
+use crate::integrity;
+use crate::jobs::JobRegistry;
+use crate::limits::Limit;
+use crate::maintenance::MaintenanceMode;
+use crate::models::admin::{
+    AppointmentArchiveQuery, DailyDigestRequest, DemoDatasetRequest, IntegrityQuery,
+    IntegrityReport, MaintenanceStatus, NotificationLogEntry, NotificationLogFilter,
+    RetentionQuery, UpdateMaintenance, UsageReport,
+};
+use crate::models::enums::{NotificationChannel, NotificationOutcome, UserRole};
+use crate::notifications;
+use crate::retention;
+use actix_web::{HttpResponse, web};
+use sqlx::PgPool;
+use std::env;
+use validator::Validate;
+
+/// Genera en segundo plano un conjunto de datos sintético para demostraciones:
+/// clientes marcados con `[DEMO]`, sus mascotas y citas del último año.
+///
+/// Responde 202 con el trabajo creado; el progreso se consulta en `/jobs/{id}`.
+/// Si `APP_ENV=production` se requiere `"confirm": true`.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "clients": 50,
+///   "confirm": false
+/// }
+/// ```
+#[actix_web::post("/demo-dataset")]
+async fn create_demo_dataset(
+    claims: Claims,
+    request: web::Json<DemoDatasetRequest>,
+    pool: web::Data<PgPool>,
+    jobs: web::Data<JobRegistry>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    let request = request.into_inner();
+    request.validate()?;
+
+    let is_production = env::var("APP_ENV").is_ok_and(|app_env| app_env == "production");
+    if is_production && !request.confirm {
+        return Err(ApiError::Forbidden(
+            "En producción se requiere \"confirm\": true para generar datos de demostración".into(),
+        ));
+    }
+
+    let veterinarians = sqlx::query_scalar!(
+        r#"
+        SELECT id
+        FROM users
+        WHERE role = 'veterinarian' AND is_active = TRUE
+        "#
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al preparar los datos de demostración", e))?;
+
+    if veterinarians.is_empty() {
+        return Err(ApiError::Conflict(
+            "Se requiere al menos un veterinario activo para generar citas".into(),
+        ));
+    }
+
+    let clients = request.clients.unwrap_or(20);
+    let job = jobs.start("demo_dataset", clients);
+    tracing::info!(
+        "Generando {} clientes de demostración (trabajo {})",
+        clients,
+        job.id
+    );
+
+    let pool = pool.get_ref().clone();
+    let registry = jobs.clone();
+    let job_id = job.id;
+    actix_web::rt::spawn(async move {
+        demo::generate_dataset(pool, &registry, job_id, clients, veterinarians).await;
+    });
+
+    Ok(HttpResponse::Accepted()
+        .append_header(("Location", format!("/jobs/{}", job.id)))
+        .json(job))
+}
+
+/// Envía ahora el resumen con la agenda de cada veterinario.
+///
+/// Sin cuerpo se usa la agenda de mañana. Los veterinarios que ya recibieron
+/// el resumen de esa fecha no se vuelven a notificar.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "date": "2024-06-02"
+/// }
+/// ```
+#[actix_web::post("/jobs/daily-digest/run")]
+async fn run_daily_digest(
+    claims: Claims,
+    request: Option<web::Json<DailyDigestRequest>>,
+    pool: web::Data<PgPool>,
+    jobs: web::Data<JobRegistry>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    let request = request.map(web::Json::into_inner).unwrap_or_default();
+    let date = request
+        .date
+        .unwrap_or_else(|| clinic::today() + chrono::Days::new(1));
+
+    let job = digest::start(pool.get_ref(), &jobs, date).await?;
+
+    Ok(HttpResponse::Accepted()
+        .append_header(("Location", format!("/jobs/{}", job.id)))
+        .json(job))
+}
+
+/// Depura ahora los datos que superan su periodo de retención.
+///
+/// Con `dry_run=true` responde 200 con las filas que cada política borraría;
+/// si no, responde 202 con el trabajo creado. Los registros clínicos nunca se
+/// depuran.
+///
+/// # Ejemplo
+/// POST /admin/jobs/retention/run?dry_run=true
+#[actix_web::post("/jobs/retention/run")]
+async fn run_retention(
+    claims: Claims,
+    query: web::Query<RetentionQuery>,
+    pool: web::Data<PgPool>,
+    jobs: web::Data<JobRegistry>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    if query.dry_run {
+        return Ok(HttpResponse::Ok().json(retention::preview(pool.get_ref()).await?));
+    }
+
+    let job = retention::start(pool.get_ref(), &jobs).await?;
+
+    Ok(HttpResponse::Accepted()
+        .append_header(("Location", format!("/jobs/{}", job.id)))
+        .json(job))
+}
+
+/// Archiva ahora las citas canceladas y `no_show` más antiguas que
+/// `APPOINTMENT_ARCHIVE_DAYS`.
+///
+/// Con `dry_run=true` responde 200 con cuántas se moverían; si no, responde
+/// 202 con el trabajo creado.
+///
+/// # Ejemplo
+/// POST /admin/jobs/appointment-archive/run?dry_run=true
+#[actix_web::post("/jobs/appointment-archive/run")]
+async fn run_appointment_archive(
+    claims: Claims,
+    query: web::Query<AppointmentArchiveQuery>,
+    pool: web::Data<PgPool>,
+    jobs: web::Data<JobRegistry>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    if query.dry_run {
+        return Ok(HttpResponse::Ok().json(archive::preview(pool.get_ref()).await?));
+    }
+
+    let job = archive::start(pool.get_ref(), &jobs).await?;
+
+    Ok(HttpResponse::Accepted()
+        .append_header(("Location", format!("/jobs/{}", job.id)))
+        .json(job))
+}
+
+/// Ejecuta revisiones de consistencia de solo lectura sobre los datos
+///
+/// # Parámetros (opcionales vía query string)
+/// - `checks`: Revisiones a ejecutar separadas por comas (default: todas)
+///
+/// # Ejemplo
+/// GET /admin/integrity?checks=overlapping_vet_appointments,procedures_before_birth
+#[actix_web::get("/integrity")]
+async fn integrity_report(
+    claims: Claims,
+    query: web::Query<IntegrityQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    let checks = integrity::select_checks(query.checks.as_deref())?;
+    tracing::info!("Ejecutando {} revisiones de integridad", checks.len());
+
+    let mut results = Vec::with_capacity(checks.len());
+    for check in checks {
+        results.push(integrity::run_check(pool.get_ref(), check).await?);
+    }
+
+    Ok(HttpResponse::Ok().json(IntegrityReport {
+        generated_at: chrono::Utc::now(),
+        checks: results,
+    }))
+}
+
+/// Calidad de los datos de razas: porcentaje de pacientes sin raza por
+/// especie, nombres repetidos entre ellos (posibles patrones para asignar en
+/// bloque con `POST /patients/bulk-set-breed`) y razas sin pacientes
+///
+/// # Ejemplo
+/// GET /admin/data-quality/breeds
+#[actix_web::get("/data-quality/breeds")]
+async fn breed_quality_report(
+    claims: Claims,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    let report = data_quality::breeds(pool.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Consumo actual de cada límite del plan
+///
+/// # Ejemplo
+/// GET /admin/usage
+#[actix_web::get("/usage")]
+async fn usage_report(claims: Claims, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    let mut limits = Vec::with_capacity(Limit::ALL.len());
+    for limit in Limit::ALL {
+        limits.push(limit.usage(pool.get_ref()).await?);
+    }
+
+    Ok(HttpResponse::Ok().json(UsageReport {
+        generated_at: chrono::Utc::now(),
+        limits,
+    }))
+}
+
+/// Activa o desactiva el modo de mantenimiento de solo lectura, por ejemplo
+/// durante una migración. Mientras está activo las escrituras responden 503
+/// con `"code": "maintenance"`; el cambio dura hasta reiniciar el servidor,
+/// que vuelve a leer `MAINTENANCE_READ_ONLY`.
+///
+/// # Ejemplo de petición
+/// ```json
+/// { "read_only": true }
+/// ```
+#[actix_web::put("/maintenance")]
+async fn update_maintenance(
+    claims: Claims,
+    request: web::Json<UpdateMaintenance>,
+    maintenance: web::Data<MaintenanceMode>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    maintenance.set_read_only(request.read_only);
+    tracing::warn!(
+        "Modo de solo lectura {} por el usuario {}",
+        if request.read_only {
+            "activado"
+        } else {
+            "desactivado"
+        },
+        claims.sub
+    );
+
+    Ok(HttpResponse::Ok().json(MaintenanceStatus {
+        read_only: maintenance.is_read_only(),
+    }))
+}
+
+/// Bitácora de notificaciones enviadas, de la más reciente a la más antigua,
+/// para revisar si a un cliente le llegó un aviso
+///
+/// # Parámetros (opcionales vía query string)
+/// - `client_id`: Solo las de este cliente
+/// - `channel`: Medio de envío (`Email`)
+/// - `outcome`: `Pending`, `Sent` o `Failed`
+/// - `start_date`: Intentos desde esta fecha
+/// - `end_date`: Intentos antes de esta fecha
+/// - `limit`: Máximo de resultados (default: 50)
+/// - `offset`: Desplazamiento (default: 0)
+///
+/// # Ejemplo
+/// GET /admin/notifications?client_id=12&outcome=Failed
+#[actix_web::get("/notifications")]
+async fn list_notifications(
+    claims: Claims,
+    filters: web::Query<NotificationLogFilter>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    let entries = sqlx::query_as!(
+        NotificationLogEntry,
+        r#"
+        SELECT
+            id,
+            channel as "channel: NotificationChannel",
+            target,
+            template,
+            subject,
+            client_id,
+            appointment_id,
+            outcome as "outcome: NotificationOutcome",
+            provider_message_id,
+            error,
+            retry_of,
+            attempted_at,
+            completed_at
+        FROM notification_log
+        WHERE
+            ($1::INT IS NULL OR client_id = $1) AND
+            ($2::notification_channel IS NULL OR channel = $2) AND
+            ($3::notification_outcome IS NULL OR outcome = $3) AND
+            ($4::TIMESTAMPTZ IS NULL OR attempted_at >= $4) AND
+            ($5::TIMESTAMPTZ IS NULL OR attempted_at < $5)
+        ORDER BY attempted_at DESC, id DESC
+        LIMIT $6 OFFSET $7
+        "#,
+        filters.client_id,
+        filters.channel as Option<NotificationChannel>,
+        filters.outcome as Option<NotificationOutcome>,
+        filters.start_date,
+        filters.end_date,
+        filters.limit.unwrap_or(50).min(400),
+        filters.offset.unwrap_or(0)
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener la bitácora de notificaciones", e))?;
+
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+/// Reenvía una notificación fallida con el mismo destinatario y contenido.
+///
+/// Responde con el nuevo intento, que puede volver a fallar (`outcome`
+/// `Failed`) y reintentarse. Si la notificación no falló, ya se reintentó
+/// con éxito o hay un reintento en curso responde 409, así que repetir la
+/// petición nunca envía el mensaje dos veces.
+///
+/// # Ejemplo
+/// POST /admin/notifications/42/retry
+#[actix_web::post("/notifications/{id}/retry")]
+async fn retry_notification(
+    claims: Claims,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    let entry = notifications::retry(pool.get_ref(), *id).await?;
+    tracing::info!(
+        "Notificación {} reintentada por el usuario {}: {:?}",
+        entry.retry_of.unwrap_or(entry.id),
+        claims.sub,
+        entry.outcome
+    );
+
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin")
+            .service(create_demo_dataset)
+            .service(run_daily_digest)
+            .service(run_retention)
+            .service(run_appointment_archive)
+            .service(integrity_report)
+            .service(usage_report)
+            .service(breed_quality_report)
+            .service(list_notifications)
+            .service(retry_notification)
+            .service(update_maintenance),
+    );
+}
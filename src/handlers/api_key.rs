@@ -0,0 +1,329 @@
+use crate::auth::{Claims, generate_api_key, hash_password};
+use crate::errors::ApiError;
+use crate::extractors::PathId;
+use crate::handlers::ensure_exists;
+use crate::models::api_key::{
+    ApiKey, ApiKeyFilter, ApiKeyResponse, CreatedApiKeyResponse, NewApiKey, UpdateApiKey,
+};
+use crate::models::enums::UserRole;
+use actix_web::{HttpResponse, web};
+use sqlx::PgPool;
+use validator::Validate;
+
+/// Solo administradores autenticados con JWT pueden gestionar llaves de API
+fn ensure_key_manager(claims: &Claims) -> Result<(), ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    if claims.api_key_id.is_some() {
+        return Err(ApiError::Forbidden(
+            "Una llave de API no puede gestionar otras llaves".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Lista las llaves de API (solo se expone el prefijo, nunca el secreto)
+///
+/// # Parámetros (opcionales vía query string)
+/// - `include_revoked`: Incluir llaves revocadas (default: false)
+/// - `limit`: Máximo de resultados (default: 50)
+/// - `offset`: Desplazamiento (default: 0)
+///
+/// # Ejemplo
+/// GET /api_keys?include_revoked=true
+#[actix_web::get("")]
+async fn list_api_keys(
+    claims: Claims,
+    filters: web::Query<ApiKeyFilter>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_key_manager(&claims)?;
+    tracing::info!("Listando llaves de API con filtros: {:?}", &filters);
+
+    let api_keys = sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT
+            id,
+            name,
+            prefix,
+            key_hash,
+            role as "role: UserRole",
+            scopes,
+            read_only,
+            expires_at,
+            last_used_at,
+            revoked_at,
+            created_by,
+            created_at
+        FROM api_keys
+        WHERE ($1::bool IS TRUE OR revoked_at IS NULL)
+        ORDER BY created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        filters.include_revoked,
+        filters.limit.unwrap_or(50).min(400),
+        filters.offset.unwrap_or(0)
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener llaves de API", e))?;
+
+    let responses: Vec<ApiKeyResponse> = api_keys.into_iter().map(ApiKeyResponse::from).collect();
+
+    Ok(HttpResponse::Ok().json(responses))
+}
+
+/// Obtiene una llave de API por ID
+#[actix_web::get("/{id}")]
+async fn get_api_key(
+    claims: Claims,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_key_manager(&claims)?;
+    tracing::info!("Obteniendo llave de API ID: {}", id);
+
+    let api_key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT
+            id,
+            name,
+            prefix,
+            key_hash,
+            role as "role: UserRole",
+            scopes,
+            read_only,
+            expires_at,
+            last_used_at,
+            revoked_at,
+            created_by,
+            created_at
+        FROM api_keys
+        WHERE id = $1
+        "#,
+        *id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or(ApiError::NotFound("La llave de API no existe".into()))?;
+
+    Ok(HttpResponse::Ok().json(ApiKeyResponse::from(api_key)))
+}
+
+/// Crea una nueva llave de API. La llave completa solo se devuelve en esta respuesta.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "name": "Script de reportes",
+///   "role": "Assistant",
+///   "scopes": ["appointments", "stats"],
+///   "read_only": true,
+///   "expires_at": null
+/// }
+/// ```
+#[actix_web::post("")]
+async fn create_api_key(
+    claims: Claims,
+    new_api_key: web::Json<NewApiKey>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_key_manager(&claims)?;
+    tracing::info!("Creando nueva llave de API");
+
+    let new_api_key = new_api_key.into_inner();
+    new_api_key.validate()?;
+
+    let (prefix, key) = generate_api_key();
+    let key_hash = hash_password(&key)
+        .map_err(|e| ApiError::internal("Error al procesar la llave de API", e))?;
+
+    let scopes: Vec<String> = new_api_key
+        .scopes
+        .iter()
+        .map(|scope| scope.trim_matches('/').to_string())
+        .collect();
+
+    let api_key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        INSERT INTO api_keys (
+            name,
+            prefix,
+            key_hash,
+            role,
+            scopes,
+            read_only,
+            expires_at,
+            created_by
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING
+            id,
+            name,
+            prefix,
+            key_hash,
+            role as "role: UserRole",
+            scopes,
+            read_only,
+            expires_at,
+            last_used_at,
+            revoked_at,
+            created_by,
+            created_at
+        "#,
+        new_api_key.name.trim(),
+        prefix,
+        key_hash,
+        new_api_key.role as UserRole,
+        &scopes,
+        new_api_key.read_only,
+        new_api_key.expires_at,
+        claims.sub
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al guardar la llave de API", e))?;
+
+    tracing::info!("Llave de API creada exitosamente ID: {}", api_key.id);
+
+    Ok(HttpResponse::Created()
+        .append_header(("Location", format!("/api_keys/{}", api_key.id)))
+        .json(CreatedApiKeyResponse {
+            api_key: ApiKeyResponse::from(api_key),
+            key,
+        }))
+}
+
+/// Actualiza una llave de API vigente (actualización parcial)
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "scopes": ["appointments"],
+///   "expires_at": null
+/// }
+/// ```
+#[actix_web::put("/{id}")]
+async fn update_api_key(
+    claims: Claims,
+    id: PathId,
+    updated_api_key: web::Json<UpdateApiKey>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_key_manager(&claims)?;
+    tracing::info!("Actualizando llave de API ID: {}", id);
+
+    // Verificar si la llave existe
+    ensure_exists(pool.get_ref(), "api_keys", *id, "La llave de API no existe").await?;
+
+    let updated_api_key = updated_api_key.into_inner();
+    updated_api_key.validate()?;
+
+    let scopes: Option<Vec<String>> = updated_api_key.scopes.map(|scopes| {
+        scopes
+            .iter()
+            .map(|scope| scope.trim_matches('/').to_string())
+            .collect()
+    });
+    let clear_expiration = updated_api_key.expires_at == Some(None);
+
+    let api_key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        UPDATE api_keys
+        SET
+            name = COALESCE($1, name),
+            scopes = COALESCE($2, scopes),
+            read_only = COALESCE($3, read_only),
+            expires_at = CASE
+                WHEN $4::TIMESTAMPTZ IS NOT NULL THEN $4 -- Nuevo valor
+                WHEN $5::BOOLEAN THEN NULL -- Quitar la expiración
+                ELSE expires_at -- Mantener el valor existente
+            END
+        WHERE id = $6 AND revoked_at IS NULL
+        RETURNING
+            id,
+            name,
+            prefix,
+            key_hash,
+            role as "role: UserRole",
+            scopes,
+            read_only,
+            expires_at,
+            last_used_at,
+            revoked_at,
+            created_by,
+            created_at
+        "#,
+        updated_api_key.name.map(|s| s.trim().to_string()),
+        scopes.as_deref(),
+        updated_api_key.read_only,
+        updated_api_key.expires_at.flatten(),
+        clear_expiration,
+        *id
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al actualizar la llave de API", e))?;
+
+    match api_key {
+        Some(api_key) => {
+            tracing::info!("Llave de API {} actualizada exitosamente", api_key.id);
+            Ok(HttpResponse::Ok().json(ApiKeyResponse::from(api_key)))
+        }
+        None => {
+            tracing::warn!("Llave de API {} no encontrada o revocada", &id);
+            Err(ApiError::NotFound(
+                "La llave de API no existe o fue revocada".into(),
+            ))
+        }
+    }
+}
+
+/// Revoca una llave de API. El efecto es inmediato: el middleware consulta
+/// el estado de la llave en cada petición.
+#[actix_web::delete("/{id}")]
+async fn revoke_api_key(
+    claims: Claims,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_key_manager(&claims)?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE api_keys
+        SET revoked_at = NOW()
+        WHERE id = $1 AND revoked_at IS NULL
+        RETURNING id
+        "#,
+        *id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?;
+
+    match result {
+        Some(api_key) => {
+            tracing::info!("Llave de API {} revocada", api_key.id);
+            Ok(HttpResponse::NoContent().finish())
+        }
+        None => {
+            tracing::warn!("Llave de API {} no encontrada o ya revocada", id);
+            Err(ApiError::NotFound("La llave de API no existe".into()))
+        }
+    }
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api_keys")
+            .service(list_api_keys)
+            .service(get_api_key)
+            .service(create_api_key)
+            .service(update_api_key)
+            .service(revoke_api_key),
+    );
+}
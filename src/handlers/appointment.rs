@@ -1,12 +1,37 @@
+use crate::auth::Claims;
+use crate::clinic;
 use crate::errors::ApiError;
+use crate::events::{AppointmentSnapshot, DomainEvent, EventBus};
+use crate::extractors::{LoadedAppointment, PathId};
+use crate::handlers::ensure_exists;
+use crate::handlers::hospitalization::ensure_veterinarian;
+use crate::handlers::room::{assign_room, required_room_type, room_bookings};
+use crate::limits::{self, Limit};
 use crate::models::appointment::{
-    Appointment, AppointmentFilter, AppointmentResponse, NewAppointment, UpdateAppointment,
+    Appointment, AppointmentConflictDetails, AppointmentFilter, AppointmentListResponse,
+    AppointmentResponse, AppointmentRow, AppointmentStatusCounts, CalendarAppointment, CalendarDay,
+    CalendarEntry, CalendarQuery, CalendarRangeQuery, CalendarVeterinarian, DEFAULT_SLOT_MINUTES,
+    DEFAULT_SUGGESTIONS, NewAppointment, NextAvailableQuery, ReassignAppointments,
+    ReassignmentResponse, SkippedReassignment, SuggestedSlot, TimeSlot, UpdateAppointment,
+    UpdateAppointmentQuery, UpdateAppointmentStatus, booking_horizon_days, booking_source,
+    check_appointment_update, check_past_edit, effective_time_range, ensure_within_booking_horizon,
+    free_slots, is_valid_status_transition,
 };
-use crate::models::enums::AppointmentStatus;
-use actix_web::{HttpResponse, web};
-use sqlx::PgPool;
+use crate::models::appointment_note::{
+    AppointmentNoteFilter, AppointmentNoteResponse, LATEST_NOTES, NewAppointmentNote,
+};
+use crate::models::enums::{AppointmentStatus, BookingSource, UserRole};
+use crate::models::page::Page;
+use crate::models::warning::{WarningOverride, WithWarnings};
+use actix_web::{HttpRequest, HttpResponse, web};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::BTreeMap;
 use validator::Validate;
 
+/// Días que revisa `GET /appointments/next-available` hacia adelante
+const NEXT_AVAILABLE_SEARCH_DAYS: u64 = 31;
+
 /// Lista citas con filtros avanzados y paginación
 ///
 /// # Parámetros (opcionales vía query string)
@@ -17,11 +42,23 @@ use validator::Validate;
 /// - `start_date`: Citas después de esta fecha
 /// - `end_date`: Citas antes de esta fecha
 /// - reason_contains: Filtra por razón
+/// - `booking_source`: Filtrar por origen (Staff, Portal, Api, Import, WalkIn)
+/// - `created_after` / `created_before`: Agendadas en ese rango (`created_at`)
+/// - `at`: Citas en curso en ese instante (`start_time <= at < end_time`)
+/// - `active_now`: Igual que `at` con la hora actual
+/// - `include_counts`: Agrega `status_counts` a la respuesta (default: false)
+/// - `include_archived`: Incluye las citas canceladas y no_show archivadas (default: false)
+/// - `envelope`: Con `false` responde el arreglo sin envoltura ni `total`
+///   (obsoleto, se mantiene mientras migran los clientes)
 /// - `limit`: Máximo de resultados (default: 50)
 /// - `offset`: Desplazamiento (default: 0)
 ///
+/// Responde `{ items, total, limit, offset }`, donde `total` cuenta todas las
+/// citas que cumplen los filtros.
+///
 /// # Ejemplo
 /// GET /appointments?patient_id=5&status=scheduled&limit=10
+/// GET /appointments?active_now=true&status=Scheduled
 #[actix_web::get("")]
 async fn list_appointments(
     filters: web::Query<AppointmentFilter>,
@@ -29,19 +66,152 @@ async fn list_appointments(
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Listando citas con filtros: {:?}", &filters);
 
-    let appointments = sqlx::query_as!(
-        Appointment,
+    let at = filters.instant()?;
+    let limit = filters.limit.unwrap_or(50).min(400);
+    let offset = filters.offset.unwrap_or(0);
+
+    // Los nombres y las últimas notas se resuelven en la misma consulta, no una por cita
+    let rows = sqlx::query_as!(
+        AppointmentRow,
         r#"
         SELECT
-            id,
-            patient_id,
-            client_id,
-            veterinarian_id,
-            start_time as "start_time!: chrono::DateTime<chrono::Utc>",
-            end_time as "end_time!: chrono::DateTime<chrono::Utc>",
-            status as "status!: AppointmentStatus",
-            reason
-        FROM appointments
+            a.id as "id!",
+            a.patient_id,
+            p.name as "patient_name?",
+            a.client_id,
+            c.name as "client_name?",
+            a.veterinarian_id as "veterinarian_id!",
+            u.name as veterinarian_name,
+            a.start_time as "start_time!: chrono::DateTime<chrono::Utc>",
+            a.end_time as "end_time!: chrono::DateTime<chrono::Utc>",
+            a.status as "status!: AppointmentStatus",
+            a.reason as "reason!",
+            a.is_emergency as "is_emergency!",
+            a.procedure_id,
+            pr.name as "procedure_name?",
+            a.room_id,
+            r.name as "room_name?",
+            a.booking_source as "booking_source!: BookingSource",
+            a.internal_notes,
+            a.created_at as "created_at!: chrono::DateTime<chrono::Utc>",
+            a.updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+            a.canceled_at,
+            a.cancellation_reason,
+            notes.latest as "latest_notes!: sqlx::types::Json<Vec<AppointmentNoteResponse>>"
+        FROM (
+            SELECT * FROM appointments
+            UNION ALL
+            -- El archivo solo se consulta si se pide explícitamente
+            SELECT * FROM appointments_archive WHERE $12
+        ) a
+        JOIN users u ON u.id = a.veterinarian_id
+        LEFT JOIN patients p ON p.id = a.patient_id
+        LEFT JOIN clients c ON c.id = a.client_id
+        LEFT JOIN procedures pr ON pr.id = a.procedure_id
+        LEFT JOIN rooms r ON r.id = a.room_id
+        CROSS JOIN LATERAL (
+            SELECT COALESCE(
+                JSON_AGG(JSON_BUILD_OBJECT(
+                    'id', n.id,
+                    'appointment_id', n.appointment_id,
+                    'author_id', n.author_id,
+                    'author_name', n.author_name,
+                    'content', n.content,
+                    'created_at', n.created_at
+                ) ORDER BY n.created_at DESC, n.id DESC),
+                '[]'
+            ) as latest
+            FROM (
+                SELECT n.id, n.appointment_id, n.author_id, nu.name as author_name, n.content, n.created_at
+                FROM appointment_notes n
+                JOIN users nu ON nu.id = n.author_id
+                WHERE n.appointment_id = a.id
+                ORDER BY n.created_at DESC, n.id DESC
+                LIMIT $15
+            ) n
+        ) notes
+        WHERE
+            ($1::int IS NULL OR a.patient_id = $1) AND
+            ($2::int IS NULL OR a.client_id = $2) AND
+            ($3::int IS NULL OR a.veterinarian_id = $3) AND
+            ($4::appointment_status IS NULL OR a.status = $4) AND
+            ($5::timestamptz IS NULL OR a.start_time >= $5) AND
+            ($6::timestamptz IS NULL OR a.end_time <= $6) AND
+            ($7::text IS NULL OR a.reason ILIKE '%' || $7 || '%') AND
+            ($10::booking_source IS NULL OR a.booking_source = $10) AND
+            -- Ninguna cita dura un día: la cota inferior acota el rango de idx_appointment_start
+            ($11::timestamptz IS NULL OR (
+                a.start_time <= $11 AND a.start_time > $11 - INTERVAL '1 day' AND a.end_time > $11
+            )) AND
+            ($13::timestamptz IS NULL OR a.created_at >= $13) AND
+            ($14::timestamptz IS NULL OR a.created_at < $14)
+        ORDER BY a.start_time DESC
+        LIMIT $8 OFFSET $9
+        "#,
+        filters.patient_id,
+        filters.client_id,
+        filters.veterinarian_id,
+        filters.status.clone() as Option<AppointmentStatus>,
+        filters.start_date,
+        filters.end_date,
+        filters.reason_contains,
+        limit,
+        offset,
+        filters.booking_source as Option<BookingSource>,
+        at,
+        filters.include_archived.unwrap_or(false),
+        filters.created_after,
+        filters.created_before,
+        LATEST_NOTES
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener citas", e))?;
+
+    let responses: Vec<AppointmentResponse> = rows
+        .into_iter()
+        .map(AppointmentResponse::from_joined_row)
+        .collect();
+
+    let include_counts = filters.include_counts.unwrap_or(false);
+    if !filters.envelope.unwrap_or(true) && !include_counts {
+        return Ok(HttpResponse::Ok()
+            .append_header(("Deprecation", "true"))
+            .json(responses));
+    }
+
+    let total = count_appointments(&filters, at, pool.get_ref()).await?;
+    let status_counts = if include_counts {
+        Some(get_status_counts(&filters, at, pool.get_ref()).await?)
+    } else {
+        None
+    };
+
+    Ok(HttpResponse::Ok().json(AppointmentListResponse {
+        page: Page {
+            items: responses,
+            total,
+            limit,
+            offset,
+        },
+        status_counts,
+    }))
+}
+
+/// Cuenta las citas que cumplen los filtros del listado, sin `limit` ni `offset`
+async fn count_appointments(
+    filters: &AppointmentFilter,
+    at: Option<DateTime<Utc>>,
+    pool: &PgPool,
+) -> Result<i64, ApiError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!"
+        FROM (
+            SELECT * FROM appointments
+            UNION ALL
+            SELECT * FROM appointments_archive WHERE $10
+        ) a
         WHERE
             ($1::int IS NULL OR patient_id = $1) AND
             ($2::int IS NULL OR client_id = $2) AND
@@ -49,9 +219,13 @@ async fn list_appointments(
             ($4::appointment_status IS NULL OR status = $4) AND
             ($5::timestamptz IS NULL OR start_time >= $5) AND
             ($6::timestamptz IS NULL OR end_time <= $6) AND
-            ($7::text IS NULL OR reason ILIKE '%' || $7 || '%')
-        ORDER BY start_time DESC
-        LIMIT $8 OFFSET $9
+            ($7::text IS NULL OR reason ILIKE '%' || $7 || '%') AND
+            ($8::booking_source IS NULL OR booking_source = $8) AND
+            ($9::timestamptz IS NULL OR (
+                start_time <= $9 AND start_time > $9 - INTERVAL '1 day' AND end_time > $9
+            )) AND
+            ($11::timestamptz IS NULL OR created_at >= $11) AND
+            ($12::timestamptz IS NULL OR created_at < $12)
         "#,
         filters.patient_id,
         filters.client_id,
@@ -60,25 +234,353 @@ async fn list_appointments(
         filters.start_date,
         filters.end_date,
         filters.reason_contains,
-        filters.limit.unwrap_or(50).min(400),
-        filters.offset.unwrap_or(0)
+        filters.booking_source as Option<BookingSource>,
+        at,
+        filters.include_archived.unwrap_or(false),
+        filters.created_after,
+        filters.created_before
     )
-    .fetch_all(pool.get_ref())
+    .fetch_one(pool)
     .await
-    .map_err(|e| {
-        tracing::error!("Error al listar citas: {}", e);
-        ApiError::InternalServerError("Error al obtener citas".into())
-    })?;
-
-    // Convertir a respuestas enriquecidas
-    let responses = futures::future::try_join_all(
-        appointments
-            .into_iter()
-            .map(|app| async { AppointmentResponse::from_appointment(app, pool.get_ref()).await }),
+    .map_err(|e| ApiError::internal("Error al contar citas", e))
+}
+
+/// Cuenta las citas por estado aplicando los filtros del listado, excepto `status`
+async fn get_status_counts(
+    filters: &AppointmentFilter,
+    at: Option<DateTime<Utc>>,
+    pool: &PgPool,
+) -> Result<AppointmentStatusCounts, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            status as "status!: AppointmentStatus",
+            COUNT(*) as "count!"
+        FROM (
+            SELECT * FROM appointments
+            UNION ALL
+            SELECT * FROM appointments_archive WHERE $9
+        ) a
+        WHERE
+            ($1::int IS NULL OR patient_id = $1) AND
+            ($2::int IS NULL OR client_id = $2) AND
+            ($3::int IS NULL OR veterinarian_id = $3) AND
+            ($4::timestamptz IS NULL OR start_time >= $4) AND
+            ($5::timestamptz IS NULL OR end_time <= $5) AND
+            ($6::text IS NULL OR reason ILIKE '%' || $6 || '%') AND
+            ($7::booking_source IS NULL OR booking_source = $7) AND
+            ($8::timestamptz IS NULL OR (
+                start_time <= $8 AND start_time > $8 - INTERVAL '1 day' AND end_time > $8
+            )) AND
+            ($10::timestamptz IS NULL OR created_at >= $10) AND
+            ($11::timestamptz IS NULL OR created_at < $11) AND
+            status IS NOT NULL
+        GROUP BY status
+        "#,
+        filters.patient_id,
+        filters.client_id,
+        filters.veterinarian_id,
+        filters.start_date,
+        filters.end_date,
+        filters.reason_contains,
+        filters.booking_source as Option<BookingSource>,
+        at,
+        filters.include_archived.unwrap_or(false),
+        filters.created_after,
+        filters.created_before
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener citas", e))?;
+
+    let mut counts = AppointmentStatusCounts::default();
+    for row in rows {
+        counts.add(row.status, row.count);
+    }
+
+    Ok(counts)
+}
+
+/// Próximos horarios libres para agendar una cita, para no adivinar horarios
+/// hasta que uno no responda 409
+///
+/// Usa el mismo cálculo que la disponibilidad de `/veterinarians/{id}`:
+/// horario de atención menos las citas de cada veterinario, con inicios en
+/// una rejilla desde la apertura (cada `duration_minutes`, máximo 30). Si se
+/// indica `procedure_id` y requiere sala, solo sugiere horarios con una sala
+/// de ese tipo libre. Revisa hasta 31 días sin pasar del horizonte de
+/// reservas.
+///
+/// # Parámetros (opcionales vía query string)
+/// - `veterinarian_id`: Solo este veterinario (default: todos los activos)
+/// - `duration_minutes`: Duración de la cita, de 5 a 720 (default: 30)
+/// - `after`: Buscar desde este instante (default: ahora)
+/// - `procedure_id`: Procedimiento de la cita, para exigir sala
+/// - `limit`: Sugerencias, de 1 a 20 (default: 5)
+///
+/// # Ejemplo
+/// GET /appointments/next-available?veterinarian_id=1&duration_minutes=45
+#[actix_web::get("/next-available")]
+async fn get_next_available(
+    query: web::Query<NextAvailableQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+    tracing::info!("Buscando horarios libres: {:?}", &query);
+
+    let now = Utc::now();
+    let after = query.after.map_or(now, |after| after.max(now));
+    let horizon = now + Duration::days(booking_horizon_days());
+    let duration = Duration::minutes(query.duration_minutes.unwrap_or(DEFAULT_SLOT_MINUTES));
+    let step = duration.min(Duration::minutes(DEFAULT_SLOT_MINUTES));
+    let limit = query.limit.unwrap_or(DEFAULT_SUGGESTIONS) as usize;
+
+    let veterinarians = sqlx::query!(
+        r#"
+        SELECT id, name
+        FROM users
+        WHERE role = 'veterinarian'
+          AND is_active = TRUE
+          AND ($1::int IS NULL OR id = $1)
+        ORDER BY id
+        "#,
+        query.veterinarian_id
     )
+    .fetch_all(pool.get_ref())
     .await?;
+    if let (Some(id), true) = (query.veterinarian_id, veterinarians.is_empty()) {
+        return Err(ApiError::NotFound(format!(
+            "Veterinario con ID {} no encontrado",
+            id
+        )));
+    }
+    let veterinarian_ids: Vec<i32> = veterinarians.iter().map(|vet| vet.id).collect();
+
+    let room_type = match query.procedure_id {
+        Some(procedure_id) => required_room_type(pool.get_ref(), procedure_id).await?,
+        None => None,
+    };
+
+    let first_day = after.with_timezone(&clinic::timezone()).date_naive();
+    let mut suggestions: Vec<SuggestedSlot> = Vec::with_capacity(limit);
+    for day in 0..NEXT_AVAILABLE_SEARCH_DAYS {
+        let date = first_day + chrono::Days::new(day);
+        let Some((opens_at, from, closes_at)) = open_window(pool.get_ref(), date, after).await?
+        else {
+            continue;
+        };
+        if from > horizon {
+            break;
+        }
+
+        let busy = busy_by_veterinarian(pool.get_ref(), &veterinarian_ids, from, closes_at).await?;
+        let rooms = match &room_type {
+            Some(room_type) => {
+                Some(room_bookings(pool.get_ref(), room_type, from, closes_at).await?)
+            }
+            None => None,
+        };
 
-    Ok(HttpResponse::Ok().json(responses))
+        let mut candidates: Vec<SuggestedSlot> = Vec::new();
+        for vet in &veterinarians {
+            let taken = busy.get(&vet.id).map_or(&[][..], Vec::as_slice);
+            for gap in free_slots(taken, opens_at, from, closes_at, step, duration) {
+                let mut start_time = gap.start_time;
+                while start_time + duration <= gap.end_time && start_time <= horizon {
+                    let end_time = start_time + duration;
+                    let room_free = rooms.as_ref().is_none_or(|rooms| {
+                        rooms.iter().any(|bookings| {
+                            bookings
+                                .iter()
+                                .all(|b| b.end_time <= start_time || end_time <= b.start_time)
+                        })
+                    });
+                    if room_free {
+                        candidates.push(SuggestedSlot {
+                            veterinarian_id: vet.id,
+                            veterinarian_name: vet.name.clone(),
+                            start_time,
+                            end_time,
+                        });
+                    }
+                    start_time += step;
+                }
+            }
+        }
+
+        candidates.sort_by_key(|slot| (slot.start_time, slot.veterinarian_id));
+        let missing = limit - suggestions.len();
+        suggestions.extend(candidates.into_iter().take(missing));
+        if suggestions.len() == limit {
+            break;
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(suggestions))
+}
+
+/// Calendario de la clínica para la vista semanal: por día local y por
+/// veterinario, las citas resumidas en orden de hora
+///
+/// # Parámetros (vía query string)
+/// - `start`: Primer día (AAAA-MM-DD)
+/// - `days`: Número de días (default: 7, máximo: 31)
+/// - `veterinarian_ids`: IDs separados por comas (default: todos)
+///
+/// # Ejemplo
+/// GET /appointments/calendar?start=2024-06-03&days=7&veterinarian_ids=1,2
+#[actix_web::get("/calendar")]
+async fn get_calendar(
+    query: web::Query<CalendarQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Obteniendo calendario: {:?}", &query);
+
+    let days = query.days()?;
+    let veterinarian_ids = query.veterinarian_ids()?;
+
+    let (range_start, _) = clinic::day_bounds(query.start);
+    let (range_end, _) = clinic::day_bounds(query.start + chrono::Days::new(days.into()));
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            a.id,
+            a.start_time,
+            a.end_time as "end_time!",
+            a.status as "status!: AppointmentStatus",
+            a.is_emergency,
+            a.veterinarian_id,
+            u.name as veterinarian_name,
+            p.name as "patient_name?"
+        FROM appointments a
+        JOIN users u ON u.id = a.veterinarian_id
+        LEFT JOIN patients p ON p.id = a.patient_id
+        WHERE a.start_time >= $1
+          AND a.start_time < $2
+          AND ($3::INT[] IS NULL OR a.veterinarian_id = ANY($3))
+        ORDER BY u.name, a.veterinarian_id, a.start_time
+        "#,
+        range_start,
+        range_end,
+        veterinarian_ids.as_deref()
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener el calendario", e))?;
+
+    let mut calendar: Vec<CalendarDay> = (0..days)
+        .map(|offset| CalendarDay {
+            date: query.start + chrono::Days::new(offset.into()),
+            timezone: clinic::timezone_name(),
+            veterinarians: Vec::new(),
+        })
+        .collect();
+
+    // Agrupar por día local y luego por veterinario (ya vienen ordenadas)
+    for row in rows {
+        let date = clinic::local(row.start_time).date_naive();
+        let Some(day) = calendar.iter_mut().find(|day| day.date == date) else {
+            continue;
+        };
+
+        if day
+            .veterinarians
+            .last()
+            .is_none_or(|vet| vet.veterinarian_id != row.veterinarian_id)
+        {
+            day.veterinarians.push(CalendarVeterinarian {
+                veterinarian_id: row.veterinarian_id,
+                veterinarian_name: row.veterinarian_name,
+                appointments: Vec::new(),
+            });
+        }
+        if let Some(vet) = day.veterinarians.last_mut() {
+            vet.appointments.push(CalendarAppointment {
+                id: row.id,
+                start: row.start_time,
+                end: row.end_time,
+                start_local: clinic::local_iso(row.start_time),
+                end_local: clinic::local_iso(row.end_time),
+                status: row.status,
+                patient_name: row.patient_name,
+                is_emergency: row.is_emergency,
+            });
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(calendar))
+}
+
+/// Calendario por día para la vista mensual: cada día local del rango con
+/// sus citas resumidas en orden de hora, de todos los veterinarios o de uno
+///
+/// Todos los días del rango aparecen, aunque no tengan citas, para que la
+/// vista dibuje la cuadrícula directamente.
+///
+/// # Parámetros (vía query string)
+/// - `from`: Primer día (AAAA-MM-DD)
+/// - `to`: Último día, inclusive (máximo 62 días en total)
+/// - `veterinarian_id`: Filtrar por veterinario (default: todos)
+///
+/// # Ejemplo
+/// GET /appointments/calendar/days?from=2024-06-01&to=2024-06-30&veterinarian_id=1
+#[actix_web::get("/calendar/days")]
+async fn get_calendar_days(
+    query: web::Query<CalendarRangeQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Obteniendo calendario por día: {:?}", &query);
+
+    let days = query.days()?;
+    let (range_start, _) = clinic::day_bounds(query.from);
+    let (_, range_end) = clinic::day_bounds(query.to);
+
+    // Mismo JOIN que el listado, solo con las columnas de la vista
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            a.id,
+            a.start_time,
+            a.end_time as "end_time!",
+            a.status as "status!: AppointmentStatus",
+            a.reason,
+            p.name as "patient_name?"
+        FROM appointments a
+        LEFT JOIN patients p ON p.id = a.patient_id
+        WHERE a.start_time >= $1
+          AND a.start_time < $2
+          AND ($3::INT IS NULL OR a.veterinarian_id = $3)
+        ORDER BY a.start_time, a.id
+        "#,
+        range_start,
+        range_end,
+        query.veterinarian_id
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener el calendario", e))?;
+
+    let mut calendar: BTreeMap<NaiveDate, Vec<CalendarEntry>> = (0..days)
+        .map(|offset| (query.from + chrono::Days::new(offset.into()), Vec::new()))
+        .collect();
+
+    let tz = clinic::timezone();
+    for row in rows {
+        let date = row.start_time.with_timezone(&tz).date_naive();
+        if let Some(entries) = calendar.get_mut(&date) {
+            entries.push(CalendarEntry {
+                id: row.id,
+                start_time: row.start_time,
+                end_time: row.end_time,
+                status: row.status,
+                patient_name: row.patient_name,
+                reason: row.reason,
+            });
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(calendar))
 }
 
 /// Obtiene una cita específica por su ID
@@ -88,113 +590,243 @@ async fn list_appointments(
 /// - 404 Not Found: Si la cita no existe
 /// - 500 Internal Server Error: Error de base de datos
 #[actix_web::get("/{id}")]
-async fn get_appointment(
-    id: web::Path<i32>,
-    pool: web::Data<PgPool>,
-) -> Result<HttpResponse, ApiError> {
+async fn get_appointment(id: PathId, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
     tracing::info!("Obteniendo cita con ID: {}", id);
 
-    // Obtener la cita básica
+    // Obtener la cita básica; las archivadas se siguen encontrando por su ID,
+    // por eso no se usa `LoadedAppointment`, que solo busca en `appointments`
     let appointment = sqlx::query_as!(
         Appointment,
         r#"
         SELECT
-            id,
+            id as "id!",
             patient_id,
             client_id,
-            veterinarian_id,
+            veterinarian_id as "veterinarian_id!",
             start_time as "start_time!: chrono::DateTime<chrono::Utc>",
             end_time as "end_time!: chrono::DateTime<chrono::Utc>",
             status as "status!: AppointmentStatus",
-            reason
-        FROM appointments
-        WHERE id = $1
+            reason as "reason!",
+            is_emergency as "is_emergency!",
+            procedure_id,
+            room_id,
+            booking_source as "booking_source!: BookingSource",
+            internal_notes,
+            created_at as "created_at!: chrono::DateTime<chrono::Utc>",
+            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+            canceled_at,
+            cancellation_reason
+        FROM (
+            SELECT * FROM appointments WHERE id = $1
+            UNION ALL
+            SELECT * FROM appointments_archive WHERE id = $1
+        ) a
+        LIMIT 1
         "#,
-        id.clone()
+        *id
     )
     .fetch_optional(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al buscar cita: {}", e);
-        ApiError::InternalServerError("Error al acceder a la base de datos".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al acceder a la base de datos", e))?;
 
-    match appointment {
-        Some(appt) => {
-            // Convertir a respuesta enriquecida
-            let response = AppointmentResponse::from_appointment(appt, pool.get_ref())
-                .await
-                .map_err(|e| {
-                    tracing::error!("Error al enriquecer datos de cita: {}", e);
-                    ApiError::InternalServerError("Error al procesar la cita".into())
-                })?;
-
-            tracing::info!("Cita {} encontrada", id);
-            Ok(HttpResponse::Ok().json(response))
+    let appointment = appointment.ok_or_else(|| ApiError::NotFound("Cita no encontrada".into()))?;
+
+    // Convertir a respuesta enriquecida
+    let response = AppointmentResponse::from_appointment(appointment, pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal("Error al procesar la cita", e))?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Verifica que el veterinario no tenga otra cita que se traslape con el rango.
+/// Las citas canceladas o no_show no ocupan el horario.
+///
+/// Si la tiene responde 409 con el horario de la cita en conflicto y el
+/// siguiente hueco libre del mismo día con la duración solicitada. El hueco se
+/// busca hasta el cierre del día si la cita cae dentro del horario de
+/// atención, o hasta el fin del día si no.
+pub(super) async fn ensure_veterinarian_available(
+    pool: &PgPool,
+    veterinarian_id: i32,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    exclude_id: Option<i32>,
+) -> Result<(), ApiError> {
+    let tz = clinic::timezone();
+    let date = start_time.with_timezone(&tz).date_naive();
+    let (_, day_end) = clinic::day_bounds(date);
+    let hours = clinic::hours_on(pool, date).await?;
+    let search_end = match hours {
+        Some((_, closing)) if clinic::within_hours(hours, start_time, end_time) => {
+            clinic::local_instant(date, closing)
         }
-        None => {
-            tracing::warn!("Cita {} no encontrada", id);
-            Err(ApiError::NotFound("Cita no encontrada".into()))
+        _ => day_end,
+    }
+    .max(end_time);
+
+    let busy = sqlx::query_as!(
+        TimeSlot,
+        r#"
+        SELECT start_time, end_time as "end_time!"
+        FROM appointments
+        WHERE veterinarian_id = $1
+          AND ($2::int IS NULL OR id != $2)
+          AND status NOT IN ('canceled', 'no_show')
+          AND start_time < $3
+          AND end_time > $4
+        ORDER BY start_time, id
+        "#,
+        veterinarian_id,
+        exclude_id,
+        search_end,
+        start_time
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let Some(conflict) = busy
+        .iter()
+        .find(|slot| slot.start_time < end_time && start_time < slot.end_time)
+    else {
+        return Ok(());
+    };
+
+    tracing::warn!(
+        "El veterinario con ID {} no está disponible de {} a {}",
+        veterinarian_id,
+        start_time,
+        end_time
+    );
+
+    // Las citas vienen ordenadas por inicio: el candidato solo avanza
+    let duration = end_time - start_time;
+    let mut candidate = start_time;
+    for slot in &busy {
+        if slot.start_time >= candidate + duration {
+            break;
+        }
+        if slot.end_time > candidate {
+            candidate = slot.end_time;
         }
     }
-}
+    let next_available = (candidate + duration <= search_end).then(|| TimeSlot {
+        start_time: candidate,
+        end_time: candidate + duration,
+    });
 
-/// Crea una nueva cita
-///
-/// # Ejemplo de petición
-/// ```json
-/// {
-///   "patient_id": 1,
-///   "client_id": 2,
-///   "veterinarian_id": 3,
-///   "start_time": "2023-11-01T10:00:00Z",
-///   "end_time": "2023-11-01T11:00:00Z",
-///   "reason": "Consulta de rutina"
-/// }
-/// ```
-#[actix_web::post("")]
-async fn create_appointment(
-    new_appointment: web::Json<NewAppointment>,
-    pool: web::Data<PgPool>,
-) -> Result<HttpResponse, ApiError> {
-    tracing::info!("Creando nueva cita");
+    let details = AppointmentConflictDetails {
+        conflicting: TimeSlot {
+            start_time: conflict.start_time,
+            end_time: conflict.end_time,
+        },
+        next_available,
+    };
+    Err(ApiError::DetailedConflict {
+        message: "El veterinario no está disponible en este horario".into(),
+        details: serde_json::to_value(details).unwrap_or_default(),
+    })
+}
 
-    // Validar los datos de entrada
-    let new_appointment = new_appointment.into_inner();
-    new_appointment.validate()?;
+/// Parte del horario de atención de un día local que queda a partir de
+/// `after`: (apertura, desde, cierre). `None` si la clínica no abre ese día o
+/// ya cerró.
+async fn open_window(
+    pool: &PgPool,
+    date: NaiveDate,
+    after: DateTime<Utc>,
+) -> Result<Option<(DateTime<Utc>, DateTime<Utc>, DateTime<Utc>)>, ApiError> {
+    let Some((opening, closing)) = clinic::hours_on(pool, date).await? else {
+        return Ok(None);
+    };
+    let opens_at = clinic::local_instant(date, opening);
+    let closes_at = clinic::local_instant(date, closing);
+    let from = opens_at.max(after);
+    Ok((from < closes_at).then_some((opens_at, from, closes_at)))
+}
 
-    // Verificar que el veterinario esté disponible en el rango de tiempo
-    let veterinarian_is_available: bool = sqlx::query_scalar!(
+/// Citas que ocupan a cada veterinario entre `from` y `to`, ordenadas por
+/// inicio, en una sola consulta. Como en la verificación de disponibilidad,
+/// las citas canceladas o de inasistencia no ocupan lugar.
+async fn busy_by_veterinarian(
+    pool: &PgPool,
+    veterinarian_ids: &[i32],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<BTreeMap<i32, Vec<TimeSlot>>, ApiError> {
+    let rows = sqlx::query!(
         r#"
-        SELECT NOT EXISTS (
-            SELECT 1
-            FROM appointments
-            WHERE veterinarian_id = $1
-              AND (
-                  ($2, $3) OVERLAPS (start_time, end_time)
-              )
-        )
+        SELECT veterinarian_id, start_time, end_time as "end_time!"
+        FROM appointments
+        WHERE veterinarian_id = ANY($1)
+          AND status NOT IN ('canceled', 'no_show')
+          AND start_time < $2
+          AND end_time > $3
+        ORDER BY veterinarian_id, start_time, id
         "#,
-        new_appointment.veterinarian_id,
-        new_appointment.start_time,
-        new_appointment.end_time,
+        veterinarian_ids,
+        to,
+        from
     )
-    .fetch_one(pool.get_ref())
-    .await?
-    .unwrap_or(true);
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ApiError::internal("Error al calcular la disponibilidad", e))?;
 
-    if !veterinarian_is_available {
-        tracing::warn!(
-            "El veterinario con ID {} no está disponible en el rango de tiempo solicitado",
-            new_appointment.veterinarian_id
-        );
-        return Err(ApiError::Conflict(
-            "El veterinario no está disponible en este horario".into(),
-        ));
+    let mut busy: BTreeMap<i32, Vec<TimeSlot>> = BTreeMap::new();
+    for row in rows {
+        busy.entry(row.veterinarian_id).or_default().push(TimeSlot {
+            start_time: row.start_time,
+            end_time: row.end_time,
+        });
     }
+    Ok(busy)
+}
 
-    // Insertar la cita en la base de datos
-    let appointment = sqlx::query_as!(
+/// Huecos libres de un veterinario en un día local de la clínica: el horario
+/// de atención menos sus citas y lo que ya pasó del día.
+pub(super) async fn veterinarian_free_slots(
+    pool: &PgPool,
+    veterinarian_id: i32,
+    date: NaiveDate,
+    slot: Duration,
+    min_length: Duration,
+) -> Result<Vec<TimeSlot>, ApiError> {
+    let Some((opens_at, from, closes_at)) = open_window(pool, date, Utc::now()).await? else {
+        return Ok(Vec::new());
+    };
+    let busy = busy_by_veterinarian(pool, &[veterinarian_id], from, closes_at).await?;
+    let taken = busy.get(&veterinarian_id).map_or(&[][..], Vec::as_slice);
+
+    Ok(free_slots(
+        taken, opens_at, from, closes_at, slot, min_length,
+    ))
+}
+
+/// Inserta una cita ya validada y le asigna sala si su procedimiento la
+/// requiere. Las visitas sin cita (`walk_in`) se crean como `Completed`; el
+/// resto como `Scheduled` con el origen indicado.
+pub(super) async fn insert_appointment(
+    tx: &mut Transaction<'_, Postgres>,
+    new_appointment: NewAppointment,
+    source: BookingSource,
+) -> Result<Appointment, ApiError> {
+    let (status, source) = if new_appointment.walk_in {
+        (AppointmentStatus::Completed, BookingSource::WalkIn)
+    } else {
+        (AppointmentStatus::Scheduled, source)
+    };
+
+    // Sala que requiere el procedimiento, reservada hasta confirmar la cita
+    let room_id = assign_room(
+        tx,
+        new_appointment.procedure_id,
+        new_appointment.start_time,
+        new_appointment.end_time,
+        None,
+    )
+    .await?;
+
+    sqlx::query_as!(
         Appointment,
         r#"
         INSERT INTO appointments (
@@ -204,9 +836,14 @@ async fn create_appointment(
             start_time,
             end_time,
             status,
-            reason
+            reason,
+            is_emergency,
+            procedure_id,
+            room_id,
+            booking_source,
+            internal_notes
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
         RETURNING
             id,
             patient_id,
@@ -215,31 +852,126 @@ async fn create_appointment(
             start_time as "start_time!: chrono::DateTime<chrono::Utc>",
             end_time as "end_time!: chrono::DateTime<chrono::Utc>",
             status as "status!: AppointmentStatus",
-            reason
+            reason,
+            is_emergency,
+            procedure_id,
+            room_id,
+            booking_source as "booking_source!: BookingSource",
+            internal_notes,
+            created_at as "created_at!: chrono::DateTime<chrono::Utc>",
+            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+            canceled_at,
+            cancellation_reason
         "#,
         new_appointment.patient_id,
         new_appointment.client_id,
         new_appointment.veterinarian_id,
         new_appointment.start_time,
         new_appointment.end_time,
-        AppointmentStatus::Scheduled as AppointmentStatus, // Estado inicial
-        new_appointment.reason
+        status as AppointmentStatus,
+        new_appointment.reason,
+        new_appointment.is_emergency,
+        new_appointment.procedure_id,
+        room_id,
+        source as BookingSource,
+        new_appointment
+            .internal_notes
+            .map(|notes| notes.trim().to_string())
     )
-    .fetch_one(pool.get_ref())
+    .fetch_one(&mut **tx)
     .await
-    .map_err(|e| {
-        tracing::error!("Error al crear cita: {}", e);
-        ApiError::InternalServerError("Error al guardar la cita".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al guardar la cita", e))
+}
+
+/// Crea una nueva cita
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "patient_id": 1,
+///   "client_id": 2,
+///   "veterinarian_id": 3,
+///   "start_time": "2023-11-01T10:00:00Z",
+///   "end_time": "2023-11-01T11:00:00Z",
+///   "reason": "Consulta de rutina"
+/// }
+/// ```
+///
+/// Una cita que empieza después del horizonte de reservas
+/// (`MAX_BOOKING_HORIZON_DAYS`, default: 365 días) responde 422.
+///
+/// La cita debe caer completa dentro del horario de atención del día
+/// (`GET /settings/clinic-hours`); si cruza el cierre o el día está cerrado
+/// responde 400. Las urgencias y las visitas sin cita se aceptan fuera de
+/// horario como advertencia: responden 400 salvo que se envíe
+/// `?override_warnings=true`, y la advertencia se incluye en `warnings`.
+///
+/// Con `"walk_in": true` se registra una visita sin cita que ya ocurrió (hasta
+/// `WALK_IN_MAX_DAYS` días atrás, default: 7): no se verifica la
+/// disponibilidad del veterinario y se crea como `Completed`.
+#[actix_web::post("")]
+async fn create_appointment(
+    claims: Option<Claims>,
+    new_appointment: web::Json<NewAppointment>,
+    override_warnings: web::Query<WarningOverride>,
+    pool: web::Data<PgPool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Creando nueva cita");
+
+    // Validar los datos de entrada
+    let new_appointment = new_appointment.into_inner();
+    new_appointment.validate()?;
+    ensure_within_booking_horizon(new_appointment.start_time)?;
+
+    // Límite del plan contratado
+    limits::ensure_within_limit(pool.get_ref(), Limit::MonthlyAppointments).await?;
+
+    // Advertencias que el usuario puede aceptar
+    let warnings = clinic::check_opening_hours(
+        pool.get_ref(),
+        new_appointment.start_time,
+        new_appointment.end_time,
+        new_appointment.is_emergency || new_appointment.walk_in,
+    )
+    .await?
+    .into_iter()
+    .collect();
+    let warnings = override_warnings.resolve(warnings)?;
+
+    // Verificar que el veterinario esté disponible en el rango de tiempo; una
+    // visita sin cita ya ocurrió, así que no se compara con la agenda
+    if !new_appointment.walk_in {
+        ensure_veterinarian_available(
+            pool.get_ref(),
+            new_appointment.veterinarian_id,
+            new_appointment.start_time,
+            new_appointment.end_time,
+            None,
+        )
+        .await?;
+    }
+
+    // Insertar la cita en la base de datos
+    let mut tx = pool.begin().await?;
+    let appointment =
+        insert_appointment(&mut tx, new_appointment, booking_source(claims.as_ref())).await?;
+    tx.commit().await?;
 
     tracing::info!("Cita creada exitosamente ID: {}", appointment.id);
+    events.publish(DomainEvent::AppointmentCreated((&appointment).into()));
+    if matches!(appointment.status, AppointmentStatus::Completed) {
+        events.publish(DomainEvent::AppointmentCompleted {
+            appointment_id: appointment.id,
+        });
+    }
 
     // Convertir a respuesta enriquecida
     let response = AppointmentResponse::from_appointment(appointment, pool.get_ref()).await?;
 
     Ok(HttpResponse::Created()
         .append_header(("Location", format!("/appointments/{}", response.id)))
-        .json(response))
+        .json(WithWarnings::new(response, warnings)))
 }
 
 /// Actualiza una cita existente (actualización parcial)
@@ -252,95 +984,139 @@ async fn create_appointment(
 ///   "reason": "Consulta de seguimiento"
 /// }
 /// ```
+///
+/// Acepta `?override_warnings=true` igual que la creación. Al pasar a
+/// `Completed` se envía al cliente la encuesta de satisfacción.
+///
+/// Solo una cita `Scheduled` cambia de estado, a `Completed`, `Canceled` o
+/// `NoShow`; cualquier otra transición responde 409, igual que cambiar el
+/// horario o el veterinario de una cita `Completed`. Al cancelar se registra
+/// `canceled_at` y se acepta `cancellation_reason`.
+///
+/// Si la cita ya terminó, cambiar el horario o el veterinario responde 409;
+/// el estado y el motivo sí se pueden modificar. Un administrador puede
+/// hacerlo de todas formas con `?allow_past_edit=true`.
 #[actix_web::put("/{id}")]
 async fn update_appointment(
-    id: web::Path<i32>,
+    claims: Claims,
+    existing_appointment: LoadedAppointment,
     update_data: web::Json<UpdateAppointment>,
+    query: web::Query<UpdateAppointmentQuery>,
     pool: web::Data<PgPool>,
+    events: web::Data<EventBus>,
 ) -> Result<HttpResponse, ApiError> {
+    let mut existing_appointment = existing_appointment.into_inner();
+    let id = existing_appointment.id;
     tracing::info!("Actualizando cita ID: {}", id);
 
     let update_data = update_data.into_inner();
     update_data.validate()?;
+    if let Some(start_time) = update_data.start_time {
+        ensure_within_booking_horizon(start_time)?;
+    }
 
     // Manejo especial para Option<Option> fields
-    let patient_id = match update_data.patient_id {
-        Some(inner) => inner, // Some(patient_id) o None (para desasociar)
-        None => None,         // Mantener valor existente
-    };
-
-    let client_id = match update_data.client_id {
-        Some(inner) => inner, // Some(client_id) o None (para desasociar)
-        None => None,         // Mantener valor existente
-    };
+    let patient_id = update_data.patient_id.flatten(); // Some(None) para desasociar
+    let client_id = update_data.client_id.flatten(); // Some(None) para desasociar
 
     let veterinarian_id = update_data.veterinarian_id;
+    let mut warnings = Vec::new();
+
+    check_appointment_update(&existing_appointment, &update_data)?;
+
+    // Una cita pasada no se mueve de horario ni de veterinario
+    if check_past_edit(
+        &existing_appointment,
+        &update_data,
+        query.allow_past_edit,
+        &claims,
+        Utc::now(),
+    )? {
+        tracing::info!("Editando horario de la cita pasada {}", id);
+    }
+
+    let (new_start_time, new_end_time) = effective_time_range(&existing_appointment, &update_data)?;
 
     // Verificar disponibilidad si se cambia el veterinario o el rango de tiempo
     if veterinarian_id.is_some()
         || update_data.start_time.is_some()
         || update_data.end_time.is_some()
     {
-        let existing_appointment = sqlx::query_as!(
-            Appointment,
-            r#"
-            SELECT
-                id,
-                patient_id,
-                client_id,
-                veterinarian_id,
-                start_time as "start_time!: chrono::DateTime<chrono::Utc>",
-                end_time as "end_time!: chrono::DateTime<chrono::Utc>",
-                status as "status!: AppointmentStatus",
-                reason
-            FROM appointments
-            WHERE id = $1
-            "#,
-            id.clone()
-        )
-        .fetch_optional(pool.get_ref())
-        .await?
-        .ok_or(ApiError::NotFound("La cita no existe".into()))?;
-
         let new_veterinarian_id = veterinarian_id.unwrap_or(existing_appointment.veterinarian_id);
-        let new_start_time = update_data
-            .start_time
-            .unwrap_or(existing_appointment.start_time);
-        let new_end_time = update_data
-            .end_time
-            .unwrap_or(existing_appointment.end_time);
-
-        let veterinarian_is_available: bool = sqlx::query_scalar!(
-            r#"
-            SELECT NOT EXISTS (
-                SELECT 1
-                FROM appointments
-                WHERE veterinarian_id = $1
-                  AND id != $2 -- Excluir la cita actual
-                  AND (
-                      ($3, $4) OVERLAPS (start_time, end_time)
-                  )
-            )
-            "#,
-            new_veterinarian_id,
-            id.clone(),
-            new_start_time,
-            new_end_time
-        )
-        .fetch_one(pool.get_ref())
-        .await?
-        .unwrap_or(true);
 
-        if !veterinarian_is_available {
-            tracing::warn!(
-                "El veterinario con ID {} no está disponible en el nuevo rango de tiempo",
-                new_veterinarian_id
+        if update_data.start_time.is_some() || update_data.end_time.is_some() {
+            let is_emergency = update_data
+                .is_emergency
+                .unwrap_or(existing_appointment.is_emergency);
+            warnings.extend(
+                clinic::check_opening_hours(
+                    pool.get_ref(),
+                    new_start_time,
+                    new_end_time,
+                    is_emergency,
+                )
+                .await?,
             );
-            return Err(ApiError::Conflict(
-                "El veterinario no está disponible en este horario".into(),
-            ));
         }
+
+        ensure_veterinarian_available(
+            pool.get_ref(),
+            new_veterinarian_id,
+            new_start_time,
+            new_end_time,
+            Some(id),
+        )
+        .await?;
+    }
+    let warnings = WarningOverride {
+        override_warnings: query.override_warnings,
     }
+    .resolve(warnings)?;
+
+    // La sala se libera al cancelar y se vuelve a buscar si cambia el
+    // procedimiento, el horario o el estado
+    let procedure_id = update_data
+        .procedure_id
+        .unwrap_or(existing_appointment.procedure_id);
+
+    // Bloquear la cita y volver a validar la transición contra el estado
+    // actual, por si otro cambio llegó después de cargarla
+    let mut tx = pool.begin().await?;
+    existing_appointment.status = sqlx::query_scalar!(
+        r#"SELECT status as "status!: AppointmentStatus" FROM appointments WHERE id = $1 FOR UPDATE"#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ApiError::NotFound("La cita no existe".into()))?;
+    check_appointment_update(&existing_appointment, &update_data)?;
+
+    let canceled = matches!(
+        update_data
+            .status
+            .as_ref()
+            .unwrap_or(&existing_appointment.status),
+        AppointmentStatus::Canceled
+    );
+    // Solo se busca otra sala si cambia el procedimiento o el horario; un
+    // cambio de estado (salvo cancelar) conserva la sala asignada
+    let rescheduled = procedure_id != existing_appointment.procedure_id
+        || new_start_time != existing_appointment.start_time
+        || new_end_time != existing_appointment.end_time;
+    let room_id = if canceled {
+        None
+    } else if rescheduled {
+        assign_room(
+            &mut tx,
+            procedure_id,
+            new_start_time,
+            new_end_time,
+            Some(id),
+        )
+        .await?
+    } else {
+        existing_appointment.room_id
+    };
 
     // Actualizar la cita en la base de datos
     let appointment = sqlx::query_as!(
@@ -353,7 +1129,16 @@ async fn update_appointment(
             start_time = CASE WHEN $4::TIMESTAMPTZ IS NOT NULL THEN $4 ELSE start_time END,
             end_time = CASE WHEN $5::TIMESTAMPTZ IS NOT NULL THEN $5 ELSE end_time END,
             status = CASE WHEN $6::appointment_status IS NOT NULL THEN $6 ELSE status END,
-            reason = CASE WHEN $7::TEXT IS NOT NULL THEN $7 ELSE reason END
+            reason = CASE WHEN $7::TEXT IS NOT NULL THEN $7 ELSE reason END,
+            is_emergency = COALESCE($9, is_emergency),
+            procedure_id = $10,
+            room_id = $11,
+            internal_notes = CASE WHEN $12 THEN $13 ELSE internal_notes END,
+            canceled_at = CASE
+                WHEN $6::appointment_status = 'canceled' AND status IS DISTINCT FROM 'canceled' THEN NOW()
+                ELSE canceled_at
+            END,
+            cancellation_reason = COALESCE($14, cancellation_reason)
         WHERE id = $8
         RETURNING
             id,
@@ -363,7 +1148,16 @@ async fn update_appointment(
             start_time as "start_time!: chrono::DateTime<chrono::Utc>",
             end_time as "end_time!: chrono::DateTime<chrono::Utc>",
             status as "status!: AppointmentStatus",
-            reason
+            reason,
+            is_emergency,
+            procedure_id,
+            room_id,
+            booking_source as "booking_source!: BookingSource",
+            internal_notes,
+            created_at as "created_at!: chrono::DateTime<chrono::Utc>",
+            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+            canceled_at,
+            cancellation_reason
         "#,
         patient_id,
         client_id,
@@ -372,20 +1166,53 @@ async fn update_appointment(
         update_data.end_time,
         update_data.status as Option<AppointmentStatus>,
         update_data.reason,
-        id.clone()
+        id,
+        update_data.is_emergency,
+        procedure_id,
+        room_id,
+        update_data.internal_notes.is_some(),
+        update_data
+            .internal_notes
+            .flatten()
+            .map(|notes| notes.trim().to_string()),
+        update_data
+            .cancellation_reason
+            .map(|reason| reason.trim().to_string())
     )
-    .fetch_optional(pool.get_ref())
+    .fetch_optional(&mut *tx)
     .await
-    .map_err(|e| {
-        tracing::error!("Error al actualizar cita: {}", e);
-        ApiError::InternalServerError("Error al actualizar la cita".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al actualizar la cita", e))?;
+
+    tx.commit().await?;
 
     match appointment {
         Some(appointment) => {
             tracing::info!("Cita {} actualizada exitosamente", appointment.id);
-            Ok(HttpResponse::Ok()
-                .json(AppointmentResponse::from_appointment(appointment, pool.get_ref()).await?))
+            let snapshot = AppointmentSnapshot::from(&appointment);
+            events.publish(
+                if matches!(appointment.status, AppointmentStatus::Canceled)
+                    && !matches!(existing_appointment.status, AppointmentStatus::Canceled)
+                {
+                    DomainEvent::AppointmentCanceled(snapshot)
+                } else if appointment.start_time != existing_appointment.start_time
+                    || appointment.end_time != existing_appointment.end_time
+                    || appointment.veterinarian_id != existing_appointment.veterinarian_id
+                {
+                    DomainEvent::AppointmentRescheduled(snapshot)
+                } else {
+                    DomainEvent::AppointmentUpdated(snapshot)
+                },
+            );
+            if matches!(appointment.status, AppointmentStatus::Completed)
+                && !matches!(existing_appointment.status, AppointmentStatus::Completed)
+            {
+                events.publish(DomainEvent::AppointmentCompleted {
+                    appointment_id: appointment.id,
+                });
+            }
+            let response =
+                AppointmentResponse::from_appointment(appointment, pool.get_ref()).await?;
+            Ok(HttpResponse::Ok().json(WithWarnings::new(response, warnings)))
         }
         None => {
             tracing::warn!("Cita {} no encontrada", &id);
@@ -394,66 +1221,397 @@ async fn update_appointment(
     }
 }
 
-/// Elimina una cita existente
-#[actix_web::delete("/{id}")]
-async fn delete_appointment(
-    id: web::Path<i32>,
+/// Cambia solo el estado de una cita
+///
+/// Aplica las mismas transiciones que la actualización completa: una cita
+/// `Scheduled` pasa a `Completed`, `Canceled` o `NoShow` y las demás son
+/// finales (409). `note` se agrega en una línea nueva al final del motivo,
+/// salvo al cancelar, que se guarda como `cancellation_reason`. Al cancelar
+/// se libera la sala y se registra `canceled_at`.
+///
+/// Un veterinario solo cambia el estado de sus propias citas (403, vía
+/// `LoadedAppointment`).
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "status": "NoShow",
+///   "note": "No contestó las llamadas de confirmación"
+/// }
+/// ```
+#[actix_web::patch("/{id}/status")]
+async fn update_appointment_status(
+    appointment: LoadedAppointment,
+    change: web::Json<UpdateAppointmentStatus>,
     pool: web::Data<PgPool>,
+    events: web::Data<EventBus>,
 ) -> Result<HttpResponse, ApiError> {
-    tracing::info!("Eliminando cita ID: {}", id);
+    let id = appointment.id;
+    let change = change.into_inner();
+    change.validate()?;
+    tracing::info!(
+        "Cambiando el estado de la cita {} a {:?}",
+        id,
+        change.status
+    );
 
-    // Verificar si la cita existe
-    let exists: bool =
-        sqlx::query_scalar("SELECT EXISTS( SELECT 1 FROM appointments WHERE id = $1 )")
-            .bind(id.clone())
-            .fetch_one(pool.get_ref())
-            .await?;
+    // Bloquear la cita para que dos cambios simultáneos no salten la transición
+    let mut tx = pool.begin().await?;
+    let previous_status = sqlx::query_scalar!(
+        r#"SELECT status as "status!: AppointmentStatus" FROM appointments WHERE id = $1 FOR UPDATE"#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ApiError::NotFound("La cita no existe".into()))?;
 
-    if !exists {
-        tracing::warn!("Intento de eliminar cita inexistente ID: {}", id);
-        return Err(ApiError::NotFound("La cita no existe".into()));
+    if !is_valid_status_transition(&previous_status, &change.status) {
+        return Err(ApiError::Conflict(format!(
+            "Una cita {:?} no puede pasar a {:?}",
+            previous_status, change.status
+        )));
     }
 
-    // Verificar restricciones de estado (opcional)
-    let status: Option<String> = sqlx::query_scalar(
+    let appointment = sqlx::query_as!(
+        Appointment,
         r#"
-        SELECT status
-        FROM appointments
+        UPDATE appointments SET
+            status = $2::appointment_status,
+            reason = CASE
+                WHEN $3::TEXT IS NULL OR $2::appointment_status = 'canceled' THEN reason
+                ELSE reason || E'\n' || $3
+            END,
+            room_id = CASE WHEN $2::appointment_status = 'canceled' THEN NULL ELSE room_id END,
+            canceled_at = CASE
+                WHEN $2::appointment_status = 'canceled' AND status IS DISTINCT FROM 'canceled' THEN NOW()
+                ELSE canceled_at
+            END,
+            cancellation_reason = CASE
+                WHEN $2::appointment_status = 'canceled' THEN COALESCE($3, cancellation_reason)
+                ELSE cancellation_reason
+            END
         WHERE id = $1
+        RETURNING
+            id,
+            patient_id,
+            client_id,
+            veterinarian_id,
+            start_time as "start_time!: chrono::DateTime<chrono::Utc>",
+            end_time as "end_time!: chrono::DateTime<chrono::Utc>",
+            status as "status!: AppointmentStatus",
+            reason,
+            is_emergency,
+            procedure_id,
+            room_id,
+            booking_source as "booking_source!: BookingSource",
+            internal_notes,
+            created_at as "created_at!: chrono::DateTime<chrono::Utc>",
+            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+            canceled_at,
+            cancellation_reason
         "#,
+        id,
+        change.status.clone() as AppointmentStatus,
+        change.note.as_deref().map(str::trim)
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al cambiar el estado de la cita", e))?;
+
+    tx.commit().await?;
+
+    let snapshot = AppointmentSnapshot::from(&appointment);
+    match (&previous_status, &appointment.status) {
+        (AppointmentStatus::Scheduled, AppointmentStatus::Canceled) => {
+            events.publish(DomainEvent::AppointmentCanceled(snapshot))
+        }
+        (AppointmentStatus::Scheduled, AppointmentStatus::Completed) => {
+            events.publish(DomainEvent::AppointmentUpdated(snapshot));
+            events.publish(DomainEvent::AppointmentCompleted {
+                appointment_id: appointment.id,
+            });
+        }
+        _ => events.publish(DomainEvent::AppointmentUpdated(snapshot)),
+    }
+
+    let response = AppointmentResponse::from_appointment(appointment, pool.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Reasigna las citas programadas de un veterinario a otro (solo admin)
+///
+/// Mueve en una transacción las citas `Scheduled` del veterinario de origen
+/// que empiezan dentro del rango y todavía no ocurren. Las que se traslapan
+/// con la agenda del veterinario de destino se omiten y se reportan con el
+/// motivo; cada cita movida publica `AppointmentReassigned` y
+/// `AppointmentRescheduled`.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "from_veterinarian_id": 3,
+///   "to_veterinarian_id": 5,
+///   "start_date": "2024-06-01T00:00:00Z",
+///   "end_date": "2024-07-01T00:00:00Z"
+/// }
+/// ```
+#[actix_web::post("/reassign")]
+async fn reassign_appointments(
+    claims: Claims,
+    reassignment: web::Json<ReassignAppointments>,
+    pool: web::Data<PgPool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    let reassignment = reassignment.into_inner();
+    reassignment.validate()?;
+    ensure_veterinarian(pool.get_ref(), reassignment.to_veterinarian_id).await?;
+
+    tracing::info!(
+        "Reasignando citas del veterinario {} al {} entre {} y {}",
+        reassignment.from_veterinarian_id,
+        reassignment.to_veterinarian_id,
+        reassignment.start_date,
+        reassignment.end_date
+    );
+
+    let mut tx = pool.begin().await?;
+    let candidates = sqlx::query!(
+        r#"
+        SELECT id, start_time, end_time as "end_time!"
+        FROM appointments
+        WHERE veterinarian_id = $1
+          AND status = 'scheduled'
+          AND start_time >= GREATEST($2, NOW())
+          AND start_time < $3
+        ORDER BY start_time, id
+        FOR UPDATE
+        "#,
+        reassignment.from_veterinarian_id,
+        reassignment.start_date,
+        reassignment.end_date
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut response = ReassignmentResponse {
+        moved_ids: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let mut moved = Vec::new();
+    for candidate in candidates {
+        // Incluye las citas ya movidas en esta misma transacción
+        let conflict: Option<i32> = sqlx::query_scalar!(
+            r#"
+            SELECT id
+            FROM appointments
+            WHERE veterinarian_id = $1
+              AND status NOT IN ('canceled', 'no_show')
+              AND start_time < $2
+              AND end_time > $3
+            ORDER BY start_time, id
+            LIMIT 1
+            "#,
+            reassignment.to_veterinarian_id,
+            candidate.end_time,
+            candidate.start_time
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(conflict) = conflict {
+            response.skipped.push(SkippedReassignment {
+                appointment_id: candidate.id,
+                reason: format!("Traslape con la cita {conflict} del veterinario de destino"),
+            });
+            continue;
+        }
+
+        sqlx::query!(
+            "UPDATE appointments SET veterinarian_id = $1 WHERE id = $2",
+            reassignment.to_veterinarian_id,
+            candidate.id
+        )
+        .execute(&mut *tx)
+        .await?;
+        response.moved_ids.push(candidate.id);
+        moved.push(AppointmentSnapshot {
+            appointment_id: candidate.id,
+            veterinarian_id: reassignment.to_veterinarian_id,
+            status: AppointmentStatus::Scheduled,
+            start_time: candidate.start_time,
+        });
+    }
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "Reasignación terminada: {} citas movidas, {} omitidas",
+        response.moved_ids.len(),
+        response.skipped.len()
+    );
+    for snapshot in moved {
+        events.publish(DomainEvent::AppointmentReassigned {
+            appointment_id: snapshot.appointment_id,
+            from_veterinarian_id: reassignment.from_veterinarian_id,
+            to_veterinarian_id: reassignment.to_veterinarian_id,
+        });
+        events.publish(DomainEvent::AppointmentRescheduled(snapshot));
+    }
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Elimina una cita existente
+#[actix_web::delete("/{id}")]
+async fn delete_appointment(
+    appointment: LoadedAppointment,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let id = appointment.id;
+    tracing::info!("Eliminando cita ID: {}", id);
+
+    // Una sola sentencia: el estado que decide el 409 es el de la fila que
+    // se intenta borrar, sin ventana para que cambie entre consultas. El
+    // bloqueo hace que un DELETE simultáneo espere y responda 404, no 409.
+    let result = sqlx::query!(
+        r#"
+        WITH target AS (
+            SELECT id, status::TEXT as status
+            FROM appointments
+            WHERE id = $1
+            FOR UPDATE
+        ), deleted AS (
+            DELETE FROM appointments a
+            USING target t
+            WHERE a.id = t.id
+            AND a.status NOT IN ('completed', 'canceled')
+            RETURNING a.id
+        )
+        SELECT
+            t.status as "status!",
+            EXISTS (SELECT 1 FROM deleted) as "deleted!"
+        FROM target t
+        "#,
+        id
     )
-    .bind(id.clone())
     .fetch_optional(pool.get_ref())
     .await?
-    .flatten();
-
-    if let Some(status) = status {
-        if status == "completed" || status == "canceled" {
-            return Err(ApiError::Conflict(format!(
-                "No se puede eliminar una cita con estado '{}'",
-                status
-            )));
-        }
+    .ok_or(ApiError::NotFound("La cita no existe".into()))?;
+
+    if !result.deleted {
+        return Err(ApiError::Conflict(format!(
+            "No se puede eliminar una cita con estado '{}'",
+            result.status
+        )));
     }
 
-    // Eliminar la cita
-    let rows_affected = sqlx::query!(
+    tracing::info!("Cita ID {} eliminada exitosamente", id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Agrega una nota de seguimiento a la cita, sin modificar el motivo original.
+/// El autor es el usuario del token.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "content": "El dueño llegará 15 minutos tarde"
+/// }
+/// ```
+#[actix_web::post("/{id}/notes")]
+async fn create_appointment_note(
+    claims: Claims,
+    id: PathId,
+    new_note: web::Json<NewAppointmentNote>,
+    pool: web::Data<PgPool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Agregando nota a la cita ID: {}", id);
+
+    ensure_exists(pool.get_ref(), "appointments", *id, "La cita no existe").await?;
+
+    let new_note = new_note.into_inner();
+    new_note.validate()?;
+
+    let note_id = sqlx::query_scalar!(
         r#"
-        DELETE FROM appointments
-        WHERE id = $1
+        INSERT INTO appointment_notes (appointment_id, author_id, content)
+        VALUES ($1, $2, $3)
+        RETURNING id
         "#,
-        id.clone()
+        *id,
+        claims.sub,
+        new_note.content.trim()
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al guardar la nota", e))?;
+
+    events.publish(DomainEvent::AppointmentNoteAdded {
+        appointment_id: *id,
+        note_id,
+        author_id: claims.sub,
+    });
+
+    let response = AppointmentNoteResponse::fetch(pool.get_ref(), *id, note_id).await?;
+
+    Ok(HttpResponse::Created()
+        .append_header((
+            "Location",
+            format!("/appointments/{}/notes/{}", *id, note_id),
+        ))
+        .json(response))
+}
+
+/// Lista las notas de la cita, de la más reciente a la más antigua
+///
+/// # Parámetros (opcionales vía query string)
+/// - `limit`: Máximo de resultados (default: 50)
+/// - `offset`: Desplazamiento (default: 0)
+#[actix_web::get("/{id}/notes")]
+async fn list_appointment_notes(
+    id: PathId,
+    filters: web::Query<AppointmentNoteFilter>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Listando notas de la cita ID: {}", id);
+
+    ensure_exists(pool.get_ref(), "appointments", *id, "La cita no existe").await?;
+
+    let notes = AppointmentNoteResponse::for_appointment(pool.get_ref(), *id, &filters)
+        .await
+        .map_err(|e| ApiError::internal("Error al obtener las notas", e))?;
+
+    Ok(HttpResponse::Ok().json(notes))
+}
+
+/// Elimina una nota de la cita (solo administradores)
+#[actix_web::delete("/{id}/notes/{note_id}")]
+async fn delete_appointment_note(
+    req: HttpRequest,
+    claims: Claims,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    let note_id = PathId::from_segment(&req, "note_id")?;
+    tracing::info!("Eliminando nota {} de la cita {}", note_id, id);
+
+    let rows_affected = sqlx::query!(
+        "DELETE FROM appointment_notes WHERE id = $1 AND appointment_id = $2",
+        *note_id,
+        *id
     )
     .execute(pool.get_ref())
     .await?
     .rows_affected();
 
     if rows_affected == 0 {
-        tracing::warn!("Cita ID {} no encontrada después de intentar eliminar", id);
-        return Err(ApiError::NotFound("La cita no existe".into()));
+        return Err(ApiError::NotFound("La nota no existe".into()));
     }
 
-    tracing::info!("Cita ID {} eliminada exitosamente", id);
     Ok(HttpResponse::NoContent().finish())
 }
 
@@ -462,9 +1620,274 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/appointments")
             .service(list_appointments)
+            .service(get_calendar) // Antes de "/{id}"
+            .service(get_calendar_days)
+            .service(get_next_available)
             .service(get_appointment)
             .service(create_appointment)
             .service(update_appointment)
-            .service(delete_appointment), // Agrega más servicios aquí...
+            .service(update_appointment_status)
+            .service(reassign_appointments)
+            .service(delete_appointment)
+            .service(create_appointment_note)
+            .service(list_appointment_notes)
+            .service(delete_appointment_note), // Agrega más servicios aquí...
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{QueryCounter, as_user, claims, concurrent_deletes};
+    use actix_web::{App, test};
+    use chrono::Datelike;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn list_loads_names_and_notes_in_one_query(
+        pool_options: PgPoolOptions,
+        connect_options: PgConnectOptions,
+    ) {
+        // Una sola conexión para que la caché de tipos del driver sea la misma
+        // en todas las peticiones
+        let pool = pool_options
+            .max_connections(1)
+            .connect_with(connect_options)
+            .await
+            .unwrap();
+        // Muchas citas con más notas de las que se muestran
+        sqlx::query(
+            r#"
+            WITH new_appointments AS (
+                INSERT INTO appointments (patient_id, veterinarian_id, start_time, end_time, reason)
+                SELECT 1, 1, '2030-01-01'::timestamptz + g * INTERVAL '1 day',
+                       '2030-01-01'::timestamptz + g * INTERVAL '1 day' + INTERVAL '30 minutes',
+                       'Revisión ' || g
+                FROM generate_series(1, 150) g
+                RETURNING id
+            )
+            INSERT INTO appointment_notes (appointment_id, author_id, content, created_at)
+            SELECT id, 3, 'Nota ' || n, NOW() + n * INTERVAL '1 minute'
+            FROM new_appointments, generate_series(1, 5) n
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(EventBus::default()))
+                .configure(config),
+        )
+        .await;
+
+        let list = |uri: &str| {
+            let request = test::TestRequest::get().uri(uri).to_request();
+            test::call_service(&app, as_user(request, claims(3, UserRole::Assistant)))
+        };
+        // La primera consulta de la conexión también carga los tipos enum en
+        // la caché del driver; se cuentan las siguientes
+        assert_eq!(
+            list("/appointments?envelope=false&limit=1").await.status(),
+            200
+        );
+
+        let (queries, _guard) = QueryCounter::install();
+        let response = list("/appointments?envelope=false&limit=400").await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(queries.count(), 1);
+
+        let items: Vec<serde_json::Value> = test::read_body_json(response).await;
+        assert!(items.len() >= 150);
+        let notes = items
+            .iter()
+            .find(|item| item["reason"] == "Revisión 150")
+            .map(|item| item["latest_notes"].as_array().unwrap().clone())
+            .unwrap();
+        let contents: Vec<_> = notes.iter().map(|note| note["content"].clone()).collect();
+        assert_eq!(contents, ["Nota 5", "Nota 4", "Nota 3"]);
+        assert_eq!(notes[0]["author_id"], 3);
+        assert!(notes[0]["author_name"].is_string());
+
+        // Con el sobre solo se agrega la consulta del total, sin importar cuántas citas haya
+        let response = list("/appointments?limit=400").await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(queries.count(), 3);
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn concurrent_deletes_remove_the_appointment_once(pool: PgPool) {
+        let id: i32 = sqlx::query_scalar(
+            "INSERT INTO appointments (patient_id, veterinarian_id, start_time, end_time, reason)
+             VALUES (1, 1, '2030-01-01 10:00Z', '2030-01-01 10:30Z', 'Vacuna') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let uri = format!("/api/appointments/{id}");
+        let statuses = concurrent_deletes(pool, &uri, claims(3, UserRole::Assistant)).await;
+        assert_eq!(statuses, [204, 404]);
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn canceled_appointment_frees_its_slot(pool: PgPool) {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(EventBus::default()))
+                .configure(config),
+        )
+        .await;
+        // Lejos de las citas de ejemplo y dentro del horario: lunes a sábado, 10:00
+        let mut date = clinic::today() + Duration::days(200);
+        if date.weekday() == chrono::Weekday::Sun {
+            date += Duration::days(1);
+        }
+        let start_time =
+            clinic::local_instant(date, chrono::NaiveTime::from_hms_opt(10, 0, 0).unwrap());
+        let book = || {
+            let request = test::TestRequest::post()
+                .uri("/appointments")
+                .set_json(serde_json::json!({
+                    "patient_id": 1,
+                    "veterinarian_id": 1,
+                    "start_time": start_time,
+                    "end_time": start_time + Duration::minutes(30),
+                    "reason": "Consulta general"
+                }))
+                .to_request();
+            test::call_service(&app, as_user(request, claims(3, UserRole::Assistant)))
+        };
+
+        let response = book().await;
+        assert_eq!(response.status(), 201);
+        let created: serde_json::Value = test::read_body_json(response).await;
+        let id = created["id"].as_i64().unwrap();
+        assert_eq!(book().await.status(), 409);
+
+        let request = test::TestRequest::put()
+            .uri(&format!("/appointments/{id}"))
+            .set_json(serde_json::json!({
+                "status": "Canceled",
+                "cancellation_reason": "El dueño no puede asistir"
+            }))
+            .to_request();
+        let response =
+            test::call_service(&app, as_user(request, claims(3, UserRole::Assistant))).await;
+        assert_eq!(response.status(), 200);
+
+        assert_eq!(book().await.status(), 201);
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn room_is_kept_unless_rescheduled_or_canceled(pool: PgPool) {
+        let (appointment_id, room_id): (i32, i32) = sqlx::query_as(
+            r#"
+            WITH room AS (
+                INSERT INTO rooms (name, room_type) VALUES ('Consultorio 9', 'consultation')
+                RETURNING id
+            )
+            INSERT INTO appointments
+                (patient_id, veterinarian_id, start_time, end_time, reason, room_id)
+            SELECT 1, 1, NOW() + INTERVAL '200 days', NOW() + INTERVAL '200 days 30 minutes',
+                   'Consulta general', room.id
+            FROM room
+            RETURNING id, room_id
+            "#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(EventBus::default()))
+                .configure(config),
+        )
+        .await;
+        let update = |body: serde_json::Value| {
+            let request = test::TestRequest::put()
+                .uri(&format!("/appointments/{appointment_id}"))
+                .set_json(body)
+                .to_request();
+            test::call_service(&app, as_user(request, claims(3, UserRole::Assistant)))
+        };
+        let stored_room = || {
+            sqlx::query_scalar!(
+                "SELECT room_id FROM appointments WHERE id = $1",
+                appointment_id
+            )
+            .fetch_one(&pool)
+        };
+
+        // Cambios que no mueven la cita no tocan la sala
+        let response = update(serde_json::json!({ "reason": "Consulta de seguimiento" })).await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(stored_room().await.unwrap(), Some(room_id));
+
+        let response = update(serde_json::json!({ "status": "Completed" })).await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(stored_room().await.unwrap(), Some(room_id));
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn canceling_releases_the_room(pool: PgPool) {
+        let appointment_id: i32 = sqlx::query_scalar(
+            r#"
+            WITH room AS (
+                INSERT INTO rooms (name, room_type) VALUES ('Consultorio 9', 'consultation')
+                RETURNING id
+            )
+            INSERT INTO appointments
+                (patient_id, veterinarian_id, start_time, end_time, reason, room_id)
+            SELECT 1, 1, NOW() + INTERVAL '200 days', NOW() + INTERVAL '200 days 30 minutes',
+                   'Consulta general', room.id
+            FROM room
+            RETURNING id
+            "#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(EventBus::default()))
+                .configure(config),
+        )
+        .await;
+
+        let request = test::TestRequest::put()
+            .uri(&format!("/appointments/{appointment_id}"))
+            .set_json(serde_json::json!({ "status": "Canceled" }))
+            .to_request();
+        let response =
+            test::call_service(&app, as_user(request, claims(3, UserRole::Assistant))).await;
+        assert_eq!(response.status(), 200);
+        let room_id = sqlx::query_scalar!(
+            "SELECT room_id FROM appointments WHERE id = $1",
+            appointment_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(room_id, None);
+    }
+}
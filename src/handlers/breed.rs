@@ -1,9 +1,15 @@
+use crate::deletion::{self, Resource};
 use crate::errors::ApiError;
-use crate::models::breed::{Breed, BreedResponse, NewBreed};
+use crate::extractors::PathId;
+use crate::handlers::species::ensure_species_exist;
+use crate::handlers::{
+    catalog_cache_headers, catalog_last_modified, catalog_not_modified, ensure_exists,
+    record_catalog_deletion,
+};
+use crate::models::breed::{Breed, BreedResponse, BreedUpsertResponse, NewBreed, PaginationParams};
 use crate::models::enums::AnimalSpecies;
 
-use actix_web::{HttpResponse, web};
-use serde::Deserialize;
+use actix_web::{HttpRequest, HttpResponse, web};
 use sqlx::PgPool;
 use validator::Validate;
 
@@ -26,6 +32,7 @@ async fn create_breed(
     // Validar los datos de entrada
     let new_breed = new_breed.into_inner();
     new_breed.validate()?;
+    ensure_species_exist(pool.get_ref(), [&new_breed.species]).await?;
 
     // Verificar si la combinación de especie y nombre ya existe
     let exists: bool = sqlx::query_scalar(
@@ -64,9 +71,11 @@ async fn create_breed(
     )
     .fetch_one(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al crear raza: {}", e);
-        ApiError::InternalServerError("Error al guardar la raza".into())
+    .map_err(|e| match e {
+        sqlx::Error::Database(err) if err.is_unique_violation() => {
+            ApiError::Conflict("La raza ya existe".into())
+        }
+        e => ApiError::internal("Error al guardar la raza", e),
     })?;
 
     tracing::info!("Raza creada exitosamente ID: {}", breed.id);
@@ -76,11 +85,64 @@ async fn create_breed(
         .json(BreedResponse::from(breed)))
 }
 
-/// Parámetros de paginación
-#[derive(Debug, Deserialize)]
-pub struct PaginationParams {
-    limit: Option<i64>,
-    offset: Option<i64>,
+/// Crea la raza si no existe o devuelve la existente, sin consultar antes.
+/// La especie y el nombre (sin distinguir mayúsculas) identifican la raza; la
+/// existente conserva su nombre tal como está.
+///
+/// Responde 201 con `created: true` o 200 con `created: false`.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "species": "Dog",
+///   "name": "Labrador Retriever"
+/// }
+/// ```
+#[actix_web::put("/upsert")]
+async fn upsert_breed(
+    new_breed: web::Json<NewBreed>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let new_breed = new_breed.into_inner();
+    new_breed.validate()?;
+    ensure_species_exist(pool.get_ref(), [&new_breed.species]).await?;
+
+    // El UPDATE sin cambios solo sirve para que RETURNING devuelva la fila
+    // existente; xmax = 0 indica que la fila se acaba de insertar
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO breeds (species, name)
+        VALUES ($1, $2)
+        ON CONFLICT (species, LOWER(name)) DO UPDATE SET name = breeds.name
+        RETURNING
+            id,
+            species as "species!: AnimalSpecies",
+            name,
+            (xmax = 0) as "created!"
+        "#,
+        new_breed.species as AnimalSpecies,
+        new_breed.name.trim()
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al guardar la raza", e))?;
+
+    tracing::info!("Upsert de raza ID: {} (creada: {})", row.id, row.created);
+
+    let response = BreedUpsertResponse {
+        breed: BreedResponse::from(Breed {
+            id: row.id,
+            species: row.species,
+            name: row.name,
+        }),
+        created: row.created,
+    };
+    if !row.created {
+        return Ok(HttpResponse::Ok().json(response));
+    }
+    Ok(HttpResponse::Created()
+        .append_header(("Location", format!("/breeds/{}", row.id)))
+        .json(response))
 }
 
 /// Lista todas las razas con paginación básica
@@ -89,15 +151,24 @@ pub struct PaginationParams {
 /// - `limit`: Límite de resultados (default: 50)
 /// - `offset`: Desplazamiento (default: 0)
 ///
+/// Responde `Last-Modified` y honra `If-Modified-Since` con 304 si el
+/// catálogo no cambió.
+///
 /// # Ejemplo
 /// GET /breeds?limit=10&offset=20
 #[actix_web::get("")]
 async fn list_breeds(
+    req: HttpRequest,
     query: web::Query<PaginationParams>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Listando razas con parámetros: {:?}", query);
 
+    let last_modified = catalog_last_modified(pool.get_ref(), "breeds").await?;
+    if let Some(not_modified) = catalog_not_modified(&req, last_modified) {
+        return Ok(not_modified);
+    }
+
     let breeds = sqlx::query_as!(
         Breed,
         r#"
@@ -111,13 +182,10 @@ async fn list_breeds(
     )
     .fetch_all(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al listar razas: {}", e);
-        ApiError::InternalServerError("Error al obtener las razas".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al obtener las razas", e))?;
 
     let response: Vec<BreedResponse> = breeds.into_iter().map(BreedResponse::from).collect();
-    Ok(HttpResponse::Ok().json(response))
+    Ok(catalog_cache_headers(&mut HttpResponse::Ok(), last_modified).json(response))
 }
 
 /// Obtiene una raza por ID
@@ -125,7 +193,7 @@ async fn list_breeds(
 /// # Ejemplo
 /// GET /breeds/1
 #[actix_web::get("/{id}")]
-async fn get_breed(id: web::Path<i32>, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+async fn get_breed(id: PathId, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
     tracing::info!("Obteniendo raza ID: {}", id);
 
     let breed = sqlx::query_as!(
@@ -135,7 +203,7 @@ async fn get_breed(id: web::Path<i32>, pool: web::Data<PgPool>) -> Result<HttpRe
         FROM breeds
         WHERE id = $1
         "#,
-        id.clone()
+        *id
     )
     .fetch_optional(pool.get_ref())
     .await?
@@ -154,33 +222,18 @@ async fn get_breed(id: web::Path<i32>, pool: web::Data<PgPool>) -> Result<HttpRe
 /// ```
 #[actix_web::put("/{id}")]
 async fn update_breed(
-    id: web::Path<i32>,
+    id: PathId,
     updated_breed: web::Json<NewBreed>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Actualizando raza ID: {}", id);
 
-    let updated_breed = updated_breed.into_inner();
-    updated_breed.validate()?;
-
     // Verificar si la raza existe
-    let exists: bool = sqlx::query_scalar(
-        r#"
-        SELECT EXISTS (
-            SELECT 1
-            FROM breeds
-            WHERE id = $1
-        )
-        "#,
-    )
-    .bind(id.clone())
-    .fetch_one(pool.get_ref())
-    .await?;
+    ensure_exists(pool.get_ref(), "breeds", *id, "La raza no existe").await?;
 
-    if !exists {
-        tracing::warn!("Intento de actualizar raza inexistente ID: {}", id);
-        return Err(ApiError::NotFound("La raza no existe".into()));
-    }
+    let updated_breed = updated_breed.into_inner();
+    updated_breed.validate()?;
+    ensure_species_exist(pool.get_ref(), [&updated_breed.species]).await?;
 
     // Verificar si la combinación de especie y nombre ya existe
     let duplicate_exists: bool = sqlx::query_scalar(
@@ -194,7 +247,7 @@ async fn update_breed(
     )
     .bind(&updated_breed.species as &AnimalSpecies)
     .bind(updated_breed.name.trim())
-    .bind(id.clone())
+    .bind(*id)
     .fetch_one(pool.get_ref())
     .await?;
 
@@ -212,91 +265,71 @@ async fn update_breed(
         Breed,
         r#"
         UPDATE breeds
-        SET species = $1, name = $2
+        SET species = $1, name = $2, updated_at = NOW()
         WHERE id = $3
         RETURNING id, species as "species!: AnimalSpecies", name
         "#,
         updated_breed.species as AnimalSpecies,
         updated_breed.name.trim(),
-        id.clone()
+        *id
     )
     .fetch_one(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al actualizar raza: {}", e);
-        ApiError::InternalServerError("Error al actualizar la raza".into())
+    .map_err(|e| match e {
+        sqlx::Error::Database(err) if err.is_unique_violation() => {
+            ApiError::Conflict("La raza ya existe".into())
+        }
+        e => ApiError::internal("Error al actualizar la raza", e),
     })?;
 
     Ok(HttpResponse::Ok().json(BreedResponse::from(breed)))
 }
 
+/// Filas que dependen de la raza y qué les pasaría al eliminarla
+///
+/// # Ejemplo
+/// GET /breeds/2/delete-preview
+#[actix_web::get("/{id}/delete-preview")]
+async fn delete_breed_preview(
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let preview = deletion::preview(pool.get_ref(), Resource::Breed, *id).await?;
+    Ok(HttpResponse::Ok().json(preview))
+}
+
 /// Elimina una raza existente
 ///
 /// # Ejemplo
 /// DELETE /breeds/1
 #[actix_web::delete("/{id}")]
-async fn delete_breed(
-    id: web::Path<i32>,
-    pool: web::Data<PgPool>,
-) -> Result<HttpResponse, ApiError> {
+async fn delete_breed(id: PathId, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
     tracing::info!("Eliminando raza ID: {}", id);
 
-    // Verificar si la raza existe
-    let exists: bool = sqlx::query_scalar(
-        r#"
-        SELECT EXISTS (
-            SELECT 1
-            FROM breeds
-            WHERE id = $1
-        )
-        "#,
-    )
-    .bind(id.clone())
-    .fetch_one(pool.get_ref())
-    .await?;
-
-    if !exists {
-        tracing::warn!("Intento de eliminar raza inexistente ID: {}", id);
-        return Err(ApiError::NotFound("La raza no existe".into()));
-    }
-
-    // Verificar dependencias (por ejemplo, pacientes asociados)
-    let has_deps: bool = sqlx::query_scalar(
-        r#"
-        SELECT EXISTS (
-            SELECT 1
-            FROM patients
-            WHERE breed = $1
-        )
-        "#,
-    )
-    .bind(id.clone())
-    .fetch_one(pool.get_ref())
-    .await?;
-
-    if has_deps {
-        return Err(ApiError::Conflict(
-            "No se puede eliminar, la raza tiene mascotas registradas".into(),
-        ));
-    }
+    // Verificar que exista y que ninguna dependencia lo impida
+    deletion::ensure_deletable(pool.get_ref(), Resource::Breed, *id).await?;
 
     // Eliminar la raza
-    let rows_affected = sqlx::query!(
+    let mut tx = pool.begin().await?;
+
+    let rows_affected = sqlx::query(
         r#"
         DELETE FROM breeds
         WHERE id = $1
         "#,
-        id.clone()
     )
-    .execute(pool.get_ref())
+    .bind(*id)
+    .execute(&mut *tx)
     .await?
     .rows_affected();
 
     if rows_affected == 0 {
-        tracing::warn!("Raza ID {} no encontrada después de intentar eliminar", id);
         return Err(ApiError::NotFound("La raza no existe".into()));
     }
 
+    record_catalog_deletion(&mut *tx, "breeds").await?;
+    tx.commit().await?;
+
     tracing::info!("Raza ID {} eliminada exitosamente", id);
     Ok(HttpResponse::NoContent().finish())
 }
@@ -306,9 +339,36 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/breeds")
             .service(create_breed)
+            .service(upsert_breed) // Antes de "/{id}"
             .service(list_breeds)
             .service(get_breed)
             .service(update_breed)
+            .service(delete_breed_preview)
             .service(delete_breed),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::enums::UserRole;
+    use crate::testing::{claims, concurrent_deletes};
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn concurrent_deletes_remove_the_breed_once(pool: PgPool) {
+        // Raza nueva, sin pacientes que impidan eliminarla
+        let id: i32 = sqlx::query_scalar(
+            "INSERT INTO breeds (species, name) VALUES ('dog', 'Xoloitzcuintle') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let uri = format!("/api/breeds/{id}");
+        let statuses = concurrent_deletes(pool, &uri, claims(4, UserRole::Admin)).await;
+        assert_eq!(statuses, [204, 404]);
+    }
+}
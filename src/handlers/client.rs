@@ -1,7 +1,18 @@
+use crate::deletion::{self, Resource};
 use crate::errors::ApiError;
-use crate::models::client::{Client, ClientFilter, ClientResponse, NewClient, UpdateClient};
-use actix_web::{HttpResponse, web};
-use sqlx::PgPool;
+use crate::extractors::PathId;
+use crate::handlers::ensure_exists;
+use crate::models::client::{
+    Client, ClientFilter, ClientResponse, NewClient, StatementFormat, StatementQuery, UpdateClient,
+};
+use crate::models::client_contact::{ClientContactResponse, NewClientContact, UpdateClientContact};
+use crate::models::custom_field::{custom_field_filters, validate_custom_fields};
+use crate::models::enums::CustomFieldEntity;
+use crate::models::warning::{WarningOverride, WithWarnings};
+use crate::statement;
+use actix_web::{HttpRequest, HttpResponse, web};
+use serde_json::Value;
+use sqlx::{PgExecutor, PgPool};
 use validator::Validate;
 
 // /// Parámetros de paginación
@@ -56,12 +67,120 @@ use validator::Validate;
 //     Ok(HttpResponse::Ok().json(users_response))
 // }
 
+/// Respuesta del cliente con su contacto principal y los demás integrantes de su hogar
+pub(super) async fn client_response(
+    pool: &PgPool,
+    client: Client,
+) -> Result<ClientResponse, ApiError> {
+    let response = ClientResponse::load(pool, vec![client]).await?.pop();
+    response.ok_or(ApiError::InternalServerError(
+        "Error al obtener cliente".into(),
+    ))
+}
+
+/// Verifica que ningún otro cliente use el email. Entre integrantes del mismo
+/// hogar se permite con una advertencia; fuera de él responde 409.
+pub(super) async fn check_email(
+    pool: &PgPool,
+    email: Option<&str>,
+    household_id: Option<i32>,
+    exclude_id: Option<i32>,
+) -> Result<Vec<String>, ApiError> {
+    let Some(email) = email else {
+        return Ok(Vec::new());
+    };
+
+    let owners = sqlx::query!(
+        "SELECT id, household_id FROM clients WHERE email = $1 AND id IS DISTINCT FROM $2",
+        email,
+        exclude_id
+    )
+    .fetch_all(pool)
+    .await?;
+    if owners.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if household_id.is_none()
+        || owners
+            .iter()
+            .any(|owner| owner.household_id != household_id)
+    {
+        tracing::warn!("Intento de registrar un email existente: {}", email);
+        return Err(ApiError::Conflict("El email ya está registrado".into()));
+    }
+
+    let ids: Vec<String> = owners.iter().map(|owner| owner.id.to_string()).collect();
+    Ok(vec![format!(
+        "El email ya lo usa otro integrante del hogar (cliente {})",
+        ids.join(", ")
+    )])
+}
+
+/// Inserta un cliente ya validado, con `custom_fields` ya validados contra
+/// sus definiciones (los de `new_client` se ignoran)
+pub(super) async fn insert_client(
+    executor: impl PgExecutor<'_>,
+    new_client: NewClient,
+    custom_fields: Value,
+) -> Result<Client, ApiError> {
+    sqlx::query_as!(
+        Client,
+        r#"
+            INSERT INTO clients (
+                name,
+                email,
+                phone,
+                address,
+                notes,
+                assigned_to,
+                marketing_opt_out,
+                custom_fields,
+                household_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING
+                id,
+                name,
+                email,
+                phone,
+                address,
+                notes,
+                assigned_to,
+                marketing_opt_out,
+                custom_fields,
+                household_id
+            "#,
+        new_client.name.trim(),
+        new_client.email.map(|s| s.trim().to_string()),
+        new_client.phone,
+        new_client.address.map(|s| s.trim().to_string()),
+        new_client.notes.map(|s| s.trim().to_string()),
+        new_client.assigned_to,
+        new_client.marketing_opt_out,
+        custom_fields,
+        new_client.household_id
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(err) if err.constraint() == Some("clients_household_id_fkey") => {
+            ApiError::ValidationError("El hogar no existe".into())
+        }
+        sqlx::Error::Database(err) if err.constraint() == Some("idx_client_phone") => {
+            ApiError::Conflict("El teléfono ya está registrado".into())
+        }
+        e => ApiError::internal("Error al guardar cliente", e),
+    })
+}
+
 /// Lista todos los clientes con filtros avanzados y paginación.
 ///
 /// # Parámetros (opcionales vía query string)
 /// - `name`: Filtrar por nombre (búsqueda parcial insensible a mayúsculas/minúsculas)
 /// - `phone`: Filtrar por número de teléfono exacto
 /// - `assigned_to`: Filtrar por ID del usuario asignado
+/// - `custom.<key>`: Valor exacto de un campo personalizado
 /// - `limit`: Máximo de resultados (default: 50)
 /// - `offset`: Desplazamiento (default: 0)
 ///
@@ -69,11 +188,19 @@ use validator::Validate;
 /// GET /clients?name=Juan&phone=1234567890&assigned_to=3&limit=10&offset=0
 #[actix_web::get("")]
 async fn list_clients(
+    req: HttpRequest,
     filters: web::Query<ClientFilter>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Listando clientes con filtros: {:?}", &filters);
 
+    let custom_filters = custom_field_filters(
+        pool.get_ref(),
+        CustomFieldEntity::Client,
+        req.query_string(),
+    )
+    .await?;
+
     let clients = sqlx::query_as!(
         Client,
         r#"
@@ -84,12 +211,20 @@ async fn list_clients(
             phone,
             address,
             notes,
-            assigned_to
+            assigned_to,
+            marketing_opt_out,
+            custom_fields,
+            household_id
         FROM clients
         WHERE
             ($1::text IS NULL OR name ILIKE '%' || $1 || '%') AND
             ($2::text IS NULL OR phone = $2) AND
-            ($3::int IS NULL OR assigned_to = $3)
+            ($3::int IS NULL OR assigned_to = $3) AND
+            ($6::JSONB IS NULL OR NOT EXISTS (
+                SELECT 1
+                FROM jsonb_each_text($6) AS filter
+                WHERE custom_fields ->> filter.key IS DISTINCT FROM filter.value
+            ))
         ORDER BY name ASC
         LIMIT $4 OFFSET $5
         "#,
@@ -97,27 +232,24 @@ async fn list_clients(
         filters.phone.as_deref(),
         filters.assigned_to,
         filters.limit.unwrap_or(50).min(400),
-        filters.offset.unwrap_or(0)
+        filters.offset.unwrap_or(0),
+        custom_filters
     )
     .fetch_all(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al listar clientes: {}", e);
-        ApiError::InternalServerError("Error al obtener clientes".into())
-    })?
+    .map_err(|e| ApiError::internal("Error al obtener clientes", e))?
     .into_iter()
     .collect::<Vec<Client>>();
 
-    // Convertir a respuestas simplificadas
-    let clients_response: Vec<ClientResponse> =
-        clients.into_iter().map(ClientResponse::from).collect();
+    // Convertir a respuestas simplificadas con su contacto principal y su hogar
+    let clients_response = ClientResponse::load(pool.get_ref(), clients).await?;
 
     Ok(HttpResponse::Ok().json(clients_response))
 }
 
 /// Obtener un cliente por su ID
 #[actix_web::get("/{id}")]
-async fn get_client(id: web::Path<i32>, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+async fn get_client(id: PathId, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
     tracing::info!("Obteniendo cliente con ID: {}", &id);
 
     let user = sqlx::query_as!(
@@ -130,23 +262,23 @@ async fn get_client(id: web::Path<i32>, pool: web::Data<PgPool>) -> Result<HttpR
             phone,
             address,
             notes,
-            assigned_to
+            assigned_to,
+            marketing_opt_out,
+            custom_fields,
+            household_id
         FROM clients
         WHERE id = $1
         "#,
-        id.clone()
+        *id
     )
     .fetch_optional(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al buscar Cliente: {}", e);
-        ApiError::InternalServerError("Error al obtener cliente".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al obtener cliente", e))?;
 
     match user {
         Some(rec) => {
             tracing::info!("Cliente {} encontrado", &id);
-            Ok(HttpResponse::Ok().json(ClientResponse::from(rec)))
+            Ok(HttpResponse::Ok().json(client_response(pool.get_ref(), rec).await?))
         }
         None => {
             tracing::warn!("Cliente {} no encontrado", id);
@@ -160,6 +292,10 @@ async fn get_client(id: web::Path<i32>, pool: web::Data<PgPool>) -> Result<HttpR
 
 /// Crea un nuevo Cliente
 ///
+/// El email no puede repetirse, salvo con otro integrante del mismo hogar
+/// (`household_id`): en ese caso es una advertencia que requiere
+/// `override_warnings=true`.
+///
 /// # Ejemplo de petición
 /// ```json
 /// {
@@ -169,11 +305,13 @@ async fn get_client(id: web::Path<i32>, pool: web::Data<PgPool>) -> Result<HttpR
 ///   "address": "C XX N XX",
 ///   "notes": "LIC-12345",
 ///   "assigned_to": 1,
+///   "household_id": 2
 /// }
 /// ```
 #[actix_web::post("")]
 async fn create_client(
     new_client: web::Json<NewClient>,
+    warning_override: web::Query<WarningOverride>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Creando nuevo cliente");
@@ -181,75 +319,55 @@ async fn create_client(
     // Validar los datos de entrada
     new_client.validate()?;
 
-    let new_client = new_client.into_inner();
+    let mut new_client = new_client.into_inner();
 
-    // Verificar si el email ya existe
-    let email_exists: bool =
-        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM clients WHERE email = $1)")
-            .bind(&new_client.email)
-            .fetch_one(pool.get_ref())
-            .await?;
+    // Verificar si el email ya existe fuera del hogar
+    let warnings = check_email(
+        pool.get_ref(),
+        new_client.email.as_deref().map(str::trim),
+        new_client.household_id,
+        None,
+    )
+    .await?;
+    let warnings = warning_override.resolve(warnings)?;
 
-    if email_exists {
-        tracing::warn!(
-            "Intento de crear cliente con email existente: {:?}",
-            new_client.email
-        );
-        return Err(ApiError::Conflict("El email ya está registrado".into()));
-    }
+    let custom_fields = validate_custom_fields(
+        pool.get_ref(),
+        CustomFieldEntity::Client,
+        std::mem::take(&mut new_client.custom_fields),
+    )
+    .await?;
 
     // Insertar en la base de datos
-    let user = sqlx::query_as!(
-        Client,
-        r#"
-            INSERT INTO clients (
-                name,
-                email,
-                phone,
-                address,
-                notes,
-                assigned_to
-            )
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING
-                id,
-                name,
-                email,
-                phone,
-                address,
-                notes,
-                assigned_to
-            "#,
-        new_client.name.trim(),
-        new_client.email.map(|s| s.trim().to_string()),
-        new_client.phone,
-        new_client.address.map(|s| s.trim().to_string()),
-        new_client.notes.map(|s| s.trim().to_string()),
-        new_client.assigned_to
-    )
-    .fetch_one(pool.get_ref())
-    .await
-    .map_err(|e| {
-        tracing::error!("Error al crear cliente: {}", e);
-        ApiError::InternalServerError("Error al guardar cliente".into())
-    })?;
+    let user = insert_client(pool.get_ref(), new_client, custom_fields).await?;
 
     tracing::info!("Cliente creado exitosamente ID: {}", user.id);
 
+    let location = format!("/users/{}", user.id);
+    let response = client_response(pool.get_ref(), user).await?;
     Ok(HttpResponse::Created()
-        .append_header(("Location", format!("/users/{}", user.id)))
-        .json(ClientResponse::from(user)))
+        .append_header(("Location", location))
+        .json(WithWarnings::new(response, warnings)))
 }
 
-/// Actualiza un cliente existente (actualización parcial)
+/// Actualiza un cliente existente (actualización parcial).
+/// El email compartido con otro integrante del hogar es una advertencia, igual que al crear.
 #[actix_web::put("/{id}")]
 async fn update_client(
-    id: web::Path<i32>,
+    id: PathId,
     updated_client: web::Json<UpdateClient>,
+    warning_override: web::Query<WarningOverride>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Actualizando cliente ID: {}", id);
 
+    // Verificar si el cliente existe
+    let current_household =
+        sqlx::query_scalar!("SELECT household_id FROM clients WHERE id = $1", *id)
+            .fetch_optional(pool.get_ref())
+            .await?
+            .ok_or(ApiError::NotFound("Cliente no encontrado".into()))?;
+
     let updated_client = updated_client.into_inner();
     updated_client.validate()?;
 
@@ -263,9 +381,20 @@ async fn update_client(
         }
     };
 
-    let assigned_to = match updated_client.assigned_to {
-        Some(inner) => inner, // Some(user_id) para asignar o None para desasignar
-        None => None,         // Mantener valor existente
+    // El email no puede repetirse fuera del hogar que tendrá el cliente
+    let household_id = updated_client.household_id.unwrap_or(current_household);
+    let warnings = check_email(pool.get_ref(), Some(&email), household_id, Some(*id)).await?;
+    let warnings = warning_override.resolve(warnings)?;
+
+    // Some(user_id) para asignar o Some(None) para desasignar
+    let assigned_to = updated_client.assigned_to.flatten();
+
+    // Si se envían, reemplazan todos los campos personalizados
+    let custom_fields = match updated_client.custom_fields {
+        Some(fields) => {
+            Some(validate_custom_fields(pool.get_ref(), CustomFieldEntity::Client, fields).await?)
+        }
+        None => None,
     };
 
     let client = sqlx::query_as!(
@@ -277,7 +406,10 @@ async fn update_client(
             phone = COALESCE($3, phone),
             address = CASE WHEN $4::TEXT IS NOT NULL THEN $4 ELSE address END,
             notes = CASE WHEN $5::TEXT IS NOT NULL THEN $5 ELSE notes END,
-            assigned_to = $6  -- Manejo directo del Option<Option>
+            assigned_to = $6,  -- Manejo directo del Option<Option>
+            custom_fields = COALESCE($8, custom_fields),
+            marketing_opt_out = COALESCE($9, marketing_opt_out),
+            household_id = CASE WHEN $10 THEN $11 ELSE household_id END
         WHERE id = $7
         RETURNING
             id,
@@ -286,7 +418,10 @@ async fn update_client(
             phone,
             address,
             notes,
-            assigned_to
+            assigned_to,
+            marketing_opt_out,
+            custom_fields,
+            household_id
         "#,
         updated_client.name,
         email, // Option<String>
@@ -294,27 +429,32 @@ async fn update_client(
         updated_client.address, // Option<String> (Some(null) será NULL)
         updated_client.notes,   // Option<String> (Some(null) será NULL)
         assigned_to,            // Option<i32>
-        id.clone()
+        *id,
+        custom_fields,
+        updated_client.marketing_opt_out,
+        updated_client.household_id.is_some(),
+        household_id
     )
     .fetch_optional(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error de base de datos: {}", e);
-        match e {
-            sqlx::Error::Database(err) if err.constraint() == Some("clients_email_key") => {
-                ApiError::Conflict("El email ya está registrado".into())
-            }
-            sqlx::Error::Database(err) if err.constraint() == Some("clients_assigned_to_fkey") => {
-                ApiError::ValidationError("El usuario asignado no existe".into())
-            }
-            _ => ApiError::InternalServerError("Error al actualizar cliente".into()),
+    .map_err(|e| match e {
+        sqlx::Error::Database(err) if err.constraint() == Some("clients_household_id_fkey") => {
+            ApiError::ValidationError("El hogar no existe".into())
+        }
+        sqlx::Error::Database(err) if err.constraint() == Some("idx_client_phone") => {
+            ApiError::Conflict("El teléfono ya está registrado".into())
         }
+        sqlx::Error::Database(err) if err.constraint() == Some("clients_assigned_to_fkey") => {
+            ApiError::ValidationError("El usuario asignado no existe".into())
+        }
+        e => ApiError::internal("Error al actualizar cliente", e),
     })?;
 
     match client {
         Some(client) => {
             tracing::info!("Cliente {} actualizado exitosamente", client.id);
-            Ok(HttpResponse::Ok().json(ClientResponse::from(client)))
+            let response = client_response(pool.get_ref(), client).await?;
+            Ok(HttpResponse::Ok().json(WithWarnings::new(response, warnings)))
         }
         None => {
             tracing::warn!("Cliente {} no encontrado", &id);
@@ -323,28 +463,296 @@ async fn update_client(
     }
 }
 
+/// Filas que dependen del cliente y qué les pasaría al eliminarlo
+///
+/// # Ejemplo
+/// GET /clients/3/delete-preview
+#[actix_web::get("/{id}/delete-preview")]
+async fn delete_client_preview(
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let preview = deletion::preview(pool.get_ref(), Resource::Client, *id).await?;
+    Ok(HttpResponse::Ok().json(preview))
+}
+
+/// Estado de cuenta del cliente en un periodo
+///
+/// Consultas completadas y procedimientos aplicados a todas sus mascotas, en
+/// orden cronológico, con subtotal por mascota y total. Los procedimientos sin
+/// precio se listan pero no suman (`unpriced_procedures`).
+///
+/// # Parámetros
+/// - `start_date`, `end_date`: Días locales del periodo, inclusive
+/// - `format`: `json` (default) o `pdf`
+///
+/// # Ejemplo
+/// GET /clients/2/statement?start_date=2024-01-01&end_date=2024-12-31&format=pdf
+#[actix_web::get("/{id}/statement")]
+async fn get_client_statement(
+    id: PathId,
+    query: web::Query<StatementQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Estado de cuenta del cliente {}: {:?}", id, &query);
+
+    let statement = statement::build(pool.get_ref(), *id, query.start_date, query.end_date).await?;
+
+    match query.format {
+        StatementFormat::Json => Ok(HttpResponse::Ok().json(statement)),
+        StatementFormat::Pdf => Ok(HttpResponse::Ok()
+            .content_type("application/pdf")
+            .append_header((
+                "Content-Disposition",
+                format!(
+                    "attachment; filename=\"estado-cuenta-{}-{}-{}.pdf\"",
+                    *id, query.start_date, query.end_date
+                ),
+            ))
+            .body(statement::render_pdf(&statement))),
+    }
+}
+
 /// Elimina un cliente
 #[actix_web::delete("/{id}")]
-async fn delete_client_hard(
-    id: web::Path<i32>,
+async fn delete_client_hard(id: PathId, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    // Verificar que exista y que ninguna dependencia lo impida
+    deletion::ensure_deletable(pool.get_ref(), Resource::Client, *id).await?;
+
+    sqlx::query!("DELETE FROM clients WHERE id = $1", *id)
+        .execute(pool.get_ref())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Bloquea al cliente hasta el final de la transacción para que los cambios
+/// de contacto principal no se crucen
+async fn lock_client(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    client_id: i32,
+) -> Result<(), ApiError> {
+    sqlx::query_scalar!("SELECT id FROM clients WHERE id = $1 FOR UPDATE", client_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or(ApiError::NotFound("Cliente no encontrado".into()))?;
+    Ok(())
+}
+
+/// Quita la marca de principal al contacto actual del cliente
+async fn demote_primary(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    client_id: i32,
+) -> Result<(), ApiError> {
+    sqlx::query!(
+        "UPDATE client_contacts SET is_primary = FALSE WHERE client_id = $1 AND is_primary",
+        client_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Agrega una persona de contacto al cliente.
+/// El primer contacto queda como principal; con `is_primary` reemplaza al actual.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "name": "Laura Pérez",
+///   "relationship": "Pareja",
+///   "phone": "5551234567",
+///   "is_primary": true
+/// }
+/// ```
+#[actix_web::post("/{id}/contacts")]
+async fn create_client_contact(
+    id: PathId,
+    new_contact: web::Json<NewClientContact>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
-    // Verificar dependencias primero
-    let has_deps: bool =
-        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM patients WHERE client_id = $1)")
-            .bind(id.clone())
-            .fetch_one(pool.get_ref())
-            .await?;
-
-    if has_deps {
-        return Err(ApiError::Conflict(
-            "No se puede eliminar, el cliente tiene mascotas registradas".into(),
-        ));
+    tracing::info!("Agregando contacto al cliente ID: {}", id);
+
+    let new_contact = new_contact.into_inner();
+    new_contact.validate()?;
+
+    let mut tx = pool.begin().await?;
+    lock_client(&mut tx, *id).await?;
+
+    let has_primary = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM client_contacts WHERE client_id = $1 AND is_primary) as "exists!""#,
+        *id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let is_primary = new_contact.is_primary || !has_primary;
+    if is_primary {
+        demote_primary(&mut tx, *id).await?;
     }
 
-    sqlx::query!("DELETE FROM clients WHERE id = $1", id.clone())
-        .execute(pool.get_ref())
+    let contact_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO client_contacts (client_id, name, relationship, phone, email, is_primary)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id
+        "#,
+        *id,
+        new_contact.name.trim(),
+        new_contact.relationship.map(|s| s.trim().to_string()),
+        new_contact.phone.map(|s| s.trim().to_string()),
+        new_contact.email.map(|s| s.trim().to_string()),
+        is_primary
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al guardar el contacto", e))?;
+
+    tx.commit().await?;
+
+    let response = ClientContactResponse::fetch(pool.get_ref(), *id, contact_id).await?;
+
+    Ok(HttpResponse::Created()
+        .append_header((
+            "Location",
+            format!("/clients/{}/contacts/{}", *id, contact_id),
+        ))
+        .json(response))
+}
+
+/// Lista los contactos del cliente, primero el principal
+#[actix_web::get("/{id}/contacts")]
+async fn list_client_contacts(
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Listando contactos del cliente ID: {}", id);
+
+    ensure_exists(pool.get_ref(), "clients", *id, "Cliente no encontrado").await?;
+
+    let contacts = ClientContactResponse::for_client(pool.get_ref(), *id)
+        .await
+        .map_err(|e| ApiError::internal("Error al obtener los contactos", e))?;
+
+    Ok(HttpResponse::Ok().json(contacts))
+}
+
+/// Obtiene un contacto del cliente
+#[actix_web::get("/{id}/contacts/{contact_id}")]
+async fn get_client_contact(
+    req: HttpRequest,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let contact_id = PathId::from_segment(&req, "contact_id")?;
+    let contact = ClientContactResponse::fetch(pool.get_ref(), *id, *contact_id).await?;
+    Ok(HttpResponse::Ok().json(contact))
+}
+
+/// Actualiza un contacto del cliente (actualización parcial).
+/// Con `is_primary: true` el contacto reemplaza al principal actual.
+#[actix_web::put("/{id}/contacts/{contact_id}")]
+async fn update_client_contact(
+    req: HttpRequest,
+    id: PathId,
+    updated_contact: web::Json<UpdateClientContact>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let contact_id = PathId::from_segment(&req, "contact_id")?;
+    tracing::info!("Actualizando contacto {} del cliente {}", contact_id, id);
+
+    let updated_contact = updated_contact.into_inner();
+    updated_contact.validate()?;
+
+    let mut tx = pool.begin().await?;
+    lock_client(&mut tx, *id).await?;
+
+    let is_primary = sqlx::query_scalar!(
+        "SELECT is_primary FROM client_contacts WHERE id = $1 AND client_id = $2",
+        *contact_id,
+        *id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ApiError::NotFound("El contacto no existe".into()))?;
+
+    match updated_contact.is_primary {
+        Some(false) if is_primary => {
+            return Err(ApiError::UnprocessableEntity(
+                "El cliente debe tener un contacto principal; marca otro contacto como principal"
+                    .into(),
+            ));
+        }
+        Some(true) if !is_primary => demote_primary(&mut tx, *id).await?,
+        _ => {}
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE client_contacts SET
+            name = COALESCE($1, name),
+            relationship = CASE WHEN $2 THEN $3 ELSE relationship END,
+            phone = CASE WHEN $4 THEN $5 ELSE phone END,
+            email = CASE WHEN $6 THEN $7 ELSE email END,
+            is_primary = is_primary OR COALESCE($8, FALSE)
+        WHERE id = $9
+        "#,
+        updated_contact.name.map(|s| s.trim().to_string()),
+        updated_contact.relationship.is_some(),
+        updated_contact.relationship.flatten(),
+        updated_contact.phone.is_some(),
+        updated_contact.phone.flatten(),
+        updated_contact.email.is_some(),
+        updated_contact.email.flatten(),
+        updated_contact.is_primary,
+        *contact_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al actualizar el contacto", e))?;
+
+    tx.commit().await?;
+
+    let response = ClientContactResponse::fetch(pool.get_ref(), *id, *contact_id).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Elimina un contacto del cliente.
+/// Si era el principal, el contacto más antiguo que quede pasa a serlo.
+#[actix_web::delete("/{id}/contacts/{contact_id}")]
+async fn delete_client_contact(
+    req: HttpRequest,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let contact_id = PathId::from_segment(&req, "contact_id")?;
+    tracing::info!("Eliminando contacto {} del cliente {}", contact_id, id);
+
+    let mut tx = pool.begin().await?;
+    lock_client(&mut tx, *id).await?;
+
+    let was_primary = sqlx::query_scalar!(
+        "DELETE FROM client_contacts WHERE id = $1 AND client_id = $2 RETURNING is_primary",
+        *contact_id,
+        *id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ApiError::NotFound("El contacto no existe".into()))?;
+
+    if was_primary {
+        sqlx::query!(
+            r#"
+            UPDATE client_contacts SET is_primary = TRUE
+            WHERE id = (SELECT MIN(id) FROM client_contacts WHERE client_id = $1)
+            "#,
+            *id
+        )
+        .execute(&mut *tx)
         .await?;
+    }
+
+    tx.commit().await?;
 
     Ok(HttpResponse::NoContent().finish())
 }
@@ -357,6 +765,13 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .service(get_client)
             .service(create_client)
             .service(update_client)
-            .service(delete_client_hard), // Agrega más servicios aquí...
+            .service(delete_client_preview)
+            .service(get_client_statement)
+            .service(delete_client_hard)
+            .service(create_client_contact)
+            .service(list_client_contacts)
+            .service(get_client_contact)
+            .service(update_client_contact)
+            .service(delete_client_contact), // Agrega más servicios aquí...
     );
 }
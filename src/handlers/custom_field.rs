@@ -0,0 +1,240 @@
+use crate::auth::Claims;
+use crate::errors::ApiError;
+use crate::extractors::PathId;
+use crate::models::custom_field::{
+    CustomFieldDefinitionFilter, CustomFieldDefinitionResponse, NewCustomFieldDefinition,
+    UpdateCustomFieldDefinition,
+};
+use crate::models::enums::{CustomFieldEntity, CustomFieldType, UserRole};
+use actix_web::{HttpResponse, web};
+use sqlx::PgPool;
+use validator::Validate;
+
+/// Obtiene una definición por ID
+async fn fetch_definition(
+    pool: &PgPool,
+    id: i32,
+) -> Result<CustomFieldDefinitionResponse, ApiError> {
+    sqlx::query_as!(
+        CustomFieldDefinitionResponse,
+        r#"
+        SELECT
+            id,
+            entity_type as "entity_type!: CustomFieldEntity",
+            key,
+            label,
+            value_type as "value_type!: CustomFieldType",
+            required,
+            created_at
+        FROM custom_field_definitions
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::NotFound(
+        "El campo personalizado no existe".into(),
+    ))
+}
+
+/// Define un nuevo campo personalizado para pacientes o clientes
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "entity_type": "Patient",
+///   "key": "insurance_number",
+///   "label": "Número de póliza",
+///   "value_type": "Text",
+///   "required": false
+/// }
+/// ```
+#[actix_web::post("")]
+async fn create_custom_field(
+    claims: Claims,
+    new_field: web::Json<NewCustomFieldDefinition>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    tracing::info!("Creando campo personalizado");
+
+    let new_field = new_field.into_inner();
+    new_field.validate()?;
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO custom_field_definitions (entity_type, key, label, value_type, required)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+        new_field.entity_type as CustomFieldEntity,
+        new_field.key.trim(),
+        new_field.label.trim(),
+        new_field.value_type as CustomFieldType,
+        new_field.required
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.constraint() == Some("uq_custom_field_key") => {
+            ApiError::Conflict("Ya existe un campo con esa clave".into())
+        }
+        sqlx::Error::Database(db) if db.constraint() == Some("chk_custom_field_key") => {
+            ApiError::ValidationError(
+                "La clave solo admite minúsculas, números y guion bajo, y debe iniciar con una letra"
+                    .into(),
+            )
+        }
+        e => ApiError::internal("Error al guardar el campo personalizado", e),
+    })?;
+
+    let response = fetch_definition(pool.get_ref(), id).await?;
+
+    tracing::info!("Campo personalizado creado ID: {}", response.id);
+
+    Ok(HttpResponse::Created()
+        .append_header(("Location", format!("/custom_field_definitions/{}", id)))
+        .json(response))
+}
+
+/// Lista los campos personalizados
+///
+/// # Parámetros (opcionales vía query string)
+/// - `entity_type`: Filtrar por entidad (Patient, Client)
+#[actix_web::get("")]
+async fn list_custom_fields(
+    filters: web::Query<CustomFieldDefinitionFilter>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Listando campos personalizados: {:?}", &filters);
+
+    let definitions = sqlx::query_as!(
+        CustomFieldDefinitionResponse,
+        r#"
+        SELECT
+            id,
+            entity_type as "entity_type!: CustomFieldEntity",
+            key,
+            label,
+            value_type as "value_type!: CustomFieldType",
+            required,
+            created_at
+        FROM custom_field_definitions
+        WHERE ($1::custom_field_entity IS NULL OR entity_type = $1)
+        ORDER BY entity_type, key
+        "#,
+        filters.entity_type as Option<CustomFieldEntity>
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener campos personalizados", e))?;
+
+    Ok(HttpResponse::Ok().json(definitions))
+}
+
+/// Obtiene un campo personalizado por ID
+#[actix_web::get("/{id}")]
+async fn get_custom_field(id: PathId, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Obteniendo campo personalizado ID: {}", id);
+
+    Ok(HttpResponse::Ok().json(fetch_definition(pool.get_ref(), *id).await?))
+}
+
+/// Actualiza la etiqueta o la obligatoriedad de un campo personalizado
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "label": "Póliza de seguro",
+///   "required": true
+/// }
+/// ```
+#[actix_web::put("/{id}")]
+async fn update_custom_field(
+    claims: Claims,
+    id: PathId,
+    updated: web::Json<UpdateCustomFieldDefinition>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    tracing::info!("Actualizando campo personalizado ID: {}", id);
+
+    fetch_definition(pool.get_ref(), *id).await?;
+
+    let updated = updated.into_inner();
+    updated.validate()?;
+
+    sqlx::query!(
+        r#"
+        UPDATE custom_field_definitions
+        SET
+            label = COALESCE($1, label),
+            required = COALESCE($2, required)
+        WHERE id = $3
+        "#,
+        updated.label.map(|s| s.trim().to_string()),
+        updated.required,
+        *id
+    )
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al actualizar el campo personalizado", e))?;
+
+    Ok(HttpResponse::Ok().json(fetch_definition(pool.get_ref(), *id).await?))
+}
+
+/// Elimina un campo personalizado y borra sus valores guardados
+#[actix_web::delete("/{id}")]
+async fn delete_custom_field(
+    claims: Claims,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    tracing::info!("Eliminando campo personalizado ID: {}", id);
+
+    let definition = fetch_definition(pool.get_ref(), *id).await?;
+
+    let mut tx = pool.begin().await?;
+
+    match definition.entity_type {
+        CustomFieldEntity::Patient => {
+            sqlx::query!(
+                "UPDATE patients SET custom_fields = custom_fields - $1 WHERE custom_fields ? $1",
+                definition.key
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        CustomFieldEntity::Client => {
+            sqlx::query!(
+                "UPDATE clients SET custom_fields = custom_fields - $1 WHERE custom_fields ? $1",
+                definition.key
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    sqlx::query!("DELETE FROM custom_field_definitions WHERE id = $1", *id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    tracing::info!("Campo personalizado {} eliminado", definition.key);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/custom_field_definitions")
+            .service(create_custom_field)
+            .service(list_custom_fields)
+            .service(get_custom_field)
+            .service(update_custom_field)
+            .service(delete_custom_field),
+    );
+}
@@ -0,0 +1,94 @@
+use crate::errors::ApiError;
+use crate::events::{EventBus, StreamSubscription};
+use crate::models::event::AppointmentEventFilter;
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{HttpResponse, web};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Cada cuánto se envía un comentario para mantener viva la conexión
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Máximo de clientes conectados al flujo (`SSE_MAX_SUBSCRIBERS`, default: 50)
+fn max_subscribers() -> usize {
+    std::env::var("SSE_MAX_SUBSCRIBERS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(50)
+}
+
+/// Flujo de cambios de citas para la pantalla de recepción (Server-Sent Events)
+///
+/// Envía un mensaje `data:` con un `AppointmentStreamEvent` en JSON cada vez
+/// que una cita se crea, se actualiza, se reprograma o se cancela, y un
+/// comentario `: keep-alive` cada 15 segundos. Si el cliente se atrasa y se
+/// pierden eventos recibe un evento `resync` y debe volver a pedir la agenda.
+/// Con más de `SSE_MAX_SUBSCRIBERS` clientes conectados responde 503.
+///
+/// # Ejemplo
+/// GET /events/appointments?veterinarian_id=1
+#[actix_web::get("/appointments")]
+async fn appointment_events(
+    filter: web::Query<AppointmentEventFilter>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    let subscription = events.subscribe_stream(max_subscribers()).ok_or_else(|| {
+        ApiError::ServiceUnavailable("Demasiados clientes conectados al flujo de eventos".into())
+    })?;
+    let veterinarian_id = filter.veterinarian_id;
+    tracing::info!("Cliente conectado al flujo de citas");
+
+    // Al desconectarse el cliente actix suelta el flujo, y con él la
+    // suscripción y su cupo
+    let stream = futures::stream::unfold(
+        (subscription, tokio::time::interval(KEEP_ALIVE)),
+        move |(mut subscription, mut keep_alive)| async move {
+            let chunk = next_chunk(&mut subscription, &mut keep_alive, veterinarian_id).await?;
+            Some((
+                Ok::<_, actix_web::Error>(web::Bytes::from(chunk)),
+                (subscription, keep_alive),
+            ))
+        },
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .streaming(stream))
+}
+
+/// Siguiente mensaje del flujo; `None` si el bus se cerró
+async fn next_chunk(
+    subscription: &mut StreamSubscription,
+    keep_alive: &mut tokio::time::Interval,
+    veterinarian_id: Option<i32>,
+) -> Option<String> {
+    loop {
+        tokio::select! {
+            received = subscription.receiver.recv() => match received {
+                Ok(event) => {
+                    let Some(event) = event.appointment_stream_event() else {
+                        continue;
+                    };
+                    if veterinarian_id.is_some_and(|id| id != event.veterinarian_id) {
+                        continue;
+                    }
+                    match serde_json::to_string(&event) {
+                        Ok(json) => return Some(format!("data: {json}\n\n")),
+                        Err(e) => tracing::error!("Evento de cita no serializable: {}", e),
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    return Some(format!("event: resync\ndata: {{\"skipped\":{skipped}}}\n\n"));
+                }
+                Err(RecvError::Closed) => return None,
+            },
+            _ = keep_alive.tick() => return Some(": keep-alive\n\n".to_string()),
+        }
+    }
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/events").service(appointment_events));
+}
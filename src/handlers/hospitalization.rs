@@ -0,0 +1,235 @@
+use crate::errors::ApiError;
+use crate::extractors::PathId;
+use crate::models::enums::AnimalSpecies;
+use crate::models::hospitalization::{
+    ActiveHospitalization, DischargeHospitalization, HospitalizationResponse, UpdateHospitalization,
+};
+use actix_web::{HttpResponse, web};
+use chrono::Utc;
+use sqlx::PgPool;
+use validator::Validate;
+
+/// Verifica que el usuario exista y sea veterinario
+pub async fn ensure_veterinarian(pool: &PgPool, veterinarian_id: i32) -> Result<(), ApiError> {
+    let is_veterinarian: bool = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM users WHERE id = $1 AND role = 'veterinarian'
+        ) as "exists!"
+        "#,
+        veterinarian_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if !is_veterinarian {
+        return Err(ApiError::ValidationError(format!(
+            "El usuario (ID: {}) no es un veterinario válido",
+            veterinarian_id
+        )));
+    }
+    Ok(())
+}
+
+/// Devuelve 409 si la hospitalización ya fue dada de alta
+async fn ensure_active(pool: &PgPool, id: i32) -> Result<(), ApiError> {
+    let hospitalization = HospitalizationResponse::fetch(pool, id).await?;
+    if hospitalization.discharged_at.is_some() {
+        return Err(ApiError::Conflict(
+            "La hospitalización ya fue dada de alta".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Lista los pacientes internados actualmente
+///
+/// # Ejemplo
+/// GET /hospitalizations/active
+#[actix_web::get("/active")]
+async fn list_active_hospitalizations(pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Listando pacientes internados");
+
+    let hospitalizations = sqlx::query_as!(
+        ActiveHospitalization,
+        r#"
+        SELECT
+            h.id,
+            h.patient_id,
+            p.name as patient_name,
+            p.species as "species!: AnimalSpecies",
+            c.id as "client_id?",
+            c.name as "client_name?",
+            c.phone as "client_phone?",
+            h.veterinarian_id,
+            u.name as "veterinarian_name?",
+            h.kennel,
+            h.admitted_at,
+            h.expected_discharge_at,
+            h.reason
+        FROM hospitalizations h
+        JOIN patients p ON p.id = h.patient_id
+        LEFT JOIN clients c ON c.id = p.client_id
+        LEFT JOIN users u ON u.id = h.veterinarian_id
+        WHERE h.discharged_at IS NULL
+        ORDER BY h.kennel NULLS LAST, h.admitted_at
+        "#
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener hospitalizaciones", e))?;
+
+    Ok(HttpResponse::Ok().json(hospitalizations))
+}
+
+/// Obtiene una hospitalización por ID
+///
+/// # Ejemplo
+/// GET /hospitalizations/1
+#[actix_web::get("/{id}")]
+async fn get_hospitalization(
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Obteniendo hospitalización ID: {}", id);
+
+    let response = HospitalizationResponse::fetch(pool.get_ref(), *id).await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Actualiza una hospitalización activa
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "kennel": "Jaula 5",
+///   "expected_discharge_at": "2024-06-03T18:00:00Z"
+/// }
+/// ```
+#[actix_web::put("/{id}")]
+async fn update_hospitalization(
+    id: PathId,
+    updated: web::Json<UpdateHospitalization>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Actualizando hospitalización ID: {}", id);
+
+    ensure_active(pool.get_ref(), *id).await?;
+
+    let updated = updated.into_inner();
+    updated.validate()?;
+
+    if let Some(Some(veterinarian_id)) = updated.veterinarian_id {
+        ensure_veterinarian(pool.get_ref(), veterinarian_id).await?;
+    }
+
+    let clear_veterinarian = updated.veterinarian_id == Some(None);
+    let clear_expected_discharge = updated.expected_discharge_at == Some(None);
+    let clear_kennel = updated.kennel == Some(None);
+
+    sqlx::query!(
+        r#"
+        UPDATE hospitalizations
+        SET
+            veterinarian_id = CASE
+                WHEN $1::INT IS NOT NULL THEN $1 -- Nuevo valor
+                WHEN $2::BOOLEAN THEN NULL -- Borrar el valor
+                ELSE veterinarian_id -- Mantener el valor existente
+            END,
+            expected_discharge_at = CASE
+                WHEN $3::TIMESTAMPTZ IS NOT NULL THEN $3
+                WHEN $4::BOOLEAN THEN NULL
+                ELSE expected_discharge_at
+            END,
+            kennel = CASE
+                WHEN $5::TEXT IS NOT NULL THEN $5
+                WHEN $6::BOOLEAN THEN NULL
+                ELSE kennel
+            END,
+            reason = COALESCE($7, reason)
+        WHERE id = $8
+        "#,
+        updated.veterinarian_id.flatten(),
+        clear_veterinarian,
+        updated.expected_discharge_at.flatten(),
+        clear_expected_discharge,
+        updated.kennel.flatten().map(|s| s.trim().to_string()),
+        clear_kennel,
+        updated.reason.map(|s| s.trim().to_string()),
+        *id
+    )
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.constraint() == Some("chk_expected_discharge") => {
+            ApiError::ValidationError("El alta esperada debe ser posterior al ingreso".into())
+        }
+        e => ApiError::internal("Error al actualizar la hospitalización", e),
+    })?;
+
+    let response = HospitalizationResponse::fetch(pool.get_ref(), *id).await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Da de alta a un paciente internado
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "discharged_at": "2024-06-02T12:00:00Z",
+///   "summary": "Evolución favorable, continuar antibiótico en casa"
+/// }
+/// ```
+#[actix_web::post("/{id}/discharge")]
+async fn discharge_hospitalization(
+    id: PathId,
+    discharge: web::Json<DischargeHospitalization>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Dando de alta hospitalización ID: {}", id);
+
+    ensure_active(pool.get_ref(), *id).await?;
+
+    let discharge = discharge.into_inner();
+    discharge.validate()?;
+
+    sqlx::query!(
+        r#"
+        UPDATE hospitalizations
+        SET
+            discharged_at = $1,
+            discharge_summary = $2
+        WHERE id = $3
+        "#,
+        discharge.discharged_at.unwrap_or_else(Utc::now),
+        discharge.summary.map(|s| s.trim().to_string()),
+        *id
+    )
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.constraint() == Some("chk_discharge_after_admission") => {
+            ApiError::ValidationError("El alta no puede ser anterior al ingreso".into())
+        }
+        e => ApiError::internal("Error al dar de alta la hospitalización", e),
+    })?;
+
+    let response = HospitalizationResponse::fetch(pool.get_ref(), *id).await?;
+
+    tracing::info!("Paciente {} dado de alta", response.patient_id);
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/hospitalizations")
+            .service(list_active_hospitalizations) // Antes de "/{id}"
+            .service(get_hospitalization)
+            .service(update_hospitalization)
+            .service(discharge_hospitalization),
+    );
+}
@@ -0,0 +1,110 @@
+use crate::errors::ApiError;
+use crate::extractors::PathId;
+use crate::models::household::{HouseholdResponse, NewHousehold};
+use actix_web::{HttpResponse, web};
+use sqlx::PgPool;
+use validator::Validate;
+
+/// Crea un hogar que agrupa clientes existentes, por ejemplo los integrantes
+/// de una familia. Sus integrantes pueden compartir email.
+///
+/// Responde 400 si algún cliente no existe y 409 si alguno ya pertenece a
+/// otro hogar. Para agregar o quitar integrantes después se usa
+/// `household_id` en `PUT /clients/{id}`.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "name": "Familia Pérez",
+///   "client_ids": [3, 7]
+/// }
+/// ```
+#[actix_web::post("")]
+async fn create_household(
+    new_household: web::Json<NewHousehold>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let new_household = new_household.into_inner();
+    new_household.validate()?;
+
+    let mut client_ids = new_household.client_ids;
+    client_ids.sort_unstable();
+    client_ids.dedup();
+    tracing::info!("Creando hogar con los clientes {:?}", client_ids);
+
+    let mut tx = pool.begin().await?;
+
+    let clients = sqlx::query!(
+        "SELECT id, household_id FROM clients WHERE id = ANY($1) ORDER BY id FOR UPDATE",
+        &client_ids
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let missing: Vec<String> = client_ids
+        .iter()
+        .filter(|id| !clients.iter().any(|client| client.id == **id))
+        .map(ToString::to_string)
+        .collect();
+    if !missing.is_empty() {
+        return Err(ApiError::ValidationError(format!(
+            "Clientes inexistentes: {}",
+            missing.join(", ")
+        )));
+    }
+
+    if let Some(client) = clients.iter().find(|client| client.household_id.is_some()) {
+        return Err(ApiError::Conflict(format!(
+            "El cliente {} ya pertenece al hogar {}",
+            client.id,
+            client.household_id.unwrap_or_default()
+        )));
+    }
+
+    let household_id = sqlx::query_scalar!(
+        "INSERT INTO households (name) VALUES ($1) RETURNING id",
+        new_household.name.map(|s| s.trim().to_string())
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al guardar el hogar", e))?;
+
+    sqlx::query!(
+        "UPDATE clients SET household_id = $1 WHERE id = ANY($2)",
+        household_id,
+        &client_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let response = HouseholdResponse::fetch(pool.get_ref(), household_id)
+        .await?
+        .ok_or(ApiError::NotFound("El hogar no existe".into()))?;
+
+    Ok(HttpResponse::Created()
+        .append_header(("Location", format!("/households/{}", household_id)))
+        .json(response))
+}
+
+/// Obtiene un hogar con sus integrantes y las mascotas de todos ellos
+///
+/// # Ejemplo
+/// GET /households/2
+#[actix_web::get("/{id}")]
+async fn get_household(id: PathId, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    let household = HouseholdResponse::fetch(pool.get_ref(), *id)
+        .await?
+        .ok_or(ApiError::NotFound("El hogar no existe".into()))?;
+    Ok(HttpResponse::Ok().json(household))
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/households")
+            .service(create_household)
+            .service(get_household),
+    );
+}
@@ -0,0 +1,159 @@
+use crate::auth::Claims;
+use crate::clinic;
+use crate::errors::ApiError;
+use crate::events::{DomainEvent, EventBus};
+use crate::handlers::appointment::{ensure_veterinarian_available, insert_appointment};
+use crate::handlers::client::{check_email, client_response, insert_client};
+use crate::handlers::patient::{insert_patient, patient_response};
+use crate::handlers::species::ensure_species_exist;
+use crate::limits::{self, Limit};
+use crate::models::appointment::{
+    AppointmentResponse, booking_source, ensure_within_booking_horizon,
+};
+use crate::models::custom_field::validate_custom_fields;
+use crate::models::enums::{AppointmentStatus, CustomFieldEntity};
+use crate::models::intake::{IntakeResponse, NewIntake};
+use crate::models::warning::{WarningOverride, WithWarnings};
+use crate::weights;
+use actix_web::{HttpResponse, web};
+use sqlx::PgPool;
+use validator::Validate;
+
+/// Alta rápida de un cliente nuevo: crea el cliente, su mascota y la primera
+/// cita en una sola transacción, para que un error en cualquiera de los tres
+/// no deje registros a medias.
+///
+/// Aplica las mismas validaciones que `POST /clients`, `POST /patients` y
+/// `POST /appointments`: un email ya registrado responde 409, un veterinario
+/// ocupado responde 409 con el siguiente hueco libre, una cita fuera del
+/// horario de atención responde 400, y las advertencias de los tres (email
+/// compartido en el hogar, peso, horario de urgencias) se aceptan juntas con
+/// `?override_warnings=true`.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "client": { "name": "Ana Pérez", "phone": "5512345678", "email": "ana@ejemplo.com" },
+///   "patient": { "name": "Luna", "species": "Cat", "weight_kg": 4.2 },
+///   "appointment": {
+///     "veterinarian_id": 1,
+///     "start_time": "2024-06-01T16:00:00Z",
+///     "end_time": "2024-06-01T16:30:00Z",
+///     "reason": "Primera consulta"
+///   }
+/// }
+/// ```
+#[actix_web::post("")]
+async fn create_intake(
+    claims: Option<Claims>,
+    intake: web::Json<NewIntake>,
+    override_warnings: web::Query<WarningOverride>,
+    pool: web::Data<PgPool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Alta rápida de cliente, paciente y cita");
+
+    // Validar los datos de entrada
+    let NewIntake {
+        mut client,
+        mut patient,
+        mut appointment,
+    } = intake.into_inner();
+    client.validate()?;
+    patient.validate()?;
+    appointment.validate()?;
+    ensure_species_exist(pool.get_ref(), [&patient.species]).await?;
+    ensure_within_booking_horizon(appointment.start_time)?;
+
+    // Límites del plan contratado
+    limits::ensure_within_limit(pool.get_ref(), Limit::ActivePatients).await?;
+    limits::ensure_within_limit(pool.get_ref(), Limit::MonthlyAppointments).await?;
+
+    // Verificar si el email ya existe fuera del hogar
+    let mut warnings = check_email(
+        pool.get_ref(),
+        client.email.as_deref().map(str::trim),
+        client.household_id,
+        None,
+    )
+    .await?;
+    warnings.extend(weights::ensure_plausible(
+        &patient.species,
+        patient.weight_kg,
+        override_warnings.override_warnings,
+    )?);
+    warnings.extend(
+        clinic::check_opening_hours(
+            pool.get_ref(),
+            appointment.start_time,
+            appointment.end_time,
+            appointment.is_emergency || appointment.walk_in,
+        )
+        .await?,
+    );
+    let warnings = override_warnings.resolve(warnings)?;
+
+    if !appointment.walk_in {
+        ensure_veterinarian_available(
+            pool.get_ref(),
+            appointment.veterinarian_id,
+            appointment.start_time,
+            appointment.end_time,
+            None,
+        )
+        .await?;
+    }
+
+    let client_fields = validate_custom_fields(
+        pool.get_ref(),
+        CustomFieldEntity::Client,
+        std::mem::take(&mut client.custom_fields),
+    )
+    .await?;
+    let patient_fields = validate_custom_fields(
+        pool.get_ref(),
+        CustomFieldEntity::Patient,
+        std::mem::take(&mut patient.custom_fields),
+    )
+    .await?;
+
+    // Los tres registros se crean juntos o ninguno
+    let mut tx = pool.begin().await?;
+    let client = insert_client(&mut *tx, client, client_fields).await?;
+    patient.client_id = client.id;
+    let patient = insert_patient(&mut *tx, patient, patient_fields).await?;
+    appointment.client_id = Some(client.id);
+    appointment.patient_id = Some(patient.id);
+    let appointment =
+        insert_appointment(&mut tx, appointment, booking_source(claims.as_ref())).await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "Alta rápida: cliente {}, paciente {}, cita {}",
+        client.id,
+        patient.id,
+        appointment.id
+    );
+    events.publish(DomainEvent::PatientCreated {
+        patient_id: patient.id,
+    });
+    events.publish(DomainEvent::AppointmentCreated((&appointment).into()));
+    if matches!(appointment.status, AppointmentStatus::Completed) {
+        events.publish(DomainEvent::AppointmentCompleted {
+            appointment_id: appointment.id,
+        });
+    }
+
+    let response = IntakeResponse {
+        client: client_response(pool.get_ref(), client).await?,
+        patient: patient_response(pool.get_ref(), patient).await?,
+        appointment: AppointmentResponse::from_appointment(appointment, pool.get_ref()).await?,
+    };
+
+    Ok(HttpResponse::Created().json(WithWarnings::new(response, warnings)))
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/intake").service(create_intake));
+}
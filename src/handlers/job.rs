@@ -0,0 +1,38 @@
+use crate::auth::Claims;
+use crate::errors::ApiError;
+use crate::extractors::PathId;
+use crate::jobs::JobRegistry;
+use crate::models::enums::UserRole;
+use actix_web::{HttpResponse, web};
+
+/// Lista los trabajos en segundo plano registrados desde el último arranque
+#[actix_web::get("")]
+async fn list_jobs(claims: Claims, jobs: web::Data<JobRegistry>) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    Ok(HttpResponse::Ok().json(jobs.list()))
+}
+
+/// Obtiene el estado y progreso de un trabajo
+///
+/// # Ejemplo
+/// GET /jobs/3
+#[actix_web::get("/{id}")]
+async fn get_job(
+    claims: Claims,
+    id: PathId,
+    jobs: web::Data<JobRegistry>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    let job = jobs
+        .get(*id)
+        .ok_or(ApiError::NotFound("El trabajo no existe".into()))?;
+
+    Ok(HttpResponse::Ok().json(job))
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/jobs").service(list_jobs).service(get_job));
+}
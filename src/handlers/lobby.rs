@@ -0,0 +1,204 @@
+use crate::clinic::{self, ClinicProfileCache};
+use crate::errors::ApiError;
+use crate::models::appointment::TimeSlot;
+use crate::models::lobby::{ClinicProfile, LobbyEntry, LobbyFeed};
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{HttpResponse, web};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+/// Segundos que la pantalla puede reutilizar la respuesta
+const LOBBY_FEED_MAX_AGE: u32 = 15;
+
+/// Segundos que el servidor y los navegadores reutilizan el perfil público
+const CLINIC_PROFILE_MAX_AGE: u32 = 60;
+
+/// Duración mínima de un hueco para contar a un veterinario como disponible
+const FREE_SLOT_MINUTES: i64 = 30;
+
+/// Cola de hoy para la pantalla de la sala de espera.
+///
+/// Accesible con el token de pantalla (`LOBBY_DISPLAY_TOKEN`) o con una llave
+/// de API con scope `public/lobby-feed`. Incluye las citas programadas de hoy
+/// que aún no terminan, en orden de hora.
+///
+/// # Ejemplo
+/// GET /public/lobby-feed
+#[actix_web::get("/lobby-feed")]
+async fn lobby_feed(pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    let date = clinic::today();
+    let (day_start, day_end) = clinic::day_bounds(date);
+
+    let entries = sqlx::query!(
+        r#"
+        SELECT
+            SPLIT_PART(TRIM(c.name), ' ', 1) as "owner_first_name!",
+            UPPER(LEFT(TRIM(p.name), 1)) as "pet_initial!",
+            u.name as veterinarian_name,
+            a.start_time <= NOW() as "in_progress!",
+            a.start_time
+        FROM appointments a
+        JOIN clients c ON c.id = a.client_id
+        JOIN patients p ON p.id = a.patient_id
+        JOIN users u ON u.id = a.veterinarian_id
+        WHERE a.status = 'scheduled'
+          AND a.start_time >= $1 AND a.start_time < $2
+          AND a.end_time > NOW()
+        ORDER BY a.start_time, a.id
+        "#,
+        day_start,
+        day_end
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener la cola del día", e))?
+    .into_iter()
+    .map(|row| LobbyEntry {
+        owner_first_name: row.owner_first_name,
+        pet_initial: row.pet_initial,
+        veterinarian_name: row.veterinarian_name,
+        status: if row.in_progress {
+            "in_progress"
+        } else {
+            "waiting"
+        }
+        .to_string(),
+        expected_time: row.start_time,
+    })
+    .collect();
+
+    Ok(HttpResponse::Ok()
+        .insert_header(CacheControl(vec![
+            CacheDirective::Private,
+            CacheDirective::MaxAge(LOBBY_FEED_MAX_AGE),
+        ]))
+        .json(LobbyFeed {
+            date,
+            generated_at: chrono::Utc::now(),
+            entries,
+        }))
+}
+
+/// Datos públicos de la clínica para el widget del sitio web: contacto,
+/// horario de hoy, si está abierta ahora y cuántos veterinarios tienen algún
+/// hueco libre hoy.
+///
+/// No requiere credenciales y nunca incluye datos de clientes, pacientes ni
+/// nombres de veterinarios. Se recalcula a lo más cada
+/// `CLINIC_PROFILE_MAX_AGE` segundos.
+///
+/// # Ejemplo
+/// GET /public/clinic-profile
+#[actix_web::get("/clinic-profile")]
+async fn clinic_profile(
+    pool: web::Data<PgPool>,
+    cache: web::Data<ClinicProfileCache>,
+) -> Result<HttpResponse, ApiError> {
+    let max_age = std::time::Duration::from_secs(CLINIC_PROFILE_MAX_AGE.into());
+    let profile = match cache.get(max_age) {
+        Some(profile) => profile,
+        None => {
+            let profile = build_clinic_profile(pool.get_ref()).await?;
+            cache.set(profile.clone());
+            profile
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(CLINIC_PROFILE_MAX_AGE),
+        ]))
+        .json(profile))
+}
+
+async fn build_clinic_profile(pool: &PgPool) -> Result<ClinicProfile, ApiError> {
+    let tz = clinic::timezone();
+    let now = Utc::now();
+    let date = clinic::today();
+    let hours = clinic::hours_on(pool, date).await?;
+
+    let mut profile = ClinicProfile {
+        name: clinic::contact("CLINIC_NAME"),
+        address: clinic::contact("CLINIC_ADDRESS"),
+        phone: clinic::contact("CLINIC_PHONE"),
+        timezone: tz.name().to_string(),
+        date,
+        opening_time: None,
+        closing_time: None,
+        open_now: false,
+        available_veterinarians: 0,
+        generated_at: now,
+    };
+    // Día cerrado: sin horario ni veterinarios disponibles
+    let Some((opening, closing)) = hours else {
+        return Ok(profile);
+    };
+    let (opens_at, closes_at) = (
+        clinic::local_instant(date, opening),
+        clinic::local_instant(date, closing),
+    );
+
+    // Citas que ocupan lo que queda del horario de hoy
+    let busy = sqlx::query!(
+        r#"
+        SELECT u.id, a.start_time as "start_time?", a.end_time as "end_time?"
+        FROM users u
+        LEFT JOIN appointments a ON a.veterinarian_id = u.id
+            AND a.status NOT IN ('canceled', 'no_show')
+            AND a.start_time < $2
+            AND a.end_time > $1
+        WHERE u.role = 'veterinarian' AND u.is_active = TRUE
+        ORDER BY u.id, a.start_time
+        "#,
+        now.max(opens_at),
+        closes_at
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ApiError::internal("Error al calcular la disponibilidad de hoy", e))?;
+
+    let mut by_veterinarian: HashMap<i32, Vec<TimeSlot>> = HashMap::new();
+    for row in busy {
+        let slots = by_veterinarian.entry(row.id).or_default();
+        if let (Some(start_time), Some(end_time)) = (row.start_time, row.end_time) {
+            slots.push(TimeSlot {
+                start_time,
+                end_time,
+            });
+        }
+    }
+
+    profile.opening_time = Some(opening.format("%H:%M").to_string());
+    profile.closing_time = Some(closing.format("%H:%M").to_string());
+    profile.open_now = opens_at <= now && now < closes_at;
+    profile.available_veterinarians = by_veterinarian
+        .values()
+        .filter(|busy| has_free_slot(busy, now.max(opens_at), closes_at))
+        .count() as i64;
+    Ok(profile)
+}
+
+/// Si entre `from` y `to` queda un hueco de `FREE_SLOT_MINUTES` fuera de las
+/// citas ocupadas, que vienen ordenadas por inicio
+fn has_free_slot(busy: &[TimeSlot], from: DateTime<Utc>, to: DateTime<Utc>) -> bool {
+    let duration = Duration::minutes(FREE_SLOT_MINUTES);
+    let mut candidate = from;
+    for slot in busy {
+        if slot.start_time >= candidate + duration {
+            break;
+        }
+        candidate = candidate.max(slot.end_time);
+    }
+    candidate + duration <= to
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/public")
+            .service(lobby_feed)
+            .service(clinic_profile),
+    );
+}
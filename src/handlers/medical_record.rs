@@ -1,21 +1,41 @@
+use crate::auth::Claims;
 use crate::errors::ApiError;
+use crate::events::{DomainEvent, EventBus};
+use crate::extractors::PathId;
+use crate::handlers::{ensure_exists, next_folio};
+use crate::models::enums::{AnimalSpecies, UserRole};
 use crate::models::medical_record::{
     MedicalRecord, MedicalRecordFilter, MedicalRecordRaw, MedicalRecordResponse, NewMedicalRecord,
     UpdateMedicalRecord,
 };
+use crate::models::record_template::RecordTemplateResponse;
+use crate::models::warning::{WarningOverride, WithWarnings};
+use crate::weights;
 
 use actix_web::{HttpResponse, web};
 use bigdecimal::FromPrimitive;
 use sqlx::{PgPool, types::BigDecimal};
 use validator::Validate;
 
-/// Crea un nuevo registro médico
+/// Crea un nuevo registro médico.
+///
+/// Requiere el rol Veterinarian: sin token de usuario responde 401 (la API_KEY
+/// del sistema no basta) y con otro rol 403. Con un token de usuario el
+/// registro queda a nombre de quien llama y `veterinarian_id` puede omitirse;
+/// una llave de API de rol veterinario debe enviarlo.
+///
+/// Con `template_id`, los campos `diagnosis`, `treatment` y `notes` que no se
+/// envíen se toman de la plantilla. Una plantilla inexistente responde 422.
+///
+/// Un peso fuera del rango plausible para la especie del paciente responde
+/// 422, salvo que se envíe `?override_warnings=true`.
 ///
 /// # Ejemplo de petición
 /// ```json
 /// {
 ///   "patient_id": 1,
 ///   "veterinarian_id": 3,
+///   "template_id": 2,
 ///   "diagnosis": "Infección en la oreja",
 ///   "treatment": "Antibióticos",
 ///   "notes": "Seguimiento en una semana",
@@ -24,14 +44,64 @@ use validator::Validate;
 /// ```
 #[actix_web::post("")]
 async fn create_medical_record(
+    claims: Claims,
     new_record: web::Json<NewMedicalRecord>,
+    override_warnings: web::Query<WarningOverride>,
     pool: web::Data<PgPool>,
+    events: web::Data<EventBus>,
 ) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Veterinarian])?;
+    let veterinarian_id = caller_veterinarian(Some(&claims), new_record.veterinarian_id)?
+        .or(new_record.veterinarian_id)
+        .ok_or_else(|| {
+            ApiError::ValidationError("El campo veterinarian_id es obligatorio".into())
+        })?;
     tracing::info!("Creando nuevo registro médico");
 
+    let mut new_record = new_record.into_inner();
+
+    // Completar con la plantilla los campos que no se enviaron
+    if let Some(template_id) = new_record.template_id {
+        let template = RecordTemplateResponse::fetch(pool.get_ref(), template_id)
+            .await?
+            .ok_or_else(|| {
+                ApiError::UnprocessableEntity(format!(
+                    "La plantilla {template_id} no existe o fue eliminada"
+                ))
+            })?;
+        new_record.diagnosis = new_record.diagnosis.or(template.diagnosis);
+        new_record.treatment = new_record.treatment.or(template.treatment);
+        new_record.notes = new_record.notes.or(template.notes);
+    }
+
     // Validar los datos de entrada
-    let new_record = new_record.into_inner();
     new_record.validate()?;
+    let diagnosis = new_record
+        .diagnosis
+        .ok_or_else(|| ApiError::ValidationError("El campo diagnosis es obligatorio".into()))?;
+
+    let species = sqlx::query_scalar!(
+        r#"SELECT species as "species: AnimalSpecies" FROM patients WHERE id = $1"#,
+        new_record.patient_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?;
+    let warnings = match &species {
+        Some(species) => weights::ensure_plausible(
+            species,
+            new_record.weight_at_visit,
+            override_warnings.override_warnings,
+        )?,
+        None => Vec::new(),
+    };
+
+    let weight_at_visit = BigDecimal::from_f64(new_record.weight_at_visit.ok_or_else(|| {
+        ApiError::ValidationError("El campo weight_at_visit es obligatorio".into())
+    })?);
+
+    // El folio se asigna en la misma transacción que el registro
+    let mut tx = pool.begin().await?;
+    let folio = next_folio(&mut tx, "medical_record").await?;
 
     // Insertar el registro médico en la base de datos
     let record: MedicalRecord = sqlx::query_as!(
@@ -44,9 +114,13 @@ async fn create_medical_record(
             diagnosis,
             treatment,
             notes,
-            weight_at_visit
+            weight_at_visit,
+            temperature_c,
+            heart_rate_bpm,
+            respiratory_rate_rpm,
+            folio
         )
-        VALUES ($1, $2, NOW(), $3, $4, $5, $6)
+        VALUES ($1, $2, NOW(), $3, $4, $5, $6, $7, $8, $9, $10)
         RETURNING
             id,
             patient_id as "patient_id!: i32",
@@ -55,25 +129,30 @@ async fn create_medical_record(
             diagnosis,
             treatment,
             notes,
-            weight_at_visit as "weight_at_visit!: BigDecimal"
+            weight_at_visit as "weight_at_visit!: BigDecimal",
+            temperature_c,
+            heart_rate_bpm,
+            respiratory_rate_rpm,
+            folio
         "#,
         new_record.patient_id,
-        new_record.veterinarian_id,
-        new_record.diagnosis.trim(),
+        veterinarian_id,
+        diagnosis.trim(),
         new_record.treatment.map(|s| s.trim().to_string()),
         new_record.notes.map(|s| s.trim().to_string()),
-        BigDecimal::from_f64(new_record.weight_at_visit.ok_or_else(|| {
-            ApiError::ValidationError("El campo weight_at_visit es obligatorio".into())
-        })?)
+        weight_at_visit,
+        new_record.temperature_c.and_then(BigDecimal::from_f64),
+        new_record.heart_rate_bpm,
+        new_record.respiratory_rate_rpm,
+        folio
     )
-    .fetch_one(pool.get_ref())
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| {
-        tracing::error!("Error al crear registro médico: {}", e);
-        ApiError::InternalServerError("Error al guardar el registro médico".into())
-    })?
+    .map_err(|e| ApiError::internal("Error al guardar el registro médico", e))?
     .into();
 
+    tx.commit().await?;
+
     // Obtener el nombre del veterinario
     let vet_name: String = sqlx::query_scalar!(
         r#"
@@ -88,11 +167,16 @@ async fn create_medical_record(
     .unwrap_or_else(|_| "Veterinario desconocido".to_string());
 
     tracing::info!("Registro médico creado exitosamente ID: {}", record.id);
+    events.publish(DomainEvent::MedicalRecordCreated {
+        record_id: record.id,
+        patient_id: record.patient_id,
+    });
 
     Ok(HttpResponse::Created()
         .append_header(("Location", format!("/medical_records/{}", record.id)))
-        .json(MedicalRecordResponse::from_record_with_vet(
-            record, vet_name,
+        .json(WithWarnings::new(
+            MedicalRecordResponse::from_record_with_vet(record, vet_name, species.as_ref()),
+            warnings,
         )))
 }
 
@@ -127,7 +211,11 @@ async fn list_medical_records(
             diagnosis,
             treatment,
             notes,
-            weight_at_visit as "weight_at_visit!: BigDecimal"
+            weight_at_visit as "weight_at_visit!: BigDecimal",
+            temperature_c,
+            heart_rate_bpm,
+            respiratory_rate_rpm,
+            folio
         FROM medical_records
         WHERE
             ($1::int IS NULL OR patient_id = $1) AND
@@ -148,10 +236,7 @@ async fn list_medical_records(
     )
     .fetch_all(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al listar registros médicos: {}", e);
-        ApiError::InternalServerError("Error al obtener registros médicos".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al obtener registros médicos", e))?;
 
     // Convertir a respuestas enriquecidas
     let mut responses = Vec::new();
@@ -170,10 +255,14 @@ async fn list_medical_records(
         .fetch_one(pool.get_ref())
         .await
         .unwrap_or_else(|_| "Unknown Veterinarian".to_string());
+        let species =
+            MedicalRecordResponse::patient_species(pool.get_ref(), medical_record.patient_id)
+                .await?;
 
         responses.push(MedicalRecordResponse::from_record_with_vet(
             medical_record,
             vet_name,
+            species.as_ref(),
         ));
     }
 
@@ -185,10 +274,7 @@ async fn list_medical_records(
 /// # Ejemplo
 /// GET /medical_records/1
 #[actix_web::get("/{id}")]
-async fn get_medical_record(
-    id: web::Path<i32>,
-    pool: web::Data<PgPool>,
-) -> Result<HttpResponse, ApiError> {
+async fn get_medical_record(id: PathId, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
     tracing::info!("Obteniendo registro médico ID: {}", id);
 
     let record: MedicalRecord = sqlx::query_as!(
@@ -202,11 +288,15 @@ async fn get_medical_record(
             diagnosis,
             treatment,
             notes,
-            weight_at_visit as "weight_at_visit!: BigDecimal"
+            weight_at_visit as "weight_at_visit!: BigDecimal",
+            temperature_c,
+            heart_rate_bpm,
+            respiratory_rate_rpm,
+            folio
         FROM medical_records
         WHERE id = $1
         "#,
-        id.clone()
+        *id
     )
     .fetch_optional(pool.get_ref())
     .await?
@@ -225,10 +315,13 @@ async fn get_medical_record(
     .fetch_one(pool.get_ref())
     .await
     .unwrap_or_else(|_| "Unknown Veterinarian".to_string());
+    let species = MedicalRecordResponse::patient_species(pool.get_ref(), record.patient_id).await?;
 
     Ok(
         HttpResponse::Ok().json(MedicalRecordResponse::from_record_with_vet(
-            record, vet_name,
+            record,
+            vet_name,
+            species.as_ref(),
         )),
     )
 }
@@ -244,36 +337,26 @@ async fn get_medical_record(
 /// ```
 #[actix_web::put("/{id}")]
 async fn update_medical_record(
-    id: web::Path<i32>,
+    claims: Option<Claims>,
+    id: PathId,
     updated_record: web::Json<UpdateMedicalRecord>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Actualizando registro médico ID: {}", id);
 
-    let updated_record = updated_record.into_inner();
-    updated_record.validate()?;
+    caller_veterinarian(claims.as_ref(), updated_record.veterinarian_id)?;
 
     // Verificar si el registro existe
-    let exists: bool = sqlx::query_scalar(
-        r#"
-        SELECT EXISTS (
-            SELECT 1
-            FROM medical_records
-            WHERE id = $1
-        )
-        "#,
+    ensure_exists(
+        pool.get_ref(),
+        "medical_records",
+        *id,
+        "El registro médico no existe",
     )
-    .bind(id.clone())
-    .fetch_one(pool.get_ref())
     .await?;
 
-    if !exists {
-        tracing::warn!(
-            "Intento de actualizar registro médico inexistente ID: {}",
-            id
-        );
-        return Err(ApiError::NotFound("El registro médico no existe".into()));
-    }
+    let updated_record = updated_record.into_inner();
+    updated_record.validate()?;
 
     let weigth_at_visit = match updated_record.weight_at_visit {
         None => None,       // No se proporciona ningún cambio
@@ -296,8 +379,14 @@ async fn update_medical_record(
                     WHEN $6::NUMERIC IS NOT NULL THEN $6 -- Nuevo valor
                     WHEN $6 IS NULL AND $7::BOOLEAN THEN NULL -- Borrar el valor
                     ELSE weight_at_visit -- Mantener el valor existente
+                    END,
+            temperature_c = CASE WHEN $8::BOOLEAN THEN $9 ELSE temperature_c END,
+            heart_rate_bpm = CASE WHEN $10::BOOLEAN THEN $11 ELSE heart_rate_bpm END,
+            respiratory_rate_rpm = CASE
+                    WHEN $12::BOOLEAN THEN $13
+                    ELSE respiratory_rate_rpm
                     END
-        WHERE id = $8
+        WHERE id = $14
         RETURNING
             id,
             patient_id as "patient_id!: i32",
@@ -306,7 +395,11 @@ async fn update_medical_record(
             diagnosis,
             treatment,
             notes,
-            weight_at_visit as "weight_at_visit!: BigDecimal"
+            weight_at_visit as "weight_at_visit!: BigDecimal",
+            temperature_c,
+            heart_rate_bpm,
+            respiratory_rate_rpm,
+            folio
         "#,
         updated_record.patient_id,
         updated_record.veterinarian_id,
@@ -318,14 +411,21 @@ async fn update_medical_record(
         updated_record.notes.flatten().map(|s| s.trim().to_string()),
         weigth_at_visit,
         updated_record.weight_at_visit.is_some() && updated_record.weight_at_visit == Some(None),
-        id.clone()
+        // Signos vitales: se escriben solo si vienen, `null` incluido
+        updated_record.temperature_c.is_some(),
+        updated_record
+            .temperature_c
+            .flatten()
+            .and_then(BigDecimal::from_f64),
+        updated_record.heart_rate_bpm.is_some(),
+        updated_record.heart_rate_bpm.flatten(),
+        updated_record.respiratory_rate_rpm.is_some(),
+        updated_record.respiratory_rate_rpm.flatten(),
+        *id
     )
     .fetch_one(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al actualizar registro médico: {}", e);
-        ApiError::InternalServerError("Error al actualizar el registro médico".into())
-    })?
+    .map_err(|e| ApiError::internal("Error al actualizar el registro médico", e))?
     .into();
 
     // Obtener el nombre del veterinario
@@ -340,68 +440,79 @@ async fn update_medical_record(
     .fetch_one(pool.get_ref())
     .await
     .unwrap_or_else(|_| "Unknown Veterinarian".to_string());
+    let species = MedicalRecordResponse::patient_species(pool.get_ref(), record.patient_id).await?;
 
     Ok(
         HttpResponse::Ok().json(MedicalRecordResponse::from_record_with_vet(
-            record, vet_name,
+            record,
+            vet_name,
+            species.as_ref(),
         )),
     )
 }
 
-/// Elimina un registro médico existente
+/// Elimina un registro médico existente.
+///
+/// Solo se eliminan los registros sin folio (anteriores a los folios): un
+/// folio ya emitido no puede desaparecer de la secuencia, así que esos
+/// registros responden 409.
 ///
 /// # Ejemplo
 /// DELETE /medical_records/1
 #[actix_web::delete("/{id}")]
 async fn delete_medical_record(
-    id: web::Path<i32>,
+    id: PathId,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Eliminando registro médico ID: {}", id);
 
-    // Verificar si el registro existe
-    let exists: bool = sqlx::query_scalar(
+    let deleted = sqlx::query_scalar!(
         r#"
-        SELECT EXISTS (
-            SELECT 1
-            FROM medical_records
-            WHERE id = $1
-        )
+        DELETE FROM medical_records
+        WHERE id = $1 AND folio IS NULL
+        RETURNING id
         "#,
+        *id
     )
-    .bind(id.clone())
-    .fetch_one(pool.get_ref())
+    .fetch_optional(pool.get_ref())
     .await?;
 
-    if !exists {
-        tracing::warn!("Intento de eliminar registro médico inexistente ID: {}", id);
-        return Err(ApiError::NotFound("El registro médico no existe".into()));
-    }
-
-    // Eliminar el registro
-    let rows_affected = sqlx::query!(
-        r#"
-        DELETE FROM medical_records
-        WHERE id = $1
-        "#,
-        id.clone()
-    )
-    .execute(pool.get_ref())
-    .await?
-    .rows_affected();
-
-    if rows_affected == 0 {
-        tracing::warn!(
-            "Registro médico ID {} no encontrado después de intentar eliminar",
-            id
-        );
-        return Err(ApiError::NotFound("El registro médico no existe".into()));
+    if deleted.is_none() {
+        // Sin fila: o no existe o tiene folio
+        let folio = sqlx::query_scalar!("SELECT folio FROM medical_records WHERE id = $1", *id)
+            .fetch_optional(pool.get_ref())
+            .await?
+            .ok_or(ApiError::NotFound("El registro médico no existe".into()))?;
+        return Err(ApiError::Conflict(format!(
+            "El registro médico tiene el folio {} y no se puede eliminar; corrígelo con una actualización",
+            folio.unwrap_or_default()
+        )));
     }
 
     tracing::info!("Registro médico ID {} eliminado exitosamente", id);
     Ok(HttpResponse::NoContent().finish())
 }
 
+/// Con el token de un veterinario, el registro queda a nombre de quien llama:
+/// devuelve su id y responde 403 si se pide otro `veterinarian_id`. Las llaves
+/// de API y la API_KEY del sistema no identifican a nadie y devuelven `None`.
+fn caller_veterinarian(
+    claims: Option<&Claims>,
+    requested: Option<i32>,
+) -> Result<Option<i32>, ApiError> {
+    let Some(claims) = claims
+        .filter(|claims| claims.api_key_id.is_none() && claims.role == UserRole::Veterinarian)
+    else {
+        return Ok(None);
+    };
+    if requested.is_some_and(|id| id != claims.sub) {
+        return Err(ApiError::Forbidden(
+            "Solo puedes registrar consultas a tu nombre".into(),
+        ));
+    }
+    Ok(Some(claims.sub))
+}
+
 // Exporta todas las funciones como un grupo
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -413,3 +524,76 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .service(delete_medical_record), // Agrega más servicios aquí...
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{as_user, claims, concurrent_deletes};
+    use actix_web::{App, test};
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn concurrent_creates_get_gapless_folios(pool: PgPool) {
+        const CREATES: i64 = 12;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(EventBus::default()))
+                .configure(config),
+        )
+        .await;
+        let last_folio = sqlx::query_scalar!(
+            "SELECT last_value FROM document_sequences WHERE document_type = 'medical_record'"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let responses = futures::future::join_all((0..CREATES).map(|_| {
+            let request = test::TestRequest::post()
+                .uri("/medical_records")
+                .set_json(serde_json::json!({
+                    "patient_id": 1,
+                    "diagnosis": "Revisión general",
+                    "weight_at_visit": 28.5
+                }))
+                .to_request();
+            test::call_service(&app, as_user(request, claims(1, UserRole::Veterinarian)))
+        }))
+        .await;
+        for response in &responses {
+            assert_eq!(response.status(), 201);
+        }
+
+        let folios = sqlx::query_scalar!(
+            r#"SELECT folio as "folio!" FROM medical_records WHERE folio > $1 ORDER BY folio"#,
+            last_folio
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        let expected: Vec<i64> = (last_folio + 1..=last_folio + CREATES).collect();
+        assert_eq!(folios, expected);
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn concurrent_deletes_remove_the_record_once(pool: PgPool) {
+        // Solo los registros sin folio se pueden eliminar
+        let id: i32 = sqlx::query_scalar(
+            "INSERT INTO medical_records (patient_id, veterinarian_id, diagnosis)
+             VALUES (1, 1, 'Revisión general') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let uri = format!("/api/medical_records/{id}");
+        let statuses = concurrent_deletes(pool, &uri, claims(1, UserRole::Veterinarian)).await;
+        assert_eq!(statuses, [204, 404]);
+    }
+}
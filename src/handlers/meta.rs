@@ -0,0 +1,19 @@
+use crate::vitals;
+use actix_web::{HttpResponse, web};
+
+/// Rangos normales de signos vitales por especie
+///
+/// Los mismos que marcan `low`, `normal` o `high` en los registros médicos.
+/// Las combinaciones sin rango conocido no aparecen.
+///
+/// # Ejemplo
+/// GET /meta/vital-ranges
+#[actix_web::get("/vital-ranges")]
+async fn get_vital_ranges() -> HttpResponse {
+    HttpResponse::Ok().json(vitals::reference_table())
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/meta").service(get_vital_ranges));
+}
@@ -1,23 +1,192 @@
+mod admin;
+mod api_key;
 mod appointment;
 mod breed;
 mod client;
+mod custom_field;
+mod events;
+mod hospitalization;
+mod household;
+mod intake;
+mod job;
+mod lobby;
 mod medical_record;
+mod meta;
 mod patient;
 mod patient_procedure;
 mod procedure;
+mod record_template;
+mod room;
+mod settings;
+mod species;
+mod species_protocol;
 mod statistic;
+mod survey;
+mod sync;
+mod treatment_task;
 mod user;
+mod veterinarian;
+
+use crate::errors::ApiError;
+use actix_web::http::header::{
+    CacheControl, CacheDirective, HttpDate, IfModifiedSince, LastModified,
+};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder};
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+
+/// Verifica que exista la fila `id` en `table` antes de validar el cuerpo de la
+/// petición, para que un recurso inexistente responda 404 y no un 400.
+async fn ensure_exists(
+    pool: &PgPool,
+    table: &'static str,
+    id: i32,
+    not_found: &str,
+) -> Result<(), ApiError> {
+    let exists: bool = sqlx::query_scalar(&format!(
+        "SELECT EXISTS (SELECT 1 FROM {table} WHERE id = $1)"
+    ))
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    if !exists {
+        tracing::warn!("ID {} inexistente en {}", id, table);
+        return Err(ApiError::NotFound(not_found.to_string()));
+    }
+    Ok(())
+}
+
+/// Última modificación de un catálogo (`breeds` o `procedures`), truncada a
+/// segundos como las fechas HTTP. Considera también la última eliminación.
+async fn catalog_last_modified(
+    pool: &PgPool,
+    catalog: &'static str,
+) -> Result<Option<HttpDate>, ApiError> {
+    let last_modified: Option<DateTime<Utc>> = sqlx::query_scalar(&format!(
+        r#"
+        SELECT DATE_TRUNC('second', GREATEST(
+            (SELECT MAX(updated_at) FROM {catalog}),
+            (SELECT deleted_at FROM catalog_deletions WHERE catalog = $1)
+        ))
+        "#
+    ))
+    .bind(catalog)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(last_modified.map(|date| HttpDate::from(std::time::SystemTime::from(date))))
+}
+
+/// Registra una eliminación en el catálogo para invalidar las copias en caché
+async fn record_catalog_deletion(
+    executor: impl PgExecutor<'_>,
+    catalog: &'static str,
+) -> Result<(), ApiError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO catalog_deletions (catalog)
+        VALUES ($1)
+        ON CONFLICT (catalog) DO UPDATE SET deleted_at = NOW()
+        "#,
+        catalog
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Siguiente folio del tipo de documento, sin huecos.
+///
+/// Bloquea el contador hasta el final de la transacción que crea el documento;
+/// si esa transacción se revierte el número se libera junto con ella.
+async fn next_folio(
+    tx: &mut Transaction<'_, Postgres>,
+    document_type: &'static str,
+) -> Result<i64, ApiError> {
+    let last_value = sqlx::query_scalar!(
+        "SELECT last_value FROM document_sequences WHERE document_type = $1 FOR UPDATE",
+        document_type
+    )
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| {
+        ApiError::internal(
+            "No se pudo asignar el folio",
+            format!("falta el consecutivo '{document_type}' en document_sequences"),
+        )
+    })?;
+
+    sqlx::query!(
+        "UPDATE document_sequences SET last_value = $1 WHERE document_type = $2",
+        last_value + 1,
+        document_type
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(last_value + 1)
+}
+
+/// Cabeceras de caché de los catálogos. `no-cache` obliga al cliente a
+/// revalidar con `If-Modified-Since` en cada uso.
+fn catalog_cache_headers(
+    builder: &mut HttpResponseBuilder,
+    last_modified: Option<HttpDate>,
+) -> &mut HttpResponseBuilder {
+    builder.insert_header(CacheControl(vec![
+        CacheDirective::Private,
+        CacheDirective::NoCache,
+    ]));
+    if let Some(last_modified) = last_modified {
+        builder.insert_header(LastModified(last_modified));
+    }
+    builder
+}
+
+/// Responde 304 si la copia del cliente (`If-Modified-Since`) sigue vigente
+fn catalog_not_modified(
+    req: &HttpRequest,
+    last_modified: Option<HttpDate>,
+) -> Option<HttpResponse> {
+    let last_modified = last_modified?;
+    let IfModifiedSince(since) = req.get_header::<IfModifiedSince>()?;
+
+    (last_modified <= since).then(|| {
+        catalog_cache_headers(&mut HttpResponse::NotModified(), Some(last_modified)).finish()
+    })
+}
 
 /// Configura todas las rutas de los Handlers
 pub fn config(cfg: &mut actix_web::web::ServiceConfig) {
+    admin::config(cfg);
+    api_key::config(cfg);
     appointment::config(cfg);
     breed::config(cfg);
     client::config(cfg);
+    custom_field::config(cfg);
+    events::config(cfg);
+    hospitalization::config(cfg);
+    household::config(cfg);
+    intake::config(cfg);
+    job::config(cfg);
+    lobby::config(cfg);
     medical_record::config(cfg);
+    meta::config(cfg);
     patient::config(cfg);
     patient_procedure::config(cfg);
     procedure::config(cfg);
+    record_template::config(cfg);
+    room::config(cfg);
+    settings::config(cfg);
+    species::config(cfg);
+    species_protocol::config(cfg);
     statistic::config(cfg);
+    survey::config(cfg);
+    sync::config(cfg);
+    treatment_task::config(cfg);
     user::config(cfg);
+    veterinarian::config(cfg);
     // ... otros configs
 }
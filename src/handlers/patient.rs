@@ -1,42 +1,89 @@
+use crate::auth::Claims;
+use crate::claims;
+use crate::clinic;
+use crate::deletion::{self, Resource};
+use crate::dossier;
 use crate::errors::ApiError;
-use crate::models::enums::{AnimalGender, AnimalSpecies};
+use crate::events::{DomainEvent, EventBus};
+use crate::extractors::{LoadedPatient, PathId};
+use crate::handlers::ensure_exists;
+use crate::handlers::hospitalization::ensure_veterinarian;
+use crate::handlers::species::ensure_species_exist;
+use crate::handlers::species_protocol::fetch_protocol;
+use crate::limits::{self, Limit};
+use crate::models::appointment::AppointmentResponse;
+use crate::models::custom_field::{custom_field_filters, validate_custom_fields};
+use crate::models::enums::{
+    AnimalGender, AnimalSpecies, CustomFieldEntity, ReferralDirection, UserRole,
+};
+use crate::models::hospitalization::{HospitalizationResponse, NewHospitalization};
+use crate::models::medical_record::MedicalRecordResponse;
 use crate::models::patient::{
-    NewPatient, Patient, PatientFilter, PatientRaw, PatientResponse, UpdatePatient,
+    BirthdayQuery, BulkSetBreed, BulkSetBreedResponse, CreatePatientQuery,
+    DuplicatePatientCandidate, DuplicatePatientFilter, DuplicatePatientPair, InsuranceClaimQuery,
+    NewPatient, Patient, PatientBirthday, PatientDetailResponse, PatientFilter,
+    PatientHistoryResponse, PatientQuery, PatientRaw, PatientResponse, UpdatePatient,
+    anniversary_in,
 };
+use crate::models::patient_procedure::{PatientProcedure, PatientProcedureResponse};
+use crate::models::referral::{NewReferral, ReferralFilter, ReferralResponse};
+use crate::models::species_protocol::{ProtocolApplicationResponse, SkippedProtocolItem};
+use crate::models::warning::{WarningOverride, WithWarnings};
+use crate::weights;
 
-use actix_web::{HttpResponse, web};
+use actix_web::{HttpRequest, HttpResponse, web};
 use bigdecimal::{BigDecimal, FromPrimitive};
-use sqlx::PgPool;
+use chrono::{Datelike, Duration, Utc};
+use serde_json::Value;
+use sqlx::{PgExecutor, PgPool};
 use validator::Validate;
 
-/// Crea un nuevo paciente
-///
-/// # Ejemplo de petición
-/// ```json
-/// {
-///   "name": "Max",
-///   "species": "Dog",
-///   "breed_id": 3,
-///   "birth_date": "2020-05-15",
-///   "gender": "Male",
-///   "weight_kg": 12.5,
-///   "client_id": 1,
-///   "photo_url": "https://example.com/max.jpg "
-/// }
-/// ```
-#[actix_web::post("")]
-async fn create_patient(
-    new_patient: web::Json<NewPatient>,
-    pool: web::Data<PgPool>,
-) -> Result<HttpResponse, ApiError> {
-    tracing::info!("Creando nuevo paciente");
+/// Similitud mínima (trigramas, 0 a 1) para considerar dos nombres como duplicados
+const DUPLICATE_SIMILARITY: f64 = 0.6;
 
-    // Validar los datos de entrada
-    let new_patient = new_patient.into_inner();
-    new_patient.validate()?;
+/// Respuesta 400 si se registran datos de póliza sin aseguradora
+const INSURANCE_REQUIRES_PROVIDER: &str = "La póliza y su vencimiento requieren insurance_provider";
 
-    // Insertar el paciente en la base de datos
-    let patient: Patient = sqlx::query_as!(
+/// Pacientes del mismo dueño con nombre igual (sin distinguir mayúsculas) o parecido
+async fn find_duplicate_candidates(
+    pool: &PgPool,
+    client_id: i32,
+    name: &str,
+) -> Result<Vec<DuplicatePatientCandidate>, ApiError> {
+    let candidates = sqlx::query_as!(
+        DuplicatePatientCandidate,
+        r#"
+        SELECT
+            id,
+            name,
+            species as "species!: AnimalSpecies",
+            CASE
+                WHEN LOWER(name) = LOWER($2) THEN 1.0
+                ELSE similarity(name, $2)::FLOAT8
+            END as "similarity!"
+        FROM patients
+        WHERE client_id = $1
+          AND (LOWER(name) = LOWER($2) OR similarity(name, $2) >= $3)
+        ORDER BY 4 DESC, id
+        "#,
+        client_id,
+        name,
+        DUPLICATE_SIMILARITY as f32
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(candidates)
+}
+
+/// Inserta un paciente ya validado, con `custom_fields` ya validados contra
+/// sus definiciones (los de `new_patient` se ignoran)
+pub(super) async fn insert_patient(
+    executor: impl PgExecutor<'_>,
+    new_patient: NewPatient,
+    custom_fields: Value,
+) -> Result<Patient, ApiError> {
+    let patient = sqlx::query_as!(
         PatientRaw,
         r#"
         INSERT INTO patients (
@@ -47,9 +94,13 @@ async fn create_patient(
             gender,
             weight_kg,
             client_id,
-            photo_url
+            photo_url,
+            custom_fields,
+            insurance_provider,
+            insurance_policy_number,
+            insurance_expires_on
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
         RETURNING
             id,
             name,
@@ -59,7 +110,12 @@ async fn create_patient(
             gender as "gender!: Option<AnimalGender>",
             weight_kg as "weight_kg!: BigDecimal",
             client_id as "client_id!: i32",
-            photo_url
+            photo_url,
+            deceased_on,
+            custom_fields,
+            insurance_provider,
+            insurance_policy_number,
+            insurance_expires_on
         "#,
         new_patient.name.trim(),
         new_patient.species as AnimalSpecies,
@@ -70,16 +126,33 @@ async fn create_patient(
             ApiError::ValidationError("El campo weight_at_visit es obligatorio".into())
         })?),
         new_patient.client_id,
-        new_patient.photo_url.map(|s| s.trim().to_string())
+        new_patient.photo_url.map(|s| s.trim().to_string()),
+        custom_fields,
+        new_patient.insurance_provider.map(|s| s.trim().to_string()),
+        new_patient
+            .insurance_policy_number
+            .map(|s| s.trim().to_string()),
+        new_patient.insurance_expires_on
     )
-    .fetch_one(pool.get_ref())
+    .fetch_one(executor)
     .await
-    .map_err(|e| {
-        tracing::error!("Error al crear paciente: {}", e);
-        ApiError::InternalServerError("Error al guardar el paciente".into())
-    })?
-    .into();
+    .map_err(|e| match e {
+        sqlx::Error::Database(err) if err.constraint() == Some("chk_patient_insurance") => {
+            ApiError::ValidationError(INSURANCE_REQUIRES_PROVIDER.into())
+        }
+        sqlx::Error::Database(err) if err.constraint() == Some("patients_client_id_fkey") => {
+            ApiError::ValidationError("El cliente no existe".into())
+        }
+        e => ApiError::internal("Error al guardar el paciente", e),
+    })?;
+    Ok(patient.into())
+}
 
+/// Respuesta del paciente con el nombre de su raza
+pub(super) async fn patient_response(
+    pool: &PgPool,
+    patient: Patient,
+) -> Result<PatientResponse, ApiError> {
     // Obtener el nombre de la raza si existe
     let breed_name: Option<String> = if let Some(breed_id) = patient.breed_id {
         sqlx::query_scalar!(
@@ -90,21 +163,99 @@ async fn create_patient(
             "#,
             breed_id
         )
-        .fetch_optional(pool.get_ref())
+        .fetch_optional(pool)
         .await?
     } else {
         None
     };
 
-    // Construir la respuesta
     let mut response: PatientResponse = patient.into();
     response.breed = breed_name;
+    Ok(response)
+}
+
+/// Crea un nuevo paciente
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "name": "Max",
+///   "species": "Dog",
+///   "breed_id": 3,
+///   "birth_date": "2020-05-15",
+///   "gender": "Male",
+///   "weight_kg": 12.5,
+///   "client_id": 1,
+///   "photo_url": "https://example.com/max.jpg "
+/// }
+/// ```
+///
+/// Si el dueño ya tiene un paciente con el mismo nombre (o muy parecido)
+/// responde 409 con los candidatos, salvo que se envíe `?force=true`.
+/// Un peso fuera del rango plausible de la especie responde 422, salvo que se
+/// envíe `?override_warnings=true`.
+#[actix_web::post("")]
+async fn create_patient(
+    new_patient: web::Json<NewPatient>,
+    query: web::Query<CreatePatientQuery>,
+    pool: web::Data<PgPool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Creando nuevo paciente");
+
+    // Validar los datos de entrada
+    let mut new_patient = new_patient.into_inner();
+    new_patient.validate()?;
+    ensure_species_exist(pool.get_ref(), [&new_patient.species]).await?;
+    let warnings = weights::ensure_plausible(
+        &new_patient.species,
+        new_patient.weight_kg,
+        query.override_warnings,
+    )?;
+
+    // Límite del plan contratado
+    limits::ensure_within_limit(pool.get_ref(), Limit::ActivePatients).await?;
+
+    let custom_fields = validate_custom_fields(
+        pool.get_ref(),
+        CustomFieldEntity::Patient,
+        std::mem::take(&mut new_patient.custom_fields),
+    )
+    .await?;
+
+    // Posibles duplicados del mismo dueño
+    if !query.force {
+        let candidates = find_duplicate_candidates(
+            pool.get_ref(),
+            new_patient.client_id,
+            new_patient.name.trim(),
+        )
+        .await?;
+        if !candidates.is_empty() {
+            tracing::warn!(
+                "Posible paciente duplicado para el cliente {}: {:?}",
+                new_patient.client_id,
+                candidates.iter().map(|c| c.id).collect::<Vec<_>>()
+            );
+            return Err(ApiError::DetailedConflict {
+                message: "El dueño ya tiene un paciente con un nombre igual o parecido".into(),
+                details: serde_json::json!({ "candidates": candidates }),
+            });
+        }
+    }
+
+    // Insertar el paciente en la base de datos
+    let patient = insert_patient(pool.get_ref(), new_patient, custom_fields).await?;
+    let response = patient_response(pool.get_ref(), patient).await?;
 
     tracing::info!("Paciente creado exitosamente ID: {}", response.id);
+    events.publish(DomainEvent::PatientCreated {
+        patient_id: response.id,
+    });
 
     Ok(HttpResponse::Created()
         .append_header(("Location", format!("/patients/{}", response.id)))
-        .json(response))
+        .json(WithWarnings::new(response, warnings)))
 }
 
 /// Lista pacientes con filtros avanzados y paginación
@@ -115,14 +266,26 @@ async fn create_patient(
 /// - `breed_id`: Filtrar por ID de raza
 /// - `client_id`: Filtrar por ID del cliente
 /// - `gender`: Filtrar por género (MALE, FEMALE, etc.)
+/// - `insured`: `true` para pacientes con seguro vigente, `false` para los demás
+/// - `custom.<key>`: Valor exacto de un campo personalizado
 /// - `limit`: Máximo de resultados (default: 50)
 /// - `offset`: Desplazamiento (default: 0)
 #[actix_web::get("")]
 async fn list_patients(
+    req: HttpRequest,
     filters: web::Query<PatientFilter>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Listando pacientes con filtros: {:?}", &filters);
+    // Una especie que no está en el catálogo es un error, no una lista vacía
+    ensure_species_exist(pool.get_ref(), &filters.species).await?;
+
+    let custom_filters = custom_field_filters(
+        pool.get_ref(),
+        CustomFieldEntity::Patient,
+        req.query_string(),
+    )
+    .await?;
 
     let patients = sqlx::query_as!(
         PatientRaw,
@@ -136,14 +299,28 @@ async fn list_patients(
             gender as "gender!: Option<AnimalGender>",
             weight_kg as "weight_kg!: BigDecimal",
             client_id as "client_id!: i32",
-            photo_url
+            photo_url,
+            deceased_on,
+            custom_fields,
+            insurance_provider,
+            insurance_policy_number,
+            insurance_expires_on
         FROM patients
         WHERE
             ($1::text IS NULL OR name ILIKE '%' || $1 || '%') AND
-            ($2::animal_species IS NULL OR species = $2) AND
+            ($2::text IS NULL OR species = $2) AND
             ($3::int IS NULL OR breed = $3) AND
             ($4::int IS NULL OR client_id = $4) AND
-            ($5::animal_gender IS NULL OR gender = $5)
+            ($5::animal_gender IS NULL OR gender = $5) AND
+            ($9::bool IS NULL OR $9 = (
+                insurance_provider IS NOT NULL AND
+                (insurance_expires_on IS NULL OR insurance_expires_on >= $10)
+            )) AND
+            ($8::JSONB IS NULL OR NOT EXISTS (
+                SELECT 1
+                FROM jsonb_each_text($8) AS filter
+                WHERE custom_fields ->> filter.key IS DISTINCT FROM filter.value
+            ))
         ORDER BY name ASC
         LIMIT $6 OFFSET $7
         "#,
@@ -153,14 +330,14 @@ async fn list_patients(
         filters.client_id,
         &filters.gender as &Option<AnimalGender>,
         filters.limit.unwrap_or(50).min(400),
-        filters.offset.unwrap_or(0)
+        filters.offset.unwrap_or(0),
+        custom_filters,
+        filters.insured,
+        clinic::today()
     )
     .fetch_all(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al listar pacientes: {}", e);
-        ApiError::InternalServerError("Error al obtener pacientes".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al obtener pacientes", e))?;
 
     // Convertir a respuestas enriquecidas
     let mut responses = Vec::new();
@@ -190,39 +367,237 @@ async fn list_patients(
     Ok(HttpResponse::Ok().json(responses))
 }
 
-/// Obtiene un paciente por ID
+/// Lista pares de pacientes del mismo dueño con nombre igual o parecido,
+/// para revisarlos y depurarlos
 ///
-/// # Ejemplo
-/// GET /patients/1
-#[actix_web::get("/{id}")]
-async fn get_patient(
-    id: web::Path<i32>,
+/// # Parámetros (opcionales vía query string)
+/// - `limit`: Máximo de resultados (default: 50)
+/// - `offset`: Desplazamiento (default: 0)
+#[actix_web::get("/duplicates")]
+async fn list_duplicate_patients(
+    claims: Claims,
+    filters: web::Query<DuplicatePatientFilter>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
-    tracing::info!("Obteniendo paciente ID: {}", id);
+    claims.require_role(&[UserRole::Admin])?;
+    tracing::info!("Buscando pacientes duplicados");
 
-    let patient: Patient = sqlx::query_as!(
-        PatientRaw,
+    let pairs = sqlx::query_as!(
+        DuplicatePatientPair,
         r#"
         SELECT
-            id,
-            name,
-            species as "species!: AnimalSpecies",
-            breed as "breed_id!: Option<i32>",
-            birth_date,
-            gender as "gender!: Option<AnimalGender>",
-            weight_kg as "weight_kg!: BigDecimal",
-            client_id as "client_id!: i32",
-            photo_url
-        FROM patients
-        WHERE id = $1
+            a.client_id as "client_id!",
+            c.name as "client_name?",
+            a.id as patient_id,
+            a.name as patient_name,
+            b.id as duplicate_id,
+            b.name as duplicate_name,
+            CASE
+                WHEN LOWER(a.name) = LOWER(b.name) THEN 1.0
+                ELSE similarity(a.name, b.name)::FLOAT8
+            END as "similarity!"
+        FROM patients a
+        JOIN patients b ON b.client_id = a.client_id AND b.id > a.id
+        LEFT JOIN clients c ON c.id = a.client_id
+        WHERE LOWER(a.name) = LOWER(b.name) OR similarity(a.name, b.name) >= $1
+        ORDER BY 7 DESC, a.id, b.id
+        LIMIT $2 OFFSET $3
         "#,
-        id.clone()
+        DUPLICATE_SIMILARITY as f32,
+        filters.limit.unwrap_or(50).min(400),
+        filters.offset.unwrap_or(0)
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener pacientes duplicados", e))?;
+
+    Ok(HttpResponse::Ok().json(pairs))
+}
+
+/// Asigna la misma raza a varios pacientes (solo admin), por ejemplo los
+/// agrupados en `GET /admin/data-quality/breeds`.
+///
+/// Valida todos los IDs antes de escribir: si alguno no existe responde 404,
+/// y si alguno es de otra especie que la raza responde 422 con la lista; en
+/// ambos casos no se modifica ningún paciente.
+///
+/// # Ejemplo de petición
+/// ```json
+/// { "patient_ids": [3, 8, 21], "breed_id": 4 }
+/// ```
+#[actix_web::post("/bulk-set-breed")]
+async fn bulk_set_breed(
+    claims: Claims,
+    request: web::Json<BulkSetBreed>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    let request = request.into_inner();
+    request.validate()?;
+    let BulkSetBreed {
+        mut patient_ids,
+        breed_id,
+    } = request;
+    patient_ids.sort_unstable();
+    patient_ids.dedup();
+
+    let breed_species = sqlx::query_scalar!(
+        r#"SELECT species as "species!: AnimalSpecies" FROM breeds WHERE id = $1"#,
+        breed_id
     )
     .fetch_optional(pool.get_ref())
     .await?
-    .ok_or(ApiError::NotFound("El paciente no existe".into()))?
-    .into();
+    .ok_or(ApiError::NotFound("La raza no existe".into()))?;
+
+    // Bloquea los pacientes para que nadie cambie su especie entre la
+    // validación y la actualización
+    let mut tx = pool.begin().await?;
+    let patients = sqlx::query!(
+        r#"
+        SELECT id, species as "species!: AnimalSpecies"
+        FROM patients
+        WHERE id = ANY($1)
+        ORDER BY id
+        FOR UPDATE
+        "#,
+        &patient_ids
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let missing: Vec<String> = patient_ids
+        .iter()
+        .filter(|id| !patients.iter().any(|patient| patient.id == **id))
+        .map(i32::to_string)
+        .collect();
+    if !missing.is_empty() {
+        return Err(ApiError::NotFound(format!(
+            "Los pacientes no existen: {}",
+            missing.join(", ")
+        )));
+    }
+
+    let mismatched: Vec<String> = patients
+        .iter()
+        .filter(|patient| patient.species != breed_species)
+        .map(|patient| patient.id.to_string())
+        .collect();
+    if !mismatched.is_empty() {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "La raza es de la especie {:?} y estos pacientes no: {}",
+            breed_species,
+            mismatched.join(", ")
+        )));
+    }
+
+    let count = sqlx::query!(
+        "UPDATE patients SET breed = $1 WHERE id = ANY($2)",
+        breed_id,
+        &patient_ids
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al asignar la raza", e))?
+    .rows_affected() as i64;
+    tx.commit().await?;
+
+    tracing::info!("Raza {} asignada a {} pacientes", breed_id, count);
+
+    Ok(HttpResponse::Ok().json(BulkSetBreedResponse {
+        breed_id,
+        count,
+        patient_ids,
+    }))
+}
+
+/// Lista los pacientes que cumplen años en un mes o en los próximos días,
+/// con los datos de contacto del dueño para campañas
+///
+/// # Parámetros (opcionales vía query string, solo uno)
+/// - `month`: Mes (1 a 12); si ya pasó este año, el del año siguiente
+/// - `upcoming_days`: Días a partir de hoy (default: 14, máximo: 366)
+///
+/// Se excluyen pacientes fallecidos o sin `birth_date`. Los dueños con
+/// `marketing_opt_out` se incluyen marcados para no enviarles mensajes.
+#[actix_web::get("/birthdays")]
+async fn list_birthdays(
+    query: web::Query<BirthdayQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let (start, end) = query.window(clinic::today())?;
+    tracing::info!("Listando cumpleaños del {} al {}", start, end);
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            p.id,
+            p.name,
+            p.species as "species: AnimalSpecies",
+            p.birth_date as "birth_date!",
+            c.id as client_id,
+            c.name as owner_name,
+            c.phone as owner_phone,
+            c.email as owner_email,
+            c.marketing_opt_out
+        FROM patients p
+        JOIN clients c ON c.id = p.client_id
+        WHERE p.birth_date IS NOT NULL AND p.deceased_on IS NULL
+        "#
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    let mut birthdays: Vec<PatientBirthday> = rows
+        .into_iter()
+        .filter_map(|row| {
+            // La ventana abarca como máximo dos años calendario
+            let anniversary = (start.year()..=end.year())
+                .filter_map(|year| anniversary_in(row.birth_date, year))
+                .find(|date| (start..=end).contains(date))?;
+            let turning_age = anniversary.year() - row.birth_date.year();
+            (turning_age > 0).then_some(PatientBirthday {
+                patient_id: row.id,
+                patient_name: row.name,
+                species: row.species,
+                birth_date: row.birth_date,
+                anniversary,
+                turning_age,
+                client_id: row.client_id,
+                owner_name: row.owner_name,
+                owner_phone: row.owner_phone,
+                owner_email: row.owner_email,
+                marketing_opt_out: row.marketing_opt_out,
+            })
+        })
+        .collect();
+    birthdays
+        .sort_by(|a, b| (a.anniversary, &a.patient_name).cmp(&(b.anniversary, &b.patient_name)));
+
+    Ok(HttpResponse::Ok().json(birthdays))
+}
+
+/// Obtiene un paciente por ID
+///
+/// # Parámetros (opcionales vía query string)
+/// - `include`: Colecciones a incluir, separadas por comas: `appointments`,
+///   `procedures`, `records`. Un valor desconocido responde 400
+/// - `include_limit`: Elementos más recientes por colección (default: 10, máximo: 100)
+///
+/// # Ejemplo
+/// GET /patients/1?include=appointments,records&include_limit=5
+#[actix_web::get("/{id}")]
+async fn get_patient(
+    patient: LoadedPatient,
+    query: web::Query<PatientQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let patient = patient.into_inner();
+    let id = patient.id;
+    tracing::info!("Obteniendo paciente ID: {}", id);
+
+    let includes = query.includes()?;
+    let limit = query.include_limit();
 
     // Obtener el nombre de la raza si existe
     let breed_name: Option<String> = if let Some(breed_id) = patient.breed_id {
@@ -244,7 +619,30 @@ async fn get_patient(
     let mut response: PatientResponse = patient.into();
     response.breed = breed_name;
 
-    Ok(HttpResponse::Ok().json(response))
+    let mut detail = PatientDetailResponse {
+        patient: response,
+        appointments: None,
+        procedures: None,
+        records: None,
+    };
+    for include in includes {
+        match include {
+            "appointments" => {
+                detail.appointments =
+                    Some(AppointmentResponse::for_patient(pool.get_ref(), id, limit).await?)
+            }
+            "procedures" => {
+                detail.procedures =
+                    Some(PatientProcedureResponse::for_patient(pool.get_ref(), id, limit).await?)
+            }
+            _ => {
+                detail.records =
+                    Some(MedicalRecordResponse::for_patient(pool.get_ref(), id, Some(limit)).await?)
+            }
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(detail))
 }
 
 /// Actualiza un paciente existente
@@ -256,35 +654,41 @@ async fn get_patient(
 ///   "weight_kg": 13.0
 /// }
 /// ```
+///
+/// Si cambia el peso o la especie y el peso resultante está fuera del rango
+/// plausible responde 422, salvo que se envíe `?override_warnings=true`.
 #[actix_web::put("/{id}")]
 async fn update_patient(
-    id: web::Path<i32>,
+    current: LoadedPatient,
     updated_patient: web::Json<UpdatePatient>,
+    override_warnings: web::Query<WarningOverride>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
+    let id = current.id;
     tracing::info!("Actualizando paciente ID: {}", id);
 
     let updated_patient = updated_patient.into_inner();
     updated_patient.validate()?;
+    ensure_species_exist(pool.get_ref(), &updated_patient.species).await?;
 
-    // Verificar si el paciente existe
-    let exists: bool = sqlx::query_scalar(
-        r#"
-        SELECT EXISTS (
-            SELECT 1
-            FROM patients
-            WHERE id = $1
-        )
-        "#,
-    )
-    .bind(id.clone())
-    .fetch_one(pool.get_ref())
-    .await?;
+    // El peso se valida contra la especie que quedará registrada
+    let warnings = if updated_patient.weight_kg.is_some() || updated_patient.species.is_some() {
+        weights::ensure_plausible(
+            updated_patient.species.as_ref().unwrap_or(&current.species),
+            updated_patient.weight_kg.or(current.weight_kg),
+            override_warnings.override_warnings,
+        )?
+    } else {
+        Vec::new()
+    };
 
-    if !exists {
-        tracing::warn!("Intento de actualizar paciente inexistente ID: {}", id);
-        return Err(ApiError::NotFound("El paciente no existe".into()));
-    }
+    // Si se envían, reemplazan todos los campos personalizados
+    let custom_fields = match updated_patient.custom_fields {
+        Some(fields) => {
+            Some(validate_custom_fields(pool.get_ref(), CustomFieldEntity::Patient, fields).await?)
+        }
+        None => None,
+    };
 
     // Actualizar el paciente
     let patient: Patient = sqlx::query_as!(
@@ -293,14 +697,19 @@ async fn update_patient(
         UPDATE patients
         SET
             name = CASE WHEN $1::TEXT IS NOT NULL THEN $1 ELSE name END,
-            species = CASE WHEN $2::animal_species IS NOT NULL THEN $2 ELSE species END,
+            species = CASE WHEN $2::text IS NOT NULL THEN $2 ELSE species END,
             breed = CASE WHEN $3::INT IS NOT NULL THEN $3 ELSE breed END,
             birth_date = CASE WHEN $4::DATE IS NOT NULL THEN $4 ELSE birth_date END,
             gender = CASE WHEN $5::animal_gender IS NOT NULL THEN $5 ELSE gender END,
             weight_kg = CASE WHEN $6::DECIMAL IS NOT NULL THEN $6 ELSE weight_kg END,
             client_id = CASE WHEN $7::INT IS NOT NULL THEN $7 ELSE client_id END,
-            photo_url = CASE WHEN $8::TEXT IS NOT NULL THEN $8 ELSE photo_url END
-        WHERE id = $9
+            photo_url = CASE WHEN $8::TEXT IS NOT NULL THEN $8 ELSE photo_url END,
+            custom_fields = COALESCE($9, custom_fields),
+            deceased_on = CASE WHEN $11 THEN $12 ELSE deceased_on END,
+            insurance_provider = CASE WHEN $13 THEN $14 ELSE insurance_provider END,
+            insurance_policy_number = CASE WHEN $15 THEN $16 ELSE insurance_policy_number END,
+            insurance_expires_on = CASE WHEN $17 THEN $18 ELSE insurance_expires_on END
+        WHERE id = $10
         RETURNING
             id,
             name,
@@ -310,7 +719,12 @@ async fn update_patient(
             gender as "gender!: Option<AnimalGender>",
             weight_kg as "weight_kg!: BigDecimal",
             client_id as "client_id!: i32",
-            photo_url
+            photo_url,
+            deceased_on,
+            custom_fields,
+            insurance_provider,
+            insurance_policy_number,
+            insurance_expires_on
         "#,
         updated_patient.name.map(|s| s.trim().to_string()),
         updated_patient.species as Option<AnimalSpecies>,
@@ -322,13 +736,30 @@ async fn update_patient(
             .and_then(|f| BigDecimal::from_f64(f)),
         updated_patient.client_id,
         updated_patient.photo_url.map(|s| s.trim().to_string()),
-        id.clone()
+        custom_fields,
+        id,
+        updated_patient.deceased_on.is_some(),
+        updated_patient.deceased_on.flatten(),
+        updated_patient.insurance_provider.is_some(),
+        updated_patient
+            .insurance_provider
+            .flatten()
+            .map(|s| s.trim().to_string()),
+        updated_patient.insurance_policy_number.is_some(),
+        updated_patient
+            .insurance_policy_number
+            .flatten()
+            .map(|s| s.trim().to_string()),
+        updated_patient.insurance_expires_on.is_some(),
+        updated_patient.insurance_expires_on.flatten()
     )
     .fetch_one(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al actualizar paciente: {}", e);
-        ApiError::InternalServerError("Error al actualizar el paciente".into())
+    .map_err(|e| match e {
+        sqlx::Error::Database(err) if err.constraint() == Some("chk_patient_insurance") => {
+            ApiError::ValidationError(INSURANCE_REQUIRES_PROVIDER.into())
+        }
+        e => ApiError::internal("Error al actualizar el paciente", e),
     })?
     .into();
 
@@ -352,52 +783,55 @@ async fn update_patient(
     let mut response: PatientResponse = patient.into();
     response.breed = breed_name;
 
-    Ok(HttpResponse::Ok().json(response))
+    Ok(HttpResponse::Ok().json(WithWarnings::new(response, warnings)))
+}
+
+/// Filas que dependen del paciente y qué les pasaría al eliminarlo
+///
+/// # Ejemplo
+/// GET /patients/5/delete-preview
+#[actix_web::get("/{id}/delete-preview")]
+async fn delete_patient_preview(
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let preview = deletion::preview(pool.get_ref(), Resource::Patient, *id).await?;
+    Ok(HttpResponse::Ok().json(preview))
 }
 
-/// Elimina un paciente existente
+/// Elimina un paciente existente (solo administradores y veterinarios)
 ///
 /// # Ejemplo
 /// DELETE /patients/1
 #[actix_web::delete("/{id}")]
 async fn delete_patient(
-    id: web::Path<i32>,
+    patient: LoadedPatient,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
+    let id = patient.id;
     tracing::info!("Eliminando paciente ID: {}", id);
 
-    // Verificar si el paciente existe
-    let exists: bool = sqlx::query_scalar(
-        r#"
-        SELECT EXISTS (
-            SELECT 1
-            FROM patients
-            WHERE id = $1
-        )
-        "#,
-    )
-    .bind(id.clone())
-    .fetch_one(pool.get_ref())
-    .await?;
-
-    if !exists {
-        tracing::warn!("Intento de eliminar paciente inexistente ID: {}", id);
-        return Err(ApiError::NotFound("El paciente no existe".into()));
-    }
+    // Verificar que exista y que ninguna dependencia lo impida
+    deletion::ensure_deletable(pool.get_ref(), Resource::Patient, id).await?;
 
-    // Eliminar el paciente
+    // Eliminar el paciente. La condición de los folios se repite en la misma
+    // sentencia por si se creó un registro después de la verificación
     let rows_affected = sqlx::query!(
         r#"
         DELETE FROM patients
         WHERE id = $1
+          AND NOT EXISTS (
+              SELECT 1 FROM medical_records WHERE patient_id = $1 AND folio IS NOT NULL
+          )
         "#,
-        id.clone()
+        id
     )
     .execute(pool.get_ref())
     .await?
     .rows_affected();
 
     if rows_affected == 0 {
+        deletion::ensure_deletable(pool.get_ref(), Resource::Patient, id).await?;
         tracing::warn!(
             "Paciente ID {} no encontrado después de intentar eliminar",
             id
@@ -409,14 +843,505 @@ async fn delete_patient(
     Ok(HttpResponse::NoContent().finish())
 }
 
+/// Genera los procedimientos de un protocolo por especie para el paciente.
+///
+/// Las fechas se calculan desde `birth_date` (o desde hoy si no se conoce).
+/// Se omiten los procedimientos que ya existen para el paciente en esa fecha y
+/// los que quedarían en el pasado. Todo se guarda en una sola transacción.
+///
+/// # Ejemplo
+/// POST /patients/1/apply-protocol/2
+#[actix_web::post("/{id}/apply-protocol/{protocol_id}")]
+async fn apply_protocol(
+    req: HttpRequest,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let protocol_id = PathId::from_segment(&req, "protocol_id")?;
+    tracing::info!("Aplicando protocolo {} al paciente {}", protocol_id, id);
+
+    let patient = sqlx::query!(
+        r#"
+        SELECT
+            species as "species!: AnimalSpecies",
+            birth_date
+        FROM patients
+        WHERE id = $1
+        "#,
+        *id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or(ApiError::NotFound("El paciente no existe".into()))?;
+
+    let protocol = fetch_protocol(pool.get_ref(), *protocol_id).await?;
+
+    if protocol.species != patient.species {
+        return Err(ApiError::ValidationError(format!(
+            "El protocolo es para la especie {:?} y el paciente es {:?}",
+            protocol.species, patient.species
+        )));
+    }
+
+    let today = Utc::now().date_naive();
+    if let Some(birth_date) = patient.birth_date
+        && !protocol.accepts_age((today - birth_date).num_days())
+    {
+        return Err(ApiError::ValidationError(
+            "La edad del paciente está fuera del rango del protocolo".into(),
+        ));
+    }
+    let base_date = patient.birth_date.unwrap_or(today);
+
+    let items = sqlx::query!(
+        r#"
+        SELECT
+            i.procedure_id,
+            pr.name as procedure_name,
+            i.offset_days
+        FROM species_protocol_items i
+        JOIN procedures pr ON pr.id = i.procedure_id
+        WHERE i.protocol_id = $1
+        ORDER BY i.offset_days, i.id
+        "#,
+        protocol.id
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    let mut created = Vec::new();
+    let mut skipped = Vec::new();
+
+    for item in items {
+        let date = base_date + Duration::days(i64::from(item.offset_days));
+        let skip = |reason: &str| SkippedProtocolItem {
+            procedure_id: item.procedure_id,
+            procedure_name: item.procedure_name.clone(),
+            date,
+            reason: reason.to_string(),
+        };
+
+        if date < today {
+            skipped.push(skip("La fecha calculada ya pasó"));
+            continue;
+        }
+
+        let already_exists: bool = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM patient_procedures
+                WHERE patient_id = $1 AND procedure_id = $2 AND date = $3
+            ) as "exists!"
+            "#,
+            *id,
+            item.procedure_id,
+            date
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if already_exists {
+            skipped.push(skip("Ya existe para el paciente"));
+            continue;
+        }
+
+        let procedure = sqlx::query_as!(
+            PatientProcedure,
+            r#"
+            INSERT INTO patient_procedures (patient_id, procedure_id, date, notes)
+            VALUES ($1, $2, $3, $4)
+            RETURNING
+                id,
+                patient_id as "patient_id!: i32",
+                procedure_id as "procedure_id!: i32",
+                veterinarian_id as "veterinarian_id!: Option<i32>",
+                performed_by,
+                date as "date!: chrono::NaiveDate",
+                next_due_date as "next_due_date!: Option<chrono::NaiveDate>",
+                notes,
+                batch_number,
+                manufacturer
+            "#,
+            *id,
+            item.procedure_id,
+            date,
+            format!("Generado por el protocolo \"{}\"", protocol.name)
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal("Error al generar los procedimientos", e))?;
+        created.push(procedure);
+    }
+
+    tx.commit().await?;
+
+    let mut responses = Vec::with_capacity(created.len());
+    for procedure in created {
+        responses.push(PatientProcedureResponse::from_procedure(procedure, pool.get_ref()).await?);
+    }
+
+    tracing::info!(
+        "Protocolo {} aplicado al paciente {}: {} creados, {} omitidos",
+        protocol.id,
+        id,
+        responses.len(),
+        skipped.len()
+    );
+
+    Ok(HttpResponse::Ok().json(ProtocolApplicationResponse {
+        created: responses,
+        skipped,
+    }))
+}
+
+/// Interna a un paciente. Responde 409 si ya tiene una hospitalización activa.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "veterinarian_id": 1,
+///   "expected_discharge_at": "2024-06-03T18:00:00Z",
+///   "kennel": "Jaula 3",
+///   "reason": "Observación postquirúrgica"
+/// }
+/// ```
+#[actix_web::post("/{id}/hospitalizations")]
+async fn admit_patient(
+    id: PathId,
+    admission: web::Json<NewHospitalization>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Internando paciente ID: {}", id);
+
+    ensure_exists(pool.get_ref(), "patients", *id, "El paciente no existe").await?;
+
+    let admission = admission.into_inner();
+    admission.validate()?;
+
+    if let Some(veterinarian_id) = admission.veterinarian_id {
+        ensure_veterinarian(pool.get_ref(), veterinarian_id).await?;
+    }
+
+    let hospitalization_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO hospitalizations (
+            patient_id,
+            veterinarian_id,
+            admitted_at,
+            expected_discharge_at,
+            kennel,
+            reason
+        )
+        VALUES ($1, $2, COALESCE($3, NOW()), $4, $5, $6)
+        RETURNING id
+        "#,
+        *id,
+        admission.veterinarian_id,
+        admission.admitted_at,
+        admission.expected_discharge_at,
+        admission.kennel.map(|s| s.trim().to_string()),
+        admission.reason.trim()
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.constraint() == Some("idx_hospitalization_active") => {
+            ApiError::Conflict("El paciente ya está internado".into())
+        }
+        e => ApiError::internal("Error al guardar la hospitalización", e),
+    })?;
+
+    let response = HospitalizationResponse::fetch(pool.get_ref(), hospitalization_id).await?;
+
+    tracing::info!(
+        "Paciente {} internado (hospitalización {})",
+        id,
+        response.id
+    );
+
+    Ok(HttpResponse::Created()
+        .append_header(("Location", format!("/hospitalizations/{}", response.id)))
+        .json(response))
+}
+
+/// Obtiene el historial clínico del paciente: registros médicos,
+/// procedimientos y hospitalizaciones, del más reciente al más antiguo
+///
+/// # Ejemplo
+/// GET /patients/1/history
+#[actix_web::get("/{id}/history")]
+async fn get_patient_history(
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Obteniendo historial del paciente ID: {}", id);
+
+    ensure_exists(pool.get_ref(), "patients", *id, "El paciente no existe").await?;
+
+    let medical_records = MedicalRecordResponse::for_patient(pool.get_ref(), *id, None).await?;
+
+    let patient_procedures = sqlx::query_as!(
+        PatientProcedure,
+        r#"
+        SELECT
+            id,
+            patient_id as "patient_id!: i32",
+            procedure_id as "procedure_id!: i32",
+            veterinarian_id as "veterinarian_id!: Option<i32>",
+            performed_by,
+            date as "date!: chrono::NaiveDate",
+            next_due_date as "next_due_date!: Option<chrono::NaiveDate>",
+            notes,
+            batch_number,
+            manufacturer
+        FROM patient_procedures
+        WHERE patient_id = $1
+        ORDER BY date DESC
+        "#,
+        *id
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    let mut procedures = Vec::with_capacity(patient_procedures.len());
+    for procedure in patient_procedures {
+        procedures.push(PatientProcedureResponse::from_procedure(procedure, pool.get_ref()).await?);
+    }
+
+    let hospitalizations = HospitalizationResponse::for_patient(pool.get_ref(), *id).await?;
+
+    let all_referrals = ReferralFilter {
+        limit: Some(400),
+        ..Default::default()
+    };
+    let referrals = ReferralResponse::for_patient(pool.get_ref(), *id, &all_referrals).await?;
+
+    Ok(HttpResponse::Ok().json(PatientHistoryResponse {
+        patient_id: *id,
+        medical_records,
+        procedures,
+        hospitalizations,
+        referrals,
+    }))
+}
+
+/// Expediente clínico completo del paciente en un solo PDF, para entregarlo
+/// al dueño (ej: cambio de clínica).
+///
+/// Incluye datos del paciente y del dueño, cartilla de vacunación, registros
+/// médicos en orden cronológico, procedimientos y hospitalizaciones. Si se
+/// excede `DOSSIER_MAX_PAGES` (default: 200) el documento se corta con un
+/// aviso en la última página.
+///
+/// # Ejemplo
+/// GET /patients/1/dossier.pdf
+#[actix_web::get("/{id}/dossier.pdf")]
+async fn get_patient_dossier(
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Generando expediente PDF del paciente ID: {}", id);
+
+    let pdf = dossier::render(pool.get_ref(), *id).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/pdf")
+        .append_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"expediente-{}.pdf\"", *id),
+        ))
+        .body(pdf))
+}
+
+/// Reclamación al seguro de una consulta: datos de la póliza, diagnóstico y
+/// tratamiento del registro médico, y los procedimientos aplicados ese día con
+/// su precio. Responde 422 si el paciente no tiene seguro o la póliza estaba
+/// vencida el día de la consulta.
+///
+/// # Ejemplo
+/// GET /patients/1/insurance-claim?medical_record_id=12
+#[actix_web::get("/{id}/insurance-claim")]
+async fn get_insurance_claim(
+    id: PathId,
+    query: web::Query<InsuranceClaimQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let claim = claims::build(pool.get_ref(), *id, query.medical_record_id).await?;
+    Ok(HttpResponse::Ok().json(claim))
+}
+
+/// La misma reclamación en PDF
+///
+/// # Ejemplo
+/// GET /patients/1/insurance-claim.pdf?medical_record_id=12
+#[actix_web::get("/{id}/insurance-claim.pdf")]
+async fn get_insurance_claim_pdf(
+    id: PathId,
+    query: web::Query<InsuranceClaimQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!(
+        "Generando reclamación de seguro del paciente {} (registro {})",
+        id,
+        query.medical_record_id
+    );
+
+    let claim = claims::build(pool.get_ref(), *id, query.medical_record_id).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/pdf")
+        .append_header((
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"reclamacion-{}-{}.pdf\"",
+                *id, query.medical_record_id
+            ),
+        ))
+        .body(claims::render_pdf(&claim)))
+}
+
+/// Registra una referencia del paciente (recibida o enviada)
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "direction": "Received",
+///   "external_clinic": "Clínica Veterinaria del Norte",
+///   "reason": "Valoración cardiológica",
+///   "referral_date": "2024-06-01",
+///   "medical_record_id": 12
+/// }
+/// ```
+#[actix_web::post("/{id}/referrals")]
+async fn create_referral(
+    id: PathId,
+    referral: web::Json<NewReferral>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Registrando referencia del paciente ID: {}", id);
+
+    ensure_exists(pool.get_ref(), "patients", *id, "El paciente no existe").await?;
+
+    let referral = referral.into_inner();
+    referral.validate()?;
+
+    if let Some(user_id) = referral.internal_user_id {
+        let exists: bool = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM users WHERE id = $1) as "exists!""#,
+            user_id
+        )
+        .fetch_one(pool.get_ref())
+        .await?;
+        if !exists {
+            return Err(ApiError::UnprocessableEntity(format!(
+                "El usuario (ID: {}) no existe",
+                user_id
+            )));
+        }
+    }
+
+    if let Some(record_id) = referral.medical_record_id {
+        let belongs_to_patient: bool = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM medical_records WHERE id = $1 AND patient_id = $2
+            ) as "exists!"
+            "#,
+            record_id,
+            *id
+        )
+        .fetch_one(pool.get_ref())
+        .await?;
+        if !belongs_to_patient {
+            return Err(ApiError::UnprocessableEntity(format!(
+                "El registro médico (ID: {}) no existe para este paciente",
+                record_id
+            )));
+        }
+    }
+
+    let referral_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO referrals (
+            patient_id,
+            direction,
+            internal_user_id,
+            external_clinic,
+            reason,
+            referral_date,
+            medical_record_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        "#,
+        *id,
+        referral.direction as ReferralDirection,
+        referral.internal_user_id,
+        referral.external_clinic.map(|s| s.trim().to_string()),
+        referral.reason.trim(),
+        referral.referral_date.unwrap_or_else(clinic::today),
+        referral.medical_record_id
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al guardar la referencia", e))?;
+
+    let response = ReferralResponse::fetch(pool.get_ref(), referral_id).await?;
+
+    tracing::info!("Referencia {} registrada", referral_id);
+
+    Ok(HttpResponse::Created()
+        .append_header((
+            "Location",
+            format!("/patients/{}/referrals", response.patient_id),
+        ))
+        .json(response))
+}
+
+/// Lista las referencias de un paciente
+///
+/// # Parámetros (opcionales vía query string)
+/// - `direction`: Received o Sent
+/// - `from_date` / `to_date`: Rango de fechas de la referencia
+/// - `limit`: Máximo de resultados (default: 50)
+/// - `offset`: Desplazamiento (default: 0)
+#[actix_web::get("/{id}/referrals")]
+async fn list_referrals(
+    id: PathId,
+    filters: web::Query<ReferralFilter>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Listando referencias del paciente ID: {}", id);
+
+    ensure_exists(pool.get_ref(), "patients", *id, "El paciente no existe").await?;
+
+    let referrals = ReferralResponse::for_patient(pool.get_ref(), *id, &filters).await?;
+
+    Ok(HttpResponse::Ok().json(referrals))
+}
+
 // Exporta todas las funciones como un grupo
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/patients")
             .service(create_patient)
             .service(list_patients)
+            .service(list_duplicate_patients) // Antes de "/{id}"
+            .service(list_birthdays) // Antes de "/{id}"
+            .service(bulk_set_breed)
             .service(get_patient)
             .service(update_patient)
-            .service(delete_patient), // Agrega más servicios aquí...
+            .service(delete_patient_preview)
+            .service(delete_patient)
+            .service(apply_protocol)
+            .service(admit_patient)
+            .service(get_patient_history)
+            .service(get_patient_dossier)
+            .service(get_insurance_claim)
+            .service(get_insurance_claim_pdf)
+            .service(create_referral)
+            .service(list_referrals), // Agrega más servicios aquí...
     );
 }
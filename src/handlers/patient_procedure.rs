@@ -1,10 +1,21 @@
+use crate::auth::Claims;
 use crate::errors::ApiError;
+use crate::extractors::PathId;
+use crate::handlers::ensure_exists;
+use crate::models::consent::{
+    CONSENT_CONTENT_TYPES, CONSENT_MAX_BYTES, ConsentDocumentQuery, ConsentResponse,
+    NewDigitalConsent,
+};
+use crate::models::enums::UserRole;
 use crate::models::patient_procedure::{
+    BulkReschedule, BulkRescheduleQuery, BulkRescheduleResponse, CreatePatientProcedureQuery,
     NewPatientProcedure, PatientProcedure, PatientProcedureFilter, PatientProcedureResponse,
-    UpdatePatientProcedure,
+    PatientProcedureWithOwner, UpdatePatientProcedure, ensure_active_performer,
+    ensure_surgery_consent, ensure_vaccine_batch, species_warning,
 };
+use crate::models::warning::{WarningOverride, WithWarnings};
 
-use actix_web::{HttpResponse, web};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, web};
 use sqlx::PgPool;
 use validator::Validate;
 
@@ -16,14 +27,31 @@ use validator::Validate;
 ///   "patient_id": 1,
 ///   "procedure_id": 2,
 ///   "veterinarian_id": 3,
+///   "performed_by": 5,
 ///   "date": "2025-05-15",
 ///   "next_due_date": "2026-05-15",
-///   "notes": "Procedimiento de rutina"
+///   "notes": "Procedimiento de rutina",
+///   "batch_number": "L2025-0412",
+///   "manufacturer": "Zoetis"
 /// }
 /// ```
+///
+/// Con `REQUIRE_VACCINE_BATCH` activo, una vacuna sin `batch_number` responde 422.
+/// Con `REQUIRE_SURGERY_CONSENT` activo, una cirugía sin `?consent_waived=true`
+/// responde 422; con él se registra y el consentimiento se adjunta después en
+/// `POST /patient_procedures/{id}/consent`, dentro de 24 h.
+///
+/// Si el procedimiento no es habitual para la especie del paciente responde
+/// 400, salvo que se envíe `?override_warnings=true`; en ese caso se crea y la
+/// advertencia se incluye en `warnings`.
+///
+/// Si el paciente ya tiene ese mismo procedimiento en la misma fecha (por
+/// ejemplo, por un doble clic al guardar) responde 409 con el `existing_id`;
+/// `?allow_duplicate=true` lo registra de todos modos.
 #[actix_web::post("")]
 async fn create_patient_procedure(
     new_procedure: web::Json<NewPatientProcedure>,
+    query: web::Query<CreatePatientProcedureQuery>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Creando nuevo procedimiento");
@@ -32,6 +60,75 @@ async fn create_patient_procedure(
     let new_procedure = new_procedure.into_inner();
     new_procedure.validate()?;
     // validate_date_pair(&new_procedure)?;
+    if let Some(performed_by) = new_procedure.performed_by {
+        ensure_active_performer(pool.get_ref(), performed_by).await?;
+    }
+    ensure_vaccine_batch(
+        pool.get_ref(),
+        new_procedure.procedure_id,
+        new_procedure.batch_number.as_deref(),
+    )
+    .await?;
+    ensure_surgery_consent(
+        pool.get_ref(),
+        new_procedure.procedure_id,
+        query.consent_waived,
+    )
+    .await?;
+
+    // Advertencias que el usuario puede aceptar
+    let warnings = species_warning(
+        pool.get_ref(),
+        new_procedure.patient_id,
+        new_procedure.procedure_id,
+    )
+    .await?
+    .into_iter()
+    .collect();
+    let warnings = WarningOverride {
+        override_warnings: query.override_warnings,
+    }
+    .resolve(warnings)?;
+
+    let mut tx = pool.begin().await?;
+
+    // Bloquear al paciente para que dos envíos simultáneos no pasen ambos la
+    // verificación de duplicados
+    sqlx::query_scalar!(
+        "SELECT id FROM patients WHERE id = $1 FOR UPDATE",
+        new_procedure.patient_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if !query.allow_duplicate {
+        let existing_id = sqlx::query_scalar!(
+            r#"
+            SELECT id
+            FROM patient_procedures
+            WHERE patient_id = $1 AND procedure_id = $2 AND date = $3
+            ORDER BY id
+            LIMIT 1
+            "#,
+            new_procedure.patient_id,
+            new_procedure.procedure_id,
+            new_procedure.date
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(existing_id) = existing_id {
+            tracing::warn!(
+                "Procedimiento duplicado para el paciente {}: ya existe el registro {}",
+                new_procedure.patient_id,
+                existing_id
+            );
+            return Err(ApiError::DetailedConflict {
+                message: "El paciente ya tiene este procedimiento registrado en esa fecha".into(),
+                details: serde_json::json!({ "existing_id": existing_id }),
+            });
+        }
+    }
 
     // Insertar el procedimiento en la base de datos
     let procedure = sqlx::query_as!(
@@ -41,33 +138,41 @@ async fn create_patient_procedure(
             patient_id,
             procedure_id,
             veterinarian_id,
+            performed_by,
             date,
             next_due_date,
-            notes
+            notes,
+            batch_number,
+            manufacturer
         )
-        VALUES ($1, $2, $3, $4, $5, $6)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         RETURNING
             id,
             patient_id as "patient_id!: i32",
             procedure_id as "procedure_id!: i32",
             veterinarian_id as "veterinarian_id!: Option<i32>",
+            performed_by,
             date as "date!: chrono::NaiveDate",
             next_due_date as "next_due_date!: Option<chrono::NaiveDate>",
-            notes
+            notes,
+            batch_number,
+            manufacturer
         "#,
         new_procedure.patient_id,
         new_procedure.procedure_id,
         new_procedure.veterinarian_id,
+        new_procedure.performed_by,
         new_procedure.date,
         new_procedure.next_due_date,
-        new_procedure.notes.map(|s| s.trim().to_string())
+        new_procedure.notes.map(|s| s.trim().to_string()),
+        new_procedure.batch_number.as_deref().map(str::trim),
+        new_procedure.manufacturer.as_deref().map(str::trim)
     )
-    .fetch_one(pool.get_ref())
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| {
-        tracing::error!("Error al crear procedimiento: {}", e);
-        ApiError::InternalServerError("Error al guardar el procedimiento".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al guardar el procedimiento", e))?;
+
+    tx.commit().await?;
 
     tracing::info!("Procedimiento creado exitosamente ID: {}", procedure.id);
 
@@ -76,7 +181,7 @@ async fn create_patient_procedure(
 
     Ok(HttpResponse::Created()
         .append_header(("Location", format!("/patient-procedures/{}", response.id)))
-        .json(response))
+        .json(WithWarnings::new(response, warnings)))
 }
 
 /// Lista procedimientos con filtros avanzados y paginación
@@ -87,11 +192,14 @@ async fn create_patient_procedure(
 /// - `veterinarian_id`: Filtrar por ID del veterinario
 /// - `start_date`: Filtrar por fecha mínima
 /// - `end_date`: Filtrar por fecha máxima
+/// - `batch_number`: Lote aplicado (sin distinguir mayúsculas); cada resultado
+///   incluye el contacto del dueño para avisar en un retiro
 /// - `limit`: Máximo de resultados (default: 50)
 /// - `offset`: Desplazamiento (default: 0)
 ///
 /// # Ejemplo
 /// GET /patient-procedures?patient_id=1&start_date=2023-01-01&limit=10
+/// GET /patient-procedures?batch_number=L2025-0412&limit=400
 #[actix_web::get("")]
 async fn list_patient_procedures(
     filters: web::Query<PatientProcedureFilter>,
@@ -107,16 +215,20 @@ async fn list_patient_procedures(
             patient_id as "patient_id!: i32",
             procedure_id as "procedure_id!: i32",
             veterinarian_id as "veterinarian_id!: Option<i32>",
+            performed_by,
             date as "date!: chrono::NaiveDate",
             next_due_date as "next_due_date!: Option<chrono::NaiveDate>",
-            notes
+            notes,
+            batch_number,
+            manufacturer
         FROM patient_procedures
         WHERE
             ($1::int IS NULL OR patient_id = $1) AND
             ($2::int IS NULL OR procedure_id = $2) AND
             ($3::int IS NULL OR veterinarian_id = $3) AND
             ($4::date IS NULL OR date >= $4) AND
-            ($5::date IS NULL OR date <= $5)
+            ($5::date IS NULL OR date <= $5) AND
+            ($8::text IS NULL OR LOWER(batch_number) = LOWER($8))
         ORDER BY date DESC
         LIMIT $6 OFFSET $7
         "#,
@@ -126,14 +238,12 @@ async fn list_patient_procedures(
         filters.start_date,
         filters.end_date,
         filters.limit.unwrap_or(50).min(400),
-        filters.offset.unwrap_or(0)
+        filters.offset.unwrap_or(0),
+        filters.batch_number.as_deref().map(str::trim)
     )
     .fetch_all(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al listar procedimientos: {}", e);
-        ApiError::InternalServerError("Error al obtener procedimientos".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al obtener procedimientos", e))?;
 
     // Convertir a respuestas enriquecidas
     let responses = futures::future::try_join_all(procedures.into_iter().map(|procedure| async {
@@ -141,16 +251,142 @@ async fn list_patient_procedures(
     }))
     .await?;
 
+    if filters.batch_number.is_none() {
+        return Ok(HttpResponse::Ok().json(responses));
+    }
+
+    // Retiro de un lote: contacto de los dueños de todos los pacientes juntos
+    let patient_ids: Vec<i32> = responses.iter().map(|r| r.patient_id).collect();
+    let owners: std::collections::HashMap<i32, _> = sqlx::query!(
+        r#"
+        SELECT
+            p.id,
+            c.id as "client_id?",
+            c.name as "client_name?",
+            c.phone as "client_phone?",
+            c.email as "client_email?"
+        FROM patients p
+        LEFT JOIN clients c ON c.id = p.client_id
+        WHERE p.id = ANY($1)
+        "#,
+        &patient_ids
+    )
+    .fetch_all(pool.get_ref())
+    .await?
+    .into_iter()
+    .map(|owner| (owner.id, owner))
+    .collect();
+
+    let responses: Vec<PatientProcedureWithOwner> = responses
+        .into_iter()
+        .map(|procedure| {
+            let owner = owners.get(&procedure.patient_id);
+            PatientProcedureWithOwner {
+                client_id: owner.and_then(|o| o.client_id),
+                client_name: owner.and_then(|o| o.client_name.clone()),
+                client_phone: owner.and_then(|o| o.client_phone.clone()),
+                client_email: owner.and_then(|o| o.client_email.clone()),
+                procedure,
+            }
+        })
+        .collect();
+
     Ok(HttpResponse::Ok().json(responses))
 }
 
+/// Reprograma en bloque el próximo vencimiento de un procedimiento (solo
+/// admin), por ejemplo cuando el fabricante retira un lote de vacunas.
+///
+/// Actualiza en una sola sentencia todos los registros del procedimiento
+/// aplicados en el rango, con `interval_days` desde la fecha de aplicación o
+/// con un `next_due_date` fijo. Devuelve los pacientes afectados para avisar
+/// a sus dueños; con `?dry_run=true` solo los reporta.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "procedure_id": 2,
+///   "administered_from": "2024-03-01",
+///   "administered_to": "2024-04-15",
+///   "interval_days": 30
+/// }
+/// ```
+#[actix_web::post("/bulk-reschedule")]
+async fn bulk_reschedule(
+    claims: Claims,
+    reschedule: web::Json<BulkReschedule>,
+    query: web::Query<BulkRescheduleQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    let reschedule = reschedule.into_inner();
+    reschedule.validate()?;
+    ensure_exists(
+        pool.get_ref(),
+        "procedures",
+        reschedule.procedure_id,
+        "El procedimiento no existe",
+    )
+    .await?;
+
+    let mut patient_ids: Vec<i32> = if query.dry_run {
+        sqlx::query_scalar!(
+            r#"
+            SELECT patient_id as "patient_id!"
+            FROM patient_procedures
+            WHERE procedure_id = $1 AND date BETWEEN $2 AND $3
+            "#,
+            reschedule.procedure_id,
+            reschedule.administered_from,
+            reschedule.administered_to
+        )
+        .fetch_all(pool.get_ref())
+        .await?
+    } else {
+        sqlx::query_scalar!(
+            r#"
+            UPDATE patient_procedures
+            SET next_due_date = COALESCE($4::date, date + $5::int)
+            WHERE procedure_id = $1 AND date BETWEEN $2 AND $3
+            RETURNING patient_id as "patient_id!"
+            "#,
+            reschedule.procedure_id,
+            reschedule.administered_from,
+            reschedule.administered_to,
+            reschedule.next_due_date,
+            reschedule.interval_days
+        )
+        .fetch_all(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal("Error al reprogramar los procedimientos", e))?
+    };
+    let count = patient_ids.len() as i64;
+    patient_ids.sort_unstable();
+    patient_ids.dedup();
+
+    tracing::info!(
+        "Reprogramación del procedimiento {}: {} registros de {} pacientes (dry_run: {})",
+        reschedule.procedure_id,
+        count,
+        patient_ids.len(),
+        query.dry_run
+    );
+
+    Ok(HttpResponse::Ok().json(BulkRescheduleResponse {
+        dry_run: query.dry_run,
+        count,
+        patient_ids,
+    }))
+}
+
 /// Obtiene un procedimiento por ID
 ///
 /// # Ejemplo
 /// GET /patient-procedures/1
 #[actix_web::get("/{id}")]
 async fn get_patient_procedure(
-    id: web::Path<i32>,
+    id: PathId,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Obteniendo procedimiento ID: {}", id);
@@ -163,13 +399,16 @@ async fn get_patient_procedure(
             patient_id as "patient_id!: i32",
             procedure_id as "procedure_id!: i32",
             veterinarian_id as "veterinarian_id!: Option<i32>",
+            performed_by,
             date as "date!: chrono::NaiveDate",
             next_due_date as "next_due_date!: Option<chrono::NaiveDate>",
-            notes
+            notes,
+            batch_number,
+            manufacturer
         FROM patient_procedures
         WHERE id = $1
         "#,
-        id.clone()
+        *id
     )
     .fetch_optional(pool.get_ref())
     .await?
@@ -190,35 +429,76 @@ async fn get_patient_procedure(
 ///   "notes": "Actualización de notas"
 /// }
 /// ```
+///
+/// Acepta `?override_warnings=true` igual que la creación.
 #[actix_web::put("/{id}")]
 async fn update_patient_procedure(
-    id: web::Path<i32>,
+    id: PathId,
     updated_procedure: web::Json<UpdatePatientProcedure>,
+    override_warnings: web::Query<WarningOverride>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Actualizando procedimiento ID: {}", id);
 
+    // Verificar si el procedimiento existe
+    ensure_exists(
+        pool.get_ref(),
+        "patient_procedures",
+        *id,
+        "El procedimiento no existe",
+    )
+    .await?;
+
     let updated_procedure = updated_procedure.into_inner();
     updated_procedure.validate()?;
+    if let Some(Some(performed_by)) = updated_procedure.performed_by {
+        ensure_active_performer(pool.get_ref(), performed_by).await?;
+    }
 
-    // Verificar si el procedimiento existe
-    let exists: bool = sqlx::query_scalar(
-        r#"
-        SELECT EXISTS (
-            SELECT 1
+    // Solo se revisan la especie y el lote si cambia el paciente, el
+    // procedimiento o el lote
+    let mut warnings = Vec::new();
+    if updated_procedure.patient_id.is_some()
+        || updated_procedure.procedure_id.is_some()
+        || updated_procedure.batch_number.is_some()
+    {
+        let current = sqlx::query!(
+            r#"
+            SELECT
+                patient_id as "patient_id!: i32",
+                procedure_id as "procedure_id!: i32",
+                batch_number
             FROM patient_procedures
             WHERE id = $1
+            "#,
+            *id
         )
-        "#,
-    )
-    .bind(id.clone())
-    .fetch_one(pool.get_ref())
-    .await?;
+        .fetch_one(pool.get_ref())
+        .await?;
 
-    if !exists {
-        tracing::warn!("Intento de actualizar procedimiento inexistente ID: {}", id);
-        return Err(ApiError::NotFound("El procedimiento no existe".into()));
+        ensure_vaccine_batch(
+            pool.get_ref(),
+            updated_procedure
+                .procedure_id
+                .unwrap_or(current.procedure_id),
+            match &updated_procedure.batch_number {
+                Some(batch_number) => batch_number.as_deref(),
+                None => current.batch_number.as_deref(),
+            },
+        )
+        .await?;
+        warnings.extend(
+            species_warning(
+                pool.get_ref(),
+                updated_procedure.patient_id.unwrap_or(current.patient_id),
+                updated_procedure
+                    .procedure_id
+                    .unwrap_or(current.procedure_id),
+            )
+            .await?,
+        );
     }
+    let warnings = override_warnings.resolve(warnings)?;
 
     // Actualizar el procedimiento
     let procedure = sqlx::query_as!(
@@ -231,16 +511,30 @@ async fn update_patient_procedure(
             veterinarian_id = CASE WHEN $3::INT IS NOT NULL THEN $3 ELSE veterinarian_id END,
             date = CASE WHEN $4::DATE IS NOT NULL THEN $4 ELSE date END,
             next_due_date = CASE WHEN $5::DATE IS NOT NULL THEN $5 ELSE next_due_date END,
-            notes = CASE WHEN $6::TEXT IS NOT NULL THEN $6 ELSE notes END
+            notes = CASE WHEN $6::TEXT IS NOT NULL THEN $6 ELSE notes END,
+            performed_by = CASE WHEN $8::INT IS NOT NULL THEN $8 ELSE performed_by END,
+            batch_number = CASE
+                WHEN $9::TEXT IS NOT NULL THEN $9 -- Nuevo valor
+                WHEN $10::BOOLEAN THEN NULL -- Borrar el valor
+                ELSE batch_number -- Mantener el valor existente
+            END,
+            manufacturer = CASE
+                WHEN $11::TEXT IS NOT NULL THEN $11
+                WHEN $12::BOOLEAN THEN NULL
+                ELSE manufacturer
+            END
         WHERE id = $7
         RETURNING
             id,
             patient_id as "patient_id!: i32",
             procedure_id as "procedure_id!: i32",
             veterinarian_id as "veterinarian_id!: Option<i32>",
+            performed_by,
             date as "date!: chrono::NaiveDate",
             next_due_date as "next_due_date!: Option<chrono::NaiveDate>",
-            notes
+            notes,
+            batch_number,
+            manufacturer
         "#,
         updated_procedure.patient_id,
         updated_procedure.procedure_id,
@@ -251,19 +545,29 @@ async fn update_patient_procedure(
             .notes
             .flatten()
             .map(|s| s.trim().to_string()),
-        id.clone()
+        *id,
+        updated_procedure.performed_by.flatten(),
+        updated_procedure
+            .batch_number
+            .as_ref()
+            .and_then(|batch| batch.as_deref())
+            .map(str::trim),
+        matches!(updated_procedure.batch_number, Some(None)),
+        updated_procedure
+            .manufacturer
+            .as_ref()
+            .and_then(|manufacturer| manufacturer.as_deref())
+            .map(str::trim),
+        matches!(updated_procedure.manufacturer, Some(None))
     )
     .fetch_one(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al actualizar procedimiento: {}", e);
-        ApiError::InternalServerError("Error al actualizar el procedimiento".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al actualizar el procedimiento", e))?;
 
     // Convertir a respuesta enriquecida
     let response = PatientProcedureResponse::from_procedure(procedure, pool.get_ref()).await?;
 
-    Ok(HttpResponse::Ok().json(response))
+    Ok(HttpResponse::Ok().json(WithWarnings::new(response, warnings)))
 }
 
 /// Elimina un procedimiento existente
@@ -272,52 +576,223 @@ async fn update_patient_procedure(
 /// DELETE /patient-procedures/1
 #[actix_web::delete("/{id}")]
 async fn delete_patient_procedure(
-    id: web::Path<i32>,
+    id: PathId,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Eliminando procedimiento ID: {}", id);
 
-    // Verificar si el procedimiento existe
-    let exists: bool = sqlx::query_scalar(
+    // Eliminar el procedimiento; si no regresa fila es que no existía
+    sqlx::query_scalar!(
         r#"
-        SELECT EXISTS (
-            SELECT 1
-            FROM patient_procedures
-            WHERE id = $1
-        )
+        DELETE FROM patient_procedures
+        WHERE id = $1
+        RETURNING id
         "#,
+        *id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or(ApiError::NotFound("El procedimiento no existe".into()))?;
+
+    tracing::info!("Procedimiento ID {} eliminado exitosamente", id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Consentimiento registrado de un procedimiento, si lo tiene
+async fn fetch_consent(
+    pool: &PgPool,
+    patient_procedure_id: i32,
+) -> Result<Option<ConsentResponse>, ApiError> {
+    sqlx::query_as!(
+        ConsentResponse,
+        r#"
+        SELECT
+            id,
+            patient_procedure_id,
+            CASE WHEN document IS NULL THEN 'digital' ELSE 'document' END as "kind!",
+            client_name,
+            accepted_at,
+            ip_address,
+            content_type,
+            LENGTH(document) as document_size,
+            recorded_by
+        FROM procedure_consents
+        WHERE patient_procedure_id = $1
+        "#,
+        patient_procedure_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener el consentimiento", e))
+}
+
+/// Registra el consentimiento firmado de un procedimiento (ej: una cirugía)
+///
+/// Acepta una de dos formas, según el `Content-Type`:
+/// - `application/json`: aceptación digital escrita; se guardan el nombre, la
+///   fecha y la IP desde la que se envía.
+/// - `application/pdf`, `image/png` o `image/jpeg`: el documento firmado
+///   escaneado como cuerpo de la petición (máx. 5 MB). `?client_name=` es
+///   opcional.
+///
+/// Cada procedimiento tiene un solo consentimiento; si ya existe responde 409.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "client_name": "Ana Pérez López"
+/// }
+/// ```
+#[actix_web::post("/{id}/consent")]
+async fn create_consent(
+    claims: Option<Claims>,
+    id: PathId,
+    req: HttpRequest,
+    body: web::Bytes,
+    query: web::Query<ConsentDocumentQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Registrando consentimiento del procedimiento ID: {}", id);
+
+    ensure_exists(
+        pool.get_ref(),
+        "patient_procedures",
+        *id,
+        "Procedimiento no encontrado",
     )
-    .bind(id.clone())
-    .fetch_one(pool.get_ref())
     .await?;
 
-    if !exists {
-        tracing::warn!("Intento de eliminar procedimiento inexistente ID: {}", id);
-        return Err(ApiError::NotFound("El procedimiento no existe".into()));
-    }
+    let (client_name, content_type, document) = if req.content_type() == "application/json" {
+        let consent: NewDigitalConsent = serde_json::from_slice(&body)
+            .map_err(|e| ApiError::ValidationError(format!("JSON inválido: {e}")))?;
+        consent.validate()?;
+        (Some(consent.client_name.trim().to_string()), None, None)
+    } else {
+        let (content_type, signature) = CONSENT_CONTENT_TYPES
+            .iter()
+            .find(|(content_type, _)| *content_type == req.content_type())
+            .ok_or(ApiError::ValidationError(
+                "Envía JSON con client_name o el documento firmado como application/pdf, image/png o image/jpeg".into(),
+            ))?;
+        if !body.starts_with(signature) {
+            return Err(ApiError::ValidationError(format!(
+                "El archivo no es un {content_type} válido"
+            )));
+        }
+        query.validate()?;
+        (
+            query
+                .client_name
+                .as_deref()
+                .map(|name| name.trim().to_string()),
+            Some(content_type.to_string()),
+            Some(body.to_vec()),
+        )
+    };
+    let ip_address = req
+        .connection_info()
+        .realip_remote_addr()
+        .map(str::to_string);
 
-    // Eliminar el procedimiento
-    let rows_affected = sqlx::query!(
+    let inserted = sqlx::query_scalar!(
         r#"
-        DELETE FROM patient_procedures
-        WHERE id = $1
+        INSERT INTO procedure_consents (
+            patient_procedure_id,
+            client_name,
+            ip_address,
+            content_type,
+            document,
+            recorded_by
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (patient_procedure_id) DO NOTHING
+        RETURNING id
         "#,
-        id.clone()
+        *id,
+        client_name,
+        ip_address,
+        content_type,
+        document,
+        claims.map(|claims| claims.sub)
     )
-    .execute(pool.get_ref())
-    .await?
-    .rows_affected();
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al guardar el consentimiento", e))?;
 
-    if rows_affected == 0 {
-        tracing::warn!(
-            "Procedimiento ID {} no encontrado después de intentar eliminar",
-            id
-        );
-        return Err(ApiError::NotFound("El procedimiento no existe".into()));
+    if inserted.is_none() {
+        return Err(ApiError::Conflict(
+            "El procedimiento ya tiene un consentimiento registrado".into(),
+        ));
     }
 
-    tracing::info!("Procedimiento ID {} eliminado exitosamente", id);
-    Ok(HttpResponse::NoContent().finish())
+    let response = fetch_consent(pool.get_ref(), *id)
+        .await?
+        .ok_or(ApiError::NotFound("El consentimiento no existe".into()))?;
+    tracing::info!(
+        "Consentimiento {} registrado para el procedimiento {}",
+        response.id,
+        id
+    );
+
+    Ok(HttpResponse::Created()
+        .append_header(("Location", format!("/patient_procedures/{}/consent", *id)))
+        .json(response))
+}
+
+/// Obtiene el consentimiento de un procedimiento; 404 si no tiene
+///
+/// # Ejemplo
+/// GET /patient_procedures/1/consent
+#[actix_web::get("/{id}/consent")]
+async fn get_consent(id: PathId, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    ensure_exists(
+        pool.get_ref(),
+        "patient_procedures",
+        *id,
+        "Procedimiento no encontrado",
+    )
+    .await?;
+
+    let consent = fetch_consent(pool.get_ref(), *id)
+        .await?
+        .ok_or(ApiError::NotFound(
+            "El procedimiento no tiene consentimiento registrado".into(),
+        ))?;
+
+    Ok(HttpResponse::Ok().json(consent))
+}
+
+/// Descarga el documento firmado del consentimiento; 404 si el procedimiento
+/// no tiene consentimiento o si es una aceptación digital
+///
+/// # Ejemplo
+/// GET /patient_procedures/1/consent/document
+#[actix_web::get("/{id}/consent/document")]
+async fn get_consent_document(
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let document = sqlx::query!(
+        r#"
+        SELECT content_type as "content_type!", document as "document!"
+        FROM procedure_consents
+        WHERE patient_procedure_id = $1 AND document IS NOT NULL
+        "#,
+        *id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or(ApiError::NotFound(
+        "El procedimiento no tiene un documento de consentimiento".into(),
+    ))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(document.content_type)
+        .append_header((
+            "Content-Disposition",
+            format!("inline; filename=\"consentimiento-{}\"", *id),
+        ))
+        .body(document.document))
 }
 
 // Exporta todas las funciones como un grupo
@@ -326,8 +801,37 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         web::scope("/patient_procedures")
             .service(create_patient_procedure)
             .service(list_patient_procedures)
+            .service(bulk_reschedule)
             .service(get_patient_procedure)
             .service(update_patient_procedure)
-            .service(delete_patient_procedure), // Agrega más servicios aquí...
+            .service(delete_patient_procedure)
+            .service(create_consent)
+            .service(get_consent)
+            .service(get_consent_document)
+            .app_data(web::PayloadConfig::new(CONSENT_MAX_BYTES)), // Agrega más servicios aquí...
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{claims, concurrent_deletes};
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn concurrent_deletes_remove_the_procedure_once(pool: PgPool) {
+        let id: i32 = sqlx::query_scalar(
+            "INSERT INTO patient_procedures (patient_id, procedure_id, veterinarian_id, date)
+             VALUES (1, (SELECT MIN(id) FROM procedures), 1, CURRENT_DATE) RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let uri = format!("/api/patient_procedures/{id}");
+        let statuses = concurrent_deletes(pool, &uri, claims(1, UserRole::Veterinarian)).await;
+        assert_eq!(statuses, [204, 404]);
+    }
+}
@@ -1,10 +1,22 @@
+use crate::auth::Claims;
+use crate::deletion::{self, Resource};
 use crate::errors::ApiError;
-use crate::models::enums::ProcedureType;
+use crate::extractors::{Locale, PathId};
+use crate::handlers::species::ensure_species_exist;
+use crate::handlers::{
+    catalog_cache_headers, catalog_last_modified, catalog_not_modified, ensure_exists,
+    record_catalog_deletion,
+};
+use crate::models::enums::{AnimalSpecies, ProcedureType, UserRole};
 use crate::models::procedure::{
-    NewProcedure, Procedure, ProcedureFilter, ProcedureResponse, UpdateProcedure,
+    CATALOG_VERSION, CatalogProcedure, ImportAction, MergeStrategy, NewProcedure, Procedure,
+    ProcedureCatalog, ProcedureFilter, ProcedureImportItem, ProcedureImportQuery,
+    ProcedureImportResponse, ProcedureResponse, UpdateProcedure,
 };
+use std::collections::HashSet;
 
-use actix_web::{HttpResponse, web};
+use actix_web::http::header::VARY;
+use actix_web::{HttpRequest, HttpResponse, web};
 use sqlx::PgPool;
 use validator::Validate;
 
@@ -22,6 +34,7 @@ use validator::Validate;
 #[actix_web::post("")]
 async fn create_procedure(
     new_procedure: web::Json<NewProcedure>,
+    locale: Locale,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Creando nuevo procedimiento");
@@ -29,6 +42,7 @@ async fn create_procedure(
     // Validar los datos de entrada
     let new_procedure = new_procedure.into_inner();
     new_procedure.validate()?;
+    ensure_species_exist(pool.get_ref(), &new_procedure.species).await?;
 
     // Insertar el procedimiento en la base de datos
     let procedure = sqlx::query_as!(
@@ -38,30 +52,38 @@ async fn create_procedure(
             name,
             type,
             description,
-            duration_minutes
+            duration_minutes,
+            species,
+            price,
+            required_room_type
         )
-        VALUES ($1, $2, $3, $4)
+        VALUES ($1, $2, $3, $4, $5, $6::FLOAT8, $7)
         RETURNING
             id,
             name,
             type as "procedure_type!: ProcedureType",
             description,
-            duration_minutes
+            duration_minutes,
+            species as "species!: Vec<AnimalSpecies>",
+            price::FLOAT8 as price,
+            required_room_type
         "#,
         new_procedure.name.trim(),
         new_procedure.procedure_type as ProcedureType,
         new_procedure.description.map(|s| s.trim().to_string()),
-        new_procedure.duration_minutes
+        new_procedure.duration_minutes,
+        &new_procedure.species as &[AnimalSpecies],
+        new_procedure.price,
+        new_procedure
+            .required_room_type
+            .map(|s| s.trim().to_string())
     )
     .fetch_one(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al crear procedimiento: {}", e);
-        ApiError::InternalServerError("Error al guardar el procedimiento".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al guardar el procedimiento", e))?;
 
     // Convertir a respuesta enriquecida
-    let response = ProcedureResponse::from(procedure);
+    let response = ProcedureResponse::localized(procedure, locale);
 
     tracing::info!("Procedimiento creado exitosamente ID: {}", response.id);
 
@@ -80,15 +102,25 @@ async fn create_procedure(
 /// - `limit`: Máximo de resultados (default: 50)
 /// - `offset`: Desplazamiento (default: 0)
 ///
+/// Responde `Last-Modified` y honra `If-Modified-Since` con 304 si el
+/// catálogo no cambió.
+///
 /// # Ejemplo
 /// GET /procedures?name_contains=dental&limit=10
 #[actix_web::get("")]
 async fn list_procedures(
+    req: HttpRequest,
     filters: web::Query<ProcedureFilter>,
+    locale: Locale,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Listando procedimientos con filtros: {:?}", &filters);
 
+    let last_modified = catalog_last_modified(pool.get_ref(), "procedures").await?;
+    if let Some(not_modified) = catalog_not_modified(&req, last_modified) {
+        return Ok(not_modified);
+    }
+
     // Obtener los procedimientos base desde la base de datos
     let procedures = sqlx::query_as!(
         Procedure,
@@ -98,7 +130,10 @@ async fn list_procedures(
             name,
             type as "procedure_type!: ProcedureType",
             description,
-            duration_minutes
+            duration_minutes,
+            species as "species!: Vec<AnimalSpecies>",
+            price::FLOAT8 as price,
+            required_room_type
         FROM procedures
         WHERE
             ($1::TEXT IS NULL OR name ILIKE '%' || $1 || '%') AND
@@ -117,18 +152,19 @@ async fn list_procedures(
     )
     .fetch_all(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al listar procedimientos: {}", e);
-        ApiError::InternalServerError("Error al obtener procedimientos".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al obtener procedimientos", e))?;
 
     // Convertir cada procedimiento a una respuesta enriquecida
     let responses: Vec<ProcedureResponse> = procedures
         .into_iter()
-        .map(ProcedureResponse::from)
+        .map(|procedure| ProcedureResponse::localized(procedure, locale))
         .collect();
 
-    Ok(HttpResponse::Ok().json(responses))
+    Ok(
+        catalog_cache_headers(&mut HttpResponse::Ok(), last_modified)
+            .insert_header((VARY, "Accept-Language"))
+            .json(responses),
+    )
 }
 
 /// Obtiene un procedimiento por ID
@@ -137,7 +173,8 @@ async fn list_procedures(
 /// GET /procedures/1
 #[actix_web::get("/{id}")]
 async fn get_procedure(
-    id: web::Path<i32>,
+    id: PathId,
+    locale: Locale,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Obteniendo procedimiento ID: {}", id);
@@ -151,18 +188,21 @@ async fn get_procedure(
             name,
             type as "procedure_type!: ProcedureType",
             description,
-            duration_minutes
+            duration_minutes,
+            species as "species!: Vec<AnimalSpecies>",
+            price::FLOAT8 as price,
+            required_room_type
         FROM procedures
         WHERE id = $1
         "#,
-        id.clone()
+        *id
     )
     .fetch_optional(pool.get_ref())
     .await?
     .ok_or(ApiError::NotFound("El procedimiento no existe".into()))?;
 
     // Convertir a respuesta enriquecida
-    let response = ProcedureResponse::from(procedure);
+    let response = ProcedureResponse::localized(procedure, locale);
 
     Ok(HttpResponse::Ok().json(response))
 }
@@ -178,37 +218,31 @@ async fn get_procedure(
 /// ```
 #[actix_web::put("/{id}")]
 async fn update_procedure(
-    id: web::Path<i32>,
+    id: PathId,
     updated_procedure: web::Json<UpdateProcedure>,
+    locale: Locale,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Actualizando procedimiento ID: {}", id);
 
-    let updated_procedure = updated_procedure.into_inner();
-    updated_procedure.validate()?;
-
     // Verificar si el procedimiento existe
-    let exists: bool = sqlx::query_scalar(
-        r#"
-        SELECT EXISTS (
-            SELECT 1
-            FROM procedures
-            WHERE id = $1
-        )
-        "#,
+    ensure_exists(
+        pool.get_ref(),
+        "procedures",
+        *id,
+        "El procedimiento no existe",
     )
-    .bind(id.clone())
-    .fetch_one(pool.get_ref())
     .await?;
 
-    if !exists {
-        return Err(ApiError::NotFound("El procedimiento no existe".into()));
-    }
+    let updated_procedure = updated_procedure.into_inner();
+    updated_procedure.validate()?;
+    ensure_species_exist(pool.get_ref(), updated_procedure.species.iter().flatten()).await?;
 
     let is_description: bool =
         updated_procedure.description.is_some() && updated_procedure.description == Some(None);
     let is_duration: bool = updated_procedure.duration_minutes.is_some()
         && updated_procedure.duration_minutes == Some(None);
+    let is_price: bool = updated_procedure.price == Some(None);
 
     // Actualizar el procedimiento
     let procedure = sqlx::query_as!(
@@ -227,14 +261,25 @@ async fn update_procedure(
                 WHEN $5::INT IS NOT NULL THEN $5 -- Nuevo valor
                 WHEN $6::BOOLEAN THEN NULL -- Borrar el valor
                 ELSE duration_minutes -- Mantener el valor existente
-            END
-        WHERE id = $7
+            END,
+            species = COALESCE($7, species),
+            price = CASE
+                WHEN $8::FLOAT8 IS NOT NULL THEN $8
+                WHEN $9::BOOLEAN THEN NULL
+                ELSE price
+            END,
+            required_room_type = CASE WHEN $11 THEN $12 ELSE required_room_type END,
+            updated_at = NOW()
+        WHERE id = $10
         RETURNING
             id,
             name,
             type as "procedure_type!: ProcedureType",
             description,
-            duration_minutes
+            duration_minutes,
+            species as "species!: Vec<AnimalSpecies>",
+            price::FLOAT8 as price,
+            required_room_type
         "#,
         updated_procedure.name,
         updated_procedure.procedure_type as Option<ProcedureType>,
@@ -242,59 +287,61 @@ async fn update_procedure(
         is_description,
         updated_procedure.duration_minutes.flatten(),
         is_duration,
-        id.clone()
+        updated_procedure.species.as_deref() as Option<&[AnimalSpecies]>,
+        updated_procedure.price.flatten(),
+        is_price,
+        *id,
+        updated_procedure.required_room_type.is_some(),
+        updated_procedure
+            .required_room_type
+            .flatten()
+            .map(|s| s.trim().to_string())
     )
     .fetch_one(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al actualizar procedimiento: {}", e);
-        ApiError::InternalServerError("Error al actualizar el procedimiento".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al actualizar el procedimiento", e))?;
 
     // Convertir a respuesta enriquecida
-    let response = ProcedureResponse::from(procedure);
+    let response = ProcedureResponse::localized(procedure, locale);
 
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// Filas que dependen del procedimiento y qué les pasaría al eliminarlo
+///
+/// # Ejemplo
+/// GET /procedures/7/delete-preview
+#[actix_web::get("/{id}/delete-preview")]
+async fn delete_procedure_preview(
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let preview = deletion::preview(pool.get_ref(), Resource::Procedure, *id).await?;
+    Ok(HttpResponse::Ok().json(preview))
+}
+
 /// Elimina un procedimiento existente
 ///
 /// # Ejemplo
 /// DELETE /procedures/1
 #[actix_web::delete("/{id}")]
-async fn delete_procedure(
-    id: web::Path<i32>,
-    pool: web::Data<PgPool>,
-) -> Result<HttpResponse, ApiError> {
+async fn delete_procedure(id: PathId, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
     tracing::info!("Eliminando procedimiento ID: {}", id);
 
-    // Verificar si el procedimiento existe
-    let exists: bool = sqlx::query_scalar(
-        r#"
-        SELECT EXISTS (
-            SELECT 1
-            FROM procedures
-            WHERE id = $1
-        )
-        "#,
-    )
-    .bind(id.clone())
-    .fetch_one(pool.get_ref())
-    .await?;
-
-    if !exists {
-        return Err(ApiError::NotFound("El procedimiento no existe".into()));
-    }
+    // Verificar que exista y que ninguna dependencia lo impida
+    deletion::ensure_deletable(pool.get_ref(), Resource::Procedure, *id).await?;
 
     // Eliminar el procedimiento
+    let mut tx = pool.begin().await?;
+
     let rows_affected = sqlx::query(
         r#"
         DELETE FROM procedures
         WHERE id = $1
         "#,
     )
-    .bind(id.clone())
-    .execute(pool.get_ref())
+    .bind(*id)
+    .execute(&mut *tx)
     .await?
     .rows_affected();
 
@@ -302,18 +349,212 @@ async fn delete_procedure(
         return Err(ApiError::NotFound("El procedimiento no existe".into()));
     }
 
+    record_catalog_deletion(&mut *tx, "procedures").await?;
+    tx.commit().await?;
+
     tracing::info!("Procedimiento ID {} eliminado exitosamente", id);
     Ok(HttpResponse::NoContent().finish())
 }
 
+/// Exporta el catálogo completo de procedimientos como documento versionado,
+/// para importarlo en otra clínica con `POST /procedures/import`
+///
+/// # Ejemplo
+/// GET /procedures/export
+#[actix_web::get("/export")]
+async fn export_procedures(pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Exportando catálogo de procedimientos");
+
+    let procedures = sqlx::query_as!(
+        Procedure,
+        r#"
+        SELECT
+            id,
+            name,
+            type as "procedure_type!: ProcedureType",
+            description,
+            duration_minutes,
+            species as "species!: Vec<AnimalSpecies>",
+            price::FLOAT8 as price,
+            required_room_type
+        FROM procedures
+        ORDER BY id
+        "#
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener procedimientos", e))?;
+
+    Ok(HttpResponse::Ok().json(ProcedureCatalog {
+        version: CATALOG_VERSION,
+        exported_at: chrono::Utc::now(),
+        procedures: procedures.into_iter().map(CatalogProcedure::from).collect(),
+    }))
+}
+
+/// Importa un catálogo exportado en una sola transacción.
+///
+/// Los procedimientos se comparan por nombre sin distinguir mayúsculas. Con
+/// `strategy=skip_existing` (default) los existentes no se modifican; con
+/// `strategy=update_existing` se sobrescriben con los datos del documento.
+///
+/// # Ejemplo
+/// POST /procedures/import?strategy=update_existing
+#[actix_web::post("/import")]
+async fn import_procedures(
+    claims: Claims,
+    query: web::Query<ProcedureImportQuery>,
+    catalog: web::Json<ProcedureCatalog>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    let catalog = catalog.into_inner();
+    if catalog.version != CATALOG_VERSION {
+        return Err(ApiError::ValidationError(format!(
+            "Versión de catálogo no soportada: {} (se espera {})",
+            catalog.version, CATALOG_VERSION
+        )));
+    }
+    catalog.validate()?;
+    ensure_species_exist(
+        pool.get_ref(),
+        catalog
+            .procedures
+            .iter()
+            .flat_map(|procedure| &procedure.species),
+    )
+    .await?;
+
+    let mut names = HashSet::new();
+    for procedure in &catalog.procedures {
+        if !names.insert(procedure.name.trim().to_lowercase()) {
+            return Err(ApiError::ValidationError(format!(
+                "El procedimiento \"{}\" aparece más de una vez en el catálogo",
+                procedure.name.trim()
+            )));
+        }
+    }
+
+    let strategy = query.strategy.unwrap_or_default();
+    tracing::info!(
+        "Importando {} procedimientos ({:?})",
+        catalog.procedures.len(),
+        strategy
+    );
+
+    let mut tx = pool.begin().await?;
+    let mut items = Vec::with_capacity(catalog.procedures.len());
+
+    for procedure in catalog.procedures {
+        let name = procedure.name.trim().to_string();
+        let existing: Option<i32> = sqlx::query_scalar!(
+            r#"
+            SELECT id
+            FROM procedures
+            WHERE LOWER(name) = LOWER($1)
+            ORDER BY id
+            LIMIT 1
+            "#,
+            name
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let (action, procedure_id) = match (existing, strategy) {
+            (Some(id), MergeStrategy::SkipExisting) => (ImportAction::Skipped, id),
+            (Some(id), MergeStrategy::UpdateExisting) => {
+                sqlx::query!(
+                    r#"
+                    UPDATE procedures
+                    SET
+                        name = $1,
+                        type = $2,
+                        description = $3,
+                        duration_minutes = $4,
+                        species = $5,
+                        price = $6::FLOAT8,
+                        updated_at = NOW()
+                    WHERE id = $7
+                    "#,
+                    name,
+                    procedure.procedure_type as ProcedureType,
+                    procedure.description,
+                    procedure.duration_minutes,
+                    &procedure.species as &[AnimalSpecies],
+                    procedure.price,
+                    id
+                )
+                .execute(&mut *tx)
+                .await?;
+                (ImportAction::Updated, id)
+            }
+            (None, _) => {
+                let id = sqlx::query_scalar!(
+                    r#"
+                    INSERT INTO procedures (
+                        name,
+                        type,
+                        description,
+                        duration_minutes,
+                        species,
+                        price
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6::FLOAT8)
+                    RETURNING id
+                    "#,
+                    name,
+                    procedure.procedure_type as ProcedureType,
+                    procedure.description,
+                    procedure.duration_minutes,
+                    &procedure.species as &[AnimalSpecies],
+                    procedure.price
+                )
+                .fetch_one(&mut *tx)
+                .await?;
+                (ImportAction::Created, id)
+            }
+        };
+
+        items.push(ProcedureImportItem {
+            name,
+            action,
+            procedure_id,
+        });
+    }
+
+    tx.commit().await?;
+
+    let count =
+        |action: ImportAction| items.iter().filter(|item| item.action == action).count() as i64;
+    let response = ProcedureImportResponse {
+        created: count(ImportAction::Created),
+        updated: count(ImportAction::Updated),
+        skipped: count(ImportAction::Skipped),
+        items,
+    };
+
+    tracing::info!(
+        "Catálogo importado: {} creados, {} actualizados, {} omitidos",
+        response.created,
+        response.updated,
+        response.skipped
+    );
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
 // Exporta todas las funciones como un grupo
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/procedures")
             .service(create_procedure)
             .service(list_procedures)
+            .service(export_procedures) // Antes de "/{id}"
+            .service(import_procedures)
             .service(get_procedure)
             .service(update_procedure)
+            .service(delete_procedure_preview)
             .service(delete_procedure), // Agrega más servicios aquí...
     );
 }
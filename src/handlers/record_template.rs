@@ -0,0 +1,240 @@
+use crate::auth::Claims;
+use crate::errors::ApiError;
+use crate::extractors::PathId;
+use crate::models::enums::UserRole;
+use crate::models::record_template::{
+    NewRecordTemplate, RecordTemplateFilter, RecordTemplateResponse, UpdateRecordTemplate,
+};
+use actix_web::{HttpResponse, web};
+use sqlx::PgPool;
+use validator::Validate;
+
+/// Las plantillas privadas solo las ve quien las creó (y los administradores)
+fn visible_to(template: &RecordTemplateResponse, claims: &Claims) -> bool {
+    template.is_shared || template.owner_id == Some(claims.sub) || claims.role == UserRole::Admin
+}
+
+/// Obtiene una plantilla visible para el usuario
+async fn fetch_visible(
+    pool: &PgPool,
+    id: i32,
+    claims: &Claims,
+) -> Result<RecordTemplateResponse, ApiError> {
+    RecordTemplateResponse::fetch(pool, id)
+        .await?
+        .filter(|template| visible_to(template, claims))
+        .ok_or(ApiError::NotFound("La plantilla no existe".into()))
+}
+
+/// Obtiene una plantilla que el usuario puede modificar (la suya, o cualquiera si es administrador)
+async fn fetch_owned(
+    pool: &PgPool,
+    id: i32,
+    claims: &Claims,
+) -> Result<RecordTemplateResponse, ApiError> {
+    let template = fetch_visible(pool, id, claims).await?;
+    if template.owner_id != Some(claims.sub) && claims.role != UserRole::Admin {
+        return Err(ApiError::Forbidden(
+            "Solo quien creó la plantilla puede modificarla".into(),
+        ));
+    }
+    Ok(template)
+}
+
+/// Crea una plantilla de diagnóstico y tratamiento
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "name": "Otitis externa",
+///   "diagnosis": "Otitis externa bilateral",
+///   "treatment": "Limpieza ótica y gotas de antibiótico por 7 días",
+///   "notes": "Revisión en una semana",
+///   "is_shared": true
+/// }
+/// ```
+#[actix_web::post("")]
+async fn create_record_template(
+    claims: Claims,
+    new_template: web::Json<NewRecordTemplate>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Creando plantilla de registro médico");
+
+    let new_template = new_template.into_inner();
+    new_template.validate()?;
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO record_templates (name, diagnosis, treatment, notes, owner_id, is_shared)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id
+        "#,
+        new_template.name.trim(),
+        new_template.diagnosis.map(|s| s.trim().to_string()),
+        new_template.treatment.map(|s| s.trim().to_string()),
+        new_template.notes.map(|s| s.trim().to_string()),
+        claims.sub,
+        new_template.is_shared
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al guardar la plantilla", e))?;
+
+    let template = fetch_visible(pool.get_ref(), id, &claims).await?;
+
+    Ok(HttpResponse::Created()
+        .append_header(("Location", format!("/record_templates/{}", template.id)))
+        .json(template))
+}
+
+/// Lista las plantillas propias y las compartidas
+///
+/// # Parámetros (opcionales vía query string)
+/// - `owner_id`: Filtrar por quien la creó
+/// - `is_shared`: Filtrar compartidas (`true`) o privadas (`false`)
+/// - `q`: Búsqueda parcial en el nombre
+/// - `limit`: Máximo de resultados (default: 50)
+/// - `offset`: Desplazamiento (default: 0)
+///
+/// # Ejemplo
+/// GET /record_templates?q=otitis&is_shared=true
+#[actix_web::get("")]
+async fn list_record_templates(
+    claims: Claims,
+    filters: web::Query<RecordTemplateFilter>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Listando plantillas: {:?}", &filters);
+
+    let templates = sqlx::query_as!(
+        RecordTemplateResponse,
+        r#"
+        SELECT
+            t.id,
+            t.name,
+            t.diagnosis,
+            t.treatment,
+            t.notes,
+            t.owner_id,
+            u.name as "owner_name?",
+            t.is_shared,
+            t.created_at
+        FROM record_templates t
+        LEFT JOIN users u ON u.id = t.owner_id
+        WHERE (t.is_shared OR t.owner_id = $1 OR $2)
+          AND ($3::int IS NULL OR t.owner_id = $3)
+          AND ($4::boolean IS NULL OR t.is_shared = $4)
+          AND ($5::text IS NULL OR t.name ILIKE '%' || $5 || '%')
+        ORDER BY t.name, t.id
+        LIMIT $6 OFFSET $7
+        "#,
+        claims.sub,
+        claims.role == UserRole::Admin,
+        filters.owner_id,
+        filters.is_shared,
+        filters
+            .q
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty()),
+        filters.limit.unwrap_or(50).min(400),
+        filters.offset.unwrap_or(0)
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener plantillas", e))?;
+
+    Ok(HttpResponse::Ok().json(templates))
+}
+
+/// Obtiene una plantilla por ID
+#[actix_web::get("/{id}")]
+async fn get_record_template(
+    claims: Claims,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(fetch_visible(pool.get_ref(), *id, &claims).await?))
+}
+
+/// Actualiza una plantilla propia
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "treatment": "Limpieza ótica y gotas de antibiótico por 10 días",
+///   "notes": null
+/// }
+/// ```
+#[actix_web::put("/{id}")]
+async fn update_record_template(
+    claims: Claims,
+    id: PathId,
+    updated: web::Json<UpdateRecordTemplate>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Actualizando plantilla ID: {}", id);
+
+    fetch_owned(pool.get_ref(), *id, &claims).await?;
+
+    let updated = updated.into_inner();
+    updated.validate()?;
+
+    sqlx::query!(
+        r#"
+        UPDATE record_templates
+        SET
+            name = COALESCE($1, name),
+            diagnosis = CASE WHEN $2 THEN $3 ELSE diagnosis END,
+            treatment = CASE WHEN $4 THEN $5 ELSE treatment END,
+            notes = CASE WHEN $6 THEN $7 ELSE notes END,
+            is_shared = COALESCE($8, is_shared)
+        WHERE id = $9
+        "#,
+        updated.name.map(|s| s.trim().to_string()),
+        updated.diagnosis.is_some(),
+        updated.diagnosis.flatten().map(|s| s.trim().to_string()),
+        updated.treatment.is_some(),
+        updated.treatment.flatten().map(|s| s.trim().to_string()),
+        updated.notes.is_some(),
+        updated.notes.flatten().map(|s| s.trim().to_string()),
+        updated.is_shared,
+        *id
+    )
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al actualizar la plantilla", e))?;
+
+    Ok(HttpResponse::Ok().json(fetch_visible(pool.get_ref(), *id, &claims).await?))
+}
+
+/// Elimina una plantilla propia. Los registros creados con ella no cambian.
+#[actix_web::delete("/{id}")]
+async fn delete_record_template(
+    claims: Claims,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Eliminando plantilla ID: {}", id);
+
+    fetch_owned(pool.get_ref(), *id, &claims).await?;
+
+    sqlx::query!("DELETE FROM record_templates WHERE id = $1", *id)
+        .execute(pool.get_ref())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/record_templates")
+            .service(create_record_template)
+            .service(list_record_templates)
+            .service(get_record_template)
+            .service(update_record_template)
+            .service(delete_record_template), // Agrega más servicios aquí...
+    );
+}
@@ -0,0 +1,337 @@
+use crate::auth::Claims;
+use crate::errors::ApiError;
+use crate::extractors::PathId;
+use crate::models::appointment::TimeSlot;
+use crate::models::enums::UserRole;
+use crate::models::room::{NewRoom, RoomFilter, RoomResponse, UpdateRoom};
+use actix_web::{HttpResponse, web};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use validator::Validate;
+
+/// Obtiene una sala por ID
+async fn fetch_room(pool: &PgPool, id: i32) -> Result<RoomResponse, ApiError> {
+    sqlx::query_as!(
+        RoomResponse,
+        "SELECT id, name, room_type, is_active FROM rooms WHERE id = $1",
+        id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::NotFound("La sala no existe".into()))
+}
+
+/// Error de nombre duplicado al guardar una sala
+fn room_save_error(e: sqlx::Error) -> ApiError {
+    match e {
+        sqlx::Error::Database(db) if db.constraint() == Some("rooms_name_key") => {
+            ApiError::Conflict("Ya existe una sala con ese nombre".into())
+        }
+        e => ApiError::internal("Error al guardar la sala", e),
+    }
+}
+
+/// Sala que requiere la cita según su procedimiento.
+///
+/// Si el procedimiento declara `required_room_type` asigna la primera sala
+/// activa de ese tipo sin citas no canceladas traslapadas (409 si no hay);
+/// sin procedimiento, o si no requiere sala, devuelve `None`. Bloquea las
+/// salas del tipo hasta el final de la transacción para que dos citas
+/// simultáneas no reciban la misma.
+pub(super) async fn assign_room(
+    tx: &mut Transaction<'_, Postgres>,
+    procedure_id: Option<i32>,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    appointment_id: Option<i32>, // Cita que se está editando, para excluirla
+) -> Result<Option<i32>, ApiError> {
+    let Some(procedure_id) = procedure_id else {
+        return Ok(None);
+    };
+
+    let room_type = sqlx::query_scalar!(
+        "SELECT required_room_type FROM procedures WHERE id = $1",
+        procedure_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or(ApiError::ValidationError(
+        "El procedimiento no existe".into(),
+    ))?;
+
+    let Some(room_type) = room_type else {
+        return Ok(None);
+    };
+
+    sqlx::query!(
+        "SELECT id FROM rooms WHERE room_type = $1 AND is_active FOR UPDATE",
+        room_type
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let room_id = sqlx::query_scalar!(
+        r#"
+        SELECT r.id
+        FROM rooms r
+        WHERE r.room_type = $1
+          AND r.is_active
+          AND NOT EXISTS (
+              SELECT 1
+              FROM appointments a
+              WHERE a.room_id = r.id
+                AND a.id IS DISTINCT FROM $4
+                AND a.status NOT IN ('canceled', 'no_show')
+                AND ($2, $3) OVERLAPS (a.start_time, a.end_time)
+          )
+        -- Conservar la sala actual de la cita si sigue libre
+        ORDER BY r.id IS NOT DISTINCT FROM (SELECT room_id FROM appointments WHERE id = $4) DESC,
+                 r.id
+        LIMIT 1
+        "#,
+        room_type,
+        start_time,
+        end_time,
+        appointment_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    match room_id {
+        Some(room_id) => Ok(Some(room_id)),
+        None => {
+            tracing::warn!(
+                "Sin sala '{}' libre de {} a {}",
+                room_type,
+                start_time,
+                end_time
+            );
+            Err(ApiError::Conflict(format!(
+                "No hay una sala de tipo '{room_type}' disponible en este horario"
+            )))
+        }
+    }
+}
+
+/// Tipo de sala que requiere el procedimiento, o `None` si no requiere.
+/// Responde 400 si el procedimiento no existe.
+pub(super) async fn required_room_type(
+    pool: &PgPool,
+    procedure_id: i32,
+) -> Result<Option<String>, ApiError> {
+    sqlx::query_scalar!(
+        "SELECT required_room_type FROM procedures WHERE id = $1",
+        procedure_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::ValidationError(
+        "El procedimiento no existe".into(),
+    ))
+}
+
+/// Citas de cada sala activa del tipo entre `from` y `to`, una lista por sala
+/// (vacía si está libre). Como en `assign_room`, solo las canceladas la liberan.
+pub(super) async fn room_bookings(
+    pool: &PgPool,
+    room_type: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<Vec<TimeSlot>>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT r.id, a.start_time as "start_time?", a.end_time as "end_time?"
+        FROM rooms r
+        LEFT JOIN appointments a
+            ON a.room_id = r.id
+           AND a.status NOT IN ('canceled', 'no_show')
+           AND a.start_time < $3
+           AND a.end_time > $2
+        WHERE r.room_type = $1 AND r.is_active
+        ORDER BY r.id, a.start_time
+        "#,
+        room_type,
+        from,
+        to
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener la ocupación de las salas", e))?;
+
+    let mut rooms: Vec<(i32, Vec<TimeSlot>)> = Vec::new();
+    for row in rows {
+        if rooms.last().is_none_or(|(id, _)| *id != row.id) {
+            rooms.push((row.id, Vec::new()));
+        }
+        if let (Some(start_time), Some(end_time), Some((_, bookings))) =
+            (row.start_time, row.end_time, rooms.last_mut())
+        {
+            bookings.push(TimeSlot {
+                start_time,
+                end_time,
+            });
+        }
+    }
+    Ok(rooms.into_iter().map(|(_, bookings)| bookings).collect())
+}
+
+/// Registra una sala o equipo (solo administradores)
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "name": "Quirófano 1",
+///   "room_type": "surgery"
+/// }
+/// ```
+#[actix_web::post("")]
+async fn create_room(
+    claims: Claims,
+    new_room: web::Json<NewRoom>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    tracing::info!("Creando sala");
+
+    let new_room = new_room.into_inner();
+    new_room.validate()?;
+
+    let room = sqlx::query_as!(
+        RoomResponse,
+        r#"
+        INSERT INTO rooms (name, room_type)
+        VALUES ($1, $2)
+        RETURNING id, name, room_type, is_active
+        "#,
+        new_room.name.trim(),
+        new_room.room_type.trim()
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(room_save_error)?;
+
+    Ok(HttpResponse::Created()
+        .append_header(("Location", format!("/rooms/{}", room.id)))
+        .json(room))
+}
+
+/// Lista las salas
+///
+/// # Parámetros (opcionales vía query string)
+/// - `room_type`: Filtrar por tipo
+/// - `is_active`: Filtrar por estado
+#[actix_web::get("")]
+async fn list_rooms(
+    filters: web::Query<RoomFilter>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Listando salas: {:?}", &filters);
+
+    let rooms = sqlx::query_as!(
+        RoomResponse,
+        r#"
+        SELECT id, name, room_type, is_active
+        FROM rooms
+        WHERE ($1::TEXT IS NULL OR room_type = $1)
+          AND ($2::BOOLEAN IS NULL OR is_active = $2)
+        ORDER BY room_type, name
+        "#,
+        filters.room_type.as_deref(),
+        filters.is_active
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener salas", e))?;
+
+    Ok(HttpResponse::Ok().json(rooms))
+}
+
+/// Obtiene una sala por ID
+#[actix_web::get("/{id}")]
+async fn get_room(id: PathId, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(fetch_room(pool.get_ref(), *id).await?))
+}
+
+/// Actualiza una sala (solo administradores). Desactivarla no libera las
+/// citas que ya la tienen asignada.
+#[actix_web::put("/{id}")]
+async fn update_room(
+    claims: Claims,
+    id: PathId,
+    updated: web::Json<UpdateRoom>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    tracing::info!("Actualizando sala ID: {}", id);
+
+    fetch_room(pool.get_ref(), *id).await?;
+
+    let updated = updated.into_inner();
+    updated.validate()?;
+
+    let room = sqlx::query_as!(
+        RoomResponse,
+        r#"
+        UPDATE rooms
+        SET
+            name = COALESCE($1, name),
+            room_type = COALESCE($2, room_type),
+            is_active = COALESCE($3, is_active)
+        WHERE id = $4
+        RETURNING id, name, room_type, is_active
+        "#,
+        updated.name.map(|s| s.trim().to_string()),
+        updated.room_type.map(|s| s.trim().to_string()),
+        updated.is_active,
+        *id
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(room_save_error)?;
+
+    Ok(HttpResponse::Ok().json(room))
+}
+
+/// Elimina una sala sin citas programadas (solo administradores)
+#[actix_web::delete("/{id}")]
+async fn delete_room(
+    claims: Claims,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    tracing::info!("Eliminando sala ID: {}", id);
+
+    fetch_room(pool.get_ref(), *id).await?;
+
+    let has_appointments = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM appointments WHERE room_id = $1 AND status = 'scheduled') as "exists!""#,
+        *id
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    if has_appointments {
+        return Err(ApiError::Conflict(
+            "No se puede eliminar, la sala tiene citas programadas; desactívala".into(),
+        ));
+    }
+
+    sqlx::query!("DELETE FROM rooms WHERE id = $1", *id)
+        .execute(pool.get_ref())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/rooms")
+            .service(create_room)
+            .service(list_rooms)
+            .service(get_room)
+            .service(update_room)
+            .service(delete_room), // Agrega más servicios aquí...
+    );
+}
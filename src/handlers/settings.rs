@@ -0,0 +1,97 @@
+use crate::auth::Claims;
+use crate::clinic::{self, ClinicProfileCache};
+use crate::errors::ApiError;
+use crate::models::enums::UserRole;
+use crate::models::settings::{ClinicHoursDay, ClinicHoursResponse, UpdateClinicHours};
+use actix_web::{HttpResponse, web};
+use sqlx::{PgExecutor, PgPool};
+use validator::Validate;
+
+/// Horario de atención guardado, ordenado por día de la semana
+async fn fetch_clinic_hours(
+    executor: impl PgExecutor<'_>,
+) -> Result<ClinicHoursResponse, ApiError> {
+    let days = sqlx::query_as!(
+        ClinicHoursDay,
+        "SELECT weekday, opens_at, closes_at FROM clinic_hours ORDER BY weekday"
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(ClinicHoursResponse {
+        timezone: clinic::timezone().name().to_string(),
+        days,
+    })
+}
+
+/// Horario de atención de la clínica por día de la semana
+///
+/// Las citas deben caer completas dentro del horario del día en que empiezan;
+/// los días que no aparecen están cerrados.
+///
+/// # Ejemplo
+/// GET /settings/clinic-hours
+#[actix_web::get("/clinic-hours")]
+async fn get_clinic_hours(pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(fetch_clinic_hours(pool.get_ref()).await?))
+}
+
+/// Reemplaza el horario de atención completo (solo administradores)
+///
+/// Los días que no se envían quedan cerrados. Las citas ya agendadas no se
+/// modifican; el horario nuevo aplica al crear o mover citas.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "days": [
+///     { "weekday": 1, "opens_at": "09:00", "closes_at": "18:00" },
+///     { "weekday": 6, "opens_at": "09:00", "closes_at": "14:00" }
+///   ]
+/// }
+/// ```
+#[actix_web::put("/clinic-hours")]
+async fn update_clinic_hours(
+    claims: Claims,
+    hours: web::Json<UpdateClinicHours>,
+    pool: web::Data<PgPool>,
+    profile_cache: web::Data<ClinicProfileCache>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    hours.validate()?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query!("DELETE FROM clinic_hours")
+        .execute(&mut *tx)
+        .await?;
+    for day in &hours.days {
+        sqlx::query!(
+            "INSERT INTO clinic_hours (weekday, opens_at, closes_at) VALUES ($1, $2, $3)",
+            day.weekday,
+            day.opens_at,
+            day.closes_at
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    let response = fetch_clinic_hours(&mut *tx).await?;
+    tx.commit().await?;
+
+    // El perfil público muestra el horario de hoy
+    profile_cache.clear();
+    tracing::info!(
+        "Horario de atención actualizado: {} días abiertos",
+        response.days.len()
+    );
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/settings")
+            .service(get_clinic_hours)
+            .service(update_clinic_hours),
+    );
+}
@@ -0,0 +1,244 @@
+use crate::auth::Claims;
+use crate::errors::ApiError;
+use crate::models::enums::{AnimalSpecies, UserRole};
+use crate::models::species::{NewSpecies, SpeciesResponse, SpeciesUsage, UpdateSpecies};
+use actix_web::{HttpResponse, web};
+use sqlx::PgPool;
+use validator::Validate;
+
+/// Verifica que las especies del catálogo existan antes de guardarlas; las
+/// integradas siempre existen. Responde 400 con las que no.
+pub(super) async fn ensure_species_exist<'a>(
+    pool: &PgPool,
+    species: impl IntoIterator<Item = &'a AnimalSpecies>,
+) -> Result<(), ApiError> {
+    let codes: Vec<String> = species
+        .into_iter()
+        .filter(|species| !species.is_built_in())
+        .map(|species| species.code().to_string())
+        .collect();
+    if codes.is_empty() {
+        return Ok(());
+    }
+
+    let missing = sqlx::query_scalar!(
+        r#"
+        SELECT wanted.code as "code!"
+        FROM UNNEST($1::text[]) AS wanted (code)
+        WHERE NOT EXISTS (SELECT 1 FROM species s WHERE s.code = wanted.code)
+        "#,
+        &codes
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if !missing.is_empty() {
+        return Err(ApiError::ValidationError(format!(
+            "Especie desconocida: {}. Consulta GET /species",
+            missing.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Lista el catálogo de especies, primero las integradas
+///
+/// # Ejemplo
+/// GET /species
+#[actix_web::get("")]
+async fn list_species(pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    let species = sqlx::query!(
+        r#"
+        SELECT code, name, is_built_in, created_at
+        FROM species
+        ORDER BY is_built_in DESC, name
+        "#
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener las especies", e))?;
+
+    let response: Vec<SpeciesResponse> = species
+        .into_iter()
+        .map(|row| SpeciesResponse {
+            species: AnimalSpecies::from_code(&row.code),
+            code: row.code,
+            name: row.name,
+            is_built_in: row.is_built_in,
+            created_at: row.created_at,
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Da de alta una especie (solo admin). A partir de ese momento se acepta su
+/// código en pacientes, razas, procedimientos y protocolos.
+///
+/// # Ejemplo de petición
+/// ```json
+/// { "code": "ferret", "name": "Hurón" }
+/// ```
+#[actix_web::post("")]
+async fn create_species(
+    claims: Claims,
+    new_species: web::Json<NewSpecies>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    let new_species = new_species.into_inner();
+    new_species.validate()?;
+
+    let created_at = sqlx::query_scalar!(
+        r#"
+        INSERT INTO species (code, name)
+        VALUES ($1, $2)
+        RETURNING created_at
+        "#,
+        new_species.code,
+        new_species.name.trim()
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(err) if err.is_unique_violation() => {
+            ApiError::Conflict("Ya existe una especie con ese código o ese nombre".into())
+        }
+        e => ApiError::internal("Error al guardar la especie", e),
+    })?;
+
+    tracing::info!(
+        "Especie {} dada de alta por el usuario {}",
+        new_species.code,
+        claims.sub
+    );
+    Ok(HttpResponse::Created()
+        .append_header(("Location", format!("/species/{}", new_species.code)))
+        .json(SpeciesResponse {
+            species: AnimalSpecies::from_code(&new_species.code),
+            code: new_species.code,
+            name: new_species.name.trim().to_string(),
+            is_built_in: false,
+            created_at,
+        }))
+}
+
+/// Cambia el nombre para mostrar de una especie (solo admin); también de las
+/// integradas
+///
+/// # Ejemplo de petición
+/// ```json
+/// { "name": "Hurón doméstico" }
+/// ```
+#[actix_web::put("/{code}")]
+async fn update_species(
+    claims: Claims,
+    code: web::Path<String>,
+    update: web::Json<UpdateSpecies>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    let update = update.into_inner();
+    update.validate()?;
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE species SET name = $2
+        WHERE code = $1
+        RETURNING code, name, is_built_in, created_at
+        "#,
+        code.as_str(),
+        update.name.trim()
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(err) if err.is_unique_violation() => {
+            ApiError::Conflict("Ya existe una especie con ese nombre".into())
+        }
+        e => ApiError::internal("Error al actualizar la especie", e),
+    })?
+    .ok_or(ApiError::NotFound("La especie no existe".into()))?;
+
+    Ok(HttpResponse::Ok().json(SpeciesResponse {
+        species: AnimalSpecies::from_code(&row.code),
+        code: row.code,
+        name: row.name,
+        is_built_in: row.is_built_in,
+        created_at: row.created_at,
+    }))
+}
+
+/// Elimina una especie del catálogo (solo admin).
+///
+/// Las integradas no se eliminan, y una especie en uso por pacientes, razas,
+/// procedimientos o protocolos responde 409 con el conteo en `details`.
+///
+/// # Ejemplo
+/// DELETE /species/ferret
+#[actix_web::delete("/{code}")]
+async fn delete_species(
+    claims: Claims,
+    code: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+
+    // El bloqueo impide que se registre un uso entre el conteo y el DELETE
+    let mut tx = pool.begin().await?;
+    let is_built_in = sqlx::query_scalar!(
+        "SELECT is_built_in FROM species WHERE code = $1 FOR UPDATE",
+        code.as_str()
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ApiError::NotFound("La especie no existe".into()))?;
+    if is_built_in {
+        return Err(ApiError::Conflict(
+            "Las especies integradas no se pueden eliminar".into(),
+        ));
+    }
+
+    let usage = sqlx::query_as!(
+        SpeciesUsage,
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM patients WHERE species = $1) as "patients!",
+            (SELECT COUNT(*) FROM breeds WHERE species = $1) as "breeds!",
+            (SELECT COUNT(*) FROM procedures WHERE $1 = ANY(species)) as "procedures!",
+            (SELECT COUNT(*) FROM species_protocols WHERE species = $1) as "species_protocols!"
+        "#,
+        code.as_str()
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if usage.is_used() {
+        return Err(ApiError::DetailedConflict {
+            message: "No se puede eliminar, la especie está en uso".into(),
+            details: serde_json::json!({ "usage": usage }),
+        });
+    }
+
+    sqlx::query!("DELETE FROM species WHERE code = $1", code.as_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal("Error al eliminar la especie", e))?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "Especie {} eliminada por el usuario {}",
+        code.as_str(),
+        claims.sub
+    );
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/species")
+            .service(list_species)
+            .service(create_species)
+            .service(update_species)
+            .service(delete_species),
+    );
+}
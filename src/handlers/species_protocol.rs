@@ -0,0 +1,337 @@
+use crate::auth::Claims;
+use crate::errors::ApiError;
+use crate::extractors::PathId;
+use crate::handlers::ensure_exists;
+use crate::handlers::species::ensure_species_exist;
+use crate::models::enums::{AnimalSpecies, UserRole};
+use crate::models::species_protocol::{
+    NewSpeciesProtocol, ProtocolItem, SpeciesProtocol, SpeciesProtocolFilter,
+    SpeciesProtocolResponse, UpdateSpeciesProtocol,
+};
+use actix_web::{HttpResponse, web};
+use sqlx::{PgPool, Postgres, Transaction};
+use validator::Validate;
+
+/// Reemplaza los procedimientos del protocolo, verificando que existan
+async fn replace_items(
+    tx: &mut Transaction<'_, Postgres>,
+    protocol_id: i32,
+    items: &[ProtocolItem],
+) -> Result<(), ApiError> {
+    let procedure_ids: Vec<i32> = items.iter().map(|item| item.procedure_id).collect();
+    let offsets: Vec<i32> = items.iter().map(|item| item.offset_days).collect();
+
+    let missing: Vec<i32> = sqlx::query_scalar!(
+        r#"
+        SELECT requested.id as "id!"
+        FROM UNNEST($1::INT[]) AS requested(id)
+        WHERE NOT EXISTS (SELECT 1 FROM procedures WHERE id = requested.id)
+        "#,
+        &procedure_ids
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    if !missing.is_empty() {
+        return Err(ApiError::ValidationError(format!(
+            "Los procedimientos {:?} no existen",
+            missing
+        )));
+    }
+
+    sqlx::query!(
+        "DELETE FROM species_protocol_items WHERE protocol_id = $1",
+        protocol_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO species_protocol_items (protocol_id, procedure_id, offset_days)
+        SELECT $1, item.procedure_id, item.offset_days
+        FROM UNNEST($2::INT[], $3::INT[]) AS item(procedure_id, offset_days)
+        "#,
+        protocol_id,
+        &procedure_ids,
+        &offsets
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Crea un protocolo de procedimientos por especie
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "name": "Esquema de vacunación de cachorro",
+///   "species": "Dog",
+///   "min_age_days": 0,
+///   "max_age_days": 120,
+///   "items": [
+///     { "procedure_id": 1, "offset_days": 42 },
+///     { "procedure_id": 1, "offset_days": 63 }
+///   ]
+/// }
+/// ```
+#[actix_web::post("")]
+async fn create_species_protocol(
+    claims: Claims,
+    new_protocol: web::Json<NewSpeciesProtocol>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    tracing::info!("Creando nuevo protocolo por especie");
+
+    let new_protocol = new_protocol.into_inner();
+    new_protocol.validate()?;
+    ensure_species_exist(pool.get_ref(), [&new_protocol.species]).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let protocol = sqlx::query_as!(
+        SpeciesProtocol,
+        r#"
+        INSERT INTO species_protocols (name, species, min_age_days, max_age_days)
+        VALUES ($1, $2, $3, $4)
+        RETURNING
+            id,
+            name,
+            species as "species!: AnimalSpecies",
+            min_age_days,
+            max_age_days
+        "#,
+        new_protocol.name.trim(),
+        new_protocol.species as AnimalSpecies,
+        new_protocol.min_age_days,
+        new_protocol.max_age_days
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al guardar el protocolo", e))?;
+
+    replace_items(&mut tx, protocol.id, &new_protocol.items).await?;
+    tx.commit().await?;
+
+    let response = SpeciesProtocolResponse::from_protocol(protocol, pool.get_ref()).await?;
+
+    tracing::info!("Protocolo creado exitosamente ID: {}", response.id);
+
+    Ok(HttpResponse::Created()
+        .append_header(("Location", format!("/species_protocols/{}", response.id)))
+        .json(response))
+}
+
+/// Lista los protocolos por especie
+///
+/// # Parámetros (opcionales vía query string)
+/// - `species`: Filtrar por especie
+/// - `limit`: Máximo de resultados (default: 50)
+/// - `offset`: Desplazamiento (default: 0)
+///
+/// # Ejemplo
+/// GET /species_protocols?species=Dog
+#[actix_web::get("")]
+async fn list_species_protocols(
+    filters: web::Query<SpeciesProtocolFilter>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Listando protocolos con filtros: {:?}", &filters);
+    ensure_species_exist(pool.get_ref(), &filters.species).await?;
+
+    let protocols = sqlx::query_as!(
+        SpeciesProtocol,
+        r#"
+        SELECT
+            id,
+            name,
+            species as "species!: AnimalSpecies",
+            min_age_days,
+            max_age_days
+        FROM species_protocols
+        WHERE ($1::text IS NULL OR species = $1)
+        ORDER BY species, name
+        LIMIT $2 OFFSET $3
+        "#,
+        &filters.species as &Option<AnimalSpecies>,
+        filters.limit.unwrap_or(50).min(400),
+        filters.offset.unwrap_or(0)
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener protocolos", e))?;
+
+    let mut responses = Vec::with_capacity(protocols.len());
+    for protocol in protocols {
+        responses.push(SpeciesProtocolResponse::from_protocol(protocol, pool.get_ref()).await?);
+    }
+
+    Ok(HttpResponse::Ok().json(responses))
+}
+
+/// Obtiene un protocolo por ID
+///
+/// # Ejemplo
+/// GET /species_protocols/1
+#[actix_web::get("/{id}")]
+async fn get_species_protocol(
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Obteniendo protocolo ID: {}", id);
+
+    let protocol = fetch_protocol(pool.get_ref(), *id).await?;
+    let response = SpeciesProtocolResponse::from_protocol(protocol, pool.get_ref()).await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Actualiza un protocolo; si se envía `items` reemplaza la lista completa
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "max_age_days": null,
+///   "items": [{ "procedure_id": 2, "offset_days": 0 }]
+/// }
+/// ```
+#[actix_web::put("/{id}")]
+async fn update_species_protocol(
+    claims: Claims,
+    id: PathId,
+    updated_protocol: web::Json<UpdateSpeciesProtocol>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    tracing::info!("Actualizando protocolo ID: {}", id);
+
+    ensure_exists(
+        pool.get_ref(),
+        "species_protocols",
+        *id,
+        "El protocolo no existe",
+    )
+    .await?;
+
+    let updated_protocol = updated_protocol.into_inner();
+    updated_protocol.validate()?;
+    ensure_species_exist(pool.get_ref(), &updated_protocol.species).await?;
+
+    let clear_min_age = updated_protocol.min_age_days == Some(None);
+    let clear_max_age = updated_protocol.max_age_days == Some(None);
+
+    let mut tx = pool.begin().await?;
+
+    let protocol = sqlx::query_as!(
+        SpeciesProtocol,
+        r#"
+        UPDATE species_protocols
+        SET
+            name = COALESCE($1, name),
+            species = COALESCE($2, species),
+            min_age_days = CASE
+                WHEN $3::INT IS NOT NULL THEN $3 -- Nuevo valor
+                WHEN $4::BOOLEAN THEN NULL -- Borrar el valor
+                ELSE min_age_days -- Mantener el valor existente
+            END,
+            max_age_days = CASE
+                WHEN $5::INT IS NOT NULL THEN $5
+                WHEN $6::BOOLEAN THEN NULL
+                ELSE max_age_days
+            END
+        WHERE id = $7
+        RETURNING
+            id,
+            name,
+            species as "species!: AnimalSpecies",
+            min_age_days,
+            max_age_days
+        "#,
+        updated_protocol.name.as_deref().map(str::trim),
+        updated_protocol.species as Option<AnimalSpecies>,
+        updated_protocol.min_age_days.flatten(),
+        clear_min_age,
+        updated_protocol.max_age_days.flatten(),
+        clear_max_age,
+        *id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.constraint() == Some("chk_protocol_age_range") => {
+            ApiError::ValidationError("La edad mínima no puede ser mayor que la edad máxima".into())
+        }
+        e => ApiError::internal("Error al actualizar el protocolo", e),
+    })?;
+
+    if let Some(items) = &updated_protocol.items {
+        replace_items(&mut tx, protocol.id, items).await?;
+    }
+    tx.commit().await?;
+
+    let response = SpeciesProtocolResponse::from_protocol(protocol, pool.get_ref()).await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Elimina un protocolo (no afecta los procedimientos ya generados)
+///
+/// # Ejemplo
+/// DELETE /species_protocols/1
+#[actix_web::delete("/{id}")]
+async fn delete_species_protocol(
+    claims: Claims,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    tracing::info!("Eliminando protocolo ID: {}", id);
+
+    let rows_affected = sqlx::query!("DELETE FROM species_protocols WHERE id = $1", *id)
+        .execute(pool.get_ref())
+        .await?
+        .rows_affected();
+
+    if rows_affected == 0 {
+        return Err(ApiError::NotFound("El protocolo no existe".into()));
+    }
+
+    tracing::info!("Protocolo ID {} eliminado exitosamente", id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Obtiene un protocolo o responde 404
+pub async fn fetch_protocol(pool: &PgPool, id: i32) -> Result<SpeciesProtocol, ApiError> {
+    sqlx::query_as!(
+        SpeciesProtocol,
+        r#"
+        SELECT
+            id,
+            name,
+            species as "species!: AnimalSpecies",
+            min_age_days,
+            max_age_days
+        FROM species_protocols
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::NotFound("El protocolo no existe".into()))
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/species_protocols")
+            .service(create_species_protocol)
+            .service(list_species_protocols)
+            .service(get_species_protocol)
+            .service(update_species_protocol)
+            .service(delete_species_protocol),
+    );
+}
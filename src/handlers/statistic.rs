@@ -1,82 +1,353 @@
+use crate::auth::Claims;
+use crate::clinic;
+use crate::extractors::PathId;
+use crate::handlers::species::ensure_species_exist;
+use crate::models::enums::{AnimalSpecies, ProcedureType};
+use crate::models::patient_procedure::{PatientProcedure, PatientProcedureResponse};
 use crate::models::statistic::*;
+use crate::vet_report;
 use crate::{errors::ApiError, models::enums::UserRole};
 
 use actix_web::{HttpResponse, web};
 use sqlx::PgPool;
 
+/// Estadísticas del sistema o de un veterinario, según quién las pide.
+///
+/// El rol sale del token (401 sin token de usuario): un veterinario recibe
+/// siempre las suyas (403 si pide las de otro) y un asistente responde 403. Un
+/// administrador recibe las del sistema, o las de un veterinario si indica
+/// `user_id`.
 #[actix_web::get("")]
 async fn get_statistics(
+    claims: Claims,
     query: web::Query<StatisticsQuery>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
-    let query = query.into_inner();
+    let mut query = query.into_inner();
+    let types = query.types()?;
+    ensure_species_exist(pool.get_ref(), &query.species).await?;
+    let wants = |t: StatisticType| types.as_ref().is_none_or(|types| types.contains(&t));
+
+    query.user_id = statistics_scope(&claims, query.user_id)?;
+    ensure_satisfaction_allowed(&claims, query.user_id, types.as_deref())?;
     let mut response = StatisticsResponse {
         appointments_by_month: None,
+        appointments_by_source: None,
         user_counts: None,
         procedures_by_type: None,
         patients_by_species: None,
         veterinarian_stats: None,
+        referrals_by_month: None,
+        satisfaction: None,
     };
 
-    match query.role {
-        UserRole::Admin => {
-            if query.type_.is_none() || query.type_ == Some("appointments".to_string()) {
+    match query.user_id {
+        None => {
+            let (series_start, series_end) = query.series_range(clinic::today())?;
+            if wants(StatisticType::Appointments) {
                 response.appointments_by_month = Some(
-                    get_appointments_by_month(pool.get_ref(), query.start_date, query.end_date)
+                    get_appointments_by_month(pool.get_ref(), series_start, series_end).await?,
+                );
+                response.appointments_by_source = Some(
+                    get_appointments_by_source(pool.get_ref(), query.start_date, query.end_date)
                         .await?,
                 );
             }
-            if query.type_.is_none() || query.type_ == Some("users".to_string()) {
+            if wants(StatisticType::Users) {
                 response.user_counts = Some(get_user_counts(pool.get_ref()).await?);
             }
-            if query.type_.is_none() || query.type_ == Some("procedures".to_string()) {
+            if wants(StatisticType::Procedures) {
                 response.procedures_by_type = Some(
-                    get_procedures_by_type(pool.get_ref(), query.start_date, query.end_date)
-                        .await?,
-                );
-            }
-            if query.type_.is_none() || query.type_ == Some("patients".to_string()) {
-                response.patients_by_species = Some(get_patients_by_species(pool.get_ref()).await?);
-            }
-        }
-        UserRole::Veterinarian => {
-            if let Some(user_id) = query.user_id {
-                response.veterinarian_stats = Some(
-                    get_veterinarian_stats(
+                    get_procedures_by_type(
                         pool.get_ref(),
-                        user_id,
                         query.start_date,
                         query.end_date,
+                        query.species.as_ref(),
                     )
                     .await?,
                 );
-            } else {
-                return Err(ApiError::ValidationError(
-                    "El ID del veterinario es requerido".into(),
-                ));
             }
+            if wants(StatisticType::Patients) {
+                response.patients_by_species = Some(get_patients_by_species(pool.get_ref()).await?);
+            }
+            if wants(StatisticType::Referrals) {
+                response.referrals_by_month =
+                    Some(get_referrals_by_month(pool.get_ref(), series_start, series_end).await?);
+            }
+            if types
+                .as_ref()
+                .is_some_and(|types| types.contains(&StatisticType::Satisfaction))
+            {
+                response.satisfaction =
+                    Some(get_satisfaction(pool.get_ref(), series_start, series_end).await?);
+            }
+        }
+        Some(user_id) => {
+            ensure_active_veterinarian(pool.get_ref(), user_id).await?;
+            response.veterinarian_stats = Some(
+                get_veterinarian_stats(
+                    pool.get_ref(),
+                    user_id,
+                    query.by.unwrap_or_default(),
+                    query.start_date,
+                    query.end_date,
+                    query.species.as_ref(),
+                )
+                .await?,
+            );
         }
-        UserRole::Assistant => {}
     }
 
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// Veterinario cuyas estadísticas se devuelven, o `None` para las del sistema.
+///
+/// Lo decide el rol del token: un veterinario solo obtiene las suyas, un
+/// administrador las del sistema o las del `user_id` que pida, y cualquier
+/// otro rol responde 403.
+fn statistics_scope(claims: &Claims, requested: Option<i32>) -> Result<Option<i32>, ApiError> {
+    if claims.role == UserRole::Veterinarian {
+        if requested.is_some_and(|id| id != claims.sub) {
+            return Err(ApiError::Forbidden(
+                "Solo puedes consultar tus propias estadísticas".into(),
+            ));
+        }
+        return Ok(Some(claims.sub));
+    }
+    claims.require_role(&[UserRole::Admin])?;
+    Ok(requested)
+}
+
+/// `satisfaction` (las calificaciones de cada veterinario) solo lo pide un
+/// administrador (403 para otro rol) y solo existe en las estadísticas del
+/// sistema (400 con `user_id`), en lugar de omitirse en silencio.
+fn ensure_satisfaction_allowed(
+    claims: &Claims,
+    user_id: Option<i32>,
+    types: Option<&[StatisticType]>,
+) -> Result<(), ApiError> {
+    if !types.is_some_and(|types| types.contains(&StatisticType::Satisfaction)) {
+        return Ok(());
+    }
+    claims.require_role(&[UserRole::Admin])?;
+    if user_id.is_some() {
+        return Err(ApiError::ValidationError(
+            "satisfaction solo está disponible en las estadísticas del sistema (sin user_id)"
+                .into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Procedimientos aplicados de un tipo, con paciente y veterinario, para el
+/// detalle de una barra de `procedures_by_type`.
+///
+/// Un veterinario solo ve los suyos (403 si pide los de otro); un asistente
+/// responde 403 y sin token de usuario responde 401.
+///
+/// # Ejemplo
+/// GET /stats/procedures/detail?procedure_type=Vaccine&start_date=2024-01-01&species=Dog
+#[actix_web::get("/procedures/detail")]
+async fn get_procedures_detail(
+    claims: Claims,
+    query: web::Query<ProcedureDetailQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let mut query = query.into_inner();
+
+    claims.require_role(&[UserRole::Admin, UserRole::Veterinarian])?;
+    if claims.role == UserRole::Veterinarian {
+        if query.user_id.is_some_and(|id| id != claims.sub) {
+            return Err(ApiError::Forbidden(
+                "Solo puedes consultar tus propios procedimientos".into(),
+            ));
+        }
+        query.user_id = Some(claims.sub);
+    }
+    tracing::info!("Detalle de procedimientos: {:?}", &query);
+    ensure_species_exist(pool.get_ref(), &query.species).await?;
+
+    let procedures = sqlx::query_as!(
+        PatientProcedure,
+        r#"
+        SELECT
+            pp.id,
+            pp.patient_id as "patient_id!: i32",
+            pp.procedure_id as "procedure_id!: i32",
+            pp.veterinarian_id as "veterinarian_id!: Option<i32>",
+            pp.performed_by,
+            pp.date as "date!: chrono::NaiveDate",
+            pp.next_due_date as "next_due_date!: Option<chrono::NaiveDate>",
+            pp.notes,
+            pp.batch_number,
+            pp.manufacturer
+        FROM patient_procedures pp
+        JOIN procedures p ON p.id = pp.procedure_id
+        JOIN patients pa ON pa.id = pp.patient_id
+        WHERE p.type = $1
+          AND ($2::date IS NULL OR pp.date >= $2)
+          AND ($3::date IS NULL OR pp.date <= $3)
+          AND ($4::text IS NULL OR pa.species = $4)
+          AND ($5::int IS NULL OR
+               (CASE WHEN $6 THEN pp.performed_by ELSE pp.veterinarian_id END) = $5)
+        ORDER BY pp.date DESC, pp.id DESC
+        LIMIT $7 OFFSET $8
+        "#,
+        &query.procedure_type as &ProcedureType,
+        query.start_date,
+        query.end_date,
+        &query.species as &Option<AnimalSpecies>,
+        query.user_id,
+        query.by.unwrap_or_default() == ProcedureActor::Performer,
+        query.limit.unwrap_or(50).min(400),
+        query.offset.unwrap_or(0)
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener el detalle de procedimientos", e))?;
+
+    let responses = futures::future::try_join_all(procedures.into_iter().map(|procedure| async {
+        PatientProcedureResponse::from_procedure(procedure, pool.get_ref()).await
+    }))
+    .await?;
+
+    Ok(HttpResponse::Ok().json(responses))
+}
+
+/// Reporte mensual de desempeño de un veterinario en PDF (admin o el propio
+/// veterinario). Un veterinario desconocido o un mes sin actividad producen
+/// un reporte "sin actividad" en lugar de un error.
+///
+/// # Ejemplo
+/// GET /stats/veterinarian/1/report.pdf?month=2024-06
+#[actix_web::get("/veterinarian/{id}/report.pdf")]
+async fn get_veterinarian_report_pdf(
+    claims: Claims,
+    id: PathId,
+    query: web::Query<VeterinarianReportQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin, UserRole::Veterinarian])?;
+    if claims.role == UserRole::Veterinarian && claims.sub != *id {
+        return Err(ApiError::Forbidden(
+            "Solo puedes consultar tus propias estadísticas".into(),
+        ));
+    }
+    let (start, end) = query.month_range(clinic::today())?;
+    tracing::info!(
+        "Generando reporte mensual del veterinario {} ({})",
+        id,
+        start
+    );
+
+    let name = sqlx::query_scalar!(
+        "SELECT name FROM users WHERE id = $1 AND role = 'veterinarian'",
+        *id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?;
+    let stats = get_veterinarian_stats(
+        pool.get_ref(),
+        *id,
+        ProcedureActor::default(),
+        Some(start),
+        Some(end),
+        None,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/pdf")
+        .append_header((
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"reporte-veterinario-{}-{}.pdf\"",
+                *id,
+                start.format("%Y-%m")
+            ),
+        ))
+        .body(vet_report::render_pdf(*id, name.as_deref(), start, &stats)))
+}
+
+/// Verifica que `user_id` sea un veterinario activo, para no devolver
+/// estadísticas en cero de un usuario inexistente
+async fn ensure_active_veterinarian(pool: &PgPool, user_id: i32) -> Result<(), ApiError> {
+    let user = sqlx::query!(
+        r#"SELECT role as "role: UserRole", is_active FROM users WHERE id = $1"#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("El usuario (ID: {user_id}) no existe")))?;
+
+    if user.role != UserRole::Veterinarian {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "El usuario (ID: {user_id}) no es veterinario"
+        )));
+    }
+    if !user.is_active {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "El veterinario (ID: {user_id}) está inactivo"
+        )));
+    }
+    Ok(())
+}
+
+/// Serie mensual de citas; los meses sin citas aparecen con 0
 async fn get_appointments_by_month(
+    pool: &PgPool,
+    start_date: chrono::NaiveDate,
+    end_date: chrono::NaiveDate,
+) -> Result<Vec<AppointmentsByMonth>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            TO_CHAR(m.month, 'YYYY-MM') AS month,
+            COUNT(a.id) AS count
+        FROM GENERATE_SERIES(
+            DATE_TRUNC('month', $1::date),
+            DATE_TRUNC('month', $2::date),
+            INTERVAL '1 month'
+        ) AS m(month)
+        LEFT JOIN appointments a
+          ON DATE_TRUNC('month', a.start_time) = m.month
+         AND a.start_time::date >= $1
+         AND a.start_time::date <= $2
+        GROUP BY m.month
+        ORDER BY m.month ASC
+        "#,
+        start_date,
+        end_date
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| AppointmentsByMonth {
+            month: row.month.unwrap_or_default(),
+            count: row.count.unwrap_or(0),
+        })
+        .collect())
+}
+
+/// Citas por origen de la reserva
+async fn get_appointments_by_source(
     pool: &PgPool,
     start_date: Option<chrono::NaiveDate>,
     end_date: Option<chrono::NaiveDate>,
-) -> Result<Vec<AppointmentsByMonth>, ApiError> {
+) -> Result<Vec<AppointmentsBySource>, ApiError> {
     let rows = sqlx::query!(
         r#"
         SELECT
-            TO_CHAR(start_time, 'YYYY-MM') AS month,
+            booking_source::text AS source,
             COUNT(*) AS count
         FROM appointments
         WHERE ($1::date IS NULL OR start_time::date >= $1)
           AND ($2::date IS NULL OR start_time::date <= $2)
-        GROUP BY month
-        ORDER BY month ASC
+        GROUP BY booking_source
+        ORDER BY count DESC
         "#,
         start_date,
         end_date
@@ -86,13 +357,134 @@ async fn get_appointments_by_month(
 
     Ok(rows
         .into_iter()
-        .map(|row| AppointmentsByMonth {
-            month: row.month.unwrap_or_default(),
+        .map(|row| AppointmentsBySource {
+            source: row.source.unwrap_or_default(),
             count: row.count.unwrap_or(0),
         })
         .collect())
 }
 
+/// Serie mensual de referencias; los meses sin referencias aparecen con 0
+async fn get_referrals_by_month(
+    pool: &PgPool,
+    start_date: chrono::NaiveDate,
+    end_date: chrono::NaiveDate,
+) -> Result<Vec<ReferralsByMonth>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            TO_CHAR(m.month, 'YYYY-MM') AS month,
+            COUNT(r.id) FILTER (WHERE r.direction = 'received') AS received,
+            COUNT(r.id) FILTER (WHERE r.direction = 'sent') AS sent
+        FROM GENERATE_SERIES(
+            DATE_TRUNC('month', $1::date),
+            DATE_TRUNC('month', $2::date),
+            INTERVAL '1 month'
+        ) AS m(month)
+        LEFT JOIN referrals r
+          ON DATE_TRUNC('month', r.referral_date) = m.month
+         AND r.referral_date >= $1
+         AND r.referral_date <= $2
+        GROUP BY m.month
+        ORDER BY m.month ASC
+        "#,
+        start_date,
+        end_date
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ReferralsByMonth {
+            month: row.month.unwrap_or_default(),
+            received: row.received.unwrap_or(0),
+            sent: row.sent.unwrap_or(0),
+        })
+        .collect())
+}
+
+/// Calificación promedio por veterinario y por mes, según la fecha de la cita.
+/// Solo incluye veterinarios con al menos una respuesta en el rango; sus meses
+/// sin respuestas aparecen con `average_rating: null`.
+async fn get_satisfaction(
+    pool: &PgPool,
+    start_date: chrono::NaiveDate,
+    end_date: chrono::NaiveDate,
+) -> Result<Vec<SatisfactionByVeterinarian>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        WITH answers AS (
+            SELECT a.veterinarian_id, DATE_TRUNC('month', a.start_time) AS month, s.rating
+            FROM satisfaction_surveys s
+            JOIN appointments a ON a.id = s.appointment_id
+            WHERE s.answered_at IS NOT NULL
+              AND a.start_time::date >= $1
+              AND a.start_time::date <= $2
+        )
+        SELECT
+            u.id AS veterinarian_id,
+            u.name AS veterinarian_name,
+            TO_CHAR(m.month, 'YYYY-MM') AS "month!",
+            COUNT(ans.rating) AS "responses!",
+            COALESCE(SUM(ans.rating), 0) AS "rating_sum!"
+        FROM users u
+        CROSS JOIN GENERATE_SERIES(
+            DATE_TRUNC('month', $1::date),
+            DATE_TRUNC('month', $2::date),
+            INTERVAL '1 month'
+        ) AS m(month)
+        LEFT JOIN answers ans ON ans.veterinarian_id = u.id AND ans.month = m.month
+        WHERE u.id IN (SELECT veterinarian_id FROM answers)
+        GROUP BY u.id, u.name, m.month
+        ORDER BY u.name, u.id, m.month
+        "#,
+        start_date,
+        end_date
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let average = |sum: i64, count: i64| {
+        (count > 0).then(|| (sum as f64 / count as f64 * 100.0).round() / 100.0)
+    };
+    let mut veterinarians: Vec<(SatisfactionByVeterinarian, i64)> = Vec::new();
+    for row in rows {
+        if veterinarians
+            .last()
+            .is_none_or(|(vet, _)| vet.veterinarian_id != row.veterinarian_id)
+        {
+            veterinarians.push((
+                SatisfactionByVeterinarian {
+                    veterinarian_id: row.veterinarian_id,
+                    veterinarian_name: row.veterinarian_name,
+                    responses: 0,
+                    average_rating: None,
+                    by_month: Vec::new(),
+                },
+                0,
+            ));
+        }
+        if let Some((vet, rating_sum)) = veterinarians.last_mut() {
+            vet.responses += row.responses;
+            *rating_sum += row.rating_sum;
+            vet.by_month.push(SatisfactionByMonth {
+                month: row.month,
+                responses: row.responses,
+                average_rating: average(row.rating_sum, row.responses),
+            });
+        }
+    }
+
+    Ok(veterinarians
+        .into_iter()
+        .map(|(mut vet, rating_sum)| {
+            vet.average_rating = average(rating_sum, vet.responses);
+            vet
+        })
+        .collect())
+}
+
 async fn get_user_counts(pool: &PgPool) -> Result<UserCounts, ApiError> {
     let counts = sqlx::query!(
         r#"
@@ -119,6 +511,7 @@ async fn get_procedures_by_type(
     pool: &PgPool,
     start_date: Option<chrono::NaiveDate>,
     end_date: Option<chrono::NaiveDate>,
+    species: Option<&AnimalSpecies>,
 ) -> Result<Vec<ProceduresByType>, ApiError> {
     let rows = sqlx::query!(
         r#"
@@ -127,13 +520,16 @@ async fn get_procedures_by_type(
             COUNT(*) AS count
         FROM patient_procedures pp
         JOIN procedures p ON pp.procedure_id = p.id
+        JOIN patients pa ON pa.id = pp.patient_id
         WHERE ($1::date IS NULL OR pp.date >= $1)
           AND ($2::date IS NULL OR pp.date <= $2)
+          AND ($3::text IS NULL OR pa.species = $3)
         GROUP BY p.type
         ORDER BY count DESC
         "#,
         start_date,
-        end_date
+        end_date,
+        species as Option<&AnimalSpecies>
     )
     .fetch_all(pool)
     .await?;
@@ -173,8 +569,10 @@ async fn get_patients_by_species(pool: &PgPool) -> Result<Vec<PatientsBySpecies>
 async fn get_veterinarian_stats(
     pool: &PgPool,
     user_id: i32,
+    by: ProcedureActor,
     start_date: Option<chrono::NaiveDate>,
     end_date: Option<chrono::NaiveDate>,
+    species: Option<&AnimalSpecies>,
 ) -> Result<VeterinarianStats, ApiError> {
     // Citas por estado
     let appointments_by_status = sqlx::query!(
@@ -201,7 +599,7 @@ async fn get_veterinarian_stats(
     })
     .collect();
 
-    // Procedimientos realizados
+    // Procedimientos realizados (como responsable o como quien los realizó)
     let procedures_performed = sqlx::query!(
         r#"
         SELECT
@@ -209,14 +607,18 @@ async fn get_veterinarian_stats(
             COUNT(*) AS count
         FROM patient_procedures pp
         JOIN procedures p ON pp.procedure_id = p.id
-        WHERE pp.veterinarian_id = $1
+        JOIN patients pa ON pa.id = pp.patient_id
+        WHERE (CASE WHEN $4 THEN pp.performed_by ELSE pp.veterinarian_id END) = $1
           AND ($2::date IS NULL OR pp.date >= $2)
           AND ($3::date IS NULL OR pp.date <= $3)
+          AND ($5::text IS NULL OR pa.species = $5)
         GROUP BY procedure_type
         "#,
         user_id,
         start_date,
-        end_date
+        end_date,
+        by == ProcedureActor::Performer,
+        species as Option<&AnimalSpecies>
     )
     .fetch_all(pool)
     .await?
@@ -270,17 +672,145 @@ async fn get_veterinarian_stats(
     })
     .collect();
 
+    // Promedios de las citas del rango y de sus encuestas respondidas
+    let averages = sqlx::query!(
+        r#"
+        SELECT
+            AVG(EXTRACT(EPOCH FROM a.end_time - a.start_time) / 60)
+                FILTER (WHERE a.status = 'completed')::FLOAT8 AS average_duration_minutes,
+            COUNT(*) FILTER (WHERE a.status = 'no_show') AS "no_shows!",
+            COUNT(*) FILTER (WHERE a.status IN ('completed', 'no_show')) AS "attended_or_missed!",
+            COUNT(s.rating) AS "survey_responses!",
+            AVG(s.rating)::FLOAT8 AS average_rating
+        FROM appointments a
+        LEFT JOIN satisfaction_surveys s ON s.appointment_id = a.id
+        WHERE a.veterinarian_id = $1
+          AND ($2::date IS NULL OR a.start_time::date >= $2)
+          AND ($3::date IS NULL OR a.start_time::date <= $3)
+        "#,
+        user_id,
+        start_date,
+        end_date
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let round = |value: f64| (value * 100.0).round() / 100.0;
     Ok(VeterinarianStats {
         appointments_by_status,
         procedures_performed,
         medical_records_created,
         patients_attended,
+        average_duration_minutes: averages.average_duration_minutes.map(round),
+        no_show_rate: (averages.attended_or_missed > 0)
+            .then(|| round(averages.no_shows as f64 / averages.attended_or_missed as f64 * 100.0)),
+        survey_responses: averages.survey_responses,
+        average_rating: averages.average_rating.map(round),
     })
 }
 
 // Exporta todas las funciones como un grupo
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
-        web::scope("/stats").service(get_statistics), // Agrega más servicios aquí...
+        web::scope("/stats")
+            .service(get_statistics)
+            .service(get_procedures_detail)
+            .service(get_veterinarian_report_pdf), // Agrega más servicios aquí...
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::as_user;
+    use actix_web::App;
+    use actix_web::test::{TestRequest, call_service, init_service, read_body_json};
+
+    fn claims(sub: i32, role: UserRole) -> Claims {
+        Claims {
+            sub,
+            role,
+            iat: 0,
+            exp: usize::MAX,
+            api_key_id: None,
+            impersonator: None,
+        }
+    }
+
+    #[test]
+    fn veterinarian_never_gets_the_system_scope() {
+        // Con alcance de veterinario no se calculan `user_counts`
+        let vet = claims(3, UserRole::Veterinarian);
+        assert_eq!(statistics_scope(&vet, None).unwrap(), Some(3));
+        assert_eq!(statistics_scope(&vet, Some(3)).unwrap(), Some(3));
+        assert!(matches!(
+            statistics_scope(&vet, Some(4)),
+            Err(ApiError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn assistant_is_rejected() {
+        let assistant = claims(9, UserRole::Assistant);
+        assert!(matches!(
+            statistics_scope(&assistant, None),
+            Err(ApiError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn only_admins_get_satisfaction_in_the_system_scope() {
+        let satisfaction = Some(&[StatisticType::Satisfaction][..]);
+        let vet = claims(3, UserRole::Veterinarian);
+        assert!(matches!(
+            ensure_satisfaction_allowed(&vet, Some(3), satisfaction),
+            Err(ApiError::Forbidden(_))
+        ));
+        assert!(ensure_satisfaction_allowed(&vet, Some(3), None).is_ok());
+
+        let admin = claims(1, UserRole::Admin);
+        assert!(ensure_satisfaction_allowed(&admin, None, satisfaction).is_ok());
+        assert!(matches!(
+            ensure_satisfaction_allowed(&admin, Some(3), satisfaction),
+            Err(ApiError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn admin_gets_the_system_or_a_veterinarian() {
+        let admin = claims(1, UserRole::Admin);
+        assert_eq!(statistics_scope(&admin, None).unwrap(), None);
+        assert_eq!(statistics_scope(&admin, Some(3)).unwrap(), Some(3));
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn veterinarian_stats_need_an_active_veterinarian(pool: PgPool) {
+        // El veterinario 2 queda inactivo; el 3 es asistente
+        sqlx::query!("UPDATE users SET is_active = FALSE WHERE id = 2")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let app = init_service(App::new().app_data(web::Data::new(pool)).configure(config)).await;
+        let stats = |user_id: i32| {
+            as_user(
+                TestRequest::get()
+                    .uri(&format!("/stats?user_id={user_id}"))
+                    .to_request(),
+                claims(4, UserRole::Admin),
+            )
+        };
+
+        for (user_id, status) in [(9999, 404), (3, 422), (4, 422), (2, 422)] {
+            let response = call_service(&app, stats(user_id)).await;
+            assert_eq!(response.status(), status, "user_id={user_id}");
+        }
+
+        let response = call_service(&app, stats(1)).await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = read_body_json(response).await;
+        assert!(body["veterinarian_stats"].is_object());
+    }
+}
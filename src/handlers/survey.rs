@@ -0,0 +1,109 @@
+use crate::clinic;
+use crate::errors::ApiError;
+use crate::models::survey::{SurveyAnswer, SurveyInfo};
+use crate::surveys;
+use actix_web::{HttpResponse, web};
+use sqlx::PgPool;
+use validator::Validate;
+
+/// Obtiene una encuesta pendiente; si ya se respondió responde 410
+async fn fetch_pending(pool: &PgPool, survey_id: i32) -> Result<SurveyInfo, ApiError> {
+    let survey = sqlx::query!(
+        r#"
+        SELECT
+            -- Sin el título (Dr., Dra.) para mostrar el nombre de pila
+            SPLIT_PART(REGEXP_REPLACE(TRIM(u.name), '^Dra?\.\s*', '', 'i'), ' ', 1)
+                as "veterinarian_first_name!",
+            a.start_time,
+            s.answered_at
+        FROM satisfaction_surveys s
+        JOIN appointments a ON a.id = s.appointment_id
+        JOIN users u ON u.id = a.veterinarian_id
+        WHERE s.id = $1
+        "#,
+        survey_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::NotFound("La encuesta no existe".into()))?;
+
+    if survey.answered_at.is_some() {
+        return Err(ApiError::Gone("La encuesta ya fue respondida".into()));
+    }
+
+    Ok(SurveyInfo {
+        veterinarian_first_name: survey.veterinarian_first_name,
+        appointment_date: clinic::local(survey.start_time).date_naive(),
+    })
+}
+
+/// Muestra la encuesta de satisfacción de una cita.
+///
+/// Pública: el token del enlace enviado al cliente es la única credencial.
+///
+/// # Ejemplo
+/// GET /survey/eyJ0eXAiOiJKV1Qi...
+#[actix_web::get("/{token}")]
+async fn get_survey(
+    token: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let survey_id = surveys::decode_token(&token)?;
+    Ok(HttpResponse::Ok().json(fetch_pending(pool.get_ref(), survey_id).await?))
+}
+
+/// Registra la calificación del cliente. Cada encuesta se responde una sola
+/// vez; los intentos siguientes responden 410.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "rating": 5,
+///   "comment": "Muy amables con Max"
+/// }
+/// ```
+#[actix_web::post("/{token}")]
+async fn answer_survey(
+    token: web::Path<String>,
+    answer: web::Json<SurveyAnswer>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let answer = answer.into_inner();
+    answer.validate()?;
+
+    let survey_id = surveys::decode_token(&token)?;
+    fetch_pending(pool.get_ref(), survey_id).await?;
+
+    // La condición sobre answered_at evita que dos envíos simultáneos se registren
+    let answered = sqlx::query!(
+        r#"
+        UPDATE satisfaction_surveys
+        SET rating = $1, comment = $2, answered_at = NOW()
+        WHERE id = $3 AND answered_at IS NULL
+        "#,
+        answer.rating,
+        answer
+            .comment
+            .map(|comment| comment.trim().to_string())
+            .filter(|comment| !comment.is_empty()),
+        survey_id
+    )
+    .execute(pool.get_ref())
+    .await?
+    .rows_affected();
+
+    if answered == 0 {
+        return Err(ApiError::Gone("La encuesta ya fue respondida".into()));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/survey")
+            .service(get_survey)
+            .service(answer_survey),
+    );
+}
@@ -0,0 +1,302 @@
+use crate::errors::ApiError;
+use crate::models::appointment::{Appointment, AppointmentResponse};
+use crate::models::client::{Client, ClientResponse};
+use crate::models::enums::{AnimalGender, AnimalSpecies, AppointmentStatus, BookingSource};
+use crate::models::patient::{Patient, PatientRaw, PatientResponse};
+use crate::models::sync::{SyncChanges, SyncQuery, SyncResponse};
+use actix_web::{HttpResponse, web};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use sqlx::types::BigDecimal;
+
+/// Cambios por entidad en cada respuesta
+const SYNC_PAGE_SIZE: i64 = 500;
+
+/// Entidades sincronizables; cada tabla tiene `updated_at` y lápidas en `sync_tombstones`
+const SYNC_ENTITIES: [&str; 3] = ["clients", "patients", "appointments"];
+
+/// Resuelve la lista separada por comas del parámetro `entities` (vacía = todas)
+fn select_entities(names: Option<&str>) -> Result<Vec<&'static str>, ApiError> {
+    let Some(names) = names.filter(|names| !names.trim().is_empty()) else {
+        return Ok(SYNC_ENTITIES.to_vec());
+    };
+
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            SYNC_ENTITIES
+                .into_iter()
+                .find(|entity| *entity == name)
+                .ok_or_else(|| {
+                    ApiError::ValidationError(format!(
+                        "Entidad desconocida: {}. Disponibles: {}",
+                        name,
+                        SYNC_ENTITIES.join(", ")
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Página de cambios de una entidad: ids modificados, ids eliminados y el
+/// cursor para continuar si quedaron más.
+struct ChangePage {
+    updated_ids: Vec<i32>,
+    deleted_ids: Vec<i32>,
+    has_more: bool,
+    next_since: Option<DateTime<Utc>>,
+}
+
+/// Modificaciones y borrados posteriores a `since`, en orden cronológico
+async fn change_page(
+    pool: &PgPool,
+    entity: &'static str,
+    since: DateTime<Utc>,
+    limit: i64,
+) -> Result<ChangePage, ApiError> {
+    let rows: Vec<(i32, DateTime<Utc>, bool)> = sqlx::query_as(&format!(
+        r#"
+        SELECT id, changed_at, deleted
+        FROM (
+            SELECT id, updated_at AS changed_at, FALSE AS deleted
+            FROM {entity}
+            WHERE updated_at > $1
+            UNION ALL
+            SELECT entity_id, deleted_at, TRUE
+            FROM sync_tombstones
+            WHERE entity = $2 AND deleted_at > $1
+        ) changes
+        ORDER BY changed_at, id
+        LIMIT $3
+        "#
+    ))
+    .bind(since)
+    .bind(entity)
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ApiError::internal("Error al sincronizar", format!("{entity}: {e}")))?;
+
+    let has_more = rows.len() as i64 > limit;
+    let rows = &rows[..rows.len().min(limit as usize)];
+    let (deleted, updated): (Vec<_>, Vec<_>) = rows.iter().partition(|(_, _, deleted)| *deleted);
+
+    Ok(ChangePage {
+        updated_ids: updated.into_iter().map(|(id, _, _)| id).collect(),
+        deleted_ids: deleted.into_iter().map(|(id, _, _)| id).collect(),
+        has_more,
+        next_since: rows
+            .last()
+            .filter(|_| has_more)
+            .map(|(_, changed_at, _)| *changed_at),
+    })
+}
+
+async fn sync_clients(
+    pool: &PgPool,
+    page: ChangePage,
+) -> Result<SyncChanges<ClientResponse>, ApiError> {
+    let clients = sqlx::query_as!(
+        Client,
+        r#"
+        SELECT
+            id,
+            name,
+            email,
+            phone,
+            address,
+            notes,
+            assigned_to,
+            marketing_opt_out,
+            custom_fields,
+            household_id
+        FROM clients
+        WHERE id = ANY($1)
+        ORDER BY updated_at, id
+        "#,
+        &page.updated_ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(SyncChanges {
+        updated: ClientResponse::load(pool, clients).await?,
+        deleted_ids: page.deleted_ids,
+        has_more: page.has_more,
+        next_since: page.next_since,
+    })
+}
+
+async fn sync_patients(
+    pool: &PgPool,
+    page: ChangePage,
+) -> Result<SyncChanges<PatientResponse>, ApiError> {
+    let patients = sqlx::query_as!(
+        PatientRaw,
+        r#"
+        SELECT
+            id,
+            name,
+            species as "species!: AnimalSpecies",
+            breed as "breed_id!: Option<i32>",
+            birth_date,
+            gender as "gender!: Option<AnimalGender>",
+            weight_kg as "weight_kg!: BigDecimal",
+            client_id as "client_id!: i32",
+            photo_url,
+            deceased_on,
+            custom_fields,
+            insurance_provider,
+            insurance_policy_number,
+            insurance_expires_on
+        FROM patients
+        WHERE id = ANY($1)
+        ORDER BY updated_at, id
+        "#,
+        &page.updated_ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let breeds: Vec<(i32, String)> = sqlx::query_as(
+        "SELECT id, name FROM breeds WHERE id IN (SELECT breed FROM patients WHERE id = ANY($1))",
+    )
+    .bind(&page.updated_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(SyncChanges {
+        updated: patients
+            .into_iter()
+            .map(|raw| {
+                let patient: Patient = raw.into();
+                let breed = patient.breed_id.and_then(|breed_id| {
+                    breeds
+                        .iter()
+                        .find(|(id, _)| *id == breed_id)
+                        .map(|(_, name)| name.clone())
+                });
+                let mut response: PatientResponse = patient.into();
+                response.breed = breed;
+                response
+            })
+            .collect(),
+        deleted_ids: page.deleted_ids,
+        has_more: page.has_more,
+        next_since: page.next_since,
+    })
+}
+
+async fn sync_appointments(
+    pool: &PgPool,
+    page: ChangePage,
+) -> Result<SyncChanges<AppointmentResponse>, ApiError> {
+    let appointments = sqlx::query_as!(
+        Appointment,
+        r#"
+        SELECT
+            id,
+            patient_id,
+            client_id,
+            veterinarian_id,
+            start_time as "start_time!: chrono::DateTime<chrono::Utc>",
+            end_time as "end_time!: chrono::DateTime<chrono::Utc>",
+            status as "status!: AppointmentStatus",
+            reason,
+            is_emergency,
+            procedure_id,
+            room_id,
+            booking_source as "booking_source!: BookingSource",
+            internal_notes,
+            created_at as "created_at!: chrono::DateTime<chrono::Utc>",
+            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+            canceled_at,
+            cancellation_reason
+        FROM appointments
+        WHERE id = ANY($1)
+        ORDER BY updated_at, id
+        "#,
+        &page.updated_ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let updated = futures::future::try_join_all(
+        appointments
+            .into_iter()
+            .map(|app| AppointmentResponse::from_appointment(app, pool)),
+    )
+    .await?;
+
+    Ok(SyncChanges {
+        updated,
+        deleted_ids: page.deleted_ids,
+        has_more: page.has_more,
+        next_since: page.next_since,
+    })
+}
+
+/// Sincronización diferencial para clientes con conexión intermitente.
+///
+/// Devuelve por entidad las filas creadas o modificadas y los ids eliminados
+/// después de `since`. Si una entidad trae `has_more`, se vuelve a pedir solo
+/// esa entidad con `since=next_since` hasta agotarla; después se usa
+/// `server_time` como el siguiente `since`. Una fila puede llegar repetida
+/// entre sincronizaciones, así que el cliente debe aplicarlas por id.
+///
+/// # Parámetros (vía query string)
+/// - `since`: Cursor, fecha en RFC 3339 (requerido)
+/// - `entities`: `clients`, `patients`, `appointments` separadas por comas (default: todas)
+/// - `limit`: Cambios por entidad (default y máximo: 500)
+///
+/// # Ejemplo
+/// GET /sync?since=2024-06-01T10:00:00Z&entities=patients,appointments
+#[actix_web::get("")]
+async fn sync(
+    query: web::Query<SyncQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let entities = select_entities(query.entities.as_deref())?;
+    let limit = query
+        .limit
+        .unwrap_or(SYNC_PAGE_SIZE)
+        .clamp(1, SYNC_PAGE_SIZE);
+    tracing::info!("Sincronizando {:?} desde {}", entities, query.since);
+
+    // El cursor no puede ser posterior al inicio de una transacción en curso:
+    // sus cambios se confirmarán con una marca de tiempo anterior.
+    let server_time: DateTime<Utc> = sqlx::query_scalar(
+        r#"
+        SELECT LEAST(CLOCK_TIMESTAMP(), MIN(xact_start))
+        FROM pg_stat_activity
+        WHERE datname = CURRENT_DATABASE() AND pid <> PG_BACKEND_PID()
+        "#,
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    let mut response = SyncResponse {
+        server_time,
+        clients: None,
+        patients: None,
+        appointments: None,
+    };
+
+    for entity in entities {
+        let page = change_page(pool.get_ref(), entity, query.since, limit).await?;
+        match entity {
+            "clients" => response.clients = Some(sync_clients(pool.get_ref(), page).await?),
+            "patients" => response.patients = Some(sync_patients(pool.get_ref(), page).await?),
+            _ => response.appointments = Some(sync_appointments(pool.get_ref(), page).await?),
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/sync").service(sync));
+}
@@ -0,0 +1,311 @@
+use crate::auth::Claims;
+use crate::errors::ApiError;
+use crate::extractors::PathId;
+use crate::models::enums::{TaskStatus, UserRole};
+use crate::models::treatment_task::{
+    CompleteTreatmentTask, DueTasksQuery, NewTreatmentTask, TreatmentTaskFilter,
+    TreatmentTaskResponse,
+};
+use crate::models::warning::WithWarnings;
+use actix_web::{HttpResponse, web};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use validator::Validate;
+
+/// Máximo de tareas que genera una sola petición
+const MAX_OCCURRENCES: i64 = 100;
+
+/// Obtiene las tareas indicadas con los datos del paciente
+async fn fetch_tasks(pool: &PgPool, ids: &[i32]) -> Result<Vec<TreatmentTaskResponse>, ApiError> {
+    let tasks = sqlx::query_as!(
+        TreatmentTaskResponse,
+        r#"
+        SELECT
+            t.id,
+            t.hospitalization_id,
+            h.patient_id,
+            p.name as patient_name,
+            h.kennel,
+            t.description,
+            t.scheduled_time,
+            t.assigned_role as "assigned_role: UserRole",
+            t.status as "status!: TaskStatus",
+            t.done_by,
+            u.name as "done_by_name?",
+            t.done_at,
+            t.notes
+        FROM treatment_tasks t
+        JOIN hospitalizations h ON h.id = t.hospitalization_id
+        JOIN patients p ON p.id = h.patient_id
+        LEFT JOIN users u ON u.id = t.done_by
+        WHERE t.id = ANY($1)
+        ORDER BY t.scheduled_time, t.id
+        "#,
+        ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tasks)
+}
+
+/// Programa una tarea de tratamiento para un paciente internado.
+///
+/// Con `repeat_every_hours` se genera una serie: `occurrences` tareas o, si no
+/// se indica, hasta el alta esperada de la hospitalización.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "hospitalization_id": 1,
+///   "description": "Amoxicilina 250 mg VO",
+///   "scheduled_time": "2024-06-01T20:00:00Z",
+///   "assigned_role": "Assistant",
+///   "repeat_every_hours": 8,
+///   "occurrences": 6
+/// }
+/// ```
+#[actix_web::post("")]
+async fn create_treatment_tasks(
+    new_task: web::Json<NewTreatmentTask>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Programando tareas de tratamiento");
+
+    let new_task = new_task.into_inner();
+    new_task.validate()?;
+
+    let hospitalization = sqlx::query!(
+        r#"
+        SELECT discharged_at, expected_discharge_at
+        FROM hospitalizations
+        WHERE id = $1
+        "#,
+        new_task.hospitalization_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or(ApiError::NotFound("La hospitalización no existe".into()))?;
+
+    if hospitalization.discharged_at.is_some() {
+        return Err(ApiError::Conflict(
+            "No se pueden programar tareas para un paciente dado de alta".into(),
+        ));
+    }
+
+    let interval_hours = new_task.repeat_every_hours.unwrap_or(0);
+    let occurrences = match (new_task.occurrences, new_task.repeat_every_hours) {
+        (Some(occurrences), _) => i64::from(occurrences),
+        (None, None) => 1,
+        (None, Some(hours)) => {
+            let until = hospitalization.expected_discharge_at.ok_or_else(|| {
+                ApiError::ValidationError(
+                    "Indica occurrences o registra el alta esperada de la hospitalización".into(),
+                )
+            })?;
+            let span = until - new_task.scheduled_time;
+            (span.num_hours() / i64::from(hours) + 1).clamp(1, MAX_OCCURRENCES)
+        }
+    };
+
+    let ids: Vec<i32> = sqlx::query_scalar!(
+        r#"
+        INSERT INTO treatment_tasks (
+            hospitalization_id,
+            description,
+            scheduled_time,
+            assigned_role
+        )
+        SELECT $1, $2, $3::TIMESTAMPTZ + n * make_interval(hours => $4), $5
+        FROM generate_series(0, $6::BIGINT - 1) AS n
+        RETURNING id
+        "#,
+        new_task.hospitalization_id,
+        new_task.description.trim(),
+        new_task.scheduled_time,
+        interval_hours,
+        new_task.assigned_role as Option<UserRole>,
+        occurrences
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al guardar las tareas", e))?;
+
+    let tasks = fetch_tasks(pool.get_ref(), &ids).await?;
+
+    tracing::info!(
+        "{} tareas programadas para la hospitalización {}",
+        tasks.len(),
+        new_task.hospitalization_id
+    );
+
+    Ok(HttpResponse::Created().json(tasks))
+}
+
+/// Lista tareas de tratamiento
+///
+/// # Parámetros (opcionales vía query string)
+/// - `hospitalization_id`: Filtrar por hospitalización
+/// - `status`: Filtrar por estado (Pending, Done, Skipped)
+/// - `limit`: Máximo de resultados (default: 50)
+/// - `offset`: Desplazamiento (default: 0)
+#[actix_web::get("")]
+async fn list_treatment_tasks(
+    filters: web::Query<TreatmentTaskFilter>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Listando tareas con filtros: {:?}", &filters);
+
+    let ids: Vec<i32> = sqlx::query_scalar!(
+        r#"
+        SELECT id
+        FROM treatment_tasks
+        WHERE
+            ($1::INT IS NULL OR hospitalization_id = $1) AND
+            ($2::task_status IS NULL OR status = $2)
+        ORDER BY scheduled_time, id
+        LIMIT $3 OFFSET $4
+        "#,
+        filters.hospitalization_id,
+        filters.status as Option<TaskStatus>,
+        filters.limit.unwrap_or(50).min(400),
+        filters.offset.unwrap_or(0)
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener tareas", e))?;
+
+    Ok(HttpResponse::Ok().json(fetch_tasks(pool.get_ref(), &ids).await?))
+}
+
+/// Lista de trabajo del turno: tareas pendientes de pacientes internados,
+/// incluidas las atrasadas, hasta `window_minutes` minutos desde ahora
+///
+/// # Ejemplo
+/// GET /treatment_tasks/due?window_minutes=60&role=Assistant
+#[actix_web::get("/due")]
+async fn list_due_tasks(
+    query: web::Query<DueTasksQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let window_minutes = query.window_minutes.unwrap_or(60).clamp(1, 720);
+    let until = Utc::now() + Duration::minutes(window_minutes);
+    tracing::info!("Listando tareas pendientes hasta {}", until);
+
+    let ids: Vec<i32> = sqlx::query_scalar!(
+        r#"
+        SELECT t.id
+        FROM treatment_tasks t
+        JOIN hospitalizations h ON h.id = t.hospitalization_id
+        WHERE t.status = 'pending'
+          AND h.discharged_at IS NULL
+          AND t.scheduled_time <= $1
+          AND ($2::user_role IS NULL OR t.assigned_role IS NULL OR t.assigned_role = $2)
+        "#,
+        until,
+        query.role.clone() as Option<UserRole>
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener tareas", e))?;
+
+    Ok(HttpResponse::Ok().json(fetch_tasks(pool.get_ref(), &ids).await?))
+}
+
+/// Marca una tarea como realizada (u omitida) por el usuario autenticado.
+///
+/// Si el paciente ya fue dado de alta la tarea se guarda igualmente y la
+/// respuesta incluye una advertencia en `warnings`.
+///
+/// # Ejemplo de petición
+/// ```json
+/// {
+///   "skipped": false,
+///   "notes": "Administrada sin incidentes"
+/// }
+/// ```
+#[actix_web::post("/{id}/complete")]
+async fn complete_treatment_task(
+    claims: Claims,
+    id: PathId,
+    completion: Option<web::Json<CompleteTreatmentTask>>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Completando tarea ID: {} (usuario {})", id, claims.sub);
+
+    let completion = completion.map(web::Json::into_inner).unwrap_or_default();
+    completion.validate()?;
+
+    let task = sqlx::query!(
+        r#"
+        SELECT
+            t.status as "status!: TaskStatus",
+            h.discharged_at
+        FROM treatment_tasks t
+        JOIN hospitalizations h ON h.id = t.hospitalization_id
+        WHERE t.id = $1
+        "#,
+        *id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or(ApiError::NotFound("La tarea no existe".into()))?;
+
+    if task.status != TaskStatus::Pending {
+        return Err(ApiError::Conflict("La tarea ya fue registrada".into()));
+    }
+
+    let status = if completion.skipped {
+        TaskStatus::Skipped
+    } else {
+        TaskStatus::Done
+    };
+
+    sqlx::query!(
+        r#"
+        UPDATE treatment_tasks
+        SET
+            status = $1,
+            done_by = $2,
+            done_at = NOW(),
+            notes = COALESCE($3, notes)
+        WHERE id = $4
+        "#,
+        status as TaskStatus,
+        claims.sub,
+        completion.notes.map(|s| s.trim().to_string()),
+        *id
+    )
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al actualizar la tarea", e))?;
+
+    let warnings: Vec<String> = task
+        .discharged_at
+        .map(|discharged_at| {
+            format!(
+                "El paciente fue dado de alta el {}",
+                discharged_at.format("%Y-%m-%d %H:%M UTC")
+            )
+        })
+        .into_iter()
+        .collect();
+
+    let response = fetch_tasks(pool.get_ref(), &[*id])
+        .await?
+        .pop()
+        .ok_or(ApiError::NotFound("La tarea no existe".into()))?;
+
+    Ok(HttpResponse::Ok().json(WithWarnings::new(response, warnings)))
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/treatment_tasks")
+            .service(create_treatment_tasks)
+            .service(list_treatment_tasks)
+            .service(list_due_tasks)
+            .service(complete_treatment_task),
+    );
+}
@@ -1,11 +1,26 @@
-use crate::auth::{create_jwt, verify_password};
+use crate::auth::{
+    Claims, IMPERSONATION_MINUTES, create_impersonation_jwt, create_jwt, generate_user_token,
+    hash_password, password_reset_minutes, refresh_token_days, user_token_prefix, verify_password,
+};
+use crate::clinic;
+use crate::deletion::{self, Resource};
 use crate::errors::ApiError;
+use crate::events::{DomainEvent, EventBus};
+use crate::extractors::PathId;
+use crate::handlers::ensure_exists;
+use crate::limits::{self, Limit};
 use crate::models::enums::UserRole;
-use crate::models::user::{NewUser, UpdateUser, User, UserFilter, UserResponse};
+use crate::models::user::{
+    ChangePassword, ImpersonationResponse, LoginResponse, LoginUser, NewUser, PasswordResetConfirm,
+    PasswordResetRequest, RefreshRequest, RefreshResponse, UpdateUser, User, UserFilter,
+    UserResponse,
+};
+use crate::notifications;
 use actix_web::{HttpResponse, web};
 use chrono::Utc;
-use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool};
+use std::collections::HashMap;
+use std::env;
 use validator::Validate;
 
 /// Lista usuarios con filtros avanzados y paginación
@@ -13,15 +28,21 @@ use validator::Validate;
 /// # Parámetros (opcionales vía query string)
 /// - `email`: Filtrar por correo electrónico
 /// - `role`: Filtrar por rol
+/// - `roles`: Varios roles separados por comas (se combina con `role`)
+/// - `q`: Búsqueda parcial en nombre o correo
 /// - `license_number`: Filtrar por número de licencia
 /// - `is_active`: Filtrar por estado activo/inactivo
 /// - `created_after`: Usuarios creados después de esta fecha
 /// - `created_before`: Usuarios creados antes de esta fecha
+/// - `last_login_before`: Usuarios sin iniciar sesión desde esta fecha,
+///   incluidos los que nunca lo han hecho
+/// - `include_workload`: Agrega `upcoming_appointments` y `appointments_this_week`;
+///   solo con `role=Veterinarian`
 /// - `limit`: Máximo de resultados (default: 50)
 /// - `offset`: Desplazamiento (default: 0)
 ///
 /// # Ejemplo
-/// GET /users?role=admin&is_active=true&limit=10
+/// GET /users?roles=Veterinarian,Assistant&q=mar&is_active=true&limit=10
 #[actix_web::get("")]
 async fn list_users(
     filters: web::Query<UserFilter>,
@@ -29,6 +50,14 @@ async fn list_users(
 ) -> Result<HttpResponse, ApiError> {
     tracing::info!("Listando usuarios con filtros: {:?}", &filters);
 
+    let roles = filters.role_list()?;
+    let include_workload = filters.include_workload.unwrap_or(false);
+    if include_workload && roles.as_deref() != Some(&[UserRole::Veterinarian]) {
+        return Err(ApiError::ValidationError(
+            "include_workload requiere filtrar por role=Veterinarian".into(),
+        ));
+    }
+
     let users = sqlx::query_as!(
         User,
         r#"
@@ -41,46 +70,89 @@ async fn list_users(
             license_number,
             is_active as "is_active!: bool",
             created_at as "created_at!: chrono::DateTime<chrono::Utc>",
-            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>"
+            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+            last_login_at
         FROM users
         WHERE
             ($1::text IS NULL OR email ILIKE '%' || $1 || '%') AND
-            ($2::user_role IS NULL OR role = $2) AND
+            ($2::user_role[] IS NULL OR role = ANY($2)) AND
             ($3::text IS NULL OR license_number = $3) AND
             ($4::bool IS NULL OR is_active = $4) AND
             ($5::timestamptz IS NULL OR created_at >= $5) AND
-            ($6::timestamptz IS NULL OR created_at <= $6)
+            ($6::timestamptz IS NULL OR created_at <= $6) AND
+            ($9::text IS NULL OR name ILIKE '%' || $9 || '%' OR email ILIKE '%' || $9 || '%') AND
+            ($10::timestamptz IS NULL OR last_login_at IS NULL OR last_login_at < $10)
         ORDER BY created_at DESC
         LIMIT $7 OFFSET $8
         "#,
         filters.email,
-        filters.role.clone() as Option<UserRole>,
+        roles as Option<Vec<UserRole>>,
         filters.license_number,
         filters.is_active,
         filters.created_after,
         filters.created_before,
         filters.limit.unwrap_or(50),
-        filters.offset.unwrap_or(0)
+        filters.offset.unwrap_or(0),
+        filters
+            .q
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty()),
+        filters.last_login_before
     )
     .fetch_all(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al listar usuarios: {}", e);
-        ApiError::InternalServerError("Error al obtener usuarios".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al obtener usuarios", e))?;
 
     // Convertir a respuestas enriquecidas
-    let responses: Vec<UserResponse> = users.into_iter().map(UserResponse::from).collect();
+    let mut responses: Vec<UserResponse> = users.into_iter().map(UserResponse::from).collect();
+    if include_workload {
+        add_workload(pool.get_ref(), &mut responses).await?;
+    }
 
     Ok(HttpResponse::Ok().json(responses))
 }
 
-/// Obtener un usuario por su ID
-#[actix_web::get("/{id}")]
-async fn get_user(id: web::Path<i32>, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
-    tracing::info!("Obteniendo usuario con ID: {}", &id);
+/// Agrega la carga de citas de cada veterinario con una sola consulta agrupada
+async fn add_workload(pool: &PgPool, users: &mut [UserResponse]) -> Result<(), ApiError> {
+    let ids: Vec<i32> = users.iter().map(|user| user.id).collect();
+    let (week_start, week_end) = clinic::week_bounds(clinic::today());
 
-    let user = sqlx::query_as!(
+    let workload: HashMap<i32, (i64, i64)> = sqlx::query!(
+        r#"
+        SELECT
+            veterinarian_id,
+            COUNT(*) FILTER (
+                WHERE status = 'scheduled' AND start_time >= NOW()
+            ) as "upcoming!",
+            COUNT(*) FILTER (
+                WHERE status <> 'canceled' AND start_time >= $2 AND start_time < $3
+            ) as "this_week!"
+        FROM appointments
+        WHERE veterinarian_id = ANY($1)
+        GROUP BY veterinarian_id
+        "#,
+        &ids,
+        week_start,
+        week_end
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| (row.veterinarian_id, (row.upcoming, row.this_week)))
+    .collect();
+
+    for user in users {
+        let (upcoming, this_week) = workload.get(&user.id).copied().unwrap_or_default();
+        user.upcoming_appointments = Some(upcoming);
+        user.appointments_this_week = Some(this_week);
+    }
+    Ok(())
+}
+
+/// Usuario activo por su ID; `None` si no existe o fue desactivado
+async fn fetch_active_user(pool: &PgPool, id: i32) -> Result<Option<User>, ApiError> {
+    sqlx::query_as!(
         User,
         r#"
         SELECT
@@ -92,20 +164,48 @@ async fn get_user(id: web::Path<i32>, pool: web::Data<PgPool>) -> Result<HttpRes
             license_number,
             is_active as "is_active!: bool",
             created_at as "created_at!: chrono::DateTime<chrono::Utc>",
-            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>"
+            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+            last_login_at
         FROM users
         WHERE id = $1 AND is_active = true
         "#,
-        id.clone()
+        id
     )
-    .fetch_optional(pool.get_ref())
+    .fetch_optional(pool)
     .await
-    .map_err(|e| {
-        tracing::error!("Error al buscar usuario: {}", e);
-        ApiError::InternalServerError("Error al obtener usuario".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al obtener usuario", e))
+}
 
-    match user {
+/// Usuario dueño del token, para que el frontend no tenga que decodificar
+/// el JWT. Responde 401 sin token de usuario (o con una llave de API) y 404
+/// si el usuario se desactivó después de emitir el token.
+///
+/// # Ejemplo
+/// GET /users/me
+#[actix_web::get("/me")]
+async fn get_current_user(
+    claims: Claims,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    if claims.api_key_id.is_some() {
+        return Err(ApiError::Unauthorized(
+            "Se requiere un token de usuario".into(),
+        ));
+    }
+
+    let user = fetch_active_user(pool.get_ref(), claims.sub)
+        .await?
+        .ok_or(ApiError::NotFound("El usuario ya no está activo".into()))?;
+
+    Ok(HttpResponse::Ok().json(UserResponse::from(user)))
+}
+
+/// Obtener un usuario por su ID
+#[actix_web::get("/{id}")]
+async fn get_user(id: PathId, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    tracing::info!("Obteniendo usuario con ID: {}", &id);
+
+    match fetch_active_user(pool.get_ref(), *id).await? {
         Some(rec) => {
             tracing::info!("Usuario {} encontrado", &id);
             Ok(HttpResponse::Ok().json(UserResponse::from(rec)))
@@ -120,7 +220,7 @@ async fn get_user(id: web::Path<i32>, pool: web::Data<PgPool>) -> Result<HttpRes
     }
 }
 
-/// Crea un nuevo usuario
+/// Crea un nuevo usuario (solo admin)
 ///
 /// # Ejemplo de petición
 /// ```json
@@ -134,9 +234,11 @@ async fn get_user(id: web::Path<i32>, pool: web::Data<PgPool>) -> Result<HttpRes
 /// ```
 #[actix_web::post("")]
 async fn create_user(
+    claims: Claims,
     new_user: web::Json<NewUser>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
     tracing::info!("Creando nuevo usuario");
 
     // Validar los datos de entrada
@@ -144,26 +246,37 @@ async fn create_user(
 
     let new_user = new_user.into_inner();
 
-    // Verificar si el email ya existe
-    let email_exists: bool =
-        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE email = $1)")
+    // Límite del plan contratado
+    limits::ensure_within_limit(pool.get_ref(), Limit::ActiveUsers).await?;
+
+    // Verificar si el email ya existe; si es de una cuenta desactivada se
+    // indica su id para ofrecer reactivarla en lugar de crear otra
+    let existing: Option<(i32, bool)> =
+        sqlx::query_as("SELECT id, is_active FROM users WHERE email = $1")
             .bind(&new_user.email)
-            .fetch_one(pool.get_ref())
+            .fetch_optional(pool.get_ref())
             .await?;
 
-    if email_exists {
+    if let Some((user_id, is_active)) = existing {
         tracing::warn!(
             "Intento de crear usuario con email existente: {:?}",
             new_user.email
         );
+        if !is_active {
+            return Err(ApiError::DetailedConflict {
+                message: "El email pertenece a una cuenta desactivada".into(),
+                details: serde_json::json!({
+                    "code": "inactive_account_exists",
+                    "user_id": user_id,
+                }),
+            });
+        }
         return Err(ApiError::Conflict("El email ya está registrado".into()));
     }
 
     // Hashear la contraseña
-    let password_hash = crate::auth::hash_password(&new_user.password).map_err(|e| {
-        tracing::error!("Error al hashear contraseña: {}", e);
-        ApiError::InternalServerError("Error al procesar contraseña".into())
-    })?;
+    let password_hash = crate::auth::hash_password(&new_user.password)
+        .map_err(|e| ApiError::internal("Error al procesar contraseña", e))?;
 
     // Insertar en la base de datos
     let user = sqlx::query_as!(
@@ -187,7 +300,8 @@ async fn create_user(
                 license_number,
                 is_active as "is_active!: bool",
                 created_at as "created_at!: chrono::DateTime<chrono::Utc>",
-                updated_at as "updated_at!: chrono::DateTime<chrono::Utc>"
+                updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+                last_login_at
             "#,
         new_user.email.trim(),
         password_hash,
@@ -198,10 +312,7 @@ async fn create_user(
     )
     .fetch_one(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al crear usuario: {}", e);
-        ApiError::InternalServerError("Error al guardar usuario".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al guardar usuario", e))?;
 
     tracing::info!("Usuario creado exitosamente ID: {}", user.id);
 
@@ -210,24 +321,29 @@ async fn create_user(
         .json(UserResponse::from(user)))
 }
 
-/// Actualiza un usuario existente (actualización parcial)
+/// Actualiza un usuario existente (actualización parcial, solo admin)
 #[actix_web::put("/{id}")]
 async fn update_user(
-    id: web::Path<i32>,
+    claims: Claims,
+    id: PathId,
     updated_user: web::Json<UpdateUser>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
     tracing::info!("Actualizando usuario ID: {}", id);
 
+    // Verificar si el usuario existe
+    ensure_exists(pool.get_ref(), "users", *id, "Usuario no encontrado").await?;
+
     let updated_user = updated_user.into_inner();
     updated_user.validate()?;
 
     // Hashear contraseña solo si se proporcionó
     let password_hash = match updated_user.password {
-        Some(password) => Some(crate::auth::hash_password(&password).map_err(|e| {
-            tracing::error!("Error al hashear contraseña: {}", e);
-            ApiError::InternalServerError("Error al procesar contraseña".into())
-        })?),
+        Some(password) => Some(
+            crate::auth::hash_password(&password)
+                .map_err(|e| ApiError::internal("Error al procesar contraseña", e))?,
+        ),
         None => None,
     };
 
@@ -252,7 +368,8 @@ async fn update_user(
             license_number,
             is_active as "is_active!: bool",
             created_at as "created_at!: chrono::DateTime<Utc>",
-            updated_at as "updated_at!: chrono::DateTime<Utc>"
+            updated_at as "updated_at!: chrono::DateTime<Utc>",
+            last_login_at
         "#,
         updated_user.email,
         password_hash,
@@ -260,18 +377,15 @@ async fn update_user(
         updated_user.role as Option<UserRole>,
         updated_user.license_number,
         updated_user.is_active,
-        id.clone()
+        *id
     )
     .fetch_optional(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error de base de datos: {}", e);
-        match e {
-            sqlx::Error::Database(err) if err.constraint() == Some("users_email_key") => {
-                ApiError::Conflict("El email ya está en uso".into())
-            }
-            _ => ApiError::InternalServerError("Error al actualizar usuario".into()),
+    .map_err(|e| match e {
+        sqlx::Error::Database(err) if err.constraint() == Some("users_email_key") => {
+            ApiError::Conflict("El email ya está en uso".into())
         }
+        e => ApiError::internal("Error al actualizar usuario", e),
     })?;
 
     match user {
@@ -286,12 +400,32 @@ async fn update_user(
     }
 }
 
-/// Elimina un usuario (borrado lógico)
+/// Filas que dependen del usuario y qué les pasaría al eliminarlo
+///
+/// El DELETE de usuarios es un borrado lógico: todas las dependencias se conservan.
+///
+/// # Ejemplo
+/// GET /users/4/delete-preview
+#[actix_web::get("/{id}/delete-preview")]
+async fn delete_user_preview(
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let preview = deletion::preview(pool.get_ref(), Resource::User, *id).await?;
+    Ok(HttpResponse::Ok().json(preview))
+}
+
+/// Elimina un usuario (borrado lógico, solo admin)
 #[actix_web::delete("/{id}")]
 async fn delete_user(
-    id: web::Path<i32>,
+    claims: Claims,
+    id: PathId,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    // Mismo mapa que la vista previa; hoy ninguna dependencia lo impide
+    deletion::ensure_deletable(pool.get_ref(), Resource::User, *id).await?;
+
     let result = sqlx::query!(
         r#"
         UPDATE users
@@ -301,13 +435,18 @@ async fn delete_user(
         WHERE id = $1 AND is_active = true
         RETURNING id, updated_at
         "#,
-        id.clone()
+        *id
     )
     .fetch_optional(pool.get_ref())
     .await?;
 
     match result {
         Some(user) => {
+            // Sus sesiones no deben revivir si se reactiva
+            sqlx::query!("DELETE FROM refresh_tokens WHERE user_id = $1", user.id)
+                .execute(pool.get_ref())
+                .await
+                .map_err(|e| ApiError::internal("Error al cerrar las sesiones del usuario", e))?;
             tracing::info!("Usuario {} desactivado el {}", user.id, user.updated_at);
             Ok(HttpResponse::NoContent().finish())
         }
@@ -318,25 +457,66 @@ async fn delete_user(
     }
 }
 
-// Estructuras para login
-#[derive(Debug, Deserialize, Validate)]
-struct LoginRequest {
-    #[validate(email)]
-    pub email: String,
-    #[validate(length(min = 8))]
-    pub password: String,
-}
+/// Reactiva un usuario desactivado (solo admin)
+///
+/// Respeta el límite de usuarios activos del plan. Responde 409 si el usuario
+/// ya está activo.
+///
+/// # Ejemplo
+/// POST /users/4/reactivate
+#[actix_web::post("/{id}/reactivate")]
+async fn reactivate_user(
+    claims: Claims,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    let is_active: bool = sqlx::query_scalar("SELECT is_active FROM users WHERE id = $1")
+        .bind(*id)
+        .fetch_optional(pool.get_ref())
+        .await?
+        .ok_or(ApiError::NotFound("Usuario no encontrado".into()))?;
+    if is_active {
+        return Err(ApiError::Conflict("El usuario ya está activo".into()));
+    }
+
+    limits::ensure_within_limit(pool.get_ref(), Limit::ActiveUsers).await?;
 
-#[derive(Debug, Serialize)]
-struct LoginResponse {
-    pub token: String,
-    pub user: UserResponse,
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        UPDATE users
+        SET
+            is_active = true,
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING
+            id,
+            email,
+            password_hash,
+            name,
+            role as "role!: UserRole",
+            license_number,
+            is_active as "is_active!: bool",
+            created_at as "created_at!: chrono::DateTime<chrono::Utc>",
+            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+            last_login_at
+        "#,
+        *id
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    tracing::info!("Usuario {} reactivado", user.id);
+
+    Ok(HttpResponse::Ok().json(UserResponse::from(user)))
 }
 
 #[actix_web::post("/login")]
 async fn login(
     pool: web::Data<PgPool>,
-    login_request: web::Json<LoginRequest>,
+    login_request: web::Json<LoginUser>,
+    events: web::Data<EventBus>,
 ) -> Result<impl actix_web::Responder, ApiError> {
     // Buscar usuario por email
     let user = sqlx::query_as!(
@@ -351,7 +531,8 @@ async fn login(
             license_number,
             is_active as "is_active!: bool",
             created_at as "created_at!: chrono::DateTime<chrono::Utc>",
-            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>"
+            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+            last_login_at
         FROM users
         WHERE email = $1 AND is_active = true
         "#,
@@ -359,32 +540,488 @@ async fn login(
     )
     .fetch_optional(pool.get_ref())
     .await
-    .map_err(|e| {
-        tracing::error!("Error al obtener usuario: {}", e);
-        ApiError::InternalServerError("Error al obtener el usuario".into())
-    })?;
+    .map_err(|e| ApiError::internal("Error al obtener el usuario", e))?;
 
     match user {
-        Some(user) => {
+        Some(mut user) => {
             let is_valid_password = verify_password(&login_request.password, &user.password_hash)?;
 
             if !is_valid_password {
-                return Err(ApiError::Unauthorized(format!("Contraseña invalida!")));
+                events.publish(DomainEvent::LoginFailed);
+                return Err(ApiError::Unauthorized("Contraseña invalida!".into()));
             }
 
-            let token = create_jwt(user.id, &user.role)?;
+            // No toca updated_at: iniciar sesión no modifica el usuario
+            user.last_login_at = sqlx::query_scalar!(
+                "UPDATE users SET last_login_at = NOW() WHERE id = $1 RETURNING last_login_at",
+                user.id
+            )
+            .fetch_one(pool.get_ref())
+            .await
+            .map_err(|e| ApiError::internal("Error al registrar el inicio de sesión", e))?;
+
+            let access = create_jwt(user.id, &user.role)?.into();
+
+            // Los tokens vencidos del usuario ya no sirven; se limpian aquí
+            sqlx::query!(
+                "DELETE FROM refresh_tokens WHERE user_id = $1 AND expires_at <= NOW()",
+                user.id
+            )
+            .execute(pool.get_ref())
+            .await
+            .map_err(|e| ApiError::internal("Error al limpiar las sesiones", e))?;
+            let refresh_token = issue_refresh_token(pool.get_ref(), user.id).await?;
 
             let response = LoginResponse {
-                token,
+                access,
+                refresh_token,
                 user: UserResponse::from(user),
             };
 
             Ok(HttpResponse::Ok().json(response))
         }
-        None => Err(ApiError::Unauthorized(format!(
-            "Correo o contraseña invalida"
-        ))),
+        None => {
+            events.publish(DomainEvent::LoginFailed);
+            Err(ApiError::Unauthorized(
+                "Correo o contraseña invalida".into(),
+            ))
+        }
+    }
+}
+
+/// Guarda un token de renovación nuevo para el usuario y lo devuelve en claro
+async fn issue_refresh_token(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+) -> Result<String, ApiError> {
+    let (prefix, token) = generate_user_token();
+    let token_hash = hash_password(&token)?;
+    let expires_at = Utc::now() + chrono::Duration::days(refresh_token_days());
+
+    sqlx::query!(
+        r#"
+        INSERT INTO refresh_tokens (user_id, prefix, token_hash, expires_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        user_id,
+        prefix,
+        token_hash,
+        expires_at
+    )
+    .execute(executor)
+    .await
+    .map_err(|e| ApiError::internal("Error al guardar el token de renovación", e))?;
+    Ok(token)
+}
+
+/// Canjea un token de renovación por un token de acceso nuevo.
+///
+/// Cada token de renovación sirve una sola vez: se elimina y la respuesta
+/// trae el que lo reemplaza. Uno inválido, vencido, ya canjeado o de un
+/// usuario inactivo responde 401 y hay que iniciar sesión de nuevo.
+///
+/// # Ejemplo de petición
+/// ```json
+/// { "refresh_token": "3f9a0c1d2e4b5a67_..." }
+/// ```
+#[actix_web::post("/refresh")]
+async fn refresh(
+    pool: web::Data<PgPool>,
+    request: web::Json<RefreshRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let invalid = || ApiError::Unauthorized("Token de renovación inválido o vencido".into());
+    let token = request.refresh_token.trim();
+    let prefix = user_token_prefix(token).ok_or_else(invalid)?;
+
+    let mut tx = pool.begin().await?;
+    // El bloqueo evita que dos peticiones canjeen el mismo token
+    let stored = sqlx::query!(
+        r#"
+        SELECT rt.id, rt.user_id, rt.token_hash, u.role as "role: UserRole"
+        FROM refresh_tokens rt
+        JOIN users u ON u.id = rt.user_id
+        WHERE rt.prefix = $1 AND rt.expires_at > NOW() AND u.is_active
+        FOR UPDATE OF rt
+        "#,
+        prefix
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al verificar el token de renovación", e))?
+    .ok_or_else(invalid)?;
+
+    if !verify_password(token, &stored.token_hash)? {
+        return Err(invalid());
+    }
+
+    sqlx::query!("DELETE FROM refresh_tokens WHERE id = $1", stored.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal("Error al renovar la sesión", e))?;
+    let refresh_token = issue_refresh_token(&mut *tx, stored.user_id).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(RefreshResponse {
+        access: create_jwt(stored.user_id, &stored.role)?.into(),
+        refresh_token,
+    }))
+}
+
+/// Cierra la sesión revocando su token de renovación. El token de acceso
+/// sigue valiendo hasta que vence, por eso dura poco. Responde 204 aunque el
+/// token ya no exista.
+///
+/// # Ejemplo de petición
+/// ```json
+/// { "refresh_token": "3f9a0c1d2e4b5a67_..." }
+/// ```
+#[actix_web::post("/logout")]
+async fn logout(
+    pool: web::Data<PgPool>,
+    request: web::Json<RefreshRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let token = request.refresh_token.trim();
+    let Some(prefix) = user_token_prefix(token) else {
+        return Ok(HttpResponse::NoContent().finish());
+    };
+
+    let stored = sqlx::query!(
+        "SELECT id, token_hash FROM refresh_tokens WHERE prefix = $1",
+        prefix
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al cerrar la sesión", e))?;
+
+    // Solo quien tiene el token completo puede revocarlo
+    if let Some(stored) = stored
+        && verify_password(token, &stored.token_hash)?
+    {
+        sqlx::query!("DELETE FROM refresh_tokens WHERE id = $1", stored.id)
+            .execute(pool.get_ref())
+            .await
+            .map_err(|e| ApiError::internal("Error al cerrar la sesión", e))?;
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Enlace del correo de restablecimiento (`PASSWORD_RESET_URL` + `?token=`)
+fn password_reset_link(token: &str) -> String {
+    let base = env::var("PASSWORD_RESET_URL")
+        .unwrap_or_else(|_| "http://localhost:3000/restablecer-contrasena".to_string());
+    format!("{base}?token={token}")
+}
+
+/// Envía por correo un enlace para restablecer la contraseña.
+///
+/// Responde 202 exista o no el correo, para no revelar qué cuentas hay; solo
+/// los usuarios activos reciben el enlace. El token vence a los
+/// `PASSWORD_RESET_MINUTES` minutos y sirve una sola vez.
+///
+/// # Ejemplo de petición
+/// ```json
+/// { "email": "usuario@ejemplo.com" }
+/// ```
+#[actix_web::post("/password-reset/request")]
+async fn request_password_reset(
+    pool: web::Data<PgPool>,
+    request: web::Json<PasswordResetRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let request = request.into_inner();
+    request.validate()?;
+
+    // El token se genera siempre para que el tiempo de respuesta no delate
+    // si el correo existe
+    let (prefix, token) = generate_user_token();
+    let token_hash = hash_password(&token)?;
+
+    let user = sqlx::query!(
+        "SELECT id, name, email FROM users WHERE email = $1 AND is_active",
+        request.email.trim()
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener el usuario", e))?;
+
+    if let Some(user) = user {
+        let mut tx = pool.begin().await?;
+        // Los enlaces anteriores dejan de servir al pedir uno nuevo
+        sqlx::query!(
+            "DELETE FROM password_reset_tokens WHERE user_id = $1",
+            user.id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal("Error al generar el enlace", e))?;
+        sqlx::query!(
+            r#"
+            INSERT INTO password_reset_tokens (user_id, prefix, token_hash, expires_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            user.id,
+            prefix,
+            token_hash,
+            Utc::now() + chrono::Duration::minutes(password_reset_minutes())
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal("Error al generar el enlace", e))?;
+        tx.commit().await?;
+
+        let body = format!(
+            "Hola {},\n\nPara elegir una contraseña nueva abre este enlace:\n{}\n\n\
+             Vence en {} minutos. Si no lo pediste, ignora este correo.\n",
+            user.name,
+            password_reset_link(&token),
+            password_reset_minutes()
+        );
+        // En segundo plano: la demora del envío tampoco delata la cuenta
+        let pool = pool.get_ref().clone();
+        tokio::spawn(async move {
+            let email = notifications::Email {
+                template: "password_reset",
+                to: &user.email,
+                subject: "Restablece tu contraseña",
+                body,
+                client_id: None,
+                appointment_id: None,
+                secret: true,
+            };
+            if let Err(e) = notifications::deliver(&pool, email).await {
+                tracing::error!(
+                    "Error al enviar el restablecimiento al usuario {}: {}",
+                    user.id,
+                    e
+                );
+            }
+        });
+        tracing::info!(
+            "Restablecimiento de contraseña solicitado por el usuario {}",
+            user.id
+        );
+    }
+
+    Ok(HttpResponse::Accepted()
+        .json("Si el correo está registrado, recibirás un enlace para restablecer la contraseña"))
+}
+
+/// Cambia la contraseña con el token del correo de restablecimiento.
+///
+/// Un token inválido, vencido o ya usado responde 400. Al cambiarla se
+/// cierran todas las sesiones del usuario.
+///
+/// # Ejemplo de petición
+/// ```json
+/// { "token": "3f9a0c1d2e4b5a67_...", "new_password": "nuevaContraseña123" }
+/// ```
+#[actix_web::post("/password-reset/confirm")]
+async fn confirm_password_reset(
+    pool: web::Data<PgPool>,
+    request: web::Json<PasswordResetConfirm>,
+) -> Result<HttpResponse, ApiError> {
+    let request = request.into_inner();
+    request.validate()?;
+    let invalid = || {
+        ApiError::ValidationError(
+            "El enlace para restablecer la contraseña no es válido o ya venció".into(),
+        )
+    };
+    let token = request.token.trim();
+    let prefix = user_token_prefix(token).ok_or_else(invalid)?;
+
+    let mut tx = pool.begin().await?;
+    // El bloqueo evita que dos peticiones usen el mismo token
+    let stored = sqlx::query!(
+        r#"
+        SELECT id, user_id, token_hash
+        FROM password_reset_tokens
+        WHERE prefix = $1 AND used_at IS NULL AND expires_at > NOW()
+        FOR UPDATE
+        "#,
+        prefix
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al verificar el enlace", e))?
+    .ok_or_else(invalid)?;
+
+    if !verify_password(token, &stored.token_hash)? {
+        return Err(invalid());
+    }
+
+    let password_hash = hash_password(&request.new_password)?;
+    let updated = sqlx::query!(
+        r#"
+        UPDATE users
+        SET password_hash = $2, updated_at = NOW()
+        WHERE id = $1 AND is_active
+        "#,
+        stored.user_id,
+        password_hash
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al actualizar la contraseña", e))?
+    .rows_affected();
+    if updated == 0 {
+        return Err(invalid());
     }
+
+    sqlx::query!(
+        "UPDATE password_reset_tokens SET used_at = NOW() WHERE id = $1",
+        stored.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al actualizar la contraseña", e))?;
+    sqlx::query!(
+        "DELETE FROM refresh_tokens WHERE user_id = $1",
+        stored.user_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al cerrar las sesiones del usuario", e))?;
+    tx.commit().await?;
+
+    tracing::info!("Contraseña restablecida para el usuario {}", stored.user_id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Cambia la contraseña del usuario del token, verificando la actual.
+///
+/// Una contraseña actual incorrecta responde 401. Los tokens de suplantación
+/// y las llaves de API no pueden cambiarla (403). Al cambiarla se cierran las
+/// sesiones del usuario (sus tokens de renovación); el token de acceso con
+/// que se hizo la petición sigue valiendo hasta que vence.
+///
+/// # Ejemplo de petición
+/// ```json
+/// { "current_password": "contraseñaActual", "new_password": "nuevaContraseña123" }
+/// ```
+#[actix_web::post("/me/password")]
+async fn change_own_password(
+    claims: Claims,
+    request: web::Json<ChangePassword>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    if claims.impersonator.is_some() || claims.api_key_id.is_some() {
+        return Err(ApiError::Forbidden(
+            "Solo el propio usuario puede cambiar su contraseña".into(),
+        ));
+    }
+    let request = request.into_inner();
+    request.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let current_hash = sqlx::query_scalar!(
+        "SELECT password_hash FROM users WHERE id = $1 AND is_active FOR UPDATE",
+        claims.sub
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener el usuario", e))?
+    .ok_or(ApiError::Unauthorized(
+        "El usuario no existe o está inactivo".into(),
+    ))?;
+
+    if !verify_password(&request.current_password, &current_hash)? {
+        tracing::warn!("Contraseña actual incorrecta del usuario {}", claims.sub);
+        return Err(ApiError::Unauthorized(
+            "La contraseña actual es incorrecta".into(),
+        ));
+    }
+
+    let password_hash = hash_password(&request.new_password)?;
+    sqlx::query!(
+        "UPDATE users SET password_hash = $2, updated_at = NOW() WHERE id = $1",
+        claims.sub,
+        password_hash
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal("Error al actualizar la contraseña", e))?;
+    sqlx::query!("DELETE FROM refresh_tokens WHERE user_id = $1", claims.sub)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal("Error al cerrar las sesiones del usuario", e))?;
+    tx.commit().await?;
+
+    tracing::info!("Usuario {} cambió su contraseña", claims.sub);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Emite un token para ver la API como otro usuario (solo administradores).
+///
+/// El token dura 15 minutos, no se renueva y lleva el claim `impersonator` con
+/// el administrador; cada petición hecha con él queda en el log atribuida a
+/// "administrador X como usuario Y". No se puede suplantar a otro administrador,
+/// ni hacerlo con una llave de API.
+///
+/// # Ejemplo
+/// POST /users/5/impersonate
+#[actix_web::post("/{id}/impersonate")]
+async fn impersonate_user(
+    claims: Claims,
+    id: PathId,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    claims.require_role(&[UserRole::Admin])?;
+    if claims.impersonator.is_some() {
+        return Err(ApiError::Forbidden(
+            "Un token de suplantación no puede emitir otro".into(),
+        ));
+    }
+    // El token emitido no tendría el alcance limitado de la llave
+    if claims.api_key_id.is_some() {
+        return Err(ApiError::Forbidden(
+            "Una llave de API no puede suplantar usuarios".into(),
+        ));
+    }
+
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        SELECT
+            id,
+            email,
+            password_hash,
+            name,
+            role as "role: UserRole",
+            license_number,
+            is_active as "is_active!: bool",
+            created_at as "created_at!: chrono::DateTime<chrono::Utc>",
+            updated_at as "updated_at!: chrono::DateTime<chrono::Utc>",
+            last_login_at
+        FROM users
+        WHERE id = $1 AND is_active = true
+        "#,
+        *id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or(ApiError::NotFound("Usuario no encontrado".into()))?;
+
+    if user.role == UserRole::Admin {
+        return Err(ApiError::Forbidden(
+            "No se puede suplantar a otro administrador".into(),
+        ));
+    }
+
+    let issued = create_impersonation_jwt(user.id, &user.role, claims.sub)?;
+    tracing::warn!(
+        "Administrador {} suplanta al usuario {} por {} minutos",
+        claims.sub,
+        user.id,
+        IMPERSONATION_MINUTES
+    );
+
+    Ok(HttpResponse::Ok().json(ImpersonationResponse {
+        expires_at: issued.claims.expires_at(),
+        token: issued.token,
+        impersonator_id: claims.sub,
+        user: UserResponse::from(user),
+    }))
 }
 
 // Exporta todas las funciones como un grupo
@@ -392,10 +1029,158 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/users")
             .service(list_users)
+            .service(get_current_user) // Antes de "/{id}"
             .service(get_user)
             .service(create_user)
             .service(update_user)
+            .service(delete_user_preview)
             .service(delete_user)
-            .service(login), // Agrega más servicios aquí...
+            .service(reactivate_user)
+            .service(impersonate_user)
+            .service(login)
+            .service(refresh)
+            .service(logout)
+            .service(request_password_reset)
+            .service(confirm_password_reset)
+            .service(change_own_password), // Agrega más servicios aquí...
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{as_user, claims};
+    use actix_web::{App, test};
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn only_a_user_token_may_impersonate(pool: PgPool) {
+        let app =
+            test::init_service(App::new().app_data(web::Data::new(pool)).configure(config)).await;
+        let impersonate = |claims| {
+            as_user(
+                test::TestRequest::post()
+                    .uri("/users/1/impersonate")
+                    .to_request(),
+                claims,
+            )
+        };
+
+        let service_key = Claims {
+            api_key_id: Some(1),
+            ..claims(4, UserRole::Admin)
+        };
+        let response = test::call_service(&app, impersonate(service_key)).await;
+        assert_eq!(response.status(), 403);
+
+        let response = test::call_service(&app, impersonate(claims(4, UserRole::Admin))).await;
+        assert_eq!(response.status(), 200);
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn only_admins_manage_users(pool: PgPool) {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .configure(config),
+        )
+        .await;
+        let requests = || {
+            [
+                test::TestRequest::post()
+                    .uri("/users")
+                    .set_json(serde_json::json!({
+                        "email": "nueva@vetclinic.com",
+                        "password": "contraseñaSegura123",
+                        "name": "Nueva Veterinaria",
+                        "role": "Veterinarian",
+                        "license_number": "VET-99999"
+                    })),
+                test::TestRequest::put()
+                    .uri("/users/2")
+                    .set_json(serde_json::json!({ "name": "Dra. Sarah Jones" })),
+                test::TestRequest::delete().uri("/users/2"),
+                test::TestRequest::post().uri("/users/2/reactivate"),
+            ]
+        };
+
+        // Ni un asistente ni un token sin claims cambian nada
+        for request in requests() {
+            let request = as_user(request.to_request(), claims(3, UserRole::Assistant));
+            let uri = request.uri().to_string();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), 403, "{uri}");
+        }
+        for request in requests() {
+            let response = test::call_service(&app, request.to_request()).await;
+            assert_eq!(response.status(), 401);
+        }
+        let users = sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE is_active")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(users, Some(4));
+
+        // Un administrador sí: crea, edita, desactiva y reactiva
+        for (request, status) in requests().into_iter().zip([201, 200, 204, 200]) {
+            let request = as_user(request.to_request(), claims(4, UserRole::Admin));
+            let uri = request.uri().to_string();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), status, "{uri}");
+        }
+    }
+
+    /// `issued_at` y `expires_at` deben ser exactamente los claims del token
+    fn assert_dates_match_token(body: &serde_json::Value) {
+        let token_claims = crate::auth::decode_jwt(body["token"].as_str().unwrap()).unwrap();
+        let date = |field: &str| {
+            serde_json::from_value::<chrono::DateTime<Utc>>(body[field].clone()).unwrap()
+        };
+        assert_eq!(date("issued_at"), token_claims.issued_at());
+        assert_eq!(date("expires_at"), token_claims.expires_at());
+        assert_eq!(
+            body["expires_in"],
+            (token_claims.exp - token_claims.iat) as i64
+        );
+    }
+
+    #[sqlx::test(
+        migrations = false,
+        fixtures(path = "../../init.sql", scripts("0", "1"))
+    )]
+    async fn login_and_refresh_dates_are_the_token_claims(pool: PgPool) {
+        let email = sqlx::query_scalar!(
+            "UPDATE users SET password_hash = $1 WHERE id = 4 RETURNING email",
+            crate::auth::hash_password("contraseña-de-prueba").unwrap()
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(EventBus::default()))
+                .configure(config),
+        )
+        .await;
+
+        let request = test::TestRequest::post()
+            .uri("/users/login")
+            .set_json(serde_json::json!({ "email": email, "password": "contraseña-de-prueba" }))
+            .to_request();
+        let logged_in: serde_json::Value = test::call_and_read_body_json(&app, request).await;
+        assert_dates_match_token(&logged_in);
+
+        let request = test::TestRequest::post()
+            .uri("/users/refresh")
+            .set_json(serde_json::json!({ "refresh_token": logged_in["refresh_token"] }))
+            .to_request();
+        let refreshed: serde_json::Value = test::call_and_read_body_json(&app, request).await;
+        assert_dates_match_token(&refreshed);
+    }
+}
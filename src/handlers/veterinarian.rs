@@ -0,0 +1,76 @@
+use crate::errors::ApiError;
+use crate::extractors::PathId;
+use crate::handlers::appointment::veterinarian_free_slots;
+use crate::models::appointment::{
+    AvailabilityQuery, DEFAULT_SLOT_MINUTES, VeterinarianAvailability,
+};
+use actix_web::{HttpResponse, web};
+use chrono::Duration;
+use sqlx::PgPool;
+use validator::Validate;
+
+/// Huecos libres de un veterinario en un día
+///
+/// Intersecta el horario de atención del día con las citas del veterinario y
+/// devuelve los rangos libres ajustados a la rejilla de `slot_minutes`, que
+/// empieza a la hora de apertura. En el día de hoy solo cuenta lo que falta.
+///
+/// # Parámetros
+/// - `date`: Día local de la clínica (AAAA-MM-DD)
+/// - `slot_minutes`: Tamaño de la rejilla, de 5 a 240 (default: 30)
+/// - `duration`: Omitir huecos más cortos que estos minutos (opcional)
+///
+/// Responde 404 si el usuario no existe, no es veterinario o está inactivo.
+///
+/// # Ejemplo
+/// GET /veterinarians/1/availability?date=2024-06-10&slot_minutes=30&duration=60
+#[actix_web::get("/{id}/availability")]
+async fn get_availability(
+    id: PathId,
+    query: web::Query<AvailabilityQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+    tracing::info!("Disponibilidad del veterinario {}: {:?}", id, &query);
+
+    let is_veterinarian = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM users
+            WHERE id = $1 AND role = 'veterinarian' AND is_active = TRUE
+        ) as "exists!"
+        "#,
+        *id
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+    if !is_veterinarian {
+        return Err(ApiError::NotFound(format!(
+            "Veterinario con ID {} no encontrado",
+            id
+        )));
+    }
+
+    let slot_minutes = query.slot_minutes.unwrap_or(DEFAULT_SLOT_MINUTES);
+    let min_length = Duration::minutes(query.duration.unwrap_or(0).max(slot_minutes));
+    let slots = veterinarian_free_slots(
+        pool.get_ref(),
+        *id,
+        query.date,
+        Duration::minutes(slot_minutes),
+        min_length,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(VeterinarianAvailability {
+        veterinarian_id: *id,
+        date: query.date,
+        slot_minutes,
+        slots,
+    }))
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/veterinarians").service(get_availability));
+}
@@ -0,0 +1,152 @@
+use crate::errors::ApiError;
+use crate::models::admin::IntegrityCheckResult;
+use sqlx::PgPool;
+
+/// Máximo de ids de ejemplo que se devuelven por revisión
+const SAMPLE_SIZE: i64 = 10;
+
+/// Revisión de consistencia de solo lectura.
+///
+/// `query` debe devolver una columna `id` con las filas que incumplen la regla.
+pub struct IntegrityCheck {
+    pub name: &'static str,
+    pub description: &'static str,
+    query: &'static str,
+}
+
+/// Revisiones disponibles; para agregar una basta con sumar una entrada
+pub const CHECKS: &[IntegrityCheck] = &[
+    IntegrityCheck {
+        name: "overlapping_vet_appointments",
+        description: "Citas que se traslapan para el mismo veterinario (sin canceladas ni no_show)",
+        query: r#"
+            SELECT a.id
+            FROM appointments a
+            WHERE a.status NOT IN ('canceled', 'no_show')
+              AND EXISTS (
+                  SELECT 1
+                  FROM appointments b
+                  WHERE b.veterinarian_id = a.veterinarian_id
+                    AND b.id != a.id
+                    AND b.status NOT IN ('canceled', 'no_show')
+                    AND (a.start_time, a.end_time) OVERLAPS (b.start_time, b.end_time)
+              )
+        "#,
+    },
+    IntegrityCheck {
+        name: "overlapping_patient_appointments",
+        description: "Citas no canceladas que se traslapan para el mismo paciente",
+        query: r#"
+            SELECT a.id
+            FROM appointments a
+            WHERE a.patient_id IS NOT NULL
+              AND a.status IS DISTINCT FROM 'canceled'
+              AND EXISTS (
+                  SELECT 1
+                  FROM appointments b
+                  WHERE b.patient_id = a.patient_id
+                    AND b.id != a.id
+                    AND b.status IS DISTINCT FROM 'canceled'
+                    AND (a.start_time, a.end_time) OVERLAPS (b.start_time, b.end_time)
+              )
+        "#,
+    },
+    IntegrityCheck {
+        name: "procedures_before_birth",
+        description: "Procedimientos de paciente con fecha anterior al nacimiento",
+        query: r#"
+            SELECT pp.id
+            FROM patient_procedures pp
+            JOIN patients p ON p.id = pp.patient_id
+            WHERE p.birth_date IS NOT NULL
+              AND pp.date < p.birth_date
+        "#,
+    },
+    IntegrityCheck {
+        name: "orphan_medical_records",
+        description: "Registros médicos cuyo paciente ya no existe",
+        query: r#"
+            SELECT mr.id
+            FROM medical_records mr
+            WHERE NOT EXISTS (
+                SELECT 1 FROM patients p WHERE p.id = mr.patient_id
+            )
+        "#,
+    },
+    IntegrityCheck {
+        name: "clients_without_phone",
+        description: "Clientes sin teléfono de contacto",
+        query: r#"
+            SELECT c.id
+            FROM clients c
+            WHERE c.phone IS NULL OR TRIM(c.phone) = ''
+        "#,
+    },
+    IntegrityCheck {
+        name: "surgeries_without_consent",
+        description: "Cirugías de paciente sin consentimiento registrado 24 h después de darse de alta",
+        query: r#"
+            SELECT pp.id
+            FROM patient_procedures pp
+            JOIN procedures p ON p.id = pp.procedure_id
+            WHERE p.type = 'surgery'
+              AND pp.created_at < NOW() - INTERVAL '24 hours'
+              AND NOT EXISTS (
+                  SELECT 1 FROM procedure_consents pc WHERE pc.patient_procedure_id = pp.id
+              )
+        "#,
+    },
+];
+
+/// Resuelve la lista separada por comas del parámetro `checks` (vacía = todas)
+pub fn select_checks(names: Option<&str>) -> Result<Vec<&'static IntegrityCheck>, ApiError> {
+    let Some(names) = names.filter(|names| !names.trim().is_empty()) else {
+        return Ok(CHECKS.iter().collect());
+    };
+
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            CHECKS
+                .iter()
+                .find(|check| check.name == name)
+                .ok_or_else(|| {
+                    let available: Vec<&str> = CHECKS.iter().map(|check| check.name).collect();
+                    ApiError::ValidationError(format!(
+                        "Revisión desconocida: {}. Disponibles: {}",
+                        name,
+                        available.join(", ")
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Ejecuta una revisión y devuelve el total de filas afectadas con una muestra de ids
+pub async fn run_check(
+    pool: &PgPool,
+    check: &IntegrityCheck,
+) -> Result<IntegrityCheckResult, ApiError> {
+    let rows: Vec<(i64, i32)> = sqlx::query_as(&format!(
+        "SELECT COUNT(*) OVER ()::BIGINT, id FROM ({}) offending ORDER BY id LIMIT $1",
+        check.query
+    ))
+    .bind(SAMPLE_SIZE)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        ApiError::internal(
+            "Error al ejecutar las revisiones de integridad",
+            format!("revisión {}: {e}", check.name),
+        )
+    })?;
+
+    Ok(IntegrityCheckResult {
+        check: check.name.to_string(),
+        description: check.description.to_string(),
+        count: rows.first().map_or(0, |(count, _)| *count),
+        sample_ids: rows.into_iter().map(|(_, id)| id).collect(),
+    })
+}
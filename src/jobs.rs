@@ -0,0 +1,68 @@
+use crate::models::job::{JobState, JobStatus};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// Registro en memoria de los trabajos en segundo plano.
+///
+/// Se comparte como `web::Data<JobRegistry>`; los trabajos se pierden al
+/// reiniciar el servidor.
+#[derive(Debug, Default)]
+pub struct JobRegistry {
+    next_id: AtomicI32,
+    jobs: Mutex<HashMap<i32, JobStatus>>,
+}
+
+impl JobRegistry {
+    /// Registra un nuevo trabajo en ejecución
+    pub fn start(&self, kind: &str, total: i64) -> JobStatus {
+        let job = JobStatus {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed) + 1,
+            kind: kind.to_string(),
+            state: JobState::Running,
+            processed: 0,
+            total,
+            message: None,
+            started_at: Utc::now(),
+            finished_at: None,
+        };
+        self.lock().insert(job.id, job.clone());
+        job
+    }
+
+    /// Actualiza el número de elementos procesados
+    pub fn progress(&self, id: i32, processed: i64) {
+        if let Some(job) = self.lock().get_mut(&id) {
+            job.processed = processed;
+        }
+    }
+
+    /// Marca el trabajo como terminado, con su resumen o la causa del fallo
+    pub fn finish(&self, id: i32, result: Result<String, String>) {
+        if let Some(job) = self.lock().get_mut(&id) {
+            let (state, message) = match result {
+                Ok(summary) => (JobState::Completed, summary),
+                Err(cause) => (JobState::Failed, cause),
+            };
+            job.state = state;
+            job.message = Some(message);
+            job.finished_at = Some(Utc::now());
+        }
+    }
+
+    pub fn get(&self, id: i32) -> Option<JobStatus> {
+        self.lock().get(&id).cloned()
+    }
+
+    /// Lista los trabajos, del más reciente al más antiguo
+    pub fn list(&self) -> Vec<JobStatus> {
+        let mut jobs: Vec<JobStatus> = self.lock().values().cloned().collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.id));
+        jobs
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<i32, JobStatus>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,117 @@
+use crate::clinic;
+use crate::errors::ApiError;
+use crate::models::admin::LimitUsage;
+use chrono::{Datelike, Months};
+use sqlx::PgPool;
+use std::env;
+
+/// Límites del plan contratado.
+///
+/// Cada límite se lee de una variable de entorno; si no está declarada el
+/// recurso es ilimitado.
+#[derive(Debug, Clone, Copy)]
+pub enum Limit {
+    ActivePatients,
+    ActiveUsers,
+    MonthlyAppointments,
+}
+
+impl Limit {
+    pub const ALL: [Limit; 3] = [
+        Limit::ActivePatients,
+        Limit::ActiveUsers,
+        Limit::MonthlyAppointments,
+    ];
+
+    /// Nombre del límite en las respuestas de la API
+    pub fn name(self) -> &'static str {
+        match self {
+            Limit::ActivePatients => "active_patients",
+            Limit::ActiveUsers => "active_users",
+            Limit::MonthlyAppointments => "monthly_appointments",
+        }
+    }
+
+    fn env_var(self) -> &'static str {
+        match self {
+            Limit::ActivePatients => "MAX_ACTIVE_PATIENTS",
+            Limit::ActiveUsers => "MAX_ACTIVE_USERS",
+            Limit::MonthlyAppointments => "MAX_MONTHLY_APPOINTMENTS",
+        }
+    }
+
+    /// Máximo configurado, `None` si es ilimitado
+    pub fn max(self) -> Option<i64> {
+        env::var(self.env_var())
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+    }
+
+    /// Consumo actual del recurso
+    pub async fn current(self, pool: &PgPool) -> Result<i64, ApiError> {
+        let count = match self {
+            Limit::ActivePatients => {
+                sqlx::query_scalar!(r#"SELECT COUNT(*) as "count!" FROM patients"#)
+                    .fetch_one(pool)
+                    .await?
+            }
+            Limit::ActiveUsers => {
+                sqlx::query_scalar!(
+                    r#"SELECT COUNT(*) as "count!" FROM users WHERE is_active = TRUE"#
+                )
+                .fetch_one(pool)
+                .await?
+            }
+            Limit::MonthlyAppointments => {
+                // Citas no canceladas del mes local en curso
+                let first_day = clinic::today().with_day(1).expect("día válido");
+                let (month_start, _) = clinic::day_bounds(first_day);
+                let (month_end, _) = clinic::day_bounds(first_day + Months::new(1));
+                sqlx::query_scalar!(
+                    r#"
+                    SELECT COUNT(*) as "count!"
+                    FROM appointments
+                    WHERE start_time >= $1
+                      AND start_time < $2
+                      AND status IS DISTINCT FROM 'canceled'
+                    "#,
+                    month_start,
+                    month_end
+                )
+                .fetch_one(pool)
+                .await?
+            }
+        };
+        Ok(count)
+    }
+
+    /// Consumo actual junto con el máximo configurado
+    pub async fn usage(self, pool: &PgPool) -> Result<LimitUsage, ApiError> {
+        Ok(LimitUsage {
+            limit: self.name().to_string(),
+            current: self.current(pool).await?,
+            max: self.max(),
+        })
+    }
+}
+
+/// Responde 402 si crear un recurso más excede el límite del plan.
+///
+/// Los límites no configurados no hacen ninguna consulta.
+pub async fn ensure_within_limit(pool: &PgPool, limit: Limit) -> Result<(), ApiError> {
+    let Some(max) = limit.max() else {
+        return Ok(());
+    };
+
+    let usage = limit.usage(pool).await?;
+    if usage.current >= max {
+        tracing::warn!(
+            "Límite {} alcanzado ({}/{})",
+            limit.name(),
+            usage.current,
+            max
+        );
+        return Err(ApiError::LimitExceeded(usage));
+    }
+    Ok(())
+}
@@ -1,10 +1,39 @@
+mod archive;
 mod auth;
+mod claims;
+mod clinic;
+mod data_quality;
 mod db;
+mod debug_trace;
+mod deletion;
+mod demo;
+mod digest;
+mod dossier;
+mod duplicate_guard;
 mod errors;
+mod events;
+mod extractors;
 mod handlers;
+mod integrity;
+mod jobs;
+mod limits;
+mod maintenance;
+mod metrics;
 mod middleware;
 mod models;
+mod notifications;
+mod pdf;
+mod rate_limit;
+mod retention;
 mod routes;
+mod statement;
+mod surveys;
+#[cfg(test)]
+mod testing;
+mod typescript;
+mod vet_report;
+mod vitals;
+mod weights;
 
 use actix_cors::Cors;
 use actix_web::{App, HttpServer, http, web};
@@ -14,32 +43,82 @@ use tracing::info;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    tracing_subscriber::fmt().init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = typescript::run_from_args(&args) {
+        return result;
+    }
+
+    debug_trace::init_tracing();
     dotenv::dotenv().ok();
 
     info!("Iniciando el servidor");
-    let allowed_origin =
+    let _allowed_origin =
         std::env::var("ALLOWED_ORIGIN").expect("ALLOWED_ORIGIN debe estar declarado");
     let port = std::env::var("PORT").unwrap_or(4000.to_string());
     let db_pool = connect_to_db()
         .await
         .expect("Fallo la conexión a la base de datos");
+
+    // Verificar el esquema antes de atender peticiones
+    if db::schema_check_skipped() {
+        tracing::warn!("Verificación del esquema omitida (SKIP_SCHEMA_CHECK)");
+    } else {
+        let problems = db::check_schema(&db_pool)
+            .await
+            .expect("Fallo la verificación del esquema");
+        if !problems.is_empty() {
+            eprintln!(
+                "El esquema de la base de datos no está completo:\n  - {}\n\
+                 Aplica init.sql/0.sql o define SKIP_SCHEMA_CHECK=1 para omitir esta verificación.",
+                problems.join("\n  - ")
+            );
+            std::process::exit(1);
+        }
+    }
+    let jobs = web::Data::new(jobs::JobRegistry::default());
+    digest::spawn_scheduler(db_pool.clone(), jobs.clone());
+    retention::spawn_scheduler(db_pool.clone(), jobs.clone());
+    archive::spawn_scheduler(db_pool.clone(), jobs.clone());
+    let events = web::Data::new(events::EventBus::default());
+    events::spawn_logger(&events);
+    surveys::spawn_sender(db_pool.clone(), &events);
+    let metrics = web::Data::new(metrics::Metrics::default());
+    metrics::spawn_recorder(metrics.clone(), &events);
+    let rate_limiter = web::Data::new(rate_limit::RateLimiter::from_env());
+    let maintenance = web::Data::new(maintenance::MaintenanceMode::from_env());
+    let duplicate_guard = web::Data::new(duplicate_guard::DuplicateGuard::from_env());
+    let clinic_profile = web::Data::new(clinic::ClinicProfileCache::default());
     HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_origin()
-            .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
+            .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
             .allowed_headers(vec![
                 http::header::CONTENT_TYPE,
                 http::header::AUTHORIZATION,
                 http::header::ACCEPT,
+                http::header::IF_MODIFIED_SINCE,
             ])
             .max_age(3600);
 
-        let auth = HttpAuthentication::bearer(middleware::api_key_validator);
+        let auth = HttpAuthentication::with_fn(middleware::api_key_validator);
 
         App::new()
             .app_data(web::Data::new(db_pool.clone()))
+            .app_data(jobs.clone())
+            .app_data(events.clone())
+            .app_data(metrics.clone())
+            .app_data(rate_limiter.clone())
+            .app_data(maintenance.clone())
+            .app_data(duplicate_guard.clone())
+            .app_data(clinic_profile.clone())
+            .app_data(errors::query_config())
             .wrap(actix_web::middleware::Logger::default())
+            .wrap(actix_web::middleware::from_fn(debug_trace::debug_trace)) // Después de auth
+            .wrap(actix_web::middleware::from_fn(
+                duplicate_guard::reject_duplicates,
+            )) // Después de auth
+            .wrap(actix_web::middleware::from_fn(rate_limit::rate_limit)) // Después de auth
+            .wrap(actix_web::middleware::from_fn(maintenance::read_only))
             .wrap(auth)
             .wrap(cors)
             .configure(routes::config)
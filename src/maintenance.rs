@@ -0,0 +1,79 @@
+use crate::errors::ApiError;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{Error, ResponseError, web};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Escrituras que se permiten aun en modo de solo lectura: el propio cambio
+/// de modo, y las sesiones (login, renovación y cierre), que solo tocan
+/// `refresh_tokens` y `users.last_login_at`, no datos clínicos
+const EXEMPT_PATHS: &[&str] = &[
+    "/health",
+    "/api/admin/maintenance",
+    "/api/users/login",
+    "/api/users/refresh",
+    "/api/users/logout",
+];
+
+/// Modo de mantenimiento de solo lectura, para migraciones de la base de datos.
+///
+/// Arranca con `MAINTENANCE_READ_ONLY` y se cambia en caliente con
+/// `PUT /admin/maintenance`; se comparte como `web::Data<MaintenanceMode>`.
+#[derive(Debug, Default)]
+pub struct MaintenanceMode {
+    read_only: AtomicBool,
+}
+
+impl MaintenanceMode {
+    pub fn from_env() -> Self {
+        let read_only = std::env::var("MAINTENANCE_READ_ONLY")
+            .is_ok_and(|value| matches!(value.trim(), "1" | "true"));
+        if read_only {
+            tracing::warn!("Modo de mantenimiento: la API arranca en solo lectura");
+        }
+        Self {
+            read_only: AtomicBool::new(read_only),
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    pub fn set_read_only(&self, read_only: bool) {
+        self.read_only.store(read_only, Ordering::Relaxed);
+    }
+}
+
+/// Middleware que rechaza con 503 las escrituras (POST, PUT, PATCH, DELETE)
+/// mientras el modo de solo lectura está activo
+pub async fn read_only(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let is_write = matches!(
+        *req.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+    let blocked = is_write
+        && !EXEMPT_PATHS.contains(&req.path())
+        && req
+            .app_data::<web::Data<MaintenanceMode>>()
+            .is_some_and(|mode| mode.is_read_only());
+
+    if blocked {
+        tracing::info!(
+            "Escritura rechazada por mantenimiento: {} {}",
+            req.method(),
+            req.path()
+        );
+        let response =
+            ApiError::Maintenance("La API está en mantenimiento y solo acepta lecturas".into())
+                .error_response();
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    Ok(next.call(req).await?.map_into_left_body())
+}
@@ -0,0 +1,173 @@
+use crate::events::{DomainEvent, EventBus};
+use crate::maintenance::MaintenanceMode;
+use actix_web::{HttpResponse, web};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::broadcast;
+
+/// Contadores de la aplicación.
+///
+/// Se comparte como `web::Data<Metrics>` y se expone en `GET /metrics` con el
+/// formato de texto de Prometheus; se reinician al reiniciar el servidor.
+/// Los contadores de negocio no llevan etiquetas, para no multiplicar series.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    rate_limited_total: AtomicU64,
+    appointments_created_total: AtomicU64,
+    appointments_canceled_total: AtomicU64,
+    patients_created_total: AtomicU64,
+    medical_records_created_total: AtomicU64,
+    login_failures_total: AtomicU64,
+}
+
+impl Metrics {
+    /// Registra una petición rechazada por el límite de peticiones
+    pub fn record_rate_limited(&self) {
+        self.rate_limited_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Cuenta un evento de dominio en su contador de negocio, si tiene
+    pub fn record_event(&self, event: &DomainEvent) {
+        let counter = match event {
+            DomainEvent::AppointmentCreated(_) => &self.appointments_created_total,
+            DomainEvent::AppointmentCanceled(_) => &self.appointments_canceled_total,
+            DomainEvent::PatientCreated { .. } => &self.patients_created_total,
+            DomainEvent::MedicalRecordCreated { .. } => &self.medical_records_created_total,
+            DomainEvent::LoginFailed => &self.login_failures_total,
+            _ => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Contadores en formato de texto de Prometheus
+    pub fn render(&self) -> String {
+        let counters = [
+            (
+                "rate_limited_total",
+                "Peticiones rechazadas con 429 por el límite de peticiones",
+                &self.rate_limited_total,
+            ),
+            (
+                "appointments_created_total",
+                "Citas creadas, incluidas las del alta rápida",
+                &self.appointments_created_total,
+            ),
+            (
+                "appointments_canceled_total",
+                "Citas que pasaron a Canceled",
+                &self.appointments_canceled_total,
+            ),
+            (
+                "patients_created_total",
+                "Pacientes creados, incluidos los del alta rápida",
+                &self.patients_created_total,
+            ),
+            (
+                "medical_records_created_total",
+                "Registros médicos creados",
+                &self.medical_records_created_total,
+            ),
+            (
+                "login_failures_total",
+                "Inicios de sesión rechazados por correo o contraseña",
+                &self.login_failures_total,
+            ),
+        ];
+
+        let mut output = String::new();
+        for (name, help, value) in counters {
+            let _ = writeln!(output, "# HELP {name} {help}");
+            let _ = writeln!(output, "# TYPE {name} counter");
+            let _ = writeln!(output, "{name} {}", value.load(Ordering::Relaxed));
+        }
+        output
+    }
+}
+
+/// Cuenta los eventos de dominio publicados en los contadores de negocio
+pub fn spawn_recorder(metrics: web::Data<Metrics>, events: &EventBus) {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => metrics.record_event(&event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Métricas atrasadas, {} eventos sin contar", skipped)
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Contadores para el sistema de monitoreo
+#[actix_web::get("/metrics")]
+async fn get_metrics(metrics: web::Data<Metrics>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics.render())
+}
+
+/// Estado del servidor para el sistema de monitoreo; `read_only` indica el
+/// modo de mantenimiento
+#[actix_web::get("/health")]
+async fn get_health(maintenance: web::Data<MaintenanceMode>) -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "ok",
+        "read_only": maintenance.is_read_only()
+    }))
+}
+
+// Exporta todas las funciones como un grupo
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_metrics).service(get_health);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{App, test};
+
+    /// Espera a que el suscriptor cuente los eventos publicados
+    async fn recorded(metrics: &Metrics, line: &str) -> bool {
+        for _ in 0..100 {
+            if metrics.render().lines().any(|l| l == line) {
+                return true;
+            }
+            actix_web::rt::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        false
+    }
+
+    #[actix_web::test]
+    async fn published_events_show_up_in_metrics() {
+        let metrics = web::Data::new(Metrics::default());
+        let events = EventBus::default();
+        spawn_recorder(metrics.clone(), &events);
+        let app = test::init_service(App::new().app_data(metrics.clone()).configure(config)).await;
+
+        events.publish(DomainEvent::PatientCreated { patient_id: 1 });
+        events.publish(DomainEvent::MedicalRecordCreated {
+            record_id: 1,
+            patient_id: 1,
+        });
+        events.publish(DomainEvent::LoginFailed);
+        events.publish(DomainEvent::LoginFailed);
+        // Sin contador de negocio: no cambia ninguno
+        events.publish(DomainEvent::AppointmentCompleted { appointment_id: 1 });
+        assert!(recorded(&metrics, "login_failures_total 2").await);
+
+        let request = test::TestRequest::get().uri("/metrics").to_request();
+        let body = test::call_and_read_body(&app, request).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        for line in [
+            "patients_created_total 1",
+            "medical_records_created_total 1",
+            "login_failures_total 2",
+            "appointments_created_total 0",
+            "appointments_canceled_total 0",
+        ] {
+            assert!(body.lines().any(|l| l == line), "{line}\n{body}");
+        }
+    }
+}
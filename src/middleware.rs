@@ -1,18 +1,152 @@
-use actix_web::{Error, dev::ServiceRequest};
+use crate::auth::{API_KEY_PREFIX, Claims, api_key_prefix, decode_jwt, verify_password};
+use crate::errors::ApiError;
+use crate::models::api_key::ApiKey;
+use crate::models::enums::UserRole;
+use actix_web::{Error, HttpMessage, dev::ServiceRequest, web};
+use actix_web_httpauth::extractors::AuthenticationError;
 use actix_web_httpauth::extractors::bearer::BearerAuth;
+use actix_web_httpauth::headers::www_authenticate::bearer::Bearer;
+use sqlx::PgPool;
 use std::env;
 
-/// Verifica que la petición lleve la API_KEY del sistema
+/// Única ruta a la que da acceso el token de pantalla (`LOBBY_DISPLAY_TOKEN`)
+const LOBBY_FEED_PATH: &str = "/api/public/lobby-feed";
+
+/// Encuestas de satisfacción: el token de la URL es la credencial
+const SURVEY_PATH_PREFIX: &str = "/api/survey/";
+
+/// Perfil público de la clínica para el sitio web, sin credenciales
+pub const CLINIC_PROFILE_PATH: &str = "/api/public/clinic-profile";
+
+/// Sondas de disponibilidad y recolectores de métricas, sin credenciales
+pub const HEALTH_PATH: &str = "/health";
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Verifica que la petición lleve credenciales válidas: la API_KEY del sistema,
+/// una llave de cuenta de servicio (`mck_...`) o un JWT emitido en el login.
+/// El token de pantalla de la sala de espera solo sirve para su feed; las
+/// encuestas de satisfacción, el perfil público de la clínica, `/health` y
+/// `/metrics` no requieren credenciales.
+///
+/// Para llaves de servicio y JWT los `Claims` quedan disponibles en la petición.
 pub async fn api_key_validator(
     req: ServiceRequest,
-    credentials: BearerAuth,
+    credentials: Option<BearerAuth>,
 ) -> Result<ServiceRequest, (Error, ServiceRequest)> {
+    if req.path().starts_with(SURVEY_PATH_PREFIX)
+        || [CLINIC_PROFILE_PATH, HEALTH_PATH, METRICS_PATH].contains(&req.path())
+    {
+        return Ok(req);
+    }
+    let Some(credentials) = credentials else {
+        return Err((AuthenticationError::new(Bearer::default()).into(), req));
+    };
+
     let valid_api_key = env::var("API_KEY").expect("API_KEY must be set");
+    let token = credentials.token();
 
-    if credentials.token().eq(&valid_api_key) {
+    if token.eq(&valid_api_key) {
         tracing::info!("API Key is valid");
-        Ok(req)
+        return Ok(req);
+    }
+
+    if env::var("LOBBY_DISPLAY_TOKEN").is_ok_and(|display| !display.is_empty() && token == display)
+    {
+        if req.path() == LOBBY_FEED_PATH {
+            return Ok(req);
+        }
+        let e =
+            ApiError::Forbidden("El token de pantalla solo da acceso a la sala de espera".into());
+        return Err((e.into(), req));
+    }
+
+    let claims = if token.starts_with(API_KEY_PREFIX) {
+        validate_service_key(&req, token).await
     } else {
-        Err((actix_web::error::ErrorUnauthorized("Invalid API Key"), req))
+        decode_jwt(token)
+    };
+
+    match claims {
+        Ok(claims) => {
+            if let Some(admin_id) = claims.impersonator {
+                tracing::info!(
+                    "Administrador {} como usuario {}: {} {}",
+                    admin_id,
+                    claims.sub,
+                    req.method(),
+                    req.path()
+                );
+            }
+            req.extensions_mut().insert(claims);
+            Ok(req)
+        }
+        Err(e) => {
+            tracing::warn!("Credenciales rechazadas: {}", e);
+            Err((e.into(), req))
+        }
     }
 }
+
+/// Valida una llave de cuenta de servicio y construye sus claims sintéticos
+async fn validate_service_key(req: &ServiceRequest, token: &str) -> Result<Claims, ApiError> {
+    let pool = req
+        .app_data::<web::Data<PgPool>>()
+        .ok_or_else(|| ApiError::InternalServerError("Pool no configurado".into()))?;
+
+    let prefix = api_key_prefix(token).ok_or(ApiError::Unauthorized("Invalid API Key".into()))?;
+
+    let api_key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT
+            id,
+            name,
+            prefix,
+            key_hash,
+            role as "role: UserRole",
+            scopes,
+            read_only,
+            expires_at,
+            last_used_at,
+            revoked_at,
+            created_by,
+            created_at
+        FROM api_keys
+        WHERE prefix = $1 AND revoked_at IS NULL
+        "#,
+        prefix
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or(ApiError::Unauthorized("Invalid API Key".into()))?;
+
+    if api_key.is_expired() || !verify_password(token, &api_key.key_hash)? {
+        return Err(ApiError::Unauthorized("Invalid API Key".into()));
+    }
+
+    if !api_key.allows(req.method(), req.path()) {
+        return Err(ApiError::Forbidden(
+            "La llave de API no tiene acceso a este recurso".into(),
+        ));
+    }
+
+    sqlx::query!(
+        "UPDATE api_keys SET last_used_at = NOW() WHERE id = $1",
+        api_key.id
+    )
+    .execute(pool.get_ref())
+    .await?;
+
+    tracing::info!("Llave de API {} válida", api_key.prefix);
+
+    Ok(Claims {
+        sub: api_key.created_by,
+        role: api_key.role,
+        iat: api_key.created_at.timestamp() as usize,
+        exp: api_key
+            .expires_at
+            .map_or(usize::MAX, |expires_at| expires_at.timestamp() as usize),
+        api_key_id: Some(api_key.id),
+        impersonator: None,
+    })
+}
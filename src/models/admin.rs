@@ -0,0 +1,206 @@
+use crate::models::enums::{AnimalSpecies, NotificationChannel, NotificationOutcome};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use validator::Validate;
+
+/// Parámetros para generar un conjunto de datos de demostración
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct DemoDatasetRequest {
+    #[validate(range(min = 1, max = 500))]
+    pub clients: Option<i64>, // Clientes a generar (default: 20)
+    #[serde(default)]
+    pub confirm: bool, // Requerido cuando APP_ENV=production
+}
+
+/// Parámetros para ejecutar manualmente el resumen diario
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(optional_fields = nullable)]
+pub struct DailyDigestRequest {
+    pub date: Option<NaiveDate>, // Día de la agenda (default: mañana)
+}
+
+/// Parámetros del reporte de integridad
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct IntegrityQuery {
+    pub checks: Option<String>, // Nombres separados por comas (default: todas)
+}
+
+/// Resultado de una revisión de integridad
+#[derive(Debug, Serialize, TS)]
+pub struct IntegrityCheckResult {
+    pub check: String,
+    pub description: String,
+    pub count: i64,           // Filas que incumplen la regla
+    pub sample_ids: Vec<i32>, // Hasta 10 ids de ejemplo
+}
+
+/// Reporte de integridad de los datos
+#[derive(Debug, Serialize, TS)]
+pub struct IntegrityReport {
+    pub generated_at: DateTime<Utc>,
+    pub checks: Vec<IntegrityCheckResult>,
+}
+
+/// Cambio del modo de mantenimiento
+#[derive(Debug, Deserialize, TS)]
+pub struct UpdateMaintenance {
+    pub read_only: bool, // Rechaza las escrituras con 503
+}
+
+/// Estado del modo de mantenimiento
+#[derive(Debug, Serialize, TS)]
+pub struct MaintenanceStatus {
+    pub read_only: bool,
+}
+
+/// Parámetros de la depuración de datos
+#[derive(Debug, Deserialize, TS)]
+pub struct RetentionQuery {
+    #[serde(default)]
+    pub dry_run: bool, // Solo reporta lo que se borraría
+}
+
+/// Filas que una política de retención borraría
+#[derive(Debug, Serialize, TS)]
+pub struct RetentionPolicyReport {
+    pub policy: String,
+    pub description: String,
+    pub retention_days: Option<i64>, // null si la política está desactivada
+    pub cutoff: Option<DateTime<Utc>>,
+    pub eligible: i64,
+}
+
+/// Vista previa de la depuración de datos
+#[derive(Debug, Serialize, TS)]
+pub struct RetentionReport {
+    pub generated_at: DateTime<Utc>,
+    pub archive_dir: Option<String>, // null si las filas se borran sin archivar
+    pub policies: Vec<RetentionPolicyReport>,
+}
+
+/// Parámetros del archivado de citas
+#[derive(Debug, Deserialize, TS)]
+pub struct AppointmentArchiveQuery {
+    #[serde(default)]
+    pub dry_run: bool, // Solo reporta cuántas citas se archivarían
+}
+
+/// Vista previa del archivado de citas canceladas y no_show
+#[derive(Debug, Serialize, TS)]
+pub struct AppointmentArchiveReport {
+    pub generated_at: DateTime<Utc>,
+    pub archive_days: Option<i64>, // null si el archivado está desactivado
+    pub cutoff: Option<DateTime<Utc>>,
+    pub eligible: i64, // Citas que se moverían ahora
+    pub archived: i64, // Citas que ya están en el archivo
+}
+
+/// Filtros de la bitácora de notificaciones
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
+pub struct NotificationLogFilter {
+    pub client_id: Option<i32>,
+    pub channel: Option<NotificationChannel>,
+    pub outcome: Option<NotificationOutcome>,
+    pub start_date: Option<DateTime<Utc>>, // Intentos desde esta fecha
+    pub end_date: Option<DateTime<Utc>>,   // Intentos antes de esta fecha
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Intento de notificación registrado (sin el cuerpo del mensaje)
+#[derive(Debug, Serialize, TS)]
+pub struct NotificationLogEntry {
+    pub id: i32,
+    pub channel: NotificationChannel,
+    pub target: String,
+    pub template: String, // satisfaction_survey, daily_digest o password_reset
+    pub subject: String,
+    pub client_id: Option<i32>,
+    pub appointment_id: Option<i32>,
+    pub outcome: NotificationOutcome,
+    pub provider_message_id: Option<String>, // null si falló o SMTP no está configurado
+    pub error: Option<String>,
+    pub retry_of: Option<i32>, // Envío original si es un reintento
+    pub attempted_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Consumo de un límite del plan
+#[derive(Debug, Clone, Serialize, TS)]
+pub struct LimitUsage {
+    pub limit: String, // active_patients, active_users o monthly_appointments
+    pub current: i64,
+    pub max: Option<i64>, // null si es ilimitado
+}
+
+/// Consumo de todos los límites del plan
+#[derive(Debug, Serialize, TS)]
+pub struct UsageReport {
+    pub generated_at: DateTime<Utc>,
+    pub limits: Vec<LimitUsage>,
+}
+
+/// Cuerpo de la respuesta 402 cuando se alcanza un límite
+#[derive(Debug, Serialize, TS)]
+pub struct LimitExceededResponse {
+    pub code: String, // Siempre "limit_exceeded"
+    pub message: String,
+    pub limit: String,
+    pub current: i64,
+    pub max: Option<i64>,
+}
+
+impl LimitExceededResponse {
+    pub fn from_usage(usage: &LimitUsage) -> Self {
+        Self {
+            code: "limit_exceeded".into(),
+            message: format!(
+                "Se alcanzó el límite del plan para {} ({} de {})",
+                usage.limit,
+                usage.current,
+                usage.max.unwrap_or(usage.current)
+            ),
+            limit: usage.limit.clone(),
+            current: usage.current,
+            max: usage.max,
+        }
+    }
+}
+
+/// Pacientes de una especie sin raza asignada
+#[derive(Debug, Serialize, TS)]
+pub struct MissingBreedBySpecies {
+    pub species: AnimalSpecies,
+    pub patients: i64,
+    pub missing: i64,
+    pub percentage: f64, // 0 a 100, con dos decimales
+}
+
+/// Nombre repetido entre los pacientes sin raza, para detectar patrones de importación
+#[derive(Debug, Serialize, TS)]
+pub struct UnassignedNameCluster {
+    pub name: String, // En minúsculas y sin espacios sobrantes
+    pub species: AnimalSpecies,
+    pub count: i64,
+}
+
+/// Raza del catálogo sin ningún paciente
+#[derive(Debug, Serialize, TS)]
+pub struct UnusedBreed {
+    pub id: i32,
+    pub species: AnimalSpecies,
+    pub name: String,
+}
+
+/// Calidad de los datos de razas
+#[derive(Debug, Serialize, TS)]
+pub struct BreedQualityReport {
+    pub generated_at: DateTime<Utc>,
+    pub missing_by_species: Vec<MissingBreedBySpecies>,
+    pub unassigned_name_clusters: Vec<UnassignedNameCluster>,
+    pub unused_breeds: Vec<UnusedBreed>,
+}
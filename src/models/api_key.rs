@@ -0,0 +1,144 @@
+use crate::models::appointment::validate_future_datetime;
+use crate::models::enums::UserRole;
+use actix_web::http::Method;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use ts_rs::TS;
+use validator::{Validate, ValidationError};
+
+/// Estructura completa de una llave de API (cuenta de servicio)
+#[derive(Debug, FromRow, Clone)]
+pub struct ApiKey {
+    pub id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub key_hash: String,
+    pub role: UserRole,
+    pub scopes: Vec<String>, // Prefijos de recursos permitidos (vacío = todos)
+    pub read_only: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_by: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiKey {
+    /// Indica si la llave ya expiró
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now())
+    }
+
+    /// Verifica que la llave permita el método y la ruta solicitados
+    pub fn allows(&self, method: &Method, path: &str) -> bool {
+        if self.read_only && !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+            return false;
+        }
+
+        if self.scopes.is_empty() {
+            return true;
+        }
+
+        let resource = path.strip_prefix("/api").unwrap_or(path);
+        self.scopes.iter().any(|scope| {
+            let scope = format!("/{}", scope.trim_matches('/'));
+            resource == scope || resource.starts_with(&format!("{scope}/"))
+        })
+    }
+}
+
+/// Estructura para crear una nueva llave de API
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct NewApiKey {
+    #[validate(length(min = 3, max = 100))]
+    pub name: String,
+    pub role: UserRole,
+    #[serde(default)]
+    #[validate(custom(function = "validate_scopes"))]
+    pub scopes: Vec<String>, // Ej: ["patients", "appointments"]
+    #[serde(default)]
+    pub read_only: bool,
+    #[validate(custom(function = "validate_future_datetime"))]
+    pub expires_at: Option<DateTime<Utc>>, // None = no expira
+}
+
+/// Estructura para actualizar una llave de API
+#[derive(Debug, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
+pub struct UpdateApiKey {
+    #[validate(length(min = 3, max = 100))]
+    pub name: Option<String>,
+    #[validate(custom(function = "validate_scopes"))]
+    pub scopes: Option<Vec<String>>,
+    pub read_only: Option<bool>,
+    /// Omitir para conservar el valor, `null` para quitar la expiración
+    #[ts(optional, type = "string | null | undefined")]
+    pub expires_at: Option<Option<DateTime<Utc>>>,
+}
+
+/// Valida que los prefijos de recursos no estén vacíos ni sean demasiado largos
+pub fn validate_scopes(scopes: &[String]) -> Result<(), ValidationError> {
+    if scopes
+        .iter()
+        .any(|scope| scope.trim_matches('/').is_empty() || scope.len() > 50)
+    {
+        return Err(ValidationError::new(
+            "Cada scope debe ser un prefijo de recurso de 1 a 50 caracteres",
+        ));
+    }
+    Ok(())
+}
+
+/// Estructura de respuesta para API (nunca incluye el secreto)
+#[derive(Debug, Serialize, TS)]
+pub struct ApiKeyResponse {
+    pub id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub role: UserRole,
+    pub scopes: Vec<String>,
+    pub read_only: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_by: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            id: api_key.id,
+            name: api_key.name,
+            prefix: api_key.prefix,
+            role: api_key.role,
+            scopes: api_key.scopes,
+            read_only: api_key.read_only,
+            expires_at: api_key.expires_at,
+            last_used_at: api_key.last_used_at,
+            revoked_at: api_key.revoked_at,
+            created_by: api_key.created_by,
+            created_at: api_key.created_at,
+        }
+    }
+}
+
+/// Respuesta de creación: la única vez que se devuelve la llave completa
+#[derive(Debug, Serialize, TS)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    pub key: String,
+}
+
+/// Filtros para búsqueda de llaves de API
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
+pub struct ApiKeyFilter {
+    pub include_revoked: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -1,7 +1,15 @@
-use crate::models::enums::AppointmentStatus;
-use chrono::{DateTime, Utc};
+use crate::auth::Claims;
+use crate::clinic;
+use crate::errors::ApiError;
+use crate::models::appointment_note::{
+    AppointmentNoteFilter, AppointmentNoteResponse, LATEST_NOTES,
+};
+use crate::models::enums::{AppointmentStatus, BookingSource, UserRole};
+use crate::models::page::Page;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use ts_rs::TS;
 use validator::{Validate, ValidationError};
 
 /// Estructura completa para citas
@@ -15,11 +23,60 @@ pub struct Appointment {
     pub end_time: DateTime<Utc>,
     pub status: AppointmentStatus,
     pub reason: String,
+    pub is_emergency: bool,
+    pub procedure_id: Option<i32>,
+    pub room_id: Option<i32>,
+    pub booking_source: BookingSource,
+    pub internal_notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub canceled_at: Option<DateTime<Utc>>,
+    pub cancellation_reason: Option<String>,
+}
+
+/// Cita con los nombres relacionados y las últimas notas ya resueltos por la
+/// misma consulta (JOIN a pacientes, clientes, usuarios, procedimientos y
+/// salas; las notas llegan agregadas como JSON)
+#[derive(Debug, FromRow)]
+pub struct AppointmentRow {
+    pub id: i32,
+    pub patient_id: Option<i32>,
+    pub patient_name: Option<String>,
+    pub client_id: Option<i32>,
+    pub client_name: Option<String>,
+    pub veterinarian_id: i32,
+    pub veterinarian_name: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub status: AppointmentStatus,
+    pub reason: String,
+    pub is_emergency: bool,
+    pub procedure_id: Option<i32>,
+    pub procedure_name: Option<String>,
+    pub room_id: Option<i32>,
+    pub room_name: Option<String>,
+    pub booking_source: BookingSource,
+    pub internal_notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub canceled_at: Option<DateTime<Utc>>,
+    pub cancellation_reason: Option<String>,
+    pub latest_notes: sqlx::types::Json<Vec<AppointmentNoteResponse>>,
+}
+
+/// Origen de la cita según quién la crea: un JWT de usuario es `Staff`; una
+/// llave de cuenta de servicio o la API_KEY del sistema (sin claims) es `Api`
+pub fn booking_source(claims: Option<&Claims>) -> BookingSource {
+    match claims {
+        Some(claims) if claims.api_key_id.is_none() => BookingSource::Staff,
+        _ => BookingSource::Api,
+    }
 }
 
 /// Estructura para crear nueva cita
-#[derive(Debug, Serialize, Deserialize, Validate)]
-#[validate(schema(function = "validate_appointment_times"))]
+#[derive(Debug, Serialize, Deserialize, Validate, TS)]
+#[validate(schema(function = "validate_new_appointment"))]
+#[ts(optional_fields = nullable)]
 pub struct NewAppointment {
     #[validate(range(min = 1))]
     pub patient_id: Option<i32>,
@@ -27,21 +84,32 @@ pub struct NewAppointment {
     pub client_id: Option<i32>,
     #[validate(range(min = 1))]
     pub veterinarian_id: i32,
-    #[validate(custom(function = "validate_future_datetime"))]
-    pub start_time: DateTime<Utc>,
-    #[validate(custom(function = "validate_future_datetime"))]
+    pub start_time: DateTime<Utc>, // En el futuro, salvo `walk_in`
     pub end_time: DateTime<Utc>,
     #[validate(length(min = 5, max = 500))]
     pub reason: String,
+    #[serde(default)]
+    pub is_emergency: bool,
+    #[serde(default)]
+    pub walk_in: bool, // Visita sin cita registrada después; se crea como `Completed`
+    #[validate(range(min = 1))]
+    pub procedure_id: Option<i32>, // Si requiere sala, se asigna una libre
+    #[validate(length(max = 2000))]
+    pub internal_notes: Option<String>, // Solo personal; el motivo (`reason`) sí lo ve el dueño
 }
 
 /// Estructura para actualizar cita
-#[derive(Debug, Serialize, Deserialize, Validate, Default)]
+#[derive(Debug, Serialize, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
 pub struct UpdateAppointment {
+    /// Omitir para conservar el valor, `null` para desasociar
     #[validate(range(min = 1))]
-    pub patient_id: Option<Option<i32>>, // Some(None) para desasociar
+    #[ts(optional, type = "number | null | undefined")]
+    pub patient_id: Option<Option<i32>>,
+    /// Omitir para conservar el valor, `null` para desasociar
     #[validate(range(min = 1))]
-    pub client_id: Option<Option<i32>>, // Some(None) para desasociar
+    #[ts(optional, type = "number | null | undefined")]
+    pub client_id: Option<Option<i32>>,
     #[validate(range(min = 1))]
     pub veterinarian_id: Option<i32>,
     pub start_time: Option<DateTime<Utc>>,
@@ -50,6 +118,76 @@ pub struct UpdateAppointment {
     pub status: Option<AppointmentStatus>,
     #[validate(length(min = 5, max = 500))]
     pub reason: Option<String>,
+    pub is_emergency: Option<bool>,
+    /// Omitir para conservar el valor, `null` para quitar el procedimiento
+    #[validate(range(min = 1))]
+    #[ts(optional, type = "number | null | undefined")]
+    pub procedure_id: Option<Option<i32>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(length(max = 2000))]
+    #[ts(optional, type = "string | null | undefined")]
+    pub internal_notes: Option<Option<String>>,
+    /// Solo junto con `status: "Canceled"`
+    #[validate(length(min = 1, max = 500))]
+    pub cancellation_reason: Option<String>,
+}
+
+/// Parámetros de query de la actualización de citas
+#[derive(Debug, Deserialize, Default, TS)]
+pub struct UpdateAppointmentQuery {
+    #[serde(default)]
+    pub override_warnings: bool, // Aceptar una urgencia fuera del horario de atención
+    #[serde(default)]
+    pub allow_past_edit: bool, // Solo admin: mover una cita que ya terminó
+}
+
+/// Cambio de estado de una cita, sin tocar el resto de sus campos
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct UpdateAppointmentStatus {
+    pub status: AppointmentStatus,
+    #[validate(length(min = 1, max = 500))]
+    pub note: Option<String>, // Motivo de la cancelación, o se agrega al final del motivo
+}
+
+/// Reasignación masiva de las citas futuras de un veterinario a otro
+#[derive(Debug, Deserialize, Validate, TS)]
+#[validate(schema(function = "validate_reassignment"))]
+pub struct ReassignAppointments {
+    #[validate(range(min = 1))]
+    pub from_veterinarian_id: i32,
+    #[validate(range(min = 1))]
+    pub to_veterinarian_id: i32,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+}
+
+fn validate_reassignment(reassignment: &ReassignAppointments) -> Result<(), ValidationError> {
+    if reassignment.from_veterinarian_id == reassignment.to_veterinarian_id {
+        return Err(ValidationError::new(
+            "El veterinario de destino debe ser distinto al de origen",
+        ));
+    }
+    if reassignment.end_date <= reassignment.start_date {
+        return Err(ValidationError::new(
+            "end_date debe ser posterior a start_date",
+        ));
+    }
+    Ok(())
+}
+
+/// Cita que no se reasignó
+#[derive(Debug, Serialize, TS)]
+pub struct SkippedReassignment {
+    pub appointment_id: i32,
+    pub reason: String, // Ej: "Traslape con la cita 12 del veterinario de destino"
+}
+
+/// Resultado de la reasignación masiva
+#[derive(Debug, Serialize, TS)]
+pub struct ReassignmentResponse {
+    pub moved_ids: Vec<i32>,
+    pub skipped: Vec<SkippedReassignment>,
 }
 
 /// Valida que la fecha/hora sea en el futuro
@@ -60,17 +198,81 @@ pub fn validate_future_datetime(dt: &DateTime<Utc>) -> Result<(), ValidationErro
     Ok(())
 }
 
+/// Días hacia el futuro en que se aceptan citas (`MAX_BOOKING_HORIZON_DAYS`, default: 365)
+pub fn booking_horizon_days() -> i64 {
+    std::env::var("MAX_BOOKING_HORIZON_DAYS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|days: &i64| *days > 0)
+        .unwrap_or(365)
+}
+
+/// Responde 422 si la cita empieza después del horizonte de reservas; evita
+/// que un año mal escrito (ej: 2039) se agende sin aviso
+pub fn ensure_within_booking_horizon(start_time: DateTime<Utc>) -> Result<(), ApiError> {
+    let days = booking_horizon_days();
+    if start_time > Utc::now() + chrono::Duration::days(days) {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "La cita no puede agendarse con más de {days} días de anticipación"
+        )));
+    }
+    Ok(())
+}
+
+/// Días hacia atrás en que se acepta registrar una visita sin cita
+/// (`WALK_IN_MAX_DAYS`, default: 7)
+pub fn walk_in_max_days() -> i64 {
+    std::env::var("WALK_IN_MAX_DAYS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|days: &i64| *days > 0)
+        .unwrap_or(7)
+}
+
+/// Validación del payload de creación: ventana de fechas según `walk_in` y
+/// relación entre inicio y fin
+pub fn validate_new_appointment(appointment: &NewAppointment) -> Result<(), ValidationError> {
+    // Las visitas sin cita se registran después de ocurrir, dentro de un
+    // margen; el resto de las citas debe ser en el futuro
+    if appointment.walk_in {
+        if appointment.start_time > Utc::now() {
+            return Err(ValidationError::new(
+                "Una visita sin cita (walk_in) debe haber comenzado ya",
+            ));
+        }
+        if appointment.start_time < Utc::now() - chrono::Duration::days(walk_in_max_days()) {
+            return Err(ValidationError::new(
+                "La visita sin cita es demasiado antigua para registrarse",
+            ));
+        }
+    } else {
+        validate_future_datetime(&appointment.start_time)?;
+        validate_future_datetime(&appointment.end_time)?;
+    }
+
+    validate_appointment_times(appointment)
+}
+
 /// Valida la relación entre start_time y end_time
 pub fn validate_appointment_times(appointment: &NewAppointment) -> Result<(), ValidationError> {
+    validate_time_range(appointment.start_time, appointment.end_time)
+}
+
+/// Reglas de inicio y fin de una cita, comunes a la creación y a la
+/// actualización (con los valores ya combinados con los guardados)
+pub fn validate_time_range(
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+) -> Result<(), ValidationError> {
     // Validar que end_time > start_time
-    if appointment.end_time <= appointment.start_time {
+    if end_time <= start_time {
         return Err(ValidationError::new(
             "La hora de fin debe ser posterior a la de inicio",
         ));
     }
 
     // Validar duración mínima (5 minutos)
-    let duration = appointment.end_time - appointment.start_time;
+    let duration = end_time - start_time;
     if duration.num_minutes() < 5 {
         return Err(ValidationError::new(
             "La cita debe durar al menos 5 minutos",
@@ -87,8 +289,103 @@ pub fn validate_appointment_times(appointment: &NewAppointment) -> Result<(), Va
     Ok(())
 }
 
+/// Si una cita puede pasar de `from` a `to`. Solo una cita `Scheduled` cambia
+/// de estado (a `Completed`, `Canceled` o `NoShow`); las demás son finales.
+/// Conservar el mismo estado siempre se permite.
+pub fn is_valid_status_transition(from: &AppointmentStatus, to: &AppointmentStatus) -> bool {
+    use AppointmentStatus::*;
+    matches!(
+        (from, to),
+        (Scheduled, _) | (Completed, Completed) | (Canceled, Canceled) | (NoShow, NoShow)
+    )
+}
+
+/// Reglas de estado de una actualización: responde 409 si la transición no es
+/// válida o si cambia el horario o el veterinario de una cita `Completed`, y
+/// 400 si trae `cancellation_reason` sin cancelar la cita
+pub fn check_appointment_update(
+    existing: &Appointment,
+    update: &UpdateAppointment,
+) -> Result<(), ApiError> {
+    if update.cancellation_reason.is_some()
+        && !matches!(update.status, Some(AppointmentStatus::Canceled))
+    {
+        return Err(ApiError::ValidationError(
+            "cancellation_reason solo se acepta con status Canceled".into(),
+        ));
+    }
+
+    if let Some(status) = &update.status
+        && !is_valid_status_transition(&existing.status, status)
+    {
+        return Err(ApiError::Conflict(format!(
+            "Una cita {:?} no puede pasar a {:?}",
+            existing.status, status
+        )));
+    }
+
+    let moved = update
+        .start_time
+        .is_some_and(|start_time| start_time != existing.start_time)
+        || update
+            .end_time
+            .is_some_and(|end_time| end_time != existing.end_time)
+        || update
+            .veterinarian_id
+            .is_some_and(|veterinarian_id| veterinarian_id != existing.veterinarian_id);
+    if moved && matches!(existing.status, AppointmentStatus::Completed) {
+        return Err(ApiError::Conflict(
+            "La cita ya se completó; su horario y veterinario no se pueden modificar".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Horario de la cita tras aplicar `update` sobre el guardado. Con un solo
+/// extremo nuevo, la relación y la duración se validan contra el otro extremo
+/// guardado (400 con los mensajes de `validate_time_range`).
+pub fn effective_time_range(
+    existing: &Appointment,
+    update: &UpdateAppointment,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), ApiError> {
+    let start_time = update.start_time.unwrap_or(existing.start_time);
+    let end_time = update.end_time.unwrap_or(existing.end_time);
+
+    if update.start_time.is_some() || update.end_time.is_some() {
+        validate_time_range(start_time, end_time)
+            .map_err(|e| ApiError::ValidationError(e.to_string()))?;
+    }
+    Ok((start_time, end_time))
+}
+
+/// Una cita que ya terminó no se mueve de horario ni de veterinario: responde
+/// 409 salvo con `allow_past_edit`, que solo acepta a un administrador (403
+/// para cualquier otro rol). Devuelve si la petición edita una cita pasada.
+pub fn check_past_edit(
+    existing: &Appointment,
+    update: &UpdateAppointment,
+    allow_past_edit: bool,
+    claims: &Claims,
+    now: DateTime<Utc>,
+) -> Result<bool, ApiError> {
+    let moves = update.start_time.is_some()
+        || update.end_time.is_some()
+        || update.veterinarian_id.is_some();
+    if existing.end_time >= now || !moves {
+        return Ok(false);
+    }
+    if !allow_past_edit {
+        return Err(ApiError::Conflict(
+            "Cita pasada; solo el estado puede modificarse".into(),
+        ));
+    }
+    claims.require_role(&[UserRole::Admin])?;
+    Ok(true)
+}
+
 /// Estructura de respuesta enriquecida para API
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct AppointmentResponse {
     pub id: i32,
     pub patient_id: Option<i32>,
@@ -99,9 +396,24 @@ pub struct AppointmentResponse {
     pub veterinarian_name: String,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
+    pub start_time_local: String, // En la zona de la clínica con desplazamiento, ej: "2024-06-03T09:00:00-06:00"
+    pub end_time_local: String,
+    pub timezone: &'static str, // Zona IANA de las horas locales, ej: "America/Mexico_City"
     pub status: AppointmentStatus,
     pub reason: String,
+    pub is_emergency: bool,
+    pub procedure_id: Option<i32>,
+    pub procedure_name: Option<String>,
+    pub room_id: Option<i32>, // Asignada según el procedimiento; se libera al cancelar
+    pub room_name: Option<String>,
+    pub booking_source: BookingSource,
+    pub internal_notes: Option<String>, // Solo personal de la clínica
+    pub created_at: DateTime<Utc>,      // Cuándo se agendó
+    pub updated_at: DateTime<Utc>,
+    pub canceled_at: Option<DateTime<Utc>>,
+    pub cancellation_reason: Option<String>,
     pub duration_minutes: i64,
+    pub latest_notes: Vec<AppointmentNoteResponse>, // Últimas 3, de la más reciente
 }
 
 impl AppointmentResponse {
@@ -113,45 +425,219 @@ impl AppointmentResponse {
         let record = sqlx::query!(
             r#"
             SELECT
-                p.name as patient_name,
-                c.name as client_name,
-                u.name as vet_name
+                p.name as "patient_name?",
+                c.name as "client_name?",
+                u.name as vet_name,
+                pr.name as "procedure_name?",
+                r.name as "room_name?"
             FROM users u
             LEFT JOIN patients p ON p.id = $1
             LEFT JOIN clients c ON c.id = $2
+            LEFT JOIN procedures pr ON pr.id = $4
+            LEFT JOIN rooms r ON r.id = $5
             WHERE u.id = $3
             "#,
             appointment.patient_id,
             appointment.client_id,
             appointment.veterinarian_id,
+            appointment.procedure_id,
+            appointment.room_id,
         )
         .fetch_one(pool)
         .await?;
 
-        let (patient_name, client_name, vet_name) =
-            (record.patient_name, record.client_name, record.vet_name);
+        let (patient_name, client_name, vet_name) = (
+            record.patient_name.clone(),
+            record.client_name.clone(),
+            record.vet_name.clone(),
+        );
+
+        let latest_notes = AppointmentNoteResponse::for_appointment(
+            pool,
+            appointment.id,
+            &AppointmentNoteFilter {
+                limit: Some(LATEST_NOTES),
+                offset: None,
+            },
+        )
+        .await?;
 
         let duration = appointment.end_time - appointment.start_time;
 
         Ok(Self {
             id: appointment.id,
             patient_id: appointment.patient_id,
-            patient_name: Some(patient_name),
+            patient_name,
             client_id: appointment.client_id,
-            client_name: Some(client_name),
+            client_name,
             veterinarian_id: appointment.veterinarian_id,
             veterinarian_name: vet_name,
             start_time: appointment.start_time,
             end_time: appointment.end_time,
+            start_time_local: clinic::local_iso(appointment.start_time),
+            end_time_local: clinic::local_iso(appointment.end_time),
+            timezone: clinic::timezone_name(),
             status: appointment.status,
             reason: appointment.reason,
+            is_emergency: appointment.is_emergency,
+            procedure_id: appointment.procedure_id,
+            procedure_name: record.procedure_name,
+            room_id: appointment.room_id,
+            room_name: record.room_name,
+            booking_source: appointment.booking_source,
+            internal_notes: appointment.internal_notes,
+            created_at: appointment.created_at,
+            updated_at: appointment.updated_at,
+            canceled_at: appointment.canceled_at,
+            cancellation_reason: appointment.cancellation_reason,
             duration_minutes: duration.num_minutes(),
+            latest_notes,
         })
     }
+
+    /// Respuesta a partir de una fila que ya trae los nombres y las notas,
+    /// sin consultar la base de datos
+    pub fn from_joined_row(row: AppointmentRow) -> Self {
+        Self {
+            id: row.id,
+            patient_id: row.patient_id,
+            patient_name: row.patient_name,
+            client_id: row.client_id,
+            client_name: row.client_name,
+            veterinarian_id: row.veterinarian_id,
+            veterinarian_name: row.veterinarian_name,
+            start_time: row.start_time,
+            end_time: row.end_time,
+            start_time_local: clinic::local_iso(row.start_time),
+            end_time_local: clinic::local_iso(row.end_time),
+            timezone: clinic::timezone_name(),
+            status: row.status,
+            reason: row.reason,
+            is_emergency: row.is_emergency,
+            procedure_id: row.procedure_id,
+            procedure_name: row.procedure_name,
+            room_id: row.room_id,
+            room_name: row.room_name,
+            booking_source: row.booking_source,
+            internal_notes: row.internal_notes,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            canceled_at: row.canceled_at,
+            cancellation_reason: row.cancellation_reason,
+            duration_minutes: (row.end_time - row.start_time).num_minutes(),
+            latest_notes: row.latest_notes.0,
+        }
+    }
+
+    /// Citas más recientes de un paciente, con nombres y notas resueltos en
+    /// una sola consulta en lugar de varias por cita
+    pub async fn for_patient(
+        pool: &sqlx::PgPool,
+        patient_id: i32,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            AppointmentRow,
+            r#"
+            SELECT
+                a.id,
+                a.patient_id,
+                p.name as "patient_name?",
+                a.client_id,
+                c.name as "client_name?",
+                a.veterinarian_id,
+                u.name as veterinarian_name,
+                a.start_time,
+                a.end_time as "end_time!",
+                a.status as "status!: AppointmentStatus",
+                a.reason,
+                a.is_emergency,
+                a.procedure_id,
+                pr.name as "procedure_name?",
+                a.room_id,
+                r.name as "room_name?",
+                a.booking_source as "booking_source: BookingSource",
+                a.internal_notes,
+                a.created_at,
+                a.updated_at,
+                a.canceled_at,
+                a.cancellation_reason,
+                notes.latest as "latest_notes!: sqlx::types::Json<Vec<AppointmentNoteResponse>>"
+            FROM appointments a
+            JOIN users u ON u.id = a.veterinarian_id
+            LEFT JOIN patients p ON p.id = a.patient_id
+            LEFT JOIN clients c ON c.id = a.client_id
+            LEFT JOIN procedures pr ON pr.id = a.procedure_id
+            LEFT JOIN rooms r ON r.id = a.room_id
+            CROSS JOIN LATERAL (
+                SELECT COALESCE(
+                    JSON_AGG(JSON_BUILD_OBJECT(
+                        'id', n.id,
+                        'appointment_id', n.appointment_id,
+                        'author_id', n.author_id,
+                        'author_name', n.author_name,
+                        'content', n.content,
+                        'created_at', n.created_at
+                    ) ORDER BY n.created_at DESC, n.id DESC),
+                    '[]'
+                ) as latest
+                FROM (
+                    SELECT n.id, n.appointment_id, n.author_id, nu.name as author_name, n.content, n.created_at
+                    FROM appointment_notes n
+                    JOIN users nu ON nu.id = n.author_id
+                    WHERE n.appointment_id = a.id
+                    ORDER BY n.created_at DESC, n.id DESC
+                    LIMIT $3
+                ) n
+            ) notes
+            WHERE a.patient_id = $1
+            ORDER BY a.start_time DESC, a.id DESC
+            LIMIT $2
+            "#,
+            patient_id,
+            limit,
+            LATEST_NOTES
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(Self::from_joined_row).collect())
+    }
+}
+
+/// Vista de la cita para el dueño de la mascota.
+///
+/// Las respuestas que ve el cliente se construyen solo con este tipo, que no
+/// tiene campos internos (`internal_notes`, origen, sala): agregar un campo a
+/// `Appointment` o a `AppointmentResponse` no lo expone.
+#[derive(Debug, Serialize, TS)]
+pub struct AppointmentPublicResponse {
+    pub id: i32,
+    pub patient_name: Option<String>,
+    pub veterinarian_name: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub status: AppointmentStatus,
+    pub reason: String,
+}
+
+impl From<AppointmentResponse> for AppointmentPublicResponse {
+    fn from(appointment: AppointmentResponse) -> Self {
+        Self {
+            id: appointment.id,
+            patient_name: appointment.patient_name,
+            veterinarian_name: appointment.veterinarian_name,
+            start_time: appointment.start_time,
+            end_time: appointment.end_time,
+            status: appointment.status,
+            reason: appointment.reason,
+        }
+    }
 }
 
 /// Filtros para búsqueda de citas
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
 pub struct AppointmentFilter {
     pub patient_id: Option<i32>,
     pub client_id: Option<i32>,
@@ -160,6 +646,591 @@ pub struct AppointmentFilter {
     pub start_date: Option<DateTime<Utc>>,
     pub end_date: Option<DateTime<Utc>>,
     pub reason_contains: Option<String>,
+    pub booking_source: Option<BookingSource>,
+    pub created_after: Option<DateTime<Utc>>, // Agendadas desde este instante
+    pub created_before: Option<DateTime<Utc>>, // Agendadas antes de este instante
+    pub at: Option<DateTime<Utc>>,            // Citas en curso en ese instante
+    pub active_now: Option<bool>,             // Atajo de `at` con la hora actual
+    pub include_counts: Option<bool>,         // Agrega `status_counts` a la respuesta
+    pub envelope: Option<bool>,               // false: arreglo sin `total` (obsoleto)
+    pub include_archived: Option<bool>,       // Incluye las citas de `appointments_archive`
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
+
+impl AppointmentFilter {
+    /// Instante que deben contener las citas (`at` o `active_now`). Responde
+    /// 400 si se envían ambos.
+    pub fn instant(&self) -> Result<Option<DateTime<Utc>>, ApiError> {
+        match (self.at, self.active_now.unwrap_or(false)) {
+            (Some(_), true) => Err(ApiError::ValidationError(
+                "Usa at o active_now, no ambos".into(),
+            )),
+            (at, active_now) => Ok(at.or(active_now.then(Utc::now))),
+        }
+    }
+}
+
+/// Conteo de citas por estado sobre los mismos filtros del listado (sin el de estado)
+#[derive(Debug, Serialize, Default, TS)]
+pub struct AppointmentStatusCounts {
+    pub scheduled: i64,
+    pub completed: i64,
+    pub canceled: i64,
+    pub no_show: i64,
+}
+
+impl AppointmentStatusCounts {
+    /// Acumula el conteo de un estado
+    pub fn add(&mut self, status: AppointmentStatus, count: i64) {
+        match status {
+            AppointmentStatus::Scheduled => self.scheduled += count,
+            AppointmentStatus::Completed => self.completed += count,
+            AppointmentStatus::Canceled => self.canceled += count,
+            AppointmentStatus::NoShow => self.no_show += count,
+        }
+    }
+}
+
+/// Respuesta del listado de citas: la página con su total y, si se solicitan,
+/// los conteos por estado
+#[derive(Debug, Serialize, TS)]
+pub struct AppointmentListResponse {
+    #[serde(flatten)]
+    pub page: Page<AppointmentResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub status_counts: Option<AppointmentStatusCounts>,
+}
+
+/// Días máximos del calendario
+pub const MAX_CALENDAR_DAYS: u32 = 31;
+
+/// Parámetros del calendario de la clínica
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct CalendarQuery {
+    pub start: NaiveDate,                 // Primer día local
+    pub days: Option<u32>,                // Default: 7, máximo: 31
+    pub veterinarian_ids: Option<String>, // IDs separados por comas (ej: "1,2,3")
+}
+
+impl CalendarQuery {
+    /// IDs de veterinarios a incluir (`None` = todos). Responde 400 si la
+    /// lista está mal formada.
+    pub fn veterinarian_ids(&self) -> Result<Option<Vec<i32>>, ApiError> {
+        let Some(raw) = &self.veterinarian_ids else {
+            return Ok(None);
+        };
+
+        raw.split(',')
+            .map(|id| id.trim().parse::<i32>().ok().filter(|id| *id > 0))
+            .collect::<Option<Vec<i32>>>()
+            .map(Some)
+            .ok_or_else(|| {
+                ApiError::ValidationError(
+                    "veterinarian_ids debe ser una lista de IDs separados por comas".into(),
+                )
+            })
+    }
+
+    /// Número de días solicitado. Responde 400 fuera de 1 a 31.
+    pub fn days(&self) -> Result<u32, ApiError> {
+        match self.days.unwrap_or(7) {
+            days @ 1..=MAX_CALENDAR_DAYS => Ok(days),
+            _ => Err(ApiError::ValidationError(format!(
+                "days debe estar entre 1 y {MAX_CALENDAR_DAYS}"
+            ))),
+        }
+    }
+}
+
+/// Cita resumida del calendario (sin datos del cliente ni motivo)
+#[derive(Debug, Serialize, TS)]
+pub struct CalendarAppointment {
+    pub id: i32,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub start_local: String, // Mismo formato que `AppointmentResponse::start_time_local`
+    pub end_local: String,
+    pub status: AppointmentStatus,
+    pub patient_name: Option<String>,
+    pub is_emergency: bool,
+}
+
+/// Citas de un veterinario en un día, ordenadas por hora
+#[derive(Debug, Serialize, TS)]
+pub struct CalendarVeterinarian {
+    pub veterinarian_id: i32,
+    pub veterinarian_name: String,
+    pub appointments: Vec<CalendarAppointment>,
+}
+
+/// Día del calendario; solo incluye veterinarios con citas
+#[derive(Debug, Serialize, TS)]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub timezone: &'static str, // Zona IANA de `date` y de las horas locales
+    pub veterinarians: Vec<CalendarVeterinarian>,
+}
+
+/// Días máximos de la vista por día del calendario (dos meses)
+pub const MAX_CALENDAR_RANGE_DAYS: u32 = 62;
+
+/// Parámetros de la vista por día del calendario
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct CalendarRangeQuery {
+    pub from: NaiveDate,              // Primer día local
+    pub to: NaiveDate,                // Último día local, inclusive
+    pub veterinarian_id: Option<i32>, // Default: todos
+}
+
+impl CalendarRangeQuery {
+    /// Número de días del rango, inclusive. Responde 400 si `to` es anterior
+    /// a `from` o si el rango pasa de 62 días.
+    pub fn days(&self) -> Result<u32, ApiError> {
+        let days = (self.to - self.from).num_days() + 1;
+        match u32::try_from(days) {
+            Ok(days @ 1..=MAX_CALENDAR_RANGE_DAYS) => Ok(days),
+            _ => Err(ApiError::ValidationError(format!(
+                "to debe ser igual o posterior a from, con {MAX_CALENDAR_RANGE_DAYS} días como máximo"
+            ))),
+        }
+    }
+}
+
+/// Cita resumida de la vista por día del calendario
+#[derive(Debug, Serialize, TS)]
+pub struct CalendarEntry {
+    pub id: i32,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub status: AppointmentStatus,
+    pub patient_name: Option<String>,
+    pub reason: String,
+}
+
+/// Cita de la agenda diaria de un veterinario
+#[derive(Debug)]
+pub struct AgendaEntry {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub patient_name: Option<String>,
+    pub client_name: Option<String>,
+    pub client_phone: Option<String>,
+    pub reason: String,
+}
+
+/// Citas programadas de un veterinario para un día local de la clínica.
+/// El teléfono es el del contacto principal del cliente; si no tiene, el de
+/// otro contacto y como último recurso el del cliente.
+pub async fn veterinarian_agenda(
+    pool: &sqlx::PgPool,
+    veterinarian_id: i32,
+    date: NaiveDate,
+) -> Result<Vec<AgendaEntry>, sqlx::Error> {
+    let (day_start, day_end) = clinic::day_bounds(date);
+
+    sqlx::query_as!(
+        AgendaEntry,
+        r#"
+        SELECT
+            a.start_time,
+            a.end_time as "end_time!",
+            p.name as "patient_name?",
+            c.name as "client_name?",
+            COALESCE(cc.phone, c.phone) as "client_phone?",
+            a.reason
+        FROM appointments a
+        LEFT JOIN patients p ON p.id = a.patient_id
+        LEFT JOIN clients c ON c.id = a.client_id
+        -- Teléfono del contacto principal o, si no tiene, del primer secundario que sí tenga
+        LEFT JOIN LATERAL (
+            SELECT phone
+            FROM client_contacts
+            WHERE client_id = c.id AND NULLIF(TRIM(phone), '') IS NOT NULL
+            ORDER BY is_primary DESC, id
+            LIMIT 1
+        ) cc ON TRUE
+        WHERE a.veterinarian_id = $1
+          AND a.status = 'scheduled'
+          AND a.start_time >= $2
+          AND a.start_time < $3
+        ORDER BY a.start_time
+        "#,
+        veterinarian_id,
+        day_start,
+        day_end
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Rango de tiempo de la agenda de un veterinario
+#[derive(Debug, Serialize, TS)]
+pub struct TimeSlot {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+/// Minutos de la rejilla de disponibilidad si no se indican
+pub const DEFAULT_SLOT_MINUTES: i64 = 30;
+
+/// Parámetros de la disponibilidad de un veterinario
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields)]
+pub struct AvailabilityQuery {
+    pub date: NaiveDate, // Día local de la clínica
+    #[validate(range(min = 5, max = 240))]
+    pub slot_minutes: Option<i64>, // Default: 30
+    #[validate(range(min = 5, max = 720))]
+    pub duration: Option<i64>, // Minutos que dura el procedimiento
+}
+
+/// Huecos libres de un veterinario en un día local de la clínica
+#[derive(Debug, Serialize, TS)]
+pub struct VeterinarianAvailability {
+    pub veterinarian_id: i32,
+    pub date: NaiveDate,
+    pub slot_minutes: i64,
+    pub slots: Vec<TimeSlot>, // Vacío si la clínica no abre ese día
+}
+
+/// Sugerencias que devuelve `GET /appointments/next-available` si no se indica `limit`
+pub const DEFAULT_SUGGESTIONS: i64 = 5;
+
+/// Parámetros de la búsqueda de los próximos horarios libres
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields)]
+pub struct NextAvailableQuery {
+    pub veterinarian_id: Option<i32>, // Default: todos los veterinarios activos
+    #[validate(range(min = 5, max = 720))]
+    pub duration_minutes: Option<i64>, // Default: 30
+    pub after: Option<DateTime<Utc>>, // Default: ahora
+    pub procedure_id: Option<i32>,    // Exige sala libre si el procedimiento la requiere
+    #[validate(range(min = 1, max = 20))]
+    pub limit: Option<i64>, // Default: 5
+}
+
+/// Horario libre sugerido para agendar una cita
+#[derive(Debug, Serialize, TS)]
+pub struct SuggestedSlot {
+    pub veterinarian_id: i32,
+    pub veterinarian_name: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+/// Huecos libres entre `from` y `to` fuera de las citas ocupadas, que vienen
+/// ordenadas por inicio. Cada hueco se recorta a la rejilla de `slot` que
+/// empieza en `grid_start` y se omite si queda más corto que `min_length`.
+pub fn free_slots(
+    busy: &[TimeSlot],
+    grid_start: DateTime<Utc>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    slot: Duration,
+    min_length: Duration,
+) -> Vec<TimeSlot> {
+    let step = slot.num_seconds().max(1);
+    let snap = |time: DateTime<Utc>, round_up: bool| {
+        let offset = (time - grid_start).num_seconds().max(0);
+        let steps = if round_up {
+            (offset + step - 1) / step
+        } else {
+            offset / step
+        };
+        grid_start + Duration::seconds(steps * step)
+    };
+
+    let mut gaps = Vec::new();
+    let mut cursor = from;
+    for taken in busy {
+        if taken.start_time > cursor {
+            gaps.push((cursor, taken.start_time.min(to)));
+        }
+        cursor = cursor.max(taken.end_time);
+    }
+    gaps.push((cursor, to));
+
+    gaps.into_iter()
+        .filter_map(|(start, end)| {
+            let (start_time, end_time) = (snap(start, true), snap(end, false));
+            (end_time - start_time >= min_length).then_some(TimeSlot {
+                start_time,
+                end_time,
+            })
+        })
+        .collect()
+}
+
+/// Detalle del 409 cuando el veterinario ya tiene una cita en el horario.
+/// De la cita en conflicto solo se expone el horario, no el motivo ni el cliente.
+#[derive(Debug, Serialize, TS)]
+pub struct AppointmentConflictDetails {
+    pub conflicting: TimeSlot,
+    pub next_available: Option<TimeSlot>, // Mismo día y misma duración; null si no hay
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(role: UserRole) -> Claims {
+        Claims {
+            sub: 3,
+            role,
+            iat: 0,
+            exp: usize::MAX,
+            api_key_id: None,
+            impersonator: None,
+        }
+    }
+
+    fn appointment(status: AppointmentStatus, start_time: DateTime<Utc>) -> Appointment {
+        Appointment {
+            id: 1,
+            patient_id: Some(1),
+            client_id: Some(1),
+            veterinarian_id: 3,
+            start_time,
+            end_time: start_time + Duration::minutes(30),
+            status,
+            reason: "Consulta general".into(),
+            is_emergency: false,
+            procedure_id: None,
+            room_id: None,
+            booking_source: BookingSource::Staff,
+            internal_notes: None,
+            created_at: start_time,
+            updated_at: start_time,
+            canceled_at: None,
+            cancellation_reason: None,
+        }
+    }
+
+    fn past_appointment() -> Appointment {
+        appointment(AppointmentStatus::Completed, Utc::now() - Duration::days(7))
+    }
+
+    fn reschedule() -> UpdateAppointment {
+        UpdateAppointment {
+            start_time: Some(Utc::now() + Duration::days(1)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn past_appointment_cannot_be_moved_without_the_flag() {
+        for role in [UserRole::Veterinarian, UserRole::Assistant, UserRole::Admin] {
+            let result = check_past_edit(
+                &past_appointment(),
+                &reschedule(),
+                false,
+                &claims(role),
+                Utc::now(),
+            );
+            assert!(matches!(result, Err(ApiError::Conflict(_))));
+        }
+    }
+
+    #[test]
+    fn only_an_admin_may_move_a_past_appointment() {
+        for role in [UserRole::Veterinarian, UserRole::Assistant] {
+            let result = check_past_edit(
+                &past_appointment(),
+                &reschedule(),
+                true,
+                &claims(role),
+                Utc::now(),
+            );
+            assert!(matches!(result, Err(ApiError::Forbidden(_))));
+        }
+        let result = check_past_edit(
+            &past_appointment(),
+            &reschedule(),
+            true,
+            &claims(UserRole::Admin),
+            Utc::now(),
+        );
+        assert!(result.unwrap());
+    }
+
+    #[test]
+    fn past_appointment_status_and_reason_stay_editable() {
+        let update = UpdateAppointment {
+            status: Some(AppointmentStatus::Completed),
+            reason: Some("Consulta de seguimiento".into()),
+            ..Default::default()
+        };
+        let result = check_past_edit(
+            &past_appointment(),
+            &update,
+            false,
+            &claims(UserRole::Assistant),
+            Utc::now(),
+        );
+        assert!(!result.unwrap());
+    }
+
+    #[test]
+    fn only_scheduled_appointments_change_status() {
+        use AppointmentStatus::*;
+        let all = [Scheduled, Completed, Canceled, NoShow];
+        for to in &all {
+            assert!(is_valid_status_transition(&Scheduled, to));
+        }
+        for from in [Completed, Canceled, NoShow] {
+            for to in &all {
+                let same = std::mem::discriminant(&from) == std::mem::discriminant(to);
+                assert_eq!(is_valid_status_transition(&from, to), same);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_transition_is_a_conflict() {
+        let existing = appointment(AppointmentStatus::Canceled, Utc::now());
+        let update = UpdateAppointment {
+            status: Some(AppointmentStatus::Completed),
+            ..Default::default()
+        };
+        assert!(matches!(
+            check_appointment_update(&existing, &update),
+            Err(ApiError::Conflict(_))
+        ));
+    }
+
+    #[test]
+    fn completed_appointment_cannot_be_moved() {
+        let existing = appointment(AppointmentStatus::Completed, Utc::now());
+        assert!(matches!(
+            check_appointment_update(&existing, &reschedule()),
+            Err(ApiError::Conflict(_))
+        ));
+    }
+
+    #[test]
+    fn cancellation_reason_requires_canceling() {
+        let existing = appointment(AppointmentStatus::Scheduled, Utc::now());
+        let update = UpdateAppointment {
+            status: Some(AppointmentStatus::Completed),
+            cancellation_reason: Some("El dueño no pudo asistir".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            check_appointment_update(&existing, &update),
+            Err(ApiError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn partial_update_can_shrink_the_window() {
+        let existing = appointment(AppointmentStatus::Scheduled, Utc::now());
+        let update = UpdateAppointment {
+            end_time: Some(existing.start_time + Duration::minutes(15)),
+            ..Default::default()
+        };
+        assert!(check_appointment_update(&existing, &update).is_ok());
+        assert_eq!(
+            effective_time_range(&existing, &update).unwrap(),
+            (
+                existing.start_time,
+                existing.start_time + Duration::minutes(15)
+            )
+        );
+
+        // Por debajo de la duración mínima ya no es válida
+        let update = UpdateAppointment {
+            start_time: Some(existing.end_time - Duration::minutes(2)),
+            ..Default::default()
+        };
+        assert!(matches!(
+            effective_time_range(&existing, &update),
+            Err(ApiError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn partial_update_cannot_invert_the_window() {
+        let existing = appointment(AppointmentStatus::Scheduled, Utc::now());
+        for update in [
+            UpdateAppointment {
+                end_time: Some(existing.start_time - Duration::minutes(30)),
+                ..Default::default()
+            },
+            UpdateAppointment {
+                start_time: Some(existing.end_time + Duration::minutes(30)),
+                ..Default::default()
+            },
+        ] {
+            assert!(check_appointment_update(&existing, &update).is_ok());
+            let Err(ApiError::ValidationError(message)) = effective_time_range(&existing, &update)
+            else {
+                panic!("se esperaba 400");
+            };
+            assert_eq!(
+                message,
+                validate_time_range(existing.end_time, existing.start_time)
+                    .unwrap_err()
+                    .to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn public_response_never_carries_internal_fields() {
+        let now = Utc::now();
+        let response = AppointmentResponse {
+            id: 1,
+            patient_id: Some(1),
+            patient_name: Some("Firulais".into()),
+            client_id: Some(1),
+            client_name: Some("Ana Pérez".into()),
+            veterinarian_id: 3,
+            veterinarian_name: "Dra. López".into(),
+            start_time: now,
+            end_time: now + Duration::minutes(30),
+            start_time_local: String::new(),
+            end_time_local: String::new(),
+            timezone: "America/Mexico_City",
+            status: AppointmentStatus::Scheduled,
+            reason: "Consulta general".into(),
+            is_emergency: false,
+            procedure_id: None,
+            procedure_name: None,
+            room_id: Some(2),
+            room_name: Some("Quirófano".into()),
+            booking_source: BookingSource::Staff,
+            internal_notes: Some("Dueño conflictivo".into()),
+            created_at: now,
+            updated_at: now,
+            canceled_at: None,
+            cancellation_reason: None,
+            duration_minutes: 30,
+            latest_notes: Vec::new(),
+        };
+        let json = serde_json::to_value(AppointmentPublicResponse::from(response)).unwrap();
+        let mut keys: Vec<&str> = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        assert_eq!(
+            keys,
+            [
+                "end_time",
+                "id",
+                "patient_name",
+                "reason",
+                "start_time",
+                "status",
+                "veterinarian_name"
+            ]
+        );
+        assert!(!json.to_string().contains("Dueño conflictivo"));
+    }
+}
@@ -0,0 +1,92 @@
+use crate::errors::ApiError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use ts_rs::TS;
+use validator::Validate;
+
+/// Notas que se incluyen en `AppointmentResponse`
+pub const LATEST_NOTES: i64 = 3;
+
+/// Estructura para agregar una nota a la cita (el autor se toma del token)
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct NewAppointmentNote {
+    #[validate(length(min = 1, max = 1000))]
+    pub content: String,
+}
+
+/// Filtros para listar las notas de una cita
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
+pub struct AppointmentNoteFilter {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Estructura de respuesta para API
+#[derive(Debug, FromRow, Serialize, Deserialize, TS)]
+pub struct AppointmentNoteResponse {
+    pub id: i32,
+    pub appointment_id: i32,
+    pub author_id: i32,
+    pub author_name: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AppointmentNoteResponse {
+    /// Obtiene una nota de la cita con el nombre del autor
+    pub async fn fetch(pool: &PgPool, appointment_id: i32, id: i32) -> Result<Self, ApiError> {
+        sqlx::query_as!(
+            AppointmentNoteResponse,
+            r#"
+            SELECT
+                n.id,
+                n.appointment_id,
+                n.author_id,
+                u.name as author_name,
+                n.content,
+                n.created_at
+            FROM appointment_notes n
+            JOIN users u ON u.id = n.author_id
+            WHERE n.id = $1 AND n.appointment_id = $2
+            "#,
+            id,
+            appointment_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or(ApiError::NotFound("La nota no existe".into()))
+    }
+
+    /// Notas de una cita, de la más reciente a la más antigua
+    pub async fn for_appointment(
+        pool: &PgPool,
+        appointment_id: i32,
+        filters: &AppointmentNoteFilter,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            AppointmentNoteResponse,
+            r#"
+            SELECT
+                n.id,
+                n.appointment_id,
+                n.author_id,
+                u.name as author_name,
+                n.content,
+                n.created_at
+            FROM appointment_notes n
+            JOIN users u ON u.id = n.author_id
+            WHERE n.appointment_id = $1
+            ORDER BY n.created_at DESC, n.id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            appointment_id,
+            filters.limit.unwrap_or(50).min(400),
+            filters.offset.unwrap_or(0)
+        )
+        .fetch_all(pool)
+        .await
+    }
+}
@@ -1,6 +1,7 @@
 use crate::models::enums::AnimalSpecies;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use ts_rs::TS;
 use validator::Validate;
 
 /// Estructura para razas de animales
@@ -12,21 +13,38 @@ pub struct Breed {
 }
 
 /// Estructura para crear nueva raza
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
 pub struct NewBreed {
     pub species: AnimalSpecies,
     #[validate(length(min = 3, max = 50))]
     pub name: String,
 }
 
+/// Parámetros de paginación
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct PaginationParams {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
 /// Estructura para respuesta API
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct BreedResponse {
     pub id: i32,
     pub species: AnimalSpecies,
     pub name: String,
 }
 
+/// Respuesta de `PUT /breeds/upsert`
+#[derive(Debug, Serialize, TS)]
+pub struct BreedUpsertResponse {
+    #[serde(flatten)]
+    pub breed: BreedResponse,
+    pub created: bool, // false si la raza ya existía
+}
+
 impl From<Breed> for BreedResponse {
     fn from(breed: Breed) -> Self {
         Self {
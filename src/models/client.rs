@@ -1,5 +1,12 @@
+use crate::models::client_contact::ClientContactResponse;
+use crate::models::custom_field::CustomFields;
+use crate::models::household::HouseholdMember;
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+use ts_rs::TS;
 use validator::Validate;
 
 /// Estructura completa del cliente (dueño de mascotas)
@@ -12,10 +19,14 @@ pub struct Client {
     pub address: Option<String>,
     pub notes: Option<String>,
     pub assigned_to: Option<i32>, // ID del usuario asignado (veterinario/asistente)
+    pub marketing_opt_out: bool,
+    pub custom_fields: Value,
+    pub household_id: Option<i32>,
 }
 
 /// Estructura para crear un nuevo cliente
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
 pub struct NewClient {
     #[validate(length(min = 3, max = 100))]
     pub name: String,
@@ -28,10 +39,17 @@ pub struct NewClient {
     #[validate(length(max = 1000))]
     pub notes: Option<String>,
     pub assigned_to: Option<i32>, // Validado en la DB via trigger
+    #[serde(default)]
+    pub marketing_opt_out: bool, // No desea mensajes promocionales (cumpleaños, campañas)
+    #[serde(default)]
+    #[ts(type = "Record<string, string | number | boolean | null>")]
+    pub custom_fields: CustomFields, // Validados contra custom_field_definitions
+    pub household_id: Option<i32>, // Ver `/households`
 }
 
 /// Estructura para actualizar cliente
-#[derive(Debug, Serialize, Deserialize, Validate, Default)]
+#[derive(Debug, Serialize, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
 pub struct UpdateClient {
     #[validate(length(min = 3, max = 100))]
     pub name: Option<String>,
@@ -43,17 +61,33 @@ pub struct UpdateClient {
     pub address: Option<String>, // Puede ser Some(null) para borrar
     #[validate(length(max = 1000))]
     pub notes: Option<String>, // Puede ser Some(null) para borrar
-    pub assigned_to: Option<Option<i32>>, // Some(None) para desasignar
+    /// Omitir para conservar el valor, `null` para desasignar
+    #[ts(optional, type = "number | null | undefined")]
+    pub assigned_to: Option<Option<i32>>,
+    pub marketing_opt_out: Option<bool>,
+    /// Si se envía, reemplaza todos los campos personalizados
+    #[ts(optional, type = "Record<string, string | number | boolean | null>")]
+    pub custom_fields: Option<CustomFields>,
+    /// Omitir para conservar el valor, `null` para salir del hogar
+    #[serde(default, deserialize_with = "crate::models::nullable")]
+    #[ts(optional, type = "number | null | undefined")]
+    pub household_id: Option<Option<i32>>,
 }
 
 /// Estructura de respuesta simplificada para el cliente
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct ClientResponse {
     pub id: i32,
     pub name: String,
     pub email: Option<String>,
     pub phone: String,
     pub assigned_to: Option<i32>,
+    pub marketing_opt_out: bool,
+    pub primary_contact: Option<ClientContactResponse>, // Ver `/clients/{id}/contacts`
+    #[ts(type = "Record<string, string | number | boolean>")]
+    pub custom_fields: Value,
+    pub household_id: Option<i32>,
+    pub household_members: Vec<HouseholdMember>, // Sin incluir al propio cliente
 }
 
 impl From<Client> for ClientResponse {
@@ -64,16 +98,107 @@ impl From<Client> for ClientResponse {
             email: client.email,
             phone: client.phone,
             assigned_to: client.assigned_to,
+            marketing_opt_out: client.marketing_opt_out,
+            primary_contact: None, // Se llenará después si es necesario
+            custom_fields: client.custom_fields,
+            household_id: client.household_id,
+            household_members: Vec::new(), // Se llenará después si es necesario
         }
     }
 }
 
+impl ClientResponse {
+    /// Respuestas con el contacto principal y los demás integrantes del hogar,
+    /// cargados con una consulta por tipo para todos los clientes
+    pub async fn load(pool: &PgPool, clients: Vec<Client>) -> Result<Vec<Self>, sqlx::Error> {
+        let ids: Vec<i32> = clients.iter().map(|client| client.id).collect();
+        let mut primaries = ClientContactResponse::primary_for(pool, &ids).await?;
+        let mut members = HouseholdMember::others_for(pool, &ids).await?;
+
+        Ok(clients
+            .into_iter()
+            .map(|client| {
+                let primary_contact = primaries.remove(&client.id);
+                let household_members = members.remove(&client.id).unwrap_or_default();
+                ClientResponse {
+                    primary_contact,
+                    household_members,
+                    ..client.into()
+                }
+            })
+            .collect())
+    }
+}
+
 /// Estructura para búsqueda/filtrado de clientes
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
 pub struct ClientFilter {
     pub name: Option<String>,
     pub phone: Option<String>,
     pub assigned_to: Option<i32>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    // Además acepta `custom.<key>=valor` por cada campo personalizado
+}
+
+/// Formato del estado de cuenta
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFormat {
+    #[default]
+    Json,
+    Pdf,
+}
+
+/// Parámetros del estado de cuenta del cliente
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct StatementQuery {
+    pub start_date: NaiveDate, // Día local, inclusive
+    pub end_date: NaiveDate,   // Día local, inclusive
+    #[serde(default)]
+    pub format: StatementFormat,
+}
+
+/// Tipo de movimiento del estado de cuenta
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementEntryKind {
+    Appointment, // Consulta completada; no genera cargo propio
+    Procedure,   // Procedimiento aplicado, al precio de catálogo
+}
+
+/// Movimiento del estado de cuenta
+#[derive(Debug, Serialize, TS)]
+pub struct StatementEntry {
+    pub date: NaiveDate, // Fecha local
+    pub kind: StatementEntryKind,
+    pub patient_id: i32,
+    pub patient_name: String,
+    pub description: String,
+    pub price: Option<BigDecimal>, // `null` en consultas y procedimientos sin precio
+}
+
+/// Subtotal de una mascota en el periodo
+#[derive(Debug, Serialize, TS)]
+pub struct StatementPetSubtotal {
+    pub patient_id: i32,
+    pub patient_name: String,
+    pub subtotal: BigDecimal,
+    pub unpriced_procedures: i64, // Procedimientos sin precio, fuera del subtotal
+}
+
+/// Estado de cuenta del cliente: consultas completadas y procedimientos
+/// aplicados a sus mascotas en el periodo, en orden cronológico
+#[derive(Debug, Serialize, TS)]
+pub struct ClientStatement {
+    pub client_id: i32,
+    pub client_name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub entries: Vec<StatementEntry>,
+    pub pets: Vec<StatementPetSubtotal>, // Solo mascotas con movimientos
+    pub total: BigDecimal,
+    pub unpriced_procedures: i64,
 }
@@ -0,0 +1,113 @@
+use crate::errors::ApiError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use ts_rs::TS;
+use validator::Validate;
+
+/// Estructura para agregar una persona de contacto al cliente.
+/// El primer contacto del cliente siempre queda como principal.
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct NewClientContact {
+    #[validate(length(min = 2, max = 100))]
+    pub name: String,
+    #[validate(length(max = 50))]
+    pub relationship: Option<String>, // "Pareja", "Hijo", "Cuidador", etc.
+    #[validate(length(min = 10, max = 20))]
+    pub phone: Option<String>,
+    #[validate(email, length(max = 255))]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub is_primary: bool, // Si es `true` reemplaza al principal actual
+}
+
+/// Estructura para actualizar un contacto
+#[derive(Debug, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
+pub struct UpdateClientContact {
+    #[validate(length(min = 2, max = 100))]
+    pub name: Option<String>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[ts(optional, type = "string | null | undefined")]
+    pub relationship: Option<Option<String>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[ts(optional, type = "string | null | undefined")]
+    pub phone: Option<Option<String>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[ts(optional, type = "string | null | undefined")]
+    pub email: Option<Option<String>>,
+    pub is_primary: Option<bool>, // Solo se acepta `true`; para cambiarlo se marca otro
+}
+
+/// Estructura de respuesta para API
+#[derive(Debug, FromRow, Serialize, TS)]
+pub struct ClientContactResponse {
+    pub id: i32,
+    pub client_id: i32,
+    pub name: String,
+    pub relationship: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub is_primary: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ClientContactResponse {
+    /// Obtiene un contacto del cliente
+    pub async fn fetch(pool: &PgPool, client_id: i32, id: i32) -> Result<Self, ApiError> {
+        sqlx::query_as!(
+            ClientContactResponse,
+            r#"
+            SELECT id, client_id, name, relationship, phone, email, is_primary, created_at
+            FROM client_contacts
+            WHERE id = $1 AND client_id = $2
+            "#,
+            id,
+            client_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or(ApiError::NotFound("El contacto no existe".into()))
+    }
+
+    /// Contactos del cliente, primero el principal
+    pub async fn for_client(pool: &PgPool, client_id: i32) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            ClientContactResponse,
+            r#"
+            SELECT id, client_id, name, relationship, phone, email, is_primary, created_at
+            FROM client_contacts
+            WHERE client_id = $1
+            ORDER BY is_primary DESC, id
+            "#,
+            client_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Contacto principal de cada cliente, indexado por `client_id`
+    pub async fn primary_for(
+        pool: &PgPool,
+        client_ids: &[i32],
+    ) -> Result<HashMap<i32, Self>, sqlx::Error> {
+        let contacts = sqlx::query_as!(
+            ClientContactResponse,
+            r#"
+            SELECT id, client_id, name, relationship, phone, email, is_primary, created_at
+            FROM client_contacts
+            WHERE client_id = ANY($1) AND is_primary
+            "#,
+            client_ids
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(contacts
+            .into_iter()
+            .map(|contact| (contact.client_id, contact))
+            .collect())
+    }
+}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use ts_rs::TS;
+use validator::Validate;
+
+/// Tipos de documento firmado que se aceptan, con la firma de sus primeros bytes
+pub const CONSENT_CONTENT_TYPES: &[(&str, &[u8])] = &[
+    ("application/pdf", b"%PDF"),
+    ("image/png", b"\x89PNG"),
+    ("image/jpeg", b"\xFF\xD8\xFF"),
+];
+
+/// Tamaño máximo del documento firmado (5 MB)
+pub const CONSENT_MAX_BYTES: usize = 5 * 1024 * 1024;
+
+/// Aceptación digital escrita del consentimiento
+#[derive(Debug, Deserialize, Validate, TS)]
+pub struct NewDigitalConsent {
+    #[validate(length(min = 2, max = 100))]
+    pub client_name: String, // Nombre completo escrito por quien acepta
+}
+
+/// Parámetros al subir el documento firmado escaneado
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields)]
+pub struct ConsentDocumentQuery {
+    #[validate(length(min = 2, max = 100))]
+    pub client_name: Option<String>, // Quien firmó, si se quiere registrar
+}
+
+/// Estructura de respuesta para API; el documento se descarga aparte
+#[derive(Debug, FromRow, Serialize, TS)]
+pub struct ConsentResponse {
+    pub id: i32,
+    pub patient_procedure_id: i32,
+    pub kind: String, // "digital" o "document"
+    pub client_name: Option<String>,
+    pub accepted_at: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub content_type: Option<String>, // Solo documentos
+    pub document_size: Option<i32>,   // Bytes; solo documentos
+    pub recorded_by: Option<i32>,     // Usuario que lo registró
+}
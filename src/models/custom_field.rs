@@ -0,0 +1,189 @@
+use crate::errors::ApiError;
+use crate::models::enums::{CustomFieldEntity, CustomFieldType};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use ts_rs::TS;
+use validator::Validate;
+
+/// Valores de los campos personalizados de un paciente o cliente
+pub type CustomFields = Map<String, Value>;
+
+/// Prefijo de los filtros por campo personalizado (`custom.<key>=valor`)
+const FILTER_PREFIX: &str = "custom.";
+
+/// Estructura para definir un campo personalizado
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct NewCustomFieldDefinition {
+    pub entity_type: CustomFieldEntity,
+    #[validate(length(min = 1, max = 50))]
+    pub key: String, // Minúsculas, números y guion bajo (ej: "insurance_number")
+    #[validate(length(min = 1, max = 100))]
+    pub label: String,
+    pub value_type: CustomFieldType,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Estructura para actualizar un campo personalizado.
+///
+/// La entidad, la clave y el tipo no se pueden cambiar para no invalidar los
+/// valores ya guardados.
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct UpdateCustomFieldDefinition {
+    #[validate(length(min = 1, max = 100))]
+    pub label: Option<String>,
+    pub required: Option<bool>,
+}
+
+/// Filtros para listar campos personalizados
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct CustomFieldDefinitionFilter {
+    pub entity_type: Option<CustomFieldEntity>,
+}
+
+/// Estructura de respuesta para API
+#[derive(Debug, FromRow, Serialize, TS)]
+pub struct CustomFieldDefinitionResponse {
+    pub id: i32,
+    pub entity_type: CustomFieldEntity,
+    pub key: String,
+    pub label: String,
+    pub value_type: CustomFieldType,
+    pub required: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Campos definidos para una entidad
+async fn definitions(
+    pool: &PgPool,
+    entity_type: CustomFieldEntity,
+) -> Result<Vec<CustomFieldDefinitionResponse>, ApiError> {
+    let definitions = sqlx::query_as!(
+        CustomFieldDefinitionResponse,
+        r#"
+        SELECT
+            id,
+            entity_type as "entity_type!: CustomFieldEntity",
+            key,
+            label,
+            value_type as "value_type!: CustomFieldType",
+            required,
+            created_at
+        FROM custom_field_definitions
+        WHERE entity_type = $1
+        ORDER BY key
+        "#,
+        entity_type as CustomFieldEntity
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(definitions)
+}
+
+/// Verifica que el valor corresponda al tipo del campo
+fn matches_type(value: &Value, value_type: CustomFieldType) -> bool {
+    match value_type {
+        CustomFieldType::Text => value.is_string(),
+        CustomFieldType::Number => value.is_number(),
+        CustomFieldType::Bool => value.is_boolean(),
+        CustomFieldType::Date => value
+            .as_str()
+            .is_some_and(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok()),
+    }
+}
+
+/// Valida los campos personalizados contra sus definiciones.
+///
+/// Las claves desconocidas, los tipos incorrectos y los campos obligatorios
+/// faltantes responden 422 con el nombre del campo. Los valores `null` se
+/// descartan.
+pub async fn validate_custom_fields(
+    pool: &PgPool,
+    entity_type: CustomFieldEntity,
+    fields: CustomFields,
+) -> Result<Value, ApiError> {
+    let definitions = definitions(pool, entity_type).await?;
+
+    let fields: CustomFields = fields
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .collect();
+
+    for (key, value) in &fields {
+        let definition = definitions
+            .iter()
+            .find(|definition| &definition.key == key)
+            .ok_or_else(|| {
+                ApiError::UnprocessableEntity(format!("Campo personalizado desconocido: {key}"))
+            })?;
+
+        if !matches_type(value, definition.value_type) {
+            let expected = match definition.value_type {
+                CustomFieldType::Text => "texto",
+                CustomFieldType::Number => "un número",
+                CustomFieldType::Date => "una fecha (AAAA-MM-DD)",
+                CustomFieldType::Bool => "verdadero o falso",
+            };
+            return Err(ApiError::UnprocessableEntity(format!(
+                "El campo personalizado {key} debe ser {expected}"
+            )));
+        }
+    }
+
+    if let Some(missing) = definitions
+        .iter()
+        .find(|definition| definition.required && !fields.contains_key(&definition.key))
+    {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "El campo personalizado {} es obligatorio",
+            missing.key
+        )));
+    }
+
+    Ok(Value::Object(fields))
+}
+
+/// Extrae los filtros `custom.<key>=valor` del query string.
+///
+/// Devuelve un objeto JSON con los valores como texto, o `None` si no hay
+/// filtros. Una clave no definida responde 400.
+pub async fn custom_field_filters(
+    pool: &PgPool,
+    entity_type: CustomFieldEntity,
+    query_string: &str,
+) -> Result<Option<Value>, ApiError> {
+    let params = actix_web::web::Query::<HashMap<String, String>>::from_query(query_string)
+        .map_err(|e| ApiError::ValidationError(e.to_string()))?
+        .into_inner();
+
+    let filters: CustomFields = params
+        .into_iter()
+        .filter_map(|(name, value)| {
+            name.strip_prefix(FILTER_PREFIX)
+                .map(|key| (key.to_string(), Value::String(value)))
+        })
+        .collect();
+
+    if filters.is_empty() {
+        return Ok(None);
+    }
+
+    let definitions = definitions(pool, entity_type).await?;
+    if let Some(unknown) = filters
+        .keys()
+        .find(|key| !definitions.iter().any(|definition| &definition.key == *key))
+    {
+        return Err(ApiError::ValidationError(format!(
+            "Campo personalizado desconocido: {unknown}"
+        )));
+    }
+
+    Ok(Some(Value::Object(filters)))
+}
@@ -0,0 +1,33 @@
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Qué le pasa a las filas dependientes al eliminar el recurso
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletionEffect {
+    Block,   // Impide la eliminación (409)
+    Cascade, // Se eliminan junto con el recurso
+    SetNull, // Se conservan sin la referencia
+    Keep,    // Se conservan sin cambios (borrado lógico)
+}
+
+/// Filas de una tabla que referencian al recurso
+#[derive(Debug, Serialize, TS)]
+pub struct DependencyCount {
+    pub table: String,
+    pub column: String,
+    pub effect: DeletionEffect,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub condition: Option<String>, // Solo cuenta las filas que cumplen la condición
+    pub count: i64,
+}
+
+/// Resultado de `GET /{recurso}/{id}/delete-preview`
+#[derive(Debug, Serialize, TS)]
+pub struct DeletePreview {
+    pub resource: String,
+    pub id: i32,
+    pub can_delete: bool, // false si alguna dependencia `block` tiene filas
+    pub dependencies: Vec<DependencyCount>,
+}
@@ -1,7 +1,13 @@
-use serde::{Deserialize, Serialize};
-use sqlx::Type;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef};
+use sqlx::{Decode, Encode, Postgres, Type};
+use std::fmt;
+use ts_rs::TS;
 
-#[derive(Debug, Type, Serialize, Deserialize, Clone)]
+#[derive(Debug, Type, Serialize, Deserialize, Clone, PartialEq, Eq, TS)]
 #[sqlx(type_name = "user_role", rename_all = "lowercase")]
 pub enum UserRole {
     Veterinarian,
@@ -9,8 +15,14 @@ pub enum UserRole {
     Admin,
 }
 
-#[derive(Debug, Type, Serialize, Deserialize, Clone)]
-#[sqlx(type_name = "animal_species", rename_all = "lowercase")]
+/// Especie de un paciente. Las integradas tienen variante propia; las demás
+/// (ej: hurón) se dan de alta en el catálogo `species` sin cambiar el código
+/// y llegan como `Custom` con su código.
+///
+/// En JSON las integradas van con su nombre (`"Dog"`, sin distinguir
+/// mayúsculas al recibir) y las del catálogo con su código (`"ferret"`). En
+/// la base de datos siempre se guarda el código (`species.code`).
+#[derive(Clone, PartialEq, Eq, Hash, TS)]
 pub enum AnimalSpecies {
     Dog,
     Cat,
@@ -19,9 +31,142 @@ pub enum AnimalSpecies {
     Rodent,
     Rabbit,
     Other,
+    #[ts(untagged)]
+    Custom(String),
 }
 
-#[derive(Debug, Type, Serialize, Deserialize, Clone)]
+impl AnimalSpecies {
+    /// Especies integradas, sembradas en el catálogo y que no se eliminan
+    pub const BUILT_IN: [AnimalSpecies; 7] = [
+        AnimalSpecies::Dog,
+        AnimalSpecies::Cat,
+        AnimalSpecies::Bird,
+        AnimalSpecies::Reptile,
+        AnimalSpecies::Rodent,
+        AnimalSpecies::Rabbit,
+        AnimalSpecies::Other,
+    ];
+
+    /// Código en la base de datos (`species.code`)
+    pub fn code(&self) -> &str {
+        match self {
+            AnimalSpecies::Dog => "dog",
+            AnimalSpecies::Cat => "cat",
+            AnimalSpecies::Bird => "bird",
+            AnimalSpecies::Reptile => "reptile",
+            AnimalSpecies::Rodent => "rodent",
+            AnimalSpecies::Rabbit => "rabbit",
+            AnimalSpecies::Other => "other",
+            AnimalSpecies::Custom(code) => code,
+        }
+    }
+
+    /// Nombre en JSON: el de la variante integrada o el código
+    fn api_name(&self) -> &str {
+        match self {
+            AnimalSpecies::Dog => "Dog",
+            AnimalSpecies::Cat => "Cat",
+            AnimalSpecies::Bird => "Bird",
+            AnimalSpecies::Reptile => "Reptile",
+            AnimalSpecies::Rodent => "Rodent",
+            AnimalSpecies::Rabbit => "Rabbit",
+            AnimalSpecies::Other => "Other",
+            AnimalSpecies::Custom(code) => code,
+        }
+    }
+
+    /// Especie de un código guardado; los que no son integrados son `Custom`
+    pub fn from_code(code: &str) -> Self {
+        Self::BUILT_IN
+            .into_iter()
+            .find(|species| species.code() == code)
+            .unwrap_or_else(|| AnimalSpecies::Custom(code.to_string()))
+    }
+
+    pub fn is_built_in(&self) -> bool {
+        !matches!(self, AnimalSpecies::Custom(_))
+    }
+}
+
+/// Código válido para una especie del catálogo: minúsculas, dígitos y `_`,
+/// empieza con letra, de 2 a 30 caracteres (ej: `ferret`, `guinea_pig`)
+pub fn is_valid_species_code(code: &str) -> bool {
+    (2..=30).contains(&code.len())
+        && code.starts_with(|c: char| c.is_ascii_lowercase())
+        && code
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Igual que en JSON, para que los mensajes muestren `Dog` o `ferret`
+impl fmt::Debug for AnimalSpecies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.api_name())
+    }
+}
+
+impl Serialize for AnimalSpecies {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.api_name())
+    }
+}
+
+impl<'de> Deserialize<'de> for AnimalSpecies {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let value = value.trim();
+        if let Some(species) = Self::BUILT_IN
+            .into_iter()
+            .find(|species| species.api_name().eq_ignore_ascii_case(value))
+        {
+            return Ok(species);
+        }
+
+        let code = value.to_ascii_lowercase();
+        if !is_valid_species_code(&code) {
+            return Err(de::Error::custom(format!(
+                "especie inválida `{value}`: usa una integrada (Dog, Cat, ...) o el código de \
+                 una especie del catálogo"
+            )));
+        }
+        Ok(AnimalSpecies::Custom(code))
+    }
+}
+
+/// Se guarda como texto con la llave foránea a `species`
+impl Type<Postgres> for AnimalSpecies {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl PgHasArrayType for AnimalSpecies {
+    fn array_type_info() -> PgTypeInfo {
+        <String as PgHasArrayType>::array_type_info()
+    }
+
+    fn array_compatible(ty: &PgTypeInfo) -> bool {
+        <String as PgHasArrayType>::array_compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for AnimalSpecies {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&str as Encode<Postgres>>::encode(self.code(), buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for AnimalSpecies {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(Self::from_code(<&str as Decode<Postgres>>::decode(value)?))
+    }
+}
+
+#[derive(Debug, Type, Serialize, Deserialize, Clone, TS)]
 #[sqlx(type_name = "animal_gender", rename_all = "lowercase")]
 pub enum AnimalGender {
     Male,
@@ -29,7 +174,7 @@ pub enum AnimalGender {
     Unknown,
 }
 
-#[derive(Debug, Type, Serialize, Deserialize, Clone)]
+#[derive(Debug, Type, Serialize, Deserialize, Clone, TS)]
 #[sqlx(type_name = "appointment_status", rename_all = "snake_case")]
 pub enum AppointmentStatus {
     Scheduled,
@@ -38,7 +183,7 @@ pub enum AppointmentStatus {
     NoShow,
 }
 
-#[derive(Debug, Type, Serialize, Deserialize, Clone)]
+#[derive(Debug, Type, Serialize, Deserialize, Clone, TS)]
 #[sqlx(type_name = "procedure_type", rename_all = "lowercase")]
 pub enum ProcedureType {
     Vaccine,
@@ -48,3 +193,63 @@ pub enum ProcedureType {
     Grooming,
     Other,
 }
+
+#[derive(Debug, Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[sqlx(type_name = "task_status", rename_all = "lowercase")]
+pub enum TaskStatus {
+    Pending,
+    Done,
+    Skipped,
+}
+
+#[derive(Debug, Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[sqlx(type_name = "custom_field_entity", rename_all = "lowercase")]
+pub enum CustomFieldEntity {
+    Patient,
+    Client,
+}
+
+#[derive(Debug, Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[sqlx(type_name = "custom_field_type", rename_all = "lowercase")]
+pub enum CustomFieldType {
+    Text,
+    Number,
+    Date,
+    Bool,
+}
+
+#[derive(Debug, Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[sqlx(type_name = "referral_direction", rename_all = "lowercase")]
+pub enum ReferralDirection {
+    Received,
+    Sent,
+}
+
+/// Origen de una cita; se deduce de las credenciales, no del payload,
+/// salvo las visitas sin cita
+#[derive(Debug, Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[sqlx(type_name = "booking_source", rename_all = "lowercase")]
+pub enum BookingSource {
+    Staff,  // JWT de un usuario de la clínica
+    Portal, // Token del portal de clientes
+    Api,    // Llave de cuenta de servicio o API_KEY del sistema
+    Import, // Cargas masivas
+    #[sqlx(rename = "walk_in")]
+    WalkIn, // Visita sin cita registrada después (`walk_in`)
+}
+
+/// Medio por el que se envía una notificación
+#[derive(Debug, Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[sqlx(type_name = "notification_channel", rename_all = "lowercase")]
+pub enum NotificationChannel {
+    Email,
+}
+
+/// Resultado de un intento de notificación
+#[derive(Debug, Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[sqlx(type_name = "notification_outcome", rename_all = "lowercase")]
+pub enum NotificationOutcome {
+    Pending, // Envío en curso
+    Sent,
+    Failed,
+}
@@ -0,0 +1,26 @@
+use crate::models::enums::AppointmentStatus;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Filtros del flujo de eventos de citas
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct AppointmentEventFilter {
+    pub veterinarian_id: Option<i32>, // Solo las citas de este veterinario
+}
+
+/// Cambio de una cita enviado por `GET /events/appointments`
+#[derive(Debug, Serialize, TS)]
+pub struct AppointmentStreamEvent {
+    #[serde(rename = "type")]
+    #[ts(
+        rename = "type",
+        type = "\"AppointmentCreated\" | \"AppointmentUpdated\" | \"AppointmentRescheduled\" | \"AppointmentCanceled\""
+    )]
+    pub event_type: &'static str,
+    pub appointment_id: i32,
+    pub veterinarian_id: i32,
+    pub status: AppointmentStatus,
+    pub start_time: DateTime<Utc>,
+}
@@ -0,0 +1,159 @@
+use crate::errors::ApiError;
+use crate::models::enums::AnimalSpecies;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use ts_rs::TS;
+use validator::{Validate, ValidationError};
+
+/// Estructura para internar a un paciente
+#[derive(Debug, Deserialize, Validate, TS)]
+#[validate(schema(function = "validate_admission_times"))]
+#[ts(optional_fields = nullable)]
+pub struct NewHospitalization {
+    #[validate(range(min = 1))]
+    pub veterinarian_id: Option<i32>,
+    pub admitted_at: Option<DateTime<Utc>>, // Default: ahora
+    pub expected_discharge_at: Option<DateTime<Utc>>,
+    #[validate(length(min = 1, max = 50))]
+    pub kennel: Option<String>, // Ej: "Jaula 3"
+    #[validate(length(min = 3, max = 2000))]
+    pub reason: String,
+}
+
+/// Estructura para actualizar una hospitalización activa
+#[derive(Debug, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
+pub struct UpdateHospitalization {
+    /// Omitir para conservar el valor, `null` para quitar el veterinario
+    #[validate(range(min = 1))]
+    #[ts(optional, type = "number | null | undefined")]
+    pub veterinarian_id: Option<Option<i32>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[ts(optional, type = "string | null | undefined")]
+    pub expected_discharge_at: Option<Option<DateTime<Utc>>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(length(min = 1, max = 50))]
+    #[ts(optional, type = "string | null | undefined")]
+    pub kennel: Option<Option<String>>,
+    #[validate(length(min = 3, max = 2000))]
+    pub reason: Option<String>,
+}
+
+/// Estructura para dar de alta a un paciente
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct DischargeHospitalization {
+    pub discharged_at: Option<DateTime<Utc>>, // Default: ahora
+    #[validate(length(max = 4000))]
+    pub summary: Option<String>,
+}
+
+/// Estructura de respuesta para API
+#[derive(Debug, FromRow, Serialize, TS)]
+pub struct HospitalizationResponse {
+    pub id: i32,
+    pub patient_id: i32,
+    pub patient_name: String,
+    pub veterinarian_id: Option<i32>,
+    pub veterinarian_name: Option<String>,
+    pub admitted_at: DateTime<Utc>,
+    pub expected_discharge_at: Option<DateTime<Utc>>,
+    pub discharged_at: Option<DateTime<Utc>>, // None mientras sigue internado
+    pub kennel: Option<String>,
+    pub reason: String,
+    pub discharge_summary: Option<String>,
+}
+
+impl HospitalizationResponse {
+    /// Obtiene una hospitalización con los nombres del paciente y del veterinario
+    pub async fn fetch(pool: &PgPool, id: i32) -> Result<Self, ApiError> {
+        sqlx::query_as!(
+            HospitalizationResponse,
+            r#"
+            SELECT
+                h.id,
+                h.patient_id,
+                p.name as patient_name,
+                h.veterinarian_id,
+                u.name as "veterinarian_name?",
+                h.admitted_at,
+                h.expected_discharge_at,
+                h.discharged_at,
+                h.kennel,
+                h.reason,
+                h.discharge_summary
+            FROM hospitalizations h
+            JOIN patients p ON p.id = h.patient_id
+            LEFT JOIN users u ON u.id = h.veterinarian_id
+            WHERE h.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or(ApiError::NotFound("La hospitalización no existe".into()))
+    }
+
+    /// Episodios de hospitalización de un paciente, del más reciente al más antiguo
+    pub async fn for_patient(pool: &PgPool, patient_id: i32) -> Result<Vec<Self>, ApiError> {
+        let hospitalizations = sqlx::query_as!(
+            HospitalizationResponse,
+            r#"
+            SELECT
+                h.id,
+                h.patient_id,
+                p.name as patient_name,
+                h.veterinarian_id,
+                u.name as "veterinarian_name?",
+                h.admitted_at,
+                h.expected_discharge_at,
+                h.discharged_at,
+                h.kennel,
+                h.reason,
+                h.discharge_summary
+            FROM hospitalizations h
+            JOIN patients p ON p.id = h.patient_id
+            LEFT JOIN users u ON u.id = h.veterinarian_id
+            WHERE h.patient_id = $1
+            ORDER BY h.admitted_at DESC
+            "#,
+            patient_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(hospitalizations)
+    }
+}
+
+/// Paciente internado actualmente, con los datos de contacto del dueño
+#[derive(Debug, FromRow, Serialize, TS)]
+pub struct ActiveHospitalization {
+    pub id: i32,
+    pub patient_id: i32,
+    pub patient_name: String,
+    pub species: AnimalSpecies,
+    pub client_id: Option<i32>,
+    pub client_name: Option<String>,
+    pub client_phone: Option<String>,
+    pub veterinarian_id: Option<i32>,
+    pub veterinarian_name: Option<String>,
+    pub kennel: Option<String>,
+    pub admitted_at: DateTime<Utc>,
+    pub expected_discharge_at: Option<DateTime<Utc>>,
+    pub reason: String,
+}
+
+/// Valida que el alta esperada sea posterior al ingreso
+fn validate_admission_times(admission: &NewHospitalization) -> Result<(), ValidationError> {
+    let admitted_at = admission.admitted_at.unwrap_or_else(Utc::now);
+    if let Some(expected) = admission.expected_discharge_at
+        && expected <= admitted_at
+    {
+        return Err(ValidationError::new(
+            "El alta esperada debe ser posterior al ingreso",
+        ));
+    }
+    Ok(())
+}
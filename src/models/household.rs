@@ -0,0 +1,115 @@
+use crate::models::enums::AnimalSpecies;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use ts_rs::TS;
+use validator::Validate;
+
+/// Estructura para crear un hogar con clientes existentes
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct NewHousehold {
+    #[validate(length(min = 2, max = 100))]
+    pub name: Option<String>, // Ej: "Familia Pérez"
+    #[validate(length(min = 1, max = 20))]
+    pub client_ids: Vec<i32>, // Ninguno puede pertenecer ya a otro hogar
+}
+
+/// Integrante de un hogar
+#[derive(Debug, Clone, Serialize, TS)]
+pub struct HouseholdMember {
+    pub id: i32, // ID del cliente
+    pub name: String,
+    pub email: Option<String>,
+}
+
+/// Mascota de alguno de los integrantes del hogar
+#[derive(Debug, Serialize, TS)]
+pub struct HouseholdPatient {
+    pub id: i32,
+    pub name: String,
+    pub species: AnimalSpecies,
+    pub client_id: i32,
+}
+
+/// Hogar con sus integrantes y todas sus mascotas
+#[derive(Debug, Serialize, TS)]
+pub struct HouseholdResponse {
+    pub id: i32,
+    pub name: Option<String>,
+    pub members: Vec<HouseholdMember>,
+    pub patients: Vec<HouseholdPatient>,
+}
+
+impl HouseholdMember {
+    /// Los demás integrantes del hogar de cada cliente, indexados por `client_id`.
+    /// Los clientes sin hogar no aparecen en el mapa.
+    pub async fn others_for(
+        pool: &PgPool,
+        client_ids: &[i32],
+    ) -> Result<HashMap<i32, Vec<Self>>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT c.id as client_id, m.id, m.name, m.email
+            FROM clients c
+            JOIN clients m ON m.household_id = c.household_id AND m.id <> c.id
+            WHERE c.id = ANY($1)
+            ORDER BY m.name, m.id
+            "#,
+            client_ids
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut members: HashMap<i32, Vec<Self>> = HashMap::new();
+        for row in rows {
+            members.entry(row.client_id).or_default().push(Self {
+                id: row.id,
+                name: row.name,
+                email: row.email,
+            });
+        }
+        Ok(members)
+    }
+}
+
+impl HouseholdResponse {
+    /// Carga el hogar con sus integrantes y mascotas; `None` si no existe
+    pub async fn fetch(pool: &PgPool, id: i32) -> Result<Option<Self>, sqlx::Error> {
+        let Some(name) = sqlx::query_scalar!("SELECT name FROM households WHERE id = $1", id)
+            .fetch_optional(pool)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let members = sqlx::query_as!(
+            HouseholdMember,
+            "SELECT id, name, email FROM clients WHERE household_id = $1 ORDER BY name, id",
+            id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let patients = sqlx::query_as!(
+            HouseholdPatient,
+            r#"
+            SELECT p.id, p.name, p.species as "species!: AnimalSpecies", p.client_id as "client_id!"
+            FROM patients p
+            JOIN clients c ON c.id = p.client_id
+            WHERE c.household_id = $1
+            ORDER BY p.name, p.id
+            "#,
+            id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(Some(Self {
+            id,
+            name,
+            members,
+            patients,
+        }))
+    }
+}
@@ -0,0 +1,23 @@
+use crate::models::appointment::{AppointmentResponse, NewAppointment};
+use crate::models::client::{ClientResponse, NewClient};
+use crate::models::patient::{NewPatient, PatientResponse};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Alta rápida de un cliente nuevo con su mascota y su primera cita
+#[derive(Debug, Deserialize, TS)]
+pub struct NewIntake {
+    pub client: NewClient,
+    #[ts(type = "Omit<NewPatient, \"client_id\">")]
+    pub patient: NewPatient, // `client_id` se toma del cliente creado
+    #[ts(type = "Omit<NewAppointment, \"patient_id\" | \"client_id\">")]
+    pub appointment: NewAppointment, // `patient_id` y `client_id` se toman de lo creado
+}
+
+/// Los tres recursos creados por el alta rápida
+#[derive(Debug, Serialize, TS)]
+pub struct IntakeResponse {
+    pub client: ClientResponse,
+    pub patient: PatientResponse,
+    pub appointment: AppointmentResponse,
+}
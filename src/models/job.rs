@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Estado de un trabajo en segundo plano
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, TS)]
+pub enum JobState {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Progreso de un trabajo en segundo plano
+#[derive(Debug, Serialize, Clone, TS)]
+pub struct JobStatus {
+    pub id: i32,
+    pub kind: String, // Ej: "demo_dataset"
+    pub state: JobState,
+    pub processed: i64,
+    pub total: i64,
+    pub message: Option<String>, // Resumen al terminar o causa del fallo
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Cita en la pantalla de la sala de espera.
+///
+/// Vista reducida para mostrarse en público: nunca incluye teléfonos, motivo
+/// de la cita ni nombres completos.
+#[derive(Debug, Serialize, TS)]
+pub struct LobbyEntry {
+    pub owner_first_name: String,
+    pub pet_initial: String,
+    pub veterinarian_name: String,
+    pub status: String, // "waiting" o "in_progress"
+    pub expected_time: DateTime<Utc>,
+}
+
+/// Perfil público de la clínica para el widget del sitio web.
+///
+/// Se sirve sin credenciales: solo datos de la clínica y conteos, nunca
+/// nombres de clientes, pacientes ni veterinarios.
+#[derive(Debug, Clone, Serialize, TS)]
+pub struct ClinicProfile {
+    pub name: Option<String>,
+    pub address: Option<String>,
+    pub phone: Option<String>,
+    pub timezone: String,
+    pub date: NaiveDate,              // Fecha local de la clínica
+    pub opening_time: Option<String>, // "HH:MM" en hora local; `None` si hoy cierra
+    pub closing_time: Option<String>, // "HH:MM" en hora local; `None` si hoy cierra
+    pub open_now: bool,
+    pub available_veterinarians: i64, // Veterinarios activos con algún hueco libre hoy
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Cola del día para la pantalla de la sala de espera
+#[derive(Debug, Serialize, TS)]
+pub struct LobbyFeed {
+    pub date: NaiveDate,
+    pub generated_at: DateTime<Utc>,
+    pub entries: Vec<LobbyEntry>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn keys(value: &Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .expect("objeto JSON")
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn lobby_feed_has_no_phone_or_reason() {
+        let feed = LobbyFeed {
+            date: NaiveDate::from_ymd_opt(2024, 6, 3).unwrap(),
+            generated_at: Utc::now(),
+            entries: vec![LobbyEntry {
+                owner_first_name: "Ana".into(),
+                pet_initial: "F.".into(),
+                veterinarian_name: "Dra. López".into(),
+                status: "waiting".into(),
+                expected_time: Utc::now(),
+            }],
+        };
+        let json = serde_json::to_value(&feed).unwrap();
+        assert_eq!(keys(&json), ["date", "entries", "generated_at"]);
+        assert_eq!(
+            keys(&json["entries"][0]),
+            [
+                "expected_time",
+                "owner_first_name",
+                "pet_initial",
+                "status",
+                "veterinarian_name"
+            ]
+        );
+        let text = json.to_string();
+        assert!(!text.contains("phone") && !text.contains("reason"));
+    }
+
+    #[test]
+    fn clinic_profile_only_exposes_allowed_fields() {
+        let profile = ClinicProfile {
+            name: Some("Clínica Veterinaria".into()),
+            address: Some("Av. Siempre Viva 742".into()),
+            phone: Some("5551234567".into()),
+            timezone: "America/Mexico_City".into(),
+            date: NaiveDate::from_ymd_opt(2024, 6, 3).unwrap(),
+            opening_time: Some("08:00".into()),
+            closing_time: Some("20:00".into()),
+            open_now: true,
+            available_veterinarians: 2,
+            generated_at: Utc::now(),
+        };
+        let json = serde_json::to_value(&profile).unwrap();
+        assert_eq!(
+            keys(&json),
+            [
+                "address",
+                "available_veterinarians",
+                "closing_time",
+                "date",
+                "generated_at",
+                "name",
+                "open_now",
+                "opening_time",
+                "phone",
+                "timezone"
+            ]
+        );
+    }
+}
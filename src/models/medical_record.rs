@@ -1,7 +1,10 @@
+use crate::models::enums::AnimalSpecies;
+use crate::vitals;
 use bigdecimal::ToPrimitive;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, types::BigDecimal};
+use ts_rs::TS;
 use validator::Validate;
 
 #[derive(Debug, FromRow)]
@@ -14,6 +17,10 @@ pub struct MedicalRecordRaw {
     pub treatment: Option<String>,
     pub notes: Option<String>,
     pub weight_at_visit: Option<BigDecimal>,
+    pub temperature_c: Option<BigDecimal>,
+    pub heart_rate_bpm: Option<i32>,
+    pub respiratory_rate_rpm: Option<i32>,
+    pub folio: Option<i64>,
 }
 
 impl From<MedicalRecordRaw> for MedicalRecord {
@@ -27,6 +34,10 @@ impl From<MedicalRecordRaw> for MedicalRecord {
             treatment: raw.treatment,
             notes: raw.notes,
             weight_at_visit: raw.weight_at_visit.and_then(|f| f.to_f64()),
+            temperature_c: raw.temperature_c.and_then(|t| t.to_f64()),
+            heart_rate_bpm: raw.heart_rate_bpm,
+            respiratory_rate_rpm: raw.respiratory_rate_rpm,
+            folio: raw.folio,
         }
     }
 }
@@ -42,27 +53,40 @@ pub struct MedicalRecord {
     pub treatment: Option<String>,
     pub notes: Option<String>,
     pub weight_at_visit: Option<f64>, // Decimal(5,2) en SQL
+    pub temperature_c: Option<f64>,   // Decimal(3,1) en SQL
+    pub heart_rate_bpm: Option<i32>,
+    pub respiratory_rate_rpm: Option<i32>,
+    pub folio: Option<i64>,
 }
 
 /// Estructura para crear nuevo registro médico
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
 pub struct NewMedicalRecord {
     #[validate(range(min = 1))]
     pub patient_id: i32,
     #[validate(range(min = 1))]
-    pub veterinarian_id: i32,
+    pub veterinarian_id: Option<i32>, // Con un token de usuario, por defecto quien llama
+    pub template_id: Option<i32>, // Completa los campos de texto que no se envíen
     #[validate(length(min = 5, max = 2000))]
-    pub diagnosis: String,
+    pub diagnosis: Option<String>, // Obligatorio si la plantilla no lo define
     #[validate(length(max = 2000))]
     pub treatment: Option<String>,
     #[validate(length(max = 2000))]
     pub notes: Option<String>,
     #[validate(range(min = 0.01, max = 999.99))]
     pub weight_at_visit: Option<f64>,
+    #[validate(range(min = 20.0, max = 50.0))]
+    pub temperature_c: Option<f64>,
+    #[validate(range(min = 1, max = 1000))]
+    pub heart_rate_bpm: Option<i32>, // Latidos por minuto
+    #[validate(range(min = 1, max = 300))]
+    pub respiratory_rate_rpm: Option<i32>, // Respiraciones por minuto
 }
 
 /// Estructura para actualizar registro médico
-#[derive(Debug, Serialize, Deserialize, Validate, Default)]
+#[derive(Debug, Serialize, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
 pub struct UpdateMedicalRecord {
     #[validate(range(min = 1))]
     pub patient_id: Option<i32>,
@@ -70,16 +94,73 @@ pub struct UpdateMedicalRecord {
     pub veterinarian_id: Option<i32>,
     #[validate(length(min = 5, max = 2000))]
     pub diagnosis: Option<String>,
+    /// Omitir para conservar el valor, `null` para borrar
     #[validate(length(max = 2000))]
-    pub treatment: Option<Option<String>>, // Some(None) para borrar
+    #[ts(optional, type = "string | null | undefined")]
+    pub treatment: Option<Option<String>>,
+    /// Omitir para conservar el valor, `null` para borrar
     #[validate(length(max = 2000))]
-    pub notes: Option<Option<String>>, // Some(None) para borrar
+    #[ts(optional, type = "string | null | undefined")]
+    pub notes: Option<Option<String>>,
+    /// Omitir para conservar el valor, `null` para borrar
     #[validate(range(min = 0.01, max = 999.99))]
-    pub weight_at_visit: Option<Option<f64>>, // Some(None) para borrar
+    #[ts(optional, type = "number | null | undefined")]
+    pub weight_at_visit: Option<Option<f64>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(range(min = 20.0, max = 50.0))]
+    #[serde(default, deserialize_with = "crate::models::nullable")]
+    #[ts(optional, type = "number | null | undefined")]
+    pub temperature_c: Option<Option<f64>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(range(min = 1, max = 1000))]
+    #[serde(default, deserialize_with = "crate::models::nullable")]
+    #[ts(optional, type = "number | null | undefined")]
+    pub heart_rate_bpm: Option<Option<i32>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(range(min = 1, max = 300))]
+    #[serde(default, deserialize_with = "crate::models::nullable")]
+    #[ts(optional, type = "number | null | undefined")]
+    pub respiratory_rate_rpm: Option<Option<i32>>,
+}
+
+/// Signo vital con rango de referencia por especie
+#[derive(Debug, Serialize, Clone, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum VitalSign {
+    TemperatureC,
+    HeartRateBpm,
+    RespiratoryRateRpm,
+}
+
+/// Posición de una lectura respecto al rango normal de la especie
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum VitalFlag {
+    Low,
+    Normal,
+    High,
+}
+
+/// Signo vital registrado; sin rango conocido para la especie no lleva `flag`
+#[derive(Debug, Serialize, TS)]
+#[ts(optional_fields)]
+pub struct VitalReading {
+    pub value: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flag: Option<VitalFlag>,
+}
+
+/// Rango normal de un signo vital para una especie, extremos incluidos
+#[derive(Debug, Serialize, TS)]
+pub struct VitalRange {
+    pub species: AnimalSpecies,
+    pub sign: VitalSign,
+    pub min: f64,
+    pub max: f64,
 }
 
 /// Estructura de respuesta para API
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct MedicalRecordResponse {
     pub id: i32,
     pub patient_id: i32,
@@ -90,11 +171,20 @@ pub struct MedicalRecordResponse {
     pub treatment: Option<String>,
     pub notes: Option<String>,
     pub weight_at_visit: Option<f64>,
+    pub temperature_c: Option<VitalReading>,
+    pub heart_rate_bpm: Option<VitalReading>,
+    pub respiratory_rate_rpm: Option<VitalReading>,
+    pub folio: Option<i64>, // null en registros anteriores a los folios
 }
 
 impl MedicalRecordResponse {
-    /// Crea una respuesta a partir del registro médico y el nombre del veterinario
-    pub fn from_record_with_vet(record: MedicalRecord, vet_name: String) -> Self {
+    /// Crea una respuesta a partir del registro médico y el nombre del
+    /// veterinario; los signos vitales se marcan según la especie del paciente
+    pub fn from_record_with_vet(
+        record: MedicalRecord,
+        vet_name: String,
+        species: Option<&AnimalSpecies>,
+    ) -> Self {
         Self {
             id: record.id,
             patient_id: record.patient_id,
@@ -105,12 +195,97 @@ impl MedicalRecordResponse {
             treatment: record.treatment,
             notes: record.notes,
             weight_at_visit: record.weight_at_visit,
+            temperature_c: vitals::reading(species, VitalSign::TemperatureC, record.temperature_c),
+            heart_rate_bpm: vitals::reading(
+                species,
+                VitalSign::HeartRateBpm,
+                record.heart_rate_bpm.map(f64::from),
+            ),
+            respiratory_rate_rpm: vitals::reading(
+                species,
+                VitalSign::RespiratoryRateRpm,
+                record.respiratory_rate_rpm.map(f64::from),
+            ),
+            folio: record.folio,
         }
     }
+
+    /// Especie del paciente para marcar los signos vitales
+    pub async fn patient_species(
+        pool: &sqlx::PgPool,
+        patient_id: i32,
+    ) -> Result<Option<AnimalSpecies>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT species as "species: AnimalSpecies" FROM patients WHERE id = $1"#,
+            patient_id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+    /// Registros de un paciente del más reciente al más antiguo (`limit`
+    /// `None` = todos)
+    pub async fn for_patient(
+        pool: &sqlx::PgPool,
+        patient_id: i32,
+        limit: Option<i64>,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let species = Self::patient_species(pool, patient_id).await?;
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                mr.id,
+                mr.veterinarian_id as "veterinarian_id!: i32",
+                u.name as "veterinarian_name?",
+                mr.date as "date!: chrono::DateTime<chrono::Utc>",
+                mr.diagnosis,
+                mr.treatment,
+                mr.notes,
+                mr.weight_at_visit,
+                mr.temperature_c,
+                mr.heart_rate_bpm,
+                mr.respiratory_rate_rpm,
+                mr.folio
+            FROM medical_records mr
+            LEFT JOIN users u ON u.id = mr.veterinarian_id
+            WHERE mr.patient_id = $1
+            ORDER BY mr.date DESC
+            LIMIT $2
+            "#,
+            patient_id,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let record = MedicalRecord {
+                    id: row.id,
+                    patient_id,
+                    veterinarian_id: row.veterinarian_id,
+                    date: row.date,
+                    diagnosis: row.diagnosis,
+                    treatment: row.treatment,
+                    notes: row.notes,
+                    weight_at_visit: row.weight_at_visit.and_then(|w| w.to_f64()),
+                    temperature_c: row.temperature_c.and_then(|t| t.to_f64()),
+                    heart_rate_bpm: row.heart_rate_bpm,
+                    respiratory_rate_rpm: row.respiratory_rate_rpm,
+                    folio: row.folio,
+                };
+                let vet_name = row
+                    .veterinarian_name
+                    .unwrap_or_else(|| "Veterinario desconocido".to_string());
+                Self::from_record_with_vet(record, vet_name, species.as_ref())
+            })
+            .collect())
+    }
 }
 
 /// Filtros para búsqueda de registros médicos
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
 pub struct MedicalRecordFilter {
     pub patient_id: Option<i32>,
     pub veterinarian_id: Option<i32>,
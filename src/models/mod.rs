@@ -1,10 +1,44 @@
+pub mod admin;
+pub mod api_key;
 pub mod appointment;
+pub mod appointment_note;
 pub mod breed;
 pub mod client;
+pub mod client_contact;
+pub mod consent;
+pub mod custom_field;
+pub mod deletion;
 pub mod enums;
+pub mod event;
+pub mod hospitalization;
+pub mod household;
+pub mod intake;
+pub mod job;
+pub mod lobby;
 pub mod medical_record;
+pub mod page;
 pub mod patient;
 pub mod patient_procedure;
 pub mod procedure;
+pub mod record_template;
+pub mod referral;
+pub mod room;
+pub mod settings;
+pub mod species;
+pub mod species_protocol;
 pub mod statistic;
+pub mod survey;
+pub mod sync;
+pub mod treatment_task;
 pub mod user;
+pub mod warning;
+
+/// Deserializa campos `Option<Option<T>>` distinguiendo `null` (`Some(None)`)
+/// de un campo ausente (`None`, vía `#[serde(default)]`)
+pub fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    serde::Deserialize::deserialize(deserializer).map(Some)
+}
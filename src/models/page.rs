@@ -0,0 +1,12 @@
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Página de un listado con el total de filas que cumplen los filtros, para
+/// que el cliente muestre "página 3 de 12"
+#[derive(Debug, Serialize, TS)]
+pub struct Page<T: TS> {
+    pub items: Vec<T>,
+    pub total: i64, // Filas que cumplen los filtros, sin `limit` ni `offset`
+    pub limit: i64, // Límite aplicado (ya acotado al máximo del listado)
+    pub offset: i64,
+}
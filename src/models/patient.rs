@@ -1,8 +1,17 @@
+use crate::errors::ApiError;
+use crate::models::appointment::AppointmentResponse;
+use crate::models::custom_field::CustomFields;
 use crate::models::enums::{AnimalGender, AnimalSpecies};
+use crate::models::hospitalization::HospitalizationResponse;
+use crate::models::medical_record::MedicalRecordResponse;
+use crate::models::patient_procedure::PatientProcedureResponse;
+use crate::models::referral::ReferralResponse;
 use bigdecimal::{BigDecimal, ToPrimitive};
-use chrono::NaiveDate;
+use chrono::{Datelike, Duration, NaiveDate};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use sqlx::FromRow;
+use ts_rs::TS;
 use validator::Validate;
 
 /// Estructura completa del paciente (mascota)
@@ -17,6 +26,11 @@ pub struct Patient {
     pub weight_kg: Option<f64>, // Decimal(5,2) en SQL se mapea a f64
     pub client_id: i32,
     pub photo_url: Option<String>,
+    pub deceased_on: Option<NaiveDate>,
+    pub custom_fields: Value,
+    pub insurance_provider: Option<String>,
+    pub insurance_policy_number: Option<String>,
+    pub insurance_expires_on: Option<NaiveDate>,
 }
 
 /// Estructura intermedia para manejar datos directamente desde la base de datos
@@ -31,6 +45,11 @@ pub struct PatientRaw {
     pub weight_kg: Option<BigDecimal>, // Usamos BigDecimal aquí
     pub client_id: i32,
     pub photo_url: Option<String>,
+    pub deceased_on: Option<NaiveDate>,
+    pub custom_fields: Value,
+    pub insurance_provider: Option<String>,
+    pub insurance_policy_number: Option<String>,
+    pub insurance_expires_on: Option<NaiveDate>,
 }
 
 impl From<PatientRaw> for Patient {
@@ -45,12 +64,18 @@ impl From<PatientRaw> for Patient {
             weight_kg: raw.weight_kg.and_then(|f| f.to_f64()), // Conversión explícita
             client_id: raw.client_id,
             photo_url: raw.photo_url,
+            deceased_on: raw.deceased_on,
+            custom_fields: raw.custom_fields,
+            insurance_provider: raw.insurance_provider,
+            insurance_policy_number: raw.insurance_policy_number,
+            insurance_expires_on: raw.insurance_expires_on,
         }
     }
 }
 
 /// Estructura para crear nuevo paciente
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
 pub struct NewPatient {
     #[validate(length(min = 2, max = 100))]
     pub name: String,
@@ -60,29 +85,61 @@ pub struct NewPatient {
     pub gender: Option<AnimalGender>,
     #[validate(range(min = 0.01, max = 999.99))]
     pub weight_kg: Option<f64>,
-    pub client_id: i32, // Validar existencia en DB
+    #[serde(default)]
+    pub client_id: i32, // Validar existencia en DB; se omite en `/intake`
     #[validate(url, length(max = 512))]
     pub photo_url: Option<String>,
+    #[serde(default)]
+    #[ts(type = "Record<string, string | number | boolean | null>")]
+    pub custom_fields: CustomFields, // Validados contra custom_field_definitions
+    #[validate(length(min = 2, max = 100))]
+    pub insurance_provider: Option<String>, // Aseguradora
+    #[validate(length(min = 1, max = 50))]
+    pub insurance_policy_number: Option<String>, // Requiere insurance_provider
+    pub insurance_expires_on: Option<NaiveDate>, // Requiere insurance_provider
 }
 
 /// Estructura para actualizar paciente
-#[derive(Debug, Serialize, Deserialize, Validate, Default)]
+#[derive(Debug, Serialize, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
 pub struct UpdatePatient {
     #[validate(length(min = 2, max = 100))]
     pub name: Option<String>,
     pub species: Option<AnimalSpecies>, // Si cambia, debe validarse con breed_id
-    pub breed_id: Option<Option<i32>>,  // Some(None) para quitar raza
-    pub birth_date: Option<NaiveDate>,  // Some(None) para borrar
-    pub gender: Option<AnimalGender>,   // Some(None) para borrar
+    /// Omitir para conservar el valor, `null` para quitar raza
+    #[ts(optional, type = "number | null | undefined")]
+    pub breed_id: Option<Option<i32>>,
+    pub birth_date: Option<NaiveDate>, // Some(None) para borrar
+    pub gender: Option<AnimalGender>,  // Some(None) para borrar
     #[validate(range(min = 0.01, max = 999.99))]
     pub weight_kg: Option<f64>, // Some(None) para borrar
     pub client_id: Option<i32>,
     #[validate(url, length(max = 512))]
     pub photo_url: Option<String>, // Some(None) para borrar
+    /// Fecha de fallecimiento. Omitir para conservar el valor, `null` para borrar
+    #[ts(optional, type = "string | null | undefined")]
+    pub deceased_on: Option<Option<NaiveDate>>,
+    /// Si se envía, reemplaza todos los campos personalizados
+    #[ts(optional, type = "Record<string, string | number | boolean | null>")]
+    pub custom_fields: Option<CustomFields>,
+    /// Aseguradora. Omitir para conservar el valor, `null` para quitar el seguro
+    #[validate(length(min = 2, max = 100))]
+    #[serde(default, deserialize_with = "crate::models::nullable")]
+    #[ts(optional, type = "string | null | undefined")]
+    pub insurance_provider: Option<Option<String>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(length(min = 1, max = 50))]
+    #[serde(default, deserialize_with = "crate::models::nullable")]
+    #[ts(optional, type = "string | null | undefined")]
+    pub insurance_policy_number: Option<Option<String>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[serde(default, deserialize_with = "crate::models::nullable")]
+    #[ts(optional, type = "string | null | undefined")]
+    pub insurance_expires_on: Option<Option<NaiveDate>>,
 }
 
 /// Estructura de respuesta para API
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct PatientResponse {
     pub id: i32,
     pub name: String,
@@ -94,6 +151,12 @@ pub struct PatientResponse {
     pub weight_kg: Option<f64>,
     pub client_id: i32,
     pub photo_url: Option<String>,
+    pub deceased_on: Option<NaiveDate>,
+    #[ts(type = "Record<string, string | number | boolean>")]
+    pub custom_fields: Value,
+    pub insurance_provider: Option<String>,
+    pub insurance_policy_number: Option<String>,
+    pub insurance_expires_on: Option<NaiveDate>,
 }
 
 impl From<Patient> for PatientResponse {
@@ -109,18 +172,270 @@ impl From<Patient> for PatientResponse {
             weight_kg: patient.weight_kg,
             client_id: patient.client_id,
             photo_url: patient.photo_url,
+            deceased_on: patient.deceased_on,
+            custom_fields: patient.custom_fields,
+            insurance_provider: patient.insurance_provider,
+            insurance_policy_number: patient.insurance_policy_number,
+            insurance_expires_on: patient.insurance_expires_on,
         }
     }
 }
 
 /// Filtros para búsqueda de pacientes
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
 pub struct PatientFilter {
     pub name: Option<String>,
     pub species: Option<AnimalSpecies>,
     pub breed_id: Option<i32>,
     pub client_id: Option<i32>,
     pub gender: Option<AnimalGender>,
+    pub insured: Option<bool>, // Con seguro vigente (aseguradora y sin vencer)
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    // Además acepta `custom.<key>=valor` por cada campo personalizado
+}
+
+/// Colecciones que se pueden incluir en el detalle del paciente
+const PATIENT_INCLUDES: [&str; 3] = ["appointments", "procedures", "records"];
+
+/// Elementos por colección incluida cuando no se indica `include_limit`
+const DEFAULT_INCLUDE_LIMIT: i64 = 10;
+
+/// Parámetros del detalle de paciente
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
+pub struct PatientQuery {
+    pub include: Option<String>, // appointments, procedures, records separados por comas
+    pub include_limit: Option<i64>, // Más recientes por colección (default: 10, máximo: 100)
+}
+
+impl PatientQuery {
+    /// Colecciones pedidas en `include`. Responde 400 si alguna no existe.
+    pub fn includes(&self) -> Result<Vec<&'static str>, ApiError> {
+        let Some(names) = &self.include else {
+            return Ok(Vec::new());
+        };
+
+        let mut includes = Vec::new();
+        for name in names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let include = PATIENT_INCLUDES
+                .into_iter()
+                .find(|include| *include == name)
+                .ok_or_else(|| {
+                    ApiError::ValidationError(format!(
+                        "include desconocido: {}. Disponibles: {}",
+                        name,
+                        PATIENT_INCLUDES.join(", ")
+                    ))
+                })?;
+            if !includes.contains(&include) {
+                includes.push(include);
+            }
+        }
+        Ok(includes)
+    }
+
+    pub fn include_limit(&self) -> i64 {
+        self.include_limit
+            .unwrap_or(DEFAULT_INCLUDE_LIMIT)
+            .clamp(1, 100)
+    }
+}
+
+/// Detalle del paciente con las colecciones pedidas en `include`.
+///
+/// Sin `include` se serializa igual que `PatientResponse`.
+#[derive(Debug, Serialize, TS)]
+pub struct PatientDetailResponse {
+    #[serde(flatten)]
+    pub patient: PatientResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub appointments: Option<Vec<AppointmentResponse>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub procedures: Option<Vec<PatientProcedureResponse>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub records: Option<Vec<MedicalRecordResponse>>,
+}
+
+/// Asignación de una misma raza a varios pacientes
+#[derive(Debug, Deserialize, Validate, TS)]
+pub struct BulkSetBreed {
+    #[validate(length(min = 1, max = 500))]
+    pub patient_ids: Vec<i32>,
+    #[validate(range(min = 1))]
+    pub breed_id: i32,
+}
+
+/// Resultado de la asignación masiva de raza
+#[derive(Debug, Serialize, TS)]
+pub struct BulkSetBreedResponse {
+    pub breed_id: i32,
+    pub count: i64,            // Pacientes actualizados
+    pub patient_ids: Vec<i32>, // Ordenados y sin repetidos
+}
+
+/// Parámetros de creación de paciente
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct CreatePatientQuery {
+    #[serde(default)]
+    pub force: bool, // Crear aunque existan posibles duplicados
+    #[serde(default)]
+    pub override_warnings: bool, // Aceptar un peso fuera del rango de la especie
+}
+
+/// Paciente existente con nombre igual o parecido para el mismo dueño
+#[derive(Debug, Serialize, TS)]
+pub struct DuplicatePatientCandidate {
+    pub id: i32,
+    pub name: String,
+    pub species: AnimalSpecies,
+    pub similarity: f64, // 1.0 = mismo nombre
+}
+
+/// Par de pacientes que probablemente son el mismo
+#[derive(Debug, Serialize, TS)]
+pub struct DuplicatePatientPair {
+    pub client_id: i32,
+    pub client_name: Option<String>,
+    pub patient_id: i32,
+    pub patient_name: String,
+    pub duplicate_id: i32,
+    pub duplicate_name: String,
+    pub similarity: f64,
+}
+
+/// Paginación del reporte de duplicados
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct DuplicatePatientFilter {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Ventana de días por defecto del listado de cumpleaños
+pub const DEFAULT_BIRTHDAY_DAYS: u32 = 14;
+
+/// Ventana máxima del listado de cumpleaños
+pub const MAX_BIRTHDAY_DAYS: u32 = 366;
+
+/// Ventana del listado de cumpleaños: un mes o los próximos días
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct BirthdayQuery {
+    pub month: Option<u32>,         // 1 a 12; el próximo mes con ese número
+    pub upcoming_days: Option<u32>, // Desde hoy (default: 14)
+}
+
+impl BirthdayQuery {
+    /// Rango de fechas (inclusivo) a partir de la fecha local `today`
+    pub fn window(&self, today: NaiveDate) -> Result<(NaiveDate, NaiveDate), ApiError> {
+        match (self.month, self.upcoming_days) {
+            (Some(_), Some(_)) => Err(ApiError::ValidationError(
+                "Usa month o upcoming_days, no ambos".into(),
+            )),
+            (Some(month), None) => {
+                let year = if month >= today.month() {
+                    today.year()
+                } else {
+                    today.year() + 1
+                };
+                let start = NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(|| {
+                    ApiError::ValidationError("month debe estar entre 1 y 12".into())
+                })?;
+                let next = if month == 12 {
+                    NaiveDate::from_ymd_opt(year + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(year, month + 1, 1)
+                };
+                Ok((start, next.expect("fecha válida") - Duration::days(1)))
+            }
+            (None, days) => {
+                let days = days.unwrap_or(DEFAULT_BIRTHDAY_DAYS);
+                if days > MAX_BIRTHDAY_DAYS {
+                    return Err(ApiError::ValidationError(format!(
+                        "upcoming_days no puede ser mayor a {MAX_BIRTHDAY_DAYS}"
+                    )));
+                }
+                Ok((today, today + Duration::days(days.into())))
+            }
+        }
+    }
+}
+
+/// Fecha en que se celebra el aniversario de `birth_date` en `year`.
+/// Los nacidos el 29 de febrero lo celebran el 28 en años no bisiestos.
+pub fn anniversary_in(birth_date: NaiveDate, year: i32) -> Option<NaiveDate> {
+    birth_date
+        .with_year(year)
+        .or_else(|| NaiveDate::from_ymd_opt(year, 2, 28))
+}
+
+/// Paciente que cumple años dentro de la ventana consultada
+#[derive(Debug, Serialize, TS)]
+pub struct PatientBirthday {
+    pub patient_id: i32,
+    pub patient_name: String,
+    pub species: AnimalSpecies,
+    pub birth_date: NaiveDate,
+    pub anniversary: NaiveDate, // Fecha de celebración dentro de la ventana
+    pub turning_age: i32,       // Años que cumple
+    pub client_id: i32,
+    pub owner_name: String,
+    pub owner_phone: String,
+    pub owner_email: Option<String>,
+    pub marketing_opt_out: bool, // El dueño no desea mensajes promocionales
+}
+
+/// Historial clínico completo de un paciente
+#[derive(Debug, Serialize, TS)]
+pub struct PatientHistoryResponse {
+    pub patient_id: i32,
+    pub medical_records: Vec<MedicalRecordResponse>,
+    pub procedures: Vec<PatientProcedureResponse>,
+    pub hospitalizations: Vec<HospitalizationResponse>,
+    pub referrals: Vec<ReferralResponse>,
+}
+
+/// Parámetros de la reclamación al seguro
+#[derive(Debug, Deserialize, TS)]
+pub struct InsuranceClaimQuery {
+    pub medical_record_id: i32, // Consulta que se reclama
+}
+
+/// Procedimiento aplicado el día de la consulta reclamada
+#[derive(Debug, Serialize, TS)]
+pub struct ClaimProcedure {
+    pub id: i32, // ID del procedimiento del paciente
+    pub name: String,
+    pub date: NaiveDate,
+    pub price: Option<f64>, // Precio de catálogo; `null` si no tiene
+}
+
+/// Reclamación lista para la aseguradora: póliza, consulta y procedimientos
+#[derive(Debug, Serialize, TS)]
+pub struct InsuranceClaim {
+    pub patient_id: i32,
+    pub patient_name: String,
+    pub species: AnimalSpecies,
+    pub owner_name: Option<String>,
+    pub insurance_provider: String,
+    pub insurance_policy_number: Option<String>,
+    pub insurance_expires_on: Option<NaiveDate>,
+    pub medical_record_id: i32,
+    pub folio: Option<i64>,
+    pub visit_date: NaiveDate, // Fecha local de la consulta
+    pub veterinarian_name: Option<String>,
+    pub diagnosis: String,
+    pub treatment: Option<String>,
+    pub procedures: Vec<ClaimProcedure>,
+    pub total: f64, // Suma de los precios conocidos
 }
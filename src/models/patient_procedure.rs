@@ -4,6 +4,8 @@ use sqlx::{FromRow, PgPool};
 use validator::{Validate, ValidationError};
 
 use crate::errors::ApiError;
+use crate::models::enums::AnimalSpecies;
+use ts_rs::TS;
 
 #[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
 pub struct PatientProcedure {
@@ -11,42 +13,74 @@ pub struct PatientProcedure {
     pub patient_id: i32,
     pub procedure_id: i32,
     pub veterinarian_id: Option<i32>,
+    pub performed_by: Option<i32>,
     pub date: NaiveDate,
     pub next_due_date: Option<NaiveDate>,
     pub notes: Option<String>,
+    pub batch_number: Option<String>,
+    pub manufacturer: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, TS)]
 #[validate(schema(function = "validate_date_pair"))]
+#[validate(schema(function = "validate_actor"))]
+#[ts(optional_fields = nullable)]
 pub struct NewPatientProcedure {
     #[validate(range(min = 1))]
     pub patient_id: i32,
     #[validate(range(min = 1))]
     pub procedure_id: i32,
     #[validate(range(min = 1))]
-    pub veterinarian_id: Option<i32>,
+    pub veterinarian_id: Option<i32>, // Veterinario responsable
+    #[validate(range(min = 1))]
+    pub performed_by: Option<i32>, // Cualquier usuario activo (ej: asistente)
     #[validate(custom(function = "validate_not_past_date"))]
     pub date: NaiveDate,
     #[validate(custom(function = "validate_next_due_date"))]
     pub next_due_date: Option<NaiveDate>,
     #[validate(length(max = 1000))]
     pub notes: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub batch_number: Option<String>, // Lote aplicado; obligatorio en vacunas con REQUIRE_VACCINE_BATCH
+    #[validate(length(min = 1, max = 100))]
+    pub manufacturer: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
 pub struct UpdatePatientProcedure {
     #[validate(range(min = 1))]
     pub patient_id: Option<i32>,
     #[validate(range(min = 1))]
     pub procedure_id: Option<i32>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(range(min = 1))]
+    #[ts(optional, type = "number | null | undefined")]
+    pub veterinarian_id: Option<Option<i32>>,
+    /// Omitir para conservar el valor, `null` para borrar
     #[validate(range(min = 1))]
-    pub veterinarian_id: Option<Option<i32>>, // Some(None) para borrar
+    #[ts(optional, type = "number | null | undefined")]
+    pub performed_by: Option<Option<i32>>,
     #[validate(custom(function = "validate_not_past_date"))]
     pub date: Option<NaiveDate>,
+    /// Omitir para conservar el valor, `null` para borrar
     #[validate(custom(function = "validate_next_due_date"))]
-    pub next_due_date: Option<Option<NaiveDate>>, // Some(None) para borrar
+    #[ts(optional, type = "string | null | undefined")]
+    pub next_due_date: Option<Option<NaiveDate>>,
+    /// Omitir para conservar el valor, `null` para borrar
     #[validate(length(max = 1000))]
-    pub notes: Option<Option<String>>, // Some(None) para borrar
+    #[ts(optional, type = "string | null | undefined")]
+    pub notes: Option<Option<String>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(length(min = 1, max = 50))]
+    #[serde(default, deserialize_with = "crate::models::nullable")]
+    #[ts(optional, type = "string | null | undefined")]
+    pub batch_number: Option<Option<String>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(length(min = 1, max = 100))]
+    #[serde(default, deserialize_with = "crate::models::nullable")]
+    #[ts(optional, type = "string | null | undefined")]
+    pub manufacturer: Option<Option<String>>,
 }
 
 /// Valida que la fecha no sea en el pasado
@@ -71,30 +105,186 @@ pub fn validate_next_due_date(next_due_date: &&NaiveDate) -> Result<(), Validati
 
 /// Valida el par date/next_due_date juntos
 pub fn validate_date_pair(procedure: &NewPatientProcedure) -> Result<(), ValidationError> {
-    if let Some(next_date) = procedure.next_due_date {
-        if next_date < procedure.date {
-            return Err(ValidationError::new(
-                "La fecha de próximo vencimiento debe ser posterior a la fecha del procedimiento",
-            ));
-        }
+    if let Some(next_date) = procedure.next_due_date
+        && next_date < procedure.date
+    {
+        return Err(ValidationError::new(
+            "La fecha de próximo vencimiento debe ser posterior a la fecha del procedimiento",
+        ));
+    }
+    Ok(())
+}
+
+/// Valida que se indique quién realizó o supervisó el procedimiento
+pub fn validate_actor(procedure: &NewPatientProcedure) -> Result<(), ValidationError> {
+    if procedure.veterinarian_id.is_none() && procedure.performed_by.is_none() {
+        return Err(ValidationError::new(
+            "Se requiere veterinarian_id o performed_by",
+        ));
+    }
+    Ok(())
+}
+
+/// Si las vacunas exigen número de lote (`REQUIRE_VACCINE_BATCH`, default: no)
+pub fn vaccine_batch_required() -> bool {
+    std::env::var("REQUIRE_VACCINE_BATCH").is_ok_and(|value| matches!(value.trim(), "1" | "true"))
+}
+
+/// Responde 422 si el procedimiento es una vacuna sin número de lote y la
+/// clínica lo exige
+pub async fn ensure_vaccine_batch(
+    pool: &PgPool,
+    procedure_id: i32,
+    batch_number: Option<&str>,
+) -> Result<(), ApiError> {
+    if !vaccine_batch_required() || batch_number.is_some_and(|batch| !batch.trim().is_empty()) {
+        return Ok(());
+    }
+
+    let is_vaccine: bool = sqlx::query_scalar!(
+        "SELECT EXISTS (SELECT 1 FROM procedures WHERE id = $1 AND type = 'vaccine')",
+        procedure_id
+    )
+    .fetch_one(pool)
+    .await?
+    .unwrap_or(false);
+
+    if is_vaccine {
+        return Err(ApiError::UnprocessableEntity(
+            "batch_number es obligatorio para las vacunas".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Si las cirugías exigen consentimiento firmado (`REQUIRE_SURGERY_CONSENT`, default: no)
+pub fn surgery_consent_required() -> bool {
+    std::env::var("REQUIRE_SURGERY_CONSENT").is_ok_and(|value| matches!(value.trim(), "1" | "true"))
+}
+
+/// Responde 422 si el procedimiento es una cirugía, la clínica exige
+/// consentimiento y no se envió `consent_waived=true`. Con él la cirugía se
+/// registra y el consentimiento debe adjuntarse dentro de 24 h; el reporte de
+/// integridad lista las que siguen sin él.
+pub async fn ensure_surgery_consent(
+    pool: &PgPool,
+    procedure_id: i32,
+    consent_waived: bool,
+) -> Result<(), ApiError> {
+    if !surgery_consent_required() || consent_waived {
+        return Ok(());
+    }
+
+    let is_surgery: bool = sqlx::query_scalar!(
+        "SELECT EXISTS (SELECT 1 FROM procedures WHERE id = $1 AND type = 'surgery')",
+        procedure_id
+    )
+    .fetch_one(pool)
+    .await?
+    .unwrap_or(false);
+
+    if is_surgery {
+        return Err(ApiError::UnprocessableEntity(
+            "Las cirugías requieren consentimiento firmado: envía consent_waived=true y adjúntalo dentro de 24 h en POST /patient_procedures/{id}/consent".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Verifica que quien realizó el procedimiento sea un usuario activo
+pub async fn ensure_active_performer(pool: &PgPool, user_id: i32) -> Result<(), ApiError> {
+    let active: bool = sqlx::query_scalar!(
+        "SELECT EXISTS (SELECT 1 FROM users WHERE id = $1 AND is_active = TRUE)",
+        user_id
+    )
+    .fetch_one(pool)
+    .await?
+    .unwrap_or(false);
+
+    if !active {
+        return Err(ApiError::UnprocessableEntity(
+            "El usuario indicado en performed_by no existe o está inactivo".into(),
+        ));
     }
     Ok(())
 }
 
+/// Reprogramación masiva de vencimientos (ej: retiro de un lote de vacunas)
+#[derive(Debug, Deserialize, Validate, TS)]
+#[validate(schema(function = "validate_bulk_reschedule"))]
+#[ts(optional_fields = nullable)]
+pub struct BulkReschedule {
+    #[validate(range(min = 1))]
+    pub procedure_id: i32,
+    pub administered_from: NaiveDate, // Aplicados entre estas fechas, inclusive
+    pub administered_to: NaiveDate,
+    #[validate(range(min = 1, max = 3650))]
+    pub interval_days: Option<i32>, // Nuevo vencimiento = fecha de aplicación + intervalo
+    #[validate(custom(function = "validate_next_due_date"))]
+    pub next_due_date: Option<NaiveDate>, // Mismo vencimiento para todos
+}
+
+fn validate_bulk_reschedule(reschedule: &BulkReschedule) -> Result<(), ValidationError> {
+    if reschedule.administered_to < reschedule.administered_from {
+        return Err(ValidationError::new(
+            "administered_to debe ser igual o posterior a administered_from",
+        ));
+    }
+    match (reschedule.interval_days, reschedule.next_due_date) {
+        (Some(_), None) => Ok(()),
+        (None, Some(next_due_date)) if next_due_date < reschedule.administered_to => Err(
+            ValidationError::new("next_due_date debe ser posterior a las aplicaciones del rango"),
+        ),
+        (None, Some(_)) => Ok(()),
+        _ => Err(ValidationError::new(
+            "Envía interval_days o next_due_date, solo uno",
+        )),
+    }
+}
+
+/// Parámetros de creación de un procedimiento aplicado
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct CreatePatientProcedureQuery {
+    #[serde(default)]
+    pub allow_duplicate: bool, // Registrar aunque ya exista el mismo ese día
+    #[serde(default)]
+    pub override_warnings: bool, // Aceptar un procedimiento poco habitual para la especie
+    #[serde(default)]
+    pub consent_waived: bool, // Registrar una cirugía sin consentimiento; se adjunta dentro de 24 h
+}
+
+/// Parámetros de la reprogramación masiva
+#[derive(Debug, Deserialize, TS)]
+pub struct BulkRescheduleQuery {
+    #[serde(default)]
+    pub dry_run: bool, // Solo reporta lo que se actualizaría
+}
+
+/// Resultado de la reprogramación masiva
+#[derive(Debug, Serialize, TS)]
+pub struct BulkRescheduleResponse {
+    pub dry_run: bool,
+    pub count: i64,            // Registros actualizados (o que se actualizarían)
+    pub patient_ids: Vec<i32>, // Pacientes afectados, para avisar a sus dueños
+}
+
 /// Filtros para búsqueda de procedimientos
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
 pub struct PatientProcedureFilter {
     pub patient_id: Option<i32>,       // Filtrar por ID del paciente
     pub procedure_id: Option<i32>,     // Filtrar por ID del procedimiento
     pub veterinarian_id: Option<i32>,  // Filtrar por ID del veterinario
     pub start_date: Option<NaiveDate>, // Filtrar por fecha mínima
     pub end_date: Option<NaiveDate>,   // Filtrar por fecha máxima
-    pub limit: Option<i64>,            // Máximo de resultados (default: 50)
-    pub offset: Option<i64>,           // Desplazamiento (default: 0)
+    pub batch_number: Option<String>, // Lote exacto, sin distinguir mayúsculas; agrega el contacto del dueño
+    pub limit: Option<i64>,           // Máximo de resultados (default: 50)
+    pub offset: Option<i64>,          // Desplazamiento (default: 0)
 }
 
 /// Estructura de respuesta para API
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct PatientProcedureResponse {
     pub id: i32,
     pub patient_id: i32,                   // ID del paciente
@@ -103,9 +293,56 @@ pub struct PatientProcedureResponse {
     pub procedure_name: String,            // Nombre del procedimiento
     pub veterinarian_id: Option<i32>,      // ID del veterinario (opcional)
     pub veterinarian_name: Option<String>, // Nombre del veterinario (opcional)
+    pub performed_by: Option<i32>,         // ID de quien lo realizó (opcional)
+    pub performed_by_name: Option<String>, // Nombre de quien lo realizó (opcional)
     pub date: NaiveDate,
     pub next_due_date: Option<NaiveDate>,
     pub notes: Option<String>,
+    pub batch_number: Option<String>,
+    pub manufacturer: Option<String>,
+}
+
+/// Procedimiento con el contacto del dueño del paciente, para avisar en un
+/// retiro de lote (`GET /patient_procedures?batch_number=...`)
+#[derive(Debug, Serialize, TS)]
+pub struct PatientProcedureWithOwner {
+    #[serde(flatten)]
+    pub procedure: PatientProcedureResponse,
+    pub client_id: Option<i32>,
+    pub client_name: Option<String>,
+    pub client_phone: Option<String>,
+    pub client_email: Option<String>,
+}
+
+/// Advertencia si el procedimiento no es habitual para la especie del paciente
+pub async fn species_warning(
+    pool: &PgPool,
+    patient_id: i32,
+    procedure_id: i32,
+) -> Result<Option<String>, ApiError> {
+    let mismatch = sqlx::query!(
+        r#"
+        SELECT
+            pr.name,
+            p.species as "species!: AnimalSpecies"
+        FROM procedures pr
+        JOIN patients p ON p.id = $1
+        WHERE pr.id = $2
+          AND cardinality(pr.species) > 0
+          AND NOT (p.species = ANY(pr.species))
+        "#,
+        patient_id,
+        procedure_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(mismatch.map(|row| {
+        format!(
+            "El procedimiento '{}' no es habitual para la especie {:?}",
+            row.name, row.species
+        )
+    }))
 }
 
 impl PatientProcedureResponse {
@@ -156,6 +393,15 @@ impl PatientProcedureResponse {
                 None
             };
 
+        // Obtener el nombre de quien lo realizó
+        let performed_by_name: Option<String> = if let Some(performed_by) = procedure.performed_by {
+            sqlx::query_scalar!("SELECT name FROM users WHERE id = $1", performed_by)
+                .fetch_optional(pool)
+                .await?
+        } else {
+            None
+        };
+
         Ok(Self {
             id: procedure.id,
             patient_id: procedure.patient_id,
@@ -164,9 +410,53 @@ impl PatientProcedureResponse {
             procedure_name,
             veterinarian_id: procedure.veterinarian_id,
             veterinarian_name,
+            performed_by: procedure.performed_by,
+            performed_by_name,
             date: procedure.date,
             next_due_date: procedure.next_due_date,
             notes: procedure.notes,
+            batch_number: procedure.batch_number,
+            manufacturer: procedure.manufacturer,
         })
     }
+    /// Procedimientos más recientes de un paciente con los nombres resueltos
+    /// en una sola consulta
+    pub async fn for_patient(
+        pool: &PgPool,
+        patient_id: i32,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            PatientProcedureResponse,
+            r#"
+            SELECT
+                pp.id,
+                pp.patient_id as "patient_id!",
+                p.name as patient_name,
+                pp.procedure_id as "procedure_id!",
+                pr.name as procedure_name,
+                pp.veterinarian_id,
+                v.name as "veterinarian_name?",
+                pp.performed_by,
+                pb.name as "performed_by_name?",
+                pp.date as "date!",
+                pp.next_due_date,
+                pp.notes,
+                pp.batch_number,
+                pp.manufacturer
+            FROM patient_procedures pp
+            JOIN patients p ON p.id = pp.patient_id
+            JOIN procedures pr ON pr.id = pp.procedure_id
+            LEFT JOIN users v ON v.id = pp.veterinarian_id
+            LEFT JOIN users pb ON pb.id = pp.performed_by
+            WHERE pp.patient_id = $1
+            ORDER BY pp.date DESC, pp.id DESC
+            LIMIT $2
+            "#,
+            patient_id,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
 }
@@ -1,6 +1,9 @@
-use crate::models::enums::ProcedureType;
+use crate::extractors::Locale;
+use crate::models::enums::{AnimalSpecies, ProcedureType};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use ts_rs::TS;
 use validator::Validate;
 
 /// Estructura completa para procedimientos médicos
@@ -12,10 +15,14 @@ pub struct Procedure {
     pub procedure_type: ProcedureType,
     pub description: Option<String>,
     pub duration_minutes: Option<i32>,
+    pub species: Vec<AnimalSpecies>, // Especies habituales (vacío = todas)
+    pub price: Option<f64>,
+    pub required_room_type: Option<String>, // Tipo de sala que ocupa la cita
 }
 
 /// Estructura para crear nuevo procedimiento
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
 pub struct NewProcedure {
     #[validate(length(min = 2, max = 100))]
     pub name: String,
@@ -24,62 +31,102 @@ pub struct NewProcedure {
     pub description: Option<String>,
     #[validate(range(min = 1, max = 1440))] // 1 minuto a 24 horas
     pub duration_minutes: Option<i32>,
+    #[serde(default)]
+    pub species: Vec<AnimalSpecies>, // Vacío = aplica a todas
+    #[validate(range(min = 0.0, max = 99999999.99))]
+    pub price: Option<f64>,
+    #[validate(length(min = 2, max = 50))]
+    pub required_room_type: Option<String>, // Ej: "surgery"; ver `/rooms`
 }
 
 /// Estructura para actualizar procedimiento
-#[derive(Debug, Serialize, Deserialize, Validate, Default)]
+#[derive(Debug, Serialize, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
 pub struct UpdateProcedure {
     #[validate(length(min = 2, max = 100))]
     pub name: Option<String>,
     pub procedure_type: Option<ProcedureType>,
+    /// Omitir para conservar el valor, `null` para borrar
     #[validate(length(max = 500))]
-    pub description: Option<Option<String>>, // Some(None) para borrar
+    #[ts(optional, type = "string | null | undefined")]
+    pub description: Option<Option<String>>,
+    /// Omitir para conservar el valor, `null` para borrar
     #[validate(range(min = 1, max = 1440))]
-    pub duration_minutes: Option<Option<i32>>, // Some(None) para borrar
+    #[ts(optional, type = "number | null | undefined")]
+    pub duration_minutes: Option<Option<i32>>,
+    pub species: Option<Vec<AnimalSpecies>>, // `[]` para aplicar a todas
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(range(min = 0.0, max = 99999999.99))]
+    #[ts(optional, type = "number | null | undefined")]
+    pub price: Option<Option<f64>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(length(min = 2, max = 50))]
+    #[ts(optional, type = "string | null | undefined")]
+    pub required_room_type: Option<Option<String>>,
 }
 
 /// Estructura de respuesta para API
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct ProcedureResponse {
     pub id: i32,
     pub name: String,
     pub procedure_type: ProcedureType,
     pub description: Option<String>,
     pub duration_minutes: Option<i32>,
-    pub duration_formatted: Option<String>, // Ej: "2 horas 30 minutos"
+    pub duration_formatted: Option<String>, // Ej: "2 horas 30 minutos", según Accept-Language
+    pub duration_iso8601: Option<String>,   // Ej: "PT2H30M"
+    pub species: Vec<AnimalSpecies>,
+    pub price: Option<f64>,
+    pub required_room_type: Option<String>,
 }
 
 impl ProcedureResponse {
-    /// Formatea la duración en minutos a texto legible
-    pub fn format_duration(minutes: Option<i32>) -> Option<String> {
-        minutes.map(|mins| {
-            let hours = mins / 60;
-            let remaining_minutes = mins % 60;
-
-            match (hours, remaining_minutes) {
-                (0, m) => format!("{m} minutos"),
-                (h, 0) => format!("{h} horas"),
-                (h, m) => format!("{h} horas {m} minutos"),
-            }
-        })
-    }
-}
-
-impl From<Procedure> for ProcedureResponse {
-    fn from(procedure: Procedure) -> Self {
+    /// Respuesta con la duración legible en el idioma indicado
+    pub fn localized(procedure: Procedure, locale: Locale) -> Self {
         Self {
             id: procedure.id,
             name: procedure.name,
             procedure_type: procedure.procedure_type,
             description: procedure.description,
             duration_minutes: procedure.duration_minutes,
-            duration_formatted: Self::format_duration(procedure.duration_minutes),
+            duration_formatted: Self::format_duration(procedure.duration_minutes, locale),
+            duration_iso8601: Self::duration_iso8601(procedure.duration_minutes),
+            species: procedure.species,
+            price: procedure.price,
+            required_room_type: procedure.required_room_type,
         }
     }
+
+    /// Formatea la duración en minutos a texto legible, con singular y plural
+    /// (ej: "1 hora 1 minuto", "2 hours 30 minutes")
+    pub fn format_duration(minutes: Option<i32>, locale: Locale) -> Option<String> {
+        let (hour, hours, minute, minutes_word) = match locale {
+            Locale::Es => ("hora", "horas", "minuto", "minutos"),
+            Locale::En => ("hour", "hours", "minute", "minutes"),
+        };
+        let unit =
+            |n: i32, one: &str, many: &str| format!("{n} {}", if n == 1 { one } else { many });
+
+        minutes.map(|mins| match (mins / 60, mins % 60) {
+            (0, m) => unit(m, minute, minutes_word),
+            (h, 0) => unit(h, hour, hours),
+            (h, m) => format!("{} {}", unit(h, hour, hours), unit(m, minute, minutes_word)),
+        })
+    }
+
+    /// Duración ISO 8601 para consumidores automáticos (ej: "PT1H30M")
+    pub fn duration_iso8601(minutes: Option<i32>) -> Option<String> {
+        minutes.map(|mins| match (mins / 60, mins % 60) {
+            (0, m) => format!("PT{m}M"),
+            (h, 0) => format!("PT{h}H"),
+            (h, m) => format!("PT{h}H{m}M"),
+        })
+    }
 }
 
 /// Filtros para búsqueda de procedimientos
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
 pub struct ProcedureFilter {
     pub name_contains: Option<String>,
     pub procedure_type: Option<ProcedureType>,
@@ -88,3 +135,130 @@ pub struct ProcedureFilter {
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
+
+/// Versión actual del documento de catálogo
+pub const CATALOG_VERSION: i32 = 1;
+
+/// Procedimiento dentro de un catálogo exportado (sin ids de la base de datos)
+#[derive(Debug, Serialize, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct CatalogProcedure {
+    #[validate(length(min = 2, max = 100))]
+    pub name: String,
+    pub procedure_type: ProcedureType,
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+    #[validate(range(min = 1, max = 1440))]
+    pub duration_minutes: Option<i32>,
+    #[serde(default)]
+    pub species: Vec<AnimalSpecies>,
+    #[validate(range(min = 0.0, max = 99999999.99))]
+    pub price: Option<f64>,
+}
+
+impl From<Procedure> for CatalogProcedure {
+    fn from(procedure: Procedure) -> Self {
+        Self {
+            name: procedure.name,
+            procedure_type: procedure.procedure_type,
+            description: procedure.description,
+            duration_minutes: procedure.duration_minutes,
+            species: procedure.species,
+            price: procedure.price,
+        }
+    }
+}
+
+/// Documento versionado con el catálogo de procedimientos
+#[derive(Debug, Serialize, Deserialize, Validate, TS)]
+pub struct ProcedureCatalog {
+    pub version: i32,
+    pub exported_at: DateTime<Utc>,
+    #[validate(nested)]
+    pub procedures: Vec<CatalogProcedure>,
+}
+
+/// Qué hacer cuando un procedimiento importado ya existe (mismo nombre)
+#[derive(Debug, Clone, Copy, Default, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    #[default]
+    SkipExisting,
+    UpdateExisting,
+}
+
+/// Parámetros de la importación
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct ProcedureImportQuery {
+    pub strategy: Option<MergeStrategy>, // Default: skip_existing
+}
+
+/// Resultado de importar un procedimiento
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportAction {
+    Created,
+    Updated,
+    Skipped,
+}
+
+/// Resultado de un elemento del catálogo importado
+#[derive(Debug, Serialize, TS)]
+pub struct ProcedureImportItem {
+    pub name: String,
+    pub action: ImportAction,
+    pub procedure_id: i32, // Procedimiento creado, actualizado o existente
+}
+
+/// Resumen de la importación
+#[derive(Debug, Serialize, TS)]
+pub struct ProcedureImportResponse {
+    pub created: i64,
+    pub updated: i64,
+    pub skipped: i64,
+    pub items: Vec<ProcedureImportItem>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatted(minutes: i32, locale: Locale) -> String {
+        ProcedureResponse::format_duration(Some(minutes), locale).unwrap()
+    }
+
+    #[test]
+    fn format_duration_pluralizes_in_spanish() {
+        assert_eq!(formatted(0, Locale::Es), "0 minutos");
+        assert_eq!(formatted(1, Locale::Es), "1 minuto");
+        assert_eq!(formatted(60, Locale::Es), "1 hora");
+        assert_eq!(formatted(61, Locale::Es), "1 hora 1 minuto");
+        assert_eq!(formatted(150, Locale::Es), "2 horas 30 minutos");
+        assert_eq!(formatted(1440, Locale::Es), "24 horas");
+    }
+
+    #[test]
+    fn format_duration_pluralizes_in_english() {
+        assert_eq!(formatted(0, Locale::En), "0 minutes");
+        assert_eq!(formatted(60, Locale::En), "1 hour");
+        assert_eq!(formatted(61, Locale::En), "1 hour 1 minute");
+        assert_eq!(formatted(1440, Locale::En), "24 hours");
+    }
+
+    #[test]
+    fn format_duration_without_duration() {
+        assert_eq!(ProcedureResponse::format_duration(None, Locale::Es), None);
+        assert_eq!(ProcedureResponse::duration_iso8601(None), None);
+    }
+
+    #[test]
+    fn duration_iso8601_matches_the_human_string() {
+        let iso = |minutes| ProcedureResponse::duration_iso8601(Some(minutes)).unwrap();
+        assert_eq!(iso(0), "PT0M");
+        assert_eq!(iso(60), "PT1H");
+        assert_eq!(iso(61), "PT1H1M");
+        assert_eq!(iso(90), "PT1H30M");
+        assert_eq!(iso(1440), "PT24H");
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use ts_rs::TS;
+use validator::Validate;
+
+/// Estructura para crear una plantilla de registro médico
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct NewRecordTemplate {
+    #[validate(length(min = 2, max = 100))]
+    pub name: String,
+    #[validate(length(min = 5, max = 2000))]
+    pub diagnosis: Option<String>,
+    #[validate(length(max = 2000))]
+    pub treatment: Option<String>,
+    #[validate(length(max = 2000))]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub is_shared: bool, // false = solo la ve quien la creó
+}
+
+/// Estructura para actualizar una plantilla
+#[derive(Debug, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
+pub struct UpdateRecordTemplate {
+    #[validate(length(min = 2, max = 100))]
+    pub name: Option<String>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(length(min = 5, max = 2000))]
+    #[ts(optional, type = "string | null | undefined")]
+    pub diagnosis: Option<Option<String>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(length(max = 2000))]
+    #[ts(optional, type = "string | null | undefined")]
+    pub treatment: Option<Option<String>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(length(max = 2000))]
+    #[ts(optional, type = "string | null | undefined")]
+    pub notes: Option<Option<String>>,
+    pub is_shared: Option<bool>,
+}
+
+/// Estructura de respuesta para API
+#[derive(Debug, FromRow, Serialize, TS)]
+pub struct RecordTemplateResponse {
+    pub id: i32,
+    pub name: String,
+    pub diagnosis: Option<String>,
+    pub treatment: Option<String>,
+    pub notes: Option<String>,
+    pub owner_id: Option<i32>,
+    pub owner_name: Option<String>,
+    pub is_shared: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RecordTemplateResponse {
+    /// Obtiene una plantilla por ID con el nombre de quien la creó
+    pub async fn fetch(pool: &PgPool, id: i32) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            RecordTemplateResponse,
+            r#"
+            SELECT
+                t.id,
+                t.name,
+                t.diagnosis,
+                t.treatment,
+                t.notes,
+                t.owner_id,
+                u.name as "owner_name?",
+                t.is_shared,
+                t.created_at
+            FROM record_templates t
+            LEFT JOIN users u ON u.id = t.owner_id
+            WHERE t.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+}
+
+/// Filtros para listar plantillas
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
+pub struct RecordTemplateFilter {
+    pub owner_id: Option<i32>,
+    pub is_shared: Option<bool>,
+    pub q: Option<String>, // Búsqueda parcial en el nombre
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -0,0 +1,134 @@
+use crate::errors::ApiError;
+use crate::models::enums::ReferralDirection;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use ts_rs::TS;
+use validator::{Validate, ValidationError};
+
+/// Estructura para registrar una referencia del paciente.
+///
+/// La contraparte es quien refiere al paciente (`Received`) o a quien se
+/// refiere (`Sent`): un usuario interno o una clínica externa.
+#[derive(Debug, Deserialize, Validate, TS)]
+#[validate(schema(function = "validate_counterpart"))]
+#[ts(optional_fields = nullable)]
+pub struct NewReferral {
+    pub direction: ReferralDirection,
+    #[validate(range(min = 1))]
+    pub internal_user_id: Option<i32>,
+    #[validate(length(min = 2, max = 150))]
+    pub external_clinic: Option<String>,
+    #[validate(length(min = 3, max = 2000))]
+    pub reason: String,
+    pub referral_date: Option<NaiveDate>, // Default: hoy
+    #[validate(range(min = 1))]
+    pub medical_record_id: Option<i32>, // Registro médico adjunto
+}
+
+/// Filtros para listar las referencias de un paciente
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
+pub struct ReferralFilter {
+    pub direction: Option<ReferralDirection>,
+    pub from_date: Option<NaiveDate>,
+    pub to_date: Option<NaiveDate>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Estructura de respuesta para API
+#[derive(Debug, FromRow, Serialize, TS)]
+pub struct ReferralResponse {
+    pub id: i32,
+    pub patient_id: i32,
+    pub direction: ReferralDirection,
+    pub internal_user_id: Option<i32>,
+    pub internal_user_name: Option<String>,
+    pub external_clinic: Option<String>,
+    pub reason: String,
+    pub referral_date: NaiveDate,
+    pub medical_record_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ReferralResponse {
+    /// Obtiene una referencia con el nombre del usuario interno
+    pub async fn fetch(pool: &PgPool, id: i32) -> Result<Self, ApiError> {
+        sqlx::query_as!(
+            ReferralResponse,
+            r#"
+            SELECT
+                r.id,
+                r.patient_id,
+                r.direction as "direction!: ReferralDirection",
+                r.internal_user_id,
+                u.name as "internal_user_name?",
+                r.external_clinic,
+                r.reason,
+                r.referral_date,
+                r.medical_record_id,
+                r.created_at
+            FROM referrals r
+            LEFT JOIN users u ON u.id = r.internal_user_id
+            WHERE r.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or(ApiError::NotFound("La referencia no existe".into()))
+    }
+
+    /// Referencias de un paciente, de la más reciente a la más antigua
+    pub async fn for_patient(
+        pool: &PgPool,
+        patient_id: i32,
+        filters: &ReferralFilter,
+    ) -> Result<Vec<Self>, ApiError> {
+        let referrals = sqlx::query_as!(
+            ReferralResponse,
+            r#"
+            SELECT
+                r.id,
+                r.patient_id,
+                r.direction as "direction!: ReferralDirection",
+                r.internal_user_id,
+                u.name as "internal_user_name?",
+                r.external_clinic,
+                r.reason,
+                r.referral_date,
+                r.medical_record_id,
+                r.created_at
+            FROM referrals r
+            LEFT JOIN users u ON u.id = r.internal_user_id
+            WHERE r.patient_id = $1
+              AND ($2::referral_direction IS NULL OR r.direction = $2)
+              AND ($3::DATE IS NULL OR r.referral_date >= $3)
+              AND ($4::DATE IS NULL OR r.referral_date <= $4)
+            ORDER BY r.referral_date DESC, r.id DESC
+            LIMIT $5 OFFSET $6
+            "#,
+            patient_id,
+            filters.direction as Option<ReferralDirection>,
+            filters.from_date,
+            filters.to_date,
+            filters.limit.unwrap_or(50).min(400),
+            filters.offset.unwrap_or(0)
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(referrals)
+    }
+}
+
+/// La contraparte es un usuario interno o una clínica externa, no ambos
+fn validate_counterpart(referral: &NewReferral) -> Result<(), ValidationError> {
+    match (&referral.internal_user_id, &referral.external_clinic) {
+        (Some(_), None) | (None, Some(_)) => Ok(()),
+        _ => Err(ValidationError::new(
+            "Indica internal_user_id o external_clinic (solo uno)",
+        )),
+    }
+}
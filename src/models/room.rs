@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use ts_rs::TS;
+use validator::Validate;
+
+/// Estructura para registrar una sala o equipo de la clínica
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct NewRoom {
+    #[validate(length(min = 2, max = 100))]
+    pub name: String,
+    #[validate(length(min = 2, max = 50))]
+    pub room_type: String, // Ej: "surgery", "xray"; se compara con `required_room_type`
+}
+
+/// Estructura para actualizar una sala
+#[derive(Debug, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
+pub struct UpdateRoom {
+    #[validate(length(min = 2, max = 100))]
+    pub name: Option<String>,
+    #[validate(length(min = 2, max = 50))]
+    pub room_type: Option<String>,
+    pub is_active: Option<bool>, // Las salas inactivas no se asignan
+}
+
+/// Estructura de respuesta para API
+#[derive(Debug, FromRow, Serialize, TS)]
+pub struct RoomResponse {
+    pub id: i32,
+    pub name: String,
+    pub room_type: String,
+    pub is_active: bool,
+}
+
+/// Filtros para listar salas
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
+pub struct RoomFilter {
+    pub room_type: Option<String>,
+    pub is_active: Option<bool>,
+}
@@ -0,0 +1,50 @@
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use ts_rs::TS;
+use validator::{Validate, ValidationError};
+
+/// Horario de atención de un día de la semana, en hora local de la clínica
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, Validate, TS)]
+pub struct ClinicHoursDay {
+    #[validate(range(min = 1, max = 7))]
+    pub weekday: i16, // ISO 8601: 1 = lunes ... 7 = domingo
+    pub opens_at: NaiveTime,  // Ej: "08:00"
+    pub closes_at: NaiveTime, // Posterior a `opens_at`; el horario no cruza la medianoche
+}
+
+/// Horario de atención de la semana. Los días que no aparecen están cerrados.
+#[derive(Debug, Deserialize, Validate, TS)]
+#[validate(schema(function = "validate_clinic_hours"))]
+pub struct UpdateClinicHours {
+    #[validate(nested)]
+    pub days: Vec<ClinicHoursDay>,
+}
+
+fn validate_clinic_hours(hours: &UpdateClinicHours) -> Result<(), ValidationError> {
+    let mut seen = [false; 7];
+    for day in &hours.days {
+        if day.closes_at <= day.opens_at {
+            return Err(ValidationError::new(
+                "closes_at debe ser posterior a opens_at",
+            ));
+        }
+        if let Some(seen) = usize::try_from(day.weekday - 1)
+            .ok()
+            .and_then(|index| seen.get_mut(index))
+        {
+            if *seen {
+                return Err(ValidationError::new("Cada día debe aparecer una sola vez"));
+            }
+            *seen = true;
+        }
+    }
+    Ok(())
+}
+
+/// Respuesta de `GET /settings/clinic-hours`
+#[derive(Debug, Serialize, TS)]
+pub struct ClinicHoursResponse {
+    pub timezone: String,          // Zona horaria en que se interpretan las horas
+    pub days: Vec<ClinicHoursDay>, // Ordenados por `weekday`; los que faltan cierran
+}
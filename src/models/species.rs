@@ -0,0 +1,60 @@
+use crate::models::enums::{AnimalSpecies, is_valid_species_code};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use validator::{Validate, ValidationError};
+
+/// Especie del catálogo
+#[derive(Debug, Serialize, TS)]
+pub struct SpeciesResponse {
+    pub species: AnimalSpecies, // Valor que se envía en los payloads (`"Dog"`, `"ferret"`)
+    pub code: String,
+    pub name: String,
+    pub is_built_in: bool, // Las integradas no se eliminan
+    pub created_at: DateTime<Utc>,
+}
+
+/// Alta de una especie en el catálogo
+///
+/// # Ejemplo
+/// ```json
+/// { "code": "ferret", "name": "Hurón" }
+/// ```
+#[derive(Debug, Deserialize, Validate, TS)]
+pub struct NewSpecies {
+    #[validate(custom(function = "validate_species_code"))]
+    pub code: String,
+    #[validate(length(min = 2, max = 50))]
+    pub name: String,
+}
+
+/// Cambio del nombre para mostrar; el código no cambia
+#[derive(Debug, Deserialize, Validate, TS)]
+pub struct UpdateSpecies {
+    #[validate(length(min = 2, max = 50))]
+    pub name: String,
+}
+
+/// Filas que usan una especie; cualquiera impide eliminarla
+#[derive(Debug, Serialize)]
+pub struct SpeciesUsage {
+    pub patients: i64,
+    pub breeds: i64,
+    pub procedures: i64,
+    pub species_protocols: i64,
+}
+
+impl SpeciesUsage {
+    pub fn is_used(&self) -> bool {
+        self.patients + self.breeds + self.procedures + self.species_protocols > 0
+    }
+}
+
+fn validate_species_code(code: &str) -> Result<(), ValidationError> {
+    if !is_valid_species_code(code) {
+        return Err(ValidationError::new(
+            "El código debe tener de 2 a 30 minúsculas, dígitos o _ y empezar con letra",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use crate::errors::ApiError;
+use crate::models::enums::AnimalSpecies;
+use crate::models::patient_procedure::PatientProcedureResponse;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use ts_rs::TS;
+use validator::{Validate, ValidationError};
+
+/// Protocolo de procedimientos por especie
+#[derive(Debug, FromRow, Clone)]
+pub struct SpeciesProtocol {
+    pub id: i32,
+    pub name: String,
+    pub species: AnimalSpecies,
+    pub min_age_days: Option<i32>,
+    pub max_age_days: Option<i32>,
+}
+
+impl SpeciesProtocol {
+    /// Indica si la edad del paciente (en días) cae dentro del rango del protocolo
+    pub fn accepts_age(&self, age_days: i64) -> bool {
+        self.min_age_days
+            .is_none_or(|min| age_days >= i64::from(min))
+            && self
+                .max_age_days
+                .is_none_or(|max| age_days <= i64::from(max))
+    }
+}
+
+/// Procedimiento del protocolo y su desfase en días
+#[derive(Debug, Serialize, Deserialize, Validate, Clone, TS)]
+pub struct ProtocolItem {
+    #[validate(range(min = 1))]
+    pub procedure_id: i32,
+    #[validate(range(min = 0, max = 3650))]
+    pub offset_days: i32, // Días desde el nacimiento (o desde la aplicación)
+}
+
+/// Estructura para crear un protocolo
+#[derive(Debug, Deserialize, Validate, TS)]
+#[validate(schema(function = "validate_new_age_range"))]
+#[ts(optional_fields = nullable)]
+pub struct NewSpeciesProtocol {
+    #[validate(length(min = 3, max = 100))]
+    pub name: String,
+    pub species: AnimalSpecies,
+    #[validate(range(min = 0))]
+    pub min_age_days: Option<i32>,
+    #[validate(range(min = 0))]
+    pub max_age_days: Option<i32>,
+    #[validate(length(min = 1, max = 50), nested)]
+    pub items: Vec<ProtocolItem>,
+}
+
+/// Estructura para actualizar un protocolo
+#[derive(Debug, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
+pub struct UpdateSpeciesProtocol {
+    #[validate(length(min = 3, max = 100))]
+    pub name: Option<String>,
+    pub species: Option<AnimalSpecies>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(range(min = 0))]
+    #[ts(optional, type = "number | null | undefined")]
+    pub min_age_days: Option<Option<i32>>,
+    /// Omitir para conservar el valor, `null` para borrar
+    #[validate(range(min = 0))]
+    #[ts(optional, type = "number | null | undefined")]
+    pub max_age_days: Option<Option<i32>>,
+    #[validate(length(min = 1, max = 50), nested)]
+    pub items: Option<Vec<ProtocolItem>>, // Reemplaza la lista completa
+}
+
+/// Filtros para listar protocolos
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct SpeciesProtocolFilter {
+    pub species: Option<AnimalSpecies>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Procedimiento del protocolo en la respuesta
+#[derive(Debug, Serialize, TS)]
+pub struct ProtocolItemResponse {
+    pub procedure_id: i32,
+    pub procedure_name: String,
+    pub offset_days: i32,
+}
+
+/// Respuesta con el protocolo y sus procedimientos
+#[derive(Debug, Serialize, TS)]
+pub struct SpeciesProtocolResponse {
+    pub id: i32,
+    pub name: String,
+    pub species: AnimalSpecies,
+    pub min_age_days: Option<i32>,
+    pub max_age_days: Option<i32>,
+    pub items: Vec<ProtocolItemResponse>,
+}
+
+impl SpeciesProtocolResponse {
+    pub async fn from_protocol(protocol: SpeciesProtocol, pool: &PgPool) -> Result<Self, ApiError> {
+        let items = sqlx::query_as!(
+            ProtocolItemResponse,
+            r#"
+            SELECT
+                i.procedure_id,
+                pr.name as procedure_name,
+                i.offset_days
+            FROM species_protocol_items i
+            JOIN procedures pr ON pr.id = i.procedure_id
+            WHERE i.protocol_id = $1
+            ORDER BY i.offset_days, i.id
+            "#,
+            protocol.id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(Self {
+            id: protocol.id,
+            name: protocol.name,
+            species: protocol.species,
+            min_age_days: protocol.min_age_days,
+            max_age_days: protocol.max_age_days,
+            items,
+        })
+    }
+}
+
+/// Procedimiento del protocolo que no se generó
+#[derive(Debug, Serialize, TS)]
+pub struct SkippedProtocolItem {
+    pub procedure_id: i32,
+    pub procedure_name: String,
+    pub date: NaiveDate,
+    pub reason: String, // Ej: "Ya existe para el paciente"
+}
+
+/// Resultado de aplicar un protocolo a un paciente
+#[derive(Debug, Serialize, TS)]
+pub struct ProtocolApplicationResponse {
+    pub created: Vec<PatientProcedureResponse>,
+    pub skipped: Vec<SkippedProtocolItem>,
+}
+
+/// Valida que la edad mínima no supere a la máxima
+fn validate_new_age_range(protocol: &NewSpeciesProtocol) -> Result<(), ValidationError> {
+    if let (Some(min), Some(max)) = (protocol.min_age_days, protocol.max_age_days)
+        && min > max
+    {
+        return Err(ValidationError::new(
+            "La edad mínima no puede ser mayor que la edad máxima",
+        ));
+    }
+    Ok(())
+}
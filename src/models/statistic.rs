@@ -1,31 +1,233 @@
-use crate::models::enums::UserRole;
+use crate::errors::ApiError;
+use crate::models::enums::{AnimalSpecies, ProcedureType};
+use chrono::{Datelike, Months, NaiveDate};
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
-#[derive(Debug, Deserialize)]
+/// Meses de las series mensuales cuando no se indica rango
+const DEFAULT_SERIES_MONTHS: u32 = 12;
+
+/// Meses máximos de una serie mensual
+const MAX_SERIES_MONTHS: u32 = 120;
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
 pub struct StatisticsQuery {
-    pub role: UserRole,       // "admin" o "veterinarian"
-    pub user_id: Option<i32>, // Solo relevante si role = "veterinarian"
+    pub user_id: Option<i32>, // Veterinario a consultar; un veterinario solo puede pedir el suyo
     pub start_date: Option<chrono::NaiveDate>,
     pub end_date: Option<chrono::NaiveDate>,
-    pub type_: Option<String>, // "appointments", "users", "procedures", etc.
+    pub type_: Option<String>, // Tipos separados por comas; sin valor = todas las del rol
+    pub by: Option<ProcedureActor>, // Procedimientos realizados (default: veterinarian)
+    pub species: Option<AnimalSpecies>, // Filtra los procedimientos por especie del paciente
+}
+
+impl StatisticsQuery {
+    /// Estadísticas pedidas en `type_` (`None` = todas). Responde 400 con los
+    /// tipos disponibles si alguno no existe o si la lista no trae ninguno
+    /// (ej: `type_=,`).
+    pub fn types(&self) -> Result<Option<Vec<StatisticType>>, ApiError> {
+        let Some(names) = self
+            .type_
+            .as_deref()
+            .filter(|names| !names.trim().is_empty())
+        else {
+            return Ok(None);
+        };
+
+        let types = names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                StatisticType::ALL
+                    .into_iter()
+                    .find(|t| t.name().eq_ignore_ascii_case(name))
+                    .ok_or_else(|| {
+                        ApiError::ValidationError(format!(
+                            "Tipo de estadística desconocido: {}. Disponibles: {}",
+                            name,
+                            available_types()
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if types.is_empty() {
+            return Err(ApiError::ValidationError(format!(
+                "type_ no indica ningún tipo de estadística. Disponibles: {}",
+                available_types()
+            )));
+        }
+        Ok(Some(types))
+    }
+
+    /// Rango de las series mensuales. Sin fechas abarca los últimos 12 meses
+    /// hasta `today`; con una sola fecha, 12 meses a partir de ella o hasta ella.
+    pub fn series_range(&self, today: NaiveDate) -> Result<(NaiveDate, NaiveDate), ApiError> {
+        let span = Months::new(DEFAULT_SERIES_MONTHS - 1);
+        let (start, end) = match (self.start_date, self.end_date) {
+            (Some(start), Some(end)) => (start, end),
+            (Some(start), None) => (start, today.max(start)),
+            (None, Some(end)) => (first_of_month(end) - span, end),
+            (None, None) => (first_of_month(today) - span, today),
+        };
+
+        if start > end {
+            return Err(ApiError::ValidationError(
+                "start_date no puede ser posterior a end_date".into(),
+            ));
+        }
+        let months = (end.year() - start.year()) * 12 + end.month() as i32 - start.month() as i32;
+        if months >= MAX_SERIES_MONTHS as i32 {
+            return Err(ApiError::ValidationError(format!(
+                "El rango de las series no puede superar {MAX_SERIES_MONTHS} meses"
+            )));
+        }
+        Ok((start, end))
+    }
+}
+
+fn available_types() -> String {
+    let names: Vec<&str> = StatisticType::ALL.iter().map(|t| t.name()).collect();
+    names.join(", ")
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// Mes del reporte PDF de un veterinario
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct VeterinarianReportQuery {
+    pub month: Option<String>, // "AAAA-MM" (default: mes actual)
+}
+
+impl VeterinarianReportQuery {
+    /// Primer y último día del mes pedido
+    pub fn month_range(&self, today: NaiveDate) -> Result<(NaiveDate, NaiveDate), ApiError> {
+        let start = match &self.month {
+            Some(month) => {
+                NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d").map_err(|_| {
+                    ApiError::ValidationError("month debe tener el formato AAAA-MM".into())
+                })?
+            }
+            None => first_of_month(today),
+        };
+        let end = start + Months::new(1) - chrono::Days::new(1);
+        Ok((start, end))
+    }
+}
+
+/// Procedimientos aplicados de un tipo, para abrir el detalle de una barra de
+/// `procedures_by_type`
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct ProcedureDetailQuery {
+    pub procedure_type: ProcedureType,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub species: Option<AnimalSpecies>,
+    pub user_id: Option<i32>,       // Solo los de este veterinario
+    pub by: Option<ProcedureActor>, // Atribución de `user_id` (default: veterinarian)
+    pub limit: Option<i64>,         // default: 50, máximo: 400
+    pub offset: Option<i64>,        // default: 0
+}
+
+/// Usuario al que se atribuyen los procedimientos realizados
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcedureActor {
+    Performer,
+    #[default]
+    Veterinarian,
+}
+
+/// Estadística solicitada en `type_`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum StatisticType {
+    Appointments,
+    Users,
+    Procedures,
+    Patients,
+    Referrals,
+    Satisfaction,
 }
 
-#[derive(Debug, Serialize)]
+impl StatisticType {
+    pub const ALL: [StatisticType; 6] = [
+        StatisticType::Appointments,
+        StatisticType::Users,
+        StatisticType::Procedures,
+        StatisticType::Patients,
+        StatisticType::Referrals,
+        StatisticType::Satisfaction,
+    ];
+
+    /// Nombre que se envía en `type_`
+    pub fn name(self) -> &'static str {
+        match self {
+            StatisticType::Appointments => "appointments",
+            StatisticType::Users => "users",
+            StatisticType::Procedures => "procedures",
+            StatisticType::Patients => "patients",
+            StatisticType::Referrals => "referrals",
+            StatisticType::Satisfaction => "satisfaction",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, TS)]
 pub struct StatisticsResponse {
     pub appointments_by_month: Option<Vec<AppointmentsByMonth>>,
+    pub appointments_by_source: Option<Vec<AppointmentsBySource>>,
     pub user_counts: Option<UserCounts>,
     pub procedures_by_type: Option<Vec<ProceduresByType>>,
     pub patients_by_species: Option<Vec<PatientsBySpecies>>,
     pub veterinarian_stats: Option<VeterinarianStats>,
+    pub referrals_by_month: Option<Vec<ReferralsByMonth>>,
+    pub satisfaction: Option<Vec<SatisfactionByVeterinarian>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct AppointmentsByMonth {
     pub month: String,
     pub count: i64,
 }
 
-#[derive(Debug, Serialize)]
+/// Citas por origen (`staff`, `portal`, `api`, `import`)
+#[derive(Debug, Serialize, TS)]
+pub struct AppointmentsBySource {
+    pub source: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, TS)]
+pub struct ReferralsByMonth {
+    pub month: String,
+    pub received: i64,
+    pub sent: i64,
+}
+
+/// Calificación promedio (1 a 5) de las encuestas respondidas de un veterinario
+#[derive(Debug, Serialize, TS)]
+pub struct SatisfactionByVeterinarian {
+    pub veterinarian_id: i32,
+    pub veterinarian_name: String,
+    pub responses: i64,
+    pub average_rating: Option<f64>,
+    pub by_month: Vec<SatisfactionByMonth>,
+}
+
+#[derive(Debug, Serialize, TS)]
+pub struct SatisfactionByMonth {
+    pub month: String,
+    pub responses: i64,
+    pub average_rating: Option<f64>, // null si no hubo respuestas en el mes
+}
+
+#[derive(Debug, Serialize, TS)]
 pub struct UserCounts {
     pub total_users: i64,
     pub veterinarians: i64,
@@ -33,28 +235,88 @@ pub struct UserCounts {
     pub admins: i64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct ProceduresByType {
     pub procedure_type: String,
     pub count: i64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct PatientsBySpecies {
     pub species: String,
     pub count: i64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct VeterinarianStats {
     pub appointments_by_status: Vec<AppointmentsByStatus>,
     pub procedures_performed: Vec<ProceduresByType>,
     pub medical_records_created: i64,
     pub patients_attended: Vec<PatientsBySpecies>,
+    pub average_duration_minutes: Option<f64>, // De las citas completadas; null sin citas
+    pub no_show_rate: Option<f64>, // % de no_show entre completadas y no_show; null sin citas
+    pub survey_responses: i64,
+    pub average_rating: Option<f64>, // Encuestas de satisfacción (1 a 5); null sin respuestas
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct AppointmentsByStatus {
     pub status: String,
     pub count: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types(type_: &str) -> Result<Option<Vec<StatisticType>>, ApiError> {
+        StatisticsQuery {
+            user_id: None,
+            start_date: None,
+            end_date: None,
+            type_: Some(type_.into()),
+            by: None,
+            species: None,
+        }
+        .types()
+    }
+
+    #[test]
+    fn single_and_several_types() {
+        assert_eq!(types("users").unwrap(), Some(vec![StatisticType::Users]));
+        assert_eq!(
+            types("appointments, procedures,satisfaction").unwrap(),
+            Some(vec![
+                StatisticType::Appointments,
+                StatisticType::Procedures,
+                StatisticType::Satisfaction
+            ])
+        );
+        assert_eq!(types("").unwrap(), None);
+    }
+
+    #[test]
+    fn type_names_ignore_case() {
+        assert_eq!(
+            types("Appointments,PATIENTS").unwrap(),
+            Some(vec![StatisticType::Appointments, StatisticType::Patients])
+        );
+    }
+
+    #[test]
+    fn unknown_type_lists_the_available_ones() {
+        let Err(ApiError::ValidationError(message)) = types("appointments,appointmets") else {
+            panic!("se esperaba 400");
+        };
+        assert!(message.contains("appointmets"));
+        for t in StatisticType::ALL {
+            assert!(message.contains(t.name()), "{message}");
+        }
+    }
+
+    #[test]
+    fn list_without_types_is_rejected() {
+        assert!(matches!(types(","), Err(ApiError::ValidationError(_))));
+        assert!(matches!(types(" , "), Err(ApiError::ValidationError(_))));
+    }
+}
@@ -0,0 +1,24 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use validator::Validate;
+
+/// Datos que ve el cliente al abrir la encuesta.
+///
+/// Solo el nombre de pila del veterinario y la fecha: el token es público y
+/// no debe revelar nada más de la cita.
+#[derive(Debug, Serialize, TS)]
+pub struct SurveyInfo {
+    pub veterinarian_first_name: String,
+    pub appointment_date: NaiveDate, // Fecha local de la clínica
+}
+
+/// Respuesta del cliente a la encuesta
+#[derive(Debug, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
+pub struct SurveyAnswer {
+    #[validate(range(min = 1, max = 5))]
+    pub rating: i16,
+    #[validate(length(max = 1000))]
+    pub comment: Option<String>,
+}
@@ -0,0 +1,34 @@
+use crate::models::appointment::AppointmentResponse;
+use crate::models::client::ClientResponse;
+use crate::models::patient::PatientResponse;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Parámetros de la sincronización diferencial
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct SyncQuery {
+    #[ts(optional = false)]
+    pub since: DateTime<Utc>, // Cursor: `server_time` de la sincronización anterior
+    pub entities: Option<String>, // Separadas por comas (default: todas)
+    pub limit: Option<i64>,       // Cambios por entidad (default y máximo: 500)
+}
+
+/// Cambios de una entidad desde el cursor
+#[derive(Debug, Serialize, TS)]
+pub struct SyncChanges<T: TS> {
+    pub updated: Vec<T>,       // Filas creadas o modificadas
+    pub deleted_ids: Vec<i32>, // Filas eliminadas
+    pub has_more: bool,
+    pub next_since: Option<DateTime<Utc>>, // Solo con `has_more`: cursor para pedir el resto de esta entidad
+}
+
+/// Respuesta de la sincronización; las entidades no pedidas van en `null`
+#[derive(Debug, Serialize, TS)]
+pub struct SyncResponse {
+    pub server_time: DateTime<Utc>, // Siguiente `since` cuando ninguna entidad tenga `has_more`
+    pub clients: Option<SyncChanges<ClientResponse>>,
+    pub patients: Option<SyncChanges<PatientResponse>>,
+    pub appointments: Option<SyncChanges<AppointmentResponse>>,
+}
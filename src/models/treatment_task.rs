@@ -0,0 +1,79 @@
+use crate::models::enums::{TaskStatus, UserRole};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use ts_rs::TS;
+use validator::{Validate, ValidationError};
+
+/// Estructura para programar una tarea (o una serie repetida)
+#[derive(Debug, Deserialize, Validate, TS)]
+#[validate(schema(function = "validate_repetition"))]
+#[ts(optional_fields = nullable)]
+pub struct NewTreatmentTask {
+    #[validate(range(min = 1))]
+    pub hospitalization_id: i32,
+    #[validate(length(min = 3, max = 500))]
+    pub description: String, // Ej: "Amoxicilina 250 mg VO"
+    pub scheduled_time: DateTime<Utc>, // Primera aplicación
+    pub assigned_role: Option<UserRole>,
+    #[validate(range(min = 1, max = 72))]
+    pub repeat_every_hours: Option<i32>,
+    #[validate(range(min = 1, max = 100))]
+    pub occurrences: Option<i32>, // Total de tareas a generar (default: 1)
+}
+
+/// Estructura para completar u omitir una tarea
+#[derive(Debug, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
+pub struct CompleteTreatmentTask {
+    #[serde(default)]
+    pub skipped: bool, // true para marcarla como omitida
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+/// Filtros de la lista de trabajo del turno
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct DueTasksQuery {
+    pub window_minutes: Option<i64>, // Minutos hacia adelante (default: 60, máx: 720)
+    pub role: Option<UserRole>,      // Tareas asignadas a este rol o a cualquiera
+}
+
+/// Filtros para listar tareas
+#[derive(Debug, Deserialize, TS)]
+#[ts(optional_fields)]
+pub struct TreatmentTaskFilter {
+    pub hospitalization_id: Option<i32>,
+    pub status: Option<TaskStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Estructura de respuesta para API
+#[derive(Debug, FromRow, Serialize, TS)]
+pub struct TreatmentTaskResponse {
+    pub id: i32,
+    pub hospitalization_id: i32,
+    pub patient_id: i32,
+    pub patient_name: String,
+    pub kennel: Option<String>,
+    pub description: String,
+    pub scheduled_time: DateTime<Utc>,
+    pub assigned_role: Option<UserRole>,
+    pub status: TaskStatus,
+    pub done_by: Option<i32>,
+    pub done_by_name: Option<String>,
+    pub done_at: Option<DateTime<Utc>>,
+    pub notes: Option<String>,
+}
+
+/// La repetición requiere el intervalo y el número de tareas juntos
+fn validate_repetition(task: &NewTreatmentTask) -> Result<(), ValidationError> {
+    if task.occurrences.is_some_and(|n| n > 1) && task.repeat_every_hours.is_none() {
+        return Err(ValidationError::new(
+            "Para repetir la tarea se requiere repeat_every_hours",
+        ));
+    }
+    Ok(())
+}
@@ -1,7 +1,10 @@
+use crate::auth::IssuedToken;
+use crate::errors::ApiError;
 use crate::models::enums::UserRole;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use ts_rs::TS;
 use validator::Validate;
 
 /// Estructura para usuario
@@ -17,10 +20,12 @@ pub struct User {
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
 }
 
 /// Estructura para crear un nuevo usuario
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
 pub struct NewUser {
     #[validate(email, length(max = 255))]
     pub email: String,
@@ -35,7 +40,8 @@ pub struct NewUser {
 }
 
 /// Estructura para actualizar usuario
-#[derive(Debug, Serialize, Deserialize, Validate, Default)]
+#[derive(Debug, Serialize, Deserialize, Validate, Default, TS)]
+#[ts(optional_fields = nullable)]
 pub struct UpdateUser {
     #[validate(email, length(max = 255))]
     pub email: Option<String>,
@@ -51,7 +57,7 @@ pub struct UpdateUser {
 }
 
 /// Estructura para respuesta pública de usuario
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, TS)]
 pub struct UserResponse {
     pub id: i32,
     pub email: String,
@@ -60,6 +66,16 @@ pub struct UserResponse {
     pub license_number: Option<String>,
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>, // null si nunca ha iniciado sesión
+    /// Citas programadas a futuro; solo con `include_workload=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub upcoming_appointments: Option<i64>,
+    /// Citas de la semana local en curso (lunes a domingo), sin canceladas;
+    /// solo con `include_workload=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub appointments_this_week: Option<i64>,
 }
 
 impl From<User> for UserResponse {
@@ -72,28 +88,203 @@ impl From<User> for UserResponse {
             license_number: user.license_number,
             is_active: user.is_active,
             created_at: user.created_at,
+            last_login_at: user.last_login_at,
+            upcoming_appointments: None,
+            appointments_this_week: None,
         }
     }
 }
 
 /// Filtros para búsqueda de usuarios
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, TS)]
+#[ts(optional_fields)]
 pub struct UserFilter {
     pub email: Option<String>,
     pub role: Option<UserRole>,
+    pub roles: Option<String>, // Roles separados por comas (ej: "Veterinarian,Assistant")
+    pub q: Option<String>,     // Búsqueda parcial en nombre o correo
     pub license_number: Option<String>,
     pub is_active: Option<bool>,
     pub created_after: Option<DateTime<Utc>>,
     pub created_before: Option<DateTime<Utc>>,
+    pub last_login_before: Option<DateTime<Utc>>, // Sin sesión desde esa fecha (incluye a quien nunca entró)
+    pub include_workload: Option<bool>,           // Carga de citas; requiere role=Veterinarian
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
 
+impl UserFilter {
+    /// Roles a incluir combinando `role` y `roles` (`None` = todos). Responde
+    /// 400 si `roles` contiene un rol desconocido.
+    pub fn role_list(&self) -> Result<Option<Vec<UserRole>>, ApiError> {
+        let mut roles: Vec<UserRole> = self.role.iter().cloned().collect();
+
+        if let Some(raw) = &self.roles {
+            for name in raw
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+            {
+                let role = serde_json::from_value(serde_json::Value::String(name.to_string()))
+                    .map_err(|_| {
+                        ApiError::ValidationError(format!(
+                            "Rol desconocido en roles: {name}. Disponibles: Veterinarian, Assistant, Admin"
+                        ))
+                    })?;
+                if !roles.contains(&role) {
+                    roles.push(role);
+                }
+            }
+        }
+
+        Ok((!roles.is_empty()).then_some(roles))
+    }
+}
+
+/// Token de acceso emitido, con las fechas exactas de sus claims
+#[derive(Debug, Serialize, TS)]
+pub struct AccessToken {
+    pub token: String,
+    pub token_type: &'static str,  // Siempre "Bearer"
+    pub issued_at: DateTime<Utc>,  // Claim `iat` del token
+    pub expires_at: DateTime<Utc>, // Claim `exp` del token
+    pub expires_in: i64,           // Segundos de vigencia de `token`
+}
+
+impl From<IssuedToken> for AccessToken {
+    fn from(issued: IssuedToken) -> Self {
+        let (issued_at, expires_at) = (issued.claims.issued_at(), issued.claims.expires_at());
+        Self {
+            token: issued.token,
+            token_type: "Bearer",
+            issued_at,
+            expires_at,
+            expires_in: (expires_at - issued_at).num_seconds(),
+        }
+    }
+}
+
+/// Estructura de respuesta para login
+#[derive(Debug, Serialize, TS)]
+pub struct LoginResponse {
+    #[serde(flatten)]
+    pub access: AccessToken,
+    pub refresh_token: String, // Se canjea una sola vez en `/users/refresh`
+    pub user: UserResponse,
+}
+
+/// Token de renovación que se canjea por un token de acceso nuevo, o que se
+/// revoca al cerrar sesión
+#[derive(Debug, Deserialize, TS)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+/// Token de acceso nuevo y el token de renovación que reemplaza al canjeado
+#[derive(Debug, Serialize, TS)]
+pub struct RefreshResponse {
+    #[serde(flatten)]
+    pub access: AccessToken,
+    pub refresh_token: String,
+}
+
+/// Solicitud de un enlace para restablecer la contraseña
+#[derive(Debug, Deserialize, Validate, TS)]
+pub struct PasswordResetRequest {
+    #[validate(email)]
+    pub email: String,
+}
+
+/// Nueva contraseña con el token recibido por correo
+#[derive(Debug, Deserialize, Validate, TS)]
+pub struct PasswordResetConfirm {
+    pub token: String,
+    #[validate(length(min = 8, max = 72))] // Mismas reglas que `NewUser::password`
+    pub new_password: String,
+}
+
+/// Cambio de contraseña del propio usuario
+#[derive(Debug, Deserialize, Validate, TS)]
+pub struct ChangePassword {
+    pub current_password: String,
+    #[validate(length(min = 8, max = 72))] // Mismas reglas que `NewUser::password`
+    pub new_password: String,
+}
+
+/// Token de suplantación emitido para soporte
+#[derive(Debug, Serialize, TS)]
+pub struct ImpersonationResponse {
+    pub token: String,
+    pub expires_at: DateTime<Utc>, // No se renueva; al expirar se vuelve a solicitar
+    pub impersonator_id: i32,
+    pub user: UserResponse,
+}
+
 /// Estructura para login
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, TS)]
+#[ts(optional_fields = nullable)]
 pub struct LoginUser {
     #[validate(email)]
     pub email: String,
     #[validate(length(min = 8))]
     pub password: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(role: Option<UserRole>, roles: &str) -> UserFilter {
+        UserFilter {
+            role,
+            roles: Some(roles.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn role_and_roles_are_combined() {
+        let roles = filter(Some(UserRole::Admin), "Veterinarian,Assistant").role_list();
+        assert_eq!(
+            roles.unwrap(),
+            Some(vec![
+                UserRole::Admin,
+                UserRole::Veterinarian,
+                UserRole::Assistant
+            ])
+        );
+        assert_eq!(UserFilter::default().role_list().unwrap(), None);
+    }
+
+    #[test]
+    fn repeated_roles_appear_once() {
+        let roles = filter(
+            Some(UserRole::Veterinarian),
+            "Veterinarian,Assistant,Assistant",
+        );
+        assert_eq!(
+            roles.role_list().unwrap(),
+            Some(vec![UserRole::Veterinarian, UserRole::Assistant])
+        );
+    }
+
+    #[test]
+    fn whitespace_and_empty_entries_are_ignored() {
+        let roles = filter(None, " Veterinarian , ,Assistant ,").role_list();
+        assert_eq!(
+            roles.unwrap(),
+            Some(vec![UserRole::Veterinarian, UserRole::Assistant])
+        );
+        assert_eq!(filter(None, " , ").role_list().unwrap(), None);
+    }
+
+    #[test]
+    fn unknown_role_is_rejected() {
+        let Err(ApiError::ValidationError(message)) =
+            filter(None, "Veterinarian,Owner").role_list()
+        else {
+            panic!("se esperaba 400");
+        };
+        assert!(message.contains("Owner"));
+    }
+}
@@ -0,0 +1,44 @@
+use crate::errors::ApiError;
+use serde::{Deserialize, Serialize};
+
+/// Respuesta exitosa con advertencias no bloqueantes.
+///
+/// Se serializa como el recurso más la llave `warnings`, que solo aparece si
+/// hay advertencias; los clientes que la ignoran no ven ningún cambio.
+#[derive(Debug, Serialize)]
+pub struct WithWarnings<T> {
+    #[serde(flatten)]
+    pub data: T,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl<T> WithWarnings<T> {
+    pub fn new(data: T, warnings: Vec<String>) -> Self {
+        Self { data, warnings }
+    }
+}
+
+/// Parámetro de query para aceptar las advertencias y continuar
+///
+/// # Ejemplo
+/// POST /appointments?override_warnings=true
+#[derive(Debug, Deserialize, Default)]
+pub struct WarningOverride {
+    #[serde(default)]
+    pub override_warnings: bool,
+}
+
+impl WarningOverride {
+    /// Sin `override_warnings` las advertencias bloquean con 400; con él se
+    /// devuelven para adjuntarlas a la respuesta.
+    pub fn resolve(&self, warnings: Vec<String>) -> Result<Vec<String>, ApiError> {
+        if warnings.is_empty() || self.override_warnings {
+            return Ok(warnings);
+        }
+        Err(ApiError::ValidationError(format!(
+            "{}. Envía override_warnings=true para continuar de todos modos",
+            warnings.join(". ")
+        )))
+    }
+}
@@ -0,0 +1,247 @@
+use crate::errors::ApiError;
+use crate::models::admin::NotificationLogEntry;
+use crate::models::enums::{NotificationChannel, NotificationOutcome};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use sqlx::PgPool;
+use std::env;
+
+/// Correo por enviar, con los datos que se registran en `notification_log`
+pub struct Email<'a> {
+    pub template: &'static str,
+    pub to: &'a str,
+    pub subject: &'a str,
+    pub body: String,
+    pub client_id: Option<i32>,
+    pub appointment_id: Option<i32>,
+    /// El cuerpo lleva un secreto (ej: un enlace de un solo uso): no se guarda
+    /// en el log y el envío no se puede reintentar
+    pub secret: bool,
+}
+
+/// Envía el correo y registra el intento en `notification_log`.
+///
+/// El intento queda `pending` mientras se envía, así que uno que nunca terminó
+/// (ej: el proceso se detuvo a medias) se distingue de uno fallido.
+pub async fn deliver(pool: &PgPool, email: Email<'_>) -> Result<(), String> {
+    let log_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO notification_log
+            (channel, target, template, subject, body, client_id, appointment_id)
+        VALUES ('email', $1, $2, $3, $4, $5, $6)
+        RETURNING id
+        "#,
+        email.to,
+        email.template,
+        email.subject,
+        (!email.secret).then_some(&email.body),
+        email.client_id,
+        email.appointment_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Error al registrar la notificación: {e}"))?;
+
+    let result = send_email(email.to, email.subject, email.body).await;
+    finish(pool, log_id, &result)
+        .await
+        .map_err(|e| format!("Error al registrar la notificación: {e}"))?;
+    result.map(|_| ())
+}
+
+/// Reenvía tal cual una notificación fallida y devuelve el nuevo intento.
+///
+/// Los reintentos apuntan al envío original (`retry_of`) y un índice único
+/// impide que haya dos en curso o exitosos, así que un reintento que ya
+/// funcionó no se repite aunque lleguen dos peticiones a la vez.
+pub async fn retry(pool: &PgPool, log_id: i32) -> Result<NotificationLogEntry, ApiError> {
+    let original = sqlx::query!(
+        r#"
+        SELECT
+            channel as "channel: NotificationChannel",
+            target,
+            template,
+            subject,
+            body,
+            client_id,
+            appointment_id,
+            outcome as "outcome: NotificationOutcome",
+            retry_of
+        FROM notification_log
+        WHERE id = $1
+        "#,
+        log_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::NotFound("La notificación no existe".into()))?;
+
+    if original.outcome != NotificationOutcome::Failed {
+        return Err(ApiError::Conflict(
+            "Solo se pueden reintentar las notificaciones fallidas".into(),
+        ));
+    }
+    let Some(body) = original.body else {
+        return Err(ApiError::Conflict(
+            "La notificación llevaba un secreto y no se guardó; no se puede reintentar".into(),
+        ));
+    };
+
+    // Reintentar un reintento fallido cuenta como reintento del original
+    let root_id = original.retry_of.unwrap_or(log_id);
+    let inserted = sqlx::query_scalar!(
+        r#"
+        INSERT INTO notification_log
+            (channel, target, template, subject, body, client_id, appointment_id, retry_of)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id
+        "#,
+        original.channel as NotificationChannel,
+        original.target,
+        original.template,
+        original.subject,
+        body,
+        original.client_id,
+        original.appointment_id,
+        root_id
+    )
+    .fetch_one(pool)
+    .await;
+
+    let retry_id = match inserted {
+        Ok(id) => id,
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+            let existing = sqlx::query!(
+                r#"
+                SELECT id, outcome as "outcome: NotificationOutcome"
+                FROM notification_log
+                WHERE retry_of = $1 AND outcome <> 'failed'
+                "#,
+                root_id
+            )
+            .fetch_optional(pool)
+            .await?;
+            return Err(ApiError::DetailedConflict {
+                message: "La notificación ya se reintentó con éxito o hay un reintento en curso"
+                    .into(),
+                details: serde_json::json!({
+                    "retry_id": existing.as_ref().map(|row| row.id),
+                    "outcome": existing.map(|row| row.outcome),
+                }),
+            });
+        }
+        Err(e) => return Err(ApiError::internal("Error al registrar el reintento", e)),
+    };
+
+    let result = match original.channel {
+        NotificationChannel::Email => send_email(&original.target, &original.subject, body).await,
+    };
+    if let Err(e) = &result {
+        tracing::warn!(
+            "Reintento {} de la notificación {}: {}",
+            retry_id,
+            root_id,
+            e
+        );
+    }
+
+    finish(pool, retry_id, &result)
+        .await
+        .map_err(|e| ApiError::internal("Error al registrar el reintento", e))
+}
+
+/// Guarda el resultado de un intento
+async fn finish(
+    pool: &PgPool,
+    log_id: i32,
+    result: &Result<Option<String>, String>,
+) -> Result<NotificationLogEntry, sqlx::Error> {
+    let (outcome, message_id, error) = match result {
+        Ok(message_id) => (NotificationOutcome::Sent, message_id.as_deref(), None),
+        Err(e) => (NotificationOutcome::Failed, None, Some(e.as_str())),
+    };
+
+    sqlx::query_as!(
+        NotificationLogEntry,
+        r#"
+        UPDATE notification_log
+        SET outcome = $2, provider_message_id = $3, error = $4, completed_at = NOW()
+        WHERE id = $1
+        RETURNING
+            id,
+            channel as "channel: NotificationChannel",
+            target,
+            template,
+            subject,
+            client_id,
+            appointment_id,
+            outcome as "outcome: NotificationOutcome",
+            provider_message_id,
+            error,
+            retry_of,
+            attempted_at,
+            completed_at
+        "#,
+        log_id,
+        outcome as NotificationOutcome,
+        message_id,
+        error
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Envía un correo por SMTP y devuelve su `Message-ID`.
+///
+/// Si `SMTP_HOST` no está configurado el mensaje solo se registra en el log,
+/// lo que permite probar los envíos en desarrollo sin un servidor de correo.
+///
+/// Variables: `SMTP_HOST`, `SMTP_PORT` (default: 587), `SMTP_USERNAME`,
+/// `SMTP_PASSWORD` y `SMTP_FROM`.
+async fn send_email(to: &str, subject: &str, body: String) -> Result<Option<String>, String> {
+    let Ok(host) = env::var("SMTP_HOST") else {
+        tracing::info!(
+            "Correo para {} (SMTP no configurado): {}\n{}",
+            to,
+            subject,
+            body
+        );
+        return Ok(None);
+    };
+
+    let from: Mailbox = env::var("SMTP_FROM")
+        .unwrap_or_else(|_| "MiCita <no-reply@micita.local>".to_string())
+        .parse()
+        .map_err(|e| format!("SMTP_FROM inválido: {e}"))?;
+    let to: Mailbox = to
+        .parse()
+        .map_err(|e| format!("Destinatario inválido: {e}"))?;
+
+    let message = Message::builder()
+        .from(from)
+        .to(to)
+        .subject(subject)
+        .message_id(None)
+        .body(body)
+        .map_err(|e| format!("Error al construir el correo: {e}"))?;
+    let message_id = message.headers().get_raw("Message-ID").map(str::to_string);
+
+    let port = env::var("SMTP_PORT")
+        .ok()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(587);
+    let mut transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)
+        .map_err(|e| format!("Error al conectar con SMTP: {e}"))?
+        .port(port);
+    if let (Ok(username), Ok(password)) = (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD")) {
+        transport = transport.credentials(Credentials::new(username, password));
+    }
+
+    transport
+        .build()
+        .send(message)
+        .await
+        .map(|_| message_id)
+        .map_err(|e| format!("Error al enviar el correo: {e}"))
+}
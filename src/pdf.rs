@@ -0,0 +1,267 @@
+use std::fmt::Write;
+
+/// Tamaño carta, en puntos
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+const MARGIN: f32 = 50.0;
+/// Altura reservada al pie de página
+const FOOTER_HEIGHT: f32 = 40.0;
+
+/// Objetos fijos: catálogo, árbol de páginas y las dos fuentes
+const CATALOG_ID: usize = 1;
+const PAGES_ID: usize = 2;
+const FIRST_FREE_ID: usize = 5;
+
+/// Estilos de texto disponibles
+#[derive(Debug, Clone, Copy)]
+pub enum Style {
+    Title,
+    Heading,
+    Body,
+}
+
+impl Style {
+    fn font(self) -> &'static str {
+        match self {
+            Style::Body => "F1",
+            Style::Title | Style::Heading => "F2",
+        }
+    }
+
+    fn size(self) -> f32 {
+        match self {
+            Style::Title => 16.0,
+            Style::Heading => 12.0,
+            Style::Body => 10.0,
+        }
+    }
+}
+
+/// Documento PDF de texto con Helvetica, números de página y pie común.
+///
+/// Cada página se serializa en cuanto se llena, así que en memoria solo
+/// queda el contenido de la página actual además de los bytes ya generados.
+/// Al alcanzar `max_pages` el resto del texto se descarta y la última
+/// página lleva un aviso de truncamiento.
+pub struct PdfWriter {
+    output: Vec<u8>,
+    offsets: Vec<usize>, // Posición de cada objeto; índice = id - 1
+    page_ids: Vec<usize>,
+    content: String,
+    y: f32,
+    footer: String,
+    max_pages: usize,
+    truncated: bool,
+}
+
+impl PdfWriter {
+    pub fn new(footer: impl Into<String>, max_pages: usize) -> Self {
+        let mut writer = Self {
+            output: b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec(),
+            offsets: vec![0; FIRST_FREE_ID - 1],
+            page_ids: Vec::new(),
+            content: String::new(),
+            y: PAGE_HEIGHT - MARGIN,
+            footer: footer.into(),
+            max_pages: max_pages.max(1),
+            truncated: false,
+        };
+        for (id, font) in [(3, "Helvetica"), (4, "Helvetica-Bold")] {
+            writer.write_object(
+                id,
+                &format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{font} /Encoding /WinAnsiEncoding >>"
+                ),
+            );
+        }
+        writer
+    }
+
+    /// Si se descartó contenido por el límite de páginas
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Agrega un párrafo; se parte en líneas y continúa en otra página si no cabe
+    pub fn text(&mut self, style: Style, text: &str) {
+        let line_height = style.size() * 1.4;
+        let max_chars = ((PAGE_WIDTH - 2.0 * MARGIN) / (style.size() * 0.52)) as usize;
+
+        for line in wrap(text, max_chars) {
+            if self.y - line_height < MARGIN + FOOTER_HEIGHT {
+                self.break_page();
+            }
+            if self.truncated {
+                return;
+            }
+            self.y -= line_height;
+            let _ = writeln!(
+                self.content,
+                "BT /{} {} Tf {} {} Td ({}) Tj ET",
+                style.font(),
+                style.size(),
+                MARGIN,
+                self.y,
+                escape(&line)
+            );
+        }
+    }
+
+    /// Espacio vertical entre bloques
+    pub fn gap(&mut self, points: f32) {
+        self.y -= points;
+    }
+
+    /// Cierra la última página y devuelve el documento completo
+    pub fn finish(mut self) -> Vec<u8> {
+        let notice = self.truncated.then(|| {
+            format!(
+                "Documento truncado: se alcanzó el máximo de {} páginas",
+                self.max_pages
+            )
+        });
+        self.flush_page(notice.as_deref());
+
+        let kids: Vec<String> = self.page_ids.iter().map(|id| format!("{id} 0 R")).collect();
+        self.write_object(
+            PAGES_ID,
+            &format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                kids.len()
+            ),
+        );
+        self.write_object(
+            CATALOG_ID,
+            &format!("<< /Type /Catalog /Pages {PAGES_ID} 0 R >>"),
+        );
+
+        let xref_offset = self.output.len();
+        let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            let _ = writeln!(xref, "{offset:010} 00000 n ");
+        }
+        let _ = write!(
+            xref,
+            "trailer\n<< /Size {} /Root {CATALOG_ID} 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+            self.offsets.len() + 1
+        );
+        self.output.extend_from_slice(xref.as_bytes());
+        self.output
+    }
+
+    fn break_page(&mut self) {
+        if self.page_ids.len() + 1 >= self.max_pages {
+            self.truncated = true;
+            return;
+        }
+        self.flush_page(None);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Serializa la página actual con su pie y la deja lista para la siguiente
+    fn flush_page(&mut self, notice: Option<&str>) {
+        let number = self.page_ids.len() + 1;
+        let mut content = std::mem::take(&mut self.content);
+        if let Some(notice) = notice {
+            let _ = writeln!(
+                content,
+                "BT /F2 10 Tf {MARGIN} {} Td ({}) Tj ET",
+                MARGIN + FOOTER_HEIGHT - 15.0,
+                escape(notice)
+            );
+        }
+        let _ = writeln!(
+            content,
+            "BT /F1 8 Tf {MARGIN} {} Td ({}) Tj ET",
+            MARGIN - 20.0,
+            escape(&self.footer)
+        );
+        let _ = writeln!(
+            content,
+            "BT /F1 8 Tf {} {} Td ({}) Tj ET",
+            PAGE_WIDTH - MARGIN - 40.0,
+            MARGIN - 20.0,
+            escape(&format!("Página {number}"))
+        );
+
+        let content_id = self.offsets.len() + 1;
+        self.write_object(
+            content_id,
+            &format!(
+                "<< /Length {} >>\nstream\n{content}endstream",
+                content.len()
+            ),
+        );
+        let page_id = self.offsets.len() + 1;
+        self.write_object(
+            page_id,
+            &format!(
+                "<< /Type /Page /Parent {PAGES_ID} 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {content_id} 0 R >>"
+            ),
+        );
+        self.page_ids.push(page_id);
+    }
+
+    fn write_object(&mut self, id: usize, body: &str) {
+        if id > self.offsets.len() {
+            self.offsets.resize(id, 0);
+        }
+        self.offsets[id - 1] = self.output.len();
+        self.output
+            .extend_from_slice(format!("{id} 0 obj\n{body}\nendobj\n").as_bytes());
+    }
+}
+
+/// Parte el texto en líneas de a lo más `max_chars` caracteres, respetando
+/// los saltos de línea y cortando palabras que no caben solas
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            while word.len() > max_chars {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                lines.push(word.drain(..max_chars).collect());
+            }
+            let word: String = word.into_iter().collect();
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Cadena literal de PDF en WinAnsi; los caracteres fuera de Latin-1 se
+/// reemplazan por `?`
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(escaped, "\\{:03o}", c as u32);
+            }
+            // Guiones largos en WinAnsiEncoding
+            '\u{2013}' => escaped.push_str("\\226"),
+            '\u{2014}' => escaped.push_str("\\227"),
+            c if c.is_whitespace() => escaped.push(' '),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
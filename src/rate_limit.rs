@@ -0,0 +1,250 @@
+use crate::auth::Claims;
+use crate::errors::ApiError;
+use crate::metrics::Metrics;
+use crate::middleware::{CLINIC_PROFILE_PATH, HEALTH_PATH, METRICS_PATH};
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage, ResponseError, web};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Rutas que nunca se limitan
+const EXEMPT_PATHS: &[&str] = &[HEALTH_PATH, METRICS_PATH];
+
+/// Límite de peticiones por usuario o llave de API.
+///
+/// `RATE_LIMIT_PER_MINUTE` (default: 600; `0` lo desactiva) es el ritmo
+/// sostenido y `RATE_LIMIT_BURST` (default: 100) cuántas peticiones seguidas
+/// se permiten antes de aplicarlo.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitConfig {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+impl RateLimitConfig {
+    /// Configuración desde el entorno, o `None` si está desactivado
+    pub fn from_env() -> Option<Self> {
+        let read = |name: &str, default: u32| {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(default)
+        };
+        let per_minute = read("RATE_LIMIT_PER_MINUTE", 600);
+        let burst = read("RATE_LIMIT_BURST", 100).max(1);
+        (per_minute > 0).then_some(Self { per_minute, burst })
+    }
+
+    fn refill_per_second(&self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+}
+
+/// Resultado de consumir una petición del bucket
+#[derive(Debug, Clone, Copy)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    pub reset_secs: u64,       // Segundos hasta que el bucket esté lleno
+    pub retry_after_secs: u64, // Segundos hasta la próxima petición permitida
+}
+
+impl Decision {
+    /// Agrega las cabeceras `X-RateLimit-*` (y `Retry-After` si se rechazó)
+    fn apply_headers(&self, headers: &mut HeaderMap) {
+        let mut insert = |name: &'static str, value: u64| {
+            headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+        };
+        insert("x-ratelimit-limit", self.limit.into());
+        insert("x-ratelimit-remaining", self.remaining.into());
+        insert("x-ratelimit-reset", self.reset_secs);
+        if !self.allowed {
+            headers.insert(RETRY_AFTER, HeaderValue::from(self.retry_after_secs));
+        }
+    }
+}
+
+/// Almacén de los buckets por cliente. La implementación en memoria sirve
+/// para una sola instancia; varias instancias requieren uno compartido.
+pub trait RateLimitStore: Send + Sync {
+    fn consume(&self, key: &str, config: &RateLimitConfig) -> Decision;
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Buckets en memoria del proceso
+#[derive(Debug, Default)]
+pub struct InMemoryStore {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimitStore for InMemoryStore {
+    fn consume(&self, key: &str, config: &RateLimitConfig) -> Decision {
+        let capacity = f64::from(config.burst);
+        let rate = config.refill_per_second();
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated_at = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        Decision {
+            allowed,
+            limit: config.burst,
+            remaining: bucket.tokens.floor() as u32,
+            reset_secs: ((capacity - bucket.tokens) / rate).ceil() as u64,
+            retry_after_secs: if allowed {
+                0
+            } else {
+                ((1.0 - bucket.tokens) / rate).ceil() as u64
+            },
+        }
+    }
+}
+
+/// Límite de peticiones compartido como `web::Data<RateLimiter>`
+pub struct RateLimiter {
+    config: Option<RateLimitConfig>,
+    store: Box<dyn RateLimitStore>,
+}
+
+impl RateLimiter {
+    pub fn new(config: Option<RateLimitConfig>, store: Box<dyn RateLimitStore>) -> Self {
+        Self { config, store }
+    }
+
+    /// Configuración del entorno con buckets en memoria
+    pub fn from_env() -> Self {
+        Self::new(
+            RateLimitConfig::from_env(),
+            Box::new(InMemoryStore::default()),
+        )
+    }
+
+    /// Consume una petición del cliente, o `None` si el límite está desactivado
+    pub fn consume(&self, key: &str) -> Option<Decision> {
+        self.config
+            .as_ref()
+            .map(|config| self.store.consume(key, config))
+    }
+}
+
+/// Cliente al que se atribuye la petición: la llave de API o el usuario del
+/// JWT. Sin claims (la API_KEY compartida, las encuestas, la sala de espera)
+/// no hay a quién atribuirla y se usa la dirección de origen, para que un
+/// integrador ruidoso no agote el límite de los demás.
+pub fn principal(req: &ServiceRequest) -> String {
+    match req.extensions().get::<Claims>() {
+        Some(Claims {
+            api_key_id: Some(id),
+            ..
+        }) => format!("api_key:{id}"),
+        Some(claims) => format!("user:{}", claims.sub),
+        None => {
+            let peer = req
+                .peer_addr()
+                .map_or_else(|| "desconocida".to_string(), |addr| addr.ip().to_string());
+            // Las visitas anónimas del sitio web no consumen el límite de la API
+            if req.path() == CLINIC_PROFILE_PATH {
+                format!("public:{peer}")
+            } else {
+                format!("peer:{peer}")
+            }
+        }
+    }
+}
+
+/// Middleware que aplica el límite de peticiones. Debe ir dentro del de
+/// autenticación para conocer los `Claims`.
+pub async fn rate_limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let decision = match req.app_data::<web::Data<RateLimiter>>() {
+        Some(limiter) if !EXEMPT_PATHS.contains(&req.path()) => limiter.consume(&principal(&req)),
+        _ => None,
+    };
+
+    let Some(decision) = decision else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+
+    if !decision.allowed {
+        tracing::warn!(
+            "Límite de peticiones excedido por {} en {}",
+            principal(&req),
+            req.path()
+        );
+        if let Some(metrics) = req.app_data::<web::Data<Metrics>>() {
+            metrics.record_rate_limited();
+        }
+
+        let mut response = ApiError::TooManyRequests(format!(
+            "Demasiadas peticiones, intenta de nuevo en {} segundos",
+            decision.retry_after_secs
+        ))
+        .error_response();
+        decision.apply_headers(response.headers_mut());
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    let mut response = next.call(req).await?;
+    decision.apply_headers(response.headers_mut());
+    Ok(response.map_into_left_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::enums::UserRole;
+    use actix_web::test::TestRequest;
+
+    fn from(peer: &str, path: &str) -> ServiceRequest {
+        TestRequest::with_uri(path)
+            .peer_addr(peer.parse().unwrap())
+            .to_srv_request()
+    }
+
+    #[test]
+    fn requests_without_claims_are_keyed_by_peer() {
+        let a = principal(&from("10.0.0.1:5000", "/api/clients"));
+        let b = principal(&from("10.0.0.2:5000", "/api/clients"));
+        assert_ne!(a, b);
+        assert_eq!(a, principal(&from("10.0.0.1:6000", "/api/clients")));
+        assert_ne!(a, principal(&from("10.0.0.1:5000", CLINIC_PROFILE_PATH)));
+    }
+
+    #[test]
+    fn users_are_keyed_by_their_id() {
+        let req = from("10.0.0.1:5000", "/api/clients");
+        req.extensions_mut().insert(Claims {
+            sub: 7,
+            role: UserRole::Assistant,
+            iat: 0,
+            exp: usize::MAX,
+            api_key_id: None,
+            impersonator: None,
+        });
+        assert_eq!(principal(&req), "user:7");
+    }
+}
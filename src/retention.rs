@@ -0,0 +1,299 @@
+//! Depuración periódica de datos operativos antiguos.
+//!
+//! Solo abarca las tablas de [`POLICIES`]; los registros clínicos
+//! (`medical_records`, `patient_procedures`) nunca entran en alcance.
+
+use crate::clinic;
+use crate::errors::ApiError;
+use crate::jobs::JobRegistry;
+use crate::models::admin::{RetentionPolicyReport, RetentionReport};
+use crate::models::job::JobStatus;
+use chrono::{DateTime, NaiveTime, Utc};
+use sqlx::PgPool;
+use std::env;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+
+/// Tipo de trabajo registrado en `/jobs`
+pub const JOB_KIND: &str = "retention";
+
+/// Filas borradas por transacción, para no retener bloqueos por mucho tiempo
+const BATCH_SIZE: i64 = 500;
+
+/// Política de retención de una tabla.
+///
+/// `condition` es el filtro SQL sobre `t`; `$1` es la fecha de corte.
+pub struct RetentionPolicy {
+    pub name: &'static str,
+    pub description: &'static str,
+    env_var: &'static str,
+    table: &'static str,
+    condition: &'static str,
+}
+
+/// Políticas disponibles; cada una se activa con su variable de entorno en días
+pub const POLICIES: &[RetentionPolicy] = &[
+    RetentionPolicy {
+        name: "canceled_appointments",
+        description: "Citas canceladas cuyo inicio es anterior al corte (con sus notas)",
+        env_var: "CANCELED_APPOINTMENT_RETENTION_DAYS",
+        table: "appointments",
+        condition: "t.status = 'canceled' AND t.start_time < $1",
+    },
+    // Misma variable: archivar una cita no la saca de la retención
+    RetentionPolicy {
+        name: "archived_canceled_appointments",
+        description: "Citas canceladas archivadas cuyo inicio es anterior al corte",
+        env_var: "CANCELED_APPOINTMENT_RETENTION_DAYS",
+        table: "appointments_archive",
+        condition: "t.status = 'canceled' AND t.start_time < $1",
+    },
+    RetentionPolicy {
+        name: "digest_deliveries",
+        description: "Bitácora de envíos del resumen diario anterior al corte",
+        env_var: "DIGEST_DELIVERY_RETENTION_DAYS",
+        table: "digest_deliveries",
+        condition: "t.attempted_at < $1",
+    },
+    RetentionPolicy {
+        name: "notification_log",
+        description: "Bitácora de notificaciones con intento anterior al corte",
+        env_var: "NOTIFICATION_LOG_RETENTION_DAYS",
+        table: "notification_log",
+        condition: "t.attempted_at < $1",
+    },
+];
+
+impl RetentionPolicy {
+    /// Días de retención configurados; `None` si la política está desactivada
+    pub fn retention_days(&self) -> Option<i64> {
+        env::var(self.env_var)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .filter(|days: &i64| *days > 0)
+    }
+
+    fn cutoff(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.retention_days()
+            .map(|days| now - chrono::Duration::days(days))
+    }
+}
+
+/// Directorio donde se archivan las filas como NDJSON antes de borrarlas
+fn archive_dir() -> Option<PathBuf> {
+    env::var("RETENTION_ARCHIVE_DIR")
+        .ok()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+/// Hora local de ejecución (`RETENTION_TIME`, default: 03:00; `off` la desactiva)
+fn scheduled_time() -> Option<NaiveTime> {
+    match env::var("RETENTION_TIME") {
+        Ok(value) if value.eq_ignore_ascii_case("off") => None,
+        Ok(value) => NaiveTime::parse_from_str(&value, "%H:%M").ok(),
+        Err(_) => NaiveTime::from_hms_opt(3, 0, 0),
+    }
+}
+
+/// Programa la depuración diaria
+pub fn spawn_scheduler(pool: PgPool, jobs: actix_web::web::Data<JobRegistry>) {
+    let Some(run_at) = scheduled_time() else {
+        tracing::info!("Depuración de datos desactivada");
+        return;
+    };
+
+    actix_web::rt::spawn(async move {
+        loop {
+            let next = clinic::next_occurrence(run_at);
+            tracing::info!("Próxima depuración de datos: {}", next);
+            let wait = (next - Utc::now()).to_std().unwrap_or_default();
+            actix_web::rt::time::sleep(wait).await;
+
+            if let Err(e) = start(&pool, &jobs).await {
+                tracing::error!("No se pudo iniciar la depuración de datos: {:?}", e);
+            }
+        }
+    });
+}
+
+/// Cuenta las filas que cada política borraría en este momento
+pub async fn preview(pool: &PgPool) -> Result<RetentionReport, ApiError> {
+    let now = Utc::now();
+    let mut policies = Vec::with_capacity(POLICIES.len());
+
+    for policy in POLICIES {
+        let cutoff = policy.cutoff(now);
+        let eligible = match cutoff {
+            Some(cutoff) => sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM {} t WHERE {}",
+                policy.table, policy.condition
+            ))
+            .bind(cutoff)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| {
+                ApiError::internal(
+                    "Error al calcular la depuración de datos",
+                    format!("política {}: {e}", policy.name),
+                )
+            })?,
+            None => 0,
+        };
+
+        policies.push(RetentionPolicyReport {
+            policy: policy.name.to_string(),
+            description: policy.description.to_string(),
+            retention_days: policy.retention_days(),
+            cutoff,
+            eligible,
+        });
+    }
+
+    Ok(RetentionReport {
+        generated_at: now,
+        archive_dir: archive_dir().map(|dir| dir.display().to_string()),
+        policies,
+    })
+}
+
+/// Registra el trabajo y lo ejecuta en segundo plano
+pub async fn start(
+    pool: &PgPool,
+    jobs: &actix_web::web::Data<JobRegistry>,
+) -> Result<JobStatus, ApiError> {
+    let report = preview(pool).await?;
+    let total = report.policies.iter().map(|policy| policy.eligible).sum();
+
+    let job = jobs.start(JOB_KIND, total);
+    tracing::info!("Depurando {} filas (trabajo {})", total, job.id);
+
+    let pool = pool.clone();
+    let registry = jobs.clone();
+    let job_id = job.id;
+    actix_web::rt::spawn(async move {
+        let archive = archive_dir();
+        let mut processed = 0;
+        let mut summary = Vec::new();
+
+        for (policy, report) in POLICIES.iter().zip(&report.policies) {
+            let Some(cutoff) = report.cutoff else {
+                continue;
+            };
+
+            match purge(&pool, policy, cutoff, archive.as_ref(), |deleted| {
+                registry.progress(job_id, processed + deleted);
+            })
+            .await
+            {
+                Ok(deleted) => {
+                    processed += deleted;
+                    summary.push(format!("{}: {}", policy.name, deleted));
+                }
+                Err(e) => {
+                    tracing::error!("Depuración de {}: {}", policy.name, e);
+                    registry.finish(
+                        job_id,
+                        Err(format!("Falló la política {}: {}", policy.name, e)),
+                    );
+                    return;
+                }
+            }
+        }
+
+        let summary = if summary.is_empty() {
+            "Sin políticas activas".to_string()
+        } else {
+            format!("Filas borradas: {}", summary.join(", "))
+        };
+        registry.finish(job_id, Ok(summary));
+    });
+
+    Ok(job)
+}
+
+/// Borra por lotes las filas de una política y devuelve cuántas se borraron.
+///
+/// Cada lote se archiva antes de confirmar su transacción, de modo que una
+/// fila nunca se borra sin quedar escrita en el archivo.
+async fn purge(
+    pool: &PgPool,
+    policy: &RetentionPolicy,
+    cutoff: DateTime<Utc>,
+    archive: Option<&PathBuf>,
+    on_progress: impl Fn(i64),
+) -> Result<i64, String> {
+    let query = format!(
+        r#"
+        WITH batch AS (
+            SELECT t.id FROM {table} t
+            WHERE {condition}
+            ORDER BY t.id
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+        )
+        DELETE FROM {table} t
+        USING batch
+        WHERE t.id = batch.id
+        RETURNING to_jsonb(t)
+        "#,
+        table = policy.table,
+        condition = policy.condition
+    );
+    let archive_path = archive.map(|dir| {
+        dir.join(format!(
+            "{}-{}.ndjson",
+            policy.name,
+            clinic::today().format("%Y%m%d")
+        ))
+    });
+
+    let mut deleted = 0;
+    loop {
+        let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+        let rows: Vec<serde_json::Value> = sqlx::query_scalar(&query)
+            .bind(cutoff)
+            .bind(BATCH_SIZE)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if rows.is_empty() {
+            return Ok(deleted);
+        }
+
+        if let Some(path) = &archive_path {
+            append_ndjson(path, &rows)
+                .await
+                .map_err(|e| format!("No se pudo archivar en {}: {}", path.display(), e))?;
+        }
+        tx.commit().await.map_err(|e| e.to_string())?;
+
+        deleted += rows.len() as i64;
+        on_progress(deleted);
+
+        if (rows.len() as i64) < BATCH_SIZE {
+            return Ok(deleted);
+        }
+    }
+}
+
+async fn append_ndjson(path: &PathBuf, rows: &[serde_json::Value]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+
+    let mut buffer = Vec::new();
+    for row in rows {
+        serde_json::to_writer(&mut buffer, row)?;
+        buffer.push(b'\n');
+    }
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(&buffer).await?;
+    file.sync_data().await
+}
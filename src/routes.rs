@@ -1,8 +1,94 @@
 use crate::handlers;
+use crate::metrics;
 use actix_web::web;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.configure(metrics::config); // /health y /metrics, fuera de /api
     cfg.service(
         web::scope("/api").configure(handlers::config), // Puedes agregar middleware global aquí
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventBus;
+    use crate::models::enums::UserRole;
+    use crate::testing::{as_user, claims};
+    use actix_web::{App, test};
+    use sqlx::PgPool;
+
+    #[sqlx::test(migrations = false, fixtures(path = "../init.sql", scripts("0", "1")))]
+    async fn invalid_enum_filters_are_rejected(pool: PgPool) {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(EventBus::default()))
+                .app_data(crate::errors::query_config())
+                .configure(config),
+        )
+        .await;
+
+        for uri in [
+            "/api/appointments?status=sheduled",
+            "/api/appointments?booking_source=fax",
+            "/api/patients?species=Dragon",
+            "/api/patients?gender=Neutral",
+            "/api/users?role=Owner",
+            "/api/procedures?procedure_type=Magic",
+            "/api/species_protocols?species=dragon",
+            "/api/stats?species=dragon",
+            "/api/stats/procedures/detail?procedure_type=Vaccine&species=dragon",
+            "/api/stats/procedures/detail?procedure_type=Magic",
+        ] {
+            let request = test::TestRequest::get().uri(uri).to_request();
+            let response =
+                test::call_service(&app, as_user(request, claims(4, UserRole::Admin))).await;
+            assert_eq!(response.status(), 400, "{uri}");
+        }
+
+        // Los valores válidos, integrados o del catálogo, siguen filtrando
+        for uri in [
+            "/api/patients?species=Dog",
+            "/api/stats?species=dog&type_=procedures",
+        ] {
+            let request = test::TestRequest::get().uri(uri).to_request();
+            let response =
+                test::call_service(&app, as_user(request, claims(4, UserRole::Admin))).await;
+            assert_eq!(response.status(), 200, "{uri}");
+        }
+    }
+
+    #[sqlx::test(migrations = false, fixtures(path = "../init.sql", scripts("0", "1")))]
+    async fn invalid_or_missing_ids_are_not_found(pool: PgPool) {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(EventBus::default()))
+                .app_data(crate::errors::query_config())
+                .configure(config),
+        )
+        .await;
+
+        // Cero, negativo, fuera de `i32` y un `i32` válido que no existe
+        for resource in ["patients", "clients", "appointments"] {
+            for id in ["0", "-5", "99999999999", "2147483647"] {
+                let uri = format!("/api/{resource}/{id}");
+                let requests = [
+                    test::TestRequest::get().uri(&uri),
+                    // El cuerpo inválido no importa: primero se responde que no existe
+                    test::TestRequest::put()
+                        .uri(&uri)
+                        .set_json(serde_json::json!({ "name": "", "reason": "" })),
+                    test::TestRequest::delete().uri(&uri),
+                ];
+                for request in requests {
+                    let request = as_user(request.to_request(), claims(4, UserRole::Admin));
+                    let method = request.method().clone();
+                    let response = test::call_service(&app, request).await;
+                    assert_eq!(response.status(), 404, "{method} {uri}");
+                }
+            }
+        }
+    }
+}
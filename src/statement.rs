@@ -0,0 +1,241 @@
+use crate::clinic;
+use crate::errors::ApiError;
+use crate::models::client::{
+    ClientStatement, StatementEntry, StatementEntryKind, StatementPetSubtotal,
+};
+use crate::pdf::{PdfWriter, Style};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+/// Un estado de cuenta anual de una familia numerosa cabe de sobra
+const STATEMENT_MAX_PAGES: usize = 50;
+
+/// Reúne las consultas completadas y los procedimientos ya aplicados a las
+/// mascotas del cliente entre `start_date` y `end_date` (días locales,
+/// inclusive), con el precio de catálogo de cada procedimiento.
+///
+/// Los procedimientos sin precio se listan pero no suman; las consultas no
+/// generan cargo propio. 404 si el cliente no existe y 400 si el periodo está
+/// invertido.
+pub async fn build(
+    pool: &PgPool,
+    client_id: i32,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<ClientStatement, ApiError> {
+    if end_date < start_date {
+        return Err(ApiError::ValidationError(
+            "end_date debe ser igual o posterior a start_date".into(),
+        ));
+    }
+
+    let client_name = sqlx::query_scalar!("SELECT name FROM clients WHERE id = $1", client_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(ApiError::NotFound("El cliente no existe".into()))?;
+
+    let (range_start, _) = clinic::day_bounds(start_date);
+    let (_, range_end) = clinic::day_bounds(end_date);
+    let appointments = sqlx::query!(
+        r#"
+        SELECT
+            a.start_time as "start_time!: DateTime<Utc>",
+            a.reason,
+            p.id as patient_id,
+            p.name as patient_name
+        FROM appointments a
+        JOIN patients p ON p.id = a.patient_id
+        WHERE p.client_id = $1
+          AND a.status = 'completed'
+          AND a.start_time >= $2
+          AND a.start_time < $3
+        ORDER BY a.start_time, a.id
+        "#,
+        client_id,
+        range_start,
+        range_end
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ApiError::internal("Error al obtener las consultas del estado de cuenta", e))?;
+
+    // Los procedimientos programados a futuro todavía no se cobran
+    let procedures = sqlx::query!(
+        r#"
+        SELECT
+            pp.date,
+            pr.name,
+            pr.price,
+            p.id as patient_id,
+            p.name as patient_name
+        FROM patient_procedures pp
+        JOIN patients p ON p.id = pp.patient_id
+        JOIN procedures pr ON pr.id = pp.procedure_id
+        WHERE p.client_id = $1
+          AND pp.date >= $2
+          AND pp.date <= $3
+        ORDER BY pp.date, pp.id
+        "#,
+        client_id,
+        start_date,
+        end_date.min(clinic::today())
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        ApiError::internal(
+            "Error al obtener los procedimientos del estado de cuenta",
+            e,
+        )
+    })?;
+
+    let tz = clinic::timezone();
+    let mut entries: Vec<StatementEntry> = appointments
+        .into_iter()
+        .map(|row| StatementEntry {
+            date: row.start_time.with_timezone(&tz).date_naive(),
+            kind: StatementEntryKind::Appointment,
+            patient_id: row.patient_id,
+            patient_name: row.patient_name,
+            description: row.reason,
+            price: None,
+        })
+        .chain(procedures.into_iter().map(|row| StatementEntry {
+            date: row.date,
+            kind: StatementEntryKind::Procedure,
+            patient_id: row.patient_id,
+            patient_name: row.patient_name,
+            description: row.name,
+            price: row.price,
+        }))
+        .collect();
+    // Orden estable: en un mismo día la consulta va antes que sus procedimientos
+    entries.sort_by_key(|entry| entry.date);
+
+    let mut pets: HashMap<i32, StatementPetSubtotal> = HashMap::new();
+    for entry in &entries {
+        let pet = pets
+            .entry(entry.patient_id)
+            .or_insert_with(|| StatementPetSubtotal {
+                patient_id: entry.patient_id,
+                patient_name: entry.patient_name.clone(),
+                subtotal: BigDecimal::from(0),
+                unpriced_procedures: 0,
+            });
+        match (&entry.kind, &entry.price) {
+            (StatementEntryKind::Procedure, Some(price)) => pet.subtotal += price,
+            (StatementEntryKind::Procedure, None) => pet.unpriced_procedures += 1,
+            (StatementEntryKind::Appointment, _) => {}
+        }
+    }
+    let mut pets: Vec<StatementPetSubtotal> = pets
+        .into_values()
+        .map(|pet| StatementPetSubtotal {
+            subtotal: pet.subtotal.with_scale(2),
+            ..pet
+        })
+        .collect();
+    pets.sort_by(|a, b| {
+        a.patient_name
+            .cmp(&b.patient_name)
+            .then(a.patient_id.cmp(&b.patient_id))
+    });
+
+    let total = pets
+        .iter()
+        .fold(BigDecimal::from(0), |total, pet| total + &pet.subtotal)
+        .with_scale(2);
+    let unpriced_procedures = pets.iter().map(|pet| pet.unpriced_procedures).sum();
+
+    Ok(ClientStatement {
+        client_id,
+        client_name,
+        start_date,
+        end_date,
+        entries,
+        pets,
+        total,
+        unpriced_procedures,
+    })
+}
+
+fn date(date: NaiveDate) -> String {
+    date.format("%d/%m/%Y").to_string()
+}
+
+fn money(amount: &BigDecimal) -> String {
+    format!("${:.2}", amount)
+}
+
+/// El mismo estado de cuenta como PDF para entregarlo al cliente
+pub fn render_pdf(statement: &ClientStatement) -> Vec<u8> {
+    let period = format!(
+        "{} al {}",
+        date(statement.start_date),
+        date(statement.end_date)
+    );
+    let mut pdf = PdfWriter::new(
+        format!("Estado de cuenta - {} - {}", statement.client_name, period),
+        STATEMENT_MAX_PAGES,
+    );
+
+    pdf.text(Style::Title, "Estado de cuenta");
+    pdf.gap(6.0);
+    pdf.text(Style::Body, &format!("Cliente: {}", statement.client_name));
+    pdf.text(Style::Body, &format!("Periodo: {period}"));
+
+    pdf.gap(10.0);
+    pdf.text(Style::Heading, "Movimientos");
+    if statement.entries.is_empty() {
+        pdf.text(Style::Body, "Sin consultas ni procedimientos en el periodo");
+    }
+    for entry in &statement.entries {
+        let line = match entry.kind {
+            StatementEntryKind::Appointment => format!(
+                "{}  {}  Consulta: {}",
+                date(entry.date),
+                entry.patient_name,
+                entry.description
+            ),
+            StatementEntryKind::Procedure => format!(
+                "{}  {}  {}  {}",
+                date(entry.date),
+                entry.patient_name,
+                entry.description,
+                entry.price.as_ref().map_or("—*".into(), money)
+            ),
+        };
+        pdf.text(Style::Body, &line);
+    }
+
+    if !statement.pets.is_empty() {
+        pdf.gap(10.0);
+        pdf.text(Style::Heading, "Subtotal por mascota");
+        for pet in &statement.pets {
+            pdf.text(
+                Style::Body,
+                &format!("{}: {}", pet.patient_name, money(&pet.subtotal)),
+            );
+        }
+    }
+
+    pdf.gap(6.0);
+    pdf.text(
+        Style::Heading,
+        &format!("Total: {}", money(&statement.total)),
+    );
+    if statement.unpriced_procedures > 0 {
+        pdf.gap(6.0);
+        pdf.text(
+            Style::Body,
+            &format!(
+                "* {} procedimiento(s) sin precio de catálogo; no se incluyen en los totales.",
+                statement.unpriced_procedures
+            ),
+        );
+    }
+
+    pdf.finish()
+}
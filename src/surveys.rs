@@ -0,0 +1,171 @@
+//! Encuestas de satisfacción que se envían al cliente cuando se completa una cita.
+//!
+//! El enlace lleva un token firmado con `JWT_SECRET` que solo identifica la
+//! encuesta; no sirve como credencial de la API ni expone datos de la cita.
+
+use crate::errors::ApiError;
+use crate::events::{DomainEvent, EventBus};
+use crate::notifications;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::env;
+use tokio::sync::broadcast;
+
+/// Días que el enlace de la encuesta sigue vigente
+const TOKEN_DAYS: i64 = 30;
+
+/// Plantilla registrada en la bitácora de notificaciones
+const TEMPLATE: &str = "satisfaction_survey";
+
+/// Contenido del token. No tiene `sub` ni `role`, así que no pasa como JWT de
+/// usuario, y un JWT de usuario no pasa como token de encuesta.
+#[derive(Debug, Serialize, Deserialize)]
+struct SurveyClaims {
+    survey_id: i32,
+    exp: usize,
+}
+
+fn secret() -> Result<String, ApiError> {
+    crate::auth::jwt_secret()
+}
+
+/// Firma el token de una encuesta
+pub fn create_token(survey_id: i32) -> Result<String, ApiError> {
+    let expiration = chrono::Utc::now() + chrono::Duration::days(TOKEN_DAYS);
+    encode(
+        &Header::default(),
+        &SurveyClaims {
+            survey_id,
+            exp: expiration.timestamp() as usize,
+        },
+        &EncodingKey::from_secret(secret()?.as_bytes()),
+    )
+    .map_err(|e| ApiError::internal("Error al firmar el token", e))
+}
+
+/// Obtiene el ID de la encuesta. Un token vencido responde 410; uno inválido, 404.
+pub fn decode_token(token: &str) -> Result<i32, ApiError> {
+    decode::<SurveyClaims>(
+        token,
+        &DecodingKey::from_secret(secret()?.as_bytes()),
+        &Validation::new(Algorithm::HS256),
+    )
+    .map(|data| data.claims.survey_id)
+    .map_err(|e| match e.kind() {
+        ErrorKind::ExpiredSignature => ApiError::Gone("El enlace de la encuesta venció".into()),
+        _ => ApiError::NotFound("La encuesta no existe".into()),
+    })
+}
+
+/// Enlace que recibe el cliente (`SURVEY_BASE_URL` + token)
+fn survey_link(token: &str) -> String {
+    let base = env::var("SURVEY_BASE_URL")
+        .unwrap_or_else(|_| "http://localhost:4000/api/survey/".to_string());
+    format!("{}/{}", base.trim_end_matches('/'), token)
+}
+
+/// Envía la encuesta de cada cita completada
+pub fn spawn_sender(pool: PgPool, events: &EventBus) {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(DomainEvent::AppointmentCompleted { appointment_id }) => {
+                    if let Err(e) = send_survey(&pool, appointment_id).await {
+                        tracing::error!(
+                            "No se pudo enviar la encuesta de la cita {}: {:?}",
+                            appointment_id,
+                            e
+                        );
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Envío de encuestas atrasado, {} eventos omitidos", skipped)
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Registra la encuesta de la cita y la envía al correo del cliente.
+///
+/// Una cita tiene una sola encuesta: si vuelve a completarse no se reenvía.
+async fn send_survey(pool: &PgPool, appointment_id: i32) -> Result<(), ApiError> {
+    let Some(survey_id) = sqlx::query_scalar!(
+        r#"
+        INSERT INTO satisfaction_surveys (appointment_id)
+        VALUES ($1)
+        ON CONFLICT (appointment_id) DO NOTHING
+        RETURNING id
+        "#,
+        appointment_id
+    )
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(());
+    };
+
+    // Correo del contacto principal o, si no tiene, el del cliente
+    let recipient = sqlx::query!(
+        r#"
+        SELECT
+            c.id as client_id,
+            c.name,
+            COALESCE(cc.email, c.email) as email,
+            p.name as "patient_name?"
+        FROM appointments a
+        JOIN clients c ON c.id = a.client_id
+        LEFT JOIN patients p ON p.id = a.patient_id
+        LEFT JOIN LATERAL (
+            SELECT email
+            FROM client_contacts
+            WHERE client_id = c.id AND is_primary AND NULLIF(TRIM(email), '') IS NOT NULL
+            LIMIT 1
+        ) cc ON TRUE
+        WHERE a.id = $1
+        "#,
+        appointment_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some((client_id, name, email, patient_name)) =
+        recipient.and_then(|row| Some((row.client_id, row.name, row.email?, row.patient_name)))
+    else {
+        tracing::info!(
+            "La cita {} no tiene un correo de cliente; encuesta sin enviar",
+            appointment_id
+        );
+        return Ok(());
+    };
+
+    let token = create_token(survey_id)?;
+    let visit = patient_name.map_or_else(
+        || "tu visita".to_string(),
+        |patient| format!("la visita de {patient}"),
+    );
+    let body = format!(
+        "Hola {name},\n\n¿Cómo fue {visit}? Califícala del 1 al 5 en este enlace:\n{}\n\nGracias por tu confianza.",
+        survey_link(&token)
+    );
+
+    notifications::deliver(
+        pool,
+        notifications::Email {
+            template: TEMPLATE,
+            to: &email,
+            subject: "¿Cómo fue tu visita?",
+            body,
+            client_id: Some(client_id),
+            appointment_id: Some(appointment_id),
+            secret: false,
+        },
+    )
+    .await
+    .map_err(|e| ApiError::internal("Error al enviar la encuesta", e))
+}
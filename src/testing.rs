@@ -0,0 +1,84 @@
+//! Utilidades compartidas por las pruebas.
+//!
+//! Las pruebas con base de datos usan `#[sqlx::test]`, que crea una base
+//! temporal en el servidor de `DATABASE_URL`, y cargan el esquema y los datos
+//! de ejemplo como fixtures:
+//! `#[sqlx::test(migrations = false, fixtures(path = "<ruta>/init.sql", scripts("0", "1")))]`.
+//! En los datos de ejemplo (1.sql) los usuarios 1 y 2 son veterinarios, el 3
+//! asistente y el 4 administrador.
+
+use crate::auth::Claims;
+use crate::events::EventBus;
+use crate::models::enums::UserRole;
+use actix_web::{App, HttpMessage, test, web};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::subscriber::DefaultGuard;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::{Layer, Registry};
+
+/// Claims de un token de usuario vigente
+pub fn claims(sub: i32, role: UserRole) -> Claims {
+    Claims {
+        sub,
+        role,
+        iat: 0,
+        exp: usize::MAX,
+        api_key_id: None,
+        impersonator: None,
+    }
+}
+
+/// Petición autenticada como lo haría el middleware de autenticación
+/// (ej: `as_user(TestRequest::get().uri("/users").to_request(), claims)`)
+pub fn as_user<R: HttpMessage>(request: R, claims: Claims) -> R {
+    request.extensions_mut().insert(claims);
+    request
+}
+
+/// Cuenta las consultas que sqlx registra (target `sqlx::query`)
+#[derive(Clone, Default)]
+pub struct QueryCounter(Arc<AtomicUsize>);
+
+impl QueryCounter {
+    /// Empieza a contar en el hilo actual hasta que se suelte el guard;
+    /// `#[sqlx::test]` corre en un runtime de un solo hilo
+    pub fn install() -> (Self, DefaultGuard) {
+        let counter = Self::default();
+        let guard = tracing::subscriber::set_default(Registry::default().with(counter.clone()));
+        (counter, guard)
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl<S: tracing::Subscriber> Layer<S> for QueryCounter {
+    fn on_event(&self, event: &tracing::Event<'_>, _: Context<'_, S>) {
+        if event.metadata().target() == "sqlx::query" {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Envía dos `DELETE` simultáneos a `uri` (ej: `/api/breeds/1`) con todas las
+/// rutas de la API y devuelve los dos códigos de respuesta ordenados
+pub async fn concurrent_deletes(pool: PgPool, uri: &str, claims: Claims) -> [u16; 2] {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool))
+            .app_data(web::Data::new(EventBus::default()))
+            .configure(crate::routes::config),
+    )
+    .await;
+    let delete = || {
+        let request = test::TestRequest::delete().uri(uri).to_request();
+        test::call_service(&app, as_user(request, claims.clone()))
+    };
+    let (first, second) = futures::join!(delete(), delete());
+    let mut statuses = [first.status().as_u16(), second.status().as_u16()];
+    statuses.sort_unstable();
+    statuses
+}
@@ -0,0 +1,420 @@
+use crate::models::admin::{
+    AppointmentArchiveQuery, AppointmentArchiveReport, BreedQualityReport, DailyDigestRequest,
+    DemoDatasetRequest, IntegrityCheckResult, IntegrityQuery, IntegrityReport,
+    LimitExceededResponse, LimitUsage, MaintenanceStatus, MissingBreedBySpecies,
+    NotificationLogEntry, NotificationLogFilter, RetentionPolicyReport, RetentionQuery,
+    RetentionReport, UnassignedNameCluster, UnusedBreed, UpdateMaintenance, UsageReport,
+};
+use crate::models::api_key::{
+    ApiKeyFilter, ApiKeyResponse, CreatedApiKeyResponse, NewApiKey, UpdateApiKey,
+};
+use crate::models::appointment::{
+    AppointmentConflictDetails, AppointmentFilter, AppointmentListResponse,
+    AppointmentPublicResponse, AppointmentResponse, AppointmentStatusCounts, AvailabilityQuery,
+    CalendarAppointment, CalendarDay, CalendarEntry, CalendarQuery, CalendarRangeQuery,
+    CalendarVeterinarian, NewAppointment, NextAvailableQuery, ReassignAppointments,
+    ReassignmentResponse, SkippedReassignment, SuggestedSlot, TimeSlot, UpdateAppointment,
+    UpdateAppointmentQuery, UpdateAppointmentStatus, VeterinarianAvailability,
+};
+use crate::models::appointment_note::{
+    AppointmentNoteFilter, AppointmentNoteResponse, NewAppointmentNote,
+};
+use crate::models::breed::{BreedResponse, BreedUpsertResponse, NewBreed, PaginationParams};
+use crate::models::client::{
+    ClientFilter, ClientResponse, ClientStatement, NewClient, StatementEntry, StatementEntryKind,
+    StatementFormat, StatementPetSubtotal, StatementQuery, UpdateClient,
+};
+use crate::models::client_contact::{ClientContactResponse, NewClientContact, UpdateClientContact};
+use crate::models::consent::{ConsentDocumentQuery, ConsentResponse, NewDigitalConsent};
+use crate::models::custom_field::{
+    CustomFieldDefinitionFilter, CustomFieldDefinitionResponse, NewCustomFieldDefinition,
+    UpdateCustomFieldDefinition,
+};
+use crate::models::deletion::{DeletePreview, DeletionEffect, DependencyCount};
+use crate::models::enums::{
+    AnimalGender, AnimalSpecies, AppointmentStatus, BookingSource, CustomFieldEntity,
+    CustomFieldType, NotificationChannel, NotificationOutcome, ProcedureType, ReferralDirection,
+    TaskStatus, UserRole,
+};
+use crate::models::event::{AppointmentEventFilter, AppointmentStreamEvent};
+use crate::models::hospitalization::{
+    ActiveHospitalization, DischargeHospitalization, HospitalizationResponse, NewHospitalization,
+    UpdateHospitalization,
+};
+use crate::models::household::{
+    HouseholdMember, HouseholdPatient, HouseholdResponse, NewHousehold,
+};
+use crate::models::intake::{IntakeResponse, NewIntake};
+use crate::models::job::{JobState, JobStatus};
+use crate::models::lobby::{ClinicProfile, LobbyEntry, LobbyFeed};
+use crate::models::medical_record::{
+    MedicalRecordFilter, MedicalRecordResponse, NewMedicalRecord, UpdateMedicalRecord, VitalFlag,
+    VitalRange, VitalReading, VitalSign,
+};
+use crate::models::page::Page;
+use crate::models::patient::{
+    BirthdayQuery, BulkSetBreed, BulkSetBreedResponse, ClaimProcedure, CreatePatientQuery,
+    DuplicatePatientCandidate, DuplicatePatientFilter, DuplicatePatientPair, InsuranceClaim,
+    InsuranceClaimQuery, NewPatient, PatientBirthday, PatientDetailResponse, PatientFilter,
+    PatientHistoryResponse, PatientQuery, PatientResponse, UpdatePatient,
+};
+use crate::models::patient_procedure::{
+    BulkReschedule, BulkRescheduleQuery, BulkRescheduleResponse, CreatePatientProcedureQuery,
+    NewPatientProcedure, PatientProcedureFilter, PatientProcedureResponse,
+    PatientProcedureWithOwner, UpdatePatientProcedure,
+};
+use crate::models::procedure::{
+    CatalogProcedure, ImportAction, MergeStrategy, NewProcedure, ProcedureCatalog, ProcedureFilter,
+    ProcedureImportItem, ProcedureImportQuery, ProcedureImportResponse, ProcedureResponse,
+    UpdateProcedure,
+};
+use crate::models::record_template::{
+    NewRecordTemplate, RecordTemplateFilter, RecordTemplateResponse, UpdateRecordTemplate,
+};
+use crate::models::referral::{NewReferral, ReferralFilter, ReferralResponse};
+use crate::models::room::{NewRoom, RoomFilter, RoomResponse, UpdateRoom};
+use crate::models::settings::{ClinicHoursDay, ClinicHoursResponse, UpdateClinicHours};
+use crate::models::species::{NewSpecies, SpeciesResponse, UpdateSpecies};
+use crate::models::species_protocol::{
+    NewSpeciesProtocol, ProtocolApplicationResponse, ProtocolItem, ProtocolItemResponse,
+    SkippedProtocolItem, SpeciesProtocolFilter, SpeciesProtocolResponse, UpdateSpeciesProtocol,
+};
+use crate::models::statistic::{
+    AppointmentsByMonth, AppointmentsBySource, AppointmentsByStatus, PatientsBySpecies,
+    ProcedureActor, ProcedureDetailQuery, ProceduresByType, ReferralsByMonth, SatisfactionByMonth,
+    SatisfactionByVeterinarian, StatisticType, StatisticsQuery, StatisticsResponse, UserCounts,
+    VeterinarianReportQuery, VeterinarianStats,
+};
+use crate::models::survey::{SurveyAnswer, SurveyInfo};
+use crate::models::sync::{SyncChanges, SyncQuery, SyncResponse};
+use crate::models::treatment_task::{
+    CompleteTreatmentTask, DueTasksQuery, NewTreatmentTask, TreatmentTaskFilter,
+    TreatmentTaskResponse,
+};
+use crate::models::user::{
+    AccessToken, ChangePassword, ImpersonationResponse, LoginResponse, LoginUser, NewUser,
+    PasswordResetConfirm, PasswordResetRequest, RefreshRequest, RefreshResponse, UpdateUser,
+    UserFilter, UserResponse,
+};
+use std::{fs, io};
+use ts_rs::TS;
+
+/// Ruta por defecto del archivo de tipos que consume el frontend
+pub const DEFAULT_TYPES_PATH: &str = "docs/api-types.d.ts";
+
+const HEADER: &str = "\
+// Archivo generado con `cargo run -- --emit-types`. No editar a mano.
+//
+// Campos de actualización (Update*) con tipo `T | null | undefined`:
+//   - ausente (undefined): se conserva el valor actual
+//   - null: se borra el valor
+//   - T: se reemplaza el valor
+";
+
+macro_rules! declarations {
+    ($($ty:ty),* $(,)?) => {
+        vec![$(<$ty as TS>::decl()),*]
+    };
+}
+
+/// Genera las definiciones TypeScript de todos los payloads de la API
+pub fn render() -> String {
+    let declarations = declarations![
+        // Enums
+        UserRole,
+        AnimalSpecies,
+        AnimalGender,
+        AppointmentStatus,
+        BookingSource,
+        ProcedureType,
+        TaskStatus,
+        CustomFieldEntity,
+        CustomFieldType,
+        ReferralDirection,
+        NotificationChannel,
+        NotificationOutcome,
+        // Paginación
+        Page<AppointmentResponse>,
+        // Usuarios
+        NewUser,
+        UpdateUser,
+        UserResponse,
+        UserFilter,
+        LoginUser,
+        AccessToken,
+        LoginResponse,
+        RefreshRequest,
+        RefreshResponse,
+        PasswordResetRequest,
+        PasswordResetConfirm,
+        ChangePassword,
+        ImpersonationResponse,
+        // Llaves de API
+        NewApiKey,
+        UpdateApiKey,
+        ApiKeyResponse,
+        CreatedApiKeyResponse,
+        ApiKeyFilter,
+        // Clientes
+        NewClient,
+        UpdateClient,
+        ClientResponse,
+        ClientFilter,
+        StatementFormat,
+        StatementQuery,
+        StatementEntryKind,
+        StatementEntry,
+        StatementPetSubtotal,
+        ClientStatement,
+        NewClientContact,
+        UpdateClientContact,
+        ClientContactResponse,
+        // Hogares
+        NewHousehold,
+        HouseholdMember,
+        HouseholdPatient,
+        HouseholdResponse,
+        // Campos personalizados
+        NewCustomFieldDefinition,
+        UpdateCustomFieldDefinition,
+        CustomFieldDefinitionResponse,
+        CustomFieldDefinitionFilter,
+        // Razas
+        NewBreed,
+        BreedResponse,
+        BreedUpsertResponse,
+        PaginationParams,
+        // Pacientes
+        NewPatient,
+        UpdatePatient,
+        PatientResponse,
+        PatientFilter,
+        PatientQuery,
+        PatientDetailResponse,
+        PatientHistoryResponse,
+        CreatePatientQuery,
+        DuplicatePatientCandidate,
+        DuplicatePatientPair,
+        DuplicatePatientFilter,
+        BirthdayQuery,
+        PatientBirthday,
+        InsuranceClaimQuery,
+        ClaimProcedure,
+        InsuranceClaim,
+        BulkSetBreed,
+        BulkSetBreedResponse,
+        // Citas
+        NewAppointment,
+        UpdateAppointment,
+        UpdateAppointmentQuery,
+        UpdateAppointmentStatus,
+        AppointmentResponse,
+        AppointmentPublicResponse,
+        AppointmentFilter,
+        AppointmentStatusCounts,
+        AppointmentListResponse,
+        CalendarQuery,
+        CalendarAppointment,
+        CalendarVeterinarian,
+        CalendarDay,
+        CalendarRangeQuery,
+        CalendarEntry,
+        TimeSlot,
+        AvailabilityQuery,
+        VeterinarianAvailability,
+        NextAvailableQuery,
+        SuggestedSlot,
+        AppointmentConflictDetails,
+        ReassignAppointments,
+        SkippedReassignment,
+        ReassignmentResponse,
+        NewAppointmentNote,
+        AppointmentNoteResponse,
+        AppointmentNoteFilter,
+        AppointmentEventFilter,
+        AppointmentStreamEvent,
+        LobbyEntry,
+        LobbyFeed,
+        ClinicProfile,
+        // Alta rápida
+        NewIntake,
+        IntakeResponse,
+        // Historial médico
+        NewMedicalRecord,
+        UpdateMedicalRecord,
+        MedicalRecordResponse,
+        MedicalRecordFilter,
+        VitalSign,
+        VitalFlag,
+        VitalReading,
+        VitalRange,
+        NewRecordTemplate,
+        UpdateRecordTemplate,
+        RecordTemplateResponse,
+        RecordTemplateFilter,
+        // Procedimientos
+        NewProcedure,
+        UpdateProcedure,
+        ProcedureResponse,
+        ProcedureFilter,
+        CatalogProcedure,
+        ProcedureCatalog,
+        MergeStrategy,
+        ProcedureImportQuery,
+        ImportAction,
+        ProcedureImportItem,
+        ProcedureImportResponse,
+        NewPatientProcedure,
+        UpdatePatientProcedure,
+        PatientProcedureResponse,
+        PatientProcedureWithOwner,
+        PatientProcedureFilter,
+        CreatePatientProcedureQuery,
+        BulkReschedule,
+        BulkRescheduleQuery,
+        BulkRescheduleResponse,
+        NewDigitalConsent,
+        ConsentDocumentQuery,
+        ConsentResponse,
+        // Salas
+        NewRoom,
+        UpdateRoom,
+        RoomResponse,
+        RoomFilter,
+        // Configuración
+        ClinicHoursDay,
+        UpdateClinicHours,
+        ClinicHoursResponse,
+        // Referencias
+        NewReferral,
+        ReferralFilter,
+        ReferralResponse,
+        // Hospitalizaciones
+        NewHospitalization,
+        UpdateHospitalization,
+        DischargeHospitalization,
+        HospitalizationResponse,
+        ActiveHospitalization,
+        NewTreatmentTask,
+        CompleteTreatmentTask,
+        DueTasksQuery,
+        TreatmentTaskFilter,
+        TreatmentTaskResponse,
+        // Catálogo de especies
+        SpeciesResponse,
+        NewSpecies,
+        UpdateSpecies,
+        // Protocolos por especie
+        ProtocolItem,
+        NewSpeciesProtocol,
+        UpdateSpeciesProtocol,
+        ProtocolItemResponse,
+        SpeciesProtocolResponse,
+        SpeciesProtocolFilter,
+        SkippedProtocolItem,
+        ProtocolApplicationResponse,
+        // Encuestas de satisfacción
+        SurveyInfo,
+        SurveyAnswer,
+        // Sincronización
+        SyncQuery,
+        SyncChanges<ClientResponse>,
+        SyncResponse,
+        // Trabajos en segundo plano y administración
+        JobState,
+        JobStatus,
+        DemoDatasetRequest,
+        DailyDigestRequest,
+        IntegrityQuery,
+        IntegrityCheckResult,
+        IntegrityReport,
+        RetentionQuery,
+        RetentionPolicyReport,
+        RetentionReport,
+        AppointmentArchiveQuery,
+        AppointmentArchiveReport,
+        LimitUsage,
+        UsageReport,
+        LimitExceededResponse,
+        UpdateMaintenance,
+        MaintenanceStatus,
+        DeletionEffect,
+        DependencyCount,
+        DeletePreview,
+        MissingBreedBySpecies,
+        UnassignedNameCluster,
+        UnusedBreed,
+        BreedQualityReport,
+        NotificationLogFilter,
+        NotificationLogEntry,
+        // Estadísticas
+        StatisticsQuery,
+        StatisticType,
+        ProcedureActor,
+        ProcedureDetailQuery,
+        StatisticsResponse,
+        AppointmentsByMonth,
+        AppointmentsBySource,
+        UserCounts,
+        ProceduresByType,
+        PatientsBySpecies,
+        VeterinarianStats,
+        AppointmentsByStatus,
+        ReferralsByMonth,
+        SatisfactionByVeterinarian,
+        SatisfactionByMonth,
+        VeterinarianReportQuery,
+    ];
+
+    let mut output = String::from(HEADER);
+    for declaration in declarations {
+        // serde_json serializa i64 como number, no como bigint
+        let declaration = declaration.replace("bigint", "number");
+        output.push('\n');
+        output.push_str("export ");
+        output.push_str(&declaration);
+        output.push('\n');
+    }
+    output
+}
+
+/// Atiende `--emit-types [ruta]` y `--check-types [ruta]`.
+///
+/// Devuelve `None` si no se pidió ninguno y el servidor debe arrancar normalmente.
+/// `--check-types` falla si el archivo no coincide con los modelos actuales.
+pub fn run_from_args(args: &[String]) -> Option<io::Result<()>> {
+    let (flag, path) = match args {
+        [flag, rest @ ..] if flag == "--emit-types" || flag == "--check-types" => (
+            flag.as_str(),
+            rest.first().map_or(DEFAULT_TYPES_PATH, String::as_str),
+        ),
+        _ => return None,
+    };
+
+    let rendered = render();
+    if flag == "--emit-types" {
+        return Some(fs::write(path, rendered).inspect(|_| {
+            println!("Tipos TypeScript escritos en {path}");
+        }));
+    }
+
+    Some(match fs::read_to_string(path) {
+        Ok(current) if current == rendered => Ok(()),
+        Ok(_) => Err(io::Error::other(format!(
+            "{path} está desactualizado; ejecuta `cargo run -- --emit-types`"
+        ))),
+        Err(e) => Err(e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    /// Mismo chequeo que `--check-types`, para que `cargo test` lo detecte
+    #[test]
+    fn types_file_matches_the_models() {
+        assert!(
+            super::render() == include_str!("../docs/api-types.d.ts"),
+            "docs/api-types.d.ts está desactualizado; ejecuta `cargo run -- --emit-types`"
+        );
+    }
+}
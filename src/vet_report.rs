@@ -0,0 +1,160 @@
+use crate::clinic;
+use crate::models::statistic::VeterinarianStats;
+use crate::pdf::{PdfWriter, Style};
+use chrono::{Datelike, NaiveDate, Utc};
+
+/// El reporte cabe en una o dos páginas; el margen es por si hay muchas filas
+const REPORT_MAX_PAGES: usize = 4;
+
+/// Caracteres de la barra más larga de cada tabla
+const BAR_WIDTH: i64 = 30;
+
+const MONTHS: [&str; 12] = [
+    "enero",
+    "febrero",
+    "marzo",
+    "abril",
+    "mayo",
+    "junio",
+    "julio",
+    "agosto",
+    "septiembre",
+    "octubre",
+    "noviembre",
+    "diciembre",
+];
+
+fn month_name(month: NaiveDate) -> String {
+    format!("{} {}", MONTHS[month.month0() as usize], month.year())
+}
+
+fn status_label(status: &str) -> &str {
+    match status {
+        "scheduled" => "Programadas",
+        "completed" => "Completadas",
+        "canceled" => "Canceladas",
+        "no_show" => "No asistieron",
+        other => other,
+    }
+}
+
+/// Una fila por concepto con una barra de `#` proporcional al mayor
+fn bars<'a>(pdf: &mut PdfWriter, rows: impl IntoIterator<Item = (&'a str, i64)>) {
+    let mut rows: Vec<_> = rows.into_iter().collect();
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let max = rows.first().map_or(0, |(_, count)| *count).max(1);
+    for (label, count) in rows {
+        let width = (count * BAR_WIDTH + max - 1) / max;
+        pdf.text(
+            Style::Body,
+            &format!("{label}: {} {count}", "#".repeat(width as usize)),
+        );
+    }
+}
+
+/// Reporte mensual de desempeño de un veterinario en PDF. Un veterinario
+/// desconocido o un mes sin actividad producen un reporte "sin actividad".
+pub fn render_pdf(
+    veterinarian_id: i32,
+    veterinarian_name: Option<&str>,
+    month: NaiveDate,
+    stats: &VeterinarianStats,
+) -> Vec<u8> {
+    let name =
+        veterinarian_name.map_or_else(|| format!("Veterinario #{veterinarian_id}"), str::to_string);
+    let mut pdf = PdfWriter::new(
+        format!(
+            "{} - {} - generado el {}",
+            name,
+            month_name(month),
+            Utc::now()
+                .with_timezone(&clinic::timezone())
+                .format("%d/%m/%Y %H:%M")
+        ),
+        REPORT_MAX_PAGES,
+    );
+
+    pdf.text(Style::Title, "Reporte mensual de desempeño");
+    pdf.text(Style::Heading, &format!("{} - {}", name, month_name(month)));
+    pdf.gap(6.0);
+
+    let appointments: i64 = stats
+        .appointments_by_status
+        .iter()
+        .map(|row| row.count)
+        .sum();
+    let procedures: i64 = stats.procedures_performed.iter().map(|row| row.count).sum();
+    if appointments == 0 && procedures == 0 && stats.medical_records_created == 0 {
+        pdf.text(Style::Body, "Sin actividad registrada en el mes.");
+        return pdf.finish();
+    }
+
+    pdf.text(Style::Heading, "Resumen");
+    pdf.text(
+        Style::Body,
+        &format!(
+            "Citas: {}    Procedimientos: {}    Registros médicos: {}",
+            appointments, procedures, stats.medical_records_created
+        ),
+    );
+    pdf.text(
+        Style::Body,
+        &format!(
+            "Duración promedio: {}    No asistieron: {}",
+            stats
+                .average_duration_minutes
+                .map_or("-".into(), |minutes| format!("{minutes:.0} min")),
+            stats
+                .no_show_rate
+                .map_or("-".into(), |rate| format!("{rate:.1}%"))
+        ),
+    );
+    pdf.text(
+        Style::Body,
+        &match stats.average_rating {
+            Some(rating) => format!(
+                "Satisfacción: {rating:.2} de 5 ({} respuestas)",
+                stats.survey_responses
+            ),
+            None => "Satisfacción: sin encuestas respondidas".into(),
+        },
+    );
+
+    pdf.gap(10.0);
+    pdf.text(Style::Heading, "Citas por estado");
+    bars(
+        &mut pdf,
+        stats
+            .appointments_by_status
+            .iter()
+            .map(|row| (status_label(&row.status), row.count)),
+    );
+
+    pdf.gap(10.0);
+    pdf.text(Style::Heading, "Procedimientos por tipo");
+    if stats.procedures_performed.is_empty() {
+        pdf.text(Style::Body, "Sin procedimientos en el mes");
+    }
+    bars(
+        &mut pdf,
+        stats
+            .procedures_performed
+            .iter()
+            .map(|row| (row.procedure_type.as_str(), row.count)),
+    );
+
+    pdf.gap(10.0);
+    pdf.text(Style::Heading, "Pacientes atendidos por especie");
+    if stats.patients_attended.is_empty() {
+        pdf.text(Style::Body, "Sin consultas registradas en el mes");
+    }
+    bars(
+        &mut pdf,
+        stats
+            .patients_attended
+            .iter()
+            .map(|row| (row.species.as_str(), row.count)),
+    );
+
+    pdf.finish()
+}
@@ -0,0 +1,159 @@
+use crate::models::enums::AnimalSpecies;
+use crate::models::medical_record::{VitalFlag, VitalRange, VitalReading, VitalSign};
+use std::env;
+
+const SIGNS: [VitalSign; 3] = [
+    VitalSign::TemperatureC,
+    VitalSign::HeartRateBpm,
+    VitalSign::RespiratoryRateRpm,
+];
+
+/// Rango normal por defecto de un signo vital en un adulto sano en reposo.
+/// Los reptiles dependen de la temperatura ambiente y `Other` agrupa especies
+/// muy distintas, así que no tienen rango salvo que se configure.
+fn default_range(species: &AnimalSpecies, sign: &VitalSign) -> Option<(f64, f64)> {
+    match (species, sign) {
+        (AnimalSpecies::Dog, VitalSign::TemperatureC) => Some((37.5, 39.2)),
+        (AnimalSpecies::Dog, VitalSign::HeartRateBpm) => Some((60.0, 140.0)),
+        (AnimalSpecies::Dog, VitalSign::RespiratoryRateRpm) => Some((10.0, 30.0)),
+        (AnimalSpecies::Cat, VitalSign::TemperatureC) => Some((37.8, 39.2)),
+        (AnimalSpecies::Cat, VitalSign::HeartRateBpm) => Some((140.0, 220.0)),
+        (AnimalSpecies::Cat, VitalSign::RespiratoryRateRpm) => Some((20.0, 30.0)),
+        (AnimalSpecies::Rabbit, VitalSign::TemperatureC) => Some((38.5, 40.0)),
+        (AnimalSpecies::Rabbit, VitalSign::HeartRateBpm) => Some((130.0, 325.0)),
+        (AnimalSpecies::Rabbit, VitalSign::RespiratoryRateRpm) => Some((30.0, 60.0)),
+        (AnimalSpecies::Bird, VitalSign::TemperatureC) => Some((40.0, 42.0)),
+        (AnimalSpecies::Rodent, VitalSign::TemperatureC) => Some((37.0, 39.5)),
+        _ => None,
+    }
+}
+
+fn env_var(species: &AnimalSpecies, sign: &VitalSign) -> String {
+    let species = species.code().to_ascii_uppercase();
+    let sign = match sign {
+        VitalSign::TemperatureC => "TEMPERATURE",
+        VitalSign::HeartRateBpm => "HEART_RATE",
+        VitalSign::RespiratoryRateRpm => "RESPIRATORY_RATE",
+    };
+    format!("VITAL_RANGE_{species}_{sign}")
+}
+
+/// Rango normal del signo vital para la especie, si se conoce.
+///
+/// Se puede ajustar con `VITAL_RANGE_<CÓDIGO>_<SIGNO>` en formato `min-max`
+/// (ej: `VITAL_RANGE_DOG_TEMPERATURE=37.5-39.3`); un valor inválido se ignora.
+/// Las especies del catálogo solo tienen rango si se configura así.
+pub fn reference_range(species: &AnimalSpecies, sign: &VitalSign) -> Option<(f64, f64)> {
+    env::var(env_var(species, sign))
+        .ok()
+        .and_then(|value| {
+            let (min, max) = value.split_once('-')?;
+            Some((min.trim().parse().ok()?, max.trim().parse().ok()?))
+        })
+        .filter(|(min, max): &(f64, f64)| *min > 0.0 && min < max)
+        .or_else(|| default_range(species, sign))
+}
+
+/// Tabla de referencia de las especies integradas, por especie y signo
+pub fn reference_table() -> Vec<VitalRange> {
+    AnimalSpecies::BUILT_IN
+        .iter()
+        .flat_map(|species| {
+            SIGNS.iter().filter_map(move |sign| {
+                let (min, max) = reference_range(species, sign)?;
+                Some(VitalRange {
+                    species: species.clone(),
+                    sign: sign.clone(),
+                    min,
+                    max,
+                })
+            })
+        })
+        .collect()
+}
+
+/// Lectura de un signo vital con su marca; sin especie o sin rango conocido
+/// no lleva marca. Los extremos del rango cuentan como normales.
+pub fn reading(
+    species: Option<&AnimalSpecies>,
+    sign: VitalSign,
+    value: Option<f64>,
+) -> Option<VitalReading> {
+    let value = value?;
+    let flag = species
+        .and_then(|species| reference_range(species, &sign))
+        .map(|(min, max)| {
+            if value < min {
+                VitalFlag::Low
+            } else if value > max {
+                VitalFlag::High
+            } else {
+                VitalFlag::Normal
+            }
+        });
+    Some(VitalReading { value, flag })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(species: &AnimalSpecies, sign: VitalSign, value: f64) -> Option<VitalFlag> {
+        reading(Some(species), sign, Some(value)).unwrap().flag
+    }
+
+    fn assert_boundaries(species: &AnimalSpecies, sign: VitalSign, min: f64, max: f64) {
+        let step = 0.1;
+        assert!(matches!(
+            flag(species, sign.clone(), min - step),
+            Some(VitalFlag::Low)
+        ));
+        assert!(matches!(
+            flag(species, sign.clone(), min),
+            Some(VitalFlag::Normal)
+        ));
+        assert!(matches!(
+            flag(species, sign.clone(), max),
+            Some(VitalFlag::Normal)
+        ));
+        assert!(matches!(
+            flag(species, sign, max + step),
+            Some(VitalFlag::High)
+        ));
+    }
+
+    #[test]
+    fn dog_boundaries() {
+        let dog = AnimalSpecies::Dog;
+        assert_boundaries(&dog, VitalSign::TemperatureC, 37.5, 39.2);
+        assert_boundaries(&dog, VitalSign::HeartRateBpm, 60.0, 140.0);
+        assert_boundaries(&dog, VitalSign::RespiratoryRateRpm, 10.0, 30.0);
+    }
+
+    #[test]
+    fn cat_boundaries() {
+        let cat = AnimalSpecies::Cat;
+        assert_boundaries(&cat, VitalSign::TemperatureC, 37.8, 39.2);
+        assert_boundaries(&cat, VitalSign::HeartRateBpm, 140.0, 220.0);
+        assert_boundaries(&cat, VitalSign::RespiratoryRateRpm, 20.0, 30.0);
+    }
+
+    #[test]
+    fn a_fever_for_a_dog_is_high() {
+        assert!(matches!(
+            flag(&AnimalSpecies::Dog, VitalSign::TemperatureC, 40.1),
+            Some(VitalFlag::High)
+        ));
+    }
+
+    #[test]
+    fn unknown_species_or_range_has_no_flag() {
+        let reptile = AnimalSpecies::Reptile;
+        assert!(flag(&reptile, VitalSign::TemperatureC, 30.0).is_none());
+        let ferret = AnimalSpecies::Custom("ferret".into());
+        assert!(flag(&ferret, VitalSign::HeartRateBpm, 200.0).is_none());
+        let without_species = reading(None, VitalSign::TemperatureC, Some(38.0)).unwrap();
+        assert!(without_species.flag.is_none());
+        assert!(reading(Some(&AnimalSpecies::Dog), VitalSign::TemperatureC, None).is_none());
+    }
+}
@@ -0,0 +1,141 @@
+use crate::errors::ApiError;
+use crate::models::enums::AnimalSpecies;
+use std::env;
+
+/// Rango que acepta la validación de los payloads; aplica a `Other` y a las
+/// especies del catálogo
+pub const GLOBAL_RANGE: (f64, f64) = (0.01, 999.99);
+
+/// Rango de peso plausible por defecto de cada especie, en kg
+fn default_range(species: &AnimalSpecies) -> (f64, f64) {
+    match species {
+        AnimalSpecies::Dog => (0.3, 110.0),
+        AnimalSpecies::Cat => (0.2, 15.0),
+        AnimalSpecies::Bird => (0.01, 20.0),
+        AnimalSpecies::Reptile => (0.01, 500.0),
+        AnimalSpecies::Rodent => (0.01, 80.0),
+        AnimalSpecies::Rabbit => (0.2, 12.0),
+        AnimalSpecies::Other | AnimalSpecies::Custom(_) => GLOBAL_RANGE,
+    }
+}
+
+fn env_var(species: &AnimalSpecies) -> String {
+    format!("WEIGHT_RANGE_{}", species.code().to_ascii_uppercase())
+}
+
+/// Rango plausible de la especie.
+///
+/// Se puede ajustar con `WEIGHT_RANGE_<CÓDIGO>` en formato `min-max`
+/// (ej: `WEIGHT_RANGE_CAT=0.2-15`, `WEIGHT_RANGE_FERRET=0.4-2.5`); un valor
+/// inválido se ignora.
+pub fn plausible_range(species: &AnimalSpecies) -> (f64, f64) {
+    env::var(env_var(species))
+        .ok()
+        .and_then(|value| parse_range(&value))
+        .unwrap_or_else(|| default_range(species))
+}
+
+/// Interpreta `min-max`; `None` si no es un rango válido
+fn parse_range(value: &str) -> Option<(f64, f64)> {
+    let (min, max) = value.split_once('-')?;
+    let range: (f64, f64) = (min.trim().parse().ok()?, max.trim().parse().ok()?);
+    (range.0 > 0.0 && range.0 < range.1).then_some(range)
+}
+
+/// Verifica que el peso sea plausible para la especie.
+///
+/// Fuera de rango responde 422 con el rango esperado, salvo que se envíe
+/// `override_warnings=true`; en ese caso devuelve la advertencia para
+/// incluirla en la respuesta.
+pub fn ensure_plausible(
+    species: &AnimalSpecies,
+    weight_kg: Option<f64>,
+    override_warnings: bool,
+) -> Result<Vec<String>, ApiError> {
+    let Some(weight_kg) = weight_kg else {
+        return Ok(Vec::new());
+    };
+    let (min, max) = plausible_range(species);
+    if (min..=max).contains(&weight_kg) {
+        return Ok(Vec::new());
+    }
+
+    let warning = format!(
+        "El peso {weight_kg} kg está fuera del rango esperado para {species:?} ({min} a {max} kg)"
+    );
+    if override_warnings {
+        return Ok(vec![warning]);
+    }
+    Err(ApiError::UnprocessableEntity(format!(
+        "{warning}. Envía override_warnings=true si el peso es correcto"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accepts(species: &AnimalSpecies, weight_kg: f64) -> bool {
+        ensure_plausible(species, Some(weight_kg), false).is_ok()
+    }
+
+    #[test]
+    fn range_boundaries_are_inclusive() {
+        for species in [
+            AnimalSpecies::Dog,
+            AnimalSpecies::Cat,
+            AnimalSpecies::Bird,
+            AnimalSpecies::Reptile,
+            AnimalSpecies::Rodent,
+            AnimalSpecies::Rabbit,
+        ] {
+            let (min, max) = default_range(&species);
+            assert!(accepts(&species, min), "{species:?} mínimo");
+            assert!(accepts(&species, max), "{species:?} máximo");
+            assert!(!accepts(&species, min - 0.01), "{species:?} bajo el mínimo");
+            assert!(
+                !accepts(&species, max + 0.01),
+                "{species:?} sobre el máximo"
+            );
+        }
+    }
+
+    #[test]
+    fn a_300_kg_cat_is_rejected_with_the_expected_range() {
+        match ensure_plausible(&AnimalSpecies::Cat, Some(300.0), false) {
+            Err(ApiError::UnprocessableEntity(message)) => {
+                assert!(message.contains("0.2 a 15 kg"), "{message}")
+            }
+            other => panic!("se esperaba 422, se obtuvo {other:?}"),
+        }
+    }
+
+    #[test]
+    fn override_turns_the_error_into_a_warning() {
+        let warnings = ensure_plausible(&AnimalSpecies::Cat, Some(300.0), true).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            ensure_plausible(&AnimalSpecies::Cat, None, false)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn other_and_catalog_species_use_the_global_range() {
+        let ferret = AnimalSpecies::Custom("ferret".into());
+        for species in [AnimalSpecies::Other, ferret] {
+            assert_eq!(default_range(&species), GLOBAL_RANGE);
+            assert!(accepts(&species, 300.0));
+        }
+    }
+
+    #[test]
+    fn env_override_must_be_a_valid_range() {
+        assert_eq!(parse_range("0.2-15"), Some((0.2, 15.0)));
+        assert_eq!(parse_range(" 0.4 - 2.5 "), Some((0.4, 2.5)));
+        assert_eq!(parse_range("15-0.2"), None);
+        assert_eq!(parse_range("0-15"), None);
+        assert_eq!(parse_range("quince"), None);
+    }
+}